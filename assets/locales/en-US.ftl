@@ -0,0 +1,74 @@
+# Built-in English strings. Community translations use the same message ids,
+# see https://halloy.chat/configuration/locale
+
+## User context menu
+
+context-menu-whois = Whois
+context-menu-message = Message
+context-menu-query-in-new-window = Open query in new window
+context-menu-give-op = Give Op ({ $action })
+context-menu-take-op = Take Op ({ $action })
+context-menu-give-voice = Give Voice ({ $action })
+context-menu-take-voice = Take Voice ({ $action })
+context-menu-send-file = Send File
+context-menu-dcc-chat = DCC Chat
+context-menu-invite-to = Invite to
+context-menu-copy-hostmask = Copy hostmask
+context-menu-monitor = Monitor
+context-menu-stop-monitoring = Stop monitoring
+context-menu-ctcp-time = Local Time (TIME)
+context-menu-ctcp-version = Client (VERSION)
+context-menu-ctcp-ping = Latency (PING)
+context-menu-ctcp-clientinfo = Capabilities (CLIENTINFO)
+context-menu-ctcp-source = Source (SOURCE)
+context-menu-ctcp-userinfo = User Info (USERINFO)
+context-menu-kill = Kill
+context-menu-gline = G-line
+context-menu-user-away = (Away)
+context-menu-user-offline = (Offline)
+
+## Channel context menu
+
+context-menu-copy-channel-name = Copy channel name
+context-menu-set-topic = Set topic
+context-menu-channel-modes = Channel modes
+context-menu-channel-info = Channel info
+context-menu-access-list = Access list
+context-menu-open-log-file = Open log file
+context-menu-part = Part
+context-menu-part-with-reason = Part with reason
+context-menu-close-buffer = Close buffer
+
+## Topic context menu
+
+context-menu-copy-topic = Copy topic
+context-menu-copy-setter = Copy setter
+context-menu-edit-topic = Edit topic
+
+## Url context menu
+
+context-menu-open-in-browser = Open in browser
+context-menu-open-with = Open with { $label }
+context-menu-copy-url = Copy URL
+context-menu-copy-page-title = Copy page title
+
+## Message context menu
+
+context-menu-copy-message = Copy message
+context-menu-copy-sender = Copy sender
+context-menu-quote = Quote
+context-menu-reply = Reply
+context-menu-reply-to = Reply to { $recipients }
+context-menu-copy-modes = Copy modes ({ $modes })
+context-menu-share = Share…
+context-menu-message-details = Message details
+
+## Sidebar
+
+sidebar-oper = oper
+
+## Tray menu
+
+tray-show-hide = Show/Hide
+tray-mark-all-as-read = Mark All as Read
+tray-quit = Quit
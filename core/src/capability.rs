@@ -0,0 +1,232 @@
+//! Capability negotiation,
+//! <https://ircv3.net/specs/extensions/capability-negotiation>
+
+use std::collections::HashSet;
+
+use irc::proto::{self, command};
+
+/// Capabilities the server lists and those it acknowledged
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    listed: Vec<String>,
+    acknowledged: HashSet<String>,
+}
+
+impl Capabilities {
+    /// Adds a line of `CAP LS`
+    pub fn list(&mut self, caps: &str) {
+        self.listed.extend(caps.split(' ').map(String::from));
+    }
+
+    /// Capabilities to request once `CAP LS` is complete
+    pub fn requests(&self, chathistory: bool) -> Vec<&str> {
+        wanted(&[], &self.listed, chathistory)
+    }
+
+    /// Adds capabilities from `CAP NEW`, returning those to request
+    pub fn add(&mut self, caps: &str, chathistory: bool) -> Vec<String> {
+        let new = caps.split(' ').map(String::from).collect::<Vec<_>>();
+
+        let requests = wanted(&self.listed, &new, chathistory)
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        self.listed.extend(new);
+
+        requests
+    }
+
+    /// Enables capabilities from `CAP ACK`, or disables those prefixed
+    /// with `-`
+    pub fn acknowledge(&mut self, caps: &str) {
+        for cap in caps.split(' ') {
+            if let Some(cap) = cap.strip_prefix('-') {
+                self.acknowledged.remove(name(cap));
+            } else {
+                self.acknowledged.insert(name(cap).to_string());
+            }
+        }
+    }
+
+    /// Removes capabilities from `CAP DEL`
+    pub fn delete(&mut self, caps: &str) {
+        let deleted = caps.split(' ').map(name).collect::<Vec<_>>();
+
+        self.acknowledged
+            .retain(|cap| !deleted.contains(&cap.as_str()));
+        self.listed.retain(|cap| !deleted.contains(&name(cap)));
+    }
+
+    pub fn is_listed(&self, cap: &str) -> bool {
+        self.listed.iter().any(|listed| name(listed) == cap)
+    }
+
+    pub fn is_acknowledged(&self, cap: &str) -> bool {
+        self.acknowledged.contains(cap)
+    }
+}
+
+/// `CAP REQ` messages for `capabilities`, split to fit the line limit
+pub fn group_requests<'a>(
+    capabilities: &'a [&'a str],
+) -> impl Iterator<Item = proto::Message> + 'a {
+    const MAX_LEN: usize = proto::format::BYTE_LIMIT - b"CAP REQ :\r\n".len();
+
+    let mut requests: Vec<(usize, Vec<&str>)> = vec![];
+
+    for capability in capabilities {
+        match requests.last_mut() {
+            // Capability + a space
+            Some((len, request)) if *len + 1 + capability.len() <= MAX_LEN => {
+                *len += 1 + capability.len();
+                request.push(capability);
+            }
+            _ => requests.push((capability.len(), vec![capability])),
+        }
+    }
+
+    requests
+        .into_iter()
+        .map(|(_, request)| command!("CAP", "REQ", request.join(" ")))
+}
+
+/// Capabilities of `offered` to request, given those `listed` before
+fn wanted<'a>(
+    listed: &[String],
+    offered: &'a [String],
+    chathistory: bool,
+) -> Vec<&'a str> {
+    let offers = |cap: &str| offered.iter().any(|offer| name(offer) == cap);
+    let available = |cap: &str| {
+        offers(cap) || listed.iter().any(|listed| name(listed) == cap)
+    };
+
+    let mut requested = vec![];
+
+    for cap in [
+        "invite-notify",
+        "userhost-in-names",
+        "away-notify",
+        "message-tags",
+        "server-time",
+        "chghost",
+        "extended-monitor",
+    ] {
+        if offers(cap) {
+            requested.push(cap);
+        }
+    }
+
+    if available("account-notify") {
+        if offers("account-notify") {
+            requested.push("account-notify");
+        }
+        if offers("extended-join") {
+            requested.push("extended-join");
+        }
+    }
+
+    if available("batch") {
+        if offers("batch") {
+            requested.push("batch");
+        }
+
+        // We require batch for our chathistory support
+        if offers("draft/chathistory") && chathistory {
+            requested.push("draft/chathistory");
+
+            if offers("draft/event-playback") {
+                requested.push("draft/event-playback");
+            }
+        }
+    }
+
+    for cap in [
+        "labeled-response",
+        "echo-message",
+        "sasl",
+        "multi-prefix",
+        "draft/read-marker",
+        "setname",
+        "draft/channel-rename",
+        "soju.im/bouncer-networks",
+    ] {
+        if offers(cap) {
+            requested.push(cap);
+        }
+    }
+
+    requested
+}
+
+/// Name of a listed capability, without its value (e.g. `sasl=PLAIN`)
+fn name(cap: &str) -> &str {
+    cap.split_once('=').map_or(cap, |(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_follow_dependencies() {
+        let mut capabilities = Capabilities::default();
+
+        capabilities
+            .list("extended-join draft/chathistory sasl=PLAIN,EXTERNAL");
+        capabilities.list("batch draft/event-playback server-time");
+
+        // extended-join is only useful along with account-notify
+        assert_eq!(
+            capabilities.requests(true),
+            [
+                "server-time",
+                "batch",
+                "draft/chathistory",
+                "draft/event-playback",
+                "sasl"
+            ]
+        );
+        assert_eq!(
+            capabilities.requests(false),
+            ["server-time", "batch", "sasl"]
+        );
+
+        assert_eq!(
+            capabilities.add("account-notify", true),
+            ["account-notify"]
+        );
+        assert!(capabilities.is_listed("sasl"));
+    }
+
+    #[test]
+    fn acknowledged_until_deleted() {
+        let mut capabilities = Capabilities::default();
+
+        capabilities.list("away-notify sasl=PLAIN labeled-response");
+        capabilities.acknowledge("away-notify sasl labeled-response");
+        capabilities.acknowledge("-labeled-response");
+
+        assert!(capabilities.is_acknowledged("away-notify"));
+        assert!(!capabilities.is_acknowledged("labeled-response"));
+
+        capabilities.delete("sasl");
+
+        assert!(!capabilities.is_acknowledged("sasl"));
+        assert!(!capabilities.is_listed("sasl"));
+    }
+
+    #[test]
+    fn requests_fit_line_limit() {
+        let capabilities = vec!["draft/read-marker"; 60];
+
+        let requests = group_requests(&capabilities).collect::<Vec<_>>();
+
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|request| {
+            proto::format::message(request.clone()).len()
+                <= proto::format::BYTE_LIMIT
+        }));
+    }
+}
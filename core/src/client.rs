@@ -0,0 +1,590 @@
+//! Registration with a server: capability negotiation, SASL authentication
+//! and the ISUPPORT parameters it advertises.
+
+use std::collections::HashMap;
+
+use futures::channel::mpsc;
+use irc::proto::{self, Command, command};
+
+use crate::capability::{self, Capabilities};
+use crate::isupport;
+
+/// How to register with the server
+#[derive(Debug, Clone)]
+pub struct Registration {
+    pub nickname: String,
+    /// Defaults to the nickname
+    pub username: Option<String>,
+    /// Defaults to the nickname
+    pub realname: Option<String>,
+    pub password: Option<String>,
+    pub sasl: Option<Sasl>,
+    /// Whether to request the chat history capabilities
+    pub chathistory: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sasl {
+    Plain {
+        username: String,
+        password: String,
+    },
+    /// Authenticates with the TLS client certificate
+    External,
+}
+
+impl Sasl {
+    pub fn command(&self) -> &'static str {
+        match self {
+            Sasl::Plain { .. } => "PLAIN",
+            Sasl::External => "EXTERNAL",
+        }
+    }
+
+    pub fn params(&self) -> Vec<String> {
+        const CHUNK_SIZE: usize = 400;
+
+        match self {
+            Sasl::Plain { username, password } => {
+                use base64::engine::Engine;
+
+                // Exclude authorization ID, to use the authentication ID as the authorization ID
+                // https://datatracker.ietf.org/doc/html/rfc4616#section-2
+                let encoding = base64::engine::general_purpose::STANDARD
+                    .encode(format!("\x00{username}\x00{password}"));
+
+                let chunks = encoding
+                    .as_bytes()
+                    .chunks(CHUNK_SIZE)
+                    .collect::<Vec<&[u8]>>();
+
+                let signal_end_of_response = chunks
+                    .iter()
+                    .last()
+                    .is_none_or(|chunk| chunk.len() == CHUNK_SIZE);
+
+                let mut params = chunks
+                    .into_iter()
+                    .map(|chunk| {
+                        String::from_utf8(chunk.into())
+                            .expect("chunks should be valid UTF-8")
+                    })
+                    .collect::<Vec<String>>();
+
+                if signal_end_of_response {
+                    params.push("+".into());
+                }
+
+                params
+            }
+            Sasl::External => vec!["+".into()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegistrationStep {
+    Start,
+    List,
+    Req,
+    Sasl,
+    End,
+    Complete,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    AddedIsupportParam(isupport::Parameter),
+    RemovedIsupportParam(isupport::Kind),
+    /// Registration completed with the end of the MOTD
+    Registered,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Send(#[from] mpsc::SendError),
+}
+
+impl From<mpsc::TrySendError<proto::Message>> for Error {
+    fn from(error: mpsc::TrySendError<proto::Message>) -> Self {
+        Self::Send(error.into_send_error())
+    }
+}
+
+pub struct Client {
+    /// Identifies the server in logs
+    name: String,
+    registration: Registration,
+    handle: mpsc::Sender<proto::Message>,
+    step: RegistrationStep,
+    capabilities: Capabilities,
+    isupport: HashMap<isupport::Kind, isupport::Parameter>,
+    sasl_succeeded: bool,
+    /// Reason of the last failed SASL authentication
+    sasl_error: Option<String>,
+}
+
+impl Client {
+    pub fn new(
+        name: String,
+        registration: Registration,
+        sender: mpsc::Sender<proto::Message>,
+    ) -> Self {
+        Self {
+            name,
+            registration,
+            handle: sender,
+            step: RegistrationStep::Start,
+            capabilities: Capabilities::default(),
+            isupport: HashMap::new(),
+            sasl_succeeded: false,
+            sasl_error: None,
+        }
+    }
+
+    /// Begins registration
+    pub fn connect(&mut self) -> Result<(), Error> {
+        self.handle.try_send(command!("CAP", "LS", "302"))?;
+
+        // Identify
+        let nick = &self.registration.nickname;
+        let user = self.registration.username.as_ref().unwrap_or(nick);
+        let real = self.registration.realname.as_ref().unwrap_or(nick);
+
+        if let Some(pass) = self.registration.password.as_ref() {
+            self.handle.try_send(command!("PASS", pass))?;
+        }
+        self.handle.try_send(command!("NICK", nick))?;
+        self.handle.try_send(command!("USER", user, real))?;
+        self.step = RegistrationStep::List;
+
+        Ok(())
+    }
+
+    pub fn send(&mut self, message: proto::Message) -> Result<(), Error> {
+        self.handle.try_send(message)?;
+
+        Ok(())
+    }
+
+    /// Handles the registration, capability, SASL and ISUPPORT messages,
+    /// ignoring any other
+    pub fn receive(
+        &mut self,
+        message: &proto::Message,
+    ) -> Result<Vec<Event>, Error> {
+        use irc::proto::command::Numeric::*;
+
+        match &message.command {
+            Command::CAP(_, sub, a, b) if sub == "LS" => {
+                let (caps, asterisk) = match (a, b) {
+                    (Some(caps), None) => (caps, None),
+                    (Some(asterisk), Some(caps)) => (caps, Some(asterisk)),
+                    // Unreachable
+                    (None, None) | (None, Some(_)) => return Ok(vec![]),
+                };
+
+                self.capabilities.list(caps);
+
+                // Finished
+                if asterisk.is_none() {
+                    let requested = self
+                        .capabilities
+                        .requests(self.registration.chathistory);
+
+                    if !requested.is_empty() {
+                        self.step = RegistrationStep::Req;
+
+                        for message in capability::group_requests(&requested) {
+                            self.handle.try_send(message)?;
+                        }
+                    } else {
+                        // If none requested, end negotiation
+                        self.end_negotiation()?;
+                    }
+                }
+            }
+            Command::CAP(_, sub, a, b) if sub == "ACK" => {
+                let Some(caps) = b.as_ref().or(a.as_ref()) else {
+                    return Ok(vec![]);
+                };
+
+                log::info!("[{}] capabilities acknowledged: {caps}", self.name);
+
+                self.capabilities.acknowledge(caps);
+
+                // Later acknowledgements are of capabilities from CAP NEW
+                if self.step == RegistrationStep::Req {
+                    if let Some(sasl) =
+                        self.registration.sasl.as_ref().filter(|_| {
+                            self.capabilities.is_acknowledged("sasl")
+                        })
+                    {
+                        self.step = RegistrationStep::Sasl;
+                        self.handle.try_send(command!(
+                            "AUTHENTICATE",
+                            sasl.command()
+                        ))?;
+                    } else {
+                        self.end_negotiation()?;
+                    }
+                }
+            }
+            Command::CAP(_, sub, a, b) if sub == "NAK" => {
+                let Some(caps) = b.as_ref().or(a.as_ref()) else {
+                    return Ok(vec![]);
+                };
+
+                log::warn!(
+                    "[{}] capabilities not acknowledged: {caps}",
+                    self.name
+                );
+
+                // End if we didn't move to sasl or already ended
+                if self.step < RegistrationStep::Sasl {
+                    self.end_negotiation()?;
+                }
+            }
+            Command::CAP(_, sub, a, b) if sub == "NEW" => {
+                let Some(caps) = b.as_ref().or(a.as_ref()) else {
+                    return Ok(vec![]);
+                };
+
+                let requested =
+                    self.capabilities.add(caps, self.registration.chathistory);
+                let requested =
+                    requested.iter().map(String::as_str).collect::<Vec<_>>();
+
+                for message in capability::group_requests(&requested) {
+                    self.handle.try_send(message)?;
+                }
+            }
+            Command::CAP(_, sub, a, b) if sub == "DEL" => {
+                let Some(caps) = b.as_ref().or(a.as_ref()) else {
+                    return Ok(vec![]);
+                };
+
+                log::info!(
+                    "[{}] capabilities no longer supported: {caps}",
+                    self.name
+                );
+
+                self.capabilities.delete(caps);
+            }
+            Command::AUTHENTICATE(param) if param == "+" => {
+                if let Some(sasl) = self.registration.sasl.as_ref() {
+                    log::info!("[{}] sasl auth: {}", self.name, sasl.command());
+
+                    for param in sasl.params() {
+                        self.handle
+                            .try_send(command!("AUTHENTICATE", param))?;
+                    }
+                }
+            }
+            Command::Numeric(RPL_SASLSUCCESS, _) => {
+                self.sasl_succeeded = true;
+                self.sasl_error = None;
+
+                // Re-authenticating after registration doesn't end it
+                if self.step == RegistrationStep::Sasl {
+                    self.end_negotiation()?;
+                }
+            }
+            Command::Numeric(
+                ERR_SASLFAIL | ERR_SASLTOOLONG | ERR_NICKLOCKED,
+                args,
+            ) => {
+                log::warn!("[{}] sasl auth failed", self.name);
+
+                self.sasl_error = args.last().cloned();
+
+                if self.step == RegistrationStep::Sasl {
+                    self.end_negotiation()?;
+                }
+            }
+            Command::Numeric(RPL_ISUPPORT, args) => {
+                return Ok(self.update_isupport(args));
+            }
+            Command::Numeric(RPL_ENDOFMOTD | ERR_NOMOTD, _) => {
+                // MOTD (or ERR_NOMOTD) is the last required message in the numerics
+                // sent on successfully completing the registration process (after
+                // RPL_ISUPPORT message(s) are sent).
+                // https://modern.ircdocs.horse/#connection-registration
+                if self.step != RegistrationStep::End {
+                    log::warn!(
+                        "[{}] Registration completed while in mode: {:?}",
+                        self.name,
+                        self.step
+                    );
+                }
+                self.step = RegistrationStep::Complete;
+
+                return Ok(vec![Event::Registered]);
+            }
+            _ => {}
+        }
+
+        Ok(vec![])
+    }
+
+    /// The `AUTHENTICATE` message restarting SASL, if it's configured, the
+    /// server supports it and registration has completed
+    pub fn reauthenticate(&mut self) -> Option<proto::Message> {
+        if !self.can_reauthenticate() {
+            return None;
+        }
+
+        let sasl = self.registration.sasl.as_ref()?;
+
+        log::info!("[{}] sasl re-authenticating", self.name);
+
+        self.sasl_error = None;
+
+        Some(command!("AUTHENTICATE", sasl.command()))
+    }
+
+    pub fn can_reauthenticate(&self) -> bool {
+        self.registration.sasl.is_some()
+            && self.capabilities.is_acknowledged("sasl")
+            && self.step == RegistrationStep::Complete
+    }
+
+    pub fn registration(&self) -> &Registration {
+        &self.registration
+    }
+
+    pub fn registration_step(&self) -> RegistrationStep {
+        self.step
+    }
+
+    pub fn is_registered(&self) -> bool {
+        self.step == RegistrationStep::Complete
+    }
+
+    pub fn capabilities(&self) -> &Capabilities {
+        &self.capabilities
+    }
+
+    pub fn isupport(&self) -> &HashMap<isupport::Kind, isupport::Parameter> {
+        &self.isupport
+    }
+
+    pub fn sasl_succeeded(&self) -> bool {
+        self.sasl_succeeded
+    }
+
+    pub fn sasl_error(&self) -> Option<&str> {
+        self.sasl_error.as_deref()
+    }
+
+    fn end_negotiation(&mut self) -> Result<(), Error> {
+        self.step = RegistrationStep::End;
+        self.handle.try_send(command!("CAP", "END"))?;
+
+        Ok(())
+    }
+
+    fn update_isupport(&mut self, args: &[String]) -> Vec<Event> {
+        let mut events = vec![];
+
+        for (index, arg) in args.iter().enumerate().skip(1) {
+            match arg.parse::<isupport::Operation>() {
+                Ok(operation) => match operation {
+                    isupport::Operation::Add(parameter) => {
+                        if let Some(kind) = parameter.kind() {
+                            log::info!(
+                                "[{}] adding ISUPPORT parameter: {:?}",
+                                self.name,
+                                parameter
+                            );
+
+                            self.isupport.insert(kind, parameter.clone());
+
+                            events.push(Event::AddedIsupportParam(parameter));
+                        } else {
+                            log::info!(
+                                "[{}] ignoring ISUPPORT parameter: {:?}",
+                                self.name,
+                                parameter
+                            );
+                        }
+                    }
+                    isupport::Operation::Remove(_) => {
+                        if let Some(kind) = operation.kind() {
+                            log::info!(
+                                "[{}] removing ISUPPORT parameter: {:?}",
+                                self.name,
+                                kind
+                            );
+
+                            self.isupport.remove(&kind);
+
+                            events.push(Event::RemovedIsupportParam(kind));
+                        }
+                    }
+                },
+                Err(error) => {
+                    // Last argument is :are supported by this server
+                    if index != args.len() - 1 {
+                        if error == isupport::UNKNOWN_ISUPPORT_PARAMETER {
+                            log::info!(
+                                "[{}] unable to parse ISUPPORT parameter: {} ({})",
+                                self.name,
+                                arg,
+                                error
+                            );
+                        } else {
+                            log::warn!(
+                                "[{}] unable to parse ISUPPORT parameter: {} ({})",
+                                self.name,
+                                arg,
+                                error
+                            );
+                        }
+                    }
+                }
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client(sasl: Option<Sasl>) -> (Client, mpsc::Receiver<proto::Message>) {
+        let (sender, receiver) = mpsc::channel(100);
+
+        let client = Client::new(
+            "libera".to_string(),
+            Registration {
+                nickname: "halloy".to_string(),
+                username: None,
+                realname: Some("Halloy".to_string()),
+                password: None,
+                sasl,
+                chathistory: true,
+            },
+            sender,
+        );
+
+        (client, receiver)
+    }
+
+    fn sent(receiver: &mut mpsc::Receiver<proto::Message>) -> Vec<String> {
+        std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|message| {
+                proto::format::message(message).trim_end().to_string()
+            })
+            .collect()
+    }
+
+    fn receive(client: &mut Client, line: &str) -> Vec<Event> {
+        client
+            .receive(
+                &proto::parse::message(&format!("{line}\r\n"))
+                    .expect("valid message"),
+            )
+            .expect("sent")
+    }
+
+    #[test]
+    fn registers_with_sasl() {
+        let (mut client, mut receiver) = client(Some(Sasl::Plain {
+            username: "halloy".to_string(),
+            password: "secret".to_string(),
+        }));
+
+        client.connect().unwrap();
+
+        assert_eq!(
+            sent(&mut receiver),
+            ["CAP LS 302", "NICK halloy", "USER halloy 0 * Halloy"]
+        );
+
+        receive(&mut client, ":irc.libera.chat CAP * LS * :sasl=PLAIN batch");
+        assert!(sent(&mut receiver).is_empty());
+
+        receive(&mut client, ":irc.libera.chat CAP * LS :away-notify");
+        assert_eq!(sent(&mut receiver), ["CAP REQ :away-notify batch sasl"]);
+
+        receive(
+            &mut client,
+            ":irc.libera.chat CAP * ACK :away-notify batch sasl",
+        );
+        assert_eq!(client.registration_step(), RegistrationStep::Sasl);
+        assert_eq!(sent(&mut receiver), ["AUTHENTICATE PLAIN"]);
+
+        receive(&mut client, "AUTHENTICATE +");
+        assert_eq!(sent(&mut receiver), ["AUTHENTICATE AGhhbGxveQBzZWNyZXQ="]);
+
+        receive(
+            &mut client,
+            ":irc.libera.chat 903 halloy :SASL authentication successful",
+        );
+        assert!(client.sasl_succeeded());
+        assert_eq!(sent(&mut receiver), ["CAP END"]);
+
+        assert!(matches!(
+            receive(
+                &mut client,
+                ":irc.libera.chat 376 halloy :End of /MOTD command."
+            )
+            .as_slice(),
+            [Event::Registered]
+        ));
+        assert!(client.is_registered());
+        assert!(client.can_reauthenticate());
+    }
+
+    #[test]
+    fn ends_negotiation_without_capabilities() {
+        let (mut client, mut receiver) = client(None);
+
+        client.connect().unwrap();
+        sent(&mut receiver);
+
+        receive(&mut client, ":irc.libera.chat CAP * LS :foo");
+
+        assert_eq!(client.registration_step(), RegistrationStep::End);
+        assert_eq!(sent(&mut receiver), ["CAP END"]);
+    }
+
+    #[test]
+    fn tracks_isupport() {
+        let (mut client, _receiver) = client(None);
+
+        let events = receive(
+            &mut client,
+            ":irc.libera.chat 005 halloy CASEMAPPING=ascii NICKLEN=16 :are supported by this server",
+        );
+
+        assert!(matches!(
+            events.as_slice(),
+            [
+                Event::AddedIsupportParam(isupport::Parameter::CASEMAPPING(
+                    isupport::CaseMap::ASCII
+                )),
+                Event::AddedIsupportParam(isupport::Parameter::NICKLEN(16)),
+            ]
+        ));
+        assert!(matches!(
+            isupport::get_casemapping(client.isupport()),
+            Some(isupport::CaseMap::ASCII)
+        ));
+
+        let events = receive(
+            &mut client,
+            ":irc.libera.chat 005 halloy -CASEMAPPING :are supported by this server",
+        );
+
+        assert!(matches!(
+            events.as_slice(),
+            [Event::RemovedIsupportParam(isupport::Kind::CASEMAPPING)]
+        ));
+        assert!(isupport::get_casemapping(client.isupport()).is_none());
+    }
+}
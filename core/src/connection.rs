@@ -0,0 +1,447 @@
+//! A connection to a server, kept alive with `PING`s and retried whenever it
+//! drops until a `QUIT` is sent.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use futures::channel::mpsc;
+use futures::never::Never;
+use futures::{FutureExt, SinkExt, StreamExt, future, stream};
+use irc::proto::{self, Command, command};
+use irc::{Connection, codec};
+use tokio::task::JoinHandle;
+use tokio::time::{self, Instant, Interval};
+
+pub use irc::connection::{Error, Proxy};
+
+const QUIT_REQUEST_TIMEOUT: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub server: String,
+    pub port: u16,
+    pub security: Security,
+    pub proxy: Option<Proxy>,
+    /// Time to wait before connecting again
+    pub reconnect_delay: Duration,
+    /// Time between `PING`s sent to the server
+    pub ping_time: Duration,
+    /// Time to wait for a `PONG` before the connection is considered lost
+    pub ping_timeout: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub enum Security {
+    Unsecured,
+    Secured {
+        accept_invalid_certs: bool,
+        root_cert_path: Option<PathBuf>,
+        client_cert_path: Option<PathBuf>,
+        client_key_path: Option<PathBuf>,
+    },
+}
+
+impl Config {
+    fn connection(&self) -> irc::connection::Config<'_> {
+        let security = match &self.security {
+            Security::Unsecured => irc::connection::Security::Unsecured,
+            Security::Secured {
+                accept_invalid_certs,
+                root_cert_path,
+                client_cert_path,
+                client_key_path,
+            } => irc::connection::Security::Secured {
+                accept_invalid_certs: *accept_invalid_certs,
+                root_cert_path: root_cert_path.as_ref(),
+                client_cert_path: client_cert_path.as_ref(),
+                client_key_path: client_key_path.as_ref(),
+            },
+        };
+
+        irc::connection::Config {
+            server: &self.server,
+            port: self.port,
+            security,
+            proxy: self.proxy.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Event {
+    /// Messages sent with `sender` go to the server. Sending a `QUIT` ends
+    /// the connection without retrying it.
+    Connected {
+        sender: mpsc::Sender<proto::Message>,
+        is_initial: bool,
+        sent_time: DateTime<Utc>,
+    },
+    Disconnected {
+        is_initial: bool,
+        error: Option<String>,
+        sent_time: DateTime<Utc>,
+    },
+    ConnectionFailed {
+        error: String,
+        sent_time: DateTime<Utc>,
+    },
+    /// Messages received since the last batch, other than `PING`s and
+    /// `PONG`s
+    MessagesReceived(Vec<proto::Message>),
+    /// Round-trip time of a PING sent by us.
+    Lag(Duration),
+    Quit(Option<String>),
+}
+
+enum State {
+    Disconnected {
+        last_retry: Option<Instant>,
+    },
+    Connected {
+        stream: Stream,
+        batch: Batch,
+        ping_time: Interval,
+        ping_timeout: Option<Interval>,
+        quit_requested: Option<(Instant, Option<String>)>,
+    },
+    Quit,
+}
+
+enum Input {
+    IrcMessage(Result<codec::ParseResult, codec::Error>),
+    Batch(Vec<proto::Message>),
+    Send(proto::Message),
+    Ping,
+    PingTimeout,
+    Quit(Option<String>),
+}
+
+struct Stream {
+    connection: Connection<irc::Codec>,
+    receiver: mpsc::Receiver<proto::Message>,
+}
+
+/// Connects to the server, starting with a `Disconnected` event. `name`
+/// identifies the server in logs.
+pub fn run(name: String, config: Config) -> impl futures::Stream<Item = Event> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    // Spawn so the connection isn't held up by a consumer with backpressure
+    // (e.g. an iced subscription). The task is aborted when the stream is
+    // dropped, independently of any other connection.
+    let runner = stream::once(async {
+        let mut task = AbortOnDrop(tokio::spawn(_run(name, config, sender)));
+
+        (&mut task.0).await
+    })
+    .map(|_| unreachable!());
+
+    stream::select(receiver, runner)
+}
+
+struct AbortOnDrop<T>(JoinHandle<T>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+async fn _run(
+    server: String,
+    config: Config,
+    sender: mpsc::UnboundedSender<Event>,
+) -> Never {
+    let mut is_initial = true;
+    let mut state = State::Disconnected { last_retry: None };
+
+    // Notify of initial disconnected state
+    let _ = sender.unbounded_send(Event::Disconnected {
+        is_initial,
+        error: None,
+        sent_time: Utc::now(),
+    });
+
+    loop {
+        match &mut state {
+            State::Disconnected { last_retry } => {
+                if let Some(last_retry) = last_retry.as_ref() {
+                    let remaining = config
+                        .reconnect_delay
+                        .saturating_sub(last_retry.elapsed());
+
+                    if !remaining.is_zero() {
+                        time::sleep(remaining).await;
+                    }
+                }
+
+                match Connection::new(config.connection(), irc::Codec).await {
+                    Ok(connection) => {
+                        log::info!("[{server}] connected");
+
+                        let (message_sender, receiver) = mpsc::channel(100);
+
+                        let _ = sender.unbounded_send(Event::Connected {
+                            sender: message_sender,
+                            is_initial,
+                            sent_time: Utc::now(),
+                        });
+
+                        is_initial = false;
+
+                        state = State::Connected {
+                            stream: Stream {
+                                connection,
+                                receiver,
+                            },
+                            batch: Batch::new(),
+                            ping_timeout: None,
+                            ping_time: interval(config.ping_time),
+                            quit_requested: None,
+                        };
+                    }
+                    Err(e) => {
+                        let error = match e {
+                            // unwrap Tls-specific error enums to access more error info
+                            Error::Tls(e) => {
+                                format!("a TLS error occurred: {e}")
+                            }
+                            _ => e.to_string(),
+                        };
+
+                        log::info!("[{server}] connection failed: {error}");
+
+                        let _ =
+                            sender.unbounded_send(Event::ConnectionFailed {
+                                error,
+                                sent_time: Utc::now(),
+                            });
+
+                        *last_retry = Some(Instant::now());
+                    }
+                }
+            }
+            State::Connected {
+                stream,
+                batch,
+                ping_time,
+                ping_timeout,
+                quit_requested,
+            } => {
+                let input = {
+                    let mut select = stream::select_all([
+                        (&mut stream.connection).map(Input::IrcMessage).boxed(),
+                        (&mut stream.receiver).map(Input::Send).boxed(),
+                        ping_time
+                            .tick()
+                            .into_stream()
+                            .map(|_| Input::Ping)
+                            .boxed(),
+                        batch.map(Input::Batch).boxed(),
+                    ]);
+
+                    if let Some(timeout) = ping_timeout.as_mut() {
+                        select.push(
+                            timeout
+                                .tick()
+                                .into_stream()
+                                .map(|_| Input::PingTimeout)
+                                .boxed(),
+                        );
+                    }
+
+                    if let Some((requested_at, reason)) = quit_requested {
+                        select.push(
+                            time::sleep_until(
+                                *requested_at + QUIT_REQUEST_TIMEOUT,
+                            )
+                            .into_stream()
+                            .map(|()| Input::Quit(reason.clone()))
+                            .boxed(),
+                        );
+                    }
+
+                    select.next().await.expect("stream input")
+                };
+
+                match input {
+                    Input::IrcMessage(Ok(Ok(message))) => match message.command
+                    {
+                        proto::Command::PING(token) => {
+                            let _ = stream
+                                .connection
+                                .send(command!("PONG", token))
+                                .await;
+                        }
+                        proto::Command::PONG(_, token) => {
+                            let token = token.unwrap_or_default();
+                            log::trace!("[{server}] pong received: {token}");
+
+                            *ping_timeout = None;
+
+                            // Our PINGs carry the time they were sent
+                            if let Some(lag) = token
+                                .parse::<u64>()
+                                .ok()
+                                .and_then(|sent| now().checked_sub(sent))
+                                .map(Duration::from_nanos)
+                            {
+                                let _ = sender.unbounded_send(Event::Lag(lag));
+                            }
+                        }
+                        proto::Command::ERROR(error) => {
+                            if let Some(reason) = quit_requested
+                                .as_ref()
+                                .map(|(_, reason)| reason)
+                            {
+                                // If QUIT was requested, then ERROR is
+                                // a valid acknowledgement
+                                // https://modern.ircdocs.horse/#quit-message
+                                let _ = sender.unbounded_send(Event::Quit(
+                                    reason.clone(),
+                                ));
+
+                                state = State::Quit;
+                            } else {
+                                log::info!("[{server}] disconnected: {error}");
+                                let _ = sender.unbounded_send(
+                                    Event::Disconnected {
+                                        is_initial,
+                                        error: Some(error),
+                                        sent_time: Utc::now(),
+                                    },
+                                );
+                                state = State::Disconnected {
+                                    last_retry: Some(Instant::now()),
+                                };
+                            }
+                        }
+                        _ => {
+                            batch.messages.push(message);
+                        }
+                    },
+                    Input::IrcMessage(Ok(Err(e))) => {
+                        log::warn!("message decoding failed: {e}");
+                    }
+                    Input::IrcMessage(Err(e)) => {
+                        log::info!("[{server}] disconnected: {e}");
+                        let _ = sender.unbounded_send(Event::Disconnected {
+                            is_initial,
+                            error: Some(e.to_string()),
+                            sent_time: Utc::now(),
+                        });
+                        state = State::Disconnected {
+                            last_retry: Some(Instant::now()),
+                        };
+                    }
+                    Input::Batch(messages) => {
+                        let _ = sender
+                            .unbounded_send(Event::MessagesReceived(messages));
+                    }
+                    Input::Send(message) => {
+                        log::trace!(
+                            "[{server}] Sending message => {message:?}"
+                        );
+
+                        if let Command::QUIT(reason) = &message.command {
+                            let reason = reason.clone();
+
+                            let _ = stream.connection.send(message).await;
+
+                            log::info!("[{server}] quit");
+
+                            *quit_requested = Some((Instant::now(), reason));
+                        } else {
+                            let _ = stream.connection.send(message).await;
+                        }
+                    }
+                    Input::Ping => {
+                        let now = now().to_string();
+                        log::trace!("[{server}] ping sent: {now}");
+
+                        let _ =
+                            stream.connection.send(command!("PING", now)).await;
+
+                        if ping_timeout.is_none() {
+                            *ping_timeout = Some(interval(config.ping_timeout));
+                        }
+                    }
+                    Input::PingTimeout => {
+                        log::info!("[{server}] ping timeout");
+                        let _ = sender.unbounded_send(Event::Disconnected {
+                            is_initial,
+                            error: Some("ping timeout".into()),
+                            sent_time: Utc::now(),
+                        });
+                        state = State::Disconnected {
+                            last_retry: Some(Instant::now()),
+                        };
+                    }
+                    Input::Quit(reason) => {
+                        let _ = sender.unbounded_send(Event::Quit(reason));
+
+                        state = State::Quit;
+                    }
+                }
+            }
+            State::Quit => {
+                // Wait forever until this stream is dropped
+                future::pending::<()>().await;
+            }
+        }
+    }
+}
+
+struct Batch {
+    interval: Interval,
+    messages: Vec<proto::Message>,
+}
+
+impl Batch {
+    const INTERVAL_MILLIS: u64 = 50;
+
+    fn new() -> Self {
+        Self {
+            interval: interval(Duration::from_millis(Self::INTERVAL_MILLIS)),
+            messages: vec![],
+        }
+    }
+}
+
+impl futures::Stream for Batch {
+    type Item = Vec<proto::Message>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let batch = self.get_mut();
+
+        match batch.interval.poll_tick(cx) {
+            std::task::Poll::Ready(_) => {
+                let messages = std::mem::take(&mut batch.messages);
+
+                if messages.is_empty() {
+                    std::task::Poll::Pending
+                } else {
+                    std::task::Poll::Ready(Some(messages))
+                }
+            }
+            std::task::Poll::Pending => std::task::Poll::Pending,
+        }
+    }
+}
+
+/// Ticks every `period`, starting after the first one
+fn interval(period: Duration) -> Interval {
+    time::interval_at(Instant::now() + period, period)
+}
+
+/// Nanoseconds since the Unix epoch, sent with our PINGs
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .expect("valid unix timestamp")
+        .as_nanos() as u64
+}
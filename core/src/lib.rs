@@ -0,0 +1,287 @@
+//! Halloy's IRC core, for embedding in bots and tests.
+//!
+//! It holds the parts of the client which don't depend on its UI: the
+//! [`connection`] loop (TLS, proxies, `PING`s and reconnecting), the
+//! [`client`] registration (capability negotiation, SASL and ISUPPORT) and
+//! the [`isupport`] and [`capability`] parsing. The `data` crate builds the
+//! full client on top of them.
+//!
+//! A [`Session`] drives a single server connection and turns everything the
+//! server sends into [`Event`]s.
+//!
+//! ```no_run
+//! use halloy_core::proto::command;
+//! use halloy_core::{Event, Session, client, connection};
+//!
+//! async fn run(config: connection::Config, registration: client::Registration) {
+//!     let mut session = Session::connect("libera".to_string(), config, registration);
+//!
+//!     while let Some(event) = session.next().await {
+//!         match event {
+//!             Event::Registered => {
+//!                 let _ = session.send(command!("JOIN", "#halloy"));
+//!             }
+//!             Event::Message(message) => println!("{message:?}"),
+//!             Event::Quit(_) => break,
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//! ```
+#![allow(clippy::large_enum_variant)]
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream::BoxStream;
+pub use irc::proto;
+use irc::proto::Command;
+
+pub use self::client::Client;
+
+pub mod capability;
+pub mod client;
+pub mod connection;
+pub mod isupport;
+
+#[derive(Debug)]
+pub enum Event {
+    /// Connected to the server, registration has started
+    Connected,
+    /// Registration completed, the server accepts any command
+    Registered,
+    /// The connection was lost, it is retried after the `reconnect_delay`
+    Disconnected { error: Option<String> },
+    /// Connecting failed, it is retried after the `reconnect_delay`
+    ConnectionFailed { error: String },
+    /// Round-trip time of the latest `PING` sent to the server
+    Lag(Duration),
+    /// A message from the server, once the client has handled it
+    Message(proto::Message),
+    /// The connection was closed after a quit and won't be retried
+    Quit(Option<String>),
+}
+
+pub struct Session {
+    name: String,
+    registration: client::Registration,
+    client: Option<Client>,
+    events: BoxStream<'static, connection::Event>,
+    pending: VecDeque<Event>,
+}
+
+impl Session {
+    /// Starts connecting to the server. The connection is only made once
+    /// the session is polled with [`Session::next`]. `name` identifies the
+    /// server in logs.
+    pub fn connect(
+        name: String,
+        config: connection::Config,
+        registration: client::Registration,
+    ) -> Self {
+        Self {
+            events: connection::run(name.clone(), config).boxed(),
+            name,
+            registration,
+            client: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// The registration state (capabilities, ISUPPORT), while connected
+    pub fn client(&self) -> Option<&Client> {
+        self.client.as_ref()
+    }
+
+    /// Waits for the next event. Returns `None` once the connection has
+    /// ended for good.
+    pub async fn next(&mut self) -> Option<Event> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+
+            let event = self.events.next().await?;
+            self.update(event);
+        }
+    }
+
+    pub fn send(&mut self, message: proto::Message) -> Result<(), Error> {
+        let client = self.client.as_mut().ok_or(Error::NotConnected)?;
+
+        Ok(client.send(message)?)
+    }
+
+    /// Quits the server, ending the session once the server closes the
+    /// connection
+    pub fn quit(&mut self, reason: Option<String>) -> Result<(), Error> {
+        self.send(Command::QUIT(reason).into())
+    }
+
+    fn update(&mut self, event: connection::Event) {
+        match event {
+            connection::Event::Connected { sender, .. } => {
+                let mut client = Client::new(
+                    self.name.clone(),
+                    self.registration.clone(),
+                    sender,
+                );
+
+                if let Err(error) = client.connect() {
+                    log::error!("[{}] {error}", self.name);
+                }
+
+                self.client = Some(client);
+                self.pending.push_back(Event::Connected);
+            }
+            connection::Event::Disconnected { error, .. } => {
+                self.client = None;
+                self.pending.push_back(Event::Disconnected { error });
+            }
+            connection::Event::ConnectionFailed { error, .. } => {
+                self.pending.push_back(Event::ConnectionFailed { error });
+            }
+            connection::Event::Lag(lag) => {
+                self.pending.push_back(Event::Lag(lag));
+            }
+            connection::Event::MessagesReceived(messages) => {
+                for message in messages {
+                    if let Some(client) = self.client.as_mut() {
+                        match client.receive(&message) {
+                            Ok(events) => {
+                                if events.iter().any(|event| {
+                                    matches!(event, client::Event::Registered)
+                                }) {
+                                    self.pending.push_back(Event::Registered);
+                                }
+                            }
+                            Err(error) => {
+                                log::error!("[{}] {error}", self.name);
+                            }
+                        }
+                    }
+
+                    self.pending.push_back(Event::Message(message));
+                }
+            }
+            connection::Event::Quit(reason) => {
+                self.client = None;
+                self.pending.push_back(Event::Quit(reason));
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("not connected")]
+    NotConnected,
+    #[error(transparent)]
+    Client(#[from] client::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use futures::channel::mpsc;
+
+    use super::*;
+
+    fn session(events: Vec<connection::Event>) -> Session {
+        Session {
+            name: "libera".to_string(),
+            registration: client::Registration {
+                nickname: "halloy".to_string(),
+                username: None,
+                realname: None,
+                password: None,
+                sasl: None,
+                chathistory: false,
+            },
+            client: None,
+            events: futures::stream::iter(events).boxed(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    fn message(line: &str) -> proto::Message {
+        proto::parse::message(&format!("{line}\r\n")).expect("valid message")
+    }
+
+    #[tokio::test]
+    async fn events_until_quit() {
+        let mut session = session(vec![
+            connection::Event::ConnectionFailed {
+                error: "refused".to_string(),
+                sent_time: Utc::now(),
+            },
+            connection::Event::Quit(Some("bye".to_string())),
+        ]);
+
+        assert!(matches!(
+            session.next().await,
+            Some(Event::ConnectionFailed { error }) if error == "refused"
+        ));
+        assert!(matches!(
+            session.next().await,
+            Some(Event::Quit(Some(reason))) if reason == "bye"
+        ));
+        assert!(session.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn registers_on_connect() {
+        let (sender, mut receiver) = mpsc::channel(100);
+
+        let mut session = session(vec![
+            connection::Event::Connected {
+                sender,
+                is_initial: true,
+                sent_time: Utc::now(),
+            },
+            connection::Event::MessagesReceived(vec![
+                message(":irc.libera.chat CAP * LS :multi-prefix"),
+                message(":irc.libera.chat CAP * ACK :multi-prefix"),
+                message(":irc.libera.chat 376 halloy :End of /MOTD command."),
+            ]),
+        ]);
+
+        assert!(matches!(session.next().await, Some(Event::Connected)));
+        assert!(matches!(session.next().await, Some(Event::Message(_))));
+        assert!(matches!(session.next().await, Some(Event::Message(_))));
+        assert!(matches!(session.next().await, Some(Event::Registered)));
+        assert!(matches!(session.next().await, Some(Event::Message(_))));
+        assert!(session.client().is_some_and(Client::is_registered));
+
+        session.send(proto::command!("JOIN", "#halloy")).unwrap();
+
+        let sent = std::iter::from_fn(|| receiver.try_recv().ok())
+            .map(|message| {
+                proto::format::message(message).trim_end().to_string()
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            sent,
+            [
+                "CAP LS 302",
+                "NICK halloy",
+                "USER halloy 0 * halloy",
+                "CAP REQ multi-prefix",
+                "CAP END",
+                "JOIN #halloy",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn send_requires_connection() {
+        let mut session = session(vec![]);
+
+        assert!(matches!(
+            session.send(proto::command!("JOIN", "#halloy")),
+            Err(Error::NotConnected)
+        ));
+    }
+}
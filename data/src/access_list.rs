@@ -0,0 +1,266 @@
+//! Channel access lists as listed by the network's services, e.g. ChanServ's
+//! `FLAGS` or `ACCESS LIST`, which reply with one notice per line.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::target;
+
+/// Services which don't end their reply with an "End of" line, e.g. when
+/// replying with an error, stop being collected after this long.
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccessList {
+    /// Column names from the header of the listing, if there was one
+    pub header: Vec<String>,
+    pub entries: Vec<Entry>,
+    /// Other lines of the reply, e.g. errors or the result of a change
+    pub notes: Vec<String>,
+    pub is_complete: bool,
+}
+
+impl AccessList {
+    /// Column of the entries holding the nickname, account or mask, which
+    /// identifies an entry when removing it.
+    pub fn mask_column(&self) -> usize {
+        self.header
+            .iter()
+            .position(|column| {
+                let column = column.to_lowercase();
+
+                ["mask", "nick", "host", "account"]
+                    .iter()
+                    .any(|name| column.contains(name))
+            })
+            .unwrap_or(1)
+    }
+
+    pub fn mask<'a>(&self, entry: &'a Entry) -> Option<&'a str> {
+        entry.fields.get(self.mask_column()).map(String::as_str)
+    }
+
+    fn receive(&mut self, line: &str) {
+        let line = strip_formatting(line);
+        let line = line.trim();
+
+        if line.is_empty()
+            || line.chars().all(|c| c == '-' || c.is_whitespace())
+        {
+            return;
+        }
+
+        if line.to_lowercase().starts_with("end of") {
+            self.is_complete = true;
+            return;
+        }
+
+        let is_entry = line
+            .split_whitespace()
+            .next()
+            .is_some_and(|first| first.parse::<u32>().is_ok());
+
+        if !is_entry {
+            self.notes.push(line.to_string());
+            return;
+        }
+
+        // The line before the first entry names the columns
+        if self.entries.is_empty()
+            && self.header.is_empty()
+            && let Some(header) = self
+                .notes
+                .pop_if(|note| note.split_whitespace().count() >= 2)
+        {
+            self.header =
+                header.split_whitespace().map(str::to_string).collect();
+        }
+
+        self.entries.push(Entry {
+            fields: split_columns(line, self.header.len()),
+        });
+    }
+}
+
+/// Splits `line` on whitespace into at most `columns` fields, the last of
+/// which holds the rest of the line.
+fn split_columns(line: &str, columns: usize) -> Vec<String> {
+    let mut fields = vec![];
+    let mut rest = line.trim();
+
+    while !rest.is_empty() {
+        if columns > 0 && fields.len() == columns - 1 {
+            fields.push(rest.to_string());
+            break;
+        }
+
+        let (field, remainder) =
+            rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+        fields.push(field.to_string());
+        rest = remainder.trim_start();
+    }
+
+    fields
+}
+
+fn strip_formatting(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\x02' | '\x0f' | '\x11' | '\x16' | '\x1d' | '\x1e' | '\x1f' => {}
+            '\x03' => {
+                for _ in 0..2 {
+                    chars.next_if(char::is_ascii_digit);
+                }
+                if chars.peek() == Some(&',') {
+                    chars.next();
+                    for _ in 0..2 {
+                        chars.next_if(char::is_ascii_digit);
+                    }
+                }
+            }
+            c => stripped.push(c),
+        }
+    }
+
+    stripped
+}
+
+/// Access lists of a single server's channels. Only the list of the last
+/// requested channel is collected.
+#[derive(Debug, Clone, Default)]
+pub struct Lists {
+    pending: Option<(target::Channel, Instant)>,
+    lists: HashMap<target::Channel, AccessList>,
+}
+
+impl Lists {
+    pub fn request(&mut self, channel: target::Channel, now: Instant) {
+        self.lists.insert(channel.clone(), AccessList::default());
+        self.pending = Some((channel, now));
+    }
+
+    pub fn get(&self, channel: &target::Channel) -> Option<&AccessList> {
+        self.lists.get(channel)
+    }
+
+    pub fn is_pending(&self, channel: &target::Channel, now: Instant) -> bool {
+        self.pending
+            .as_ref()
+            .is_some_and(|(pending, requested_at)| {
+                pending == channel
+                    && now.duration_since(*requested_at) < TIMEOUT
+            })
+    }
+
+    /// Collects a notice from the services into the pending list. Returns
+    /// whether it was collected.
+    pub fn receive(&mut self, text: &str, now: Instant) -> bool {
+        let Some((channel, requested_at)) = &self.pending else {
+            return false;
+        };
+
+        if now.duration_since(*requested_at) >= TIMEOUT {
+            self.pending = None;
+            return false;
+        }
+
+        let Some(list) = self.lists.get_mut(channel) else {
+            return false;
+        };
+
+        list.receive(text);
+
+        if list.is_complete {
+            self.pending = None;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::isupport;
+
+    fn channel() -> target::Channel {
+        target::Channel::from_str(
+            "#halloy",
+            isupport::DEFAULT_CHANTYPES,
+            isupport::CaseMap::default(),
+        )
+    }
+
+    fn receive(lines: &[&str]) -> AccessList {
+        let now = Instant::now();
+        let mut lists = Lists::default();
+        lists.request(channel(), now);
+
+        for line in lines {
+            assert!(lists.receive(line, now), "{line}");
+        }
+
+        lists.get(&channel()).unwrap().clone()
+    }
+
+    #[test]
+    fn atheme_flags() {
+        let list = receive(&[
+            "Entry Nickname/Host          Flags",
+            "----- ---------------------- -----",
+            "1     \x02casperstorm\x02            +AFRefiorstv [modified 1y ago]",
+            "2     *!*@example.org        +Vv",
+            "----- ---------------------- -----",
+            "End of \x02#halloy\x02 FLAGS listing.",
+        ]);
+
+        assert!(list.is_complete);
+        assert_eq!(list.header, vec!["Entry", "Nickname/Host", "Flags"]);
+        assert_eq!(
+            list.entries[0].fields,
+            vec!["1", "casperstorm", "+AFRefiorstv [modified 1y ago]"]
+        );
+        assert_eq!(list.mask(&list.entries[1]), Some("*!*@example.org"));
+        assert!(list.notes.is_empty());
+    }
+
+    #[test]
+    fn anope_access_list() {
+        let list = receive(&[
+            "Access list for \x02#halloy\x02:",
+            "  Number  Level  Mask",
+            "  1       10000  casperstorm",
+            "  2       3      tarkah",
+            "End of access list.",
+        ]);
+
+        assert!(list.is_complete);
+        assert_eq!(list.mask_column(), 2);
+        assert_eq!(list.mask(&list.entries[1]), Some("tarkah"));
+        assert_eq!(list.notes, vec!["Access list for #halloy:"]);
+    }
+
+    #[test]
+    fn timeout() {
+        let now = Instant::now();
+        let mut lists = Lists::default();
+        lists.request(channel(), now);
+
+        assert!(lists.receive("You are not authorized to do that.", now));
+        assert!(!lists.receive("Unrelated notice", now + TIMEOUT));
+        assert!(!lists.is_pending(&channel(), now));
+        assert_eq!(
+            lists.get(&channel()).unwrap().notes,
+            vec!["You are not authorized to do that."]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Replies to the STATS, LINKS and MAP queries, collected for the server
+//! admin view instead of being shown as raw numerics.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use irc::proto::{self, Command, command};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Query {
+    /// `STATS` with the given query letter
+    Stats(char),
+    Links,
+    Map,
+}
+
+impl Query {
+    fn message(self) -> proto::Message {
+        match self {
+            Query::Stats(letter) => {
+                proto::command!("STATS", letter.to_string())
+            }
+            Query::Links => proto::command!("LINKS"),
+            Query::Map => proto::command!("MAP"),
+        }
+    }
+}
+
+/// A row of a `STATS` reply, e.g. a single `RPL_STATSLINKINFO`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsRow {
+    pub numeric: String,
+    pub fields: Vec<String>,
+}
+
+/// A server link from a `LINKS` reply.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub server: String,
+    /// Server `server` is linked to, the root server is linked to itself
+    pub hub: String,
+    pub hops: u32,
+    pub info: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Stats(Vec<StatsRow>),
+    Links(Vec<Link>),
+    /// Lines of the server map, which the server already draws as a tree
+    Map(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub reply: Reply,
+    pub error: Option<String>,
+    pub is_complete: bool,
+    pub requested_at: DateTime<Utc>,
+}
+
+impl Report {
+    fn new(query: Query) -> Self {
+        Self {
+            reply: match query {
+                Query::Stats(_) => Reply::Stats(vec![]),
+                Query::Links => Reply::Links(vec![]),
+                Query::Map => Reply::Map(vec![]),
+            },
+            error: None,
+            is_complete: false,
+            requested_at: Utc::now(),
+        }
+    }
+}
+
+/// Reports of a single server, by query. Only one query is collected at a
+/// time, the replies of an earlier one are left in the server buffer.
+#[derive(Debug, Clone, Default)]
+pub struct Reports {
+    pending: Option<Query>,
+    reports: HashMap<Query, Report>,
+}
+
+impl Reports {
+    /// Starts collecting the replies to `query`, returning the message to
+    /// send.
+    pub fn request(&mut self, query: Query) -> proto::Message {
+        self.pending = Some(query);
+        self.reports.insert(query, Report::new(query));
+
+        query.message()
+    }
+
+    pub fn get(&self, query: Query) -> Option<&Report> {
+        self.reports.get(&query)
+    }
+
+    /// Collects `command` into the report of the pending query. Returns
+    /// whether `command` was part of the reply.
+    pub fn receive(&mut self, command: &Command) -> bool {
+        use command::Numeric::*;
+
+        let Some(query) = self.pending else {
+            return false;
+        };
+        let Some(report) = self.reports.get_mut(&query) else {
+            return false;
+        };
+
+        match (&mut report.reply, command) {
+            (Reply::Links(links), Command::Numeric(RPL_LINKS, args)) => {
+                if let Some(link) = parse_link(args) {
+                    links.push(link);
+                }
+            }
+            (Reply::Map(lines), Command::Numeric(RPL_MAP, args)) => {
+                lines.push(args.last().cloned().unwrap_or_default());
+            }
+            (Reply::Stats(rows), Command::Numeric(numeric, args))
+                if is_stats_row(*numeric as u16) =>
+            {
+                rows.push(StatsRow {
+                    numeric: format!("{:03}", *numeric as u16),
+                    fields: args.iter().skip(1).cloned().collect(),
+                });
+            }
+            (Reply::Stats(rows), Command::Unknown(tag, args))
+                if tag.parse::<u16>().is_ok_and(is_stats_row) =>
+            {
+                rows.push(StatsRow {
+                    numeric: tag.clone(),
+                    fields: args.iter().skip(1).cloned().collect(),
+                });
+            }
+            (Reply::Links(_), Command::Numeric(RPL_ENDOFLINKS, _))
+            | (Reply::Map(_), Command::Numeric(RPL_MAPEND, _))
+            | (Reply::Stats(_), Command::Numeric(RPL_ENDOFSTATS, _)) => {
+                report.is_complete = true;
+                self.pending = None;
+            }
+            (
+                _,
+                Command::Numeric(
+                    ERR_NOPRIVILEGES | ERR_NOPRIVS | ERR_UNKNOWNCOMMAND,
+                    args,
+                ),
+            ) => {
+                report.error = args.last().cloned();
+                report.is_complete = true;
+                self.pending = None;
+            }
+            _ => return false,
+        }
+
+        true
+    }
+}
+
+/// Numerics of `STATS` replies, which vary between servers.
+fn is_stats_row(numeric: u16) -> bool {
+    (210..=250).contains(&numeric) && numeric != 219 && numeric != 221
+}
+
+fn parse_link(args: &[String]) -> Option<Link> {
+    let [_, server, hub, text] = args else {
+        return None;
+    };
+
+    let (hops, info) = text.split_once(' ').unwrap_or((text, ""));
+
+    Some(Link {
+        server: server.clone(),
+        hub: hub.clone(),
+        hops: hops.parse().ok()?,
+        info: info.to_string(),
+    })
+}
+
+/// Orders `links` depth first from the root server, pairing each link with
+/// its depth in the tree. Links which can't be placed, e.g. because the
+/// server hides its topology, are appended at the root level.
+pub fn link_tree(links: &[Link]) -> Vec<(usize, &Link)> {
+    fn visit<'a>(
+        link: &'a Link,
+        depth: usize,
+        links: &'a [Link],
+        visited: &mut HashSet<&'a str>,
+        tree: &mut Vec<(usize, &'a Link)>,
+    ) {
+        if !visited.insert(link.server.as_str()) {
+            return;
+        }
+
+        tree.push((depth, link));
+
+        for child in links.iter().filter(|child| {
+            child.hub == link.server && child.hub != child.server
+        }) {
+            visit(child, depth + 1, links, visited, tree);
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut tree = Vec::with_capacity(links.len());
+
+    for root in links.iter().filter(|link| link.hub == link.server) {
+        visit(root, 0, links, &mut visited, &mut tree);
+    }
+
+    for link in links {
+        visit(link, 0, links, &mut visited, &mut tree);
+    }
+
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use irc::proto;
+
+    use super::*;
+
+    fn receive(reports: &mut Reports, lines: &[&str]) {
+        for line in lines {
+            let message =
+                proto::parse::message(&format!("{line}\r\n")).unwrap();
+
+            assert!(reports.receive(&message.command), "{line}");
+        }
+    }
+
+    #[test]
+    fn links() {
+        let mut reports = Reports::default();
+        reports.request(Query::Links);
+
+        receive(
+            &mut reports,
+            &[
+                ":hub.example 364 dan leaf.example hub.example :1 Leaf server",
+                ":hub.example 364 dan hub.example hub.example :0 Hub server",
+                ":hub.example 364 dan edge.example leaf.example :2 Edge",
+                ":hub.example 365 dan * :End of /LINKS list.",
+            ],
+        );
+
+        let report = reports.get(Query::Links).unwrap();
+        assert!(report.is_complete);
+
+        let Reply::Links(links) = &report.reply else {
+            panic!("expected links");
+        };
+
+        let tree = link_tree(links)
+            .into_iter()
+            .map(|(depth, link)| (depth, link.server.as_str()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            tree,
+            vec![(0, "hub.example"), (1, "leaf.example"), (2, "edge.example")]
+        );
+        assert_eq!(links[0].info, "Leaf server");
+    }
+
+    #[test]
+    fn stats() {
+        let mut reports = Reports::default();
+        reports.request(Query::Stats('u'));
+
+        receive(
+            &mut reports,
+            &[
+                ":irc.example 242 dan :Server Up 3 days, 2:01:07",
+                ":irc.example 249 dan :Max connections: 42",
+                ":irc.example 219 dan u :End of /STATS report",
+            ],
+        );
+
+        let report = reports.get(Query::Stats('u')).unwrap();
+        assert!(report.is_complete);
+        assert_eq!(
+            report.reply,
+            Reply::Stats(vec![
+                StatsRow {
+                    numeric: "242".to_string(),
+                    fields: vec!["Server Up 3 days, 2:01:07".to_string()],
+                },
+                StatsRow {
+                    numeric: "249".to_string(),
+                    fields: vec!["Max connections: 42".to_string()],
+                },
+            ])
+        );
+
+        // Replies are only collected while a query is pending
+        let message =
+            proto::parse::message(":irc.example 242 dan :Server Up\r\n")
+                .unwrap();
+        assert!(!reports.receive(&message.command));
+    }
+
+    #[test]
+    fn no_privileges() {
+        let mut reports = Reports::default();
+        reports.request(Query::Map);
+
+        receive(
+            &mut reports,
+            &[
+                ":irc.example 481 dan :Permission Denied - You're not an IRC operator",
+            ],
+        );
+
+        let report = reports.get(Query::Map).unwrap();
+        assert!(report.is_complete);
+        assert_eq!(
+            report.error.as_deref(),
+            Some("Permission Denied - You're not an IRC operator")
+        );
+    }
+}
@@ -44,4 +44,24 @@ impl Selected {
     pub fn specific(theme: Theme) -> Selected {
         Selected::Static(theme)
     }
+
+    /// The same selection with its themes replaced by their versions in
+    /// `all`, keeping a theme picked at random from a list when reloading.
+    /// Themes no longer in `all` are kept as they were.
+    pub fn reloaded(&self, all: &[Theme]) -> Selected {
+        let reload = |theme: &Theme| {
+            all.iter()
+                .find(|reloaded| reloaded.name == theme.name)
+                .unwrap_or(theme)
+                .clone()
+        };
+
+        match self {
+            Selected::Static(theme) => Selected::Static(reload(theme)),
+            Selected::Dynamic { light, dark } => Selected::Dynamic {
+                light: reload(light),
+                dark: reload(dark),
+            },
+        }
+    }
 }
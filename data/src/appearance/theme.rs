@@ -11,6 +11,11 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use thiserror::Error;
 use tokio::fs;
 
+use crate::buffer;
+
+pub mod community;
+pub mod import;
+
 const DEFAULT_THEME_NAME: &str = "Ferra";
 const DEFAULT_THEME_CONTENT: &str =
     include_str!("../../../assets/themes/ferra.toml");
@@ -55,6 +60,8 @@ pub struct Styles {
     pub buffer: Buffer,
     #[serde(default)]
     pub buttons: Buttons,
+    #[serde(default)]
+    pub formatting: Formatting,
 }
 
 impl Default for Styles {
@@ -127,6 +134,50 @@ impl Default for Button {
     }
 }
 
+/// Number of mIRC color codes which can be remapped, `0` through `98`
+pub const FORMATTING_COLORS: usize = 99;
+
+/// How colors sent in messages with mIRC color codes are shown
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Formatting {
+    /// Replacements for color codes, indexed by code
+    #[serde(with = "formatting_colors_serde")]
+    pub colors: [Option<Color>; FORMATTING_COLORS],
+    /// Saturation (0.0-1.0) which message colors are clamped to
+    pub max_saturation: Option<f32>,
+    /// Minimum contrast ratio of message text against the buffer background
+    pub min_contrast: Option<f32>,
+}
+
+impl Default for Formatting {
+    fn default() -> Self {
+        Self {
+            colors: [None; FORMATTING_COLORS],
+            max_saturation: None,
+            min_contrast: None,
+        }
+    }
+}
+
+impl Formatting {
+    /// Clamps the saturation of a message color, if configured.
+    pub fn clamp(&self, color: Color) -> Color {
+        match self.max_saturation {
+            Some(max_saturation) => {
+                let mut hsl = to_hsl(color);
+                hsl.saturation = hsl.saturation.min(max_saturation);
+
+                Color {
+                    a: color.a,
+                    ..from_hsl(hsl)
+                }
+            }
+            None => color,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(default)]
 pub struct General {
@@ -182,6 +233,19 @@ pub struct Buffer {
     pub topic: TextStyle,
     pub url: TextStyle,
     pub nickname_offline: OptionalTextStyle,
+    /// Background of every other message, striping the message view
+    #[serde(with = "color_serde_maybe")]
+    pub background_alt: Option<Color>,
+    /// Background of the message under the cursor
+    #[serde(with = "color_serde_maybe")]
+    pub hover: Option<Color>,
+    /// Background of messages which highlight us
+    #[serde(with = "color_serde_maybe")]
+    pub highlight_background: Option<Color>,
+    /// Accent drawn along the left edge of messages which highlight us
+    #[serde(with = "color_serde_maybe")]
+    pub highlight_border: Option<Color>,
+    pub nickname_highlight: OptionalTextStyle,
 }
 
 impl Default for Buffer {
@@ -202,6 +266,11 @@ impl Default for Buffer {
             topic: TextStyle::default(),
             url: TextStyle::default(),
             nickname_offline: OptionalTextStyle::default(),
+            background_alt: None,
+            hover: None,
+            highlight_background: None,
+            highlight_border: None,
+            nickname_highlight: OptionalTextStyle::default(),
         }
     }
 }
@@ -381,6 +450,21 @@ pub enum FontStyle {
     Italic,
     #[serde(alias = "bold-italic")]
     ItalicBold,
+    Light,
+    #[serde(alias = "light-italic")]
+    ItalicLight,
+    Semibold,
+    #[serde(alias = "semibold-italic")]
+    ItalicSemibold,
+}
+
+/// Weight of a [`FontStyle`], relative to the configured font weight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FontWeight {
+    Light,
+    Normal,
+    Semibold,
+    Bold,
 }
 
 impl FontStyle {
@@ -392,28 +476,55 @@ impl FontStyle {
             (true, true) => FontStyle::ItalicBold,
         }
     }
+
+    pub fn from_parts(weight: FontWeight, italic: bool) -> Self {
+        match (weight, italic) {
+            (FontWeight::Light, false) => FontStyle::Light,
+            (FontWeight::Light, true) => FontStyle::ItalicLight,
+            (FontWeight::Normal, italic) => FontStyle::new(false, italic),
+            (FontWeight::Semibold, false) => FontStyle::Semibold,
+            (FontWeight::Semibold, true) => FontStyle::ItalicSemibold,
+            (FontWeight::Bold, italic) => FontStyle::new(true, italic),
+        }
+    }
+
+    pub fn weight(self) -> FontWeight {
+        match self {
+            FontStyle::Normal | FontStyle::Italic => FontWeight::Normal,
+            FontStyle::Bold | FontStyle::ItalicBold => FontWeight::Bold,
+            FontStyle::Light | FontStyle::ItalicLight => FontWeight::Light,
+            FontStyle::Semibold | FontStyle::ItalicSemibold => {
+                FontWeight::Semibold
+            }
+        }
+    }
+
+    pub fn is_italic(self) -> bool {
+        matches!(
+            self,
+            FontStyle::Italic
+                | FontStyle::ItalicBold
+                | FontStyle::ItalicLight
+                | FontStyle::ItalicSemibold
+        )
+    }
 }
 
 impl std::ops::Add<FontStyle> for FontStyle {
     type Output = FontStyle;
 
+    /// Combines two styles, e.g. a theme style with the formatting of a
+    /// message. Italics from either side are kept, and a normal weight
+    /// defers to the other side, otherwise the heavier weight wins.
     fn add(self, rhs: FontStyle) -> FontStyle {
-        match self {
-            FontStyle::Normal => rhs,
-            FontStyle::Italic => match rhs {
-                FontStyle::Normal | FontStyle::Italic => FontStyle::Italic,
-                FontStyle::Bold | FontStyle::ItalicBold => {
-                    FontStyle::ItalicBold
-                }
-            },
-            FontStyle::Bold => match rhs {
-                FontStyle::Normal | FontStyle::Bold => FontStyle::Bold,
-                FontStyle::Italic | FontStyle::ItalicBold => {
-                    FontStyle::ItalicBold
-                }
-            },
-            FontStyle::ItalicBold => self,
-        }
+        let weight = match (self.weight(), rhs.weight()) {
+            (FontWeight::Normal, weight) | (weight, FontWeight::Normal) => {
+                weight
+            }
+            (lhs, rhs) => lhs.max(rhs),
+        };
+
+        FontStyle::from_parts(weight, self.is_italic() || rhs.is_italic())
     }
 }
 
@@ -502,6 +613,93 @@ pub fn randomize_color(original_color: Color, seed: &str) -> Color {
     from_hsl(randomized_hsl)
 }
 
+/// Color of the nickname with `seed`, as configured by `kind`. Returns `None`
+/// for solid nicknames, which use the theme's color as is.
+pub fn nickname_color(
+    kind: &buffer::Color,
+    original_color: Color,
+    background: Color,
+    seed: &str,
+) -> Option<Color> {
+    match kind {
+        buffer::Color::Solid => None,
+        buffer::Color::Unique => Some(randomize_color(original_color, seed)),
+        buffer::Color::Palette(palette) => {
+            let index = seahash::hash(seed.as_bytes())
+                % palette.colors.len().max(1) as u64;
+            let color = palette
+                .colors
+                .get(index as usize)
+                .copied()
+                .unwrap_or(original_color);
+
+            Some(match palette.min_contrast {
+                Some(min_contrast) => {
+                    ensure_contrast(color, background, min_contrast)
+                }
+                None => color,
+            })
+        }
+    }
+}
+
+/// WCAG contrast ratio between two colors, from 1.0 to 21.0.
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let luminance = |color: Color| {
+        let channel = |value: f32| {
+            if value <= 0.039_28 {
+                value / 12.92
+            } else {
+                ((value + 0.055) / 1.055).powf(2.4)
+            }
+        };
+
+        0.2126 * channel(color.r)
+            + 0.7152 * channel(color.g)
+            + 0.0722 * channel(color.b)
+    };
+
+    let (a, b) = (luminance(a), luminance(b));
+    let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+/// Moves the lightness of `color` away from `background` until their
+/// contrast ratio is at least `min_contrast`, or lightness runs out.
+pub fn ensure_contrast(
+    color: Color,
+    background: Color,
+    min_contrast: f32,
+) -> Color {
+    const STEP: f32 = 0.02;
+
+    let mut hsl = to_hsl(color);
+    let step = if to_hsl(background).lightness > 0.5 {
+        -STEP
+    } else {
+        STEP
+    };
+
+    let mut adjusted = color;
+
+    while contrast_ratio(adjusted, background) < min_contrast {
+        let lightness = (hsl.lightness + step).clamp(0.0, 1.0);
+
+        if lightness == hsl.lightness {
+            break;
+        }
+
+        hsl.lightness = lightness;
+        adjusted = Color {
+            a: color.a,
+            ..from_hsl(hsl)
+        };
+    }
+
+    adjusted
+}
+
 pub fn to_hsl(color: Color) -> Okhsl {
     let mut hsl = Okhsl::from_color(to_rgb(color));
     if hsl.saturation.is_nan() {
@@ -599,14 +797,73 @@ mod color_serde_maybe {
     }
 }
 
+mod formatting_colors_serde {
+    use std::collections::BTreeMap;
+
+    use iced_core::Color;
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    use super::FORMATTING_COLORS;
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> Result<[Option<Color>; FORMATTING_COLORS], D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let table = BTreeMap::<String, String>::deserialize(deserializer)?;
+        let mut colors = [None; FORMATTING_COLORS];
+
+        for (code, hex) in table {
+            let index = code
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < FORMATTING_COLORS)
+                .ok_or_else(|| {
+                    de::Error::custom(format!("invalid color code: {code}"))
+                })?;
+
+            colors[index] =
+                Some(super::hex_to_color(&hex).ok_or_else(|| {
+                    de::Error::custom(format!("invalid color: {hex}"))
+                })?);
+        }
+
+        Ok(colors)
+    }
+
+    pub fn serialize<S>(
+        colors: &[Option<Color>; FORMATTING_COLORS],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_map(colors.iter().enumerate().filter_map(
+            |(code, color)| {
+                color
+                    .map(|color| (code.to_string(), super::color_to_hex(color)))
+            },
+        ))
+    }
+}
+
 mod binary {
     use iced_core::Color;
     use strum::{IntoEnumIterator, VariantArray};
 
-    use super::{Buffer, Buttons, General, Styles, Text};
+    use super::{
+        Buffer, Buttons, FORMATTING_COLORS, Formatting, General, Styles, Text,
+    };
+
+    /// First of the tags of the formatting colors, one per color code. They
+    /// take the end of the tag range so [`Tag`] can keep growing.
+    const FORMATTING_COLOR_TAG: u8 = (256 - FORMATTING_COLORS) as u8;
 
     pub fn encode(styles: &Styles) -> Vec<u8> {
-        let mut bytes = Vec::with_capacity(Tag::VARIANTS.len() * (1 + 4));
+        let mut bytes = Vec::with_capacity(
+            (Tag::VARIANTS.len() + FORMATTING_COLORS) * (1 + 4),
+        );
 
         for tag in Tag::iter() {
             if let Some(color) = tag.encode(styles) {
@@ -615,6 +872,13 @@ mod binary {
             }
         }
 
+        for (code, color) in styles.formatting.colors.iter().enumerate() {
+            if let Some(color) = color {
+                bytes.push(FORMATTING_COLOR_TAG + code as u8);
+                bytes.extend(color.into_rgba8());
+            }
+        }
+
         bytes
     }
 
@@ -624,19 +888,24 @@ mod binary {
             text: Text::default(),
             buffer: Buffer::default(),
             buttons: Buttons::default(),
+            formatting: Formatting::default(),
         };
 
         for chunk in bytes.chunks(5) {
-            if chunk.len() == 5
-                && let Ok(tag) = Tag::try_from(chunk[0])
-            {
-                let color = Color::from_rgba8(
-                    chunk[1],
-                    chunk[2],
-                    chunk[3],
-                    chunk[4] as f32 / 255.0,
-                );
+            if chunk.len() != 5 {
+                continue;
+            }
 
+            let color = Color::from_rgba8(
+                chunk[1],
+                chunk[2],
+                chunk[3],
+                chunk[4] as f32 / 255.0,
+            );
+
+            if let Some(code) = chunk[0].checked_sub(FORMATTING_COLOR_TAG) {
+                styles.formatting.colors[usize::from(code)] = Some(color);
+            } else if let Ok(tag) = Tag::try_from(chunk[0]) {
                 tag.update_color(&mut styles, color);
             }
         }
@@ -645,7 +914,8 @@ mod binary {
     }
 
     // IMPORTANT: Tags cannot be rearranged or deleted to preserve
-    // backwards compatibility. Only append new items in the future
+    // backwards compatibility. Only append new items in the future, below
+    // `FORMATTING_COLOR_TAG`
     #[derive(
         Debug,
         Clone,
@@ -709,6 +979,11 @@ mod binary {
         BufferNicknameOffline = 49,
         GeneralHighlightIndicator = 50,
         BufferServerMessagesChangeTopic = 51,
+        BufferBackgroundAlt = 52,
+        BufferHover = 53,
+        BufferHighlightBackground = 54,
+        BufferHighlightBorder = 55,
+        BufferNicknameHighlight = 56,
     }
 
     impl Tag {
@@ -820,6 +1095,15 @@ mod binary {
                 Tag::BufferServerMessagesChangeTopic => {
                     styles.buffer.server_messages.change_topic.color?
                 }
+                Tag::BufferBackgroundAlt => styles.buffer.background_alt?,
+                Tag::BufferHover => styles.buffer.hover?,
+                Tag::BufferHighlightBackground => {
+                    styles.buffer.highlight_background?
+                }
+                Tag::BufferHighlightBorder => styles.buffer.highlight_border?,
+                Tag::BufferNicknameHighlight => {
+                    styles.buffer.nickname_highlight.color?
+                }
             };
 
             Some(color.into_rgba8())
@@ -951,7 +1235,152 @@ mod binary {
                     styles.buffer.server_messages.change_topic.color =
                         Some(color);
                 }
+                Tag::BufferBackgroundAlt => {
+                    styles.buffer.background_alt = Some(color);
+                }
+                Tag::BufferHover => styles.buffer.hover = Some(color),
+                Tag::BufferHighlightBackground => {
+                    styles.buffer.highlight_background = Some(color);
+                }
+                Tag::BufferHighlightBorder => {
+                    styles.buffer.highlight_border = Some(color);
+                }
+                Tag::BufferNicknameHighlight => {
+                    styles.buffer.nickname_highlight.color = Some(color);
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_nickname_color_is_deterministic() {
+        let kind = buffer::Color::Palette(buffer::Palette {
+            colors: vec![Color::from_rgb8(0xe0, 0x6c, 0x75), Color::WHITE],
+            min_contrast: None,
+        });
+
+        let first = nickname_color(&kind, Color::BLACK, Color::BLACK, "halloy");
+        let second =
+            nickname_color(&kind, Color::BLACK, Color::BLACK, "halloy");
+
+        assert!(first.is_some());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn font_styles_combine() {
+        assert_eq!(
+            FontStyle::Semibold + FontStyle::Italic,
+            FontStyle::ItalicSemibold
+        );
+        assert_eq!(FontStyle::Light + FontStyle::Bold, FontStyle::Bold);
+        assert_eq!(
+            FontStyle::ItalicBold + FontStyle::Normal,
+            FontStyle::ItalicBold
+        );
+        assert_eq!(
+            FontStyle::Normal + FontStyle::ItalicLight,
+            FontStyle::ItalicLight
+        );
+    }
+
+    #[test]
+    fn text_style_with_font_weight() {
+        #[derive(Deserialize)]
+        struct Data {
+            nickname: TextStyle,
+        }
+
+        let data: Data = toml::from_str(
+            r##"nickname = { color = "#ffffff", font_style = "semibold-italic" }"##,
+        )
+        .expect("parse text style");
+
+        assert_eq!(data.nickname.color, Color::WHITE);
+        assert_eq!(data.nickname.font_style, Some(FontStyle::ItalicSemibold));
+    }
+
+    #[test]
+    fn formatting_colors_serde() {
+        let formatting: Formatting = toml::from_str(
+            r##"
+            max_saturation = 0.5
+            colors = { 4 = "#ff0000", 98 = "#00ff00" }
+            "##,
+        )
+        .expect("parse formatting");
+
+        assert_eq!(formatting.colors[4], Some(Color::from_rgb8(255, 0, 0)));
+        assert_eq!(formatting.colors[98], Some(Color::from_rgb8(0, 255, 0)));
+        assert_eq!(formatting.colors.iter().flatten().count(), 2);
+
+        let serialized = toml::to_string(&formatting).expect("serialize");
+        let roundtrip: Formatting =
+            toml::from_str(&serialized).expect("parse serialized");
+
+        assert_eq!(roundtrip.colors, formatting.colors);
+
+        assert!(
+            toml::from_str::<Formatting>(r##"colors = { 99 = "#ff0000" }"##)
+                .is_err()
+        );
+        assert!(
+            toml::from_str::<Formatting>(r##"colors = { 4 = "red" }"##)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn formatting_clamps_saturation() {
+        let red = Color::from_rgb8(255, 0, 0);
+
+        assert_eq!(Formatting::default().clamp(red), red);
+
+        let formatting = Formatting {
+            max_saturation: Some(0.5),
+            ..Formatting::default()
+        };
+        let clamped = formatting.clamp(Color { a: 0.5, ..red });
+
+        assert!(to_hsl(clamped).saturation <= 0.5 + 1e-3);
+        assert_eq!(clamped.a, 0.5);
+
+        // Already below the maximum, so left as is
+        let grey = Color::from_rgb8(0x80, 0x80, 0x80);
+        let clamped = formatting.clamp(grey);
+
+        assert!((clamped.r - grey.r).abs() < 1e-3);
+        assert!((clamped.g - grey.g).abs() < 1e-3);
+        assert!((clamped.b - grey.b).abs() < 1e-3);
+    }
+
+    #[test]
+    fn binary_keeps_formatting_colors() {
+        let mut styles = binary::decode(&[]);
+        styles.formatting.colors[0] = Some(Color::WHITE);
+        styles.formatting.colors[98] = Some(Color::from_rgb8(1, 2, 3));
+        styles.buffer.background = Color::from_rgb8(4, 5, 6);
+
+        let decoded = binary::decode(&binary::encode(&styles));
+
+        assert_eq!(decoded.formatting.colors, styles.formatting.colors);
+        assert_eq!(decoded.buffer.background, styles.buffer.background);
+    }
+
+    #[test]
+    fn ensure_contrast_reaches_minimum() {
+        let background = Color::from_rgb8(0x20, 0x20, 0x20);
+        let color = Color::from_rgb8(0x30, 0x30, 0x60);
+
+        assert!(contrast_ratio(color, background) < 4.5);
+
+        let adjusted = ensure_contrast(color, background, 4.5);
+
+        assert!(contrast_ratio(adjusted, background) >= 4.5);
+    }
+}
@@ -0,0 +1,143 @@
+//! The community theme index of the theme website, from which themes can be
+//! previewed before installing them into the themes directory.
+use std::io;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::Styles;
+use super::import::{self, file_name};
+use crate::environment::THEME_WEBSITE;
+
+const INDEX_PATH: &str = "index.json";
+
+/// A theme listed in the community index.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Location of the theme's TOML, relative to the theme website unless
+    /// absolute
+    pub url: String,
+}
+
+/// A theme downloaded from the index, which can be previewed with its
+/// styles and installed as downloaded.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub entry: Entry,
+    pub styles: Styles,
+    content: String,
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("request failed: {0}")]
+    Request(String),
+    #[error("invalid theme index: {0}")]
+    Index(String),
+    #[error("invalid theme url: {0}")]
+    Url(String),
+    #[error("invalid theme: {0}")]
+    Theme(String),
+    #[error("a theme named {0} is already installed")]
+    Exists(String),
+    #[error("failed to save theme: {0}")]
+    Io(String),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Request(error.to_string())
+    }
+}
+
+/// Fetches the entries of the community index, sorted by name.
+pub async fn index() -> Result<Vec<Entry>, Error> {
+    let content = get(INDEX_PATH).await?;
+
+    parse_index(&content)
+}
+
+/// Downloads the theme of `entry`, checking that it can be parsed.
+pub async fn fetch(entry: Entry) -> Result<Candidate, Error> {
+    let content = get(&entry.url).await?;
+
+    let styles = toml::from_str::<Styles>(&content)
+        .map_err(|error| Error::Theme(error.to_string()))?;
+
+    Ok(Candidate {
+        entry,
+        styles,
+        content,
+    })
+}
+
+/// Writes `candidate` into the themes directory, returning the name of the
+/// installed theme. A theme already installed under the same name is kept.
+pub async fn install(candidate: Candidate) -> Result<String, Error> {
+    let name = file_name(&candidate.entry.name);
+
+    match import::create(&name, &candidate.content).await {
+        Ok(()) => Ok(name),
+        Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+            Err(Error::Exists(name))
+        }
+        Err(error) => Err(Error::Io(error.to_string())),
+    }
+}
+
+async fn get(path: &str) -> Result<String, Error> {
+    let url = url::Url::parse(THEME_WEBSITE)
+        .and_then(|website| website.join(path))
+        .map_err(|error| Error::Url(error.to_string()))?;
+
+    let client = reqwest::Client::builder().user_agent("halloy").build()?;
+
+    Ok(client
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?)
+}
+
+fn parse_index(content: &str) -> Result<Vec<Entry>, Error> {
+    let mut entries = serde_json::from_str::<Vec<Entry>>(content)
+        .map_err(|error| Error::Index(error.to_string()))?;
+
+    entries.sort_by_cached_key(|entry| entry.name.to_lowercase());
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_is_sorted_by_name() {
+        let entries = parse_index(
+            r#"[
+                { "name": "ferra", "url": "themes/ferra.toml" },
+                { "name": "Catppuccin", "author": "cat", "url": "https://example.com/catppuccin.toml" }
+            ]"#,
+        )
+        .expect("parse index");
+
+        assert_eq!(entries[0].name, "Catppuccin");
+        assert_eq!(entries[0].author.as_deref(), Some("cat"));
+        assert_eq!(entries[1].name, "ferra");
+        assert_eq!(entries[1].author, None);
+    }
+
+    #[test]
+    fn invalid_index_is_an_error() {
+        assert!(matches!(
+            parse_index(r#"{ "themes": [] }"#),
+            Err(Error::Index(_))
+        ));
+    }
+}
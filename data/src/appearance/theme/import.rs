@@ -0,0 +1,725 @@
+//! Conversion of terminal color schemes into themes.
+//!
+//! Supported are base16 schemes (YAML), iTerm2 `.itermcolors` property lists
+//! and Windows Terminal schemes (JSON). Each scheme is reduced to its
+//! background, foreground and 16 ANSI colors, which are then mapped onto the
+//! theme's styles.
+use std::io;
+use std::path::{Path, PathBuf};
+
+use iced_core::Color;
+use thiserror::Error;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use super::{
+    Buffer, Button, Buttons, FORMATTING_COLORS, Formatting, General,
+    OptionalTextStyle, ServerMessages, Styles, Text, TextStyle, Theme,
+    hex_to_color,
+};
+use crate::Config;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Base16,
+    ITerm,
+    WindowsTerminal,
+}
+
+impl Format {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "yaml" | "yml" => Some(Format::Base16),
+            "itermcolors" => Some(Format::ITerm),
+            "json" => Some(Format::WindowsTerminal),
+            _ => None,
+        }
+    }
+}
+
+/// Colors shared by all supported terminal color scheme formats
+#[derive(Debug, Clone, Copy)]
+pub struct Scheme {
+    pub background: Color,
+    pub foreground: Color,
+    pub selection: Option<Color>,
+    /// black, red, green, yellow, blue, magenta, cyan, white, followed by
+    /// their bright variants
+    pub ansi: [Color; 16],
+}
+
+impl Scheme {
+    const BLACK: usize = 0;
+    const RED: usize = 1;
+    const GREEN: usize = 2;
+    const YELLOW: usize = 3;
+    const BLUE: usize = 4;
+    const MAGENTA: usize = 5;
+    const CYAN: usize = 6;
+    const WHITE: usize = 7;
+    const BRIGHT: usize = 8;
+
+    pub fn styles(&self) -> Styles {
+        let ansi = |slot: usize| self.ansi[slot];
+        let bright = |slot: usize| self.ansi[Self::BRIGHT + slot];
+
+        let background = self.background;
+        let foreground = self.foreground;
+        // Toward the black slot, which is what panes are set against in
+        // the bundled themes
+        let darker = |amount| mix(background, ansi(Self::BLACK), amount);
+        let muted = mix(foreground, background, 0.35);
+        let rule = mix(background, foreground, 0.08);
+
+        Styles {
+            general: General {
+                background,
+                border: mix(background, foreground, 0.2),
+                horizontal_rule: rule,
+                scrollbar: Some(rule),
+                unread_indicator: ansi(Self::YELLOW),
+                highlight_indicator: Some(bright(Self::YELLOW)),
+            },
+            text: Text {
+                primary: text(foreground),
+                secondary: text(muted),
+                tertiary: text(ansi(Self::MAGENTA)),
+                success: text(ansi(Self::GREEN)),
+                error: text(ansi(Self::RED)),
+                warning: optional_text(ansi(Self::YELLOW)),
+                info: optional_text(ansi(Self::CYAN)),
+                debug: optional_text(ansi(Self::BLUE)),
+                trace: optional_text(ansi(Self::WHITE)),
+            },
+            buffer: Buffer {
+                action: text(ansi(Self::GREEN)),
+                background: darker(0.15),
+                background_text_input: darker(0.3),
+                background_title_bar: darker(0.3),
+                border: Color::TRANSPARENT,
+                border_selected: ansi(Self::BLUE),
+                code: text(bright(Self::MAGENTA)),
+                highlight: mix(background, ansi(Self::YELLOW), 0.2),
+                nickname: text(bright(Self::BLUE)),
+                selection: self
+                    .selection
+                    .unwrap_or_else(|| mix(background, foreground, 0.2)),
+                server_messages: ServerMessages {
+                    default: text(bright(Self::YELLOW)),
+                    ..ServerMessages::default()
+                },
+                timestamp: text(bright(Self::BLACK)),
+                topic: text(muted),
+                url: text(bright(Self::CYAN)),
+                nickname_offline: optional_text(bright(Self::BLACK)),
+                background_alt: None,
+                hover: None,
+                highlight_background: None,
+                highlight_border: None,
+                nickname_highlight: OptionalTextStyle::default(),
+            },
+            buttons: Buttons {
+                primary: Button {
+                    background,
+                    background_hover: darker(0.15),
+                    background_selected: darker(0.3),
+                    background_selected_hover: darker(0.5),
+                },
+                secondary: Button {
+                    background: rule,
+                    background_hover: mix(background, foreground, 0.14),
+                    background_selected: mix(background, foreground, 0.25),
+                    background_selected_hover: mix(
+                        background, foreground, 0.32,
+                    ),
+                },
+            },
+            formatting: self.formatting(),
+        }
+    }
+
+    /// The 16 standard mIRC colors, taken from the scheme's ANSI colors
+    fn formatting(&self) -> Formatting {
+        let ansi = |slot: usize| Some(self.ansi[slot]);
+        let bright = |slot: usize| Some(self.ansi[Self::BRIGHT + slot]);
+
+        let mut colors = [None; FORMATTING_COLORS];
+
+        colors[..16].copy_from_slice(&[
+            bright(Self::WHITE),   // white
+            ansi(Self::BLACK),     // black
+            ansi(Self::BLUE),      // blue
+            ansi(Self::GREEN),     // green
+            bright(Self::RED),     // red
+            ansi(Self::RED),       // brown
+            ansi(Self::MAGENTA),   // magenta
+            ansi(Self::YELLOW),    // orange
+            bright(Self::YELLOW),  // yellow
+            bright(Self::GREEN),   // light green
+            ansi(Self::CYAN),      // cyan
+            bright(Self::CYAN),    // light cyan
+            bright(Self::BLUE),    // light blue
+            bright(Self::MAGENTA), // pink
+            bright(Self::BLACK),   // grey
+            ansi(Self::WHITE),     // light grey
+        ]);
+
+        Formatting {
+            colors,
+            ..Formatting::default()
+        }
+    }
+}
+
+/// Parses the color scheme(s) in `content`. Windows Terminal settings may
+/// contain several schemes, the other formats a single one, which is named
+/// after `file_stem` when the scheme itself doesn't carry a name.
+pub fn parse(
+    format: Format,
+    content: &str,
+    file_stem: &str,
+) -> Result<Vec<Theme>, Error> {
+    let schemes = match format {
+        Format::Base16 => vec![base16::parse(content)?],
+        Format::ITerm => vec![(None, iterm::parse(content)?)],
+        Format::WindowsTerminal => windows_terminal::parse(content)?,
+    };
+
+    Ok(schemes
+        .into_iter()
+        .map(|(name, scheme)| {
+            Theme::new(
+                name.unwrap_or_else(|| file_stem.to_string()),
+                scheme.styles(),
+            )
+        })
+        .collect())
+}
+
+/// Imports the color scheme(s) in the file at `path` into the themes
+/// directory, returning the names of the imported themes.
+pub async fn import(path: PathBuf) -> Result<Vec<String>, Error> {
+    let format = Format::from_path(&path).ok_or(Error::UnknownFormat)?;
+    let content = fs::read_to_string(&path).await?;
+    let file_stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("imported");
+
+    let themes = parse(format, &content, file_stem)?;
+
+    let mut names = vec![];
+
+    for theme in themes {
+        let name = file_name(&theme.name);
+        let content =
+            toml::to_string(&theme.styles).map_err(super::Error::from)?;
+
+        match create(&name, &content).await {
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(Error::Exists(name));
+            }
+            result => result?,
+        }
+
+        names.push(name);
+    }
+
+    Ok(names)
+}
+
+/// Writes `content` as the theme `name` in the themes directory, failing
+/// with [`io::ErrorKind::AlreadyExists`] rather than replacing a theme
+/// that's already there
+pub(super) async fn create(name: &str, content: &str) -> io::Result<()> {
+    let dir = Config::themes_dir();
+
+    fs::create_dir_all(&dir).await?;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(dir.join(format!("{name}.toml")))
+        .await?;
+
+    file.write_all(content.as_bytes()).await?;
+
+    Ok(())
+}
+
+/// Theme names are file stems, so keep them to characters that are safe in
+/// a file name on every platform
+pub(super) fn file_name(name: &str) -> String {
+    let name = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+
+    let name = name.trim_matches('-');
+
+    if name.is_empty() {
+        "imported".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+fn text(color: Color) -> TextStyle {
+    TextStyle {
+        color,
+        font_style: None,
+    }
+}
+
+fn optional_text(color: Color) -> OptionalTextStyle {
+    OptionalTextStyle {
+        color: Some(color),
+        font_style: None,
+    }
+}
+
+/// Linear interpolation from `a` to `b`
+fn mix(a: Color, b: Color, amount: f32) -> Color {
+    Color::from_rgb(
+        a.r + (b.r - a.r) * amount,
+        a.g + (b.g - a.g) * amount,
+        a.b + (b.b - a.b) * amount,
+    )
+}
+
+/// Parses `#rrggbb` (with or without `#`)
+fn color(hex: &str) -> Option<Color> {
+    let hex = hex.trim();
+
+    if hex.starts_with('#') {
+        hex_to_color(hex)
+    } else {
+        hex_to_color(&format!("#{hex}"))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Unknown color scheme format")]
+    UnknownFormat,
+    #[error("Missing color in scheme: {0}")]
+    MissingColor(String),
+    #[error("No color schemes found")]
+    NoSchemes,
+    #[error("A theme named {0} already exists")]
+    Exists(String),
+    #[error("Failed to parse color scheme: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Failed to read color scheme: {0}")]
+    Read(#[from] std::io::Error),
+    #[error(transparent)]
+    Save(#[from] super::Error),
+}
+
+mod base16 {
+    use std::collections::HashMap;
+
+    use super::{Error, Scheme, color};
+
+    /// Slots of the base16 palette used for the ANSI colors, following
+    /// base16-shell
+    const ANSI: [&str; 16] = [
+        "base00", "base08", "base0B", "base0A", "base0D", "base0E", "base0C",
+        "base05", "base03", "base08", "base0B", "base0A", "base0D", "base0E",
+        "base0C", "base07",
+    ];
+
+    /// Parses the flat `key: "value"` mappings used by base16 schemes. The
+    /// newer tinted format nests colors under `palette:`, which is handled
+    /// by ignoring indentation.
+    pub fn parse(content: &str) -> Result<(Option<String>, Scheme), Error> {
+        let values = content
+            .lines()
+            .filter_map(|line| {
+                let line = line.split(" #").next().unwrap_or(line).trim();
+                let (key, value) = line.split_once(':')?;
+                let value = value.trim().trim_matches(['"', '\'']);
+
+                (!value.is_empty()).then(|| (key.trim(), value))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let get = |key: &str| {
+            values
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .and_then(|(_, value)| color(value))
+                .ok_or_else(|| Error::MissingColor(key.to_string()))
+        };
+
+        let mut ansi = [iced_core::Color::TRANSPARENT; 16];
+        for (slot, key) in ansi.iter_mut().zip(ANSI) {
+            *slot = get(key)?;
+        }
+
+        let name = values
+            .get("scheme")
+            .or_else(|| values.get("name"))
+            .map(ToString::to_string);
+
+        Ok((
+            name,
+            Scheme {
+                background: get("base00")?,
+                foreground: get("base05")?,
+                selection: get("base02").ok(),
+                ansi,
+            },
+        ))
+    }
+}
+
+mod iterm {
+    use std::collections::HashMap;
+
+    use iced_core::Color;
+
+    use super::{Error, Scheme};
+
+    /// Parses the color entries of an `.itermcolors` property list, whose
+    /// root dictionary maps each color's name to a dictionary of its
+    /// components.
+    pub fn parse(content: &str) -> Result<Scheme, Error> {
+        let mut colors = HashMap::new();
+
+        let root = content
+            .find("<dict>")
+            .map_or("", |start| &content[start + "<dict>".len()..]);
+
+        for (key, value) in entries(root) {
+            let ("key", name) = key else {
+                continue;
+            };
+            let ("dict", dict) = value else {
+                continue;
+            };
+
+            let components = entries(dict)
+                .filter_map(|(key, value)| {
+                    let ("key", name) = key else {
+                        return None;
+                    };
+
+                    let value = value.1.trim().parse::<f32>().ok()?;
+
+                    Some((name.trim(), value))
+                })
+                .collect::<HashMap<_, _>>();
+
+            let component =
+                |name: &str| components.get(name).copied().unwrap_or(0.0);
+
+            colors.insert(
+                name.trim().to_string(),
+                Color::from_rgba(
+                    component("Red Component"),
+                    component("Green Component"),
+                    component("Blue Component"),
+                    components.get("Alpha Component").copied().unwrap_or(1.0),
+                ),
+            );
+        }
+
+        let get = |key: &str| {
+            colors
+                .get(key)
+                .copied()
+                .ok_or_else(|| Error::MissingColor(key.to_string()))
+        };
+
+        let mut ansi = [Color::TRANSPARENT; 16];
+        for (index, slot) in ansi.iter_mut().enumerate() {
+            *slot = get(&format!("Ansi {index} Color"))?;
+        }
+
+        Ok(Scheme {
+            background: get("Background Color")?,
+            foreground: get("Foreground Color")?,
+            selection: get("Selection Color").ok(),
+            ansi,
+        })
+    }
+
+    /// Consecutive pairs of elements in the body of a `<dict>`, each as its
+    /// tag and content
+    fn entries(
+        mut content: &str,
+    ) -> impl Iterator<Item = ((&str, &str), (&str, &str))> {
+        std::iter::from_fn(move || {
+            let (key, rest) = element(content)?;
+            let (value, rest) = element(rest)?;
+
+            content = rest;
+
+            Some((key, value))
+        })
+    }
+
+    /// The element at the start of `content`, as its tag and content, and
+    /// what follows it. Ends at a closing tag, so nested dictionaries are
+    /// not supported, which `.itermcolors` files don't use.
+    fn element(content: &str) -> Option<((&str, &str), &str)> {
+        let content = content.trim_start().strip_prefix('<')?;
+
+        if content.starts_with('/') {
+            return None;
+        }
+
+        let (tag, rest) = content.split_once('>')?;
+
+        if let Some(tag) = tag.strip_suffix('/') {
+            return Some(((tag.trim(), ""), rest));
+        }
+
+        let close = format!("</{tag}>");
+        let (inner, rest) = rest.split_once(&close)?;
+
+        Some(((tag, inner), rest))
+    }
+}
+
+mod windows_terminal {
+    use serde::Deserialize;
+
+    use super::{Error, Scheme, color};
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Entry {
+        name: Option<String>,
+        background: String,
+        foreground: String,
+        selection_background: Option<String>,
+        black: String,
+        red: String,
+        green: String,
+        yellow: String,
+        blue: String,
+        purple: String,
+        cyan: String,
+        white: String,
+        bright_black: String,
+        bright_red: String,
+        bright_green: String,
+        bright_yellow: String,
+        bright_blue: String,
+        bright_purple: String,
+        bright_cyan: String,
+        bright_white: String,
+    }
+
+    /// Either a single scheme, a list of schemes, or the `schemes` of a
+    /// settings file
+    #[derive(Debug, Deserialize)]
+    #[serde(untagged)]
+    enum Data {
+        Settings { schemes: Vec<Entry> },
+        List(Vec<Entry>),
+        Single(Box<Entry>),
+    }
+
+    pub fn parse(
+        content: &str,
+    ) -> Result<Vec<(Option<String>, Scheme)>, Error> {
+        let entries = match serde_json::from_str(content)? {
+            Data::Settings { schemes } | Data::List(schemes) => schemes,
+            Data::Single(entry) => vec![*entry],
+        };
+
+        if entries.is_empty() {
+            return Err(Error::NoSchemes);
+        }
+
+        entries
+            .into_iter()
+            .map(|entry| {
+                let get = |key: &str, value: &str| {
+                    color(value)
+                        .ok_or_else(|| Error::MissingColor(key.to_string()))
+                };
+
+                let ansi = [
+                    get("black", &entry.black)?,
+                    get("red", &entry.red)?,
+                    get("green", &entry.green)?,
+                    get("yellow", &entry.yellow)?,
+                    get("blue", &entry.blue)?,
+                    get("purple", &entry.purple)?,
+                    get("cyan", &entry.cyan)?,
+                    get("white", &entry.white)?,
+                    get("brightBlack", &entry.bright_black)?,
+                    get("brightRed", &entry.bright_red)?,
+                    get("brightGreen", &entry.bright_green)?,
+                    get("brightYellow", &entry.bright_yellow)?,
+                    get("brightBlue", &entry.bright_blue)?,
+                    get("brightPurple", &entry.bright_purple)?,
+                    get("brightCyan", &entry.bright_cyan)?,
+                    get("brightWhite", &entry.bright_white)?,
+                ];
+
+                Ok((
+                    entry.name.clone(),
+                    Scheme {
+                        background: get("background", &entry.background)?,
+                        foreground: get("foreground", &entry.foreground)?,
+                        selection: entry
+                            .selection_background
+                            .as_deref()
+                            .and_then(color),
+                        ansi,
+                    },
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(color: Color) -> String {
+        super::super::color_to_hex(color)
+    }
+
+    #[test]
+    fn base16() {
+        let content = r##"
+scheme: "Ocean"
+author: "Chris Kempson"
+base00: "2b303b" # background
+base01: "343d46"
+base02: "4f5b66"
+base03: "65737e"
+base04: "a7adba"
+base05: "c0c5ce"
+base06: "dfe1e8"
+base07: "eff1f5"
+base08: "bf616a"
+base09: "d08770"
+base0A: "ebcb8b"
+base0B: "a3be8c"
+base0C: "96b5b4"
+base0D: "8fa1b3"
+base0E: "b48ead"
+base0F: "ab7967"
+"##;
+
+        let themes = parse(Format::Base16, content, "ocean").unwrap();
+
+        assert_eq!(themes.len(), 1);
+        assert_eq!(themes[0].name, "Ocean");
+
+        let styles = themes[0].styles;
+        assert_eq!(hex(styles.general.background), "#2B303B");
+        assert_eq!(hex(styles.text.primary.color), "#C0C5CE");
+        assert_eq!(hex(styles.text.error.color), "#BF616A");
+        assert_eq!(hex(styles.buffer.selection), "#4F5B66");
+    }
+
+    #[test]
+    fn iterm() {
+        let color = |key: &str, value: f32| {
+            format!(
+                "<key>{key}</key>
+                <dict>
+                    <key>Blue Component</key>
+                    <real>{value}</real>
+                    <key>Color Space</key>
+                    <string>sRGB</string>
+                    <key>Green Component</key>
+                    <real>{value}</real>
+                    <key>Red Component</key>
+                    <real>{value}</real>
+                </dict>"
+            )
+        };
+
+        let entries = (0..16)
+            .map(|slot| color(&format!("Ansi {slot} Color"), 0.5))
+            .chain([
+                color("Background Color", 0.0),
+                color("Foreground Color", 1.0),
+            ])
+            .collect::<String>();
+        let content = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+            <plist version=\"1.0\"><dict>{entries}</dict></plist>"
+        );
+
+        let themes = parse(Format::ITerm, &content, "dark").unwrap();
+
+        assert_eq!(themes[0].name, "dark");
+        assert_eq!(hex(themes[0].styles.general.background), "#000000");
+        assert_eq!(hex(themes[0].styles.text.primary.color), "#FFFFFF");
+    }
+
+    #[test]
+    fn windows_terminal() {
+        let scheme = |name: &str| {
+            format!(
+                r##"{{
+                    "name": "{name}",
+                    "background": "#0C0C0C",
+                    "foreground": "#CCCCCC",
+                    "black": "#0C0C0C",
+                    "red": "#C50F1F",
+                    "green": "#13A10E",
+                    "yellow": "#C19C00",
+                    "blue": "#0037DA",
+                    "purple": "#881798",
+                    "cyan": "#3A96DD",
+                    "white": "#CCCCCC",
+                    "brightBlack": "#767676",
+                    "brightRed": "#E74856",
+                    "brightGreen": "#16C60C",
+                    "brightYellow": "#F9F1A5",
+                    "brightBlue": "#3B78FF",
+                    "brightPurple": "#B4009E",
+                    "brightCyan": "#61D6D6",
+                    "brightWhite": "#F2F2F2"
+                }}"##
+            )
+        };
+
+        let single =
+            parse(Format::WindowsTerminal, &scheme("Campbell"), "x").unwrap();
+
+        assert_eq!(single.len(), 1);
+        assert_eq!(single[0].name, "Campbell");
+        assert_eq!(hex(single[0].styles.text.success.color), "#13A10E");
+
+        let settings = format!(
+            r#"{{ "schemes": [{}, {}] }}"#,
+            scheme("One"),
+            scheme("Two")
+        );
+        let themes = parse(Format::WindowsTerminal, &settings, "x").unwrap();
+
+        assert_eq!(
+            themes.iter().map(|theme| &theme.name).collect::<Vec<_>>(),
+            ["One", "Two"]
+        );
+    }
+
+    #[test]
+    fn file_names() {
+        assert_eq!(file_name("Solarized Dark"), "solarized-dark");
+        assert_eq!(file_name("../etc"), "etc");
+        assert_eq!(file_name("  "), "imported");
+    }
+}
@@ -6,18 +6,21 @@ use crate::serde::{
     deserialize_strftime_date, deserialize_strftime_date_maybe,
 };
 use crate::target::{self, Target};
-use crate::{Server, channel, config, message};
+use crate::user::AccessLevel;
+use crate::{Server, channel, config, dcc_chat, message};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Buffer {
     Upstream(Upstream),
     Internal(Internal),
+    DccChat(dcc_chat::Id),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Upstream {
     Server(Server),
+    ServerCategory(Server, target::Category),
     Channel(Server, target::Channel),
     Query(Server, target::Query),
 }
@@ -38,6 +41,9 @@ pub enum Internal {
     FileTransfers,
     Logs,
     Highlights,
+    Search,
+    #[strum(serialize = "Server Admin")]
+    Admin,
 }
 
 impl Buffer {
@@ -45,6 +51,7 @@ impl Buffer {
         match self {
             Buffer::Upstream(upstream) => upstream.key(),
             Buffer::Internal(internal) => internal.key(),
+            Buffer::DccChat(id) => format!("dcc-chat:{}", u16::from(*id)),
         }
     }
 
@@ -69,6 +76,11 @@ impl Upstream {
     pub fn key(&self) -> String {
         match self {
             Upstream::Server(server) => format!("server:{server}"),
+            // `:` can't appear in a channel or nickname, so this won't
+            // collide with their keys
+            Upstream::ServerCategory(server, category) => {
+                format!("server:{server}::{}", category.key())
+            }
             Upstream::Channel(server, channel) => {
                 format!("server:{server}:{}", channel.as_str())
             }
@@ -81,6 +93,7 @@ impl Upstream {
     pub fn server(&self) -> &Server {
         match self {
             Self::Server(server)
+            | Self::ServerCategory(server, _)
             | Self::Channel(server, _)
             | Self::Query(server, _) => server,
         }
@@ -89,7 +102,9 @@ impl Upstream {
     pub fn channel(&self) -> Option<&target::Channel> {
         match self {
             Self::Channel(_, channel) => Some(channel),
-            Self::Server(_) | Self::Query(_, _) => None,
+            Self::Server(_)
+            | Self::ServerCategory(_, _)
+            | Self::Query(_, _) => None,
         }
     }
 
@@ -97,7 +112,7 @@ impl Upstream {
         match self {
             Self::Channel(_, channel) => Some(Target::Channel(channel.clone())),
             Self::Query(_, query) => Some(Target::Query(query.clone())),
-            Self::Server(_) => None,
+            Self::Server(_) | Self::ServerCategory(_, _) => None,
         }
     }
 
@@ -109,6 +124,12 @@ impl Upstream {
             Self::Server(_) => message::Target::Server {
                 source: message::Source::Server(source),
             },
+            Self::ServerCategory(_, category) => {
+                message::Target::ServerCategory {
+                    category,
+                    source: message::Source::Server(source),
+                }
+            }
             Self::Channel(_, channel) => message::Target::Channel {
                 channel,
                 source: message::Source::Server(source),
@@ -122,14 +143,21 @@ impl Upstream {
 }
 
 impl Internal {
-    pub const ALL: &'static [Self] =
-        &[Self::FileTransfers, Self::Logs, Self::Highlights];
+    pub const ALL: &'static [Self] = &[
+        Self::FileTransfers,
+        Self::Logs,
+        Self::Highlights,
+        Self::Search,
+        Self::Admin,
+    ];
 
     pub fn key(&self) -> String {
         match self {
             Internal::FileTransfers => "file-transfers",
             Internal::Logs => "logs",
             Internal::Highlights => "highlights",
+            Internal::Search => "search",
+            Internal::Admin => "admin",
         }
         .to_string()
     }
@@ -274,6 +302,42 @@ impl Default for DateSeparators {
 #[serde(default)]
 pub struct StatusMessagePrefix {
     pub brackets: Brackets,
+    /// Show who the message was sent to (e.g. "to ops") instead of the
+    /// prefix
+    pub badge: bool,
+}
+
+impl StatusMessagePrefix {
+    pub fn format(&self, prefixes: &[char]) -> String {
+        if self.badge {
+            self.brackets
+                .format(format!("to {}", status_message_recipients(prefixes)))
+        } else {
+            self.brackets.format(String::from_iter(prefixes))
+        }
+    }
+}
+
+/// Who receives a message sent with the STATUSMSG prefixes, e.g. "ops" for
+/// `@#channel`
+pub fn status_message_recipients(prefixes: &[char]) -> String {
+    prefixes
+        .iter()
+        .find_map(|prefix| AccessLevel::try_from(*prefix).ok())
+        .map_or_else(
+            || String::from_iter(prefixes),
+            |access_level| {
+                match access_level {
+                    AccessLevel::Founder => "founders",
+                    AccessLevel::Protected(_) => "protected users",
+                    AccessLevel::Oper => "ops",
+                    AccessLevel::HalfOp => "half-ops",
+                    AccessLevel::Voice => "voiced users",
+                    AccessLevel::Member => "members",
+                }
+                .to_string()
+            },
+        )
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -288,12 +352,49 @@ impl Brackets {
     }
 }
 
-#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum Color {
     Solid,
     #[default]
     Unique,
+    Palette(Palette),
+}
+
+/// Ordered set of colors nicknames are picked from, in place of randomizing
+/// the hue of the theme's nickname color.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Palette {
+    #[serde(deserialize_with = "deserialize_palette_colors")]
+    pub colors: Vec<iced_core::Color>,
+    /// Minimum contrast ratio against the buffer background, colors with
+    /// less contrast are lightened or darkened until they reach it
+    #[serde(default)]
+    pub min_contrast: Option<f32>,
+}
+
+fn deserialize_palette_colors<'de, D>(
+    deserializer: D,
+) -> Result<Vec<iced_core::Color>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let colors: Vec<String> = Deserialize::deserialize(deserializer)?;
+
+    if colors.is_empty() {
+        return Err(serde::de::Error::custom(
+            "palette must contain at least one color",
+        ));
+    }
+
+    colors
+        .iter()
+        .map(|hex| {
+            crate::appearance::theme::hex_to_color(hex).ok_or_else(|| {
+                serde::de::Error::custom(format!("invalid color: {hex}"))
+            })
+        })
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
@@ -311,6 +412,29 @@ impl Alignment {
     }
 }
 
+/// How messages of a pane are laid out.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Density {
+    /// Classic log, each line prefixed with its nickname
+    #[default]
+    Compact,
+    /// Consecutive messages of a user grouped under a single nickname,
+    /// with space between groups
+    Comfortable,
+}
+
+impl Density {
+    pub fn toggle(self) -> Self {
+        match self {
+            Density::Compact => Density::Comfortable,
+            Density::Comfortable => Density::Compact,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Resize {
     None,
@@ -9,13 +9,14 @@ use anyhow::{Context as ErrorContext, Result, anyhow, bail};
 use chrono::{DateTime, Utc};
 use futures::channel::mpsc;
 use futures::{Future, FutureExt};
+use halloy_core::client::RegistrationStep;
 use indexmap::IndexMap;
 use irc::proto::{self, Command, command, tags};
 use itertools::{Either, Itertools};
 use log::error;
 use tokio::fs;
 
-pub use self::on_connect::on_connect;
+pub use self::on_connect::{on_connect, on_disconnect};
 use crate::bouncer::{self, BouncerNetwork};
 use crate::environment::{SOURCE_WEBSITE, VERSION};
 use crate::history::ReadMarker;
@@ -27,12 +28,14 @@ use crate::message::{message_id, server_time, source};
 use crate::rate_limit::{BackoffInterval, TokenBucket, TokenPriority};
 use crate::target::{self, Target};
 use crate::time::Posix;
-use crate::user::{ChannelUsers, Nick, NickRef};
+use crate::user::{AccessLevel, ChannelUsers, Nick, NickRef};
 use crate::{
-    Server, User, buffer, compression, config, ctcp, dcc, environment,
-    file_transfer, history, isupport, message, mode, server,
+    Server, User, access_list, admin, buffer, compression, config, ctcp, dcc,
+    dcc_chat, environment, file_transfer, history, isupport, message, mode,
+    server,
 };
 
+pub mod exec;
 pub mod on_connect;
 
 const HIGHLIGHT_BLACKOUT_INTERVAL: Duration = Duration::from_secs(5);
@@ -40,6 +43,10 @@ const CLIENT_CHATHISTORY_LIMIT: u16 = 500;
 const CHATHISTORY_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
 const MODE_REQUEST_DELAY: Duration = Duration::from_millis(600);
 const MODE_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+const ISON_POLL_INTERVAL: Duration = Duration::from_secs(60);
+const CTCP_REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+/// Minimum time between nicklist refreshes, see [`Map::refresh_nicklists`]
+pub const NICKLIST_REFRESH_INTERVAL: Duration = Duration::from_millis(250);
 
 #[derive(Debug, Clone, Copy)]
 pub enum Status {
@@ -62,11 +69,24 @@ pub enum State {
 
 #[derive(Debug)]
 pub enum Broadcast {
+    /// Connected without authenticating and with an uncloaked hostname
+    HostnameExposed {
+        hostname: Option<String>,
+        sent_time: DateTime<Utc>,
+    },
+    /// CTCP request received from another user
+    CtcpRequest {
+        user: User,
+        command: String,
+        replied: bool,
+        sent_time: DateTime<Utc>,
+    },
     Quit {
         user: User,
         comment: Option<String>,
         channels: Vec<target::Channel>,
         sent_time: DateTime<Utc>,
+        batch: Option<message::Batch>,
     },
     Nickname {
         old_user: User,
@@ -107,6 +127,7 @@ pub enum Message {
         DateTime<Utc>,
         Result<(), Error>,
     ),
+    ChannelKeysSaved(Server, Result<(), Error>),
     RequestNewerChatHistory(Server, Target, DateTime<Utc>),
     RequestChatHistoryTargets(Server, Option<DateTime<Utc>>, DateTime<Utc>),
 }
@@ -114,21 +135,30 @@ pub enum Message {
 #[derive(Debug)]
 pub enum Event {
     Single(message::Encoded, Nick),
+    /// Message for the server buffer which goes to the buffer of its
+    /// category instead
+    SingleInCategory(message::Encoded, Nick, target::Category),
     PrivOrNotice(message::Encoded, Nick, bool),
     WithTarget(message::Encoded, Nick, message::Target),
     Broadcast(Broadcast),
     FileTransferRequest(file_transfer::ReceiveRequest),
+    DccChatRequest(dcc_chat::ReceiveRequest),
     UpdateReadMarker(Target, ReadMarker),
     JoinedChannel(target::Channel, DateTime<Utc>),
     LoggedIn(DateTime<Utc>),
     AddedIsupportParam(isupport::Parameter),
+    RemovedIsupportParam(isupport::Kind),
     ChatHistoryTargetReceived(Target, DateTime<Utc>),
     ChatHistoryTargetsReceived(DateTime<Utc>),
+    Backfilled(Target, DateTime<Utc>, DateTime<Utc>),
     DirectMessage(message::Encoded, Nick, User),
     MonitoredOnline(Vec<User>),
     MonitoredOffline(Vec<Nick>),
+    HostmaskResolved(String),
     OnConnect(on_connect::Stream),
     BouncerNetwork(Server, config::Server),
+    ChannelKeysUpdated,
+    ChannelRenamed(target::Channel, target::Channel),
 }
 
 struct ChatHistoryRequest {
@@ -148,29 +178,62 @@ pub struct Client {
     labels: HashMap<String, Context>,
     batches: HashMap<Target, Batch>,
     reroute_responses_to: Option<buffer::Upstream>,
+    ctcp_requests: HashMap<(String, String), (buffer::Upstream, Instant)>,
+    hostmask_requests: HashSet<Nick>,
+    monitored: Vec<Nick>,
+    ison_online: HashSet<Nick>,
+    ison_polled_at: Option<Instant>,
+    ison_requested: bool,
+    /// User-issued ISON commands awaiting a reply, which is shown rather than
+    /// taken for a poll's
+    ison_user_requests: usize,
+    /// Nicknames of open queries, whose presence is tracked
+    tracked_queries: HashSet<Nick>,
+    /// Tracked queries we added to the server's MONITOR list
+    monitored_queries: HashSet<Nick>,
+    /// Whether nicknames are online, from MONITOR or ISON replies
+    presence: HashMap<Nick, bool>,
+    /// Our hostname as seen by other users, if the server told us
+    visible_host: Option<String>,
+    /// Whether our hostname was replaced by a cloak
+    host_hidden: bool,
+    hostname_warned: bool,
     logged_in: bool,
-    registration_step: RegistrationStep,
-    listed_caps: Vec<String>,
-    supports_labels: bool,
-    supports_away_notify: bool,
-    supports_account_notify: bool,
-    supports_extended_join: bool,
-    supports_read_marker: bool,
-    supports_chathistory: bool,
-    supports_bouncer_networks: bool,
+    /// Registration, capabilities, SASL and ISUPPORT
+    core: halloy_core::Client,
     supports_detach: bool,
-    sasl_succeeded: bool,
+    /// Account we're logged in to, from `RPL_LOGGEDIN` or `ACCOUNT`
+    account: Option<String>,
+    /// Round-trip time of the last answered PING
+    lag: Option<Duration>,
     chathistory_requests: HashMap<Target, ChatHistoryRequest>,
     chathistory_exhausted: HashMap<Target, bool>,
     chathistory_targets_request: Option<ChatHistoryRequest>,
     highlight_notification_blackout: HighlightNotificationBlackout,
     registration_required_channels: Vec<target::Channel>,
-    isupport: HashMap<isupport::Kind, isupport::Parameter>,
+    /// Keys channels were joined with, by normalized channel name
+    channel_keys: HashMap<String, String>,
+    /// Keys sent with a JOIN, remembered once the channel is joined
+    pending_channel_keys: HashMap<target::Channel, String>,
     who_polls: VecDeque<WhoPoll>,
     who_poll_interval: BackoffInterval,
+    /// Channels open in a pane, which are preferred when WHO polling
+    visible_channels: HashSet<target::Channel>,
     resolved_netid: Option<String>,
     anti_flood: Option<TokenBucket<message::Encoded>>,
     mode_requests: Vec<ModeRequest>,
+    is_oper: bool,
+    /// Our user modes, as far as the server told us
+    user_modes: HashSet<char>,
+    /// Whether the connection replaces one which dropped unexpectedly, so
+    /// the `on_reconnect` commands run
+    is_reconnect: bool,
+    /// Forwards changes to our state to the running `on_connect` commands
+    on_connect_signals: Option<mpsc::UnboundedSender<on_connect::Signal>>,
+    /// Challenge text received so far, while answering a CHALLENGE
+    oper_challenge: Option<String>,
+    admin_reports: admin::Reports,
+    access_lists: access_list::Lists,
 }
 
 impl fmt::Debug for Client {
@@ -184,7 +247,15 @@ impl Client {
         server: Server,
         config: Arc<config::Server>,
         sender: mpsc::Sender<proto::Message>,
+        channel_keys: HashMap<String, String>,
+        is_reconnect: bool,
     ) -> Self {
+        let core = halloy_core::Client::new(
+            server.to_string(),
+            config.registration(),
+            sender.clone(),
+        );
+
         Self {
             server,
             handle: sender,
@@ -199,34 +270,54 @@ impl Client {
             labels: HashMap::new(),
             batches: HashMap::new(),
             reroute_responses_to: None,
+            ctcp_requests: HashMap::new(),
+            hostmask_requests: HashSet::new(),
+            monitored: config
+                .monitor
+                .iter()
+                .map(|nick| Nick::from_str(nick, isupport::CaseMap::default()))
+                .unique()
+                .collect(),
+            ison_online: HashSet::new(),
+            ison_polled_at: None,
+            ison_requested: false,
+            ison_user_requests: 0,
+            tracked_queries: HashSet::new(),
+            monitored_queries: HashSet::new(),
+            presence: HashMap::new(),
+            visible_host: None,
+            host_hidden: false,
+            hostname_warned: false,
             logged_in: false,
-            registration_step: RegistrationStep::Start,
-            listed_caps: vec![],
-            supports_labels: false,
-            supports_away_notify: false,
-            supports_account_notify: false,
-            supports_extended_join: false,
-            supports_read_marker: false,
-            supports_chathistory: false,
-            supports_bouncer_networks: false,
+            core,
             supports_detach: false,
-            sasl_succeeded: false,
+            account: None,
+            lag: None,
             chathistory_requests: HashMap::new(),
             chathistory_exhausted: HashMap::new(),
             chathistory_targets_request: None,
             highlight_notification_blackout:
                 HighlightNotificationBlackout::Blackout(Instant::now()),
             registration_required_channels: vec![],
-            isupport: HashMap::new(),
+            channel_keys,
+            pending_channel_keys: HashMap::new(),
             who_polls: VecDeque::new(),
             who_poll_interval: BackoffInterval::from(
                 config
                     .who_poll_interval
                     .min(config.anti_flood.saturating_mul(2)),
             ),
+            visible_channels: HashSet::new(),
             resolved_netid: None,
             anti_flood: Some(TokenBucket::new(config.anti_flood, 10)),
             mode_requests: Vec::new(),
+            is_oper: false,
+            user_modes: HashSet::new(),
+            is_reconnect,
+            on_connect_signals: None,
+            oper_challenge: None,
+            admin_reports: admin::Reports::default(),
+            access_lists: access_list::Lists::default(),
             config,
         }
     }
@@ -250,24 +341,14 @@ impl Client {
     }
 
     pub fn connect(&mut self) -> Result<()> {
-        // Begin registration
-        self.handle.try_send(command!("CAP", "LS", "302"))?;
+        self.core.connect()?;
 
-        // Identify
-        let nick = &self.config.nickname;
-        let user = self.config.username.as_ref().unwrap_or(nick);
-        let real = self.config.realname.as_ref().unwrap_or(nick);
-
-        if let Some(pass) = self.config.password.as_ref() {
-            self.handle.try_send(command!("PASS", pass))?;
-        }
-        self.handle.try_send(command!("NICK", nick))?;
-        self.handle.try_send(command!("USER", user, real))?;
-        self.registration_step = RegistrationStep::List;
         Ok(())
     }
 
-    fn quit(&mut self, reason: Option<String>) {
+    /// Quits, once the `on_disconnect` commands are sent by the returned
+    /// stream if there are any.
+    fn quit(&mut self, reason: Option<String>) -> Option<on_connect::Stream> {
         self.who_polls.retain(|who_poll| {
             matches!(
                 who_poll.status,
@@ -275,6 +356,23 @@ impl Client {
             )
         });
 
+        if self.config.on_disconnect.is_empty() {
+            self.send_quit(reason);
+
+            None
+        } else {
+            Some(on_disconnect(
+                &self.server,
+                self.handle.clone(),
+                self.config.clone(),
+                self.on_connect_status(),
+                self.core.isupport(),
+                reason,
+            ))
+        }
+    }
+
+    fn send_quit(&mut self, reason: Option<String>) {
         if let Err(e) = if let Some(reason) = reason {
             self.handle.try_send(command!("QUIT", reason))
         } else {
@@ -285,7 +383,7 @@ impl Client {
     }
 
     fn join(&mut self, channels: &[target::Channel]) {
-        let keys = HashMap::new();
+        let keys = self.join_keys(channels);
 
         let messages = group_joins(channels, &keys);
 
@@ -370,7 +468,7 @@ impl Client {
         priority: TokenPriority,
     ) {
         if let Some(buffer) = buffer {
-            if self.supports_labels {
+            if self.supports_labels() {
                 let label = generate_label();
                 let context = Context::new(&message, buffer.clone());
 
@@ -382,6 +480,41 @@ impl Client {
 
             self.reroute_responses_to =
                 self.start_reroute(&message.command).then(|| buffer.clone());
+
+            // Record CTCP requests so their responses are shown in the buffer
+            // they were sent from
+            if let Command::PRIVMSG(target, text) = &message.command
+                && let Some(query) = ctcp::parse_query(text)
+                && !matches!(query.command, ctcp::Command::Action)
+            {
+                self.ctcp_requests.insert(
+                    (
+                        self.casemapping().normalize(target),
+                        query.command.as_ref().to_string(),
+                    ),
+                    (buffer.clone(), Instant::now()),
+                );
+            }
+        }
+
+        if let Command::JOIN(channels, Some(keys)) = &message.command {
+            for (channel, key) in channels.split(',').zip(keys.split(',')) {
+                if let Ok(channel) = target::Channel::parse(
+                    channel,
+                    self.chantypes(),
+                    self.statusmsg(),
+                    self.casemapping(),
+                ) && !key.is_empty()
+                {
+                    self.pending_channel_keys.insert(channel, key.to_string());
+                }
+            }
+        }
+
+        if matches!(priority, TokenPriority::User)
+            && message.command.command().eq_ignore_ascii_case("ISON")
+        {
+            self.ison_user_requests += 1;
         }
 
         if matches!(message.command, Command::WHO(..))
@@ -430,6 +563,10 @@ impl Client {
             }
         }
 
+        if let Some(command) = self.via_channel(&message.command, &priority) {
+            message.command = command;
+        }
+
         if let Some(ref mut anti_flood) = self.anti_flood {
             anti_flood.add_token(message, priority);
         } else if let Err(e) = self.handle.try_send(message.into()) {
@@ -437,6 +574,61 @@ impl Client {
         }
     }
 
+    // PRIVMSG or NOTICE typed to a user sent as CPRIVMSG or CNOTICE through
+    // a channel we share with them and are an operator in, which exempts it
+    // from the server's target change limits
+    fn via_channel(
+        &self,
+        command: &Command,
+        priority: &TokenPriority,
+    ) -> Option<Command> {
+        let (target, text, kind) = typed_message(command, priority)?;
+
+        if !self.core.isupport().contains_key(&kind)
+            || target::Channel::parse(
+                target,
+                self.chantypes(),
+                self.statusmsg(),
+                self.casemapping(),
+            )
+            .is_ok()
+        {
+            return None;
+        }
+
+        let nick = Nick::from_str(target, self.casemapping());
+
+        let channel =
+            self.chanmap.iter().find_map(|(channel, state)| {
+                let is_operator =
+                    state.users.get_by_nick(self.nickname()).is_some_and(
+                        |user| user.highest_access_level() >= AccessLevel::Oper,
+                    );
+
+                (is_operator
+                    && state.users.get_by_nick(nick.as_nickref()).is_some())
+                .then_some(channel)
+            })?;
+
+        log::debug!(
+            "[{}] sending {kind:?} to {target} via {channel}",
+            self.server
+        );
+
+        Some(match kind {
+            isupport::Kind::CNOTICE => Command::CNOTICE(
+                target.clone(),
+                channel.to_string(),
+                text.clone(),
+            ),
+            _ => Command::CPRIVMSG(
+                target.clone(),
+                channel.to_string(),
+                text.clone(),
+            ),
+        })
+    }
+
     fn receive(
         &mut self,
         message: message::Encoded,
@@ -509,6 +701,24 @@ impl Client {
             };
         }
 
+        // Replies to the queries of the server admin view aren't shown as
+        // messages
+        if self.admin_reports.receive(&message.command) {
+            return Ok(vec![]);
+        }
+
+        // Neither are the services' replies to the access list view
+        if let Command::NOTICE(_, text) = &message.command
+            && message.user(self.casemapping()).is_some_and(|user| {
+                user.nickname()
+                    .as_str()
+                    .eq_ignore_ascii_case(&self.config.access_list.services)
+            })
+            && self.access_lists.receive(text, Instant::now())
+        {
+            return Ok(vec![]);
+        }
+
         match &message.command {
             Command::BATCH(batch, params) => {
                 let mut chars = batch.chars();
@@ -537,6 +747,24 @@ impl Client {
                                 _ => None,
                             };
 
+                        batch.kind = params
+                            .first()
+                            .map(String::as_str)
+                            .and_then(message::Batch::parse);
+
+                        if params.first().map(String::as_str)
+                            == Some("znc.in/playback")
+                        {
+                            batch.playback = params.get(1).map(|target| {
+                                Target::parse(
+                                    target,
+                                    self.chantypes(),
+                                    self.statusmsg(),
+                                    self.casemapping(),
+                                )
+                            });
+                        }
+
                         self.batches.insert(
                             Target::parse(
                                 &reference,
@@ -567,12 +795,36 @@ impl Client {
                                     ))
                                 })
                             {
+                                if let Some(kind) = finished.kind {
+                                    tag_batch(&mut finished.events, kind);
+                                }
+
                                 parent.events.extend(finished.events);
                             } else {
                                 match &finished.chathistory {
                                     Some(ChatHistoryBatch::Target(
                                         batch_target,
                                     )) => {
+                                        // Messages missed since the last
+                                        // known message, as opposed to older
+                                        // messages requested by scrolling up
+                                        let is_backfill = self
+                                            .chathistory_requests
+                                            .get(batch_target)
+                                            .is_some_and(|request| {
+                                                matches!(
+                                                    request.subcommand,
+                                                    ChatHistorySubcommand::Latest(
+                                                        _,
+                                                        MessageReference::Timestamp(_)
+                                                            | MessageReference::MessageId(_),
+                                                        _,
+                                                    ) | ChatHistorySubcommand::Between(
+                                                        ..
+                                                    )
+                                                )
+                                            });
+
                                         let continuation_subcommand =
                                             if let Some(ChatHistoryRequest {
                                                 subcommand,
@@ -692,6 +944,15 @@ impl Client {
                                                 TokenPriority::High,
                                             );
                                         }
+
+                                        if is_backfill
+                                            && let Some(event) = backfilled(
+                                                batch_target,
+                                                &finished.events,
+                                            )
+                                        {
+                                            finished.events.push(event);
+                                        }
                                     }
                                     Some(ChatHistoryBatch::Targets) => {
                                         if let Some(ChatHistoryRequest {
@@ -727,6 +988,20 @@ impl Client {
                                     _ => (),
                                 }
 
+                                if let Some(kind) = finished.kind {
+                                    tag_batch(&mut finished.events, kind);
+                                }
+
+                                if let Some(playback_target) =
+                                    &finished.playback
+                                    && let Some(event) = backfilled(
+                                        playback_target,
+                                        &finished.events,
+                                    )
+                                {
+                                    finished.events.push(event);
+                                }
+
                                 return Ok(finished.events);
                             }
                         }
@@ -915,7 +1190,7 @@ impl Client {
                     );
                 };
 
-                if !self.sasl_succeeded {
+                if !self.core.sasl_succeeded() {
                     // our connection isn't currently SASL. We have to assume that SASL won't
                     // succeed for any other bouncer networks, which means they won't be able to
                     // connect. Don't add them.
@@ -935,317 +1210,35 @@ impl Client {
                     self.config.bouncer_config(),
                 )]);
             }
-            Command::CAP(_, sub, a, b) if sub == "LS" => {
-                let (caps, asterisk) = match (a, b) {
-                    (Some(caps), None) => (caps, None),
-                    (Some(asterisk), Some(caps)) => (caps, Some(asterisk)),
-                    // Unreachable
-                    (None, None) | (None, Some(_)) => return Ok(vec![]),
-                };
-
-                self.listed_caps.extend(caps.split(' ').map(String::from));
-
-                // Finished
-                if asterisk.is_none() {
-                    let mut requested = vec![];
-
-                    let contains =
-                        |s| self.listed_caps.iter().any(|cap| cap == s);
-
-                    if contains("invite-notify") {
-                        requested.push("invite-notify");
-                    }
-                    if contains("userhost-in-names") {
-                        requested.push("userhost-in-names");
-                    }
-                    if contains("away-notify") {
-                        requested.push("away-notify");
-                    }
-                    if contains("message-tags") {
-                        requested.push("message-tags");
-                    }
-                    if contains("server-time") {
-                        requested.push("server-time");
-                    }
-                    if contains("chghost") {
-                        requested.push("chghost");
-                    }
-                    if contains("extended-monitor") {
-                        requested.push("extended-monitor");
-                    }
-                    if contains("account-notify") {
-                        requested.push("account-notify");
-
-                        if contains("extended-join") {
-                            requested.push("extended-join");
-                        }
-                    }
-                    if contains("batch") {
-                        requested.push("batch");
-
-                        // We require batch for our chathistory support
-                        if contains("draft/chathistory") {
-                            requested.push("draft/chathistory");
-
-                            if contains("draft/event-playback") {
-                                requested.push("draft/event-playback");
-                            }
-                        }
-                    }
-                    if contains("labeled-response") {
-                        requested.push("labeled-response");
-                    }
-                    if contains("echo-message") {
-                        requested.push("echo-message");
-                    }
-                    if self
-                        .listed_caps
-                        .iter()
-                        .any(|cap| cap.starts_with("sasl"))
-                    {
-                        requested.push("sasl");
-                    }
-                    if contains("multi-prefix") {
-                        requested.push("multi-prefix");
-                    }
-                    if contains("draft/read-marker") {
-                        requested.push("draft/read-marker");
-                    }
-                    if contains("setname") {
-                        requested.push("setname");
-                    }
-                    if contains("soju.im/bouncer-networks") {
-                        requested.push("soju.im/bouncer-networks");
-                    }
-
-                    if !requested.is_empty() {
-                        // Request
-                        self.registration_step = RegistrationStep::Req;
-
-                        for message in group_capability_requests(&requested) {
-                            self.handle.try_send(message)?;
-                        }
-                    } else {
-                        // If none requested, end negotiation
-                        self.registration_step = RegistrationStep::End;
-                        self.handle.try_send(command!("CAP", "END"))?;
-                    }
-                }
-            }
-            Command::CAP(_, sub, a, b) if sub == "ACK" => {
-                // TODO this code is duplicated several times. Fix in `Command`.
-                let caps = ok!(b.as_ref().or(a.as_ref()));
-
-                log::info!(
-                    "[{}] capabilities acknowledged: {caps}",
-                    self.server
-                );
-
-                let caps = caps.split(' ').collect::<Vec<_>>();
-
-                if caps.contains(&"labeled-response") {
-                    self.supports_labels = true;
-                }
-                if caps.contains(&"away-notify") {
-                    self.supports_away_notify = true;
-                }
-                if caps.contains(&"account-notify") {
-                    self.supports_account_notify = true;
-                }
-                if caps.contains(&"extended-join") {
-                    self.supports_extended_join = true;
-                }
-                if caps.contains(&"draft/read-marker") {
-                    self.supports_read_marker = true;
-                }
-                if caps.contains(&"soju.im/bouncer-networks") {
-                    self.supports_bouncer_networks = true;
-                }
-
-                let supports_sasl = caps.iter().any(|cap| cap.contains("sasl"));
-
-                if let Some(sasl) =
-                    self.config.sasl.as_ref().filter(|_| supports_sasl)
-                {
-                    self.registration_step = RegistrationStep::Sasl;
-                    self.handle
-                        .try_send(command!("AUTHENTICATE", sasl.command()))?;
-                } else {
-                    self.registration_step = RegistrationStep::End;
-                    self.handle.try_send(command!("CAP", "END"))?;
-                }
-
-                if caps.contains(&"draft/chathistory")
-                    && self.config.chathistory
-                {
-                    self.supports_chathistory = true;
-                }
-            }
-            Command::CAP(_, sub, a, b) if sub == "NAK" => {
-                let caps = ok!(b.as_ref().or(a.as_ref()));
-
-                log::warn!(
-                    "[{}] capabilities not acknowledged: {caps}",
-                    self.server
-                );
-
-                // End if we didn't move to sasl or already ended
-                if self.registration_step < RegistrationStep::Sasl {
-                    self.registration_step = RegistrationStep::End;
-                    self.handle.try_send(command!("CAP", "END"))?;
-                }
-            }
-            Command::CAP(_, sub, a, b) if sub == "NEW" => {
-                let caps = ok!(b.as_ref().or(a.as_ref()));
-
-                let new_caps =
-                    caps.split(' ').map(String::from).collect::<Vec<String>>();
-
-                let mut requested = vec![];
-
-                let newly_contains = |s| new_caps.iter().any(|cap| cap == s);
-
-                let contains = |s| self.listed_caps.iter().any(|cap| cap == s);
-
-                if newly_contains("invite-notify") {
-                    requested.push("invite-notify");
-                }
-                if newly_contains("userhost-in-names") {
-                    requested.push("userhost-in-names");
-                }
-                if newly_contains("away-notify") {
-                    requested.push("away-notify");
-                }
-                if newly_contains("message-tags") {
-                    requested.push("message-tags");
-                }
-                if newly_contains("server-time") {
-                    requested.push("server-time");
-                }
-                if newly_contains("chghost") {
-                    requested.push("chghost");
-                }
-                if newly_contains("extended-monitor") {
-                    requested.push("extended-monitor");
-                }
-                if contains("account-notify")
-                    || newly_contains("account-notify")
-                {
-                    if newly_contains("account-notify") {
-                        requested.push("account-notify");
-                    }
-
-                    if newly_contains("extended-join") {
-                        requested.push("extended-join");
-                    }
-                }
-                if contains("batch") || newly_contains("batch") {
-                    if newly_contains("batch") {
-                        requested.push("batch");
-                    }
-
-                    // We require batch for our chathistory support
-                    if newly_contains("draft/chathistory")
-                        && self.config.chathistory
-                    {
-                        requested.push("draft/chathistory");
-
-                        if newly_contains("draft/event-playback") {
-                            requested.push("draft/event-playback");
-                        }
-                    }
-                }
-                if newly_contains("labeled-response") {
-                    requested.push("labeled-response");
-                }
-                if newly_contains("echo-message") {
-                    requested.push("echo-message");
-                }
-                if newly_contains("multi-prefix") {
-                    requested.push("multi-prefix");
-                }
-                if newly_contains("draft/read-marker") {
-                    requested.push("draft/read-marker");
-                }
-                if newly_contains("setname") {
-                    requested.push("setname");
-                }
-                if newly_contains("soju.im/bouncer-networks") {
-                    requested.push("soju.im/bouncer-networks");
-                }
-
-                if !requested.is_empty() {
-                    for message in group_capability_requests(&requested) {
-                        self.handle.try_send(message)?;
-                    }
-                }
-
-                self.listed_caps.extend(new_caps);
-            }
-            Command::CAP(_, sub, a, b) if sub == "DEL" => {
-                let caps = ok!(b.as_ref().or(a.as_ref()));
-
-                log::info!(
-                    "[{}] capabilities no longer supported: {caps}",
-                    self.server
-                );
-
-                let del_caps = caps.split(' ').collect::<Vec<_>>();
-
-                if del_caps.contains(&"labeled-response") {
-                    self.supports_labels = false;
-                }
-                if del_caps.contains(&"away-notify") {
-                    self.supports_away_notify = false;
-                }
-                if del_caps.contains(&"account-notify") {
-                    self.supports_account_notify = false;
-                }
-                if del_caps.contains(&"extended-join") {
-                    self.supports_extended_join = false;
-                }
-                if del_caps.contains(&"draft/read-marker") {
-                    self.supports_read_marker = false;
-                }
-                if del_caps.contains(&"draft/chathistory") {
-                    self.supports_chathistory = false;
-                }
-                if del_caps.contains(&"soju.im/bouncer-networks") {
-                    self.supports_bouncer_networks = false;
-                }
-
-                self.listed_caps.retain(|cap| {
-                    !del_caps.iter().any(|del_cap| del_cap == cap)
-                });
+            Command::CAP(_, sub, _, _)
+                if matches!(
+                    sub.as_str(),
+                    "LS" | "ACK" | "NAK" | "NEW" | "DEL"
+                ) =>
+            {
+                self.core.receive(&message)?;
             }
             Command::AUTHENTICATE(param) if param == "+" => {
-                if let Some(sasl) = self.config.sasl.as_ref() {
-                    log::info!(
-                        "[{}] sasl auth: {}",
-                        self.server,
-                        sasl.command()
-                    );
+                self.core.receive(&message)?;
 
-                    for param in sasl.params() {
-                        self.handle
-                            .try_send(command!("AUTHENTICATE", param))?;
-                    }
-                    // now that we are authenticated, we can connect to our desired network
-                    if let Some(id) = self.server.bouncer_netid() {
-                        self.handle
-                            .try_send(command!("BOUNCER", "BIND", id))?;
-                    }
+                // now that we are authenticated, we can connect to our desired network
+                if let Some(id) = self.server.bouncer_netid().filter(|_| {
+                    self.core.registration_step() == RegistrationStep::Sasl
+                }) {
+                    self.handle.try_send(command!("BOUNCER", "BIND", id))?;
                 }
             }
             Command::Numeric(RPL_LOGGEDIN, args) => {
                 log::info!("[{}] logged in", self.server);
 
                 self.logged_in = true;
+                self.account = args.get(2).cloned();
+                self.signal_on_connect(on_connect::Signal::LoggedIn);
 
                 if !self.registration_required_channels.is_empty() {
                     for message in group_joins(
                         &self.registration_required_channels,
-                        &self.config.channel_keys,
+                        &self.join_keys(&self.registration_required_channels),
                     ) {
                         self.handle.try_send(message)?;
                     }
@@ -1253,7 +1246,7 @@ impl Client {
                     self.registration_required_channels.clear();
                 }
 
-                if !self.supports_account_notify {
+                if !self.supports_account_notify() {
                     let accountname = ok!(args.get(2));
 
                     let old_user = User::from(self.nickname().to_owned());
@@ -1273,8 +1266,9 @@ impl Client {
                 log::info!("[{}] logged out", self.server);
 
                 self.logged_in = false;
+                self.account = None;
 
-                if !self.supports_account_notify {
+                if !self.supports_account_notify() {
                     let old_user = User::from(self.nickname().to_owned());
 
                     self.chanmap.values_mut().for_each(|channel| {
@@ -1313,6 +1307,19 @@ impl Client {
                                     },
                                 )]);
                             }
+                            dcc::Command::Chat(request) => {
+                                log::trace!(
+                                    "[{}] DCC Chat => {request:?}",
+                                    self.server
+                                );
+                                return Ok(vec![Event::DccChatRequest(
+                                    dcc_chat::ReceiveRequest {
+                                        from: user,
+                                        dcc_chat: request,
+                                        server: self.server.clone(),
+                                    },
+                                )]);
+                            }
                             dcc::Command::Unsupported(command) => {
                                 bail!("Unsupported DCC command: {command}",);
                             }
@@ -1328,6 +1335,21 @@ impl Client {
                             // Response to us sending a CTCP request to another client
                             if matches!(&message.command, Command::NOTICE(_, _))
                             {
+                                if let Some((buffer, _)) =
+                                    self.ctcp_requests.remove(&(
+                                        user.nickname()
+                                            .as_normalized_str()
+                                            .to_string(),
+                                        query.command.as_ref().to_string(),
+                                    ))
+                                {
+                                    return Ok(vec![Event::WithTarget(
+                                        message,
+                                        self.nickname().to_owned(),
+                                        buffer.server_message_target(None),
+                                    )]);
+                                }
+
                                 let event = Event::PrivOrNotice(
                                     message,
                                     self.nickname().to_owned(),
@@ -1343,108 +1365,71 @@ impl Client {
                                 &message.command,
                                 Command::PRIVMSG(_, _)
                             ) {
-                                match query.command {
-                                    ctcp::Command::Action => (),
-                                    ctcp::Command::ClientInfo => {
-                                        self.send(
-                                            None,
-                                            ctcp::response_message(
-                                                &query.command,
-                                                user.nickname().to_string(),
-                                                Some(ctcp_config.client_info()),
-                                            )
-                                            .into(),
-                                            TokenPriority::High,
-                                        );
-                                    }
-                                    ctcp::Command::UserInfo => {
-                                        if ctcp_config.userinfo.is_some() {
-                                            self.send(
-                                                None,
-                                                ctcp::response_message(
-                                                    &query.command,
-                                                    user.nickname().to_string(),
-                                                    ctcp_config
-                                                        .userinfo
-                                                        .clone(),
-                                                )
-                                                .into(),
-                                                TokenPriority::High,
-                                            );
+                                let replied = ctcp_config.replies_to(
+                                    &query.command,
+                                    &user,
+                                    self.casemapping(),
+                                );
+
+                                if replied {
+                                    let params = match &query.command {
+                                        ctcp::Command::ClientInfo => {
+                                            Some(ctcp_config.client_info())
                                         }
-                                    }
-                                    ctcp::Command::DCC => (),
-                                    ctcp::Command::Ping => {
-                                        if ctcp_config.ping {
-                                            self.send(
-                                                None,
-                                                ctcp::response_message(
-                                                    &query.command,
-                                                    user.nickname().to_string(),
-                                                    query.params,
-                                                )
-                                                .into(),
-                                                TokenPriority::High,
-                                            );
+                                        ctcp::Command::UserInfo => {
+                                            ctcp_config.userinfo.clone()
                                         }
-                                    }
-                                    ctcp::Command::Source => {
-                                        if ctcp_config.source {
-                                            self.send(
-                                                None,
-                                                ctcp::response_message(
-                                                    &query.command,
-                                                    user.nickname().to_string(),
-                                                    Some(SOURCE_WEBSITE),
-                                                )
-                                                .into(),
-                                                TokenPriority::High,
-                                            );
+                                        ctcp::Command::Ping => {
+                                            query.params.map(String::from)
                                         }
-                                    }
-                                    ctcp::Command::Version => {
-                                        if ctcp_config.version {
-                                            self.send(
-                                                None,
-                                                ctcp::response_message(
-                                                    &query.command,
-                                                    user.nickname().to_string(),
-                                                    Some(format!(
-                                                        "Halloy {VERSION}"
-                                                    )),
-                                                )
-                                                .into(),
-                                                TokenPriority::High,
-                                            );
+                                        ctcp::Command::Source => {
+                                            Some(SOURCE_WEBSITE.to_string())
                                         }
-                                    }
-                                    ctcp::Command::Time => {
-                                        if ctcp_config.time {
-                                            let utc_time = Utc::now();
-                                            let formatted = utc_time
-                                                .to_rfc3339_opts(
+                                        ctcp::Command::Version => Some(
+                                            ctcp_config.version_reply(VERSION),
+                                        ),
+                                        ctcp::Command::Time => {
+                                            Some(Utc::now().to_rfc3339_opts(
                                                 chrono::SecondsFormat::Millis,
                                                 true,
-                                            );
-
-                                            self.send(
-                                                None,
-                                                ctcp::response_message(
-                                                    &query.command,
-                                                    user.nickname().to_string(),
-                                                    Some(formatted),
-                                                )
-                                                .into(),
-                                                TokenPriority::High,
-                                            );
+                                            ))
                                         }
-                                    }
-                                    ctcp::Command::Unknown(command) => {
-                                        log::debug!(
-                                            "[{}] Ignoring CTCP command {command}: Unknown command",
-                                            self.server
-                                        );
-                                    }
+                                        ctcp::Command::Action
+                                        | ctcp::Command::DCC
+                                        | ctcp::Command::Unknown(_) => None,
+                                    };
+
+                                    self.send(
+                                        None,
+                                        ctcp::response_message(
+                                            &query.command,
+                                            user.nickname().to_string(),
+                                            params,
+                                        )
+                                        .into(),
+                                        TokenPriority::High,
+                                    );
+                                } else {
+                                    log::debug!(
+                                        "[{}] Not replying to CTCP {} from {}",
+                                        self.server,
+                                        query.command.as_ref(),
+                                        user.nickname()
+                                    );
+                                }
+
+                                if ctcp_config.log {
+                                    return Ok(vec![Event::Broadcast(
+                                        Broadcast::CtcpRequest {
+                                            user,
+                                            command: query
+                                                .command
+                                                .as_ref()
+                                                .to_uppercase(),
+                                            replied,
+                                            sent_time: server_time(&message),
+                                        },
+                                    )]);
                                 }
                             }
 
@@ -1506,10 +1491,13 @@ impl Client {
                 let ourself = self.nickname() == old_user.nickname();
 
                 if ourself {
-                    self.resolved_nick = Some(Nick::from_string(
-                        nick.to_string(),
-                        self.casemapping(),
+                    let nick =
+                        Nick::from_string(nick.to_string(), self.casemapping());
+
+                    self.signal_on_connect(on_connect::Signal::Nickname(
+                        nick.clone(),
                     ));
+                    self.resolved_nick = Some(nick);
                 }
 
                 let channels = self.user_channels(old_user.nickname());
@@ -1564,6 +1552,20 @@ impl Client {
                     nick.to_string(),
                     self.casemapping(),
                 ));
+
+                // The welcome usually ends with our full mask
+                if let Some((_, host)) = args
+                    .last()
+                    .and_then(|text| text.split_whitespace().last())
+                    .filter(|mask| mask.contains('!'))
+                    .and_then(|mask| mask.split_once('@'))
+                {
+                    self.visible_host = Some(host.to_string());
+                }
+            }
+            Command::Numeric(RPL_HOSTHIDDEN, args) => {
+                self.visible_host = args.get(1).cloned();
+                self.host_hidden = true;
             }
             Command::Numeric(RPL_MYINFO, args) => {
                 let server_version = ok!(args.get(2));
@@ -1587,6 +1589,7 @@ impl Client {
                     comment: comment.clone(),
                     channels,
                     sent_time: server_time(&message),
+                    batch: None,
                 })]);
             }
             Command::PART(channel, _) => {
@@ -1660,14 +1663,23 @@ impl Client {
                         });
                     }
 
-                    return Ok(vec![Event::JoinedChannel(
+                    let mut events = vec![];
+
+                    if let Some(key) =
+                        self.pending_channel_keys.remove(&target_channel)
+                        && self.remember_channel_key(&target_channel, Some(key))
+                    {
+                        events.push(Event::ChannelKeysUpdated);
+                    }
+
+                    events.push(Event::JoinedChannel(
                         target_channel,
                         server_time(&message),
-                    )]);
-                } else if let Some(channel) =
-                    self.chanmap.get_mut(&target_channel)
-                {
-                    let user = if self.supports_extended_join {
+                    ));
+
+                    return Ok(events);
+                } else {
+                    let user = if self.supports_extended_join() {
                         accountname
                             .as_ref()
                             .map_or(user.clone(), |accountname| {
@@ -1677,8 +1689,67 @@ impl Client {
                         user
                     };
 
-                    channel.users.insert(user);
+                    if let Some(channel) = self.chanmap.get_mut(&target_channel)
+                    {
+                        channel.users.insert(user);
+                    }
+                }
+            }
+            Command::RENAME(old, new, _) => {
+                let casemapping = self.casemapping();
+
+                let old = context!(target::Channel::parse(
+                    old,
+                    self.chantypes(),
+                    self.statusmsg(),
+                    casemapping,
+                ));
+                let new = context!(target::Channel::parse(
+                    new,
+                    self.chantypes(),
+                    self.statusmsg(),
+                    casemapping,
+                ));
+
+                let Some(channel) = self.chanmap.shift_remove(&old) else {
+                    return Ok(vec![]);
+                };
+
+                let (Ok(i) | Err(i)) = self.chanmap.binary_search_by(|c, _| {
+                    self.compare_channels(
+                        c.as_normalized_str(),
+                        new.as_normalized_str(),
+                    )
+                });
+                self.chanmap.insert_before(i, new.clone(), channel);
+
+                for who_poll in &mut self.who_polls {
+                    if who_poll.channel == old {
+                        who_poll.channel = new.clone();
+                    }
+                }
+
+                for mode_request in &mut self.mode_requests {
+                    if mode_request.channel == old {
+                        mode_request.channel = new.clone();
+                    }
+                }
+
+                let mut events =
+                    vec![Event::ChannelRenamed(old.clone(), new.clone())];
+
+                if let Some(key) =
+                    self.channel_keys.remove(old.as_normalized_str())
+                {
+                    self.channel_keys
+                        .insert(new.as_normalized_str().to_string(), key);
+
+                    events.push(Event::ChannelKeysUpdated);
                 }
+
+                events.push(Event::Single(message, self.nickname().to_owned()));
+
+                return Ok(events);
             }
             Command::KICK(channel, victim, reason) => {
                 let casemapping = self.casemapping();
@@ -1713,6 +1784,93 @@ impl Client {
                     }
                 }
             }
+            Command::Numeric(RPL_USERHOST, args)
+                if !self.hostmask_requests.is_empty() =>
+            {
+                let replies = ok!(args.get(1));
+                let casemapping = self.casemapping();
+
+                // An empty reply can't be told apart from one to the user
+                let mut is_requested = !replies.trim().is_empty();
+
+                // Each reply is formatted as `nick[*]=<+|->user@host`
+                let mut events = replies
+                    .split_whitespace()
+                    .filter_map(|reply| {
+                        let (nick, userhost) = reply.split_once('=')?;
+                        let nick = nick.trim_end_matches('*');
+                        let userhost = userhost.get(1..)?;
+
+                        let is_pending = self
+                            .hostmask_requests
+                            .remove(&Nick::from_str(nick, casemapping));
+
+                        is_requested &= is_pending;
+
+                        is_pending.then(|| {
+                            Event::HostmaskResolved(format!(
+                                "{nick}!{userhost}"
+                            ))
+                        })
+                    })
+                    .collect::<Vec<_>>();
+
+                // Replies to a USERHOST sent by the user are shown as usual
+                if !is_requested {
+                    events.push(Event::Single(
+                        message,
+                        self.nickname().to_owned(),
+                    ));
+                }
+
+                return Ok(events);
+            }
+            Command::Numeric(RPL_ISON, _) if self.ison_user_requests > 0 => {
+                self.ison_user_requests -= 1;
+            }
+            Command::Numeric(RPL_ISON, args) if self.ison_requested => {
+                self.ison_requested = false;
+
+                let casemapping = self.casemapping();
+
+                let online = ok!(args.get(1))
+                    .split_whitespace()
+                    .map(|nick| Nick::from_str(nick, casemapping))
+                    .collect::<HashSet<_>>();
+
+                for nick in &self.tracked_queries {
+                    self.presence.insert(nick.clone(), online.contains(nick));
+                }
+
+                // Queries are polled along with monitored users, but only
+                // the latter are announced
+                let came_online = online
+                    .difference(&self.ison_online)
+                    .filter(|nick| self.is_announced(nick.as_nickref()))
+                    .cloned()
+                    .map(User::from)
+                    .collect::<Vec<_>>();
+                let went_offline = self
+                    .ison_online
+                    .difference(&online)
+                    .filter(|nick| self.is_announced(nick.as_nickref()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                self.ison_online = online;
+
+                let mut events = vec![];
+
+                if !came_online.is_empty() {
+                    events.push(Event::MonitoredOnline(came_online));
+                }
+
+                if !went_offline.is_empty() {
+                    events.push(Event::MonitoredOffline(went_offline));
+                }
+
+                return Ok(events);
+            }
             Command::Numeric(RPL_WHOREPLY, args) => {
                 let channel = ok!(args.get(1));
 
@@ -2027,6 +2185,7 @@ impl Client {
                     self.statusmsg(),
                     casemapping,
                 ) {
+                    let channel_modes = self.parse_channel_modes(modes, args);
                     let modes = mode::parse::<mode::Channel>(
                         modes,
                         args,
@@ -2034,7 +2193,32 @@ impl Client {
                         self.prefix(),
                     );
 
+                    let mut keys_updated = false;
+
+                    if self.chanmap.contains_key(&channel) {
+                        for mode in &modes {
+                            let key = match mode {
+                                mode::Mode::Add(
+                                    mode::Channel::KeyLock,
+                                    Some(key),
+                                ) => Some(key.clone()),
+                                mode::Mode::Remove(
+                                    mode::Channel::KeyLock,
+                                    _,
+                                ) => None,
+                                _ => continue,
+                            };
+
+                            keys_updated |=
+                                self.remember_channel_key(&channel, key);
+                        }
+                    }
+
                     if let Some(channel) = self.chanmap.get_mut(&channel) {
+                        for mode in channel_modes {
+                            channel.metadata.apply(mode);
+                        }
+
                         for mode in modes {
                             if let Some((op, lookup)) =
                                 mode.operation().zip(mode.arg().map(|nick| {
@@ -2051,13 +2235,52 @@ impl Client {
                             }
                         }
                     }
+
+                    if keys_updated {
+                        return Ok(vec![
+                            Event::Single(message, self.nickname().to_owned()),
+                            Event::ChannelKeysUpdated,
+                        ]);
+                    }
                 } else {
+                    if casemapping.normalize(target)
+                        == self.nickname().as_normalized_str()
+                    {
+                        on_connect::apply_modes(&mut self.user_modes, modes);
+                        self.signal_on_connect(on_connect::Signal::Modes(
+                            modes.clone(),
+                        ));
+                    }
+
+                    if casemapping.normalize(target)
+                        == self.nickname().as_normalized_str()
+                        && mode::parse::<mode::User>(
+                            modes,
+                            args,
+                            self.chanmodes(),
+                            self.prefix(),
+                        )
+                        .into_iter()
+                        .any(|mode| {
+                            matches!(
+                                mode,
+                                mode::Mode::Remove(
+                                    mode::User::GlobalOperator
+                                        | mode::User::LocalOperator,
+                                    _
+                                )
+                            )
+                        })
+                    {
+                        self.is_oper = false;
+                    }
+
                     // Only check for being logged in via mode if account-notify is not available,
                     // since it is not standardized across networks.
 
                     if casemapping.normalize(target)
                         == self.nickname().as_normalized_str()
-                        && !self.supports_account_notify
+                        && !self.supports_account_notify()
                         && !self.registration_required_channels.is_empty()
                     {
                         let modes = mode::parse::<mode::User>(
@@ -2075,7 +2298,9 @@ impl Client {
                         }) {
                             for message in group_joins(
                                 &self.registration_required_channels,
-                                &self.config.channel_keys,
+                                &self.join_keys(
+                                    &self.registration_required_channels,
+                                ),
                             ) {
                                 self.handle.try_send(message)?;
                             }
@@ -2096,14 +2321,31 @@ impl Client {
                         self.casemapping(),
                     )))
                 {
-                    let casemapping = isupport::get_casemapping(&self.isupport);
-                    let prefix = isupport::get_prefix(&self.isupport);
-                    for user in args[3].split(' ') {
-                        if let Ok(user) = User::parse(user, casemapping, prefix)
-                        {
-                            channel.users.insert(user);
-                        }
-                    }
+                    let casemapping =
+                        isupport::get_casemapping(self.core.isupport());
+                    let prefix = isupport::get_prefix(self.core.isupport());
+                    let users = args[3]
+                        .split(' ')
+                        .filter_map(|user| {
+                            User::parse(user, casemapping, prefix).ok()
+                        })
+                        .map(|user| {
+                            // Update any existing entry so a refreshed names
+                            // list brings its access levels up to date
+                            match channel.users.resolve(&user) {
+                                Some(existing) => {
+                                    let mut existing = existing.clone();
+                                    existing.update_from_names(user);
+                                    existing
+                                }
+                                None => user,
+                            }
+                        })
+                        .collect::<Vec<_>>();
+
+                    // Names replies arrive in bursts of hundreds of users, so
+                    // insert each reply at once instead of user by user
+                    channel.users.extend(users);
 
                     // Don't save to history if names list was triggered by JOIN
                     if !channel.names_init {
@@ -2189,8 +2431,8 @@ impl Client {
                     channel.topic.who = Some(
                         context!(User::parse(
                             ok!(args.get(2)),
-                            isupport::get_casemapping(&self.isupport),
-                            isupport::get_prefix(&self.isupport),
+                            isupport::get_casemapping(self.core.isupport()),
+                            isupport::get_prefix(self.core.isupport()),
                         ))
                         .nickname()
                         .to_owned(),
@@ -2227,6 +2469,10 @@ impl Client {
             }
             Command::Numeric(RPL_CHANNELMODEIS, args) => {
                 let channel = ok!(args.get(1));
+                let modes = self.parse_channel_modes(
+                    ok!(args.get(2)),
+                    args.get(3..).unwrap_or_default(),
+                );
 
                 if let Ok(target_channel) = target::Channel::parse(
                     channel,
@@ -2258,7 +2504,7 @@ impl Client {
                             false
                         };
 
-                    channel.mode = args.get(2).cloned();
+                    channel.metadata.set_modes(modes);
 
                     if mode_request_response {
                         return Ok(vec![]);
@@ -2273,18 +2519,30 @@ impl Client {
                     self.chantypes(),
                     self.statusmsg(),
                     self.casemapping(),
-                ) && let Some(position) =
-                    self.mode_requests.iter().position(|mode_request| {
-                        mode_request.channel == target_channel
-                            && matches!(
-                                mode_request.status,
-                                ModeStatus::Received(_)
-                            )
-                    })
-                {
-                    self.mode_requests.swap_remove(position);
+                ) {
+                    if let Some(channel) = self.chanmap.get_mut(&target_channel)
+                    {
+                        channel.metadata.created = args
+                            .get(2)
+                            .and_then(|created| created.parse::<i64>().ok())
+                            .and_then(|created| {
+                                DateTime::from_timestamp(created, 0)
+                            });
+                    }
 
-                    return Ok(vec![]);
+                    if let Some(position) =
+                        self.mode_requests.iter().position(|mode_request| {
+                            mode_request.channel == target_channel
+                                && matches!(
+                                    mode_request.status,
+                                    ModeStatus::Received(_)
+                                )
+                        })
+                    {
+                        self.mode_requests.swap_remove(position);
+
+                        return Ok(vec![]);
+                    }
                 }
             }
             Command::Numeric(ERR_NOCHANMODES, args) => {
@@ -2306,125 +2564,121 @@ impl Client {
                     self.registration_required_channels.push(channel.clone());
                 }
             }
-            Command::Numeric(RPL_ISUPPORT, args) => {
-                let args_len = args.len();
+            Command::Numeric(ERR_BADCHANNELKEY, args) => {
+                let channel = context!(target::Channel::parse(
+                    ok!(args.get(1)),
+                    self.chantypes(),
+                    self.statusmsg(),
+                    self.casemapping(),
+                ));
+
+                self.pending_channel_keys.remove(&channel);
+
+                // The remembered key is outdated
+                if self.remember_channel_key(&channel, None) {
+                    return Ok(vec![
+                        Event::Single(message, self.nickname().to_owned()),
+                        Event::ChannelKeysUpdated,
+                    ]);
+                }
+            }
+            Command::Numeric(RPL_ISUPPORT, _) => {
                 let mut events: Vec<Event> = Vec::new();
-                for (index, arg) in args.iter().enumerate().skip(1) {
-                    let operation = arg.parse::<isupport::Operation>();
-
-                    match operation {
-                        Ok(operation) => {
-                            match operation {
-                                isupport::Operation::Add(parameter) => {
-                                    if let Some(kind) = parameter.kind() {
-                                        log::info!(
-                                            "[{}] adding ISUPPORT parameter: {:?}",
-                                            self.server,
-                                            parameter
-                                        );
-
-                                        self.isupport.insert(
-                                            kind.clone(),
-                                            parameter.clone(),
-                                        );
-
-                                        match parameter {
-                                            isupport::Parameter::CASEMAPPING(casemapping) => {
-                                                if let Some(resolved_nick) = self.resolved_nick.as_mut() {
-                                                    resolved_nick.renormalize(casemapping);
-                                                }
-                                                self.configured_nick.renormalize(casemapping);
 
-                                                // TODO: When casemapping
-                                                // changes, ChannelUsers,
-                                                // Targets, etc should be
-                                                // renormalized and resorted
-                                            }
-                                            isupport::Parameter::MONITOR(
-                                                target_limit,
-                                            ) => {
-                                                let messages = group_monitors(
-                                                    &self.config.monitor,
-                                                    target_limit,
+                for event in self.core.receive(&message)? {
+                    match event {
+                        halloy_core::client::Event::AddedIsupportParam(
+                            parameter,
+                        ) => {
+                            match parameter {
+                                isupport::Parameter::CASEMAPPING(
+                                    casemapping,
+                                ) => {
+                                    if let Some(resolved_nick) =
+                                        self.resolved_nick.as_mut()
+                                    {
+                                        resolved_nick.renormalize(casemapping);
+                                    }
+                                    self.configured_nick
+                                        .renormalize(casemapping);
+
+                                    events.extend(self.renormalize_targets());
+                                }
+                                isupport::Parameter::MONITOR(target_limit) => {
+                                    let targets = self.monitor_targets();
+                                    let messages =
+                                        group_monitors(&targets, target_limit);
+                                    for message in messages {
+                                        self.handle.try_send(message)?;
+                                    }
+                                }
+                                isupport::Parameter::SAFERATE => {
+                                    if let Some(ref mut anti_flood) =
+                                        self.anti_flood
+                                    {
+                                        for message in anti_flood.drain_tokens()
+                                        {
+                                            if let Err(e) = self
+                                                .handle
+                                                .try_send(message.into())
+                                            {
+                                                log::warn!(
+                                                    "[{}] Error sending message: {e}",
+                                                    self.server
                                                 );
-                                                for message in messages {
-                                                    self.handle
-                                                        .try_send(message)?;
-                                                }
                                             }
-                                            isupport::Parameter::SAFERATE => {
-                                                if let Some(ref mut anti_flood) = self.anti_flood {
-                                                    for message in anti_flood.drain_tokens() {
-                                                        if let Err(e) = self.handle.try_send(message.into()) {
-                                                            log::warn!("[{}] Error sending message: {e}", self.server);
-                                                        }
-                                                    }
-                                                }
+                                        }
+                                    }
 
-                                                self.anti_flood = None;
+                                    self.anti_flood = None;
 
-                                                self.who_poll_interval.set_min(self.config.who_poll_interval);
-                                            }
-                                            isupport::Parameter::BOUNCER_NETID(ref id) => {
-                                                match self.server.bouncer_netid() {
-                                                    Some(requested_id) if id != requested_id => {
-                                                        log::warn!(
-                                                            "[{}] Requested bouncer id `{requested_id}`, but was connected to bouncer `{id}`",
-                                                            self.server,
-                                                        );
-                                                        // quit on fatal error?
-                                                        self.quit(None);
-                                                    },
-                                                    _ => (),
-                                                }
-                                                self.resolved_netid = Some(id.clone());
-                                            }
-                                            _ => (),
+                                    self.who_poll_interval
+                                        .set_min(self.config.who_poll_interval);
+                                }
+                                isupport::Parameter::BOUNCER_NETID(ref id) => {
+                                    match self.server.bouncer_netid() {
+                                        Some(requested_id)
+                                            if id != requested_id =>
+                                        {
+                                            log::warn!(
+                                                "[{}] Requested bouncer id `{requested_id}`, but was connected to bouncer `{id}`",
+                                                self.server,
+                                            );
+                                            // quit on fatal error?
+                                            self.send_quit(None);
                                         }
-                                        events.push(Event::AddedIsupportParam(
-                                            parameter,
-                                        ));
-                                    } else {
-                                        log::info!(
-                                            "[{}] ignoring ISUPPORT parameter: {:?}",
-                                            self.server,
-                                            parameter
-                                        );
+                                        _ => (),
                                     }
+                                    self.resolved_netid = Some(id.clone());
                                 }
-                                isupport::Operation::Remove(_) => {
-                                    if let Some(kind) = operation.kind() {
-                                        log::info!(
-                                            "[{}] removing ISUPPORT parameter: {:?}",
-                                            self.server,
-                                            kind
-                                        );
-                                        self.isupport.remove(&kind);
-                                    }
+                                isupport::Parameter::PREFIX(_) => {
+                                    self.refresh_names();
                                 }
-                            };
+                                _ => (),
+                            }
+                            events.push(Event::AddedIsupportParam(parameter));
                         }
-                        Err(error) => {
-                            // Last argument is :are supported by this server
-                            if index != args_len - 1 {
-                                if error == isupport::UNKNOWN_ISUPPORT_PARAMETER
+                        halloy_core::client::Event::RemovedIsupportParam(
+                            kind,
+                        ) => {
+                            if kind == isupport::Kind::PREFIX {
+                                self.refresh_names();
+                            } else if kind == isupport::Kind::CASEMAPPING {
+                                let casemapping = self.casemapping();
+
+                                if let Some(resolved_nick) =
+                                    self.resolved_nick.as_mut()
                                 {
-                                    log::info!(
-                                        "[{}] unable to parse ISUPPORT parameter: {} ({})",
-                                        self.server,
-                                        arg,
-                                        error
-                                    );
-                                } else {
-                                    log::warn!(
-                                        "[{}] unable to parse ISUPPORT parameter: {} ({})",
-                                        self.server,
-                                        arg,
-                                        error
-                                    );
+                                    resolved_nick.renormalize(casemapping);
                                 }
+                                self.configured_nick.renormalize(casemapping);
+
+                                events.extend(self.renormalize_targets());
                             }
+
+                            events.push(Event::RemovedIsupportParam(kind));
                         }
+                        halloy_core::client::Event::Registered => {}
                     }
                 }
 
@@ -2436,6 +2690,11 @@ impl Client {
             Command::ACCOUNT(accountname) => {
                 let old_user = ok!(message.user(self.casemapping()));
 
+                if old_user.nickname() == self.nickname() {
+                    self.account =
+                        (accountname != "*").then(|| accountname.clone());
+                }
+
                 self.chanmap.values_mut().for_each(|channel| {
                     if let Some(user) = channel.users.take(&old_user) {
                         channel
@@ -2450,7 +2709,7 @@ impl Client {
                 {
                     for message in group_joins(
                         &self.registration_required_channels,
-                        &self.config.channel_keys,
+                        &self.join_keys(&self.registration_required_channels),
                     ) {
                         self.handle.try_send(message)?;
                     }
@@ -2463,6 +2722,11 @@ impl Client {
 
                 let ourself = old_user.nickname() == self.nickname();
 
+                if ourself {
+                    self.visible_host = Some(new_hostname.clone());
+                    self.host_hidden = true;
+                }
+
                 self.chanmap.values_mut().for_each(|channel| {
                     if let Some(user) = channel.users.take(&old_user) {
                         channel.users.insert(user.with_username_and_hostname(
@@ -2485,8 +2749,9 @@ impl Client {
                 })]);
             }
             Command::Numeric(RPL_MONONLINE, args) => {
-                let casemapping = isupport::get_casemapping(&self.isupport);
-                let prefix = isupport::get_prefix(&self.isupport);
+                let casemapping =
+                    isupport::get_casemapping(self.core.isupport());
+                let prefix = isupport::get_prefix(self.core.isupport());
 
                 let targets = ok!(args.get(1))
                     .split(',')
@@ -2500,6 +2765,19 @@ impl Client {
                     })
                     .collect::<Vec<_>>();
 
+                for user in &targets {
+                    self.presence.insert(user.nickname().to_owned(), true);
+                }
+
+                let targets = targets
+                    .into_iter()
+                    .filter(|user| self.is_announced(user.nickname()))
+                    .collect::<Vec<_>>();
+
+                if targets.is_empty() {
+                    return Ok(vec![]);
+                }
+
                 return Ok(vec![
                     Event::Single(message.clone(), self.nickname().to_owned()),
                     Event::MonitoredOnline(targets),
@@ -2511,6 +2789,19 @@ impl Client {
                     .map(|target| Nick::from_str(target, self.casemapping()))
                     .collect::<Vec<_>>();
 
+                for nick in &targets {
+                    self.presence.insert(nick.clone(), false);
+                }
+
+                let targets = targets
+                    .into_iter()
+                    .filter(|nick| self.is_announced(nick.as_nickref()))
+                    .collect::<Vec<_>>();
+
+                if targets.is_empty() {
+                    return Ok(vec![]);
+                }
+
                 return Ok(vec![
                     Event::Single(message.clone(), self.nickname().to_owned()),
                     Event::MonitoredOffline(targets),
@@ -2576,16 +2867,54 @@ impl Client {
 
                 return Ok(events);
             }
-            Command::Numeric(RPL_SASLSUCCESS, _) => {
-                self.sasl_succeeded = true;
-                self.registration_step = RegistrationStep::End;
-                self.handle.try_send(command!("CAP", "END"))?;
+            Command::Numeric(RPL_YOUREOPER, _) => {
+                self.is_oper = true;
+            }
+            Command::Numeric(RPL_RSACHALLENGE2, args)
+                if self.oper_challenge.is_some() =>
+            {
+                let text = ok!(args.get(1));
+
+                if let Some(challenge) = self.oper_challenge.as_mut() {
+                    challenge.push_str(text);
+                }
+
+                return Ok(vec![]);
             }
-            Command::Numeric(ERR_SASLFAIL | ERR_SASLTOOLONG, _) => {
-                log::debug!("[{}] sasl auth failed", self.server);
+            Command::Numeric(RPL_ENDOFRSACHALLENGE2, _)
+                if self.oper_challenge.is_some() =>
+            {
+                let challenge = self.oper_challenge.take().unwrap_or_default();
+
+                match self
+                    .config
+                    .oper
+                    .as_ref()
+                    .map(|oper| oper.challenge_response(&challenge))
+                {
+                    Some(Ok(response)) => {
+                        self.handle.try_send(command!(
+                            "CHALLENGE",
+                            format!("+{response}")
+                        ))?;
+                    }
+                    Some(Err(error)) => {
+                        log::warn!(
+                            "[{}] unable to answer oper challenge: {error}",
+                            self.server
+                        );
+                    }
+                    None => {}
+                }
 
-                self.registration_step = RegistrationStep::End;
-                self.handle.try_send(command!("CAP", "END"))?;
+                return Ok(vec![]);
+            }
+            Command::Numeric(
+                RPL_SASLSUCCESS | ERR_SASLFAIL | ERR_SASLTOOLONG
+                | ERR_NICKLOCKED,
+                _,
+            ) => {
+                self.core.receive(&message)?;
             }
             Command::Numeric(RPL_TRYAGAIN, args) => {
                 let command = ok!(args.get(1));
@@ -2611,18 +2940,8 @@ impl Client {
                 }
             }
             Command::Numeric(RPL_ENDOFMOTD | ERR_NOMOTD, _) => {
-                // MOTD (or ERR_NOMOTD) is the last required message in the numerics
-                // sent on successfully completing the registration process (after
-                // RPL_ISUPPORT message(s) are sent).
-                // https://modern.ircdocs.horse/#connection-registration
-                if self.registration_step != RegistrationStep::End {
-                    log::warn!(
-                        "[{}] Registration completed while in mode: {:?}",
-                        self.server,
-                        self.registration_step
-                    );
-                }
-                self.registration_step = RegistrationStep::Complete;
+                // Completes registration
+                self.core.receive(&message)?;
 
                 if let Some(id) = self.server.bouncer_netid()
                     && self.resolved_netid.is_none()
@@ -2715,7 +3034,7 @@ impl Client {
                 // Request bouncer networks
                 // TODO(pounce) replace this with "bouncer-networks-notify" after the cap handling
                 // is cleaned up.
-                if self.is_primary() && self.supports_bouncer_networks {
+                if self.is_primary() && self.supports_bouncer_networks() {
                     self.handle
                         .try_send(command!("BOUNCER", "LISTNETWORKS"))?;
                 }
@@ -2736,21 +3055,50 @@ impl Client {
                     .collect::<Vec<_>>();
 
                 // Send JOIN
-                for message in group_joins(&channels, &self.config.channel_keys)
+                for message in
+                    group_joins(&channels, &self.join_keys(&channels))
                 {
                     self.handle.try_send(message)?;
                 }
 
-                return Ok(vec![Event::OnConnect(on_connect(
+                let (sender, receiver) = mpsc::unbounded();
+                self.on_connect_signals = Some(sender);
+
+                let mut events = vec![Event::OnConnect(on_connect(
+                    &self.server,
                     self.handle.clone(),
                     self.config.clone(),
-                    self.nickname(),
-                    &self.isupport,
-                ))]);
+                    self.on_connect_status(),
+                    receiver,
+                    self.core.isupport(),
+                    self.is_reconnect,
+                ))];
+
+                if self.is_hostname_exposed() {
+                    self.hostname_warned = true;
+
+                    events.push(Event::Broadcast(Broadcast::HostnameExposed {
+                        hostname: self.visible_host.clone(),
+                        sent_time: server_time(&message),
+                    }));
+                }
+
+                return Ok(events);
             }
             _ => {}
         }
 
+        if let Some(category) =
+            message::category(&message, self.resolved_nick.is_some())
+                .filter(|category| self.config.sub_buffers.contains(category))
+        {
+            return Ok(vec![Event::SingleInCategory(
+                message,
+                self.nickname().to_owned(),
+                category,
+            )]);
+        }
+
         Ok(vec![Event::Single(message, self.nickname().to_owned())])
     }
 
@@ -2760,7 +3108,7 @@ impl Client {
         read_marker: ReadMarker,
         priority: TokenPriority,
     ) {
-        if self.supports_read_marker {
+        if self.supports_read_marker() {
             self.send(
                 None,
                 command!(
@@ -2774,51 +3122,215 @@ impl Client {
         }
     }
 
-    fn user_who_request(&self, channel: &target::Channel) -> bool {
-        if let Some(who_poll) = self
-            .who_polls
-            .iter()
-            .find(|who_poll| who_poll.channel == *channel)
-        {
-            matches!(
-                who_poll.status,
-                WhoStatus::Requested(WhoSource::User, _, _)
-                    | WhoStatus::Receiving(WhoSource::User, _)
-            )
-        } else {
-            false
-        }
-    }
+    fn oper(
+        &mut self,
+        buffer: &buffer::Upstream,
+        name: Option<String>,
+        password: Option<String>,
+    ) -> Result<(), OperError> {
+        let config = self.config.oper.clone().unwrap_or_default();
 
-    // TODO allow configuring the "sorting method"
-    // this function sorts channels together which have similar names when the chantype prefix
-    // (sometimes multiplied) is removed
-    // e.g. '#chat', '##chat-offtopic' and '&chat-local' all get sorted together instead of in
-    // wildly different places.
-    fn compare_channels(&self, a: &str, b: &str) -> Ordering {
-        let (Some(a_chantype), Some(b_chantype)) =
-            (a.chars().nth(0), b.chars().nth(0))
-        else {
-            return a.cmp(b);
-        };
+        let name = name.or(config.name).ok_or(OperError::MissingName)?;
 
-        if [a_chantype, b_chantype]
-            .iter()
-            .all(|c| self.chantypes().contains(c))
-        {
-            let ord = a
-                .trim_start_matches(a_chantype)
-                .cmp(b.trim_start_matches(b_chantype));
-            if ord != Ordering::Equal {
-                return ord;
+        let message = match (password, config.challenge_key, config.password) {
+            (Some(password), _, _) | (None, None, Some(password)) => {
+                command!("OPER", name, password)
+            }
+            (None, Some(_), _) => {
+                self.oper_challenge = Some(String::new());
+
+                command!("CHALLENGE", name)
+            }
+            (None, None, None) => return Err(OperError::MissingPassword),
+        };
+
+        self.send(Some(buffer), message.into(), TokenPriority::User);
+
+        Ok(())
+    }
+
+    fn request_access_list(&mut self, channel: &target::Channel) {
+        let list = self.config.access_list.list.clone();
+
+        if self.send_access_list_command(&list, channel, "", "") {
+            self.access_lists.request(channel.clone(), Instant::now());
+        }
+    }
+
+    fn add_access(
+        &mut self,
+        channel: &target::Channel,
+        mask: &str,
+        flags: &str,
+    ) {
+        let add = self.config.access_list.add.clone();
+
+        if self.send_access_list_command(&add, channel, mask, flags) {
+            self.request_access_list(channel);
+        }
+    }
+
+    fn remove_access(&mut self, channel: &target::Channel, mask: &str) {
+        let remove = self.config.access_list.remove.clone();
+
+        if self.send_access_list_command(&remove, channel, mask, "") {
+            self.request_access_list(channel);
+        }
+    }
+
+    fn send_access_list_command(
+        &mut self,
+        template: &str,
+        channel: &target::Channel,
+        mask: &str,
+        flags: &str,
+    ) -> bool {
+        let input = config::server::AccessList::command(
+            template,
+            channel.as_str(),
+            mask,
+            flags,
+        );
+
+        let encoded = match crate::command::parse(
+            &input,
+            None,
+            Some(self.nickname()),
+            self.core.isupport(),
+            &self.config.command_aliases,
+        ) {
+            Ok(crate::Command::Irc(command)) => {
+                message::Encoded::try_from(command).ok()
+            }
+            _ => None,
+        };
+
+        if let Some(encoded) = encoded {
+            self.send(None, encoded, TokenPriority::User);
+            true
+        } else {
+            log::warn!(
+                "[{}] invalid access list command: {input}",
+                self.server
+            );
+            false
+        }
+    }
+
+    fn request_admin(&mut self, query: admin::Query) {
+        let message = self.admin_reports.request(query);
+
+        self.send(None, message.into(), TokenPriority::User);
+    }
+
+    /// Whether SASL can be restarted, i.e. it's configured, the server
+    /// supports it and registration has completed.
+    fn can_reauthenticate(&self) -> bool {
+        self.core.can_reauthenticate()
+    }
+
+    fn reauthenticate(&mut self) {
+        if let Some(message) = self.core.reauthenticate() {
+            self.send(None, message.into(), TokenPriority::User);
+        }
+    }
+
+    fn oper_actions(&self) -> Option<&config::server::Oper> {
+        self.config
+            .oper
+            .as_ref()
+            .filter(|oper| self.is_oper && oper.actions)
+    }
+
+    fn user_who_request(&self, channel: &target::Channel) -> bool {
+        if let Some(who_poll) = self
+            .who_polls
+            .iter()
+            .find(|who_poll| who_poll.channel == *channel)
+        {
+            matches!(
+                who_poll.status,
+                WhoStatus::Requested(WhoSource::User, _, _)
+                    | WhoStatus::Receiving(WhoSource::User, _)
+            )
+        } else {
+            false
+        }
+    }
+
+    // TODO allow configuring the "sorting method"
+    // this function sorts channels together which have similar names when the chantype prefix
+    // (sometimes multiplied) is removed
+    // e.g. '#chat', '##chat-offtopic' and '&chat-local' all get sorted together instead of in
+    // wildly different places.
+    fn compare_channels(&self, a: &str, b: &str) -> Ordering {
+        let (Some(a_chantype), Some(b_chantype)) =
+            (a.chars().nth(0), b.chars().nth(0))
+        else {
+            return a.cmp(b);
+        };
+
+        if [a_chantype, b_chantype]
+            .iter()
+            .all(|c| self.chantypes().contains(c))
+        {
+            let ord = a
+                .trim_start_matches(a_chantype)
+                .cmp(b.trim_start_matches(b_chantype));
+            if ord != Ordering::Equal {
+                return ord;
             }
         }
         a.cmp(b)
     }
 
+    /// Keys to join `channels` with, a remembered key taking precedence over
+    /// a configured one
+    fn join_keys(
+        &self,
+        channels: &[target::Channel],
+    ) -> HashMap<String, String> {
+        let mut keys = self.config.channel_keys.clone();
+
+        keys.extend(channels.iter().filter_map(|channel| {
+            self.channel_keys
+                .get(channel.as_normalized_str())
+                .map(|key| (channel.as_str().to_string(), key.clone()))
+        }));
+
+        keys
+    }
+
+    /// Returns true if the remembered keys changed
+    fn remember_channel_key(
+        &mut self,
+        channel: &target::Channel,
+        key: Option<String>,
+    ) -> bool {
+        let normalized = channel.as_normalized_str().to_string();
+
+        if let Some(key) = key {
+            self.channel_keys.insert(normalized, key.clone()) != Some(key)
+        } else {
+            self.channel_keys.remove(&normalized).is_some()
+        }
+    }
+
+    pub fn save_channel_keys(&self) -> impl Future<Output = Message> + use<> {
+        let server = self.server.clone();
+        let keys = self.channel_keys.clone();
+
+        async move {
+            let result = save_channel_keys(server.clone(), keys).await;
+
+            Message::ChannelKeysSaved(server, result)
+        }
+        .boxed()
+    }
+
     pub fn chathistory_limit(&self) -> u16 {
         if let Some(isupport::Parameter::CHATHISTORY(server_limit)) =
-            self.isupport.get(&isupport::Kind::CHATHISTORY)
+            self.core.isupport().get(&isupport::Kind::CHATHISTORY)
             && *server_limit != 0
         {
             return std::cmp::min(*server_limit, CLIENT_CHATHISTORY_LIMIT);
@@ -2831,7 +3343,7 @@ impl Client {
         &self,
     ) -> Vec<isupport::MessageReferenceType> {
         if let Some(isupport::Parameter::MSGREFTYPES(message_reference_types)) =
-            self.isupport.get(&isupport::Kind::MSGREFTYPES)
+            self.core.isupport().get(&isupport::Kind::MSGREFTYPES)
         {
             message_reference_types.clone()
         } else {
@@ -2855,7 +3367,7 @@ impl Client {
     ) {
         use std::collections::hash_map;
 
-        if self.supports_chathistory {
+        if self.supports_chathistory() {
             if let Some(target) = subcommand.target() {
                 if let hash_map::Entry::Vacant(entry) =
                     self.chathistory_requests.entry(Target::parse(
@@ -3107,10 +3619,19 @@ impl Client {
         self.chanmap.get(channel).map(|channel| &channel.topic)
     }
 
-    fn mode<'a>(&'a self, channel: &target::Channel) -> Option<&'a String> {
-        self.chanmap
-            .get(channel)
-            .and_then(|channel| channel.mode.as_ref())
+    fn metadata<'a>(
+        &'a self,
+        channel: &target::Channel,
+    ) -> Option<&'a Metadata> {
+        self.chanmap.get(channel).map(|channel| &channel.metadata)
+    }
+
+    fn parse_channel_modes(
+        &self,
+        modes: &str,
+        args: &[String],
+    ) -> Vec<mode::Mode<char>> {
+        parse_channel_modes(modes, args, self.chanmodes(), self.prefix())
     }
 
     fn resolve_user_attributes<'a>(
@@ -3130,6 +3651,260 @@ impl Client {
         self.chanmap.get(channel).map(|chanimpl| &chanimpl.users)
     }
 
+    fn nicklist(&self, channel: &target::Channel) -> Option<&[User]> {
+        self.chanmap
+            .get(channel)
+            .map(|chanimpl| chanimpl.nicklist.as_slice())
+    }
+
+    fn has_stale_nicklists(&self) -> bool {
+        self.chanmap
+            .values()
+            .any(|channel| channel.users.is_dirty())
+    }
+
+    fn refresh_nicklists(&mut self) {
+        for channel in self
+            .chanmap
+            .values_mut()
+            .filter(|channel| channel.users.is_dirty())
+        {
+            channel.nicklist = channel.users.iter().cloned().collect();
+            channel.users.mark_clean();
+        }
+    }
+
+    /// Looks up the `nick!user@host` of a user in any joined channel.
+    fn hostmask(&self, nick: NickRef) -> Option<String> {
+        self.chanmap
+            .values()
+            .filter_map(|channel| channel.users.get_by_nick(nick))
+            .find_map(User::hostmask)
+    }
+
+    /// Requests the userhost of `nick`, which is reported back through
+    /// [`Event::HostmaskResolved`].
+    fn request_hostmask(&mut self, nick: Nick) {
+        self.send(
+            None,
+            command!("USERHOST", nick.to_string()).into(),
+            TokenPriority::User,
+        );
+
+        self.hostmask_requests.insert(nick);
+    }
+
+    /// Nicknames from the server configuration and the monitor list.
+    fn monitor_targets(&self) -> Vec<String> {
+        self.monitored.iter().map(ToString::to_string).collect()
+    }
+
+    /// Whether the warning about an exposed hostname is due: we're neither
+    /// authenticated nor about to identify with NickServ, and the server
+    /// didn't cloak our hostname.
+    fn is_hostname_exposed(&self) -> bool {
+        self.config.warn_exposed_hostname
+            && !self.hostname_warned
+            && !self.host_hidden
+            && !self.core.sasl_succeeded()
+            && self.account.is_none()
+            && self.config.nick_password.is_none()
+    }
+
+    /// Whether `nick` was monitored by the user, rather than only tracked
+    /// for an open query.
+    fn is_announced(&self, nick: NickRef) -> bool {
+        self.is_monitored(nick)
+    }
+
+    /// Sets the open queries, whose peers' presence is tracked with MONITOR
+    /// or ISON.
+    pub fn track_queries<'a>(
+        &mut self,
+        queries: impl Iterator<Item = &'a target::Query>,
+    ) {
+        let casemapping = self.casemapping();
+
+        let tracked = queries
+            .map(|query| Nick::from_str(query.as_str(), casemapping))
+            .collect::<HashSet<_>>();
+
+        if tracked != self.tracked_queries {
+            // Picked up by the next poll, which is due right away
+            self.ison_polled_at = None;
+            self.tracked_queries = tracked;
+        }
+    }
+
+    /// Sets the channels open in a pane. Periodic WHO polls are limited to
+    /// these when `who_poll_visible_only` is enabled.
+    pub fn set_visible_channels<'a>(
+        &mut self,
+        channels: impl Iterator<Item = &'a target::Channel>,
+    ) {
+        self.visible_channels = channels.cloned().collect();
+    }
+
+    /// Whether `channel` should be WHO polled periodically. Servers with
+    /// away-notify only need the initial WHO, which is always sent.
+    fn wants_who_poll(&self, channel: &target::Channel) -> bool {
+        self.supports_away_notify()
+            || !self.config.who_poll_visible_only
+            || self.visible_channels.contains(channel)
+    }
+
+    /// Whether the peer of `query` is online, if known. Sharing a channel
+    /// with the peer means they're online.
+    pub fn query_presence(&self, query: &target::Query) -> Option<bool> {
+        let nick = Nick::from_str(query.as_str(), self.casemapping());
+
+        if !self.user_channels(nick.as_nickref()).is_empty() {
+            return Some(true);
+        }
+
+        self.presence.get(&nick).copied()
+    }
+
+    /// Adds tracked queries to the server's MONITOR list, and removes the
+    /// ones which were closed.
+    fn sync_monitored_queries(&mut self) {
+        let added = self
+            .tracked_queries
+            .difference(&self.monitored_queries)
+            .filter(|nick| !self.is_announced(nick.as_nickref()))
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        let removed = self
+            .monitored_queries
+            .difference(&self.tracked_queries)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for nick in removed {
+            self.monitored_queries.remove(&nick);
+            self.presence.remove(&nick);
+
+            if !self.is_announced(nick.as_nickref()) {
+                self.send(
+                    None,
+                    command!("MONITOR", "-", nick.to_string()).into(),
+                    TokenPriority::Low,
+                );
+            }
+        }
+
+        if !added.is_empty() {
+            let casemapping = self.casemapping();
+
+            for message in group_monitors(&added, None) {
+                self.send(None, message.into(), TokenPriority::Low);
+            }
+
+            self.monitored_queries.extend(
+                added.iter().map(|nick| Nick::from_str(nick, casemapping)),
+            );
+        }
+    }
+
+    fn supports_monitor(&self) -> bool {
+        self.core.isupport().contains_key(&isupport::Kind::MONITOR)
+    }
+
+    fn supports_labels(&self) -> bool {
+        self.core.capabilities().is_acknowledged("labeled-response")
+    }
+
+    fn supports_away_notify(&self) -> bool {
+        self.core.capabilities().is_acknowledged("away-notify")
+    }
+
+    fn supports_account_notify(&self) -> bool {
+        self.core.capabilities().is_acknowledged("account-notify")
+    }
+
+    fn supports_extended_join(&self) -> bool {
+        self.core.capabilities().is_acknowledged("extended-join")
+    }
+
+    fn supports_read_marker(&self) -> bool {
+        self.core
+            .capabilities()
+            .is_acknowledged("draft/read-marker")
+    }
+
+    fn supports_chathistory(&self) -> bool {
+        self.config.chathistory
+            && self
+                .core
+                .capabilities()
+                .is_acknowledged("draft/chathistory")
+    }
+
+    fn supports_bouncer_networks(&self) -> bool {
+        self.core
+            .capabilities()
+            .is_acknowledged("soju.im/bouncer-networks")
+    }
+
+    fn is_monitored(&self, nick: NickRef) -> bool {
+        self.monitored
+            .iter()
+            .any(|monitored| monitored.as_nickref() == nick)
+    }
+
+    /// Sets the monitor list to the server configuration's nicknames and
+    /// `nicks`.
+    fn set_monitored(&mut self, nicks: &[String]) {
+        let casemapping = self.casemapping();
+
+        self.monitored = self
+            .config
+            .monitor
+            .iter()
+            .chain(nicks)
+            .map(|nick| Nick::from_str(nick, casemapping))
+            .unique()
+            .collect();
+    }
+
+    /// Adds `nick` to the monitor list. Without MONITOR support the nick is
+    /// picked up by the next ISON poll instead.
+    fn monitor(&mut self, nick: Nick) {
+        if self.is_monitored(nick.as_nickref()) {
+            return;
+        }
+
+        if self.supports_monitor() {
+            self.send(
+                None,
+                command!("MONITOR", "+", nick.to_string()).into(),
+                TokenPriority::User,
+            );
+        } else {
+            self.ison_polled_at = None;
+        }
+
+        self.monitored.push(nick);
+    }
+
+    fn unmonitor(&mut self, nick: NickRef) {
+        self.monitored
+            .retain(|monitored| monitored.as_nickref() != nick);
+        self.ison_online
+            .retain(|online| online.as_nickref() != nick);
+        // Added back by the next tick while its query is still open
+        self.monitored_queries
+            .retain(|monitored| monitored.as_nickref() != nick);
+
+        if self.supports_monitor() {
+            self.send(
+                None,
+                command!("MONITOR", "-", nick.to_string()).into(),
+                TokenPriority::User,
+            );
+        }
+    }
+
     fn user_channels(&self, nick: NickRef) -> Vec<target::Channel> {
         self.chanmap
             .iter()
@@ -3146,6 +3921,23 @@ impl Client {
         self.resolved_queries.get(query)
     }
 
+    fn on_connect_status(&self) -> on_connect::Status {
+        on_connect::Status {
+            nickname: self.nickname().to_owned(),
+            modes: self.user_modes.clone(),
+            logged_in: self.logged_in,
+        }
+    }
+
+    /// Forwards `signal` to the `on_connect` commands, while they run.
+    fn signal_on_connect(&mut self, signal: on_connect::Signal) {
+        if let Some(sender) = &self.on_connect_signals
+            && sender.unbounded_send(signal).is_err()
+        {
+            self.on_connect_signals = None;
+        }
+    }
+
     pub fn nickname(&self) -> NickRef<'_> {
         // TODO: Fallback nicks
         self.resolved_nick
@@ -3165,6 +3957,26 @@ impl Client {
             HighlightNotificationBlackout::Receiving => {}
         }
 
+        // Forget CTCP requests which were never answered
+        self.ctcp_requests.retain(|_, (_, sent)| {
+            now.duration_since(*sent) < CTCP_REQUEST_TIMEOUT
+        });
+
+        if let Some(position) =
+            next_wanted_who_poll(&self.who_polls, |channel| {
+                self.wants_who_poll(channel)
+            })
+        {
+            self.who_polls.rotate_left(position);
+        }
+
+        let wants_front_poll = self
+            .who_polls
+            .front()
+            .is_some_and(|who_poll| self.wants_who_poll(&who_poll.channel));
+        let supports_away_notify = self.supports_away_notify();
+        let supports_account_notify = self.supports_account_notify();
+
         if let Some(who_poll) = self.who_polls.front_mut() {
             #[derive(Debug)]
             enum Request {
@@ -3173,11 +3985,11 @@ impl Client {
             }
 
             let request = match &who_poll.status {
-                WhoStatus::Joined => (self.supports_away_notify
+                WhoStatus::Joined => (supports_away_notify
                     || self.config.who_poll_enabled)
                     .then_some(Request::Poll),
                 WhoStatus::Waiting(last) => {
-                    if self.supports_away_notify {
+                    if supports_away_notify {
                         self.chanmap.get(&who_poll.channel).and_then(
                             |channel| {
                                 (!channel.who_init
@@ -3188,6 +4000,7 @@ impl Client {
                         )
                     } else {
                         (self.config.who_poll_enabled
+                            && wants_front_poll
                             && (now.duration_since(*last)
                                 >= self.who_poll_interval.duration()))
                         .then_some(Request::Poll)
@@ -3219,8 +4032,9 @@ impl Client {
                 );
 
                 let message =
-                    if self.isupport.contains_key(&isupport::Kind::WHOX) {
-                        let whox_params = if self.supports_account_notify {
+                    if self.core.isupport().contains_key(&isupport::Kind::WHOX)
+                    {
+                        let whox_params = if supports_account_notify {
                             WhoXPollParameters::WithAccountName
                         } else {
                             WhoXPollParameters::Default
@@ -3252,6 +4066,38 @@ impl Client {
             }
         }
 
+        if self.core.is_registered() && self.supports_monitor() {
+            self.sync_monitored_queries();
+        }
+
+        // Poll with ISON when the server doesn't support MONITOR
+        if self.core.is_registered()
+            && !self.supports_monitor()
+            && !self.ison_requested
+            && self.ison_polled_at.is_none_or(|polled_at| {
+                now.duration_since(polled_at) >= ISON_POLL_INTERVAL
+            })
+        {
+            let targets = self
+                .monitored
+                .iter()
+                .chain(&self.tracked_queries)
+                .unique()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>();
+
+            if !targets.is_empty() {
+                self.ison_polled_at = Some(now);
+                self.ison_requested = true;
+
+                self.send(
+                    None,
+                    proto::command("ISON", targets).into(),
+                    TokenPriority::Low,
+                );
+            }
+        }
+
         self.mode_requests.retain(|mode_request| {
             if let ModeStatus::Requested(instant)
             | ModeStatus::Received(instant) = mode_request.status
@@ -3298,23 +4144,162 @@ impl Client {
     }
 
     pub fn casemapping(&self) -> isupport::CaseMap {
-        isupport::get_casemapping_or_default(&self.isupport)
+        isupport::get_casemapping_or_default(self.core.isupport())
     }
 
     pub fn chanmodes(&self) -> &[isupport::ModeKind] {
-        isupport::get_chanmodes_or_default(&self.isupport)
+        isupport::get_chanmodes_or_default(self.core.isupport())
+    }
+
+    /// Re-requests the names of all joined channels, silently, so access
+    /// levels are re-derived after the server changes PREFIX.
+    fn refresh_names(&mut self) {
+        if !self.core.is_registered() {
+            return;
+        }
+
+        let channels = self
+            .chanmap
+            .iter_mut()
+            .map(|(channel, state)| {
+                state.names_init = false;
+                channel.to_string()
+            })
+            .collect::<Vec<_>>();
+
+        for channel in channels {
+            self.send(
+                None,
+                command!("NAMES", channel).into(),
+                TokenPriority::Low,
+            );
+        }
+    }
+
+    /// Normalizes tracked targets again after `CASEMAPPING` changed, merging
+    /// those whose normalized names now collide.
+    ///
+    /// Channel users are refreshed with `NAMES`, since their nicks were
+    /// normalized with the previous casemapping.
+    fn renormalize_targets(&mut self) -> Vec<Event> {
+        let casemapping = self.casemapping();
+        let mut channel_keys_updated = false;
+
+        let renormalized = |mut channel: target::Channel| {
+            channel.renormalize(casemapping);
+            channel
+        };
+
+        let mut chanmap: IndexMap<target::Channel, Channel> = IndexMap::new();
+
+        for (channel, state) in std::mem::take(&mut self.chanmap) {
+            let stale = channel.as_normalized_str().to_string();
+            let channel = renormalized(channel);
+
+            if channel.as_normalized_str() != stale
+                && let Some(key) = self.channel_keys.remove(&stale)
+            {
+                self.channel_keys
+                    .entry(channel.as_normalized_str().to_string())
+                    .or_insert(key);
+
+                channel_keys_updated = true;
+            }
+
+            // The first channel wins, its users are refreshed below anyway
+            chanmap.entry(channel).or_insert(state);
+        }
+
+        chanmap.sort_by(|a, _, b, _| {
+            self.compare_channels(a.as_normalized_str(), b.as_normalized_str())
+        });
+        self.chanmap = chanmap;
+
+        self.resolved_queries = std::mem::take(&mut self.resolved_queries)
+            .into_iter()
+            .map(|mut query| {
+                query.renormalize(casemapping);
+                query
+            })
+            .collect();
+
+        self.pending_channel_keys =
+            std::mem::take(&mut self.pending_channel_keys)
+                .into_iter()
+                .map(|(channel, key)| (renormalized(channel), key))
+                .collect();
+
+        self.registration_required_channels =
+            std::mem::take(&mut self.registration_required_channels)
+                .into_iter()
+                .map(renormalized)
+                .unique()
+                .collect();
+
+        for who_poll in &mut self.who_polls {
+            who_poll.channel.renormalize(casemapping);
+        }
+        let mut polled = HashSet::new();
+        self.who_polls
+            .retain(|who_poll| polled.insert(who_poll.channel.clone()));
+
+        for mode_request in &mut self.mode_requests {
+            mode_request.channel.renormalize(casemapping);
+        }
+
+        self.chathistory_exhausted = renormalize_keys(
+            std::mem::take(&mut self.chathistory_exhausted),
+            casemapping,
+        );
+        self.chathistory_requests = renormalize_keys(
+            std::mem::take(&mut self.chathistory_requests),
+            casemapping,
+        );
+
+        for nick in &mut self.monitored {
+            nick.renormalize(casemapping);
+        }
+        self.ison_online = std::mem::take(&mut self.ison_online)
+            .into_iter()
+            .map(|mut nick| {
+                nick.renormalize(casemapping);
+                nick
+            })
+            .collect();
+        self.monitored_queries = std::mem::take(&mut self.monitored_queries)
+            .into_iter()
+            .map(|mut nick| {
+                nick.renormalize(casemapping);
+                nick
+            })
+            .collect();
+        self.presence = std::mem::take(&mut self.presence)
+            .into_iter()
+            .map(|(mut nick, is_online)| {
+                nick.renormalize(casemapping);
+                (nick, is_online)
+            })
+            .collect();
+
+        self.refresh_names();
+
+        if channel_keys_updated {
+            vec![Event::ChannelKeysUpdated]
+        } else {
+            vec![]
+        }
     }
 
     pub fn chantypes(&self) -> &[char] {
-        isupport::get_chantypes_or_default(&self.isupport)
+        isupport::get_chantypes_or_default(self.core.isupport())
     }
 
     pub fn prefix(&self) -> &[isupport::PrefixMap] {
-        isupport::get_prefix_or_default(&self.isupport)
+        isupport::get_prefix_or_default(self.core.isupport())
     }
 
     pub fn statusmsg(&self) -> &[char] {
-        isupport::get_statusmsg_or_default(&self.isupport)
+        isupport::get_statusmsg_or_default(self.core.isupport())
     }
 
     pub fn is_channel(&self, target: &str) -> bool {
@@ -3393,6 +4378,45 @@ pub async fn overwrite_chathistory_targets_timestamp(
     Ok(())
 }
 
+async fn channel_keys_path(server: &Server) -> Result<PathBuf, Error> {
+    let data_dir = environment::data_dir();
+
+    let keys_dir = data_dir.join("channel-keys");
+
+    if !keys_dir.exists() {
+        fs::create_dir_all(&keys_dir).await?;
+    }
+
+    let hashed_server = seahash::hash(format!("{server}").as_bytes());
+
+    Ok(keys_dir.join(format!("{hashed_server}.json")))
+}
+
+pub async fn load_channel_keys(
+    server: Server,
+) -> Result<HashMap<String, String>, Error> {
+    let path = channel_keys_path(&server).await?;
+
+    if let Ok(bytes) = fs::read(path).await {
+        Ok(serde_json::from_slice(&bytes).unwrap_or_default())
+    } else {
+        Ok(HashMap::new())
+    }
+}
+
+async fn save_channel_keys(
+    server: Server,
+    keys: HashMap<String, String>,
+) -> Result<(), Error> {
+    let bytes = serde_json::to_vec(&keys)?;
+
+    let path = channel_keys_path(&server).await?;
+
+    fs::write(path, &bytes).await?;
+
+    Ok(())
+}
+
 #[derive(Debug)]
 enum HighlightNotificationBlackout {
     Blackout(Instant),
@@ -3420,119 +4444,372 @@ impl Map {
         self.0.insert(server, State::Disconnected);
     }
 
-    pub fn ready(&mut self, server: Server, client: Client) {
-        self.0.insert(server, State::Ready(client));
+    pub fn ready(&mut self, server: Server, client: Client) {
+        self.0.insert(server, State::Ready(client));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn remove(&mut self, server: &Server) -> Option<Client> {
+        self.0.remove(server).and_then(|state| match state {
+            State::Disconnected => None,
+            State::Ready(client) => Some(client),
+        })
+    }
+
+    pub fn client(&self, server: &Server) -> Option<&Client> {
+        if let Some(State::Ready(client)) = self.0.get(server) {
+            Some(client)
+        } else {
+            None
+        }
+    }
+
+    pub fn client_mut(&mut self, server: &Server) -> Option<&mut Client> {
+        if let Some(State::Ready(client)) = self.0.get_mut(server) {
+            Some(client)
+        } else {
+            None
+        }
+    }
+
+    pub fn nickname<'a>(&'a self, server: &Server) -> Option<NickRef<'a>> {
+        self.client(server).map(Client::nickname)
+    }
+
+    pub fn receive(
+        &mut self,
+        server: &Server,
+        message: message::Encoded,
+        ctcp_config: &config::Ctcp,
+    ) -> Result<Vec<Event>> {
+        if let Some(client) = self.client_mut(server) {
+            client.receive(message, ctcp_config)
+        } else {
+            Ok(Vec::default())
+        }
+    }
+
+    pub fn send(
+        &mut self,
+        buffer: &buffer::Upstream,
+        message: message::Encoded,
+        priority: TokenPriority,
+    ) {
+        if let Some(client) = self.client_mut(buffer.server()) {
+            client.send(Some(buffer), message, priority);
+        }
+    }
+
+    pub fn send_markread(
+        &mut self,
+        server: &Server,
+        target: Target,
+        read_marker: ReadMarker,
+        priority: TokenPriority,
+    ) {
+        if let Some(client) = self.client_mut(server) {
+            client.send_markread(target, read_marker, priority);
+        }
+    }
+
+    pub fn join(&mut self, server: &Server, channels: &[target::Channel]) {
+        if let Some(client) = self.client_mut(server) {
+            client.join(channels);
+        }
+    }
+
+    /// Quits `server`, returning the stream of its `on_disconnect` commands
+    /// which sends the QUIT if there are any.
+    pub fn quit(
+        &mut self,
+        server: &Server,
+        reason: Option<String>,
+    ) -> Option<on_connect::Stream> {
+        self.client_mut(server)
+            .and_then(|client| client.quit(reason))
+    }
+
+    /// Quits every connected server, returning them along with the streams
+    /// of their `on_disconnect` commands.
+    pub fn exit(
+        &mut self,
+    ) -> (HashSet<Server>, Vec<(Server, on_connect::Stream)>) {
+        let mut servers = HashSet::new();
+        let mut streams = vec![];
+
+        for (server, state) in &mut self.0 {
+            if let State::Ready(client) = state {
+                if let Some(stream) = client.quit(None) {
+                    streams.push((server.clone(), stream));
+                }
+
+                servers.insert(server.clone());
+            }
+        }
+
+        (servers, streams)
+    }
+
+    pub fn resolve_user_attributes<'a>(
+        &'a self,
+        server: &Server,
+        channel: &target::Channel,
+        user: &User,
+    ) -> Option<&'a User> {
+        self.client(server)
+            .and_then(|client| client.resolve_user_attributes(channel, user))
+    }
+
+    pub fn get_channel_users(
+        &self,
+        server: &Server,
+        channel: &target::Channel,
+    ) -> Option<&ChannelUsers> {
+        self.client(server).and_then(|client| client.users(channel))
+    }
+
+    /// Users of `channel` as shown in the nicklist. Unlike
+    /// [`Map::get_channel_users`] this only follows changes to the channel's
+    /// users once [`Map::refresh_nicklists`] is called.
+    pub fn get_nicklist(
+        &self,
+        server: &Server,
+        channel: &target::Channel,
+    ) -> Option<&[User]> {
+        self.client(server)
+            .and_then(|client| client.nicklist(channel))
+    }
+
+    /// Whether the users of any channel changed since the last refresh
+    pub fn has_stale_nicklists(&self) -> bool {
+        self.0.values().any(|client| {
+            matches!(client, State::Ready(client) if client.has_stale_nicklists())
+        })
+    }
+
+    /// Brings nicklists up to date with their channel's users. Called at most
+    /// every [`NICKLIST_REFRESH_INTERVAL`], so a flood of joins, parts or
+    /// names replies is laid out once rather than for every message.
+    pub fn refresh_nicklists(&mut self) {
+        for client in self.0.values_mut() {
+            if let State::Ready(client) = client {
+                client.refresh_nicklists();
+            }
+        }
+    }
+
+    pub fn resolve_hostmask(
+        &self,
+        server: &Server,
+        nick: NickRef,
+    ) -> Option<String> {
+        self.client(server).and_then(|client| client.hostmask(nick))
+    }
+
+    pub fn request_hostmask(&mut self, server: &Server, nick: Nick) {
+        if let Some(client) = self.client_mut(server) {
+            client.request_hostmask(nick);
+        }
+    }
+
+    pub fn oper(
+        &mut self,
+        buffer: &buffer::Upstream,
+        name: Option<String>,
+        password: Option<String>,
+    ) -> Result<(), OperError> {
+        if let Some(client) = self.client_mut(buffer.server()) {
+            client.oper(buffer, name, password)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn request_access_list(
+        &mut self,
+        server: &Server,
+        channel: &target::Channel,
+    ) {
+        if let Some(client) = self.client_mut(server) {
+            client.request_access_list(channel);
+        }
+    }
+
+    pub fn add_access(
+        &mut self,
+        server: &Server,
+        channel: &target::Channel,
+        mask: &str,
+        flags: &str,
+    ) {
+        if let Some(client) = self.client_mut(server) {
+            client.add_access(channel, mask, flags);
+        }
+    }
+
+    pub fn remove_access(
+        &mut self,
+        server: &Server,
+        channel: &target::Channel,
+        mask: &str,
+    ) {
+        if let Some(client) = self.client_mut(server) {
+            client.remove_access(channel, mask);
+        }
     }
 
-    pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+    pub fn access_list(
+        &self,
+        server: &Server,
+        channel: &target::Channel,
+    ) -> Option<&access_list::AccessList> {
+        self.client(server)
+            .and_then(|client| client.access_lists.get(channel))
     }
 
-    pub fn remove(&mut self, server: &Server) -> Option<Client> {
-        self.0.remove(server).and_then(|state| match state {
-            State::Disconnected => None,
-            State::Ready(client) => Some(client),
+    pub fn is_access_list_pending(
+        &self,
+        server: &Server,
+        channel: &target::Channel,
+    ) -> bool {
+        self.client(server).is_some_and(|client| {
+            client.access_lists.is_pending(channel, Instant::now())
         })
     }
 
-    pub fn client(&self, server: &Server) -> Option<&Client> {
-        if let Some(State::Ready(client)) = self.0.get(server) {
-            Some(client)
-        } else {
-            None
+    pub fn request_admin(&mut self, server: &Server, query: admin::Query) {
+        if let Some(client) = self.client_mut(server) {
+            client.request_admin(query);
         }
     }
 
-    pub fn client_mut(&mut self, server: &Server) -> Option<&mut Client> {
-        if let Some(State::Ready(client)) = self.0.get_mut(server) {
-            Some(client)
-        } else {
-            None
-        }
+    pub fn admin_report(
+        &self,
+        server: &Server,
+        query: admin::Query,
+    ) -> Option<&admin::Report> {
+        self.client(server)
+            .and_then(|client| client.admin_reports.get(query))
     }
 
-    pub fn nickname<'a>(&'a self, server: &Server) -> Option<NickRef<'a>> {
-        self.client(server).map(Client::nickname)
+    pub fn account(&self, server: &Server) -> Option<&str> {
+        self.client(server)
+            .and_then(|client| client.account.as_deref())
     }
 
-    pub fn receive(
-        &mut self,
-        server: &Server,
-        message: message::Encoded,
-        ctcp_config: &config::Ctcp,
-    ) -> Result<Vec<Event>> {
-        if let Some(client) = self.client_mut(server) {
-            client.receive(message, ctcp_config)
-        } else {
-            Ok(Vec::default())
+    pub fn sasl_error(&self, server: &Server) -> Option<&str> {
+        self.client(server)
+            .and_then(|client| client.core.sasl_error())
+    }
+
+    pub fn can_reauthenticate(&self, server: &Server) -> bool {
+        self.client(server).is_some_and(Client::can_reauthenticate)
+    }
+
+    /// Sets the open queries of every server, see [`Client::track_queries`].
+    pub fn track_queries(&mut self, queries: &[(Server, target::Query)]) {
+        for (server, state) in &mut self.0 {
+            if let State::Ready(client) = state {
+                client.track_queries(
+                    queries
+                        .iter()
+                        .filter(|(query_server, _)| query_server == server)
+                        .map(|(_, query)| query),
+                );
+            }
         }
     }
 
-    pub fn send(
+    /// Sets the open channels of every server, see
+    /// [`Client::set_visible_channels`].
+    pub fn set_visible_channels(
         &mut self,
-        buffer: &buffer::Upstream,
-        message: message::Encoded,
-        priority: TokenPriority,
+        channels: &[(Server, target::Channel)],
     ) {
-        if let Some(client) = self.client_mut(buffer.server()) {
-            client.send(Some(buffer), message, priority);
+        for (server, state) in &mut self.0 {
+            if let State::Ready(client) = state {
+                client.set_visible_channels(
+                    channels
+                        .iter()
+                        .filter(|(channel_server, _)| channel_server == server)
+                        .map(|(_, channel)| channel),
+                );
+            }
         }
     }
 
-    pub fn send_markread(
-        &mut self,
+    pub fn query_presence(
+        &self,
         server: &Server,
-        target: Target,
-        read_marker: ReadMarker,
-        priority: TokenPriority,
-    ) {
-        if let Some(client) = self.client_mut(server) {
-            client.send_markread(target, read_marker, priority);
-        }
+        query: &target::Query,
+    ) -> Option<bool> {
+        self.client(server)
+            .and_then(|client| client.query_presence(query))
     }
 
-    pub fn join(&mut self, server: &Server, channels: &[target::Channel]) {
+    /// Our hostname as seen by other users, and whether it's cloaked.
+    pub fn visible_host(&self, server: &Server) -> Option<(&str, bool)> {
+        self.client(server).and_then(|client| {
+            client
+                .visible_host
+                .as_deref()
+                .map(|host| (host, client.host_hidden))
+        })
+    }
+
+    pub fn lag(&self, server: &Server) -> Option<Duration> {
+        self.client(server).and_then(|client| client.lag)
+    }
+
+    pub fn set_lag(&mut self, server: &Server, lag: Duration) {
         if let Some(client) = self.client_mut(server) {
-            client.join(channels);
+            client.lag = Some(lag);
         }
     }
 
-    pub fn quit(&mut self, server: &Server, reason: Option<String>) {
+    pub fn reauthenticate(&mut self, server: &Server) {
         if let Some(client) = self.client_mut(server) {
-            client.quit(reason);
+            client.reauthenticate();
         }
     }
 
-    pub fn exit(&mut self) -> HashSet<Server> {
-        self.0
-            .iter_mut()
-            .filter_map(|(server, state)| {
-                if let State::Ready(client) = state {
-                    client.quit(None);
-                    Some(server.clone())
-                } else {
-                    None
-                }
-            })
-            .collect()
+    /// The server's oper config, if oper actions are enabled and we're
+    /// currently opered up.
+    pub fn oper_actions(
+        &self,
+        server: &Server,
+    ) -> Option<&config::server::Oper> {
+        self.client(server).and_then(Client::oper_actions)
     }
 
-    pub fn resolve_user_attributes<'a>(
-        &'a self,
-        server: &Server,
-        channel: &target::Channel,
-        user: &User,
-    ) -> Option<&'a User> {
+    pub fn is_oper(&self, server: &Server) -> bool {
+        self.client(server).is_some_and(|client| client.is_oper)
+    }
+
+    pub fn is_monitored(&self, server: &Server, nick: NickRef) -> bool {
         self.client(server)
-            .and_then(|client| client.resolve_user_attributes(channel, user))
+            .is_some_and(|client| client.is_monitored(nick))
     }
 
-    pub fn get_channel_users(
-        &self,
-        server: &Server,
-        channel: &target::Channel,
-    ) -> Option<&ChannelUsers> {
-        self.client(server).and_then(|client| client.users(channel))
+    pub fn set_monitored(&mut self, server: &Server, nicks: &[String]) {
+        if let Some(client) = self.client_mut(server) {
+            client.set_monitored(nicks);
+        }
+    }
+
+    pub fn monitor(&mut self, server: &Server, nick: Nick) {
+        if let Some(client) = self.client_mut(server) {
+            client.monitor(nick);
+        }
+    }
+
+    pub fn unmonitor(&mut self, server: &Server, nick: NickRef) {
+        if let Some(client) = self.client_mut(server) {
+            client.unmonitor(nick);
+        }
     }
 
     pub fn get_user_channels(
@@ -3555,16 +4832,26 @@ impl Map {
             .unwrap_or_default()
     }
 
-    pub fn get_channel_mode<'a>(
+    pub fn get_channel_metadata<'a>(
         &'a self,
         server: &Server,
         channel: &target::Channel,
-    ) -> Option<&'a String> {
+    ) -> Option<&'a Metadata> {
         self.client(server)
-            .map(|client| client.mode(channel))
+            .map(|client| client.metadata(channel))
             .unwrap_or_default()
     }
 
+    pub fn is_topic_protected(
+        &self,
+        server: &Server,
+        channel: &target::Channel,
+    ) -> Option<bool> {
+        self.client(server)
+            .and_then(|client| client.chanmap.get(channel))
+            .and_then(Channel::is_topic_protected)
+    }
+
     pub fn get_channels<'a>(
         &'a self,
         server: &Server,
@@ -3598,7 +4885,7 @@ impl Map {
         server: &Server,
     ) -> HashMap<isupport::Kind, isupport::Parameter> {
         self.client(server)
-            .map(|client| client.isupport.clone())
+            .map(|client| client.core.isupport().clone())
             .unwrap_or_default()
     }
 
@@ -3632,6 +4919,16 @@ impl Map {
             .unwrap_or_default()
     }
 
+    /// Categories of server traffic split out of the server buffer
+    pub fn get_sub_buffers<'a>(
+        &'a self,
+        server: &Server,
+    ) -> &'a [target::Category] {
+        self.client(server)
+            .map(|client| client.config.sub_buffers.as_slice())
+            .unwrap_or_default()
+    }
+
     pub fn get_prefix<'a>(
         &'a self,
         server: &Server,
@@ -3663,7 +4960,7 @@ impl Map {
 
     pub fn get_server_supports_chathistory(&self, server: &Server) -> bool {
         self.client(server)
-            .is_some_and(|client| client.supports_chathistory)
+            .is_some_and(Client::supports_chathistory)
     }
 
     pub fn get_chathistory_request(
@@ -3711,7 +5008,7 @@ impl Map {
         target: &Target,
     ) -> Option<ChatHistoryState> {
         self.client(server).and_then(|client| {
-            if client.supports_chathistory {
+            if client.supports_chathistory() {
                 if client.chathistory_request(target).is_some() {
                     Some(ChatHistoryState::PendingRequest)
                 } else if client.chathistory_exhausted(target) {
@@ -3745,6 +5042,13 @@ impl Map {
         })
     }
 
+    pub fn save_channel_keys(
+        &self,
+        server: &Server,
+    ) -> Option<impl Future<Output = Message> + use<>> {
+        self.client(server).map(Client::save_channel_keys)
+    }
+
     pub fn get_server_supports_detach(&self, server: &Server) -> bool {
         self.client(server)
             .is_some_and(|client| client.supports_detach)
@@ -3870,6 +5174,8 @@ pub struct Batch {
     context: Option<Context>,
     events: Vec<Event>,
     chathistory: Option<ChatHistoryBatch>,
+    playback: Option<Target>,
+    kind: Option<message::Batch>,
 }
 
 impl Batch {
@@ -3878,31 +5184,107 @@ impl Batch {
             context,
             events: vec![],
             chathistory: None,
+            playback: None,
+            kind: None,
+        }
+    }
+}
+
+/// Tags the messages of a finished batch with its kind (unless already tagged
+/// by a nested batch), and keeps messages from history from triggering
+/// notifications.
+fn tag_batch(events: &mut Vec<Event>, kind: message::Batch) {
+    let is_history = matches!(kind, message::Batch::History);
+
+    if is_history {
+        events.retain(|event| !matches!(event, Event::DirectMessage(..)));
+    }
+
+    for event in events {
+        match event {
+            Event::PrivOrNotice(encoded, _, highlight_notification_enabled) => {
+                tag_batch_kind(encoded, kind);
+
+                if is_history {
+                    *highlight_notification_enabled = false;
+                }
+            }
+            Event::Single(encoded, _)
+            | Event::WithTarget(encoded, _, _)
+            | Event::DirectMessage(encoded, _, _) => {
+                tag_batch_kind(encoded, kind);
+            }
+            Event::Broadcast(Broadcast::Quit { batch, .. }) => {
+                batch.get_or_insert(kind);
+            }
+            _ => {}
         }
     }
 }
 
+// Messages carry the batch reference in their tag, which is replaced with the
+// batch kind unless a nested batch already did so
+fn tag_batch_kind(encoded: &mut message::Encoded, kind: message::Batch) {
+    let tag = encoded.tags.entry("batch".to_string()).or_default();
+
+    if message::Batch::parse(tag).is_none() {
+        *tag = kind.as_str().to_string();
+    }
+}
+
+/// The range of messages backfilled into `target` by a finished batch.
+fn backfilled(target: &Target, events: &[Event]) -> Option<Event> {
+    let (start, end) = events
+        .iter()
+        .filter_map(|event| match event {
+            Event::Single(message, _)
+            | Event::PrivOrNotice(message, _, _)
+            | Event::WithTarget(message, _, _) => Some(server_time(message)),
+            _ => None,
+        })
+        .minmax()
+        .into_option()?;
+
+    Some(Event::Backfilled(target.clone(), start, end))
+}
+
 fn generate_label() -> String {
     Posix::now().as_nanos().to_string()
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-enum RegistrationStep {
-    Start,
-    List,
-    Req,
-    Sasl,
-    End,
-    Complete,
+/// Target and text of a plain message the user typed, along with the
+/// command sending it through a channel. CTCP queries, DCC offers among them,
+/// and automated messages are sent as is.
+fn typed_message<'a>(
+    command: &'a Command,
+    priority: &TokenPriority,
+) -> Option<(&'a String, &'a String, isupport::Kind)> {
+    if !matches!(priority, TokenPriority::User) {
+        return None;
+    }
+
+    let (target, text, kind) = match command {
+        Command::PRIVMSG(target, text) => {
+            (target, text, isupport::Kind::CPRIVMSG)
+        }
+        Command::NOTICE(target, text) => {
+            (target, text, isupport::Kind::CNOTICE)
+        }
+        _ => return None,
+    };
+
+    (!ctcp::is_query(text)).then_some((target, text, kind))
 }
 
 #[derive(Debug, Default)]
 pub struct Channel {
     pub users: ChannelUsers,
+    /// `users` as of the last [`Map::refresh_nicklists`]
+    pub nicklist: Vec<User>,
     pub topic: Topic,
     pub names_init: bool,
     pub who_init: bool,
-    pub mode: Option<String>,
+    pub metadata: Metadata,
 }
 
 impl Channel {
@@ -3929,6 +5311,12 @@ impl Channel {
         }
     }
 
+    /// Whether only privileged users may set the topic (`+t`), if the channel
+    /// modes are known
+    pub fn is_topic_protected(&self) -> Option<bool> {
+        self.metadata.has_mode('t')
+    }
+
     pub fn update_user_accountname(
         &mut self,
         user: &str,
@@ -3950,6 +5338,136 @@ pub struct Topic {
     pub time: Option<DateTime<Utc>>,
 }
 
+/// Channel state reported by the server, kept up to date with MODE changes
+#[derive(Default, Debug, Clone)]
+pub struct Metadata {
+    /// Modes set on the channel with their argument (if any), in the order
+    /// they were set. List modes (e.g. bans) and membership prefixes (e.g.
+    /// op) are not included. `None` until RPL_CHANNELMODEIS is received.
+    modes: Option<Vec<(char, Option<String>)>>,
+    /// When the channel was created, from RPL_CREATIONTIME
+    pub created: Option<DateTime<Utc>>,
+}
+
+impl Metadata {
+    pub fn modes(&self) -> Option<&[(char, Option<String>)]> {
+        self.modes.as_deref()
+    }
+
+    /// Whether `mode` is set, if the channel modes are known
+    pub fn has_mode(&self, mode: char) -> Option<bool> {
+        self.modes
+            .as_ref()
+            .map(|modes| modes.iter().any(|(set, _)| *set == mode))
+    }
+
+    fn arg(&self, mode: char) -> Option<&str> {
+        self.modes
+            .as_ref()?
+            .iter()
+            .find(|(set, _)| *set == mode)
+            .and_then(|(_, arg)| arg.as_deref())
+    }
+
+    /// Maximum number of users (`+l`)
+    pub fn limit(&self) -> Option<u32> {
+        self.arg('l').and_then(|limit| limit.parse().ok())
+    }
+
+    /// Key required to join (`+k`)
+    pub fn key(&self) -> Option<&str> {
+        self.arg('k')
+    }
+
+    /// The set modes without their arguments, e.g. `+ntl`
+    pub fn mode_string(&self) -> Option<String> {
+        let modes = self.modes.as_ref()?;
+
+        Some(if modes.is_empty() {
+            String::new()
+        } else {
+            std::iter::once('+')
+                .chain(modes.iter().map(|(mode, _)| *mode))
+                .collect()
+        })
+    }
+
+    /// Replaces all modes, as listed by RPL_CHANNELMODEIS
+    fn set_modes(&mut self, modes: Vec<mode::Mode<char>>) {
+        self.modes = Some(vec![]);
+
+        for mode in modes {
+            self.apply(mode);
+        }
+    }
+
+    /// Applies a mode change. Changes are ignored until the modes are known,
+    /// since they would only describe part of the channel's modes.
+    fn apply(&mut self, mode: mode::Mode<char>) {
+        let Some(modes) = self.modes.as_mut() else {
+            return;
+        };
+
+        match mode {
+            mode::Mode::Add(mode, arg) => {
+                if let Some(set) =
+                    modes.iter_mut().find(|(set, _)| *set == mode)
+                {
+                    set.1 = arg;
+                } else {
+                    modes.push((mode, arg));
+                }
+            }
+            mode::Mode::Remove(mode, _) => {
+                modes.retain(|(set, _)| *set != mode);
+            }
+            mode::Mode::NoPrefix(_) => {}
+        }
+    }
+}
+
+/// Modes describing the channel itself, i.e. without list modes (e.g.
+/// bans) and membership prefixes (e.g. op)
+fn parse_channel_modes(
+    modes: &str,
+    args: &[String],
+    chanmodes: &[isupport::ModeKind],
+    prefix: &[isupport::PrefixMap],
+) -> Vec<mode::Mode<char>> {
+    mode::parse::<char>(modes, args, chanmodes, prefix)
+        .into_iter()
+        .filter(|mode| {
+            let mode = *mode.value();
+
+            prefix.iter().all(|prefix_map| prefix_map.prefix != mode)
+                && !chanmodes.iter().any(|chanmode| {
+                    chanmode.kind == 'A' && chanmode.modes.contains(mode)
+                })
+        })
+        .collect()
+}
+
+/// Position of the WHO poll to move to the front of `who_polls`, if the
+/// front is waiting on a channel which isn't `wanted`. A single WHO is in
+/// flight at a time, so such channels are skipped over instead of holding up
+/// the queue.
+fn next_wanted_who_poll(
+    who_polls: &VecDeque<WhoPoll>,
+    wanted: impl Fn(&target::Channel) -> bool,
+) -> Option<usize> {
+    let front = who_polls.front()?;
+
+    if !matches!(front.status, WhoStatus::Waiting(_)) || wanted(&front.channel)
+    {
+        return None;
+    }
+
+    who_polls.iter().position(|who_poll| {
+        !matches!(who_poll.status, WhoStatus::Waiting(_))
+            || wanted(&who_poll.channel)
+    })
+}
+
 #[derive(Debug, Clone)]
 pub struct WhoPoll {
     pub channel: target::Channel,
@@ -3984,28 +5502,6 @@ pub enum ModeStatus {
     Received(Instant),
 }
 
-fn group_capability_requests<'a>(
-    capabilities: &'a [&'a str],
-) -> impl Iterator<Item = proto::Message> + 'a {
-    const MAX_LEN: usize = proto::format::BYTE_LIMIT - b"CAP REQ :\r\n".len();
-
-    capabilities
-        .iter()
-        .scan(0, |count, capability| {
-            // Capability + a space
-            *count += capability.len() + 1;
-
-            let chunk = *count / MAX_LEN;
-
-            Some((chunk, capability))
-        })
-        .into_group_map()
-        .into_values()
-        .map(|capabilities| {
-            command!("CAP", "REQ", capabilities.into_iter().join(" "))
-        })
-}
-
 /// Group channels together into as few JOIN messages as possible
 fn group_joins<'a>(
     channels: &'a [target::Channel],
@@ -4083,6 +5579,18 @@ fn group_monitors(
     .map(|targets| command!("MONITOR", "+", targets.into_iter().join(",")))
 }
 
+fn renormalize_keys<V>(
+    map: HashMap<Target, V>,
+    casemapping: isupport::CaseMap,
+) -> HashMap<Target, V> {
+    map.into_iter()
+        .map(|(mut target, value)| {
+            target.renormalize(casemapping);
+            (target, value)
+        })
+        .collect()
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -4096,3 +5604,170 @@ pub enum Error {
     #[error(transparent)]
     BouncerNetwork(#[from] bouncer::Error),
 }
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum OperError {
+    #[error("no operator name given or configured")]
+    MissingName,
+    #[error("no operator password given or configured")]
+    MissingPassword,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_message_via_channel() {
+        let privmsg = Command::PRIVMSG("dan".into(), "hello".into());
+        let notice = Command::NOTICE("dan".into(), "hello".into());
+
+        assert!(matches!(
+            typed_message(&privmsg, &TokenPriority::User),
+            Some((_, _, isupport::Kind::CPRIVMSG))
+        ));
+        assert!(matches!(
+            typed_message(&notice, &TokenPriority::User),
+            Some((_, _, isupport::Kind::CNOTICE))
+        ));
+
+        // Automated messages
+        assert!(typed_message(&privmsg, &TokenPriority::High).is_none());
+        assert!(typed_message(&notice, &TokenPriority::Low).is_none());
+
+        // CTCP queries and replies, including DCC offers
+        for text in [
+            "\u{1}VERSION\u{1}",
+            "\u{1}ACTION waves\u{1}",
+            "\u{1}DCC SEND file.txt 2130706433 5000 1024\u{1}",
+        ] {
+            let privmsg = Command::PRIVMSG("dan".into(), text.into());
+            let notice = Command::NOTICE("dan".into(), text.into());
+
+            assert!(typed_message(&privmsg, &TokenPriority::User).is_none());
+            assert!(typed_message(&notice, &TokenPriority::User).is_none());
+        }
+
+        assert!(
+            typed_message(
+                &Command::JOIN("#halloy".into(), None),
+                &TokenPriority::User
+            )
+            .is_none()
+        );
+    }
+
+    fn modes(modes: &str, args: &[&str]) -> Vec<mode::Mode<char>> {
+        let args = args.iter().map(ToString::to_string).collect::<Vec<_>>();
+        let prefix = [
+            isupport::PrefixMap {
+                prefix: '@',
+                mode: 'o',
+            },
+            isupport::PrefixMap {
+                prefix: '+',
+                mode: 'v',
+            },
+        ];
+
+        parse_channel_modes(modes, &args, isupport::DEFAULT_CHANMODES, &prefix)
+    }
+
+    #[test]
+    fn channel_modes_skip_lists_and_memberships() {
+        assert_eq!(
+            modes("+ntlbo", &["50", "*!*@spam", "dan"]),
+            vec![
+                mode::Mode::Add('n', None),
+                mode::Mode::Add('t', None),
+                mode::Mode::Add('l', Some("50".to_string())),
+            ]
+        );
+        assert_eq!(
+            modes("-kv", &["secret", "dan"]),
+            vec![mode::Mode::Remove('k', Some("secret".to_string()))]
+        );
+    }
+
+    #[test]
+    fn channel_metadata_follows_mode_changes() {
+        let mut metadata = Metadata::default();
+
+        // Changes only describe part of the modes until they are listed
+        metadata.apply(mode::Mode::Add('m', None));
+        assert_eq!(metadata.has_mode('m'), None);
+        assert_eq!(metadata.mode_string(), None);
+
+        metadata.set_modes(modes("+ntk", &["secret"]));
+        assert_eq!(metadata.mode_string().as_deref(), Some("+ntk"));
+        assert_eq!(metadata.has_mode('t'), Some(true));
+        assert_eq!(metadata.key(), Some("secret"));
+        assert_eq!(metadata.limit(), None);
+
+        for mode in modes("+lo-t", &["50", "dan"]) {
+            metadata.apply(mode);
+        }
+        assert_eq!(metadata.mode_string().as_deref(), Some("+nkl"));
+        assert_eq!(metadata.has_mode('t'), Some(false));
+        assert_eq!(metadata.limit(), Some(50));
+
+        // A new limit replaces the previous one
+        for mode in modes("+l", &["100"]) {
+            metadata.apply(mode);
+        }
+        assert_eq!(metadata.mode_string().as_deref(), Some("+nkl"));
+        assert_eq!(metadata.limit(), Some(100));
+
+        metadata.set_modes(vec![]);
+        assert_eq!(metadata.mode_string().as_deref(), Some(""));
+        assert_eq!(metadata.key(), None);
+    }
+
+    #[test]
+    fn who_polls_skip_unwanted_channels() {
+        let poll = |channel: &str, status: WhoStatus| WhoPoll {
+            channel: target::Channel::from_str(
+                channel,
+                isupport::DEFAULT_CHANTYPES,
+                isupport::CaseMap::default(),
+            ),
+            status,
+        };
+        let waiting = || WhoStatus::Waiting(Instant::now());
+        let visible = |channel: &target::Channel| {
+            ["#halloy", "#rust"].contains(&channel.as_str())
+        };
+
+        let who_polls = VecDeque::from([
+            poll("#hidden", waiting()),
+            poll("#offscreen", waiting()),
+            poll("#rust", waiting()),
+            poll("#halloy", waiting()),
+        ]);
+        assert_eq!(next_wanted_who_poll(&who_polls, visible), Some(2));
+
+        // A wanted channel at the front is polled in turn
+        let who_polls = VecDeque::from([
+            poll("#halloy", waiting()),
+            poll("#hidden", waiting()),
+        ]);
+        assert_eq!(next_wanted_who_poll(&who_polls, visible), None);
+
+        // Channels which were just joined still get their initial WHO
+        let who_polls = VecDeque::from([
+            poll("#hidden", waiting()),
+            poll("#joined", WhoStatus::Joined),
+        ]);
+        assert_eq!(next_wanted_who_poll(&who_polls, visible), Some(1));
+
+        let who_polls = VecDeque::from([
+            poll("#hidden", WhoStatus::Joined),
+            poll("#rust", waiting()),
+        ]);
+        assert_eq!(next_wanted_who_poll(&who_polls, visible), None);
+
+        // Nothing wanted, so nothing to skip to
+        let who_polls = VecDeque::from([poll("#hidden", waiting())]);
+        assert_eq!(next_wanted_who_poll(&who_polls, visible), None);
+    }
+}
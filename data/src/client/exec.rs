@@ -0,0 +1,167 @@
+use std::fmt;
+use std::process::Stdio;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream::{self, BoxStream};
+use tokio::io::{AsyncBufReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdout};
+use tokio::time::{self, Instant};
+
+use crate::config;
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A line printed by the program
+    Output(String),
+    Finished(Result<(), Error>),
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum Error {
+    #[error("failed to run {0}: {1}")]
+    Spawn(String, String),
+    #[error("{0} didn't finish within {1} seconds")]
+    TimedOut(String, u64),
+    #[error("{0} printed more than {1} lines")]
+    TooManyLines(String, usize),
+    #[error("{0} exited with {1}")]
+    Failed(String, String),
+}
+
+pub struct Stream(BoxStream<'static, Event>);
+
+impl futures::Stream for Stream {
+    type Item = Event;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.0.poll_next_unpin(cx)
+    }
+}
+
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stream").finish()
+    }
+}
+
+enum State {
+    Starting,
+    Running {
+        child: Child,
+        lines: Lines<BufReader<ChildStdout>>,
+        printed: usize,
+    },
+    Done,
+}
+
+/// Runs `command` with the system shell, streaming each line it prints.
+///
+/// The program is stopped once it runs longer than the configured timeout
+/// or prints more than the configured number of lines.
+pub fn exec(command: String, config: &config::buffer::Exec) -> Stream {
+    let timeout = config.timeout;
+    let max_lines = config.max_lines;
+    let deadline = Instant::now() + Duration::from_secs(timeout);
+
+    Stream(
+        stream::unfold(State::Starting, move |state| {
+            let command = command.clone();
+
+            async move {
+                let finished =
+                    |result| Some((Event::Finished(result), State::Done));
+
+                let (mut child, mut lines, printed) = match state {
+                    // Spawned from within the stream, so it's on the runtime
+                    State::Starting => match spawn(&command) {
+                        Ok((child, stdout)) => {
+                            (child, BufReader::new(stdout).lines(), 0)
+                        }
+                        Err(error) => {
+                            return finished(Err(Error::Spawn(
+                                command,
+                                error.to_string(),
+                            )));
+                        }
+                    },
+                    State::Running {
+                        child,
+                        lines,
+                        printed,
+                    } => (child, lines, printed),
+                    State::Done => return None,
+                };
+
+                match time::timeout_at(deadline, lines.next_line()).await {
+                    Err(_) => finished(Err(Error::TimedOut(command, timeout))),
+                    Ok(Ok(Some(_))) if printed >= max_lines => {
+                        finished(Err(Error::TooManyLines(command, max_lines)))
+                    }
+                    Ok(Ok(Some(line))) => Some((
+                        Event::Output(line),
+                        State::Running {
+                            child,
+                            lines,
+                            printed: printed + 1,
+                        },
+                    )),
+                    Ok(Ok(None)) => {
+                        match time::timeout_at(deadline, child.wait()).await {
+                            Err(_) => {
+                                finished(Err(Error::TimedOut(command, timeout)))
+                            }
+                            Ok(Ok(status)) if status.success() => {
+                                finished(Ok(()))
+                            }
+                            Ok(Ok(status)) => finished(Err(Error::Failed(
+                                command,
+                                status.to_string(),
+                            ))),
+                            Ok(Err(error)) => finished(Err(Error::Failed(
+                                command,
+                                error.to_string(),
+                            ))),
+                        }
+                    }
+                    Ok(Err(error)) => {
+                        finished(Err(Error::Failed(command, error.to_string())))
+                    }
+                }
+            }
+        })
+        .boxed(),
+    )
+}
+
+fn spawn(command: &str) -> std::io::Result<(Child, ChildStdout)> {
+    #[cfg(windows)]
+    let mut process = {
+        let mut process = tokio::process::Command::new("cmd");
+        process.arg("/C").arg(command);
+        process
+    };
+    #[cfg(not(windows))]
+    let mut process = {
+        let mut process = tokio::process::Command::new("sh");
+        process.arg("-c").arg(command);
+        process
+    };
+
+    let mut child = process
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| std::io::Error::other("no output"))?;
+
+    Ok((child, stdout))
+}
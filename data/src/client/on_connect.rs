@@ -1,14 +1,20 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
 
+use futures::channel::mpsc;
 use futures::stream::{self, BoxStream};
 use futures::{SinkExt, StreamExt};
+use irc::proto;
 use tokio::time;
 
-use crate::user::NickRef;
-use crate::{Command, Target, command, config, isupport, message, server};
+use crate::config::buffer::ServerLimits;
+use crate::config::server::{Condition, OnConnect};
+use crate::user::Nick;
+use crate::{
+    Command, Server, Target, buffer, command, config, isupport, message, server,
+};
 
 #[derive(Debug)]
 pub enum Event {
@@ -35,66 +41,319 @@ impl fmt::Debug for Stream {
     }
 }
 
+/// Changes to our state on the server, sent by the client while the
+/// commands wait for conditions.
+#[derive(Debug, Clone)]
+pub enum Signal {
+    Nickname(Nick),
+    /// Our user modes changed, e.g. `+x` or `-i`
+    Modes(String),
+    LoggedIn,
+}
+
+/// Our state on the server, which conditions are checked against.
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub nickname: Nick,
+    pub modes: HashSet<char>,
+    pub logged_in: bool,
+}
+
+impl Status {
+    fn apply(&mut self, signal: Signal) {
+        match signal {
+            Signal::Nickname(nickname) => self.nickname = nickname,
+            Signal::Modes(modes) => apply_modes(&mut self.modes, &modes),
+            Signal::LoggedIn => self.logged_in = true,
+        }
+    }
+
+    fn is_met(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::MotdEnd => true,
+            Condition::LoggedIn => self.logged_in,
+            Condition::Mode { set, unset } => {
+                set.iter().all(|mode| self.modes.contains(mode))
+                    && !unset.iter().any(|mode| self.modes.contains(mode))
+            }
+        }
+    }
+}
+
+/// Applies mode changes such as `+x-i` to `modes`.
+pub fn apply_modes(modes: &mut HashSet<char>, changes: &str) {
+    let mut adding = true;
+
+    for c in changes.chars() {
+        match c {
+            '+' => adding = true,
+            '-' => adding = false,
+            c if adding => {
+                modes.insert(c);
+            }
+            c => {
+                modes.remove(&c);
+            }
+        }
+    }
+}
+
+enum Step {
+    Command {
+        command: Command,
+        if_nick: Option<Nick>,
+    },
+    Wait {
+        condition: Condition,
+        timeout: Duration,
+    },
+    Quit(Option<String>),
+}
+
+struct State {
+    server: String,
+    handle: server::Handle,
+    steps: VecDeque<Step>,
+    status: Status,
+    signals: mpsc::UnboundedReceiver<Signal>,
+}
+
+impl State {
+    /// Applies the signals received so far.
+    fn receive(&mut self) {
+        while let Ok(Some(signal)) = self.signals.try_next() {
+            self.status.apply(signal);
+        }
+    }
+
+    async fn wait_until(&mut self, condition: &Condition) {
+        self.receive();
+
+        while !self.status.is_met(condition) {
+            let Some(signal) = self.signals.next().await else {
+                return;
+            };
+
+            self.status.apply(signal);
+        }
+    }
+
+    async fn quit(&mut self, reason: Option<String>) {
+        let message = if let Some(reason) = reason {
+            proto::command!("QUIT", reason)
+        } else {
+            proto::command!("QUIT")
+        };
+
+        if let Err(e) = self.handle.send(message).await {
+            log::warn!("[{}] Error sending quit: {e}", self.server);
+        }
+    }
+
+    async fn execute(&mut self, command: Command) -> Option<Event> {
+        match command {
+            Command::Irc(command) => {
+                if let Ok(message) = message::Encoded::try_from(command)
+                    && let Err(e) = self.handle.send(message.into()).await
+                {
+                    log::warn!("Error sending message: {e}");
+                }
+                None
+            }
+            Command::Internal(cmd) => match cmd {
+                command::Internal::OpenBuffers(targets) => {
+                    Some(Event::OpenBuffers(targets))
+                }
+                command::Internal::LeaveBuffers(targets, reason) => {
+                    Some(Event::LeaveBuffers(targets, reason))
+                }
+                command::Internal::Detach(channels) => {
+                    Some(Event::LeaveBuffers(
+                        channels.into_iter().map(Target::Channel).collect(),
+                        Some("detach".to_string()),
+                    ))
+                }
+                command::Internal::Delay(seconds) => {
+                    time::sleep(Duration::from_secs(seconds)).await;
+                    None
+                }
+                // We don't handle hop, clear-buffer, sysinfo, oper, exec when called from connected.
+                command::Internal::ClearBuffer
+                | command::Internal::Hop(_, _)
+                | command::Internal::SysInfo
+                | command::Internal::Oper(_, _)
+                | command::Internal::Exec(_, _) => None,
+            },
+        }
+    }
+}
+
+/// Runs the `on_connect` commands, followed by the `on_reconnect` commands
+/// when the connection replaces one which dropped unexpectedly.
 pub fn on_connect(
+    server: &Server,
+    handle: server::Handle,
+    config: Arc<config::Server>,
+    status: Status,
+    signals: mpsc::UnboundedReceiver<Signal>,
+    isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+    is_reconnect: bool,
+) -> Stream {
+    let on_reconnect = if is_reconnect {
+        config.on_reconnect.as_slice()
+    } else {
+        &[]
+    };
+
+    let steps = steps(
+        server,
+        &config,
+        &status,
+        isupport,
+        config.on_connect.iter().chain(on_reconnect),
+    );
+
+    run(server, handle, steps, status, signals)
+}
+
+/// Runs the `on_disconnect` commands, then quits with `reason`. Nothing is
+/// waited for, so quitting isn't held up.
+pub fn on_disconnect(
+    server: &Server,
     handle: server::Handle,
     config: Arc<config::Server>,
-    our_nickname: NickRef,
+    status: Status,
     isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+    reason: Option<String>,
 ) -> Stream {
-    let commands = config
-        .on_connect
-        .iter()
-        .filter_map(|command| {
-            command::parse(command, None, Some(our_nickname), isupport).ok()
+    let mut steps = steps(
+        server,
+        &config,
+        &status,
+        isupport,
+        config
+            .on_disconnect
+            .iter()
+            .filter(|entry| !matches!(entry, OnConnect::WaitUntil { .. })),
+    );
+    steps.push_back(Step::Quit(reason));
+
+    // No signals are forwarded while quitting
+    let (_, signals) = mpsc::unbounded();
+
+    run(server, handle, steps, status, signals)
+}
+
+fn steps<'a>(
+    server: &Server,
+    config: &config::Server,
+    status: &Status,
+    isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+    entries: impl Iterator<Item = &'a OnConnect>,
+) -> VecDeque<Step> {
+    let casemapping = isupport::get_casemapping_or_default(isupport);
+    let our_nickname = status.nickname.as_nickref();
+    // Lets aliases refer to `$server`
+    let buffer = buffer::Upstream::Server(server.clone());
+
+    let parse = |command: &str| {
+        command::parse(
+            command,
+            Some(&buffer),
+            Some(our_nickname),
+            isupport,
+            &config.command_aliases,
+        )
+        .ok()
+        .map(|command| match command {
+            Command::Irc(command) => command::fit_to_limits(
+                command,
+                isupport,
+                ServerLimits::default(),
+            )
+            .map(|(commands, _)| {
+                commands.into_iter().map(Command::Irc).collect()
+            })
+            .unwrap_or_default(),
+            command => vec![command],
+        })
+        .unwrap_or_default()
+    };
+
+    entries
+        .flat_map(|entry| match entry {
+            OnConnect::Command(command) => parse(command)
+                .into_iter()
+                .map(|command| Step::Command {
+                    command,
+                    if_nick: None,
+                })
+                .collect(),
+            OnConnect::Conditional { command, if_nick } => parse(command)
+                .into_iter()
+                .map(|command| Step::Command {
+                    command,
+                    if_nick: Some(Nick::from_str(if_nick, casemapping)),
+                })
+                .collect(),
+            OnConnect::WaitUntil {
+                wait_until,
+                timeout,
+            } => vec![Step::Wait {
+                condition: wait_until.clone(),
+                timeout: Duration::from_secs(*timeout),
+            }],
         })
-        .collect::<Vec<_>>();
+        .collect()
+}
+
+fn run(
+    server: &Server,
+    handle: server::Handle,
+    steps: VecDeque<Step>,
+    status: Status,
+    signals: mpsc::UnboundedReceiver<Signal>,
+) -> Stream {
+    let state = State {
+        server: server.to_string(),
+        handle,
+        steps,
+        status,
+        signals,
+    };
 
     Stream(
-        stream::iter(commands)
-            .filter_map(move |command| {
-                let mut handle = handle.clone();
-
-                async move {
-                    match command {
-                        Command::Irc(command) => {
-                            if let Ok(message) =
-                                message::Encoded::try_from(command)
-                                && let Err(e) =
-                                    handle.send(message.into()).await
-                            {
-                                log::warn!("Error sending message: {e}");
-                            }
-                            None
+        stream::unfold(state, |mut state| async move {
+            loop {
+                match state.steps.pop_front()? {
+                    Step::Command { command, if_nick } => {
+                        state.receive();
+
+                        if if_nick.is_some_and(|nick| {
+                            nick != state.status.nickname
+                        }) {
+                            continue;
+                        }
+
+                        if let Some(event) = state.execute(command).await {
+                            return Some((event, state));
                         }
-                        Command::Internal(cmd) => match cmd {
-                            command::Internal::OpenBuffers(targets) => {
-                                Some(Event::OpenBuffers(targets))
-                            }
-                            command::Internal::LeaveBuffers(
-                                targets,
-                                reason,
-                            ) => Some(Event::LeaveBuffers(targets, reason)),
-                            command::Internal::Detach(channels) => {
-                                Some(Event::LeaveBuffers(
-                                    channels
-                                        .into_iter()
-                                        .map(Target::Channel)
-                                        .collect(),
-                                    Some("detach".to_string()),
-                                ))
-                            }
-                            command::Internal::Delay(seconds) => {
-                                time::sleep(Duration::from_secs(seconds)).await;
-                                None
-                            }
-                            // We don't handle hop, clear-buffer, sysinfo when called from connected.
-                            command::Internal::ClearBuffer
-                            | command::Internal::Hop(_, _)
-                            | command::Internal::SysInfo => None,
-                        },
                     }
+                    Step::Wait { condition, timeout } => {
+                        if time::timeout(timeout, state.wait_until(&condition))
+                            .await
+                            .is_err()
+                        {
+                            log::warn!(
+                                "[{}] on_connect timed out waiting for {condition}",
+                                state.server
+                            );
+                        }
+                    }
+                    Step::Quit(reason) => state.quit(reason).await,
                 }
-            })
-            .boxed(),
+            }
+        })
+        .boxed(),
     )
 }
@@ -6,8 +6,11 @@ use irc::proto;
 use itertools::Itertools;
 
 use crate::buffer::{self, Upstream};
+use crate::config::buffer::ServerLimits;
+use crate::config::{Aliases, alias};
 use crate::isupport::{self, find_target_limit};
 use crate::message::{self, formatting};
+use crate::target::{TargetList, TargetListError};
 use crate::user::NickRef;
 use crate::{Target, ctcp, target};
 
@@ -30,9 +33,20 @@ pub enum Internal {
     Delay(u64),
     SysInfo,
     Detach(Vec<target::Channel>),
+    /// Authenticate as an IRC operator, falling back to the server's
+    /// configured oper credentials.
+    ///
+    /// - Operator name
+    /// - Operator password
+    Oper(Option<String>, Option<String>),
+    /// Run a program, showing its output in the buffer.
+    ///
+    /// - Command line of the program
+    /// - Whether the output is sent to the buffer's target instead
+    Exec(String, bool),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Irc {
     Join(String, Option<String>),
     Motd(Option<String>),
@@ -77,6 +91,8 @@ enum Kind {
     ClearTopic,
     SysInfo,
     Detach,
+    Oper,
+    Exec,
     Raw,
 }
 
@@ -108,6 +124,8 @@ impl FromStr for Kind {
             "cleartopic" | "ct" => Ok(Kind::ClearTopic),
             "sysinfo" => Ok(Kind::SysInfo),
             "detach" => Ok(Kind::Detach),
+            "oper" => Ok(Kind::Oper),
+            "exec" => Ok(Kind::Exec),
             _ => Err(()),
         }
     }
@@ -118,6 +136,7 @@ pub fn parse(
     buffer: Option<&buffer::Upstream>,
     our_nickname: Option<NickRef>,
     isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+    aliases: &Aliases,
 ) -> Result<Command, Error> {
     let (head, rest) = s.split_once('/').ok_or(Error::MissingSlash)?;
     // Don't allow leading whitespace before slash
@@ -136,6 +155,21 @@ pub fn parse(
         &rest[cmd.len() + 1..]
     };
 
+    // Aliases take precedence over commands, but don't expand further so an
+    // alias can wrap the command it's named after
+    if let Some(template) = aliases.get(cmd) {
+        let expanded =
+            expand_alias(template, &args, raw, buffer, our_nickname)?;
+
+        return parse(
+            &expanded,
+            buffer,
+            our_nickname,
+            isupport,
+            &Aliases::default(),
+        );
+    }
+
     let unknown = || {
         Command::Irc(Irc::Unknown(
             cmd.to_string(),
@@ -160,14 +194,31 @@ pub fn parse(
                         if let Some(isupport::Parameter::CHANNELLEN(max_len)) =
                             isupport.get(&isupport::Kind::CHANNELLEN)
                         {
-                            Some(*max_len as usize)
+                            Some(*max_len)
                         } else {
                             None
                         };
 
-                    if chan_limits.is_some() || channel_len.is_some() {
+                    // `JOIN 0` parts all channels
+                    if chanlist != "0" {
+                        let chantypes =
+                            isupport::get_chantypes_or_default(isupport);
                         let channels = chanlist.split(',').collect::<Vec<_>>();
 
+                        for channel in &channels {
+                            target::Channel::validate(
+                                channel,
+                                chantypes,
+                                channel_len,
+                            )
+                            .map_err(|error| {
+                                Error::InvalidChannel {
+                                    channel: (*channel).to_string(),
+                                    error,
+                                }
+                            })?;
+                        }
+
                         if let Some(chan_limits) = chan_limits {
                             for chan_limit in chan_limits {
                                 if let Some(limit) = chan_limit.limit {
@@ -191,18 +242,6 @@ pub fn parse(
                                 }
                             }
                         }
-
-                        if let Some(max_len) = channel_len
-                            && let Some(channel) = channels
-                                .into_iter()
-                                .find(|channel| channel.len() > max_len)
-                        {
-                            return Err(Error::ArgTooLong {
-                                name: "channel in channels",
-                                len: channel.len(),
-                                max_len,
-                            });
-                        }
                     }
 
                     if let Some(ref chankeys) = chankeys
@@ -251,43 +290,21 @@ pub fn parse(
                 Ok(Command::Irc(Irc::Quit(comment)))
             }),
             Kind::Msg => validated::<1, 1, true>(args, |[targets], [msg]| {
-                let target_limit = find_target_limit(isupport, "PRIVMSG")
-                    .map(|limit| limit as usize);
+                let targets = TargetList::parse(
+                    &targets,
+                    isupport::get_chantypes_or_default(isupport),
+                    isupport::get_statusmsg_or_default(isupport),
+                    isupport::get_casemapping_or_default(isupport),
+                );
 
-                if let Some(target_limit) = target_limit {
-                    let targets = targets.split(',').collect::<Vec<_>>();
-
-                    if targets.len() > target_limit {
-                        return Err(Error::TooManyTargets {
-                            name: "targets",
-                            number: targets.len(),
-                            max_number: target_limit,
-                        });
-                    }
-                }
+                // Too many targets are split up by `fit_to_limits`
+                targets.validate(None)?;
 
                 if let Some(msg) = msg {
-                    Ok(Command::Irc(Irc::Msg(targets, msg)))
+                    Ok(Command::Irc(Irc::Msg(targets.to_string(), msg)))
                 } else {
-                    let casemapping =
-                        isupport::get_casemapping_or_default(isupport);
-                    let chantypes =
-                        isupport::get_chantypes_or_default(isupport);
-                    let statusmsg =
-                        isupport::get_statusmsg_or_default(isupport);
-
                     Ok(Command::Internal(Internal::OpenBuffers(
-                        targets
-                            .split(",")
-                            .map(|target| {
-                                Target::parse(
-                                    target,
-                                    chantypes,
-                                    statusmsg,
-                                    casemapping,
-                                )
-                            })
-                            .collect(),
+                        targets.into_iter().collect(),
                     )))
                 }
             }),
@@ -430,21 +447,6 @@ pub fn parse(
                         (channel.to_string(), None)
                     };
 
-                    if let Some(ref topic) = topic
-                        && let Some(isupport::Parameter::TOPICLEN(max_len)) =
-                            isupport.get(&isupport::Kind::TOPICLEN)
-                    {
-                        let max_len = *max_len as usize;
-
-                        if topic.len() > max_len {
-                            return Err(Error::ArgTooLong {
-                                name: "topic",
-                                len: topic.len(),
-                                max_len,
-                            });
-                        }
-                    }
-
                     Ok(Command::Irc(Irc::Topic(channel, topic)))
                 })
             }
@@ -491,36 +493,6 @@ pub fn parse(
                                 (channel, users, comment)
                             };
 
-                        let target_limit = find_target_limit(isupport, "KICK")
-                            .map(|limit| limit as usize);
-
-                        if let Some(target_limit) = target_limit {
-                            let users = users.split(',').collect::<Vec<_>>();
-
-                            if users.len() > target_limit {
-                                return Err(Error::TooManyTargets {
-                                    name: "users",
-                                    number: users.len(),
-                                    max_number: target_limit,
-                                });
-                            }
-                        }
-
-                        if let Some(ref comment) = comment
-                            && let Some(isupport::Parameter::KICKLEN(max_len)) =
-                                isupport.get(&isupport::Kind::KICKLEN)
-                        {
-                            let max_len = *max_len as usize;
-
-                            if comment.len() > max_len {
-                                return Err(Error::ArgTooLong {
-                                    name: "comment",
-                                    len: comment.len(),
-                                    max_len,
-                                });
-                            }
-                        }
-
                         Ok(Command::Irc(Irc::Kick(channel, users, comment)))
                     },
                 )
@@ -568,9 +540,8 @@ pub fn parse(
                             ),
                     );
 
-                    let mode_limit =
-                        isupport::get_mode_limit_or_default(isupport);
-
+                    // The number of modes per command is limited by
+                    // `fit_to_limits`
                     if let Some(mode_string) = mode_string {
                         if mode_string == "+" || mode_string == "-" {
                             Err(Error::NoModeString)
@@ -595,16 +566,7 @@ pub fn parse(
                                 for prefix_map in prefix {
                                     channel_modes_regex.push(prefix_map.mode);
                                 }
-                                channel_modes_regex += r"]";
-                                if let Some(mode_limit) = mode_limit {
-                                    channel_modes_regex += r"{1,";
-                                    channel_modes_regex +=
-                                        &format!("{mode_limit}");
-                                    channel_modes_regex += r"}";
-                                } else {
-                                    channel_modes_regex += r"+";
-                                }
-                                channel_modes_regex += r")+$";
+                                channel_modes_regex += r"]+)+$";
 
                                 Regex::new(&channel_modes_regex).unwrap_or(
                                     Regex::new(r"^((\+|\-)[A-Za-z]+)+$")
@@ -614,22 +576,7 @@ pub fn parse(
                                 // User modes from RPL_MYINFO is unreliable,
                                 // so use the most permissive regex instead of
                                 // crafting a regex for the server
-
-                                let mut user_modes_regex =
-                                    String::from(r"^((\+|\-)[A-Za-z]");
-                                if let Some(mode_limit) = mode_limit {
-                                    user_modes_regex += r"{1,";
-                                    user_modes_regex +=
-                                        &format!("{mode_limit}");
-                                    user_modes_regex += r"}";
-                                } else {
-                                    user_modes_regex += r"+";
-                                }
-                                user_modes_regex += r")+$";
-                                Regex::new(&user_modes_regex).unwrap_or(
-                                    Regex::new(r"^((\+|\-)[A-Za-z]+)+$")
-                                        .unwrap(),
-                                )
+                                Regex::new(r"^((\+|\-)[A-Za-z]+)+$").unwrap()
                             };
 
                             if !mode_string_regex
@@ -663,21 +610,6 @@ pub fn parse(
                 },
             ),
             Kind::Away => validated::<0, 1, true>(args, |_, [comment]| {
-                if let Some(ref comment) = comment
-                    && let Some(isupport::Parameter::AWAYLEN(max_len)) =
-                        isupport.get(&isupport::Kind::AWAYLEN)
-                {
-                    let max_len = *max_len as usize;
-
-                    if comment.len() > max_len {
-                        return Err(Error::ArgTooLong {
-                            name: "reason",
-                            len: comment.len(),
-                            max_len,
-                        });
-                    }
-                }
-
                 Ok(Command::Irc(Irc::Away(comment)))
             }),
             Kind::SetName => validated::<1, 0, true>(args, |[realname], _| {
@@ -699,43 +631,21 @@ pub fn parse(
             }),
             Kind::Notice => {
                 validated::<1, 1, true>(args, |[targets], [msg]| {
-                    let target_limit = find_target_limit(isupport, "NOTICE")
-                        .map(|limit| limit as usize);
+                    let targets = TargetList::parse(
+                        &targets,
+                        isupport::get_chantypes_or_default(isupport),
+                        isupport::get_statusmsg_or_default(isupport),
+                        isupport::get_casemapping_or_default(isupport),
+                    );
 
-                    if let Some(target_limit) = target_limit {
-                        let targets = targets.split(',').collect::<Vec<_>>();
-
-                        if targets.len() > target_limit {
-                            return Err(Error::TooManyTargets {
-                                name: "targets",
-                                number: targets.len(),
-                                max_number: target_limit,
-                            });
-                        }
-                    }
+                    // Too many targets are split up by `fit_to_limits`
+                    targets.validate(None)?;
 
                     if let Some(msg) = msg {
-                        Ok(Command::Irc(Irc::Notice(targets, msg)))
+                        Ok(Command::Irc(Irc::Notice(targets.to_string(), msg)))
                     } else {
-                        let casemapping =
-                            isupport::get_casemapping_or_default(isupport);
-                        let chantypes =
-                            isupport::get_chantypes_or_default(isupport);
-                        let statusmsg =
-                            isupport::get_statusmsg_or_default(isupport);
-
                         Ok(Command::Internal(Internal::OpenBuffers(
-                            targets
-                                .split(",")
-                                .map(|target| {
-                                    Target::parse(
-                                        target,
-                                        chantypes,
-                                        statusmsg,
-                                        casemapping,
-                                    )
-                                })
-                                .collect(),
+                            targets.into_iter().collect(),
                         )))
                     }
                 })
@@ -873,6 +783,36 @@ pub fn parse(
                     Ok(Command::Internal(Internal::Detach(channels)))
                 })
             }
+            Kind::Oper => {
+                validated::<0, 2, false>(args, |_, [name, password]| {
+                    Ok(Command::Internal(Internal::Oper(name, password)))
+                })
+            }
+            Kind::Exec => {
+                let raw = raw.trim();
+
+                let (send, command) = if raw == "-o" {
+                    (true, "")
+                } else if let Some(command) = raw.strip_prefix("-o ") {
+                    (true, command.trim_start())
+                } else {
+                    (false, raw)
+                };
+
+                if command.is_empty() {
+                    return Err(Error::IncorrectArgCount {
+                        min: 1,
+                        max: usize::MAX,
+                        actual: 0,
+                    });
+                }
+
+                if send && buffer.and_then(Upstream::target).is_none() {
+                    return Err(Error::NoOutputTarget);
+                }
+
+                Ok(Command::Internal(Internal::Exec(command.to_string(), send)))
+            }
             Kind::ClearTopic => {
                 validated::<0, 1, false>(args, |_, [channel]| {
                     if let Some(channel) = channel {
@@ -926,6 +866,89 @@ pub fn parse(
     }
 }
 
+/// Expands the variables of an alias `template`: `$1`, `$2`, etc. with the
+/// arguments it was called with, `$*` with all of them, and `$channel`,
+/// `$server` and `$nick` with those of `buffer`. Other `$` sequences are
+/// left as is, since they may be formatting.
+fn expand_alias(
+    template: &str,
+    args: &[&str],
+    raw: &str,
+    buffer: Option<&buffer::Upstream>,
+    our_nickname: Option<NickRef>,
+) -> Result<String, Error> {
+    let args = args
+        .iter()
+        .filter(|arg| !arg.is_empty())
+        .copied()
+        .collect::<Vec<_>>();
+
+    let arguments = alias::Arguments::of(template);
+
+    if !arguments.accepts(args.len()) {
+        return Err(Error::IncorrectArgCount {
+            min: arguments.positional,
+            max: if arguments.any {
+                usize::MAX
+            } else {
+                arguments.positional
+            },
+            actual: args.len(),
+        });
+    }
+
+    let mut expanded = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(index) = rest.find('$') {
+        expanded.push_str(&rest[..index]);
+
+        let after = &rest[index + 1..];
+        let digits = after.chars().take_while(char::is_ascii_digit).count();
+
+        let (value, remaining) = if let Some(position) = after[..digits]
+            .parse::<usize>()
+            .ok()
+            .filter(|position| *position > 0)
+        {
+            (
+                args.get(position - 1)
+                    .copied()
+                    .unwrap_or_default()
+                    .to_string(),
+                &after[digits..],
+            )
+        } else if let Some(remaining) = after.strip_prefix('*') {
+            (raw.trim().to_string(), remaining)
+        } else if let Some(remaining) = after.strip_prefix("channel") {
+            let channel = buffer
+                .and_then(Upstream::channel)
+                .ok_or(Error::UnavailableVariable("channel"))?;
+
+            (channel.to_string(), remaining)
+        } else if let Some(remaining) = after.strip_prefix("server") {
+            let server =
+                buffer.ok_or(Error::UnavailableVariable("server"))?.server();
+
+            (server.name.to_string(), remaining)
+        } else if let Some(remaining) = after.strip_prefix("nick") {
+            let nick =
+                our_nickname.ok_or(Error::UnavailableVariable("nick"))?;
+
+            (nick.to_string(), remaining)
+        } else {
+            ("$".to_string(), after)
+        };
+
+        expanded.push_str(&value);
+        rest = remaining;
+    }
+
+    expanded.push_str(rest);
+
+    Ok(expanded)
+}
+
 // TODO: Expand `validated` so we can better indicate which parameters is optional.
 fn validated<const EXACT: usize, const OPT: usize, const TEXT: bool>(
     args: Vec<&str>,
@@ -992,6 +1015,287 @@ fn get_combined_arg(
     (!combined_arg.is_empty()).then_some(combined_arg)
 }
 
+/// Fits `command` within the limits advertised by the server (`TARGMAX`,
+/// `MODES`, `AWAYLEN`, `TOPICLEN` and `KICKLEN`), so it isn't rejected.
+///
+/// Commands with too many targets or modes are split into several commands
+/// and text which is too long is truncated, unless `server_limits` is
+/// [`ServerLimits::Reject`] in which case an error is returned instead.
+pub fn fit_to_limits(
+    command: Irc,
+    isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+    server_limits: ServerLimits,
+) -> Result<(Vec<Irc>, Vec<Adjustment>), Error> {
+    let mut limits = Limits {
+        server_limits,
+        adjustments: vec![],
+    };
+
+    let commands = match command {
+        Irc::Msg(targets, text) => limits
+            .split_targets(
+                &targets,
+                find_target_limit(isupport, "PRIVMSG"),
+                "targets",
+            )?
+            .into_iter()
+            .map(|targets| Irc::Msg(targets, text.clone()))
+            .collect(),
+        Irc::Notice(targets, text) => limits
+            .split_targets(
+                &targets,
+                find_target_limit(isupport, "NOTICE"),
+                "targets",
+            )?
+            .into_iter()
+            .map(|targets| Irc::Notice(targets, text.clone()))
+            .collect(),
+        Irc::Kick(channel, users, comment) => {
+            let kicklen = match isupport.get(&isupport::Kind::KICKLEN) {
+                Some(isupport::Parameter::KICKLEN(max_len)) => Some(*max_len),
+                _ => None,
+            };
+
+            let comment = comment
+                .map(|comment| limits.truncate(comment, kicklen, "comment"))
+                .transpose()?;
+
+            limits
+                .split_targets(
+                    &users,
+                    find_target_limit(isupport, "KICK"),
+                    "users",
+                )?
+                .into_iter()
+                .map(|users| Irc::Kick(channel.clone(), users, comment.clone()))
+                .collect()
+        }
+        Irc::Topic(channel, Some(topic)) => {
+            let topiclen = match isupport.get(&isupport::Kind::TOPICLEN) {
+                Some(isupport::Parameter::TOPICLEN(max_len)) => Some(*max_len),
+                _ => None,
+            };
+
+            vec![Irc::Topic(
+                channel,
+                Some(limits.truncate(topic, topiclen, "topic")?),
+            )]
+        }
+        Irc::Away(Some(reason)) => {
+            let awaylen = match isupport.get(&isupport::Kind::AWAYLEN) {
+                Some(isupport::Parameter::AWAYLEN(max_len)) => Some(*max_len),
+                _ => None,
+            };
+
+            vec![Irc::Away(Some(limits.truncate(reason, awaylen, "reason")?))]
+        }
+        Irc::Mode(target, Some(mode_string), mode_arguments)
+            if proto::is_channel(
+                &target,
+                isupport::get_chantypes_or_default(isupport),
+            ) =>
+        {
+            limits
+                .split_modes(
+                    mode_string,
+                    mode_arguments,
+                    isupport::get_mode_limit_or_default(isupport),
+                    isupport::get_chanmodes_or_default(isupport),
+                    isupport::get_prefix_or_default(isupport),
+                )?
+                .into_iter()
+                .map(|(mode_string, mode_arguments)| {
+                    Irc::Mode(target.clone(), Some(mode_string), mode_arguments)
+                })
+                .collect()
+        }
+        command => vec![command],
+    };
+
+    Ok((commands, limits.adjustments))
+}
+
+struct Limits {
+    server_limits: ServerLimits,
+    adjustments: Vec<Adjustment>,
+}
+
+/// Mode string of a `MODE` command, along with its arguments
+type ModeChange = (String, Option<Vec<String>>);
+
+impl Limits {
+    /// Splits the comma separated `targets` into lists of at most `limit`.
+    fn split_targets(
+        &mut self,
+        targets: &str,
+        limit: Option<u16>,
+        name: &'static str,
+    ) -> Result<Vec<String>, Error> {
+        let targets = targets.split(',').collect::<Vec<_>>();
+
+        let Some(max_number) = limit.map(usize::from).filter(|max_number| {
+            *max_number > 0 && targets.len() > *max_number
+        }) else {
+            return Ok(vec![targets.join(",")]);
+        };
+
+        if self.server_limits == ServerLimits::Reject {
+            return Err(Error::TooManyTargets {
+                name,
+                number: targets.len(),
+                max_number,
+            });
+        }
+
+        let lists = targets
+            .chunks(max_number)
+            .map(|chunk| chunk.join(","))
+            .collect::<Vec<_>>();
+
+        self.adjustments.push(Adjustment::Split {
+            name,
+            number: targets.len(),
+            max_number,
+            commands: lists.len(),
+        });
+
+        Ok(lists)
+    }
+
+    /// Truncates `text` to at most `max_len` bytes.
+    fn truncate(
+        &mut self,
+        mut text: String,
+        max_len: Option<u16>,
+        name: &'static str,
+    ) -> Result<String, Error> {
+        let Some(max_len) = max_len
+            .map(usize::from)
+            .filter(|max_len| text.len() > *max_len)
+        else {
+            return Ok(text);
+        };
+
+        if self.server_limits == ServerLimits::Reject {
+            return Err(Error::ArgTooLong {
+                name,
+                len: text.len(),
+                max_len,
+            });
+        }
+
+        let len = text.len();
+        let end = (0..=max_len)
+            .rev()
+            .find(|end| text.is_char_boundary(*end))
+            .unwrap_or_default();
+        text.truncate(end);
+
+        self.adjustments
+            .push(Adjustment::Truncated { name, len, max_len });
+
+        Ok(text)
+    }
+
+    /// Splits channel mode changes into commands with at most `limit` modes
+    /// taking a parameter each.
+    fn split_modes(
+        &mut self,
+        mode_string: String,
+        mode_arguments: Option<Vec<String>>,
+        limit: Option<u16>,
+        chanmodes: &[isupport::ModeKind],
+        prefix: &[isupport::PrefixMap],
+    ) -> Result<Vec<ModeChange>, Error> {
+        let Some(max_number) =
+            limit.map(usize::from).filter(|limit| *limit > 0)
+        else {
+            return Ok(vec![(mode_string, mode_arguments)]);
+        };
+
+        let takes_argument = |sign: char, mode: char| {
+            prefix.iter().any(|prefix_map| prefix_map.mode == mode)
+                || chanmodes.iter().any(|chanmode| {
+                    chanmode.modes.contains(mode)
+                        && match chanmode.kind {
+                            'A' | 'B' => true,
+                            'C' => sign == '+',
+                            _ => false,
+                        }
+                })
+        };
+
+        let mut arguments =
+            mode_arguments.clone().unwrap_or_default().into_iter();
+        let mut commands = vec![];
+        let mut modes = String::new();
+        let mut modes_arguments = vec![];
+        let mut modes_sign = None;
+        let mut sign = '+';
+        let mut number = 0;
+
+        for mode in mode_string.chars() {
+            if matches!(mode, '+' | '-') {
+                sign = mode;
+                continue;
+            }
+
+            // Type A modes without an argument list the mode instead
+            let argument = takes_argument(sign, mode)
+                .then(|| arguments.next())
+                .flatten();
+
+            if argument.is_some() && modes_arguments.len() == max_number {
+                commands.push((
+                    std::mem::take(&mut modes),
+                    std::mem::take(&mut modes_arguments),
+                ));
+                modes_sign = None;
+            }
+
+            if modes_sign != Some(sign) {
+                modes.push(sign);
+                modes_sign = Some(sign);
+            }
+            modes.push(mode);
+
+            if let Some(argument) = argument {
+                modes_arguments.push(argument);
+                number += 1;
+            }
+        }
+
+        if commands.is_empty() {
+            return Ok(vec![(mode_string, mode_arguments)]);
+        }
+
+        modes_arguments.extend(arguments);
+        commands.push((modes, modes_arguments));
+
+        if self.server_limits == ServerLimits::Reject {
+            return Err(Error::TooManyTargets {
+                name: "modes with a parameter",
+                number,
+                max_number,
+            });
+        }
+
+        self.adjustments.push(Adjustment::Split {
+            name: "modes with a parameter",
+            number,
+            max_number,
+            commands: commands.len(),
+        });
+
+        Ok(commands
+            .into_iter()
+            .map(|(modes, arguments)| {
+                (modes, (!arguments.is_empty()).then_some(arguments))
+            })
+            .collect())
+    }
+}
+
 impl TryFrom<Irc> for proto::Command {
     type Error = ();
 
@@ -1023,6 +1327,13 @@ impl TryFrom<Irc> for proto::Command {
             Irc::Notice(target, msg) => proto::Command::NOTICE(target, msg),
             Irc::Raw(raw) => proto::Command::Raw(raw),
             Irc::Unknown(command, args) => proto::Command::new(&command, args),
+            Irc::Ctcp(ctcp::Command::Ping, target, None) => {
+                ctcp::query_command(
+                    &ctcp::Command::Ping,
+                    target,
+                    Some(ctcp::ping_timestamp()),
+                )
+            }
             Irc::Ctcp(command, target, params) => {
                 ctcp::query_command(&command, target, params)
             }
@@ -1070,8 +1381,54 @@ pub enum Error {
     },
     #[error("must be a number greater than zero")]
     NotPositiveInteger,
-    #[error("invalid channel name ({requirements}")]
+    #[error("invalid channel name ({requirements})")]
     InvalidChannelName { requirements: String },
+    #[error("invalid channel {channel:?}: {error}")]
+    InvalidChannel {
+        channel: String,
+        error: target::ChannelNameError,
+    },
+    #[error("no targets")]
+    NoTargets,
+    #[error("${0} isn't available here")]
+    UnavailableVariable(&'static str),
+    #[error("output can only be sent in a channel or query")]
+    NoOutputTarget,
+}
+
+/// A change made to a command so it fits within the server's limits.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Adjustment {
+    #[error(
+        "too many {name} ({number}/{max_number} allowed), sent as {commands} commands"
+    )]
+    Split {
+        name: &'static str,
+        number: usize,
+        max_number: usize,
+        commands: usize,
+    },
+    #[error("{name} is too long ({len}/{max_len} characters), truncated")]
+    Truncated {
+        name: &'static str,
+        len: usize,
+        max_len: usize,
+    },
+}
+
+impl From<TargetListError> for Error {
+    fn from(error: TargetListError) -> Self {
+        match error {
+            TargetListError::Empty => Error::NoTargets,
+            TargetListError::TooManyTargets { number, max_number } => {
+                Error::TooManyTargets {
+                    name: "targets",
+                    number,
+                    max_number,
+                }
+            }
+        }
+    }
 }
 
 fn fmt_incorrect_arg_count(min: usize, max: usize, actual: usize) -> String {
@@ -1081,6 +1438,10 @@ fn fmt_incorrect_arg_count(min: usize, max: usize, actual: usize) -> String {
         format!(
             "too {relational} arguments ({actual} provided, {min} expected)"
         )
+    } else if max == usize::MAX {
+        format!(
+            "too {relational} arguments ({actual} provided, at least {min} expected)"
+        )
     } else {
         format!(
             "too {relational} arguments ({actual} provided, {min} to {max} expected)"
@@ -1109,3 +1470,280 @@ fn fmt_channel_name_requirements(chantypes: &[char]) -> String {
 
     requirements
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn isupport() -> HashMap<isupport::Kind, isupport::Parameter> {
+        HashMap::from([
+            (
+                isupport::Kind::TARGMAX,
+                isupport::Parameter::TARGMAX(vec![
+                    isupport::CommandTargetLimit {
+                        command: "PRIVMSG".to_string(),
+                        limit: Some(2),
+                    },
+                    isupport::CommandTargetLimit {
+                        command: "KICK".to_string(),
+                        limit: Some(1),
+                    },
+                ]),
+            ),
+            (isupport::Kind::MODES, isupport::Parameter::MODES(Some(3))),
+            (isupport::Kind::TOPICLEN, isupport::Parameter::TOPICLEN(5)),
+            (isupport::Kind::KICKLEN, isupport::Parameter::KICKLEN(4)),
+        ])
+    }
+
+    #[test]
+    fn fit_to_limits_splits() {
+        let isupport = isupport();
+
+        let (commands, adjustments) = fit_to_limits(
+            Irc::Msg("#a,#b,dan".to_string(), "hi".to_string()),
+            &isupport,
+            ServerLimits::Adjust,
+        )
+        .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                Irc::Msg("#a,#b".to_string(), "hi".to_string()),
+                Irc::Msg("dan".to_string(), "hi".to_string()),
+            ]
+        );
+        assert_eq!(
+            adjustments,
+            vec![Adjustment::Split {
+                name: "targets",
+                number: 3,
+                max_number: 2,
+                commands: 2,
+            }]
+        );
+
+        let (commands, adjustments) = fit_to_limits(
+            Irc::Mode(
+                "#chan".to_string(),
+                Some("+oovv-l".to_string()),
+                Some(["a", "b", "c", "d"].map(String::from).to_vec()),
+            ),
+            &isupport,
+            ServerLimits::Adjust,
+        )
+        .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                Irc::Mode(
+                    "#chan".to_string(),
+                    Some("+oov".to_string()),
+                    Some(["a", "b", "c"].map(String::from).to_vec()),
+                ),
+                Irc::Mode(
+                    "#chan".to_string(),
+                    Some("+v-l".to_string()),
+                    Some(vec!["d".to_string()]),
+                ),
+            ]
+        );
+        assert_eq!(adjustments.len(), 1);
+
+        // Modes without parameters aren't limited
+        let (commands, adjustments) = fit_to_limits(
+            Irc::Mode("#chan".to_string(), Some("+imnst".to_string()), None),
+            &isupport,
+            ServerLimits::Adjust,
+        )
+        .unwrap();
+
+        assert_eq!(commands.len(), 1);
+        assert!(adjustments.is_empty());
+    }
+
+    #[test]
+    fn fit_to_limits_truncates() {
+        let isupport = isupport();
+
+        let (commands, adjustments) = fit_to_limits(
+            Irc::Topic("#chan".to_string(), Some("héllo world".to_string())),
+            &isupport,
+            ServerLimits::Adjust,
+        )
+        .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![Irc::Topic("#chan".to_string(), Some("héll".to_string()))]
+        );
+        assert_eq!(
+            adjustments,
+            vec![Adjustment::Truncated {
+                name: "topic",
+                len: 12,
+                max_len: 5,
+            }]
+        );
+
+        let (commands, adjustments) = fit_to_limits(
+            Irc::Kick(
+                "#chan".to_string(),
+                "dan,casper".to_string(),
+                Some("bye bye".to_string()),
+            ),
+            &isupport,
+            ServerLimits::Adjust,
+        )
+        .unwrap();
+
+        assert_eq!(
+            commands,
+            vec![
+                Irc::Kick(
+                    "#chan".to_string(),
+                    "dan".to_string(),
+                    Some("bye ".to_string()),
+                ),
+                Irc::Kick(
+                    "#chan".to_string(),
+                    "casper".to_string(),
+                    Some("bye ".to_string()),
+                ),
+            ]
+        );
+        assert_eq!(adjustments.len(), 2);
+    }
+
+    #[test]
+    fn fit_to_limits_rejects() {
+        let isupport = isupport();
+
+        assert!(matches!(
+            fit_to_limits(
+                Irc::Msg("#a,#b,dan".to_string(), "hi".to_string()),
+                &isupport,
+                ServerLimits::Reject,
+            ),
+            Err(Error::TooManyTargets {
+                name: "targets",
+                ..
+            })
+        ));
+        assert!(matches!(
+            fit_to_limits(
+                Irc::Topic(
+                    "#chan".to_string(),
+                    Some("hello world".to_string())
+                ),
+                &isupport,
+                ServerLimits::Reject,
+            ),
+            Err(Error::ArgTooLong { name: "topic", .. })
+        ));
+        assert_eq!(
+            fit_to_limits(
+                Irc::Msg("#a,#b".to_string(), "hi".to_string()),
+                &isupport,
+                ServerLimits::Reject,
+            )
+            .unwrap(),
+            (
+                vec![Irc::Msg("#a,#b".to_string(), "hi".to_string())],
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn expands_aliases() {
+        let server = crate::Server {
+            name: "libera".into(),
+            network: None,
+        };
+        let channel = target::Channel::from_str(
+            "#halloy",
+            isupport::DEFAULT_CHANTYPES,
+            isupport::CaseMap::default(),
+        );
+        let buffer = Upstream::Channel(server, channel);
+        let nick =
+            crate::user::Nick::from_str("casper", isupport::CaseMap::default());
+
+        assert_eq!(
+            expand_alias(
+                "/msg ChanServ OP $channel $1",
+                &["dan"],
+                "dan",
+                Some(&buffer),
+                Some(nick.as_nickref()),
+            )
+            .ok(),
+            Some("/msg ChanServ OP #halloy dan".to_string())
+        );
+        assert_eq!(
+            expand_alias(
+                "/msg $1 $nick on $server says $* $b",
+                &["dan", "", "hi"],
+                "dan  hi",
+                Some(&buffer),
+                Some(nick.as_nickref()),
+            )
+            .ok(),
+            Some("/msg dan casper on libera says dan  hi $b".to_string())
+        );
+        assert!(matches!(
+            expand_alias("/kick $channel $1", &["dan"], "dan", None, None),
+            Err(Error::UnavailableVariable("channel"))
+        ));
+        assert!(matches!(
+            expand_alias("/op $1 $2", &["dan"], "dan", Some(&buffer), None),
+            Err(Error::IncorrectArgCount {
+                min: 2,
+                max: 2,
+                actual: 1,
+            })
+        ));
+    }
+
+    #[test]
+    fn parses_exec() {
+        let server = crate::Server {
+            name: "libera".into(),
+            network: None,
+        };
+        let channel = Upstream::Channel(
+            server.clone(),
+            target::Channel::from_str(
+                "#halloy",
+                isupport::DEFAULT_CHANTYPES,
+                isupport::CaseMap::default(),
+            ),
+        );
+        let server = Upstream::Server(server);
+        let parse = |input, buffer| {
+            parse(input, buffer, None, &HashMap::new(), &Aliases::default())
+        };
+
+        assert!(matches!(
+            parse("/exec uname -a", None),
+            Ok(Command::Internal(Internal::Exec(command, false)))
+                if command == "uname -a"
+        ));
+        assert!(matches!(
+            parse("/exec -o  fortune", Some(&channel)),
+            Ok(Command::Internal(Internal::Exec(command, true)))
+                if command == "fortune"
+        ));
+        assert!(matches!(
+            parse("/exec -o fortune", Some(&server)),
+            Err(Error::NoOutputTarget)
+        ));
+        assert!(matches!(
+            parse("/exec -o", Some(&channel)),
+            Err(Error::IncorrectArgCount { actual: 0, .. })
+        ));
+    }
+}
@@ -1,4 +1,5 @@
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::{str, string};
 
 use iced_core::font;
@@ -11,40 +12,51 @@ use tokio_stream::StreamExt;
 use tokio_stream::wrappers::ReadDirStream;
 
 pub use self::actions::Actions;
+pub use self::alias::Aliases;
 pub use self::buffer::Buffer;
 pub use self::ctcp::Ctcp;
 pub use self::file_transfer::FileTransfer;
 pub use self::highlights::Highlights;
 pub use self::keys::Keyboard;
 pub use self::logs::Logs;
+pub use self::mini_mode::MiniMode;
 pub use self::notification::Notifications;
 pub use self::pane::Pane;
 pub use self::platform_specific::PlatformSpecific;
 pub use self::preview::Preview;
 pub use self::proxy::Proxy;
+pub use self::search::Search;
 pub use self::server::Server;
 pub use self::sidebar::Sidebar;
+pub use self::tray::Tray;
+pub use self::update::Update;
 use crate::appearance::theme::Styles;
 use crate::appearance::{self, Appearance};
 use crate::audio::{self};
 use crate::serde::deserialize_positive_integer_maybe;
 use crate::server::{ConfigMap as ServerMap, ServerName};
-use crate::{Theme, environment};
+use crate::target::Target;
+use crate::{Theme, environment, isupport};
 
 pub mod actions;
+pub mod alias;
 pub mod buffer;
 pub mod ctcp;
 pub mod file_transfer;
 pub mod highlights;
 pub mod keys;
 pub mod logs;
+pub mod mini_mode;
 pub mod notification;
 pub mod pane;
 pub mod platform_specific;
 pub mod preview;
 pub mod proxy;
+pub mod search;
 pub mod server;
 pub mod sidebar;
+pub mod tray;
+pub mod update;
 
 const CONFIG_TEMPLATE: &str = include_str!("../../config.toml");
 const DEFAULT_THEME_NAME: &str = "ferra";
@@ -63,11 +75,22 @@ pub struct Config {
     pub notifications: Notifications,
     pub file_transfer: FileTransfer,
     pub tooltips: bool,
+    pub always_on_top: bool,
+    pub borderless: bool,
+    pub opacity: Opacity,
+    pub blur: bool,
+    pub reload_on_change: bool,
+    pub mini_mode: MiniMode,
+    pub locale: Option<String>,
     pub preview: Preview,
     pub highlights: Highlights,
     pub actions: Actions,
     pub ctcp: Ctcp,
+    pub aliases: Aliases,
     pub logs: Logs,
+    pub search: Search,
+    pub update: Update,
+    pub tray: Tray,
     pub platform_specific: PlatformSpecific,
 }
 
@@ -92,6 +115,34 @@ impl From<ScaleFactor> for f32 {
     }
 }
 
+/// Opacity of the window background, from `0.1` to `1.0` (opaque)
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Opacity(f32);
+
+impl Default for Opacity {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl From<f32> for Opacity {
+    fn from(value: f32) -> Self {
+        Opacity(value.clamp(0.1, 1.0))
+    }
+}
+
+impl From<Opacity> for f32 {
+    fn from(value: Opacity) -> Self {
+        value.0.clamp(0.1, 1.0)
+    }
+}
+
+impl Opacity {
+    pub fn is_translucent(self) -> bool {
+        f32::from(self) < 1.0
+    }
+}
+
 #[derive(Debug, Copy, Clone, Deserialize)]
 #[serde(default)]
 pub struct Scrollbar {
@@ -178,6 +229,41 @@ where
 }
 
 impl Config {
+    /// The alias shown in place of `target` on `server`, if one is configured
+    pub fn alias(
+        &self,
+        server: &crate::Server,
+        target: &Target,
+        casemapping: isupport::CaseMap,
+    ) -> Option<&str> {
+        self.servers.get(&server.name)?.alias(target, casemapping)
+    }
+
+    /// The name of the theme configured for `buffer`, see
+    /// [`Server::theme`]
+    pub fn theme(
+        &self,
+        buffer: &crate::buffer::Upstream,
+        casemapping: isupport::CaseMap,
+    ) -> Option<&server::Theme> {
+        self.servers
+            .get(&buffer.server().name)?
+            .theme(buffer.target().as_ref(), casemapping)
+    }
+
+    /// Whether the selected theme or any server's themes follow the OS
+    /// light or dark appearance.
+    pub fn follows_os_appearance(&self) -> bool {
+        self.appearance.selected.is_dynamic()
+            || self.servers.iter().any(|(_, server)| {
+                server
+                    .theme
+                    .iter()
+                    .chain(server.themes.values())
+                    .any(|theme| matches!(theme, server::Theme::Dynamic { .. }))
+            })
+    }
+
     pub fn config_dir() -> PathBuf {
         let dir = environment::config_dir();
 
@@ -311,11 +397,23 @@ impl Config {
             pub notifications: Notifications,
             pub file_transfer: FileTransfer,
             pub tooltips: bool,
+            pub always_on_top: bool,
+            pub borderless: bool,
+            pub opacity: Opacity,
+            pub blur: bool,
+            pub reload_on_change: bool,
+            pub mini_mode: MiniMode,
+            pub locale: Option<String>,
             pub preview: Preview,
             pub highlights: Highlights,
             pub actions: Actions,
             pub ctcp: Ctcp,
+            #[serde(rename = "alias")]
+            pub aliases: Aliases,
             pub logs: Logs,
+            pub search: Search,
+            pub update: Update,
+            pub tray: Tray,
             pub platform_specific: PlatformSpecific,
         }
 
@@ -334,11 +432,22 @@ impl Config {
                     notifications: Notifications::default(),
                     file_transfer: FileTransfer::default(),
                     tooltips: true,
+                    always_on_top: false,
+                    borderless: false,
+                    opacity: Opacity::default(),
+                    blur: false,
+                    reload_on_change: true,
+                    mini_mode: MiniMode::default(),
+                    locale: None,
                     preview: Preview::default(),
                     highlights: Highlights::default(),
                     actions: Actions::default(),
                     ctcp: Ctcp::default(),
+                    aliases: Aliases::default(),
                     logs: Logs::default(),
+                    search: Search::default(),
+                    update: Update::default(),
+                    tray: Tray::default(),
                     platform_specific: PlatformSpecific::default(),
                 }
             }
@@ -366,19 +475,36 @@ impl Config {
             notifications,
             file_transfer,
             tooltips,
+            always_on_top,
+            borderless,
+            opacity,
+            blur,
+            reload_on_change,
+            mini_mode,
+            locale,
             preview,
             pane,
             highlights,
             actions,
             ctcp,
+            aliases,
             logs,
+            search,
+            update,
+            tray,
             platform_specific,
         } = serde_ignored::deserialize(config, |ignored| {
             log::warn!("[config.toml] Ignoring unknown setting: {ignored}");
         })
         .map_err(|e| Error::Parse(e.to_string()))?;
 
-        let servers = ServerMap::new(servers).await?;
+        // Aliases can be used by the commands servers run on connect
+        let servers =
+            ServerMap::new(servers.into_iter().map(|(name, mut server)| {
+                server.command_aliases = aliases.clone();
+                (name, server)
+            }))
+            .await?;
 
         let appearance = Self::load_appearance(theme.keys())
             .await
@@ -396,12 +522,23 @@ impl Config {
             notifications,
             file_transfer,
             tooltips,
+            always_on_top,
+            borderless,
+            opacity,
+            blur,
+            reload_on_change,
+            mini_mode,
+            locale,
             preview,
             pane,
             highlights,
             actions,
             ctcp,
+            aliases,
             logs,
+            search,
+            update,
+            tray,
             platform_specific,
         })
     }
@@ -482,6 +619,24 @@ impl Config {
         Ok(Appearance { selected, all })
     }
 
+    /// Checks that the theme file at `path` can be parsed, so a reload can
+    /// keep the current themes when it can't. A removed file is fine.
+    pub async fn check_theme(path: &Path) -> Result<(), Error> {
+        let content = match tokio::fs::read_to_string(path).await {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                return Ok(());
+            }
+            Err(error) => return Err(Error::Io(error.to_string())),
+        };
+
+        toml::from_str::<Styles>(&content)
+            .map(|_| ())
+            .map_err(|error| {
+                Error::Parse(format!("{}: {error}", path.display()))
+            })
+    }
+
     pub fn load_logs() -> Option<Logs> {
         #[derive(Default, Deserialize)]
         #[serde(default)]
@@ -559,6 +714,10 @@ pub enum Error {
         "Exactly one of sasl.plain.password, sasl.plain.password_file or sasl.plain.password_command must be set."
     )]
     DuplicateSaslPassword,
+    #[error(
+        "Only one of oper.password, oper.password_file and oper.password_command can be set."
+    )]
+    DuplicateOperPassword,
     #[error("Config does not exist")]
     ConfigMissing,
 }
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Deserializer};
+
+/// Commands defined by the user, each expanding to another command.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aliases(HashMap<String, String>);
+
+impl Aliases {
+    /// Command `name` expands to, ignoring case.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, command)| (name.as_str(), command.as_str()))
+    }
+}
+
+/// Arguments an alias expanding to `command` is called with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Arguments {
+    /// Highest `$1`, `$2`, etc. referred to
+    pub positional: usize,
+    /// Whether `$*` is referred to, taking any number of arguments
+    pub any: bool,
+}
+
+impl Arguments {
+    pub fn of(command: &str) -> Self {
+        let mut positional = 0;
+        let mut any = false;
+
+        for (_, after) in command
+            .match_indices('$')
+            .map(|(index, _)| command.split_at(index + 1))
+        {
+            let digits = after.chars().take_while(char::is_ascii_digit).count();
+
+            if let Ok(position) = after[..digits].parse::<usize>() {
+                positional = positional.max(position);
+            } else if after.starts_with('*') {
+                any = true;
+            }
+        }
+
+        Self { positional, any }
+    }
+
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.positional && (self.any || count <= self.positional)
+    }
+}
+
+impl<'de> Deserialize<'de> for Aliases {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let aliases = HashMap::<String, String>::deserialize(deserializer)?;
+
+        aliases
+            .into_iter()
+            .map(|(name, command)| {
+                let name = name.trim_start_matches('/').to_lowercase();
+
+                if name.is_empty() || name.contains(char::is_whitespace) {
+                    return Err(serde::de::Error::custom(format!(
+                        "invalid alias name {name:?}"
+                    )));
+                }
+
+                if !command.starts_with('/') {
+                    return Err(serde::de::Error::custom(format!(
+                        "alias {name} must expand to a command starting \
+                         with /"
+                    )));
+                }
+
+                Ok((name, command))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
@@ -5,15 +5,17 @@ use iced::Color;
 use serde::{Deserialize, Deserializer};
 
 pub use self::channel::Channel;
+pub use self::context_menu::ContextMenu;
 use crate::appearance::theme::{alpha_color, alpha_color_calculate};
 use crate::config::buffer::nickname::Nickname;
 
 pub mod channel;
+pub mod context_menu;
 pub mod nickname;
 
 use crate::buffer::{
-    BacklogSeparator, DateSeparators, SkinTone, StatusMessagePrefix, TextInput,
-    Timestamp,
+    BacklogSeparator, DateSeparators, Density, SkinTone, StatusMessagePrefix,
+    TextInput, Timestamp,
 };
 use crate::message::source;
 
@@ -28,13 +30,19 @@ pub struct Buffer {
     pub internal_messages: InternalMessages,
     pub status_message_prefix: StatusMessagePrefix,
     pub chathistory: ChatHistory,
+    pub history: History,
     pub backlog_separator: BacklogSeparator,
     pub date_separators: DateSeparators,
     pub commands: Commands,
+    pub context_menu: ContextMenu,
     pub emojis: Emojis,
     pub mark_as_read: MarkAsRead,
     pub url: Url,
+    pub polls: Polls,
+    pub formatting: Formatting,
+    pub digest: Digest,
     pub line_spacing: u32,
+    pub density: Density,
     pub scroll_position_on_open: ScrollPosition,
 }
 
@@ -70,6 +78,124 @@ impl Default for Emojis {
 #[serde(default)]
 pub struct Url {
     pub prompt_before_open: bool,
+    pub hover_card: HoverCard,
+}
+
+/// Card shown when hovering a url, with its full address and domain.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct HoverCard {
+    pub enabled: bool,
+    /// Follow redirects to show where shortened urls lead, which requests
+    /// the url when it's hovered.
+    pub resolve_redirects: bool,
+}
+
+impl Default for HoverCard {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            resolve_redirects: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct Polls {
+    pub enabled: bool,
+    pub start_prefix: String,
+    pub vote_prefix: String,
+    pub exclude: Vec<String>,
+    pub include: Vec<String>,
+}
+
+impl Default for Polls {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start_prefix: "!poll".to_string(),
+            vote_prefix: "!vote".to_string(),
+            exclude: Vec::default(),
+            include: Vec::default(),
+        }
+    }
+}
+
+impl Polls {
+    pub fn is_enabled(&self, channel: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let is_channel_filtered = |list: &Vec<String>, channel: &str| -> bool {
+            let wildcards = ["*", "all"];
+
+            list.iter().any(|item| {
+                wildcards.contains(&item.as_str()) || item == channel
+            })
+        };
+
+        let channel_included = is_channel_filtered(&self.include, channel);
+        let channel_excluded = is_channel_filtered(&self.exclude, channel);
+
+        // If the channel is included, it has precedence over excluded.
+        channel_included || !channel_excluded
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Formatting {
+    pub strip_colors: bool,
+    pub exclude: Vec<String>,
+    pub include: Vec<String>,
+}
+
+impl Formatting {
+    /// Whether colors are stripped from messages in the buffer named
+    /// `buffer`, or in buffers without a name (e.g. the server buffer).
+    pub fn strips_colors(&self, buffer: Option<&str>) -> bool {
+        if !self.strip_colors {
+            return false;
+        }
+
+        let Some(buffer) = buffer else {
+            return true;
+        };
+
+        let is_buffer_filtered = |list: &Vec<String>, buffer: &str| -> bool {
+            let wildcards = ["*", "all"];
+
+            list.iter().any(|item| {
+                wildcards.contains(&item.as_str()) || item == buffer
+            })
+        };
+
+        let buffer_included = is_buffer_filtered(&self.include, buffer);
+        let buffer_excluded = is_buffer_filtered(&self.exclude, buffer);
+
+        // If the buffer is included, it has precedence over excluded.
+        buffer_included || !buffer_excluded
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Digest {
+    pub enabled: bool,
+    /// Minutes between the read marker and the latest unread message before
+    /// a digest is shown.
+    pub threshold: u64,
+}
+
+impl Default for Digest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 60,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Deserialize)]
@@ -152,11 +278,31 @@ impl Default for SysInfo {
     }
 }
 
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Exec {
+    /// Seconds before the program is stopped
+    pub timeout: u64,
+    /// Lines of output shown or sent before the program is stopped
+    pub max_lines: usize,
+}
+
+impl Default for Exec {
+    fn default() -> Self {
+        Self {
+            timeout: 10,
+            max_lines: 20,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct Commands {
     pub show_description: bool,
     pub sysinfo: SysInfo,
+    pub exec: Exec,
+    pub server_limits: ServerLimits,
 }
 
 impl Default for Commands {
@@ -164,10 +310,20 @@ impl Default for Commands {
         Self {
             show_description: true,
             sysinfo: SysInfo::default(),
+            exec: Exec::default(),
+            server_limits: ServerLimits::default(),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServerLimits {
+    #[default]
+    Adjust,
+    Reject,
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Away {
     Dimmed(Dimmed),
@@ -310,9 +466,11 @@ impl Condensation {
             source::server::Kind::ChangeNick => {
                 self.messages.contains(&CondensationMessage::ChangeNick)
             }
+            source::server::Kind::ChangeMode => {
+                self.messages.contains(&CondensationMessage::ChangeMode)
+            }
             source::server::Kind::ReplyTopic
             | source::server::Kind::ChangeHost
-            | source::server::Kind::ChangeMode
             | source::server::Kind::MonitoredOnline
             | source::server::Kind::MonitoredOffline
             | source::server::Kind::StandardReply(_)
@@ -322,6 +480,16 @@ impl Condensation {
         }
     }
 
+    /// Whether joins and quits from netsplit and netjoin batches are
+    /// condensed, regardless of the other condensed messages.
+    pub fn net_changes(&self, server: &source::Server) -> bool {
+        self.messages.contains(&CondensationMessage::Netsplit)
+            && matches!(
+                server.kind(),
+                source::server::Kind::Join | source::server::Kind::Quit
+            )
+    }
+
     pub fn any(&self) -> bool {
         !self.messages.is_empty()
     }
@@ -334,6 +502,8 @@ pub enum CondensationMessage {
     Part,
     Quit,
     ChangeNick,
+    ChangeMode,
+    Netsplit,
 }
 
 #[derive(Debug, Default, Clone, Deserialize)]
@@ -451,6 +621,12 @@ impl Default for ChatHistory {
     }
 }
 
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct History {
+    pub memory_limit: Option<usize>,
+}
+
 #[derive(Debug, Copy, Clone, Default, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum UsernameFormat {
@@ -548,3 +724,33 @@ where
         Data::Float(dim) => Some(Dimmed(Some(dim))),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_colors() {
+        let formatting = |exclude: &[&str], include: &[&str]| Formatting {
+            strip_colors: true,
+            exclude: exclude.iter().map(ToString::to_string).collect(),
+            include: include.iter().map(ToString::to_string).collect(),
+        };
+
+        assert!(!Formatting::default().strips_colors(Some("#halloy")));
+        assert!(!Formatting::default().strips_colors(None));
+
+        let all = formatting(&[], &[]);
+        assert!(all.strips_colors(Some("#halloy")));
+        assert!(all.strips_colors(None));
+
+        let excluded = formatting(&["#halloy"], &[]);
+        assert!(!excluded.strips_colors(Some("#halloy")));
+        assert!(excluded.strips_colors(Some("#rust")));
+        assert!(excluded.strips_colors(None));
+
+        let only_included = formatting(&["*"], &["#halloy"]);
+        assert!(only_included.strips_colors(Some("#halloy")));
+        assert!(!only_included.strips_colors(Some("#rust")));
+    }
+}
@@ -28,6 +28,7 @@ pub struct Nicklist {
     pub position: Position,
     pub color: Color,
     pub width: Option<f32>,
+    pub compact_below: Option<f32>,
     pub alignment: Alignment,
     pub show_access_levels: bool,
     pub click: NicknameClickAction,
@@ -41,6 +42,7 @@ impl Default for Nicklist {
             position: Position::default(),
             color: Color::default(),
             width: None,
+            compact_below: None,
             alignment: Alignment::default(),
             show_access_levels: true,
             click: NicknameClickAction::default(),
@@ -0,0 +1,340 @@
+use std::str::FromStr;
+
+use fancy_regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer};
+
+use crate::user::NickRef;
+use crate::{Server, target};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ContextMenu {
+    #[serde(deserialize_with = "deserialize_user_entries")]
+    pub user: Vec<UserEntry>,
+    #[serde(deserialize_with = "deserialize_entries")]
+    pub url: Vec<UrlEntry>,
+    #[serde(deserialize_with = "deserialize_entries")]
+    pub timestamp: Vec<TimestampEntry>,
+    pub open_with: Vec<OpenWith>,
+}
+
+impl Default for ContextMenu {
+    fn default() -> Self {
+        Self {
+            user: vec![
+                UserEntry::UserInfo,
+                UserEntry::Separator,
+                UserEntry::Whois,
+                UserEntry::Query,
+                UserEntry::QueryInNewWindow,
+                UserEntry::SendFile,
+                UserEntry::DccChat,
+                UserEntry::InviteTo,
+                UserEntry::CopyHostmask,
+                UserEntry::Monitor,
+                UserEntry::Separator,
+                UserEntry::ToggleOp,
+                UserEntry::ToggleVoice,
+                UserEntry::Separator,
+                UserEntry::CtcpVersion,
+                UserEntry::CtcpTime,
+                UserEntry::CtcpPing,
+                UserEntry::CtcpClientInfo,
+                UserEntry::CtcpSource,
+                UserEntry::CtcpUserInfo,
+            ],
+            url: vec![
+                UrlEntry::OpenUrl,
+                UrlEntry::OpenWith,
+                UrlEntry::Separator,
+                UrlEntry::CopyUrl,
+                UrlEntry::CopyTitle,
+            ],
+            timestamp: vec![TimestampEntry::CopyTimestamp],
+            open_with: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserEntry {
+    UserInfo,
+    Whois,
+    Query,
+    QueryInNewWindow,
+    SendFile,
+    DccChat,
+    InviteTo,
+    CopyHostmask,
+    Monitor,
+    ToggleOp,
+    ToggleVoice,
+    CtcpVersion,
+    CtcpTime,
+    CtcpPing,
+    CtcpClientInfo,
+    CtcpSource,
+    CtcpUserInfo,
+    Separator,
+    Custom(CustomEntry),
+}
+
+impl FromStr for UserEntry {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user-info" => Ok(UserEntry::UserInfo),
+            "whois" => Ok(UserEntry::Whois),
+            "query" => Ok(UserEntry::Query),
+            "query-in-new-window" => Ok(UserEntry::QueryInNewWindow),
+            "send-file" => Ok(UserEntry::SendFile),
+            "dcc-chat" => Ok(UserEntry::DccChat),
+            "invite-to" => Ok(UserEntry::InviteTo),
+            "copy-hostmask" => Ok(UserEntry::CopyHostmask),
+            "monitor" => Ok(UserEntry::Monitor),
+            "toggle-op" => Ok(UserEntry::ToggleOp),
+            "toggle-voice" => Ok(UserEntry::ToggleVoice),
+            "ctcp-version" => Ok(UserEntry::CtcpVersion),
+            "ctcp-time" => Ok(UserEntry::CtcpTime),
+            "ctcp-ping" => Ok(UserEntry::CtcpPing),
+            "ctcp-clientinfo" => Ok(UserEntry::CtcpClientInfo),
+            "ctcp-source" => Ok(UserEntry::CtcpSource),
+            "ctcp-userinfo" => Ok(UserEntry::CtcpUserInfo),
+            SEPARATOR => Ok(UserEntry::Separator),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UrlEntry {
+    OpenUrl,
+    OpenWith,
+    CopyUrl,
+    CopyTitle,
+    Separator,
+}
+
+impl FromStr for UrlEntry {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "open-url" => Ok(UrlEntry::OpenUrl),
+            "open-with" => Ok(UrlEntry::OpenWith),
+            "copy-url" => Ok(UrlEntry::CopyUrl),
+            "copy-title" => Ok(UrlEntry::CopyTitle),
+            SEPARATOR => Ok(UrlEntry::Separator),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampEntry {
+    CopyTimestamp,
+    Separator,
+}
+
+impl FromStr for TimestampEntry {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "copy-timestamp" => Ok(TimestampEntry::CopyTimestamp),
+            SEPARATOR => Ok(TimestampEntry::Separator),
+            _ => Err(()),
+        }
+    }
+}
+
+const SEPARATOR: &str = "---";
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct CustomEntry {
+    pub label: String,
+    pub command: String,
+}
+
+impl CustomEntry {
+    /// Expands `$nick`, `$channel` and `$server` in the configured command.
+    pub fn command(
+        &self,
+        nick: NickRef,
+        channel: Option<&target::Channel>,
+        server: &Server,
+    ) -> String {
+        self.command
+            .replace("$nick", nick.as_str())
+            .replace("$channel", channel.map_or("", target::Channel::as_str))
+            .replace("$server", &server.to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OpenWith {
+    pub label: String,
+    pub command: String,
+    pub pattern: Option<Regex>,
+}
+
+impl<'de> Deserialize<'de> for OpenWith {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Inner {
+            label: String,
+            command: String,
+            #[serde(default)]
+            pattern: Option<String>,
+        }
+
+        let Inner {
+            label,
+            command,
+            pattern,
+        } = Inner::deserialize(deserializer)?;
+
+        let pattern = pattern
+            .map(|pattern| {
+                RegexBuilder::new(&pattern).build().map_err(|err| {
+                    serde::de::Error::custom(format!(
+                        "invalid regex '{pattern}': {err}"
+                    ))
+                })
+            })
+            .transpose()?;
+
+        Ok(OpenWith {
+            label,
+            command,
+            pattern,
+        })
+    }
+}
+
+impl OpenWith {
+    pub fn matches(&self, url: &str) -> bool {
+        self.pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(url).unwrap_or_default())
+    }
+
+    /// Program and arguments to run for `url`. Each `$url` argument is
+    /// replaced with the URL, which is appended when there is none. The URL
+    /// is passed as a single argument and never through a shell.
+    pub fn command(&self, url: &str) -> Option<(String, Vec<String>)> {
+        let mut parts = self.command.split_whitespace();
+
+        let program = parts.next()?.to_string();
+
+        let mut args = parts
+            .map(|arg| arg.replace("$url", url))
+            .collect::<Vec<_>>();
+
+        if !self.command.contains("$url") {
+            args.push(url.to_string());
+        }
+
+        Some((program, args))
+    }
+}
+
+fn parse_entry<T: FromStr>(id: &str) -> Option<T> {
+    let entry = id.parse().ok();
+
+    if entry.is_none() {
+        log::warn!("[config.toml] Ignoring unknown context menu entry: {id}");
+    }
+
+    entry
+}
+
+fn deserialize_user_entries<'de, D>(
+    deserializer: D,
+) -> Result<Vec<UserEntry>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Data {
+        Builtin(String),
+        Custom(CustomEntry),
+    }
+
+    let entries: Vec<Data> = Deserialize::deserialize(deserializer)?;
+
+    // Lists of only custom entries predate the built-in entries being
+    // configurable, and were appended to the default menu
+    let is_custom_only = !entries.is_empty()
+        && entries.iter().all(|entry| matches!(entry, Data::Custom(_)));
+
+    let entries = entries.into_iter().filter_map(|entry| match entry {
+        Data::Builtin(id) => parse_entry(&id),
+        Data::Custom(custom) => Some(UserEntry::Custom(custom)),
+    });
+
+    if is_custom_only {
+        Ok(ContextMenu::default()
+            .user
+            .into_iter()
+            .chain(Some(UserEntry::Separator))
+            .chain(entries)
+            .collect())
+    } else {
+        Ok(entries.collect())
+    }
+}
+
+fn deserialize_entries<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+{
+    let ids: Vec<String> = Deserialize::deserialize(deserializer)?;
+
+    Ok(ids
+        .iter()
+        .map(String::as_str)
+        .filter_map(parse_entry)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(toml: &str) -> Vec<UserEntry> {
+        toml::from_str::<ContextMenu>(toml).unwrap().user
+    }
+
+    #[test]
+    fn user_entries() {
+        let slap = UserEntry::Custom(CustomEntry {
+            label: "Slap".to_string(),
+            command: "/me slaps $nick".to_string(),
+        });
+
+        assert_eq!(
+            user(
+                r#"user = ["whois", "---", { label = "Slap", command = "/me slaps $nick" }, "unknown"]"#
+            ),
+            vec![UserEntry::Whois, UserEntry::Separator, slap.clone()]
+        );
+
+        // Only custom entries are appended to the default menu
+        let mut appended = ContextMenu::default().user;
+        appended.extend([UserEntry::Separator, slap]);
+
+        assert_eq!(
+            user(r#"user = [{ label = "Slap", command = "/me slaps $nick" }]"#),
+            appended
+        );
+
+        assert!(user("user = []").is_empty());
+    }
+}
@@ -2,6 +2,7 @@ use serde::Deserialize;
 
 use crate::buffer::{Alignment, Brackets, Color};
 use crate::config::buffer::{Away, NicknameClickAction};
+use crate::user::BridgedName;
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
@@ -15,6 +16,7 @@ pub struct Nickname {
     pub click: NicknameClickAction,
     pub shown_status: ShownStatus,
     pub truncate: Option<u16>,
+    pub bridged_display_names: bool,
 }
 
 impl Default for Nickname {
@@ -29,6 +31,17 @@ impl Default for Nickname {
             click: NicknameClickAction::default(),
             shown_status: ShownStatus::default(),
             truncate: None,
+            bridged_display_names: false,
+        }
+    }
+}
+
+impl Nickname {
+    pub fn bridged_name(&self) -> BridgedName {
+        if self.bridged_display_names {
+            BridgedName::DisplayName
+        } else {
+            BridgedName::Nickname
         }
     }
 }
@@ -1,5 +1,7 @@
 use serde::Deserialize;
 
+use crate::{User, ctcp, isupport};
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct Ctcp {
@@ -7,7 +9,17 @@ pub struct Ctcp {
     pub source: bool,
     pub time: bool,
     pub version: bool,
+    pub clientinfo: bool,
     pub userinfo: Option<String>,
+    /// Reply sent to VERSION requests instead of the client and version
+    pub version_reply: Option<String>,
+    /// Show received requests in the server buffer
+    pub log: bool,
+    /// Masks of users whose requests are never replied to
+    pub ignore: Vec<String>,
+    /// Masks of users whose requests are replied to even when the request
+    /// is disabled above
+    pub allow: Vec<String>,
 }
 
 impl Default for Ctcp {
@@ -17,7 +29,12 @@ impl Default for Ctcp {
             source: true,
             time: true,
             version: true,
+            clientinfo: true,
             userinfo: Option::default(),
+            version_reply: Option::default(),
+            log: false,
+            ignore: Vec::default(),
+            allow: Vec::default(),
         }
     }
 }
@@ -48,4 +65,93 @@ impl Ctcp {
 
         commands.join(" ")
     }
+
+    pub fn version_reply(&self, version: &str) -> String {
+        self.version_reply
+            .clone()
+            .unwrap_or_else(|| format!("Halloy {version}"))
+    }
+
+    /// Whether a `command` request from `user` should be replied to.
+    /// `ignore` takes precedence over `allow`, which in turn takes
+    /// precedence over the per-command settings.
+    pub fn replies_to(
+        &self,
+        command: &ctcp::Command,
+        user: &User,
+        casemapping: isupport::CaseMap,
+    ) -> bool {
+        let matches = |masks: &[String]| {
+            masks
+                .iter()
+                .any(|mask| user.matches_mask(mask, casemapping))
+        };
+
+        if matches(&self.ignore) {
+            return false;
+        }
+
+        match command {
+            ctcp::Command::Action
+            | ctcp::Command::DCC
+            | ctcp::Command::Unknown(_) => false,
+            ctcp::Command::UserInfo => self.userinfo.is_some(),
+            _ if matches(&self.allow) => true,
+            ctcp::Command::ClientInfo => self.clientinfo,
+            ctcp::Command::Ping => self.ping,
+            ctcp::Command::Source => self.source,
+            ctcp::Command::Version => self.version,
+            ctcp::Command::Time => self.time,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replies_to() {
+        let casemapping = isupport::CaseMap::default();
+        let friend =
+            User::parse("dan!d@friend.example.org", None, None).unwrap();
+        let stranger = User::parse("eve!e@example.net", None, None).unwrap();
+
+        let config = Ctcp {
+            version: false,
+            ignore: vec!["eve".to_string()],
+            allow: vec!["*!*@*.example.org".to_string()],
+            ..Ctcp::default()
+        };
+
+        let tests = [
+            (ctcp::Command::Version, &friend, true),
+            (ctcp::Command::Ping, &friend, true),
+            (ctcp::Command::UserInfo, &friend, false),
+            (ctcp::Command::Version, &stranger, false),
+            (ctcp::Command::Ping, &stranger, false),
+        ];
+
+        for (command, user, expected) in tests {
+            assert_eq!(
+                config.replies_to(&command, user, casemapping),
+                expected,
+                "{} from {}",
+                command.as_ref(),
+                user.nickname()
+            );
+        }
+
+        let config = Ctcp {
+            version: false,
+            ..Ctcp::default()
+        };
+
+        assert!(!config.replies_to(
+            &ctcp::Command::Version,
+            &friend,
+            casemapping
+        ));
+        assert!(config.replies_to(&ctcp::Command::Time, &friend, casemapping));
+    }
 }
@@ -17,6 +17,7 @@ pub struct Keyboard {
     pub leave_buffer: KeyBind,
     pub toggle_nick_list: KeyBind,
     pub toggle_topic: KeyBind,
+    pub toggle_density: KeyBind,
     pub toggle_sidebar: KeyBind,
     pub toggle_fullscreen: KeyBind,
     pub command_bar: KeyBind,
@@ -27,6 +28,7 @@ pub struct Keyboard {
     // Keep highlight as alias for backwards compatibility
     #[serde(alias = "highlight")]
     pub highlights: KeyBind,
+    pub search: KeyBind,
     pub scroll_up_page: KeyBind,
     pub scroll_down_page: KeyBind,
     pub scroll_to_top: KeyBind,
@@ -34,6 +36,11 @@ pub struct Keyboard {
     pub cycle_next_unread_buffer: KeyBind,
     pub cycle_previous_unread_buffer: KeyBind,
     pub mark_as_read: KeyBind,
+    /// Keybinds of the pinned buffers, by slot
+    pub focus_pinned_buffers: Vec<KeyBind>,
+    pub toggle_always_on_top: Option<KeyBind>,
+    pub toggle_mini_mode: Option<KeyBind>,
+    pub toggle_borderless: Option<KeyBind>,
     pub quit_application: Option<KeyBind>,
 }
 
@@ -53,6 +60,7 @@ impl Default for Keyboard {
             toggle_nick_list: KeyBind::toggle_nick_list(),
             toggle_sidebar: KeyBind::toggle_sidebar(),
             toggle_topic: KeyBind::toggle_topic(),
+            toggle_density: KeyBind::toggle_density(),
             toggle_fullscreen: KeyBind::toggle_fullscreen(),
             command_bar: KeyBind::command_bar(),
             reload_configuration: KeyBind::reload_configuration(),
@@ -60,6 +68,7 @@ impl Default for Keyboard {
             logs: KeyBind::logs(),
             theme_editor: KeyBind::theme_editor(),
             highlights: KeyBind::highlights(),
+            search: KeyBind::search(),
             scroll_up_page: KeyBind::scroll_up_page(),
             scroll_down_page: KeyBind::scroll_down_page(),
             scroll_to_top: KeyBind::scroll_to_top(),
@@ -68,6 +77,10 @@ impl Default for Keyboard {
             cycle_previous_unread_buffer: KeyBind::cycle_previous_unread_buffer(
             ),
             mark_as_read: KeyBind::mark_as_read(),
+            focus_pinned_buffers: KeyBind::focus_pinned_buffers(),
+            toggle_always_on_top: None,
+            toggle_mini_mode: None,
+            toggle_borderless: None,
             quit_application: None,
         }
     }
@@ -90,6 +103,7 @@ impl Keyboard {
             shortcut(self.leave_buffer.clone(), LeaveBuffer),
             shortcut(self.toggle_nick_list.clone(), ToggleNicklist),
             shortcut(self.toggle_topic.clone(), ToggleTopic),
+            shortcut(self.toggle_density.clone(), ToggleDensity),
             shortcut(self.toggle_sidebar.clone(), ToggleSidebar),
             shortcut(self.toggle_fullscreen.clone(), ToggleFullscreen),
             shortcut(self.command_bar.clone(), CommandBar),
@@ -102,6 +116,7 @@ impl Keyboard {
             shortcut(self.scroll_to_top.clone(), ScrollToTop),
             shortcut(self.scroll_to_bottom.clone(), ScrollToBottom),
             shortcut(self.highlights.clone(), Highlights),
+            shortcut(self.search.clone(), Search),
             shortcut(
                 self.cycle_next_unread_buffer.clone(),
                 CycleNextUnreadBuffer,
@@ -113,6 +128,24 @@ impl Keyboard {
             shortcut(self.mark_as_read.clone(), MarkAsRead),
         ];
 
+        shortcuts.extend(self.focus_pinned_buffers.iter().enumerate().map(
+            |(slot, key_bind)| {
+                shortcut(key_bind.clone(), FocusPinnedBuffer(slot))
+            },
+        ));
+
+        if let Some(toggle_always_on_top) = self.toggle_always_on_top.clone() {
+            shortcuts.push(shortcut(toggle_always_on_top, ToggleAlwaysOnTop));
+        }
+
+        if let Some(toggle_mini_mode) = self.toggle_mini_mode.clone() {
+            shortcuts.push(shortcut(toggle_mini_mode, ToggleMiniMode));
+        }
+
+        if let Some(toggle_borderless) = self.toggle_borderless.clone() {
+            shortcuts.push(shortcut(toggle_borderless, ToggleBorderless));
+        }
+
         if let Some(quit_application) = self.quit_application.clone() {
             shortcuts.push(shortcut(quit_application, QuitApplication));
         }
@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct MiniMode {
+    /// Width of the window when first switched to mini mode.
+    pub width: f32,
+    /// Height of the window when first switched to mini mode.
+    pub height: f32,
+}
+
+impl Default for MiniMode {
+    fn default() -> Self {
+        Self {
+            width: 480.0,
+            height: 360.0,
+        }
+    }
+}
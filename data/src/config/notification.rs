@@ -8,6 +8,10 @@ use crate::audio::Sound;
 #[serde(default)]
 pub struct Notification {
     pub show_toast: bool,
+    /// Send to the service configured in [`Notifications::push`]
+    pub push: bool,
+    /// Include in the digest configured in [`Notifications::email`]
+    pub email: bool,
     pub show_content: bool,
     pub sound: Option<String>,
     pub delay: Option<u32>,
@@ -19,6 +23,8 @@ impl Default for Notification {
     fn default() -> Self {
         Self {
             show_toast: false,
+            push: false,
+            email: false,
             show_content: false,
             sound: None,
             delay: Some(500),
@@ -54,8 +60,68 @@ pub struct Notifications {
     pub direct_message: Notification,
     pub highlight: Notification,
     pub file_transfer_request: Notification,
+    pub dcc_chat_request: Notification,
     pub monitored_online: Notification,
     pub monitored_offline: Notification,
+    pub watch: Notification,
+    pub script: Notification,
+    pub push: Option<Push>,
+    pub email: Option<Email>,
+}
+
+/// Push notification service, e.g. to reach a phone while away from the
+/// desktop running Halloy
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "service", rename_all = "kebab-case")]
+pub enum Push {
+    Ntfy {
+        #[serde(default = "default_ntfy_url")]
+        url: String,
+        topic: String,
+        #[serde(default)]
+        token: Option<String>,
+    },
+    Gotify {
+        url: String,
+        token: String,
+        #[serde(default = "default_gotify_priority")]
+        priority: u8,
+    },
+}
+
+fn default_ntfy_url() -> String {
+    String::from("https://ntfy.sh")
+}
+
+fn default_gotify_priority() -> u8 {
+    5
+}
+
+/// Periodic email digest of notifications, handed to a sendmail compatible
+/// command (e.g. sendmail or msmtp) which takes care of delivering it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Email {
+    pub to: String,
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default = "default_email_command")]
+    pub command: Vec<String>,
+    /// Minutes between digests
+    #[serde(default = "default_email_interval")]
+    pub interval: u64,
+}
+
+// `-i` keeps a line with a single dot in a message from ending the email
+fn default_email_command() -> Vec<String> {
+    vec![
+        String::from("sendmail"),
+        String::from("-t"),
+        String::from("-i"),
+    ]
+}
+
+fn default_email_interval() -> u64 {
+    15
 }
 
 impl Notifications {
@@ -95,12 +161,21 @@ impl Notifications {
         if let Some(sound_name) = self.file_transfer_request.sound.as_deref() {
             load_and_insert(sound_name);
         }
+        if let Some(sound_name) = self.dcc_chat_request.sound.as_deref() {
+            load_and_insert(sound_name);
+        }
         if let Some(sound_name) = self.monitored_online.sound.as_deref() {
             load_and_insert(sound_name);
         }
         if let Some(sound_name) = self.monitored_offline.sound.as_deref() {
             load_and_insert(sound_name);
         }
+        if let Some(sound_name) = self.watch.sound.as_deref() {
+            load_and_insert(sound_name);
+        }
+        if let Some(sound_name) = self.script.sound.as_deref() {
+            load_and_insert(sound_name);
+        }
 
         sounds
     }
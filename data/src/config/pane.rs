@@ -8,6 +8,7 @@ pub struct Pane {
     /// Default axis used when splitting a pane.
     pub split_axis: SplitAxis,
     pub scrollbar: Scrollbar,
+    pub minimap: Minimap,
     pub restore_on_launch: bool,
 }
 
@@ -16,11 +17,31 @@ impl Default for Pane {
         Self {
             split_axis: SplitAxis::default(),
             scrollbar: Scrollbar::default(),
+            minimap: Minimap::default(),
             restore_on_launch: true,
         }
     }
 }
 
+/// Strip next to the scrollbar of buffers marking where highlights, sent
+/// messages and search matches are in the history.
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(default)]
+pub struct Minimap {
+    pub enabled: bool,
+    /// Width of the strip.
+    pub width: u32,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            width: 6,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum SplitAxis {
@@ -1,4 +1,5 @@
-use serde::Deserialize;
+use fancy_regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Deserializer};
 
 use crate::{Target, isupport};
 
@@ -6,18 +7,36 @@ use crate::{Target, isupport};
 #[serde(default)]
 pub struct Preview {
     pub enabled: bool,
+    /// Memory in megabytes the thumbnails of loaded previews may use
+    /// before those no longer visible are evicted
+    pub memory_limit: usize,
     pub request: Request,
     pub card: Card,
     pub image: Image,
+    /// Commands which resolve the title of matching urls, instead of
+    /// requesting the url directly
+    pub resolvers: Vec<Resolver>,
+}
+
+impl Preview {
+    pub fn memory_limit_bytes(&self) -> usize {
+        self.memory_limit * 1024 * 1024
+    }
+
+    pub fn resolver(&self, url: &str) -> Option<&Resolver> {
+        self.resolvers.iter().find(|resolver| resolver.matches(url))
+    }
 }
 
 impl Default for Preview {
     fn default() -> Self {
         Self {
             enabled: true,
+            memory_limit: 64,
             request: Request::default(),
             card: Card::default(),
             image: Image::default(),
+            resolvers: vec![],
         }
     }
 }
@@ -64,6 +83,60 @@ impl Default for Request {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    pub pattern: Regex,
+    pub command: String,
+}
+
+impl<'de> Deserialize<'de> for Resolver {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Inner {
+            pattern: String,
+            command: String,
+        }
+
+        let Inner { pattern, command } = Inner::deserialize(deserializer)?;
+
+        let pattern = RegexBuilder::new(&pattern).build().map_err(|err| {
+            serde::de::Error::custom(format!(
+                "invalid regex '{pattern}': {err}"
+            ))
+        })?;
+
+        Ok(Resolver { pattern, command })
+    }
+}
+
+impl Resolver {
+    pub fn matches(&self, url: &str) -> bool {
+        self.pattern.is_match(url).unwrap_or_default()
+    }
+
+    /// Program and arguments to run for `url`. Each `$url` argument is
+    /// replaced with the URL, which is appended when there is none. The URL
+    /// is passed as a single argument and never through a shell.
+    pub fn command(&self, url: &str) -> Option<(String, Vec<String>)> {
+        let mut parts = self.command.split_whitespace();
+
+        let program = parts.next()?.to_string();
+
+        let mut args = parts
+            .map(|arg| arg.replace("$url", url))
+            .collect::<Vec<_>>();
+
+        if !self.command.contains("$url") {
+            args.push(url.to_string());
+        }
+
+        Some((program, args))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
 pub struct Card {
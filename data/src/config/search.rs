@@ -0,0 +1,54 @@
+use serde::Deserialize;
+
+use crate::{Message, Notification, Server, digest, history, isupport, search};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Search {
+    pub watches: Vec<Watch>,
+}
+
+/// A named search evaluated against every incoming message.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Watch {
+    pub name: String,
+    pub query: search::Query,
+    #[serde(default)]
+    pub notify: bool,
+}
+
+impl Search {
+    /// Notifications for the watches with `notify` enabled which match a
+    /// message received on `server`.
+    pub fn notifications(
+        &self,
+        server: &Server,
+        message: &Message,
+        casemapping: isupport::CaseMap,
+    ) -> Vec<Notification> {
+        let Some(kind) =
+            history::Kind::from_server_message(server.clone(), message)
+        else {
+            return vec![];
+        };
+
+        let (Some(target), Some(nick)) =
+            (kind.target(), digest::sender(message))
+        else {
+            return vec![];
+        };
+
+        self.watches
+            .iter()
+            .filter(|watch| {
+                watch.notify && watch.query.matches(&kind, message, casemapping)
+            })
+            .map(|watch| Notification::Watch {
+                name: watch.name.clone(),
+                nick: nick.to_owned(),
+                target: target.as_str().to_string(),
+                message: message.text(),
+            })
+            .collect()
+    }
+}
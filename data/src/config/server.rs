@@ -1,15 +1,19 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 
-use irc::connection;
+use halloy_core::{client, connection};
 use serde::{Deserialize, Deserializer};
 
-use crate::config;
 use crate::serde::{
     deserialize_path_buf_with_path_transformations,
     deserialize_path_buf_with_path_transformations_maybe,
 };
+use crate::target::{self, Target};
+use crate::user::User;
+use crate::{config, isupport};
 
 const DEFAULT_PORT: u16 = 6667;
 const DEFAULT_TLS_PORT: u16 = 6697;
@@ -59,6 +63,16 @@ pub struct Server {
     pub channels: Vec<String>,
     /// A mapping of channel names to keys for join-on-connect.
     pub channel_keys: HashMap<String, String>,
+    /// A mapping of channel and user names to aliases shown in their place.
+    pub aliases: HashMap<String, String>,
+    /// Theme used for the server's buffers instead of the selected theme.
+    pub theme: Option<Theme>,
+    /// A mapping of channel and user names to themes used for their buffers,
+    /// taking precedence over `theme`.
+    pub themes: HashMap<String, Theme>,
+    /// Categories of server traffic shown in their own buffer instead of
+    /// the server buffer.
+    pub sub_buffers: Vec<target::Category>,
     /// The amount of inactivity in seconds before the client will ping the server.
     pub ping_time: u64,
     /// The amount of time in seconds for a client to reconnect due to no ping response.
@@ -87,15 +101,35 @@ pub struct Server {
     root_cert_path: Option<PathBuf>,
     /// Sasl authentication
     pub sasl: Option<Sasl>,
-    /// Commands which are executed once connected.
-    pub on_connect: Vec<String>,
+    /// IRC operator credentials and actions
+    pub oper: Option<Oper>,
+    /// Services commands of the channel access list view
+    pub access_list: AccessList,
+    /// Commands which are executed once connected, along with conditions
+    /// the commands after them wait for.
+    pub on_connect: Vec<OnConnect>,
+    /// Commands which are executed after `on_connect` when reconnecting
+    /// after the connection dropped unexpectedly.
+    pub on_reconnect: Vec<OnConnect>,
+    /// Commands which are sent before quitting, as far as the connection
+    /// allows.
+    pub on_disconnect: Vec<OnConnect>,
+    /// Command aliases of the configuration, which the `on_connect` commands
+    /// can use.
+    #[serde(skip)]
+    pub command_aliases: config::Aliases,
     /// Enable WHO polling. Defaults to `true`.
     pub who_poll_enabled: bool,
     /// WHO poll interval for servers without away-notify.
     #[serde(deserialize_with = "deserialize_who_poll_interval")]
     pub who_poll_interval: Duration,
+    /// Only WHO poll channels which are open in a pane. Defaults to `true`.
+    pub who_poll_visible_only: bool,
     /// A list of nicknames to monitor (if MONITOR is supported by the server).
     pub monitor: Vec<String>,
+    /// Warn in the server buffer when connected without authenticating and
+    /// with an uncloaked hostname.
+    pub warn_exposed_hostname: bool,
     pub chathistory: bool,
     #[serde(deserialize_with = "deserialize_anti_flood")]
     pub anti_flood: Duration,
@@ -129,32 +163,77 @@ impl Server {
     pub fn connection(
         &self,
         proxy: Option<config::Proxy>,
-    ) -> connection::Config<'_> {
+    ) -> connection::Config {
         let security = if self.use_tls {
             connection::Security::Secured {
                 accept_invalid_certs: self.dangerously_accept_invalid_certs,
-                root_cert_path: self.root_cert_path.as_ref(),
+                root_cert_path: self.root_cert_path.clone(),
                 client_cert_path: self
                     .sasl
                     .as_ref()
-                    .and_then(Sasl::external_cert),
+                    .and_then(Sasl::external_cert)
+                    .cloned(),
                 client_key_path: self
                     .sasl
                     .as_ref()
-                    .and_then(Sasl::external_key),
+                    .and_then(Sasl::external_key)
+                    .cloned(),
             }
         } else {
             connection::Security::Unsecured
         };
 
         connection::Config {
-            server: &self.server,
+            server: self.server.clone(),
             port: self.port,
             security,
             proxy: proxy.map(From::from),
+            reconnect_delay: Duration::from_secs(self.reconnect_delay),
+            ping_time: Duration::from_secs(self.ping_time),
+            ping_timeout: Duration::from_secs(self.ping_timeout),
+        }
+    }
+
+    pub fn registration(&self) -> client::Registration {
+        client::Registration {
+            nickname: self.nickname.clone(),
+            username: self.username.clone(),
+            realname: self.realname.clone(),
+            password: self.password.clone(),
+            sasl: self.sasl.as_ref().map(Sasl::credentials),
+            chathistory: self.chathistory,
         }
     }
 
+    /// The alias shown in place of `target`, if one is configured
+    pub fn alias(
+        &self,
+        target: &Target,
+        casemapping: isupport::CaseMap,
+    ) -> Option<&str> {
+        self.aliases.iter().find_map(|(name, alias)| {
+            (casemapping.normalize(name) == target.as_normalized_str())
+                .then_some(alias.as_str())
+        })
+    }
+
+    /// The theme configured for the buffer of `target`, or for the server's
+    /// buffers when there is no target
+    pub fn theme(
+        &self,
+        target: Option<&Target>,
+        casemapping: isupport::CaseMap,
+    ) -> Option<&Theme> {
+        target
+            .and_then(|target| {
+                self.themes.iter().find_map(|(name, theme)| {
+                    (casemapping.normalize(name) == target.as_normalized_str())
+                        .then_some(theme)
+                })
+            })
+            .or(self.theme.as_ref())
+    }
+
     pub fn bouncer_config(&self) -> Self {
         Self {
             // nickserv info not relevant to the bounced network
@@ -175,6 +254,136 @@ impl Server {
     }
 }
 
+/// A theme name, or a pair of names following the OS light or dark mode.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Theme {
+    Static(String),
+    Dynamic { light: String, dark: String },
+}
+
+impl Theme {
+    /// Name of the theme to use while the OS is in dark mode or not
+    pub fn name(&self, is_dark: bool) -> &str {
+        match self {
+            Theme::Static(name) => name,
+            Theme::Dynamic { light, dark } => {
+                if is_dark {
+                    dark
+                } else {
+                    light
+                }
+            }
+        }
+    }
+}
+
+/// A command executed once connected, or a condition the commands after it
+/// wait for.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum OnConnect {
+    Command(String),
+    /// A command which is only executed while our nickname is `if_nick`
+    Conditional {
+        command: String,
+        if_nick: String,
+    },
+    /// Waits until `wait_until` is met, or `timeout` seconds have passed
+    WaitUntil {
+        wait_until: Condition,
+        #[serde(default = "default_wait_timeout")]
+        timeout: u64,
+    },
+}
+
+fn default_wait_timeout() -> u64 {
+    30
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    /// The end of the message of the day, which has always been received
+    /// by the time `on_connect` runs
+    MotdEnd,
+    /// Logged in to an account, e.g. after identifying with services
+    LoggedIn,
+    /// Our user modes include every mode in `set` and none in `unset`
+    Mode { set: Vec<char>, unset: Vec<char> },
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().split_once(' ') {
+            None if s.trim() == "motd-end" => Ok(Condition::MotdEnd),
+            None if s.trim() == "logged-in" => Ok(Condition::LoggedIn),
+            Some(("mode", modes)) => {
+                let mut set = vec![];
+                let mut unset = vec![];
+                let mut adding = None;
+
+                for c in modes.trim().chars() {
+                    match (c, adding) {
+                        ('+', _) => adding = Some(true),
+                        ('-', _) => adding = Some(false),
+                        (c, Some(true)) if c.is_ascii_alphabetic() => {
+                            set.push(c);
+                        }
+                        (c, Some(false)) if c.is_ascii_alphabetic() => {
+                            unset.push(c);
+                        }
+                        _ => return Err(format!("invalid modes: {modes}")),
+                    }
+                }
+
+                if set.is_empty() && unset.is_empty() {
+                    return Err(format!("invalid modes: {modes}"));
+                }
+
+                Ok(Condition::Mode { set, unset })
+            }
+            _ => Err(format!(
+                "unknown condition {s:?}, expected \"motd-end\", \
+                 \"logged-in\" or \"mode <modes>\""
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::MotdEnd => write!(f, "motd-end"),
+            Condition::LoggedIn => write!(f, "logged-in"),
+            Condition::Mode { set, unset } => {
+                write!(f, "mode ")?;
+
+                if !set.is_empty() {
+                    write!(f, "+{}", set.iter().collect::<String>())?;
+                }
+                if !unset.is_empty() {
+                    write!(f, "-{}", unset.iter().collect::<String>())?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Condition {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 impl Default for Server {
     fn default() -> Self {
         Self {
@@ -196,6 +405,10 @@ impl Default for Server {
             filters: Option::default(),
             channels: Vec::default(),
             channel_keys: HashMap::default(),
+            aliases: HashMap::default(),
+            theme: Option::default(),
+            themes: HashMap::default(),
+            sub_buffers: Vec::default(),
             ping_time: 180,
             ping_timeout: 20,
             reconnect_delay: 10,
@@ -206,10 +419,17 @@ impl Default for Server {
             dangerously_accept_invalid_certs: Default::default(),
             root_cert_path: Option::default(),
             sasl: Option::default(),
+            oper: Option::default(),
+            access_list: AccessList::default(),
             on_connect: Vec::default(),
+            on_reconnect: Vec::default(),
+            on_disconnect: Vec::default(),
+            command_aliases: config::Aliases::default(),
             who_poll_enabled: true,
             who_poll_interval: Duration::from_secs(2),
+            who_poll_visible_only: true,
             monitor: Vec::default(),
+            warn_exposed_hostname: true,
             chathistory: true,
             anti_flood: Duration::from_millis(2000),
             order: 0,
@@ -259,56 +479,16 @@ pub enum Sasl {
 }
 
 impl Sasl {
-    pub fn command(&self) -> &'static str {
-        match self {
-            Sasl::Plain { .. } => "PLAIN",
-            Sasl::External { .. } => "EXTERNAL",
-        }
-    }
-
-    pub fn params(&self) -> Vec<String> {
-        const CHUNK_SIZE: usize = 400;
-
+    fn credentials(&self) -> client::Sasl {
         match self {
             Sasl::Plain {
                 username, password, ..
-            } => {
-                use base64::engine::Engine;
-
-                let password = password
-                    .as_ref()
-                    .expect("SASL password must exist at this point!");
-
-                // Exclude authorization ID, to use the authentication ID as the authorization ID
-                // https://datatracker.ietf.org/doc/html/rfc4616#section-2
-                let encoding = base64::engine::general_purpose::STANDARD
-                    .encode(format!("\x00{username}\x00{password}"));
-
-                let chunks = encoding
-                    .as_bytes()
-                    .chunks(CHUNK_SIZE)
-                    .collect::<Vec<&[u8]>>();
-
-                let signal_end_of_response = chunks
-                    .iter()
-                    .last()
-                    .is_none_or(|chunk| chunk.len() == CHUNK_SIZE);
-
-                let mut params = chunks
-                    .into_iter()
-                    .map(|chunk| {
-                        String::from_utf8(chunk.into())
-                            .expect("chunks should be valid UTF-8")
-                    })
-                    .collect::<Vec<String>>();
-
-                if signal_end_of_response {
-                    params.push("+".into());
-                }
-
-                params
-            }
-            Sasl::External { .. } => vec!["+".into()],
+            } => client::Sasl::Plain {
+                username: username.clone(),
+                // Read from the file or command, if not given, when loaded
+                password: password.clone().unwrap_or_default(),
+            },
+            Sasl::External { .. } => client::Sasl::External,
         }
     }
 
@@ -329,10 +509,149 @@ impl Sasl {
     }
 }
 
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Oper {
+    /// Operator name sent with OPER or CHALLENGE
+    pub name: Option<String>,
+    /// Operator password
+    pub password: Option<String>,
+    /// Operator password file, of which the first line is used
+    #[serde(
+        deserialize_with = "deserialize_path_buf_with_path_transformations_maybe"
+    )]
+    pub password_file: Option<PathBuf>,
+    /// Operator password command
+    pub password_command: Option<String>,
+    /// The path to a PEM encoded RSA private key used to answer CHALLENGE
+    /// instead of sending a password with OPER
+    #[serde(
+        deserialize_with = "deserialize_path_buf_with_path_transformations_maybe"
+    )]
+    pub challenge_key: Option<PathBuf>,
+    /// Show operator actions in the user context menu once opered up
+    pub actions: bool,
+    /// Command inserted into the input by the "Kill" action
+    pub kill: String,
+    /// Command inserted into the input by the "G-line" action
+    pub gline: String,
+}
+
+impl Default for Oper {
+    fn default() -> Self {
+        Self {
+            name: None,
+            password: None,
+            password_file: None,
+            password_command: None,
+            challenge_key: None,
+            actions: false,
+            kill: "/raw KILL $nick :".to_string(),
+            gline: "/raw GLINE *@$host 1d :".to_string(),
+        }
+    }
+}
+
+impl Oper {
+    /// Answers a CHALLENGE by decrypting it with `challenge_key` and
+    /// returning the base64 encoded SHA-1 digest of the plaintext.
+    pub fn challenge_response(
+        &self,
+        challenge: &str,
+    ) -> Result<String, ChallengeError> {
+        use base64::engine::Engine;
+        use rsa::pkcs1::DecodeRsaPrivateKey;
+        use rsa::pkcs8::DecodePrivateKey;
+        use sha1::Digest;
+
+        let path = self
+            .challenge_key
+            .as_ref()
+            .ok_or(ChallengeError::MissingKey)?;
+        let pem = std::fs::read_to_string(path)
+            .map_err(|error| ChallengeError::Io(error.to_string()))?;
+        let key = rsa::RsaPrivateKey::from_pkcs1_pem(&pem)
+            .or_else(|_| rsa::RsaPrivateKey::from_pkcs8_pem(&pem))
+            .map_err(|_| ChallengeError::InvalidKey)?;
+
+        let challenge = base64::engine::general_purpose::STANDARD
+            .decode(challenge)
+            .map_err(|_| ChallengeError::InvalidChallenge)?;
+        let plaintext = key
+            .decrypt(rsa::Oaep::new::<sha1::Sha1>(), &challenge)
+            .map_err(|_| ChallengeError::InvalidChallenge)?;
+
+        Ok(base64::engine::general_purpose::STANDARD
+            .encode(sha1::Sha1::digest(plaintext)))
+    }
+
+    /// Expands `$nick`, `$username` and `$host` in an action's command.
+    /// Unknown usernames and hosts are replaced with `*`.
+    pub fn action_command(template: &str, user: &User) -> String {
+        template
+            .replace("$nick", user.nickname().as_str())
+            .replace("$username", user.username().unwrap_or("*"))
+            .replace("$host", user.hostname().unwrap_or("*"))
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ChallengeError {
+    #[error("no challenge_key is configured")]
+    MissingKey,
+    #[error("unable to read challenge_key: {0}")]
+    Io(String),
+    #[error("challenge_key is not a PEM encoded RSA private key")]
+    InvalidKey,
+    #[error("unable to decrypt the challenge with challenge_key")]
+    InvalidChallenge,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AccessList {
+    /// Nickname of the services replying to `list`
+    pub services: String,
+    /// Command listing a channel's access list
+    pub list: String,
+    /// Command adding `$mask` with `$flags` to a channel's access list
+    pub add: String,
+    /// Command removing `$mask` from a channel's access list
+    pub remove: String,
+}
+
+impl Default for AccessList {
+    fn default() -> Self {
+        Self {
+            services: "ChanServ".to_string(),
+            list: "/msg ChanServ FLAGS $channel".to_string(),
+            add: "/msg ChanServ FLAGS $channel $mask $flags".to_string(),
+            remove: "/msg ChanServ FLAGS $channel $mask -*".to_string(),
+        }
+    }
+}
+
+impl AccessList {
+    /// Expands `$channel`, `$mask` and `$flags` in one of the commands.
+    pub fn command(
+        template: &str,
+        channel: &str,
+        mask: &str,
+        flags: &str,
+    ) -> String {
+        template
+            .replace("$channel", channel)
+            .replace("$mask", mask)
+            .replace("$flags", flags)
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Deserialize, Default)]
 #[serde(default)]
 pub struct Filters {
     pub ignore: Vec<String>,
+    /// Hide messages carrying a tag, given as `key` or `key=value`
+    pub tags: Vec<String>,
 }
 
 fn deserialize_anti_flood<'de, D>(deserializer: D) -> Result<Duration, D::Error>
@@ -368,3 +687,23 @@ where
         Ok(Duration::from_secs(seconds))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_conditions() {
+        assert_eq!("motd-end".parse(), Ok(Condition::MotdEnd));
+        assert_eq!("logged-in".parse(), Ok(Condition::LoggedIn));
+        assert_eq!(
+            "mode +xr-i".parse(),
+            Ok(Condition::Mode {
+                set: vec!['x', 'r'],
+                unset: vec!['i'],
+            })
+        );
+        assert!("mode x".parse::<Condition>().is_err());
+        assert!("registered".parse::<Condition>().is_err());
+    }
+}
@@ -0,0 +1,26 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Tray {
+    /// Show an icon in the system tray.
+    pub enabled: bool,
+    /// Hide the window to the tray when it is closed, keeping servers
+    /// connected, instead of exiting.
+    pub close_to_tray: bool,
+    /// Start with the window hidden to the tray.
+    pub start_minimized: bool,
+    /// Mark the tray icon when there are unread messages or highlights.
+    pub show_unread: bool,
+}
+
+impl Default for Tray {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            close_to_tray: true,
+            start_minimized: false,
+            show_unread: true,
+        }
+    }
+}
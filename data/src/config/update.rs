@@ -0,0 +1,19 @@
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct Update {
+    /// Check for a newer release when Halloy starts.
+    pub check: bool,
+    /// Show a banner with the release notes when a newer release is found.
+    pub banner: bool,
+}
+
+impl Default for Update {
+    fn default() -> Self {
+        Self {
+            check: true,
+            banner: true,
+        }
+    }
+}
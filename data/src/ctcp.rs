@@ -1,10 +1,11 @@
 use std::fmt;
 
+use chrono::{TimeDelta, Utc};
 use irc::proto;
 
 // Reference: https://rawgit.com/DanielOaks/irc-rfcs/master/dist/draft-oakley-irc-ctcp-latest.html
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
     Action,
     ClientInfo,
@@ -88,6 +89,22 @@ pub fn format(command: &Command, params: Option<impl fmt::Display>) -> String {
     }
 }
 
+/// Timestamp sent with a PING request, echoed back in the response.
+pub fn ping_timestamp() -> String {
+    Utc::now().timestamp_millis().to_string()
+}
+
+/// Round-trip latency of a PING response, from its echoed timestamp.
+pub fn ping_latency(params: &str) -> Option<TimeDelta> {
+    let sent = params.trim().parse::<i64>().ok()?;
+
+    Utc::now()
+        .timestamp_millis()
+        .checked_sub(sent)
+        .and_then(TimeDelta::try_milliseconds)
+        .filter(|latency| *latency >= TimeDelta::zero())
+}
+
 pub fn query_command(
     command: &Command,
     target: String,
@@ -111,3 +128,15 @@ pub fn response_message(
 ) -> proto::Message {
     proto::command!("NOTICE", target, format(command, params))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ping() {
+        assert!(ping_latency(&ping_timestamp()).is_some());
+        assert_eq!(ping_latency("not a timestamp"), None);
+        assert_eq!(ping_latency(&i64::MAX.to_string()), None);
+    }
+}
@@ -3,11 +3,13 @@ use std::io;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
 
 use crate::buffer::{self, Buffer};
 use crate::pane::Pane;
 use crate::serde::fail_as_none;
-use crate::{compression, environment};
+use crate::user::NickRef;
+use crate::{Profile, Server, compression, environment, isupport, window};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Dashboard {
@@ -15,9 +17,19 @@ pub struct Dashboard {
     #[serde(default)]
     pub popout_panes: Vec<Pane>,
     #[serde(default)]
+    pub popout_windows: window::Popouts,
+    #[serde(default)]
     pub buffer_settings: BufferSettings,
+    #[serde(default)]
+    pub monitored: Monitored,
+    #[serde(default)]
+    pub pinned: Pinned,
     #[serde(default, deserialize_with = "fail_as_none")]
     pub focus_buffer: Option<Buffer>,
+    /// Buffer last focused in the main window, which differs from
+    /// `focus_buffer` when a popped out buffer has focus.
+    #[serde(default, deserialize_with = "fail_as_none")]
+    pub main_focus_buffer: Option<Buffer>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -39,6 +51,84 @@ impl BufferSettings {
     }
 }
 
+/// Nicknames monitored from the user context menu, keyed by server.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Monitored(HashMap<String, Vec<String>>);
+
+impl Monitored {
+    pub fn get(&self, server: &Server) -> &[String] {
+        self.0.get(&server.to_string()).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds `nick` to the list of `server`, unless already listed.
+    pub fn insert(
+        &mut self,
+        server: &Server,
+        nick: NickRef,
+        casemapping: isupport::CaseMap,
+    ) {
+        let nicks = self.0.entry(server.to_string()).or_default();
+
+        if !nicks.iter().any(|monitored| {
+            casemapping.normalize(monitored) == nick.as_normalized_str()
+        }) {
+            nicks.push(nick.to_string());
+        }
+    }
+
+    pub fn remove(
+        &mut self,
+        server: &Server,
+        nick: NickRef,
+        casemapping: isupport::CaseMap,
+    ) {
+        let key = server.to_string();
+
+        if let Some(nicks) = self.0.get_mut(&key) {
+            nicks.retain(|monitored| {
+                casemapping.normalize(monitored) != nick.as_normalized_str()
+            });
+
+            if nicks.is_empty() {
+                self.0.remove(&key);
+            }
+        }
+    }
+}
+
+/// Buffers pinned to the top of the sidebar, in the order they were pinned.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Pinned(Vec<buffer::Upstream>);
+
+impl Pinned {
+    pub fn iter(&self) -> impl Iterator<Item = &buffer::Upstream> {
+        self.0.iter()
+    }
+
+    pub fn contains(&self, buffer: &buffer::Upstream) -> bool {
+        self.0.contains(buffer)
+    }
+
+    /// Buffer pinned in `slot`, counting from zero.
+    pub fn get(&self, slot: usize) -> Option<&buffer::Upstream> {
+        self.0.get(slot)
+    }
+
+    /// Pins `buffer`, or unpins it if already pinned. Returns whether
+    /// `buffer` is pinned afterwards.
+    pub fn toggle(&mut self, buffer: &buffer::Upstream) -> bool {
+        if let Some(index) = self.0.iter().position(|pinned| pinned == buffer) {
+            self.0.remove(index);
+
+            false
+        } else {
+            self.0.push(buffer.clone());
+
+            true
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum BufferAction {
@@ -56,39 +146,49 @@ pub enum BufferFocusedAction {
 }
 
 impl Dashboard {
-    pub fn exists() -> Result<bool, Error> {
-        let path = path()?;
+    pub fn exists(profile: &Profile) -> Result<bool, Error> {
+        let path = path(profile)?;
 
         Ok(std::fs::exists(path)?)
     }
 
-    pub fn load() -> Result<Self, Error> {
-        let path = path()?;
+    pub fn load(profile: &Profile) -> Result<Self, Error> {
+        let path = path(profile)?;
 
         let bytes = std::fs::read(path)?;
 
         Ok(compression::decompress(&bytes)?)
     }
 
-    pub async fn save(self) -> Result<(), Error> {
-        let path = path()?;
+    pub async fn save(mut self, profile: Profile) -> Result<(), Error> {
+        let path = path(&profile)?;
+
+        self.popout_windows.record_displays();
 
         let bytes = compression::compress(&self)?;
 
-        tokio::fs::write(path, &bytes).await?;
+        // Written aside and moved into place, so a crash mid-write leaves the
+        // previous dashboard intact
+        let temporary = path.with_extension("gz.tmp");
+
+        let mut file = tokio::fs::File::create(&temporary).await?;
+        file.write_all(&bytes).await?;
+        file.sync_all().await?;
+
+        tokio::fs::rename(&temporary, &path).await?;
 
         Ok(())
     }
 }
 
-fn path() -> Result<PathBuf, Error> {
+fn path(profile: &Profile) -> Result<PathBuf, Error> {
     let parent = environment::data_dir();
 
     if !parent.exists() {
         std::fs::create_dir_all(&parent)?;
     }
 
-    Ok(parent.join("dashboard.json.gz"))
+    Ok(parent.join(profile.file_name("dashboard.json.gz")))
 }
 
 #[derive(Debug, thiserror::Error)]
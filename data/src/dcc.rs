@@ -17,6 +17,7 @@ pub fn decode(content: &str) -> Option<Command> {
 
     match args.next()?.to_lowercase().as_str() {
         "send" => Send::decode(args).map(Command::Send),
+        "chat" => Chat::decode(args).map(Command::Chat),
         cmd => Some(Command::Unsupported(cmd.to_string())),
     }
 }
@@ -24,9 +25,42 @@ pub fn decode(content: &str) -> Option<Command> {
 #[derive(Debug, Clone)]
 pub enum Command {
     Send(Send),
+    Chat(Chat),
     Unsupported(String),
 }
 
+/// Offer to open a direct chat connection on `host` and `port`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chat {
+    pub host: IpAddr,
+    pub port: NonZeroU16,
+}
+
+impl Chat {
+    fn decode<'a>(mut args: impl Iterator<Item = &'a str>) -> Option<Self> {
+        // Only the plain text "chat" protocol is supported
+        if !args.next()?.eq_ignore_ascii_case("chat") {
+            return None;
+        }
+
+        let host = args.next().and_then(decode_host)?;
+        let port = NonZeroU16::new(args.next()?.parse().ok()?)?;
+
+        Some(Self { host, port })
+    }
+
+    pub fn encode(self, target: &dyn ToString) -> proto::Message {
+        let Self { host, port } = self;
+        let host = encode_host(host);
+
+        ctcp::query_message(
+            &ctcp::Command::DCC,
+            target.to_string(),
+            Some(format!("CHAT chat {host} {port}")),
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Send {
     Reverse {
@@ -256,6 +290,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn chat_decode() {
+        let chat = decode("\u{1}DCC CHAT chat 1402301083 12350\u{1}");
+        assert!(matches!(
+            chat,
+            Some(Command::Chat(Chat { host, port }))
+                if host == IpAddr::V4(Ipv4Addr::from(1402301083))
+                    && port.get() == 12350
+        ));
+
+        // Port zero (passive chat) isn't supported
+        let args = "chat 1402301083 0";
+        assert_eq!(Chat::decode(args.split_whitespace()), None);
+
+        // Only the chat protocol is supported
+        let args = "wboard 1402301083 12350";
+        assert_eq!(Chat::decode(args.split_whitespace()), None);
+    }
+
     #[test]
     fn send_decode_numeric_filename() {
         // Succeeds because only 4 args so we know to only
@@ -0,0 +1,170 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+pub use self::manager::Manager;
+pub use self::task::Task;
+use crate::{Server, User, dcc, server};
+
+pub mod manager;
+pub mod task;
+
+/// Max # lines kept for a chat
+const MAX_LINES: usize = 10_000;
+/// # lines to truncate after hitting [`MAX_LINES`]
+const TRUNC_COUNT: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Id(u16);
+
+impl From<u16> for Id {
+    fn from(value: u16) -> Self {
+        Id(value)
+    }
+}
+
+impl From<Id> for u16 {
+    fn from(id: Id) -> Self {
+        id.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DccChat {
+    pub id: Id,
+    pub server: Server,
+    pub created_at: DateTime<Utc>,
+    pub direction: Direction,
+    pub remote_user: User,
+    pub status: Status,
+    pub lines: Vec<Line>,
+}
+
+impl DccChat {
+    pub fn is_connected(&self) -> bool {
+        matches!(self.status, Status::Connected)
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self.status, Status::Closed { .. })
+    }
+
+    fn push(&mut self, line: Line) {
+        self.lines.push(line);
+
+        if self.lines.len() > MAX_LINES {
+            self.lines
+                .drain(0..self.lines.len() - (MAX_LINES - TRUNC_COUNT));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Direction {
+    Sent,
+    Received,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// Pending approval
+    PendingApproval,
+    /// Queued (needs an open port to offer the chat)
+    Queued,
+    /// Ready (waiting for remote user to connect)
+    Ready,
+    /// Connected to the remote user
+    Connected,
+    /// Connection was closed, with the error which closed it
+    Closed { error: Option<String> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub timestamp: DateTime<Utc>,
+    pub direction: Direction,
+    pub text: String,
+    pub is_action: bool,
+}
+
+impl Line {
+    fn new(direction: Direction, text: String) -> Self {
+        let (text, is_action) = match parse_action(&text) {
+            Some(action) => (action.to_string(), true),
+            None => (text, false),
+        };
+
+        Self {
+            timestamp: Utc::now(),
+            direction,
+            text,
+            is_action,
+        }
+    }
+}
+
+/// Encodes `/me` as a CTCP ACTION, which is how actions are sent over DCC
+/// CHAT by most clients
+pub fn encode(text: &str) -> String {
+    match text.strip_prefix("/me ") {
+        Some(action) => format!("\u{1}ACTION {action}\u{1}"),
+        None => text.to_string(),
+    }
+}
+
+fn parse_action(text: &str) -> Option<&str> {
+    text.strip_prefix("\u{1}ACTION ")
+        .map(|action| action.strip_suffix('\u{1}').unwrap_or(action))
+}
+
+#[derive(Debug, Clone)]
+pub struct ReceiveRequest {
+    pub from: User,
+    pub dcc_chat: dcc::Chat,
+    pub server: Server,
+}
+
+#[derive(Debug)]
+pub struct OfferRequest {
+    pub to: User,
+    pub server: Server,
+    pub server_handle: server::Handle,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions() {
+        assert_eq!(encode("/me waves"), "\u{1}ACTION waves\u{1}");
+        assert_eq!(encode("hello /me"), "hello /me");
+
+        let line = Line::new(Direction::Received, encode("/me waves"));
+        assert!(line.is_action);
+        assert_eq!(line.text, "waves");
+
+        let line = Line::new(Direction::Received, "hello".to_string());
+        assert!(!line.is_action);
+        assert_eq!(line.text, "hello");
+    }
+
+    #[test]
+    fn truncates_lines() {
+        let mut chat = DccChat {
+            id: Id(0),
+            server: Server::from(crate::server::ServerName::from("server")),
+            created_at: Utc::now(),
+            direction: Direction::Received,
+            remote_user: User::parse("dan", None, None).unwrap(),
+            status: Status::Connected,
+            lines: vec![],
+        };
+
+        for i in 0..=MAX_LINES {
+            chat.push(Line::new(Direction::Received, i.to_string()));
+        }
+
+        assert_eq!(chat.lines.len(), MAX_LINES - TRUNC_COUNT);
+        assert_eq!(chat.lines.last().unwrap().text, MAX_LINES.to_string());
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::{HashMap, VecDeque};
+use std::num::NonZeroU16;
+use std::time::Duration;
+
+use chrono::Utc;
+use futures::StreamExt;
+use futures::stream::BoxStream;
+use rand::Rng;
+
+use super::{
+    DccChat, Direction, Id, Line, OfferRequest, ReceiveRequest, Status, Task,
+    task,
+};
+use crate::{Config, Server, User, file_transfer};
+
+struct Item {
+    chat: DccChat,
+    task: task::Handle,
+}
+
+pub enum Event {
+    NewChat(DccChat, BoxStream<'static, task::Update>),
+}
+
+#[derive(Default)]
+pub struct Manager {
+    items: HashMap<Id, Item>,
+    /// Queued = waiting for port assignment
+    queued: VecDeque<Id>,
+    used_ports: HashMap<Id, NonZeroU16>,
+}
+
+impl Manager {
+    fn get_random_id(&self) -> Id {
+        let mut rng = rand::rng();
+
+        loop {
+            let id = Id(rng.random());
+
+            if !self.items.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
+    fn server(&self, config: &Config) -> Option<file_transfer::task::Server> {
+        config.file_transfer.server.as_ref().map(|server| {
+            file_transfer::task::Server {
+                public_address: server.public_address,
+                bind_address: server.bind_address,
+            }
+        })
+    }
+
+    pub fn offer(&mut self, request: OfferRequest, config: &Config) -> Event {
+        let OfferRequest {
+            to,
+            server,
+            server_handle,
+        } = request;
+
+        log::debug!("DCC chat offer to {}", to.nickname());
+
+        let id = self.get_random_id();
+
+        let chat = DccChat {
+            id,
+            server,
+            created_at: Utc::now(),
+            direction: Direction::Sent,
+            remote_user: to.clone(),
+            // Task will trigger queued update
+            status: Status::Queued,
+            lines: vec![],
+        };
+
+        let task = Task::offer(id, to, server_handle);
+
+        self.spawn(chat, task, config)
+    }
+
+    pub fn receive(
+        &mut self,
+        request: ReceiveRequest,
+        config: &Config,
+    ) -> Event {
+        let ReceiveRequest {
+            from,
+            dcc_chat,
+            server,
+        } = request;
+
+        log::debug!("DCC chat offer received from {}", from.nickname());
+
+        let id = self.get_random_id();
+
+        let chat = DccChat {
+            id,
+            server,
+            created_at: Utc::now(),
+            direction: Direction::Received,
+            remote_user: from,
+            status: Status::PendingApproval,
+            lines: vec![],
+        };
+
+        let task = Task::receive(id, dcc_chat);
+
+        self.spawn(chat, task, config)
+    }
+
+    fn spawn(&mut self, chat: DccChat, task: Task, config: &Config) -> Event {
+        let (handle, stream) = task.spawn(
+            self.server(config),
+            Duration::from_secs(config.file_transfer.timeout),
+            config.proxy.clone(),
+        );
+
+        self.items.insert(
+            chat.id,
+            Item {
+                chat: chat.clone(),
+                task: handle,
+            },
+        );
+
+        Event::NewChat(chat, stream.boxed())
+    }
+
+    pub fn update(&mut self, update: task::Update, config: &Config) {
+        match update {
+            task::Update::Queued(id) => {
+                let available_port = self.get_available_port(config);
+
+                if let Some(Item { chat, task }) = self.items.get_mut(&id) {
+                    if let Some(port) = available_port {
+                        task.port_available(port);
+                        self.used_ports.insert(id, port);
+                    } else {
+                        // If port is not available, queue the item so it
+                        // can be assigned the next available port
+                        chat.status = Status::Queued;
+                        self.queued.push_back(id);
+                    }
+                }
+            }
+            task::Update::Ready(id) => {
+                if let Some(item) = self.items.get_mut(&id) {
+                    item.chat.status = Status::Ready;
+                }
+            }
+            task::Update::Connected(id) => {
+                if let Some(item) = self.items.get_mut(&id) {
+                    log::debug!(
+                        "DCC chat connected with {}",
+                        item.chat.remote_user.nickname()
+                    );
+                    item.chat.status = Status::Connected;
+                }
+            }
+            task::Update::Received(id, text) => {
+                if let Some(item) = self.items.get_mut(&id) {
+                    item.chat.push(Line::new(Direction::Received, text));
+                }
+            }
+            task::Update::Closed(id, error) => {
+                if let Some(item) = self.items.get_mut(&id) {
+                    if let Some(error) = &error {
+                        log::error!(
+                            "DCC chat with {} failed: {error}",
+                            item.chat.remote_user.nickname()
+                        );
+                    }
+
+                    item.chat.status = Status::Closed { error };
+
+                    self.recycle_port(id);
+                }
+            }
+        }
+    }
+
+    // Ports are taken from the end of the range, as file transfers take them
+    // from the start
+    fn get_available_port(&self, config: &Config) -> Option<NonZeroU16> {
+        let server = config.file_transfer.server.as_ref()?;
+
+        server
+            .bind_ports
+            .clone()
+            .rev()
+            .find(|port| {
+                !self.used_ports.values().any(|used| used.get() == *port)
+            })
+            .and_then(NonZeroU16::new)
+    }
+
+    fn recycle_port(&mut self, id: Id) {
+        if let Some(port) = self.used_ports.remove(&id)
+            && let Some(Item { task, chat }) = self
+                .queued
+                .pop_front()
+                .and_then(|id| self.items.get_mut(&id))
+        {
+            task.port_available(port);
+            self.used_ports.insert(chat.id, port);
+        }
+    }
+
+    pub fn approve(&mut self, id: &Id) {
+        if let Some(Item { task, .. }) = self.items.get_mut(id) {
+            task.approve();
+        }
+    }
+
+    pub fn send(&mut self, id: &Id, text: &str) {
+        if let Some(Item { chat, task }) = self.items.get_mut(id)
+            && chat.is_connected()
+        {
+            let text = super::encode(text);
+
+            task.send(text.clone());
+            chat.push(Line::new(Direction::Sent, text));
+        }
+    }
+
+    /// Closes the connection, dropping the chat and its history
+    pub fn remove(&mut self, id: &Id) {
+        let _ = self.items.remove(id);
+        self.queued.retain(|i| i != id);
+        self.recycle_port(*id);
+    }
+
+    pub fn get<'a>(&'a self, id: &Id) -> Option<&'a DccChat> {
+        self.items.get(id).map(|item| &item.chat)
+    }
+
+    /// The chat with the user which is still open, if any
+    pub fn find(&self, server: &Server, user: &User) -> Option<Id> {
+        self.items
+            .values()
+            .map(|item| &item.chat)
+            .find(|chat| {
+                !chat.is_closed()
+                    && chat.server == *server
+                    && chat.remote_user.nickname() == user.nickname()
+            })
+            .map(|chat| chat.id)
+    }
+}
@@ -0,0 +1,273 @@
+use std::num::NonZeroU16;
+use std::time::Duration;
+
+use futures::channel::mpsc::{self, Receiver, Sender};
+use futures::{SinkExt, Stream, StreamExt, stream};
+use irc::{Connection, LinesCodec, LinesCodecError, connection};
+use thiserror::Error;
+use tokio::task::JoinHandle;
+use tokio::time;
+
+use super::Id;
+use crate::file_transfer::task::Server;
+use crate::{User, config, dcc, server};
+
+/// Longest line accepted from the remote user
+const MAX_LINE_LENGTH: usize = 16 * 1024;
+
+pub struct Handle {
+    sender: Sender<Action>,
+    task: JoinHandle<()>,
+}
+
+impl Handle {
+    pub fn approve(&mut self) {
+        let _ = self.sender.try_send(Action::Approve);
+    }
+
+    pub fn port_available(&mut self, port: NonZeroU16) {
+        let _ = self.sender.try_send(Action::PortAvailable { port });
+    }
+
+    pub fn send(&mut self, text: String) {
+        let _ = self.sender.try_send(Action::Send(text));
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub enum Task {
+    Receive {
+        id: Id,
+        dcc_chat: dcc::Chat,
+    },
+    Offer {
+        id: Id,
+        remote_user: User,
+        server_handle: server::Handle,
+    },
+}
+
+impl Task {
+    pub fn receive(id: Id, dcc_chat: dcc::Chat) -> Self {
+        Self::Receive { id, dcc_chat }
+    }
+
+    pub fn offer(
+        id: Id,
+        remote_user: User,
+        server_handle: server::Handle,
+    ) -> Self {
+        Self::Offer {
+            id,
+            remote_user,
+            server_handle,
+        }
+    }
+
+    fn id(&self) -> Id {
+        match self {
+            Task::Receive { id, .. } | Task::Offer { id, .. } => *id,
+        }
+    }
+
+    pub fn spawn(
+        self,
+        server: Option<Server>,
+        timeout: Duration,
+        proxy: Option<config::Proxy>,
+    ) -> (Handle, impl Stream<Item = Update>) {
+        let (action_sender, action_receiver) = mpsc::channel(100);
+        let (update_sender, update_receiver) = mpsc::channel(100);
+
+        let task = tokio::spawn(async move {
+            let id = self.id();
+            let mut update = update_sender.clone();
+
+            let result = match self {
+                Task::Receive { id, dcc_chat } => {
+                    receive(id, dcc_chat, action_receiver, update_sender, proxy)
+                        .await
+                }
+                Task::Offer {
+                    id,
+                    remote_user,
+                    server_handle,
+                } => {
+                    offer(
+                        id,
+                        remote_user,
+                        server_handle,
+                        action_receiver,
+                        update_sender,
+                        server,
+                        timeout,
+                    )
+                    .await
+                }
+            };
+
+            let _ = update
+                .send(Update::Closed(
+                    id,
+                    result.err().as_ref().map(ToString::to_string),
+                ))
+                .await;
+        });
+
+        (
+            Handle {
+                sender: action_sender,
+                task,
+            },
+            update_receiver,
+        )
+    }
+}
+
+pub enum Action {
+    Approve,
+    PortAvailable { port: NonZeroU16 },
+    Send(String),
+}
+
+#[derive(Debug)]
+pub enum Update {
+    Queued(Id),
+    Ready(Id),
+    Connected(Id),
+    Received(Id, String),
+    Closed(Id, Option<String>),
+}
+
+async fn receive(
+    id: Id,
+    dcc_chat: dcc::Chat,
+    mut action: Receiver<Action>,
+    mut update: Sender<Update>,
+    proxy: Option<config::Proxy>,
+) -> Result<(), Error> {
+    // Wait for approval
+    let Some(Action::Approve) = action.next().await else {
+        return Ok(());
+    };
+
+    let connection = Connection::new(
+        connection::Config {
+            server: &dcc_chat.host.to_string(),
+            port: dcc_chat.port.get(),
+            security: connection::Security::Unsecured,
+            proxy: proxy.map(From::from),
+        },
+        LinesCodec::new_with_max_length(MAX_LINE_LENGTH),
+    )
+    .await?;
+
+    let _ = update.send(Update::Connected(id)).await;
+
+    chat(id, connection, action, update).await
+}
+
+async fn offer(
+    id: Id,
+    remote_user: User,
+    mut server_handle: server::Handle,
+    mut action: Receiver<Action>,
+    mut update: Sender<Update>,
+    server: Option<Server>,
+    timeout: Duration,
+) -> Result<(), Error> {
+    let server = server.ok_or(Error::OfferNoServerConfig)?;
+
+    let _ = update.send(Update::Queued(id)).await;
+
+    let Some(Action::PortAvailable { port }) = action.next().await else {
+        return Ok(());
+    };
+
+    let _ = server_handle
+        .send(
+            dcc::Chat {
+                host: server.public_address,
+                port,
+            }
+            .encode(&remote_user.nickname()),
+        )
+        .await;
+
+    let _ = update.send(Update::Ready(id)).await;
+
+    let connection = time::timeout(
+        timeout,
+        Connection::listen_and_accept(
+            server.bind_address,
+            port.get(),
+            connection::Security::Unsecured,
+            LinesCodec::new_with_max_length(MAX_LINE_LENGTH),
+        ),
+    )
+    .await
+    .map_err(|_| Error::TimeoutConnection)??;
+
+    let _ = update.send(Update::Connected(id)).await;
+
+    chat(id, connection, action, update).await
+}
+
+enum Event {
+    Line(Result<String, LinesCodecError>),
+    Send(String),
+    Disconnected,
+}
+
+async fn chat(
+    id: Id,
+    connection: Connection<LinesCodec>,
+    action: Receiver<Action>,
+    mut update: Sender<Update>,
+) -> Result<(), Error> {
+    let (mut sink, lines) = connection.split();
+
+    let sends = action.filter_map(|action| async move {
+        match action {
+            Action::Send(text) => Some(Event::Send(text)),
+            Action::Approve | Action::PortAvailable { .. } => None,
+        }
+    });
+
+    let lines = lines
+        .map(Event::Line)
+        .chain(stream::once(async { Event::Disconnected }));
+
+    let mut events = stream::select(lines, sends).boxed();
+
+    while let Some(event) = events.next().await {
+        match event {
+            Event::Line(line) => {
+                let _ = update.send(Update::Received(id, line?)).await;
+            }
+            Event::Send(text) => sink.send(text).await?,
+            Event::Disconnected => break,
+        }
+    }
+
+    let _ = sink.close().await;
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+enum Error {
+    #[error("[file_transfer.server] must be configured to offer a DCC chat")]
+    OfferNoServerConfig,
+    #[error("connection error: {0}")]
+    Connection(#[from] connection::Error),
+    #[error("chat error: {0}")]
+    Lines(#[from] LinesCodecError),
+    #[error("timed out waiting for remote to connect")]
+    TimeoutConnection,
+}
@@ -0,0 +1,209 @@
+//! Where a link leads, shown when hovering it so that disguised and
+//! look-alike links stand out.
+use thiserror::Error;
+
+/// Limit on the number of redirects followed when resolving a link
+const MAX_REDIRECTS: usize = 10;
+
+/// Second level labels commonly registered under by country code domains
+const SECOND_LEVEL_SUFFIXES: &[&str] =
+    &["ac", "co", "com", "edu", "gov", "net", "org"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Destination {
+    pub url: String,
+    pub host: Option<Host>,
+    pub warnings: Vec<Warning>,
+}
+
+/// Host of a link, split so the domain it belongs to can be emphasized over
+/// its subdomains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    pub subdomain: Option<String>,
+    pub domain: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Warning {
+    /// The host contains internationalized labels, shown in their punycode
+    /// form since they can imitate other domains
+    Punycode,
+    /// The host is an IP address rather than a domain
+    IpAddress,
+    /// A user name (or password) precedes the host, which can make a link
+    /// look like it leads to the user name
+    Credentials,
+    /// The link isn't encrypted
+    Unencrypted,
+}
+
+impl std::fmt::Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::Punycode => {
+                write!(f, "International domain, shown as punycode")
+            }
+            Warning::IpAddress => write!(f, "Links to an IP address"),
+            Warning::Credentials => {
+                write!(f, "Contains a user name before the domain")
+            }
+            Warning::Unencrypted => write!(f, "Not encrypted (http)"),
+        }
+    }
+}
+
+impl Destination {
+    /// Inspects `url`, or `None` if it can't be parsed.
+    pub fn inspect(url: &str) -> Option<Self> {
+        let parsed = url::Url::parse(url).ok()?;
+
+        let mut warnings = vec![];
+
+        if !parsed.username().is_empty() || parsed.password().is_some() {
+            warnings.push(Warning::Credentials);
+        }
+
+        if parsed.scheme() == "http" {
+            warnings.push(Warning::Unencrypted);
+        }
+
+        let host = match parsed.host() {
+            Some(url::Host::Domain(domain)) => {
+                if domain.split('.').any(|label| label.starts_with("xn--")) {
+                    warnings.push(Warning::Punycode);
+                }
+
+                Some(split_host(domain))
+            }
+            Some(host @ (url::Host::Ipv4(_) | url::Host::Ipv6(_))) => {
+                warnings.push(Warning::IpAddress);
+
+                Some(Host {
+                    subdomain: None,
+                    domain: host.to_string(),
+                })
+            }
+            None => None,
+        };
+
+        Some(Self {
+            url: parsed.to_string(),
+            host,
+            warnings,
+        })
+    }
+}
+
+/// Splits `domain` into the domain it's registered under and its
+/// subdomains.
+///
+/// There's no public suffix list to consult, so country code domains with
+/// a common second level (e.g. `co.uk`, `com.au`) are taken to be suffixes,
+/// which covers the usual cases.
+fn split_host(domain: &str) -> Host {
+    let labels = domain.trim_end_matches('.').split('.').collect::<Vec<_>>();
+
+    let suffix_labels = match labels.as_slice() {
+        [.., _, second, top]
+            if top.len() == 2 && SECOND_LEVEL_SUFFIXES.contains(second) =>
+        {
+            2
+        }
+        _ => 1,
+    };
+
+    let split = labels.len().saturating_sub(suffix_labels + 1);
+
+    Host {
+        subdomain: (split > 0).then(|| labels[..split].join(".")),
+        domain: labels[split..].join("."),
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+pub enum Error {
+    #[error("request failed: {0}")]
+    Request(String),
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Request(error.to_string())
+    }
+}
+
+/// Follows the redirects of `url` with `HEAD` requests, returning the url
+/// it ends up at.
+pub async fn resolve(url: String) -> Result<String, Error> {
+    let client = reqwest::Client::builder()
+        .user_agent("halloy")
+        .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+        .build()?;
+
+    let response = client.head(&url).send().await?;
+
+    Ok(response.url().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_subdomains() {
+        assert_eq!(
+            split_host("www.example.com"),
+            Host {
+                subdomain: Some("www".to_string()),
+                domain: "example.com".to_string(),
+            }
+        );
+        assert_eq!(
+            split_host("login.example.co.uk"),
+            Host {
+                subdomain: Some("login".to_string()),
+                domain: "example.co.uk".to_string(),
+            }
+        );
+        assert_eq!(
+            split_host("example.com"),
+            Host {
+                subdomain: None,
+                domain: "example.com".to_string(),
+            }
+        );
+        assert_eq!(
+            split_host("bit.ly"),
+            Host {
+                subdomain: None,
+                domain: "bit.ly".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn warns_about_disguised_links() {
+        let destination =
+            Destination::inspect("http://paypal.com@xn--pypal-4ve.com/login")
+                .expect("inspect url");
+
+        assert_eq!(
+            destination.warnings,
+            vec![
+                Warning::Credentials,
+                Warning::Unencrypted,
+                Warning::Punycode
+            ]
+        );
+        assert_eq!(
+            destination.host.map(|host| host.domain),
+            Some("xn--pypal-4ve.com".to_string())
+        );
+
+        let destination =
+            Destination::inspect("https://192.168.0.1/").expect("inspect url");
+
+        assert_eq!(destination.warnings, vec![Warning::IpAddress]);
+    }
+}
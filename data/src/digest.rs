@@ -0,0 +1,104 @@
+use chrono::TimeDelta;
+
+use crate::config::buffer::Digest as Config;
+use crate::history::ReadMarker;
+use crate::message::{self, Source};
+use crate::user::NickRef;
+
+/// Summary of the unread messages in a buffer, shown when returning to it
+/// after a while.
+#[derive(Debug, Default)]
+pub struct Digest<'a> {
+    pub highlights: Vec<&'a message::Message>,
+    pub questions: Vec<&'a message::Message>,
+    pub urls: Vec<(&'a message::Message, &'a url::Url)>,
+}
+
+impl Digest<'_> {
+    pub fn is_empty(&self) -> bool {
+        self.highlights.is_empty()
+            && self.questions.is_empty()
+            && self.urls.is_empty()
+    }
+}
+
+/// Builds a digest of the unread `messages` (in chronological order), if
+/// they span at least the configured threshold since the `read_marker`.
+pub fn collect<'a>(
+    messages: &[&'a message::Message],
+    read_marker: Option<ReadMarker>,
+    config: &Config,
+) -> Option<Digest<'a>> {
+    if !config.enabled {
+        return None;
+    }
+
+    let read_marker = read_marker?;
+    let last = messages.last()?;
+
+    let threshold = i64::try_from(config.threshold)
+        .ok()
+        .and_then(TimeDelta::try_minutes)
+        .unwrap_or(TimeDelta::MAX);
+
+    if last.server_time - read_marker.date_time() < threshold {
+        return None;
+    }
+
+    let mut digest = Digest::default();
+
+    for message in messages.iter().copied() {
+        if !message.triggers_unread() {
+            continue;
+        }
+
+        if message.triggers_highlight() {
+            if is_question(&message.text()) {
+                digest.questions.push(message);
+            } else {
+                digest.highlights.push(message);
+            }
+        }
+
+        if let message::Content::Fragments(fragments) = &message.content {
+            for url in fragments.iter().filter_map(message::Fragment::url) {
+                if !digest.urls.iter().any(|(_, seen)| *seen == url) {
+                    digest.urls.push((message, url));
+                }
+            }
+        }
+    }
+
+    (!digest.is_empty()).then_some(digest)
+}
+
+/// The nickname of the user who sent `message`, if any.
+pub fn sender(message: &message::Message) -> Option<NickRef<'_>> {
+    match message.target.source() {
+        Source::User(user) | Source::Action(Some(user)) => {
+            Some(user.nickname())
+        }
+        _ => None,
+    }
+}
+
+fn is_question(text: &str) -> bool {
+    text.trim_end()
+        .trim_end_matches(|c: char| !c.is_alphanumeric() && c != '?')
+        .ends_with('?')
+        || text.contains("? ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn question() {
+        assert!(is_question("nick: are you there?"));
+        assert!(is_question("nick: are you there? :)"));
+        assert!(is_question("nick: did you see this? https://example.com"));
+        assert!(!is_question("nick: ping"));
+        assert!(!is_question("nick: see https://example.com/?q=1"));
+    }
+}
@@ -1,4 +1,4 @@
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -8,6 +8,7 @@ use chrono::{DateTime, Utc};
 use futures::future::BoxFuture;
 use futures::{Future, FutureExt};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 use tokio::time::Instant;
 
 pub use self::manager::{Manager, Resource};
@@ -16,10 +17,11 @@ use crate::message::{self, MessageReferences, Source};
 use crate::target::{self, Target};
 use crate::user::Nick;
 use crate::{
-    Buffer, Message, Server, buffer, compression, environment, isupport,
+    Buffer, Message, Server, buffer, compression, environment, isupport, search,
 };
 
 pub mod filter;
+mod journal;
 pub mod manager;
 pub mod metadata;
 
@@ -30,10 +32,13 @@ const MAX_MESSAGES: usize = 10_000;
 const TRUNC_COUNT: usize = 500;
 /// Duration to wait after receiving last message before flushing
 const FLUSH_AFTER_LAST_RECEIVED: Duration = Duration::from_secs(5);
+/// # messages kept in memory when a history's older messages are evicted
+const RETAIN_ON_EVICT: usize = 500;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Kind {
     Server(Server),
+    ServerCategory(Server, target::Category),
     Channel(Server, target::Channel),
     Query(Server, target::Query),
     Logs,
@@ -64,6 +69,9 @@ impl Kind {
     pub fn from_input_buffer(buffer: buffer::Upstream) -> Self {
         match buffer {
             buffer::Upstream::Server(server) => Self::Server(server),
+            buffer::Upstream::ServerCategory(server, category) => {
+                Self::ServerCategory(server, category)
+            }
             buffer::Upstream::Channel(server, channel) => {
                 Self::Channel(server, channel)
             }
@@ -77,6 +85,9 @@ impl Kind {
     ) -> Option<Self> {
         match &message.target {
             message::Target::Server { .. } => Some(Self::Server(server)),
+            message::Target::ServerCategory { category, .. } => {
+                Some(Self::ServerCategory(server, *category))
+            }
             message::Target::Channel { channel, .. } => {
                 Some(Self::Channel(server, channel.clone()))
             }
@@ -93,6 +104,10 @@ impl Kind {
             Buffer::Upstream(buffer::Upstream::Server(server)) => {
                 Some(Kind::Server(server))
             }
+            Buffer::Upstream(buffer::Upstream::ServerCategory(
+                server,
+                category,
+            )) => Some(Kind::ServerCategory(server, category)),
             Buffer::Upstream(buffer::Upstream::Channel(server, channel)) => {
                 Some(Kind::Channel(server, channel))
             }
@@ -103,7 +118,12 @@ impl Kind {
             Buffer::Internal(buffer::Internal::Highlights) => {
                 Some(Kind::Highlights)
             }
-            Buffer::Internal(buffer::Internal::FileTransfers) => None,
+            Buffer::Internal(
+                buffer::Internal::FileTransfers
+                | buffer::Internal::Search
+                | buffer::Internal::Admin,
+            )
+            | Buffer::DccChat(_) => None,
         }
     }
 }
@@ -112,6 +132,7 @@ impl Kind {
     pub fn server(&self) -> Option<&Server> {
         match self {
             Kind::Server(server) => Some(server),
+            Kind::ServerCategory(server, _) => Some(server),
             Kind::Channel(server, _) => Some(server),
             Kind::Query(server, _) => Some(server),
             Kind::Logs => None,
@@ -122,6 +143,7 @@ impl Kind {
     pub fn target(&self) -> Option<Target> {
         match self {
             Kind::Server(_) => None,
+            Kind::ServerCategory(..) => None,
             Kind::Channel(_, channel) => Some(Target::Channel(channel.clone())),
             Kind::Query(_, nick) => Some(Target::Query(nick.clone())),
             Kind::Logs => None,
@@ -134,6 +156,9 @@ impl fmt::Display for Kind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Kind::Server(server) => write!(f, "server on {server}"),
+            Kind::ServerCategory(server, category) => {
+                write!(f, "{category} on {server}")
+            }
             Kind::Channel(server, channel) => {
                 write!(f, "channel {channel} on {server}")
             }
@@ -150,6 +175,9 @@ impl From<Kind> for Buffer {
             Kind::Server(server) => {
                 Buffer::Upstream(buffer::Upstream::Server(server))
             }
+            Kind::ServerCategory(server, category) => Buffer::Upstream(
+                buffer::Upstream::ServerCategory(server, category),
+            ),
             Kind::Channel(server, channel) => {
                 Buffer::Upstream(buffer::Upstream::Channel(server, channel))
             }
@@ -176,7 +204,17 @@ pub enum Seed {
 pub async fn load(kind: Kind, seed: Option<Seed>) -> Result<Loaded, Error> {
     let path = path(&kind).await?;
 
-    let mut messages = read_all(&path).await.unwrap_or_default();
+    let mut messages = match read_all(&path).await {
+        Ok(messages) => messages,
+        Err(Error::Io(error)) if error.kind() == io::ErrorKind::NotFound => {
+            vec![]
+        }
+        Err(error) => return Err(error),
+    };
+
+    for message in journal::read(&kind).await? {
+        insert_message(&mut messages, message);
+    }
 
     if let Some(seed) = seed {
         // TODO: Utilize DeserializeSeed (or equivalent) so proper normalization
@@ -189,6 +227,38 @@ pub async fn load(kind: Kind, seed: Option<Seed>) -> Result<Loaded, Error> {
     Ok(Loaded { messages, metadata })
 }
 
+/// Messages on disk of the histories `kinds` matching `query`, newest
+/// first. Searches what isn't loaded into memory, which
+/// [`Manager::search`] covers.
+pub async fn search(
+    kinds: Vec<(Kind, isupport::CaseMap)>,
+    query: search::Query,
+    limit: usize,
+) -> Vec<(Kind, Message)> {
+    let mut results = vec![];
+
+    for (kind, casemapping) in kinds {
+        match load(kind.clone(), Some(Seed::Single(casemapping))).await {
+            Ok(Loaded { messages, .. }) => results.extend(
+                messages
+                    .into_iter()
+                    .filter(|message| {
+                        query.matches(&kind, message, casemapping)
+                    })
+                    .map(|message| (kind.clone(), message)),
+            ),
+            Err(error) => {
+                log::warn!("failed to search history of {kind}: {error}");
+            }
+        }
+    }
+
+    results.sort_by_key(|(_, message)| Reverse(message.server_time));
+    results.truncate(limit);
+
+    results
+}
+
 pub fn renormalize_messages(messages: &mut [Message], seed: Seed) {
     match seed {
         Seed::Multiple(casemappings) => {
@@ -209,6 +279,8 @@ pub fn renormalize_messages(messages: &mut [Message], seed: Seed) {
     }
 }
 
+/// Replaces the history file with `messages`, dropping the sealed journal.
+/// Records appended after the journal is sealed are kept in a new journal.
 pub async fn overwrite(
     kind: &Kind,
     messages: &[Message],
@@ -220,38 +292,111 @@ pub async fn overwrite(
 
     let latest = &messages[messages.len().saturating_sub(MAX_MESSAGES)..];
 
+    journal::seal(kind).await?;
+
     let path = path(kind).await?;
     let compressed = compression::compress(&latest)?;
 
-    fs::write(path, &compressed).await?;
+    // Written aside and moved into place, so a crash mid-write leaves the
+    // previous history file intact
+    let temporary = path.with_extension("gz.tmp");
+
+    let mut file = fs::File::create(&temporary).await?;
+    file.write_all(&compressed).await?;
+    file.sync_all().await?;
+
+    fs::rename(&temporary, &path).await?;
+
+    journal::delete_sealed(kind).await?;
 
     metadata::save(kind, latest, read_marker).await?;
 
     Ok(())
 }
 
+/// Appends `messages` to the journal, compacting it into the history file
+/// once it grows past [`journal::COMPACT_AFTER_BYTES`]
 pub async fn append(
     kind: &Kind,
     seed: Option<Seed>,
     messages: Vec<Message>,
     read_marker: Option<ReadMarker>,
 ) -> Result<(), Error> {
-    let loaded = load(kind.clone(), seed).await?;
+    let journal_size = if messages.is_empty() {
+        0
+    } else {
+        journal::append(kind, &messages).await?
+    };
 
-    let mut all_messages = loaded.messages;
-    messages.into_iter().for_each(|message| {
-        insert_message(&mut all_messages, message);
-    });
+    metadata::append(kind, &messages, read_marker).await?;
+
+    if journal_size >= journal::COMPACT_AFTER_BYTES {
+        compact(kind, seed).await?;
+    }
+
+    Ok(())
+}
+
+/// Folds the journal into the history file
+async fn compact(kind: &Kind, seed: Option<Seed>) -> Result<(), Error> {
+    journal::seal(kind).await?;
 
-    overwrite(kind, &all_messages, read_marker).await
+    let Loaded { messages, metadata } = load(kind.clone(), seed).await?;
+
+    if messages.is_empty() {
+        return journal::delete_sealed(kind).await;
+    }
+
+    overwrite(kind, &messages, metadata.read_marker).await
+}
+
+/// Moves the history of a renamed channel from `old` to `new`, merging it
+/// with any history `new` already has
+pub async fn rename(
+    old: &Kind,
+    new: &Kind,
+    seed: Option<Seed>,
+) -> Result<(), Error> {
+    if !path(old).await?.exists() && !journal::exists(old).await? {
+        return Ok(());
+    }
+
+    let Loaded {
+        mut messages,
+        metadata,
+    } = load(old.clone(), seed).await?;
+
+    if let Kind::Channel(_, channel) = new {
+        retarget_messages(&mut messages, channel);
+    }
+
+    append(new, None, messages, metadata.read_marker).await?;
+
+    delete(old).await
+}
+
+fn retarget_messages(messages: &mut [Message], channel: &target::Channel) {
+    for message in messages {
+        if let message::Target::Channel {
+            channel: target, ..
+        } = &mut message.target
+        {
+            *target = channel.clone();
+        }
+    }
 }
 
 pub async fn delete(kind: &Kind) -> Result<(), Error> {
-    let path = path(kind).await?;
+    journal::delete(kind).await?;
 
-    fs::remove_file(path).await?;
+    let path = path(kind).await?;
 
-    Ok(())
+    match fs::remove_file(path).await {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => {
+            Err(error.into())
+        }
+        _ => Ok(()),
+    }
 }
 
 async fn read_all(path: &PathBuf) -> Result<Vec<Message>, Error> {
@@ -260,7 +405,12 @@ async fn read_all(path: &PathBuf) -> Result<Vec<Message>, Error> {
 }
 
 pub async fn dir_path() -> Result<PathBuf, Error> {
-    let data_dir = environment::data_dir();
+    // Tests keep their history out of the user's data directory
+    let data_dir = if cfg!(test) {
+        std::env::temp_dir().join(format!("halloy-test-{}", std::process::id()))
+    } else {
+        environment::data_dir()
+    };
 
     let history_dir = data_dir.join("history");
 
@@ -271,11 +421,14 @@ pub async fn dir_path() -> Result<PathBuf, Error> {
     Ok(history_dir)
 }
 
-async fn path(kind: &Kind) -> Result<PathBuf, Error> {
+pub async fn path(kind: &Kind) -> Result<PathBuf, Error> {
     let dir = dir_path().await?;
 
     let name = match kind {
         Kind::Server(server) => format!("{server:b}"),
+        Kind::ServerCategory(server, category) => {
+            format!("{server:b}category{}", category.key())
+        }
         Kind::Channel(server, channel) => {
             format!("{server:b}channel{}", channel.as_normalized_str())
         }
@@ -310,6 +463,10 @@ pub enum History {
         read_marker: Option<ReadMarker>,
         last_seen: HashMap<Nick, DateTime<Utc>>,
         cleared: bool,
+        /// Older messages were evicted from memory and are only on disk
+        evicted: bool,
+        /// Messages added or changed since the history was last written
+        unsaved: Vec<Message>,
     },
 }
 
@@ -412,6 +569,10 @@ impl History {
                 (*max_triggers_highlight).max(Some(message.server_time));
         }
 
+        if let History::Full { unsaved, .. } = self {
+            unsaved.push(message.clone());
+        }
+
         match self {
             History::Partial {
                 messages,
@@ -441,133 +602,272 @@ impl History {
         now: Option<Instant>,
         seed: Option<Seed>,
     ) -> Option<BoxFuture<'static, Result<(), Error>>> {
-        match self {
-            History::Partial {
-                kind,
-                messages,
-                last_updated_at,
-                read_marker,
-                ..
-            } => {
-                if let Some(last_received) = *last_updated_at
-                    && now.is_none_or(|now| {
-                        now.duration_since(last_received)
-                            >= FLUSH_AFTER_LAST_RECEIVED
-                    })
-                {
-                    let kind = kind.clone();
-                    let messages = std::mem::take(messages);
-                    let read_marker = *read_marker;
+        let (History::Partial {
+            last_updated_at, ..
+        }
+        | History::Full {
+            last_updated_at, ..
+        }) = self;
 
-                    *last_updated_at = None;
+        let last_received = (*last_updated_at)?;
 
-                    return Some(
-                        async move {
-                            append(&kind, seed, messages, read_marker).await
-                        }
-                        .boxed(),
-                    );
-                }
+        if now.is_some_and(|now| {
+            now.duration_since(last_received) < FLUSH_AFTER_LAST_RECEIVED
+        }) {
+            return None;
+        }
 
-                None
-            }
+        *last_updated_at = None;
+
+        match self {
+            History::Partial { .. } => {}
             History::Full {
-                kind,
                 messages,
-                last_updated_at,
-                read_marker,
+                cleared,
+                unsaved,
                 ..
             } => {
-                if let Some(last_received) = *last_updated_at
-                    && now.is_none_or(|now| {
-                        now.duration_since(last_received)
-                            >= FLUSH_AFTER_LAST_RECEIVED
-                    })
-                    && !messages.is_empty()
-                {
-                    let kind = kind.clone();
-                    let read_marker = *read_marker;
-                    *last_updated_at = None;
-
-                    if messages.len() > MAX_MESSAGES {
-                        messages.drain(
-                            0..messages.len() - (MAX_MESSAGES - TRUNC_COUNT),
-                        );
-                    }
-
-                    let messages = messages.clone();
-
-                    return Some(
-                        async move {
-                            overwrite(&kind, &messages, read_marker).await
-                        }
-                        .boxed(),
+                if messages.len() > MAX_MESSAGES {
+                    messages.drain(
+                        0..messages.len() - (MAX_MESSAGES - TRUNC_COUNT),
                     );
                 }
 
-                None
+                let nothing_to_save = if *cleared {
+                    messages.is_empty()
+                } else {
+                    unsaved.is_empty()
+                };
+
+                if nothing_to_save {
+                    return None;
+                }
             }
         }
+
+        Some(self.save(seed))
     }
 
-    fn make_partial(
+    /// Takes what isn't on disk yet, returning the write that persists it
+    fn save(
         &mut self,
-    ) -> Option<impl Future<Output = Result<(), Error>> + use<>> {
+        seed: Option<Seed>,
+    ) -> BoxFuture<'static, Result<(), Error>> {
         match self {
-            History::Partial { .. } => None,
-            History::Full {
+            History::Partial {
                 kind,
                 messages,
                 read_marker,
-                last_seen,
                 ..
             } => {
                 let kind = kind.clone();
                 let messages = std::mem::take(messages);
                 let read_marker = *read_marker;
-                let max_triggers_unread =
-                    metadata::latest_triggers_unread(&messages);
-                let max_triggers_highlight =
-                    metadata::latest_triggers_highlight(&messages);
-
-                let chathistory_references =
-                    metadata::latest_can_reference(&messages);
-
-                *self = Self::Partial {
-                    kind: kind.clone(),
-                    messages: vec![],
-                    last_updated_at: None,
-                    read_marker,
-                    max_triggers_unread,
-                    max_triggers_highlight,
-                    chathistory_references,
-                    last_seen: last_seen.clone(),
-                };
 
-                Some(
-                    async move { overwrite(&kind, &messages, read_marker).await },
-                )
+                async move { append(&kind, seed, messages, read_marker).await }
+                    .boxed()
+            }
+            // Once cleared, the history file is replaced by what was
+            // received since
+            History::Full {
+                kind,
+                messages,
+                read_marker,
+                cleared: true,
+                unsaved,
+                ..
+            } => {
+                let kind = kind.clone();
+                let messages = messages.clone();
+                let read_marker = *read_marker;
+
+                unsaved.clear();
+
+                async move { overwrite(&kind, &messages, read_marker).await }
+                    .boxed()
+            }
+            History::Full {
+                kind,
+                read_marker,
+                unsaved,
+                ..
+            } => {
+                let kind = kind.clone();
+                let unsaved = std::mem::take(unsaved);
+                let read_marker = *read_marker;
+
+                async move { append(&kind, seed, unsaved, read_marker).await }
+                    .boxed()
+            }
+        }
+    }
+
+    fn make_partial(
+        &mut self,
+        seed: Option<Seed>,
+    ) -> Option<impl Future<Output = Result<(), Error>> + use<>> {
+        if let History::Partial { .. } = self {
+            return None;
+        }
+
+        let save = self.save(seed);
+
+        let History::Full {
+            kind,
+            messages,
+            read_marker,
+            last_seen,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let messages = std::mem::take(messages);
+        let max_triggers_unread = metadata::latest_triggers_unread(&messages);
+        let max_triggers_highlight =
+            metadata::latest_triggers_highlight(&messages);
+        let chathistory_references = metadata::latest_can_reference(&messages);
+
+        *self = Self::Partial {
+            kind: kind.clone(),
+            messages: vec![],
+            last_updated_at: None,
+            read_marker: *read_marker,
+            max_triggers_unread,
+            max_triggers_highlight,
+            chathistory_references,
+            last_seen: last_seen.clone(),
+        };
+
+        Some(save)
+    }
+
+    /// Drops all but the latest messages from memory, returning the write
+    /// that keeps them on disk
+    fn evict(
+        &mut self,
+        seed: Option<Seed>,
+    ) -> Option<BoxFuture<'static, Result<(), Error>>> {
+        let History::Full {
+            messages, cleared, ..
+        } = self
+        else {
+            return None;
+        };
+
+        if messages.len() <= RETAIN_ON_EVICT || *cleared {
+            return None;
+        }
+
+        let save = self.save(seed);
+
+        if let History::Full {
+            messages,
+            last_updated_at,
+            evicted,
+            ..
+        } = self
+        {
+            messages.drain(0..messages.len() - RETAIN_ON_EVICT);
+
+            *last_updated_at = None;
+            *evicted = true;
+        }
+
+        Some(save)
+    }
+
+    /// Moves the history to the renamed channel of `new`
+    fn rename(&mut self, new: Kind) {
+        if let Kind::Channel(_, channel) = &new {
+            match self {
+                History::Partial { messages, .. } => {
+                    retarget_messages(messages, channel);
+                }
+                History::Full {
+                    messages, unsaved, ..
+                } => {
+                    retarget_messages(messages, channel);
+                    retarget_messages(unsaved, channel);
+                }
             }
         }
+
+        let (History::Partial { kind, .. } | History::Full { kind, .. }) = self;
+
+        *kind = new;
     }
 
-    async fn close(self, seed: Option<Seed>) -> Result<(), Error> {
+    /// Takes in the messages of a history whose target now normalizes to
+    /// the same name, e.g. after the server's `CASEMAPPING` changed
+    fn merge(&mut self, other: History) {
+        let (other_messages, other_unsaved, other_read_marker) = match other {
+            History::Partial {
+                messages,
+                read_marker,
+                ..
+            } => (vec![], messages, read_marker),
+            History::Full {
+                messages,
+                read_marker,
+                unsaved,
+                ..
+            } => (messages, unsaved, read_marker),
+        };
+
         match self {
             History::Partial {
-                kind,
                 messages,
                 read_marker,
                 ..
-            } => append(&kind, seed, messages, read_marker).await,
+            } => {
+                for message in other_unsaved {
+                    insert_message(messages, message);
+                }
+
+                *read_marker = (*read_marker).max(other_read_marker);
+            }
             History::Full {
-                kind,
                 messages,
                 read_marker,
+                evicted,
+                unsaved,
                 ..
-            } => overwrite(&kind, &messages, read_marker).await,
+            } => {
+                for message in other_messages {
+                    insert_message(messages, message);
+                }
+                unsaved.extend(other_unsaved);
+
+                *read_marker = (*read_marker).max(other_read_marker);
+
+                // The merged history on disk holds messages that aren't in
+                // memory, so scrolling up reloads them
+                *evicted = true;
+            }
         }
     }
 
+    pub fn is_evicted(&self) -> bool {
+        matches!(self, History::Full { evicted: true, .. })
+    }
+
+    pub fn revision(&self) -> Revision {
+        let messages = self.messages();
+
+        Revision {
+            len: messages.len(),
+            first: messages.first().map(|message| message.hash),
+            last: messages.last().map(|message| message.hash),
+            is_full: matches!(self, History::Full { .. }),
+        }
+    }
+
+    async fn close(mut self, seed: Option<Seed>) -> Result<(), Error> {
+        self.save(seed).await
+    }
+
     pub fn mark_as_read(&mut self) -> Option<ReadMarker> {
         let (read_marker, latest) = match self {
             History::Partial {
@@ -611,6 +911,13 @@ impl History {
         }
     }
 
+    pub fn messages(&self) -> &[Message] {
+        match self {
+            History::Partial { messages, .. }
+            | History::Full { messages, .. } => messages,
+        }
+    }
+
     pub fn first_can_reference(&self) -> Option<&Message> {
         match self {
             History::Partial { messages, .. }
@@ -673,10 +980,77 @@ impl History {
         }
     }
 
+    /// Counts the unread messages and highlights sent between `start` and
+    /// `end` (inclusive) which are newer than the read marker.
+    pub fn backfill(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Backfill {
+        let read_marker = self.read_marker();
+
+        let unread = self
+            .messages()
+            .iter()
+            .filter(|message| {
+                !message.blocked
+                    && (start..=end).contains(&message.server_time)
+                    && read_marker.is_none_or(|read_marker| {
+                        read_marker.date_time() < message.server_time
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        Backfill {
+            end,
+            unread: unread
+                .iter()
+                .filter(|message| message.triggers_unread())
+                .count(),
+            highlights: unread
+                .iter()
+                .filter(|message| message.triggers_highlight())
+                .count(),
+        }
+    }
+
+    /// Summarizes the loaded messages, for showing alongside a buffer
+    /// without opening it.
+    pub fn activity(&self) -> Activity {
+        let read_marker = self.read_marker();
+
+        let mut activity = Activity::default();
+
+        for message in self.messages().iter().filter(|message| !message.blocked)
+        {
+            if matches!(
+                message.target.source(),
+                Source::User(_) | Source::Action(_)
+            ) {
+                activity.last_message = Some(message.server_time);
+            }
+
+            if message.triggers_unread()
+                && read_marker.is_none_or(|read_marker| {
+                    read_marker.date_time() < message.server_time
+                })
+            {
+                activity.unread += 1;
+
+                if message.triggers_highlight() {
+                    activity.highlights += 1;
+                }
+            }
+        }
+
+        activity
+    }
+
     pub fn hide_preview(&mut self, message: message::Hash, url: url::Url) {
         if let Self::Full {
             messages,
             last_updated_at,
+            unsaved,
             ..
         } = self
             && let Some(message) =
@@ -684,6 +1058,8 @@ impl History {
         {
             message.hidden_urls.insert(url);
 
+            unsaved.push(message.clone());
+
             *last_updated_at = Some(Instant::now());
         }
     }
@@ -893,6 +1269,49 @@ pub struct View<'a> {
     pub cleared: bool,
 }
 
+/// Identifies the messages of a history, changing as messages are added,
+/// removed or loaded, so what's derived from them can be cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Revision {
+    len: usize,
+    first: Option<message::Hash>,
+    last: Option<message::Hash>,
+    is_full: bool,
+}
+
+/// Unread messages of a history and when the last message was sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Activity {
+    /// Server time of the last message sent by a user
+    pub last_message: Option<DateTime<Utc>>,
+    /// Messages which trigger unread, newer than the read marker
+    pub unread: usize,
+    /// Messages which trigger highlight, newer than the read marker
+    pub highlights: usize,
+}
+
+/// Missed messages received through chat history or bouncer playback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backfill {
+    /// Server time of the last backfilled message
+    pub end: DateTime<Utc>,
+    /// Backfilled messages which trigger unread, newer than the read marker
+    pub unread: usize,
+    /// Backfilled messages which trigger highlight, newer than the read marker
+    pub highlights: usize,
+}
+
+impl Backfill {
+    /// Combines consecutive backfills of the same history.
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            end: self.end.max(other.end),
+            unread: self.unread + other.unread,
+            highlights: self.highlights + other.highlights,
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -902,3 +1321,149 @@ pub enum Error {
     #[error(transparent)]
     SerdeJson(#[from] serde_json::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn kind(name: &str) -> Kind {
+        Kind::Server(Server::from(Arc::<str>::from(name)))
+    }
+
+    fn message(text: &str) -> Message {
+        Message::sent(
+            message::Target::Server {
+                source: Source::Server(None),
+            },
+            message::plain(text.to_string()),
+        )
+    }
+
+    fn texts(messages: &[Message]) -> Vec<String> {
+        messages.iter().map(Message::text).collect()
+    }
+
+    #[test]
+    fn revision_follows_messages() {
+        let mut history = History::partial(kind("revision"));
+        let empty = history.revision();
+
+        history.add_message(message("first"));
+        let first = history.revision();
+        assert_ne!(first, empty);
+        assert_eq!(history.revision(), first);
+
+        history.add_message(message("second"));
+        assert_ne!(history.revision(), first);
+    }
+
+    #[tokio::test]
+    async fn append_then_load() {
+        let kind = kind("append-then-load");
+
+        append(&kind, None, vec![message("first")], None)
+            .await
+            .unwrap();
+        append(&kind, None, vec![message("second")], None)
+            .await
+            .unwrap();
+
+        let Loaded { messages, .. } = load(kind.clone(), None).await.unwrap();
+
+        assert_eq!(texts(&messages), vec!["first", "second"]);
+        assert!(journal::exists(&kind).await.unwrap());
+
+        delete(&kind).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn compacts_journal() {
+        let kind = kind("compacts-journal");
+
+        append(&kind, None, vec![message("first")], None)
+            .await
+            .unwrap();
+
+        assert!(!path(&kind).await.unwrap().exists());
+
+        let long = "a".repeat(journal::COMPACT_AFTER_BYTES as usize);
+
+        append(&kind, None, vec![message(&long)], None)
+            .await
+            .unwrap();
+
+        assert!(path(&kind).await.unwrap().exists());
+        assert!(!journal::exists(&kind).await.unwrap());
+
+        let Loaded { messages, .. } = load(kind.clone(), None).await.unwrap();
+
+        assert_eq!(texts(&messages), vec!["first".to_string(), long]);
+
+        delete(&kind).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn deletes_history_and_journal() {
+        let kind = kind("deletes-history-and-journal");
+
+        overwrite(&kind, &[message("first")], None).await.unwrap();
+        append(&kind, None, vec![message("second")], None)
+            .await
+            .unwrap();
+
+        assert!(path(&kind).await.unwrap().exists());
+        assert!(journal::exists(&kind).await.unwrap());
+
+        delete(&kind).await.unwrap();
+
+        assert!(!path(&kind).await.unwrap().exists());
+        assert!(!journal::exists(&kind).await.unwrap());
+
+        let Loaded { messages, .. } = load(kind, None).await.unwrap();
+
+        assert!(messages.is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_appends_during_compaction() {
+        let kind = kind("keeps-appends-during-compaction");
+
+        append(&kind, None, vec![message("first")], None)
+            .await
+            .unwrap();
+
+        // Appended after the journal is sealed and read for the compaction
+        journal::seal(&kind).await.unwrap();
+        let Loaded { messages, .. } = load(kind.clone(), None).await.unwrap();
+        append(&kind, None, vec![message("second")], None)
+            .await
+            .unwrap();
+
+        overwrite(&kind, &messages, None).await.unwrap();
+
+        let Loaded { messages, .. } = load(kind.clone(), None).await.unwrap();
+
+        assert_eq!(texts(&messages), vec!["first", "second"]);
+
+        delete(&kind).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn load_fails_on_unreadable_history() {
+        let kind = kind("load-fails-on-unreadable-history");
+
+        append(&kind, None, vec![message("first")], None)
+            .await
+            .unwrap();
+        fs::write(path(&kind).await.unwrap(), b"not gzip")
+            .await
+            .unwrap();
+
+        assert!(load(kind.clone(), None).await.is_err());
+        assert!(journal::exists(&kind).await.unwrap());
+
+        delete(&kind).await.unwrap();
+    }
+}
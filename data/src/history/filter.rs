@@ -20,12 +20,26 @@ enum FilterClass {
 #[derive(Debug, Clone)]
 enum FilterTarget {
     User(User),
+    Tag { key: String, value: Option<String> },
 }
 
 impl FilterTarget {
     pub fn from_nick(nick: Nick) -> Self {
         Self::User(User::from(nick))
     }
+
+    pub fn from_tag(tag: &str) -> Self {
+        match tag.split_once('=') {
+            Some((key, value)) => Self::Tag {
+                key: key.to_string(),
+                value: Some(value.to_string()),
+            },
+            None => Self::Tag {
+                key: tag.to_string(),
+                value: None,
+            },
+        }
+    }
 }
 
 impl Filter {
@@ -39,15 +53,31 @@ impl Filter {
                 return;
             };
 
-            for idx in 0..filters.ignore.len() {
-                let chantypes = clients.get_chantypes(&entry.server);
-                let casemapping = clients.get_casemapping(&entry.server);
+            let chantypes = clients.get_chantypes(&entry.server);
+            let casemapping = clients.get_casemapping(&entry.server);
 
+            for idx in 0..filters.ignore.len() {
                 new_filters.push(Filter::from_str_with_server(
                     &entry.server,
                     chantypes,
                     casemapping,
                     &filters.ignore[idx],
+                    |nick| {
+                        FilterTarget::from_nick(Nick::from_str(
+                            nick,
+                            casemapping,
+                        ))
+                    },
+                ));
+            }
+
+            for tag in &filters.tags {
+                new_filters.push(Filter::from_str_with_server(
+                    &entry.server,
+                    chantypes,
+                    casemapping,
+                    tag,
+                    FilterTarget::from_tag,
                 ));
             }
         });
@@ -59,21 +89,16 @@ impl Filter {
         chantypes: &[char],
         casemapping: isupport::CaseMap,
         value: &str,
+        target: impl FnOnce(&str) -> FilterTarget,
     ) -> Self {
         let (class, target) = match value.split_once(' ') {
-            Some((channel, nick)) => {
+            Some((channel, value)) => {
                 let channel =
                     Channel::from_str(channel, chantypes, casemapping);
 
-                let target =
-                    FilterTarget::from_nick(Nick::from_str(nick, casemapping));
-
-                (FilterClass::Channel(server.clone(), channel), target)
+                (FilterClass::Channel(server.clone(), channel), target(value))
             }
-            None => (
-                FilterClass::Server(server.clone()),
-                FilterTarget::from_nick(Nick::from_str(value, casemapping)),
-            ),
+            None => (FilterClass::Server(server.clone()), target(value)),
         };
 
         Self { class, target }
@@ -103,6 +128,11 @@ impl Filter {
                 }
                 _ => false,
             },
+            FilterTarget::Tag { key, value } => {
+                message.tags.get(key).is_some_and(|tag_value| {
+                    value.as_ref().is_none_or(|value| value == tag_value)
+                })
+            }
         }
     }
 
@@ -121,6 +151,7 @@ impl Filter {
                         == query.as_normalized_str()
                 }
             },
+            FilterTarget::Tag { .. } => false,
         }
     }
 
@@ -148,6 +179,7 @@ impl Filter {
             },
             FilterClass::Server(target_server) => match kind {
                 Kind::Server(server)
+                | Kind::ServerCategory(server, _)
                 | Kind::Channel(server, _)
                 | Kind::Query(server, _) => target_server == server,
                 Kind::Highlights | Kind::Logs => false,
@@ -178,6 +210,7 @@ impl Filter {
             FilterTarget::User(user) => {
                 user.renormalize(casemapping);
             }
+            FilterTarget::Tag { .. } => (),
         }
 
         match &self.class {
@@ -0,0 +1,225 @@
+//! Append-only journal of messages not yet compacted into the history file.
+//!
+//! Each record is one message serialized as a single line of JSON. Records
+//! are only ever appended, so a write interrupted by a crash or power loss
+//! can at most leave a truncated final record behind, which is skipped when
+//! the journal is read back.
+//!
+//! Compaction first seals the journal by moving it aside, so records
+//! appended while the history file is rewritten start a new journal rather
+//! than being deleted along with the sealed one.
+use std::io::{self, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use crate::Message;
+use crate::history::{Error, Kind};
+
+/// Journal size after which it is compacted into the history file
+pub const COMPACT_AFTER_BYTES: u64 = 512 * 1024;
+
+/// Appends `messages` to the journal, returning its size afterwards
+pub async fn append(kind: &Kind, messages: &[Message]) -> Result<u64, Error> {
+    let mut records = vec![];
+
+    for message in messages {
+        serde_json::to_writer(&mut records, message)?;
+        records.push(b'\n');
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path(kind).await?)
+        .await?;
+
+    // Terminate a record truncated by an earlier crash, so it can't run
+    // into the first record appended here
+    if file.metadata().await?.len() > 0 {
+        file.seek(SeekFrom::End(-1)).await?;
+
+        if file.read_u8().await? != b'\n' {
+            records.insert(0, b'\n');
+        }
+    }
+
+    file.write_all(&records).await?;
+    file.sync_data().await?;
+
+    Ok(file.metadata().await?.len())
+}
+
+/// Reads back all complete records of the sealed and current journal, in
+/// the order they were appended
+pub async fn read(kind: &Kind) -> Result<Vec<Message>, Error> {
+    let mut bytes = read_file(&sealed_path(kind).await?).await?;
+    bytes.push(b'\n');
+    bytes.extend(read_file(&path(kind).await?).await?);
+
+    let mut skipped = 0;
+
+    let messages = bytes
+        .split(|byte| *byte == b'\n')
+        .filter(|record| !record.is_empty())
+        .filter_map(|record| {
+            serde_json::from_slice(record)
+                .inspect_err(|_| skipped += 1)
+                .ok()
+        })
+        .collect();
+
+    if skipped > 0 {
+        log::warn!("skipped {skipped} unreadable journal records for {kind}");
+    }
+
+    Ok(messages)
+}
+
+async fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    match fs::read(path).await {
+        Ok(bytes) => Ok(bytes),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(error.into()),
+    }
+}
+
+/// Moves the journal aside ahead of compaction. A journal already sealed by
+/// an interrupted compaction is kept, and is compacted along with the
+/// current one.
+pub async fn seal(kind: &Kind) -> Result<(), Error> {
+    let sealed = sealed_path(kind).await?;
+
+    if fs::try_exists(&sealed).await? {
+        return Ok(());
+    }
+
+    match fs::rename(path(kind).await?, sealed).await {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => {
+            Err(error.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Deletes the sealed journal once it's compacted into the history file
+pub async fn delete_sealed(kind: &Kind) -> Result<(), Error> {
+    remove_file(&sealed_path(kind).await?).await
+}
+
+pub async fn delete(kind: &Kind) -> Result<(), Error> {
+    remove_file(&sealed_path(kind).await?).await?;
+    remove_file(&path(kind).await?).await
+}
+
+async fn remove_file(path: &Path) -> Result<(), Error> {
+    match fs::remove_file(path).await {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => {
+            Err(error.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn exists(kind: &Kind) -> Result<bool, Error> {
+    Ok(fs::try_exists(sealed_path(kind).await?).await?
+        || fs::try_exists(path(kind).await?).await?)
+}
+
+async fn path(kind: &Kind) -> Result<PathBuf, Error> {
+    // `<hash>.json.gz` -> `<hash>.jsonl`
+    Ok(super::path(kind)
+        .await?
+        .with_extension("")
+        .with_extension("jsonl"))
+}
+
+async fn sealed_path(kind: &Kind) -> Result<PathBuf, Error> {
+    Ok(path(kind).await?.with_extension("jsonl.sealed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::message::{self, Source, Target};
+    use crate::server::Server;
+
+    #[tokio::test]
+    async fn skips_truncated_record() {
+        let kind = Kind::Server(Server::from(Arc::<str>::from(
+            "skips-truncated-record",
+        )));
+        let message = |text: &str| {
+            Message::sent(
+                Target::Server {
+                    source: Source::Server(None),
+                },
+                message::plain(text.to_string()),
+            )
+        };
+
+        append(&kind, &[message("first"), message("second")])
+            .await
+            .unwrap();
+
+        // A record cut short by a crash mid-write
+        let record = serde_json::to_vec(&message("third")).unwrap();
+
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(path(&kind).await.unwrap())
+            .await
+            .unwrap();
+        file.write_all(&record[..record.len() / 2]).await.unwrap();
+
+        let messages = read(&kind).await.unwrap();
+
+        assert_eq!(
+            messages.iter().map(Message::text).collect::<Vec<_>>(),
+            vec!["first", "second"]
+        );
+
+        delete(&kind).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn appends_after_truncated_record() {
+        let kind = Kind::Server(Server::from(Arc::<str>::from(
+            "appends-after-truncated-record",
+        )));
+        let message = |text: &str| {
+            Message::sent(
+                Target::Server {
+                    source: Source::Server(None),
+                },
+                message::plain(text.to_string()),
+            )
+        };
+
+        append(&kind, &[message("first")]).await.unwrap();
+
+        let record = serde_json::to_vec(&message("second")).unwrap();
+
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(path(&kind).await.unwrap())
+            .await
+            .unwrap();
+        file.write_all(&record[..record.len() / 2]).await.unwrap();
+
+        append(&kind, &[message("third")]).await.unwrap();
+
+        let messages = read(&kind).await.unwrap();
+
+        assert_eq!(
+            messages.iter().map(Message::text).collect::<Vec<_>>(),
+            vec!["first", "third"]
+        );
+
+        delete(&kind).await.unwrap();
+    }
+}
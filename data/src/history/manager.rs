@@ -1,3 +1,4 @@
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet, hash_map};
 
 use chrono::{DateTime, Local, NaiveDate, Utc};
@@ -14,7 +15,7 @@ use crate::target::{self, Target};
 use crate::user::{ChannelUsers, Nick};
 use crate::{
     Config, Input, Server, User, buffer, client, config, input, isupport,
-    server,
+    search, server,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -39,6 +40,7 @@ impl Resource {
 #[derive(Debug)]
 pub enum Message {
     LoadFull(history::Kind, Result<history::Loaded, history::Error>),
+    Restored(history::Kind, Result<history::Loaded, history::Error>),
     UpdatePartial(history::Kind, Result<history::Metadata, history::Error>),
     UpdateReadMarker(
         history::Kind,
@@ -47,6 +49,7 @@ pub enum Message {
     ),
     Closed(history::Kind, Result<(), history::Error>),
     Flushed(history::Kind, Result<(), history::Error>),
+    Renamed(history::Kind, history::Kind, Result<(), history::Error>),
     Exited(Vec<(history::Kind, Result<(), history::Error>)>),
     SentMessageUpdated(history::Kind, history::ReadMarker),
 }
@@ -61,6 +64,8 @@ pub enum Event {
 pub struct Manager {
     resources: HashSet<Resource>,
     filters: Vec<Filter>,
+    backfills: HashMap<history::Kind, history::Backfill>,
+    last_viewed: HashMap<history::Kind, Instant>,
     data: Data,
 }
 
@@ -75,16 +80,22 @@ impl Manager {
 
             match history {
                 History::Full {
-                    messages, cleared, ..
+                    messages,
+                    cleared,
+                    evicted,
+                    ..
                 } => {
                     messages.clear();
                     *cleared = true;
+                    *evicted = false;
                 }
                 History::Partial { messages, .. } => {
                     messages.clear();
                 }
             }
 
+            self.data.index.remove(&kind);
+
             log::debug!("cleared messages for {kind}");
 
             return task.map(move |task| {
@@ -117,7 +128,12 @@ impl Manager {
         });
 
         let removed = removed.into_iter().filter_map(|resource| {
-            self.data.untrack(&resource.kind).map(|task| {
+            self.last_viewed.remove(&resource.kind);
+
+            let seed =
+                clients.and_then(|clients| clients.get_seed(&resource.kind));
+
+            self.data.untrack(&resource.kind, seed).map(|task| {
                 task.map(|result| Message::Closed(resource.kind, result))
                     .boxed()
             })
@@ -140,6 +156,7 @@ impl Manager {
             Message::LoadFull(kind, Ok(loaded)) => {
                 let len = loaded.messages.len();
                 self.data.load_full(kind.clone(), loaded);
+                self.data.reindex(&kind);
                 log::debug!("loaded history for {kind}: {len} messages");
 
                 self.process_messages(kind.clone(), clients, buffer_config);
@@ -149,6 +166,17 @@ impl Manager {
             Message::LoadFull(kind, Err(error)) => {
                 log::warn!("failed to load history for {kind}: {error}");
             }
+            Message::Restored(kind, Ok(loaded)) => {
+                let len = loaded.messages.len();
+                self.data.restore(&kind, loaded);
+                self.data.reindex(&kind);
+                log::debug!("restored history for {kind}: {len} messages");
+
+                self.process_messages(kind, clients, buffer_config);
+            }
+            Message::Restored(kind, Err(error)) => {
+                log::warn!("failed to restore history for {kind}: {error}");
+            }
             Message::Closed(kind, Ok(())) => {
                 log::debug!("closed history for {kind}",);
             }
@@ -164,6 +192,14 @@ impl Manager {
             Message::Flushed(kind, Err(error)) => {
                 log::warn!("failed to flush history for {kind}: {error}");
             }
+            Message::Renamed(old, new, Ok(())) => {
+                log::debug!("moved history for {old} to {new}");
+            }
+            Message::Renamed(old, new, Err(error)) => {
+                log::warn!(
+                    "failed to move history for {old} to {new}: {error}"
+                );
+            }
             Message::UpdatePartial(kind, Ok(metadata)) => {
                 log::debug!("loaded metadata for {kind}");
                 self.data.update_partial(kind, metadata);
@@ -219,8 +255,206 @@ impl Manager {
         &mut self,
         now: Instant,
         clients: &client::Map,
+        memory_limit: Option<usize>,
     ) -> Vec<BoxFuture<'static, Message>> {
-        self.data.flush_all(now, clients)
+        let mut tasks = self.data.flush_all(now, clients);
+
+        if let Some(memory_limit) = memory_limit {
+            tasks.extend(self.evict(memory_limit, clients));
+        }
+
+        tasks
+    }
+
+    /// Records that the buffer of `kind` is being viewed, histories viewed
+    /// least recently are evicted first
+    pub fn viewed(&mut self, kind: history::Kind, now: Instant) {
+        self.last_viewed.insert(kind, now);
+    }
+
+    /// Evicts older messages of the least recently viewed histories until
+    /// the messages in memory are within `memory_limit`
+    fn evict(
+        &mut self,
+        memory_limit: usize,
+        clients: &client::Map,
+    ) -> Vec<BoxFuture<'static, Message>> {
+        let mut in_memory = self.data.in_memory();
+
+        if in_memory <= memory_limit {
+            return vec![];
+        }
+
+        let mut kinds = self
+            .data
+            .map
+            .iter()
+            .filter(|(_, history)| matches!(history, History::Full { .. }))
+            .map(|(kind, _)| kind.clone())
+            .collect::<Vec<_>>();
+
+        kinds.sort_by_key(|kind| self.last_viewed.get(kind).copied());
+
+        // Keep the most recently viewed history whole
+        kinds.pop();
+
+        let mut tasks = vec![];
+
+        for kind in kinds {
+            if in_memory <= memory_limit {
+                break;
+            }
+
+            let Some(history) = self.data.map.get_mut(&kind) else {
+                continue;
+            };
+
+            let len = history.messages().len();
+
+            if let Some(task) = history.evict(clients.get_seed(&kind)) {
+                in_memory -= len - history.messages().len();
+
+                log::debug!("evicted older messages of {kind} from memory");
+
+                tasks.push(
+                    task.map(move |result| Message::Flushed(kind, result))
+                        .boxed(),
+                );
+            }
+        }
+
+        tasks
+    }
+
+    /// Moves the history of a renamed channel, in memory and on disk
+    pub fn rename_channel(
+        &mut self,
+        server: &Server,
+        old: target::Channel,
+        new: target::Channel,
+        clients: &client::Map,
+    ) -> BoxFuture<'static, Message> {
+        let old = history::Kind::Channel(server.clone(), old);
+        let new = history::Kind::Channel(server.clone(), new);
+
+        self.rekey(old, new, clients)
+    }
+
+    /// Re-keys the histories of `server` after its `CASEMAPPING` changed,
+    /// merging those whose targets now normalize to the same name
+    pub fn renormalize_targets(
+        &mut self,
+        server: &Server,
+        clients: &client::Map,
+    ) -> Vec<BoxFuture<'static, Message>> {
+        let casemapping = clients.get_casemapping(server);
+
+        let stale = self
+            .data
+            .map
+            .keys()
+            .filter(|kind| kind.server() == Some(server))
+            .filter_map(|kind| {
+                let mut target = kind.target()?;
+                target.renormalize(casemapping);
+
+                (kind.target()?.as_normalized_str()
+                    != target.as_normalized_str())
+                .then(|| {
+                    (
+                        kind.clone(),
+                        history::Kind::from_target(server.clone(), target),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        stale
+            .into_iter()
+            .map(|(old, new)| self.rekey(old, new, clients))
+            .collect()
+    }
+
+    fn rekey(
+        &mut self,
+        old: history::Kind,
+        new: history::Kind,
+        clients: &client::Map,
+    ) -> BoxFuture<'static, Message> {
+        if let Some(mut history) = self.data.map.remove(&old) {
+            history.rename(new.clone());
+
+            if let Some(existing) = self.data.map.get_mut(&new) {
+                existing.merge(history);
+            } else {
+                self.data.map.insert(new.clone(), history);
+            }
+
+            self.data.index.remove(&old);
+            self.data.reindex(&new);
+        }
+
+        // Keep the history tracked, its pane is renamed along with it
+        if self.resources.remove(&Resource { kind: old.clone() }) {
+            self.resources.insert(Resource { kind: new.clone() });
+        }
+
+        if let Some(backfill) = self.backfills.remove(&old) {
+            self.backfills.insert(new.clone(), backfill);
+        }
+
+        if let Some(last_viewed) = self.last_viewed.remove(&old) {
+            self.last_viewed.insert(new.clone(), last_viewed);
+        }
+
+        let seed = clients.get_seed(&old);
+
+        async move {
+            let result = history::rename(&old, &new, seed).await;
+
+            Message::Renamed(old, new, result)
+        }
+        .boxed()
+    }
+
+    /// Reloads the messages evicted from memory for `kind`
+    pub fn restore(
+        &self,
+        kind: history::Kind,
+        clients: &client::Map,
+    ) -> Option<BoxFuture<'static, Message>> {
+        if !self.data.map.get(&kind)?.is_evicted() {
+            return None;
+        }
+
+        let seed = clients.get_seed(&kind);
+
+        Some(
+            async move {
+                history::load(kind.clone(), seed)
+                    .map(move |result| Message::Restored(kind, result))
+                    .await
+            }
+            .boxed(),
+        )
+    }
+
+    pub fn is_evicted(&self, kind: &history::Kind) -> bool {
+        self.data.map.get(kind).is_some_and(History::is_evicted)
+    }
+
+    pub fn revision(&self, kind: &history::Kind) -> Option<history::Revision> {
+        self.data.map.get(kind).map(History::revision)
+    }
+
+    /// Whether every tracked resource has its full history loaded
+    pub fn is_loaded(&self) -> bool {
+        self.resources.iter().all(|resource| {
+            matches!(
+                self.data.map.get(&resource.kind),
+                Some(History::Full { .. })
+            )
+        })
     }
 
     pub fn close(
@@ -228,6 +462,10 @@ impl Manager {
         kind: history::Kind,
         clients: &client::Map,
     ) -> Option<impl Future<Output = Message> + use<>> {
+        self.backfills.remove(&kind);
+        self.last_viewed.remove(&kind);
+        self.data.index.remove(&kind);
+
         let history = self.data.map.remove(&kind)?;
 
         Some(
@@ -393,6 +631,38 @@ impl Manager {
         self.data.load_metadata(server, target)
     }
 
+    /// Records that messages between `start` and `end` were backfilled into
+    /// the history of `target`, counting those which are unread.
+    pub fn record_backfill(
+        &mut self,
+        server: Server,
+        target: Target,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) {
+        let kind = history::Kind::from_target(server, target);
+
+        let Some(backfill) = self
+            .data
+            .map
+            .get(&kind)
+            .map(|history| history.backfill(start, end))
+        else {
+            return;
+        };
+
+        let backfill = match self.backfills.remove(&kind) {
+            Some(previous) => previous.merge(backfill),
+            None => backfill,
+        };
+
+        self.backfills.insert(kind, backfill);
+    }
+
+    pub fn backfill(&self, kind: &history::Kind) -> Option<history::Backfill> {
+        self.backfills.get(kind).copied()
+    }
+
     pub fn first_can_reference(
         &self,
         server: Server,
@@ -428,6 +698,80 @@ impl Manager {
         self.data.history_view(kind, limit, buffer_config)
     }
 
+    /// Messages of all loaded histories matching `query`, newest first.
+    ///
+    /// Messages only on disk are searched by [`history::search`], for the
+    /// histories returned by [`Manager::unloaded`].
+    pub fn search(
+        &self,
+        query: &search::Query,
+        limit: usize,
+        clients: &client::Map,
+    ) -> Vec<(&history::Kind, &message::Message)> {
+        let mut results = self
+            .data
+            .map
+            .keys()
+            .filter(|kind| {
+                matches!(
+                    kind,
+                    history::Kind::Channel(..) | history::Kind::Query(..)
+                )
+            })
+            .flat_map(|kind| {
+                let casemapping =
+                    clients.get_casemapping_or_default(kind.server());
+
+                self.data
+                    .search(kind, query, casemapping)
+                    .into_iter()
+                    .map(move |message| (kind, message))
+            })
+            .collect::<Vec<_>>();
+
+        results.sort_by_key(|(_, message)| Reverse(message.server_time));
+        results.truncate(limit);
+
+        results
+    }
+
+    /// Messages of the loaded history of `kind` matching `query`, in the
+    /// order they are shown.
+    pub fn search_buffer(
+        &self,
+        kind: &history::Kind,
+        query: &search::Query,
+        casemapping: isupport::CaseMap,
+    ) -> Vec<&message::Message> {
+        self.data.search(kind, query, casemapping)
+    }
+
+    /// Histories with messages which are only on disk, either because they
+    /// were never loaded or because older messages were evicted, along with
+    /// the casemapping of their server.
+    pub fn unloaded(
+        &self,
+        clients: &client::Map,
+    ) -> Vec<(history::Kind, isupport::CaseMap)> {
+        self.data
+            .map
+            .iter()
+            .filter(|(kind, history)| {
+                matches!(
+                    kind,
+                    history::Kind::Channel(..) | history::Kind::Query(..)
+                ) && (history.is_evicted()
+                    || matches!(history, History::Partial { .. }))
+            })
+            .map(|(kind, _)| {
+                (
+                    kind.clone(),
+                    clients.get_casemapping_or_default(kind.server()),
+                )
+            })
+            .collect()
+    }
+
     pub fn get_last_seen(
         &self,
         buffer: &buffer::Upstream,
@@ -498,6 +842,24 @@ impl Manager {
         self.data.map.get(kind).is_some_and(History::has_highlight)
     }
 
+    pub fn activity(&self, kind: &history::Kind) -> history::Activity {
+        self.data
+            .map
+            .get(kind)
+            .map(History::activity)
+            .unwrap_or_default()
+    }
+
+    /// Whether any history has unread messages.
+    pub fn any_unread(&self) -> bool {
+        self.data.map.values().any(History::has_unread)
+    }
+
+    /// Whether any history has unread highlights.
+    pub fn any_highlight(&self) -> bool {
+        self.data.map.values().any(History::has_highlight)
+    }
+
     pub fn read_marker(
         &self,
         kind: &history::Kind,
@@ -517,6 +879,12 @@ impl Manager {
         config: &Config,
         sent_time: DateTime<Utc>,
     ) -> Vec<impl Future<Output = Message> + use<>> {
+        // Missed messages are backfilled anew after (re)connecting
+        if matches!(broadcast, Broadcast::Connected | Broadcast::Reconnected) {
+            self.backfills
+                .retain(|kind, _| kind.server() != Some(server));
+        }
+
         let channels = self
             .data
             .map
@@ -922,9 +1290,47 @@ fn with_limit<'a>(
 struct Data {
     map: HashMap<history::Kind, History>,
     input: input::Storage,
+    index: search::Index,
 }
 
 impl Data {
+    /// Messages of `kind` matching `query`, looked up through the search
+    /// index when the query has words to narrow it by.
+    fn search(
+        &self,
+        kind: &history::Kind,
+        query: &search::Query,
+        casemapping: isupport::CaseMap,
+    ) -> Vec<&crate::Message> {
+        let Some(history) = self.map.get(kind) else {
+            return vec![];
+        };
+        let messages = history.messages();
+
+        let matches = |message: &&crate::Message| {
+            query.matches(kind, message, casemapping)
+        };
+
+        match self.index.candidates(kind, query) {
+            Some(candidates) => candidates
+                .into_iter()
+                .filter_map(|hash| self.index.position(kind, messages, hash))
+                .sorted()
+                .map(|position| &messages[position])
+                .filter(matches)
+                .collect(),
+            None => messages.iter().filter(matches).collect(),
+        }
+    }
+
+    /// Rebuilds the search index of `kind` from its messages
+    fn reindex(&mut self, kind: &history::Kind) {
+        match self.map.get(kind) {
+            Some(history) => self.index.rebuild(kind, history.messages()),
+            None => self.index.remove(kind),
+        }
+    }
+
     fn load_full(&mut self, kind: history::Kind, data: history::Loaded) {
         use std::collections::hash_map;
 
@@ -949,13 +1355,14 @@ impl Data {
 
                     let mut last_seen = last_seen.clone();
 
-                    std::mem::take(new_messages).into_iter().for_each(
-                        |message| {
-                            history::update_last_seen(&mut last_seen, &message);
+                    // Received while closed and not written yet
+                    let unsaved = std::mem::take(new_messages);
 
-                            history::insert_message(&mut messages, message);
-                        },
-                    );
+                    unsaved.iter().cloned().for_each(|message| {
+                        history::update_last_seen(&mut last_seen, &message);
+
+                        history::insert_message(&mut messages, message);
+                    });
 
                     entry.insert(History::Full {
                         kind,
@@ -964,6 +1371,8 @@ impl Data {
                         read_marker,
                         last_seen,
                         cleared: false,
+                        evicted: false,
+                        unsaved,
                     });
                 }
                 _ => {
@@ -976,6 +1385,8 @@ impl Data {
                         read_marker: metadata.read_marker,
                         last_seen,
                         cleared: false,
+                        evicted: false,
+                        unsaved: vec![],
                     });
                 }
             },
@@ -989,6 +1400,8 @@ impl Data {
                     read_marker: metadata.read_marker,
                     last_seen,
                     cleared: false,
+                    evicted: false,
+                    unsaved: vec![],
                 });
             }
         }
@@ -1069,12 +1482,11 @@ impl Data {
                                 buffer_config
                                     .nickname
                                     .brackets
-                                    .format(
-                                        user.display(
-                                            with_access_levels,
-                                            truncate,
-                                        ),
-                                    )
+                                    .format(user.display(
+                                        with_access_levels,
+                                        truncate,
+                                        buffer_config.nickname.bridged_name(),
+                                    ))
                                     .chars()
                                     .count(),
                             )
@@ -1095,8 +1507,7 @@ impl Data {
                             message.target.prefixes().map(|prefixes| {
                                 buffer_config
                                     .status_message_prefix
-                                    .brackets
-                                    .format(prefixes.iter().collect::<String>())
+                                    .format(prefixes)
                                     .chars()
                                     .count()
                                     + 1
@@ -1206,9 +1617,12 @@ impl Data {
         kind: history::Kind,
         message: crate::Message,
     ) -> Option<impl Future<Output = Message> + use<>> {
+        self.index.insert(&kind, &message);
+
         match self.map.entry(kind.clone()) {
             hash_map::Entry::Occupied(mut entry) => {
                 let read_marker = entry.get_mut().add_message(message);
+                self.index.prune(&kind, entry.get().messages());
 
                 read_marker.map(|read_marker| {
                     async move {
@@ -1324,8 +1738,42 @@ impl Data {
     fn untrack(
         &mut self,
         kind: &history::Kind,
+        seed: Option<history::Seed>,
     ) -> Option<impl Future<Output = Result<(), history::Error>> + use<>> {
-        self.map.get_mut(kind).and_then(History::make_partial)
+        self.map
+            .get_mut(kind)
+            .and_then(|history| history.make_partial(seed))
+    }
+
+    /// # messages held in memory by open histories
+    fn in_memory(&self) -> usize {
+        self.map
+            .values()
+            .filter(|history| matches!(history, History::Full { .. }))
+            .map(|history| history.messages().len())
+            .sum()
+    }
+
+    fn restore(&mut self, kind: &history::Kind, data: history::Loaded) {
+        let Some(History::Full {
+            messages, evicted, ..
+        }) = self.map.get_mut(kind)
+        else {
+            return;
+        };
+
+        if !*evicted {
+            return;
+        }
+
+        let mut restored = data.messages;
+
+        std::mem::take(messages).into_iter().for_each(|message| {
+            history::insert_message(&mut restored, message);
+        });
+
+        *messages = restored;
+        *evicted = false;
     }
 
     fn flush_all(
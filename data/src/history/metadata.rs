@@ -6,6 +6,7 @@ use chrono::format::SecondsFormat;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 
 use crate::Message;
 use crate::history::{Error, Kind, dir_path};
@@ -127,11 +128,30 @@ pub async fn save(
         chathistory_references: latest_can_reference(messages),
     })?;
 
-    let path = path(kind).await?;
+    write(kind, &bytes).await
+}
 
-    fs::write(path, &bytes).await?;
+/// Updates the metadata with `messages` appended to the history
+pub async fn append(
+    kind: &Kind,
+    messages: &[Message],
+    read_marker: Option<ReadMarker>,
+) -> Result<(), Error> {
+    let metadata = load(kind.clone()).await?;
 
-    Ok(())
+    let bytes = serde_json::to_vec(&Metadata {
+        read_marker: metadata.read_marker.max(read_marker),
+        last_triggers_unread: metadata
+            .last_triggers_unread
+            .max(latest_triggers_unread(messages)),
+        last_triggers_highlight: metadata
+            .last_triggers_highlight
+            .max(latest_triggers_highlight(messages)),
+        chathistory_references: latest_can_reference(messages)
+            .or(metadata.chathistory_references),
+    })?;
+
+    write(kind, &bytes).await
 }
 
 pub async fn update(
@@ -153,9 +173,20 @@ pub async fn update(
         chathistory_references: metadata.chathistory_references,
     })?;
 
+    write(kind, &bytes).await
+}
+
+/// Written aside and moved into place, so a crash mid-write leaves the
+/// previous metadata intact
+async fn write(kind: &Kind, bytes: &[u8]) -> Result<(), Error> {
     let path = path(kind).await?;
+    let temporary = path.with_extension("json.tmp");
+
+    let mut file = fs::File::create(&temporary).await?;
+    file.write_all(bytes).await?;
+    file.sync_all().await?;
 
-    fs::write(path, &bytes).await?;
+    fs::rename(&temporary, &path).await?;
 
     Ok(())
 }
@@ -165,6 +196,9 @@ async fn path(kind: &Kind) -> Result<PathBuf, Error> {
 
     let name = match kind {
         Kind::Server(server) => format!("{server}-metadata"),
+        Kind::ServerCategory(server, category) => {
+            format!("{server}category{}-metadata", category.key())
+        }
         Kind::Channel(server, channel) => {
             format!("{server}channel{}-metadata", channel.as_normalized_str())
         }
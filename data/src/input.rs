@@ -4,8 +4,10 @@ use irc::proto;
 use irc::proto::format;
 
 use crate::buffer::{self, AutoFormat};
+use crate::config::Aliases;
+use crate::config::buffer::ServerLimits;
 use crate::message::formatting;
-use crate::target::Target;
+use crate::target::{Target, TargetList};
 use crate::user::{ChannelUsers, NickRef};
 use crate::{
     Command, Config, Message, Server, User, command, isupport, message,
@@ -19,38 +21,65 @@ pub fn parse(
     input: &str,
     our_nickname: Option<NickRef>,
     isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+    server_limits: ServerLimits,
+    aliases: &Aliases,
 ) -> Result<Parsed, Error> {
-    let content =
-        match command::parse(input, Some(&buffer), our_nickname, isupport) {
-            Ok(Command::Internal(command)) => {
-                return Ok(Parsed::Internal(command));
-            }
-            Ok(Command::Irc(command)) => Content::Command(command),
-            Err(command::Error::MissingSlash) => {
-                let text = match auto_format {
-                    AutoFormat::Disabled => input.to_string(),
-                    AutoFormat::Markdown => formatting::encode(input, true),
-                    AutoFormat::All => formatting::encode(input, false),
-                };
+    let (contents, adjustments) = match command::parse(
+        input,
+        Some(&buffer),
+        our_nickname,
+        isupport,
+        aliases,
+    ) {
+        Ok(Command::Internal(command)) => {
+            return Ok(Parsed::Internal(command));
+        }
+        Ok(Command::Irc(command)) => {
+            let (commands, adjustments) =
+                command::fit_to_limits(command, isupport, server_limits)
+                    .map_err(Error::Command)?;
+
+            (
+                commands.into_iter().map(Content::Command).collect(),
+                adjustments,
+            )
+        }
+        Err(command::Error::MissingSlash) => {
+            let text = match auto_format {
+                AutoFormat::Disabled => input.to_string(),
+                AutoFormat::Markdown => formatting::encode(input, true),
+                AutoFormat::All => formatting::encode(input, false),
+            };
 
-                Content::Text(text)
+            (vec![Content::Text(text)], vec![])
+        }
+        Err(error) => return Err(Error::Command(error)),
+    };
+
+    let inputs = contents
+        .into_iter()
+        .map(|content| {
+            if let Some(message_bytes) = content
+                .proto(&buffer)
+                .map(|message| format::message(message).len())
+                && message_bytes > format::BYTE_LIMIT
+            {
+                return Err(Error::ExceedsByteLimit { message_bytes });
             }
-            Err(error) => return Err(Error::Command(error)),
-        };
 
-    if let Some(message_bytes) = content
-        .proto(&buffer)
-        .map(|message| format::message(message).len())
-        && message_bytes > format::BYTE_LIMIT
-    {
-        return Err(Error::ExceedsByteLimit { message_bytes });
-    }
+            Ok(Input {
+                buffer: buffer.clone(),
+                content,
+            })
+        })
+        .collect::<Result<_, _>>()?;
 
-    Ok(Parsed::Input(Input { buffer, content }))
+    Ok(Parsed::Input(inputs, adjustments))
 }
 
 pub enum Parsed {
-    Input(Input),
+    /// Inputs to send, along with any changes made to fit server limits
+    Input(Vec<Input>, Vec<command::Adjustment>),
     Internal(command::Internal),
 }
 
@@ -81,12 +110,7 @@ impl Input {
         casemapping: isupport::CaseMap,
         config: &Config,
     ) -> Option<Vec<Message>> {
-        let to_target = |target: &str, source| match Target::parse(
-            target,
-            chantypes,
-            statusmsg,
-            casemapping,
-        ) {
+        let to_target = |target: Target, source| match target {
             Target::Channel(channel) => {
                 message::Target::Channel { channel, source }
             }
@@ -98,28 +122,33 @@ impl Input {
         match command {
             command::Irc::Msg(targets, text)
             | command::Irc::Notice(targets, text) => Some(
-                targets
-                    .split(',')
+                TargetList::parse(&targets, chantypes, statusmsg, casemapping)
+                    .into_iter()
                     .map(|target| {
+                        let content = message::parse_fragments_with_highlights(
+                            text.clone(),
+                            channel_users,
+                            target.as_str(),
+                            None,
+                            &config.highlights,
+                            casemapping,
+                        );
+
                         Message::sent(
                             to_target(
                                 target,
                                 message::Source::User(user.clone()),
                             ),
-                            message::parse_fragments_with_highlights(
-                                text.clone(),
-                                channel_users,
-                                target,
-                                None,
-                                &config.highlights,
-                                casemapping,
-                            ),
+                            content,
                         )
                     })
                     .collect(),
             ),
             command::Irc::Me(target, action) => Some(vec![Message::sent(
-                to_target(&target, message::Source::Action(Some(user.clone()))),
+                to_target(
+                    Target::parse(&target, chantypes, statusmsg, casemapping),
+                    message::Source::Action(Some(user.clone())),
+                ),
                 message::action_text(
                     user.nickname(),
                     Some(&action),
@@ -145,11 +174,8 @@ impl Input {
         match command {
             command::Irc::Msg(targets, _)
             | command::Irc::Notice(targets, _) => Some(
-                targets
-                    .split(',')
-                    .map(|target| {
-                        Target::parse(target, chantypes, statusmsg, casemapping)
-                    })
+                TargetList::parse(&targets, chantypes, statusmsg, casemapping)
+                    .into_iter()
                     .collect(),
             ),
             command::Irc::Me(target, _) => Some(vec![Target::parse(
@@ -11,6 +11,7 @@ pub use self::mode::Mode;
 pub use self::notification::Notification;
 pub use self::pane::Pane;
 pub use self::preview::Preview;
+pub use self::profile::Profile;
 pub use self::server::Server;
 pub use self::shortcut::Shortcut;
 pub use self::target::Target;
@@ -19,6 +20,8 @@ pub use self::user::User;
 pub use self::version::Version;
 pub use self::window::Window;
 
+pub mod access_list;
+pub mod admin;
 pub mod appearance;
 pub mod audio;
 pub mod bouncer;
@@ -31,20 +34,30 @@ pub mod config;
 pub mod ctcp;
 pub mod dashboard;
 pub mod dcc;
+pub mod dcc_chat;
+pub mod destination;
+pub mod digest;
 pub mod environment;
 pub mod file_transfer;
 pub mod history;
 pub mod input;
 pub mod isupport;
 pub mod log;
+pub mod mask;
 pub mod message;
 pub mod mode;
 pub mod notification;
 pub mod pane;
+pub mod poll;
 pub mod preview;
+pub mod print;
+pub mod profile;
 pub mod rate_limit;
+pub mod script;
+pub mod search;
 pub mod serde;
 pub mod server;
+pub mod share;
 pub mod shortcut;
 pub mod stream;
 pub mod target;
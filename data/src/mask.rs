@@ -0,0 +1,110 @@
+//! IRC style masks, where `*` matches any number of characters and `?`
+//! matches exactly one.
+//!
+//! Both the mask and the value are normalized with the server's casemapping
+//! before matching, so e.g. `[foo]*` matches `{FOO}bar` on RFC1459 servers
+//! but not on ascii ones. There is no escape character, since `\` is itself
+//! case-folded (to `|`) by RFC1459.
+use crate::isupport;
+
+pub fn matches(
+    mask: &str,
+    value: &str,
+    casemapping: isupport::CaseMap,
+) -> bool {
+    let mask = casemapping.normalize(mask).chars().collect::<Vec<_>>();
+    let value = casemapping.normalize(value).chars().collect::<Vec<_>>();
+
+    let (mut m, mut v) = (0, 0);
+    // The last `*` seen in the mask and the position in the value it is
+    // currently assumed to match up to, resumed from when a later part of
+    // the mask fails to match
+    let mut backtrack = None;
+
+    while v < value.len() {
+        match mask.get(m) {
+            Some('*') => {
+                backtrack = Some((m, v));
+                m += 1;
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                m += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    m = star + 1;
+                    v = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    mask[m..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        let casemapping = isupport::CaseMap::ASCII;
+
+        let tests = [
+            ("*", "", true),
+            ("*", "dan", true),
+            ("?", "", false),
+            ("???", "dan", true),
+            ("??", "dan", false),
+            ("d*", "dan", true),
+            ("*n", "dan", true),
+            ("d*n", "dn", true),
+            ("d?n", "dn", false),
+            ("*a*", "dan", true),
+            ("*x*", "dan", false),
+            ("**a**", "dan", true),
+            ("dan", "dan", true),
+            ("dan", "dana", false),
+            ("*!*@*.example.org", "dan!d@irc.example.org", true),
+            ("*!*@*.example.org", "dan!d@example.org", false),
+            ("*!~*@*", "dan!~d@localhost", true),
+            ("*!~*@*", "dan!d@localhost", false),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYbZ", false),
+        ];
+
+        for (mask, value, expected) in tests {
+            assert_eq!(
+                matches(mask, value, casemapping),
+                expected,
+                "{mask} {value}"
+            );
+        }
+    }
+
+    #[test]
+    fn casemapping() {
+        let tests = [
+            ("DAN", "dan", isupport::CaseMap::ASCII, true),
+            ("DAN", "dan", isupport::CaseMap::RFC1459, true),
+            ("[dan]*", "{DAN}away", isupport::CaseMap::RFC1459, true),
+            ("[dan]*", "{DAN}away", isupport::CaseMap::ASCII, false),
+            ("dan\\", "DAN|", isupport::CaseMap::RFC1459, true),
+            ("dan\\", "DAN|", isupport::CaseMap::ASCII, false),
+            ("dan~", "dan^", isupport::CaseMap::RFC1459, true),
+            ("dan~", "dan^", isupport::CaseMap::RFC1459_STRICT, false),
+            ("dan~", "dan^", isupport::CaseMap::ASCII, false),
+        ];
+
+        for (mask, value, casemapping, expected) in tests {
+            assert_eq!(
+                matches(mask, value, casemapping),
+                expected,
+                "{mask} {value} {casemapping:?}"
+            );
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::hash::{DefaultHasher, Hash as _, Hasher};
 use std::iter;
 use std::sync::{Arc, LazyLock};
@@ -26,7 +26,7 @@ use crate::server::Server;
 use crate::target::{Channel, join_targets};
 use crate::time::Posix;
 use crate::user::{ChannelUsers, Nick, NickRef};
-use crate::{Config, User, ctcp, isupport, target};
+use crate::{Config, User, ctcp, isupport, mode, target};
 
 // References:
 // - https://datatracker.ietf.org/doc/html/rfc1738#section-5
@@ -129,6 +129,10 @@ pub enum Target {
     Server {
         source: Source,
     },
+    ServerCategory {
+        category: target::Category,
+        source: Source,
+    },
     Channel {
         channel: target::Channel,
         source: Source,
@@ -148,9 +152,22 @@ pub enum Target {
 }
 
 impl Target {
+    /// Name of the channel or query the message belongs to.
+    pub fn buffer_name(&self) -> Option<&str> {
+        match self {
+            Target::Channel { channel, .. }
+            | Target::Highlights { channel, .. } => Some(channel.as_str()),
+            Target::Query { query, .. } => Some(query.as_str()),
+            Target::Server { .. }
+            | Target::ServerCategory { .. }
+            | Target::Logs { .. } => None,
+        }
+    }
+
     pub fn prefixes(&self) -> Option<&[char]> {
         match self {
             Target::Server { .. } => None,
+            Target::ServerCategory { .. } => None,
             Target::Channel { channel, .. } => {
                 if channel.prefixes().is_empty() {
                     None
@@ -167,6 +184,7 @@ impl Target {
     pub fn source(&self) -> &Source {
         match self {
             Target::Server { source } => source,
+            Target::ServerCategory { source, .. } => source,
             Target::Channel { source, .. } => source,
             Target::Query { source, .. } => source,
             Target::Logs { source } => source,
@@ -177,6 +195,7 @@ impl Target {
     pub fn source_mut(&mut self) -> &mut Source {
         match self {
             Target::Server { source } => source,
+            Target::ServerCategory { source, .. } => source,
             Target::Channel { source, .. } => source,
             Target::Query { source, .. } => source,
             Target::Logs { source } => source,
@@ -191,6 +210,41 @@ pub enum Direction {
     Received,
 }
 
+/// Kind of IRCv3 batch a message was received in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Batch {
+    Netsplit,
+    Netjoin,
+    /// Chat history or bouncer playback
+    History,
+}
+
+impl Batch {
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind {
+            "netsplit" => Some(Batch::Netsplit),
+            "netjoin" => Some(Batch::Netjoin),
+            "chathistory" | "znc.in/playback" => Some(Batch::History),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Batch::Netsplit => "netsplit",
+            Batch::Netjoin => "netjoin",
+            Batch::History => "chathistory",
+        }
+    }
+
+    /// Whether the batch groups quits or joins caused by a server
+    /// (dis)connecting from the network.
+    pub fn is_net_change(&self) -> bool {
+        matches!(self, Batch::Netsplit | Batch::Netjoin)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Message {
     pub received_at: Posix,
@@ -204,6 +258,10 @@ pub struct Message {
     pub is_echo: bool,
     pub blocked: bool,
     pub condensed: Option<Arc<Message>>,
+    pub batch: Option<Batch>,
+    /// Tags of the received message which aren't otherwise represented
+    /// (e.g. vendor-specific tags)
+    pub tags: BTreeMap<String, String>,
 }
 
 impl Message {
@@ -266,6 +324,8 @@ impl Message {
     ) -> bool {
         if let Source::Server(Some(source)) = self.target.source() {
             condense.kind(source)
+                || (self.batch.as_ref().is_some_and(Batch::is_net_change)
+                    && condense.net_changes(source))
         } else {
             false
         }
@@ -288,9 +348,12 @@ impl Message {
         statusmsg: &[char],
         casemapping: isupport::CaseMap,
         prefix: &[isupport::PrefixMap],
+        chanmodes: &[isupport::ModeKind],
     ) -> Option<Message> {
         let server_time = server_time(&encoded);
         let id = message_id(&encoded);
+        let batch = batch(&encoded);
+        let tags = tags(&encoded);
         let is_echo = encoded
             .user(casemapping)
             .is_some_and(|user| user.nickname() == our_nick);
@@ -304,6 +367,7 @@ impl Message {
             statusmsg,
             casemapping,
             prefix,
+            chanmodes,
         )?;
         let target = target(
             encoded,
@@ -328,6 +392,8 @@ impl Message {
             is_echo,
             blocked: false,
             condensed: None,
+            batch,
+            tags,
         })
     }
 
@@ -348,6 +414,8 @@ impl Message {
             is_echo: false,
             blocked: false,
             condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
         }
     }
 
@@ -379,6 +447,8 @@ impl Message {
             is_echo: false,
             blocked: false,
             condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
         }
     }
 
@@ -408,6 +478,65 @@ impl Message {
             is_echo: false,
             blocked: false,
             condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    pub fn dcc_chat_request_received(
+        from: &User,
+        query: &target::Query,
+    ) -> Message {
+        let received_at = Posix::now();
+        let server_time = Utc::now();
+        let content =
+            plain(format!("{} wants to start a DCC chat", from.nickname()));
+        let hash = Hash::new(&server_time, &content);
+
+        Message {
+            received_at,
+            server_time,
+            direction: Direction::Received,
+            target: Target::Query {
+                query: query.clone(),
+                source: Source::Action(None),
+            },
+            content,
+            id: None,
+            hash,
+            hidden_urls: HashSet::default(),
+            is_echo: false,
+            blocked: false,
+            condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    pub fn dcc_chat_request_sent(to: &User, query: &target::Query) -> Message {
+        let received_at = Posix::now();
+        let server_time = Utc::now();
+        let content =
+            plain(format!("offering a DCC chat to {}", to.nickname()));
+        let hash = Hash::new(&server_time, &content);
+
+        Message {
+            received_at,
+            server_time,
+            direction: Direction::Sent,
+            target: Target::Query {
+                query: query.clone(),
+                source: Source::Action(None),
+            },
+            content,
+            id: None,
+            hash,
+            hidden_urls: HashSet::default(),
+            is_echo: false,
+            blocked: false,
+            condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
         }
     }
 
@@ -415,6 +544,30 @@ impl Message {
         Self { target, ..self }
     }
 
+    /// Moves a message bound for the server buffer into the buffer of
+    /// `category`, leaving any other message where it is
+    pub fn with_category(self, category: target::Category) -> Self {
+        match self.target {
+            Target::Server { source } => Self {
+                target: Target::ServerCategory { category, source },
+                ..self
+            },
+            _ => self,
+        }
+    }
+
+    /// Severity of an operator server notice, `None` for any other message
+    pub fn snotice_severity(&self) -> Option<SnoticeSeverity> {
+        matches!(
+            self.target,
+            Target::ServerCategory {
+                category: target::Category::Snotices,
+                ..
+            }
+        )
+        .then(|| snotice_severity(&self.content.text()))
+    }
+
     pub fn plain(&self) -> Option<&str> {
         match &self.content {
             Content::Plain(s) => Some(s),
@@ -427,6 +580,25 @@ impl Message {
         self.content.text().to_string()
     }
 
+    /// Raw mode strings of the mode changes described in the message.
+    pub fn raw_modes(&self) -> Option<String> {
+        let Content::Fragments(fragments) = &self.content else {
+            return None;
+        };
+
+        let raw_modes = fragments
+            .iter()
+            .filter_map(|fragment| match fragment {
+                Fragment::ModeChange { raw, .. } => Some(raw.split("; ")),
+                _ => None,
+            })
+            .flatten()
+            .unique()
+            .join("; ");
+
+        (!raw_modes.is_empty()).then_some(raw_modes)
+    }
+
     pub fn log(record: crate::log::Record) -> Self {
         let received_at = Posix::now();
         let server_time = record.timestamp;
@@ -448,6 +620,53 @@ impl Message {
             is_echo: false,
             blocked: false,
             condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
+        }
+    }
+
+    /// Message shown in `buffer` without being sent anywhere, e.g. the
+    /// output of `/exec`
+    pub fn local(
+        buffer: &crate::buffer::Upstream,
+        source: Source,
+        content: Content,
+    ) -> Self {
+        let received_at = Posix::now();
+        let server_time = Utc::now();
+        let target = match buffer {
+            crate::buffer::Upstream::Server(_) => Target::Server { source },
+            crate::buffer::Upstream::ServerCategory(_, category) => {
+                Target::ServerCategory {
+                    category: *category,
+                    source,
+                }
+            }
+            crate::buffer::Upstream::Channel(_, channel) => Target::Channel {
+                channel: channel.clone(),
+                source,
+            },
+            crate::buffer::Upstream::Query(_, query) => Target::Query {
+                query: query.clone(),
+                source,
+            },
+        };
+        let hash = Hash::new(&server_time, &content);
+
+        Self {
+            received_at,
+            server_time,
+            direction: Direction::Received,
+            target,
+            content,
+            id: None,
+            hash,
+            hidden_urls: HashSet::default(),
+            is_echo: false,
+            blocked: false,
+            condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
         }
     }
 
@@ -547,6 +766,10 @@ impl Serialize for Message {
             text: Cow<'a, str>,
             hidden_urls: &'a HashSet<url::Url>,
             is_echo: &'a bool,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            batch: &'a Option<Batch>,
+            #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+            tags: &'a BTreeMap<String, String>,
         }
 
         Data {
@@ -559,6 +782,8 @@ impl Serialize for Message {
             text: self.content.text(),
             hidden_urls: &self.hidden_urls,
             is_echo: &self.is_echo,
+            batch: &self.batch,
+            tags: &self.tags,
         }
         .serialize(serializer)
     }
@@ -586,6 +811,10 @@ impl<'de> Deserialize<'de> for Message {
             // New field, optional for upgrade compatibility
             #[serde(default, deserialize_with = "fail_as_none")]
             is_echo: Option<bool>,
+            #[serde(default, deserialize_with = "fail_as_none")]
+            batch: Option<Batch>,
+            #[serde(default)]
+            tags: BTreeMap<String, String>,
         }
 
         let Data {
@@ -598,6 +827,8 @@ impl<'de> Deserialize<'de> for Message {
             id,
             hidden_urls,
             is_echo,
+            batch,
+            tags,
         } = Data::deserialize(deserializer)?;
 
         let content = if let Some(content) = content {
@@ -626,6 +857,8 @@ impl<'de> Deserialize<'de> for Message {
             is_echo,
             blocked: false,
             condensed: None,
+            batch,
+            tags,
         })
     }
 }
@@ -643,6 +876,10 @@ pub fn condense(
 
         let target = match &first_message.target {
             Target::Server { .. } => Target::Server { source },
+            Target::ServerCategory { category, .. } => Target::ServerCategory {
+                category: *category,
+                source,
+            },
             Target::Channel { channel, .. } => Target::Channel {
                 channel: channel.clone(),
                 source,
@@ -663,8 +900,44 @@ pub fn condense(
 
         let mut condensed_fragments: IndexMap<Nick, Vec<Fragment>> =
             IndexMap::new();
+        let mut mode_changes: IndexMap<Nick, (Fragment, Vec<ModeGroup>)> =
+            IndexMap::new();
 
         messages.iter().for_each(|message| {
+            // Mode changes are grouped by who set them
+            if let Source::Server(Some(source)) = message.target.source()
+                && matches!(source.kind(), Kind::ChangeMode)
+                && let Some(nick) = source.nick()
+            {
+                let groups = mode_groups(&message.content);
+
+                if !groups.is_empty() {
+                    let setter = match &message.content {
+                        Content::Fragments(fragments) => fragments
+                            .first()
+                            .filter(|fragment| {
+                                matches!(fragment, Fragment::User(..))
+                            })
+                            .cloned(),
+                        _ => None,
+                    }
+                    .unwrap_or_else(|| {
+                        Fragment::User(
+                            User::from(nick.clone()),
+                            nick.to_string(),
+                        )
+                    });
+
+                    mode_changes
+                        .entry(nick.clone())
+                        .or_insert_with(|| (setter, vec![]))
+                        .1
+                        .extend(groups);
+                }
+
+                return;
+            }
+
             if let Source::Server(Some(source)) = message.target.source()
                 && let Some(nick) = source.nick()
                 && let Some(nick_fragment) = match source.kind() {
@@ -819,6 +1092,25 @@ pub fn condense(
             .collect();
         condensed_fragments.pop(); // Remove trailing whitespace fragment
 
+        for (setter, groups) in mode_changes.into_values() {
+            if !condensed_fragments.is_empty() {
+                condensed_fragments.push(Fragment::Text(String::from("  ")));
+            }
+
+            condensed_fragments.push(setter);
+            condensed_fragments.push(Fragment::Text(String::from(" ")));
+            condensed_fragments.extend(mode_group_fragments(groups));
+        }
+
+        // Label netsplits and netjoins condensed on their own
+        if let Some(batch) = first_message.batch.filter(Batch::is_net_change)
+            && !condensed_fragments.is_empty()
+            && messages.iter().all(|message| message.batch == Some(batch))
+        {
+            condensed_fragments
+                .insert(0, Fragment::Text(format!("{}: ", batch.as_str())));
+        }
+
         Some(Arc::new(Message {
             received_at: Posix::now(),
             server_time: first_message.server_time,
@@ -831,12 +1123,144 @@ pub fn condense(
             is_echo: false,
             blocked: false,
             condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
         }))
     } else {
         None
     }
 }
 
+/// Mode changes sharing a description, e.g. "gave op to" with the nicknames
+/// given op.
+struct ModeGroup {
+    description: String,
+    raw: String,
+    args: Vec<Fragment>,
+}
+
+/// Fragments describing channel mode changes in plain language, e.g.
+/// "alice gave op to bob, carol; set channel invite-only".
+fn mode_change_fragments(
+    user: &User,
+    changes: &[mode::Mode<mode::Channel>],
+    raw: &str,
+    channel_users: Option<&ChannelUsers>,
+    casemapping: isupport::CaseMap,
+) -> Vec<Fragment> {
+    let groups = changes.iter().map(|change| {
+        let (description, arg) = change.describe();
+
+        let arg = arg.map(|arg| {
+            if change.value().is_membership() {
+                let nick = Nick::from_str(arg, casemapping);
+
+                let user = channel_users
+                    .and_then(|users| users.get_by_nick(nick.as_nickref()))
+                    .cloned()
+                    .unwrap_or_else(|| User::from(nick));
+
+                Fragment::User(user, arg.to_string())
+            } else {
+                Fragment::Text(arg.to_string())
+            }
+        });
+
+        ModeGroup {
+            description,
+            raw: raw.to_string(),
+            args: arg.into_iter().collect(),
+        }
+    });
+
+    let mut fragments = vec![
+        Fragment::User(user.clone(), user.nickname().to_string()),
+        Fragment::Text(String::from(" ")),
+    ];
+
+    fragments.extend(mode_group_fragments(groups));
+
+    fragments
+}
+
+/// Merges consecutive groups with the same description and lays them out
+/// as fragments.
+fn mode_group_fragments(
+    groups: impl IntoIterator<Item = ModeGroup>,
+) -> Vec<Fragment> {
+    let mut merged: Vec<ModeGroup> = vec![];
+
+    for group in groups {
+        if let Some(last) = merged.last_mut()
+            && last.description == group.description
+        {
+            last.args.extend(group.args);
+
+            if !last.raw.split("; ").any(|raw| raw == group.raw) {
+                last.raw = format!("{}; {}", last.raw, group.raw);
+            }
+        } else {
+            merged.push(group);
+        }
+    }
+
+    let mut fragments = vec![];
+
+    for (index, group) in merged.into_iter().enumerate() {
+        if index > 0 {
+            fragments.push(Fragment::Text(String::from("; ")));
+        }
+
+        let text = if group.args.is_empty() {
+            group.description
+        } else {
+            format!("{} ", group.description)
+        };
+
+        fragments.push(Fragment::ModeChange {
+            text,
+            raw: group.raw,
+        });
+
+        for (index, arg) in group.args.into_iter().enumerate() {
+            if index > 0 {
+                fragments.push(Fragment::Text(String::from(", ")));
+            }
+
+            fragments.push(arg);
+        }
+    }
+
+    fragments
+}
+
+/// Recovers the groups laid out by [`mode_group_fragments`].
+fn mode_groups(content: &Content) -> Vec<ModeGroup> {
+    let Content::Fragments(fragments) = content else {
+        return vec![];
+    };
+
+    let mut groups: Vec<ModeGroup> = vec![];
+
+    for fragment in fragments {
+        match fragment {
+            Fragment::ModeChange { text, raw } => groups.push(ModeGroup {
+                description: text.trim_end().to_string(),
+                raw: raw.clone(),
+                args: vec![],
+            }),
+            Fragment::Text(text) if text == ", " || text == "; " => (),
+            fragment => {
+                if let Some(group) = groups.last_mut() {
+                    group.args.push(fragment.clone());
+                }
+            }
+        }
+    }
+
+    groups
+}
+
 fn pre_filter_fragments(
     mut fragments: Vec<Fragment>,
     condense: &config::buffer::Condensation,
@@ -1328,7 +1752,7 @@ pub enum Content {
 }
 
 impl Content {
-    fn text(&self) -> Cow<'_, str> {
+    pub fn text(&self) -> Cow<'_, str> {
         match self {
             Content::Plain(s) => s.into(),
             Content::Fragments(fragments) => {
@@ -1371,6 +1795,12 @@ pub enum Fragment {
         text: String,
         source: source::Server,
     },
+    /// Plain language description of mode changes, along with the raw mode
+    /// string they were described from.
+    ModeChange {
+        text: String,
+        raw: String,
+    },
 }
 
 impl Fragment {
@@ -1392,6 +1822,7 @@ impl Fragment {
             Fragment::HighlightNick(_, s) => s,
             Fragment::HighlightMatch(s) => s,
             Fragment::Condensed { text, .. } => text,
+            Fragment::ModeChange { text, .. } => text,
         }
     }
 }
@@ -1475,6 +1906,20 @@ fn target(
                 ))),
             })
         }
+        Command::RENAME(_, channel, _) => {
+            let channel = target::Channel::parse(
+                &channel,
+                chantypes,
+                statusmsg,
+                casemapping,
+            )
+            .ok()?;
+
+            Some(Target::Channel {
+                channel,
+                source: Source::Server(None),
+            })
+        }
         Command::PART(channel, _) => {
             let channel = target::Channel::parse(
                 &channel,
@@ -1615,6 +2060,10 @@ fn target(
                             source: source(user),
                         })
                     }
+                    // Notice from the server itself
+                    (target::Target::Query(_), None) => Some(Target::Server {
+                        source: Source::Server(None),
+                    }),
                     _ => None,
                 }
             }
@@ -1715,10 +2164,90 @@ fn target(
     }
 }
 
+/// Category of server traffic `message` belongs to. Only messages which
+/// would otherwise be shown in the server buffer are moved into the buffer
+/// of their category, see [`Message::with_category`].
+pub fn category(
+    message: &Encoded,
+    registered: bool,
+) -> Option<target::Category> {
+    use proto::command::Numeric::*;
+
+    match &message.command {
+        Command::NOTICE(_, text)
+            if !matches!(message.source, Some(proto::Source::User(_))) =>
+        {
+            // Operator server notices are prefixed with `***`, same as the
+            // notices sent while connecting, before registration completes
+            if registered && text.starts_with("*** ") {
+                Some(target::Category::Snotices)
+            } else {
+                Some(target::Category::Notices)
+            }
+        }
+        Command::WALLOPS(_) => Some(target::Category::Wallops),
+        Command::Numeric(
+            RPL_MOTDSTART | RPL_MOTD | RPL_ENDOFMOTD | ERR_NOMOTD,
+            _,
+        ) => Some(target::Category::Motd),
+        Command::Numeric(_, _) => Some(target::Category::Numerics),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnoticeSeverity {
+    Info,
+    Warning,
+    Alert,
+}
+
+/// Severity of an operator server notice, judged by keywords in its text
+pub fn snotice_severity(text: &str) -> SnoticeSeverity {
+    const ALERT: &[&str] = &[
+        "kill", "k-line", "kline", "g-line", "gline", "d-line", "dline",
+        "z-line", "zline", "flood", "spambot",
+    ];
+    const WARNING: &[&str] =
+        &["oper", "failed", "denied", "rehash", "split", "squit"];
+
+    let text = text.to_lowercase();
+
+    if ALERT.iter().any(|keyword| text.contains(keyword)) {
+        SnoticeSeverity::Alert
+    } else if WARNING.iter().any(|keyword| text.contains(keyword)) {
+        SnoticeSeverity::Warning
+    } else {
+        SnoticeSeverity::Info
+    }
+}
+
 pub fn message_id(message: &Encoded) -> Option<String> {
     message.tags.get("msgid").cloned()
 }
 
+/// The kind of batch a message was received in, tagged by the client once
+/// the batch has finished.
+pub fn batch(message: &Encoded) -> Option<Batch> {
+    message
+        .tags
+        .get("batch")
+        .map(String::as_str)
+        .and_then(Batch::parse)
+}
+
+/// Tags which aren't already represented elsewhere in the message
+pub fn tags(message: &Encoded) -> BTreeMap<String, String> {
+    message
+        .tags
+        .iter()
+        .filter(|(key, _)| {
+            !matches!(key.as_str(), "time" | "msgid" | "batch" | "label")
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
 pub fn server_time(message: &Encoded) -> DateTime<Utc> {
     message
         .tags
@@ -1737,6 +2266,7 @@ fn content<'a>(
     statusmsg: &[char],
     casemapping: isupport::CaseMap,
     prefix: &[isupport::PrefixMap],
+    chanmodes: &[isupport::ModeKind],
 ) -> Option<Content> {
     use irc::proto::command::Numeric::*;
 
@@ -1850,16 +2380,16 @@ fn content<'a>(
 
             Some(kick_text(user, victim, ourself, reason, None, casemapping))
         }
-        Command::MODE(target, modes, args) => {
+        Command::MODE(target, encoded_modes, mode_args) => {
             let raw_user = message.user(casemapping)?;
 
-            let modes = modes
+            let modes = encoded_modes
                 .iter()
                 .map(ToString::to_string)
                 .collect::<Vec<_>>()
                 .join(" ");
 
-            let mut args = args
+            let mut args = mode_args
                 .iter()
                 .flatten()
                 .map(ToString::to_string)
@@ -1888,11 +2418,33 @@ fn content<'a>(
                 .ok()
                 .and_then(|channel| channel_users(&channel));
 
-                Some(parse_fragments_with_users(
-                    format!("{} sets mode {modes}{args}", user.nickname()),
-                    channel_users,
-                    casemapping,
-                ))
+                let changes = encoded_modes
+                    .as_deref()
+                    .map(|encoded| {
+                        mode::parse::<mode::Channel>(
+                            encoded,
+                            mode_args.as_deref().unwrap_or_default(),
+                            chanmodes,
+                            prefix,
+                        )
+                    })
+                    .unwrap_or_default();
+
+                if changes.is_empty() {
+                    Some(parse_fragments_with_users(
+                        format!("{} sets mode {modes}{args}", user.nickname()),
+                        channel_users,
+                        casemapping,
+                    ))
+                } else {
+                    Some(Content::Fragments(mode_change_fragments(
+                        &user,
+                        &changes,
+                        &format!("{modes}{args}"),
+                        channel_users,
+                        casemapping,
+                    )))
+                }
             } else if raw_user.nickname() == *our_nick {
                 if casemapping.normalize(target) == our_nick.as_normalized_str()
                 {
@@ -1958,7 +2510,22 @@ fn content<'a>(
 
                 let command = query.command.as_ref();
 
-                let text = if let Some(params) = query.params {
+                // Show round-trip latency for responses to our PING
+                let latency = matches!(message.command, Command::NOTICE(..))
+                    && matches!(query.command, ctcp::Command::Ping)
+                    && casemapping.normalize(target)
+                        == our_nick.as_normalized_str();
+
+                let text = if let Some(latency) = query
+                    .params
+                    .filter(|_| latency)
+                    .and_then(ctcp::ping_latency)
+                {
+                    format!(
+                        "{arrow} {command} {}ms",
+                        latency.num_milliseconds()
+                    )
+                } else if let Some(params) = query.params {
                     [arrow, command, params].join(" ")
                 } else {
                     [arrow, command].join(" ")
@@ -2241,6 +2808,16 @@ fn content<'a>(
                 Some(plain(format!("{command} notice: {description}")))
             }
         }
+        Command::RENAME(old, new, reason) => {
+            let reason = reason
+                .as_ref()
+                .map(|reason| format!(" ({reason})"))
+                .unwrap_or_default();
+
+            Some(parse_fragments(format!(
+                "{old} has been renamed to {new}{reason}"
+            )))
+        }
         Command::WALLOPS(text) => {
             let user = message.user(casemapping)?;
 
@@ -2398,6 +2975,8 @@ pub enum Link {
     Url(String),
     User(User),
     GoToMessage(Server, target::Channel, Hash),
+    /// The message itself, used to open its context menu.
+    Message,
 }
 
 impl Link {
@@ -2472,7 +3051,7 @@ impl PartialOrd for MessageReferences {
 pub mod tests {
     #[allow(unused_imports)]
     use super::{
-        parse_fragments, parse_fragments_with_highlights,
+        condense, parse_fragments, parse_fragments_with_highlights,
         parse_fragments_with_users,
     };
     #[allow(unused_imports)]
@@ -2778,6 +3357,46 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn mode_changes() {
+        use crate::config::buffer::{Condensation, CondensationMessage};
+
+        let message = message_from_irc_message(
+            ":rabbit MODE #chan +oo-v bunny dan bunny\r\n",
+        );
+
+        assert_eq!(
+            message.content.text(),
+            "rabbit gave op to bunny, dan; took voice from bunny"
+        );
+
+        let messages = [
+            message,
+            message_from_irc_message(":rabbit MODE #chan +o chat\r\n"),
+            message_from_irc_message(":rabbit MODE #chan +i\r\n"),
+        ];
+
+        let condensation = Condensation {
+            messages: [CondensationMessage::ChangeMode].into_iter().collect(),
+            ..Condensation::default()
+        };
+
+        assert!(
+            messages
+                .iter()
+                .all(|message| message.can_condense(&condensation))
+        );
+
+        let condensed =
+            condense(&messages.iter().collect::<Vec<_>>(), &condensation)
+                .unwrap();
+
+        assert_eq!(
+            condensed.content.text(),
+            "rabbit gave op to bunny, dan; took voice from bunny; gave op to chat; set channel invite-only"
+        );
+    }
+
     pub const SERDE_IRC_MESSAGES: &[&str] = &[
         "@time=2023-07-20T21:19:11.000Z :chat!test@user/test/bot/chat PRIVMSG ##chat :\\_o< quack!\r\n",
         "@id=234AB :dan!d@localhost PRIVMSG #chan :Hey what's up! \r\n",
@@ -2789,6 +3408,7 @@ pub mod tests {
         ":soju.bouncer FAIL * ACCOUNT_REQUIRED :Authentication required\r\n",
         ":rabbit MODE #토끼세계 +o bunny\r\n",
         ":dan!d@localhost PRIVMSG #chan :Need a highlight our_nick?\r\n",
+        "@time=2025-06-01T12:00:00.000Z;msgid=AB12;+example.org/bridge=discord :dan!d@localhost PRIVMSG #chan :relayed from elsewhere\r\n",
     ];
 
     pub fn message_from_irc_message(irc_message: &str) -> Message {
@@ -2838,6 +3458,7 @@ pub mod tests {
             isupport::get_statusmsg_or_default(&isupport),
             isupport::get_casemapping_or_default(&isupport),
             isupport::get_prefix_or_default(&isupport),
+            isupport::get_chanmodes_or_default(&isupport),
         )
         .unwrap_or_else(|| panic!("failed to create Message from {encoded:?}"))
     }
@@ -2882,6 +3503,7 @@ pub mod tests {
                 comment: Some("see you later our_nick".to_string()),
                 user_channels: user_channels.clone(),
                 casemapping: isupport::get_casemapping_or_default(&isupport),
+                batch: None,
             },
             Broadcast::Nickname {
                 old_nick: Nick::from_str(
@@ -3054,6 +3676,108 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn vendor_tags() {
+        let message = message_from_irc_message(SERDE_IRC_MESSAGES[10]);
+
+        assert_eq!(
+            message.tags.into_iter().collect::<Vec<_>>(),
+            vec![("+example.org/bridge".to_string(), "discord".to_string())]
+        );
+    }
+
+    #[test]
+    fn server_traffic_category() {
+        use irc::proto;
+
+        use crate::message::{Encoded, category};
+        use crate::target::Category;
+
+        let tests = [
+            (
+                ":irc.example.org NOTICE * :*** Looking up your hostname",
+                false,
+                Some(Category::Notices),
+            ),
+            (
+                ":irc.example.org NOTICE nick :*** Notice -- Client connecting",
+                true,
+                Some(Category::Snotices),
+            ),
+            (
+                ":irc.example.org NOTICE nick :Welcome to the network",
+                true,
+                Some(Category::Notices),
+            ),
+            (":dan!d@localhost NOTICE nick :*** hello", true, None),
+            (
+                ":irc.example.org WALLOPS :Rehashing",
+                true,
+                Some(Category::Wallops),
+            ),
+            (
+                ":irc.example.org 372 nick :- Be nice",
+                true,
+                Some(Category::Motd),
+            ),
+            (
+                ":irc.example.org 422 nick :MOTD File is missing",
+                true,
+                Some(Category::Motd),
+            ),
+            (
+                ":irc.example.org 251 nick :There are 3 users",
+                true,
+                Some(Category::Numerics),
+            ),
+            (":dan!d@localhost PRIVMSG #chat :hello", true, None),
+        ];
+
+        for (line, registered, expected) in tests {
+            let encoded = Encoded::from(
+                proto::parse::message(&format!("{line}\r\n")).unwrap(),
+            );
+
+            assert_eq!(category(&encoded, registered), expected, "{line}");
+        }
+    }
+
+    #[test]
+    fn snotice_severities() {
+        use crate::message::{SnoticeSeverity, snotice_severity};
+
+        let tests = [
+            (
+                "*** Notice -- Client connecting: dan (d@localhost) [127.0.0.1]",
+                SnoticeSeverity::Info,
+            ),
+            (
+                "*** Notice -- dan (d@localhost) is now an operator",
+                SnoticeSeverity::Warning,
+            ),
+            (
+                "*** Notice -- Failed OPER attempt by dan (d@localhost)",
+                SnoticeSeverity::Warning,
+            ),
+            (
+                "*** Notice -- Received KILL message for spam. From oper",
+                SnoticeSeverity::Alert,
+            ),
+            (
+                "*** Notice -- oper added global K-Line for [*@spam.example]",
+                SnoticeSeverity::Alert,
+            ),
+            (
+                "*** Notice -- Possible Flooder spam[s@spam.example]",
+                SnoticeSeverity::Alert,
+            ),
+        ];
+
+        for (text, expected) in tests {
+            assert_eq!(snotice_severity(text), expected, "{text}");
+        }
+    }
+
     // Test Message deserialization from samples of messages serialized by
     // earlier versions (i.e. backward compatibility)
     #[test]
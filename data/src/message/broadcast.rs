@@ -1,5 +1,5 @@
 //! Generate messages that can be broadcast into every buffer
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
 use chrono::{DateTime, Utc};
 
@@ -40,6 +40,8 @@ fn expand(
             is_echo: false,
             blocked: false,
             condensed: None,
+            batch: None,
+            tags: BTreeMap::new(),
         }
     };
 
@@ -105,6 +107,50 @@ pub fn connected(sent_time: DateTime<Utc>) -> Vec<Message> {
     )
 }
 
+pub fn hostname_exposed(
+    hostname: Option<String>,
+    sent_time: DateTime<Utc>,
+) -> Vec<Message> {
+    let hostname = hostname.map_or_else(
+        || "your hostname".to_string(),
+        |hostname| format!("your hostname ({hostname})"),
+    );
+    let content = plain(format!(
+        "not authenticated and {hostname} isn't cloaked, so it's visible \
+         to other users (authenticate with SASL to get the network's cloak, \
+         if it has one)"
+    ));
+    expand(
+        [],
+        [],
+        true,
+        Cause::Status(source::Status::Error),
+        content,
+        sent_time,
+    )
+}
+
+pub fn ctcp_request(
+    user: &User,
+    command: &str,
+    replied: bool,
+    sent_time: DateTime<Utc>,
+) -> Vec<Message> {
+    let content = plain(format!(
+        "received CTCP {command} request from {}{}",
+        user.nickname(),
+        if replied { "" } else { " (not replied)" }
+    ));
+    expand(
+        [],
+        [],
+        true,
+        Cause::Status(source::Status::Success),
+        content,
+        sent_time,
+    )
+}
+
 pub fn connection_failed(
     error: String,
     sent_time: DateTime<Utc>,
@@ -340,6 +386,7 @@ pub enum Broadcast {
         comment: Option<String>,
         user_channels: Vec<target::Channel>,
         casemapping: isupport::CaseMap,
+        batch: Option<message::Batch>,
     },
     Nickname {
         old_nick: Nick,
@@ -370,6 +417,14 @@ pub enum Broadcast {
         channel: target::Channel,
         casemapping: isupport::CaseMap,
     },
+    HostnameExposed {
+        hostname: Option<String>,
+    },
+    CtcpRequest {
+        user: User,
+        command: String,
+        replied: bool,
+    },
 }
 
 pub fn into_messages(
@@ -390,11 +445,20 @@ pub fn into_messages(
             disconnected(channels, queries, error, sent_time)
         }
         Broadcast::Reconnected => reconnected(channels, queries, sent_time),
+        Broadcast::HostnameExposed { hostname } => {
+            hostname_exposed(hostname, sent_time)
+        }
+        Broadcast::CtcpRequest {
+            user,
+            command,
+            replied,
+        } => ctcp_request(&user, &command, replied, sent_time),
         Broadcast::Quit {
             user,
             comment,
             user_channels,
             casemapping,
+            batch,
         } => {
             let user_query = queries.find(|query| {
                 user.as_normalized_str() == query.as_normalized_str()
@@ -409,6 +473,9 @@ pub fn into_messages(
                 casemapping,
                 sent_time,
             )
+            .into_iter()
+            .map(|message| Message { batch, ..message })
+            .collect()
         }
         Broadcast::Nickname {
             old_nick,
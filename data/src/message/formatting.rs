@@ -595,11 +595,39 @@ impl Color {
         }
     }
 
-    pub fn into_iced(
+    /// The color to draw, taking the theme's replacement for the code and
+    /// saturation limit into account.
+    pub fn into_iced(self, styles: &theme::Styles) -> Option<iced_core::Color> {
+        let formatting = &styles.formatting;
+
+        formatting
+            .colors
+            .get(usize::from(self.digit()))
+            .copied()
+            .flatten()
+            .or_else(|| self.default_iced())
+            .map(|color| formatting.clamp(color))
+    }
+
+    /// [`Color::into_iced`] for text, which is additionally kept readable
+    /// against the buffer background when the theme asks for it.
+    pub fn into_iced_foreground(
         self,
-        _styles: &theme::Styles,
+        styles: &theme::Styles,
     ) -> Option<iced_core::Color> {
-        // TODO: Theme aware 0 - 15 colors
+        let color = self.into_iced(styles)?;
+
+        Some(match styles.formatting.min_contrast {
+            Some(min_contrast) => theme::ensure_contrast(
+                color,
+                styles.buffer.background,
+                min_contrast,
+            ),
+            None => color,
+        })
+    }
+
+    fn default_iced(self) -> Option<iced_core::Color> {
         match self {
             Color::White => Some(color!(0xffffff)),
             Color::Black => Some(color!(0x000000)),
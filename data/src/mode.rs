@@ -185,6 +185,94 @@ impl Parser for Channel {
     }
 }
 
+impl Parser for char {
+    fn from_char(c: char) -> Self {
+        c
+    }
+}
+
+impl Channel {
+    /// Whether the argument of the mode is a nickname.
+    pub fn is_membership(&self) -> bool {
+        matches!(
+            self,
+            Channel::Founder
+                | Channel::Protected(_)
+                | Channel::Oper
+                | Channel::HalfOp
+                | Channel::Voice
+        )
+    }
+}
+
+impl Mode<Channel> {
+    /// Plain language description of the change, e.g. "gave op to", along
+    /// with the argument to follow it (if any).
+    pub fn describe(&self) -> (String, Option<&str>) {
+        use Channel::*;
+
+        let Some(operation) = self.operation() else {
+            return (format!("listed mode {}", self.value()), None);
+        };
+        let is_add = matches!(operation, Operation::Add);
+        let arg = self.arg();
+
+        let membership = |name: &str| {
+            if is_add {
+                format!("gave {name} to")
+            } else {
+                format!("took {name} from")
+            }
+        };
+
+        let description = match self.value() {
+            Founder => membership("owner"),
+            Protected(_) => membership("admin"),
+            Oper => membership("op"),
+            HalfOp => membership("half-op"),
+            Voice => membership("voice"),
+            Ban if is_add => String::from("banned"),
+            Ban => String::from("unbanned"),
+            BanException | InviteException => {
+                let name = self.value().to_string().to_lowercase();
+
+                if is_add {
+                    format!("added {name} for")
+                } else {
+                    format!("removed {name} for")
+                }
+            }
+            KeyLock => {
+                // Don't show the key itself
+                return (
+                    String::from(if is_add {
+                        "set channel key"
+                    } else {
+                        "removed channel key"
+                    }),
+                    None,
+                );
+            }
+            Limit if is_add => String::from("set user limit to"),
+            Limit => String::from("removed user limit"),
+            Unknown(c) => {
+                format!("set mode {}{c}", if is_add { '+' } else { '-' })
+            }
+            value => {
+                let name = value.to_string().to_lowercase().replace(' ', "-");
+
+                match (is_add, arg.is_some()) {
+                    (true, true) => format!("set channel {name} to"),
+                    (true, false) => format!("set channel {name}"),
+                    (false, _) => format!("unset channel {name}"),
+                }
+            }
+        };
+
+        (description, arg)
+    }
+}
+
 // Reference: https://defs.ircdocs.horse/defs/usermodes
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -418,4 +506,56 @@ mod test {
             assert_eq!(modes, expected);
         }
     }
+    #[test]
+    fn describe() {
+        let tests = [
+            (
+                Mode::Add(Channel::Oper, Some("bob".into())),
+                "gave op to",
+                Some("bob"),
+            ),
+            (
+                Mode::Remove(Channel::Voice, Some("bob".into())),
+                "took voice from",
+                Some("bob"),
+            ),
+            (
+                Mode::Add(Channel::InviteOnly, None),
+                "set channel invite-only",
+                None,
+            ),
+            (
+                Mode::Remove(Channel::Moderated, None),
+                "unset channel moderated",
+                None,
+            ),
+            (
+                Mode::Add(Channel::Ban, Some("*!*@host".into())),
+                "banned",
+                Some("*!*@host"),
+            ),
+            (
+                Mode::Add(Channel::KeyLock, Some("hunter2".into())),
+                "set channel key",
+                None,
+            ),
+            (
+                Mode::Add(Channel::Limit, Some("10".into())),
+                "set user limit to",
+                Some("10"),
+            ),
+            (
+                Mode::Remove(Channel::Unknown('Z'), None),
+                "set mode -Z",
+                None,
+            ),
+        ];
+
+        for (mode, description, arg) in tests {
+            let (actual_description, actual_arg) = mode.describe();
+
+            assert_eq!(actual_description, description);
+            assert_eq!(actual_arg, arg);
+        }
+    }
 }
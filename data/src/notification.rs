@@ -1,4 +1,8 @@
+use chrono::{DateTime, Local, Utc};
+use serde_json::json;
+
 use crate::User;
+use crate::config::notification::{Email, Push};
 use crate::target::Channel;
 use crate::user::Nick;
 
@@ -10,17 +14,172 @@ pub enum Notification {
     DirectMessage {
         user: User,
         message: String,
+        /// Configured alias of the user
+        alias: Option<String>,
     },
     Highlight {
         user: User,
         channel: Channel,
         message: String,
         description: String,
+        /// Configured alias of the channel
+        alias: Option<String>,
     },
     FileTransferRequest {
         nick: Nick,
         filename: String,
     },
+    DccChatRequest {
+        nick: Nick,
+    },
     MonitoredOnline(Vec<User>),
     MonitoredOffline(Vec<Nick>),
+    Watch {
+        name: String,
+        nick: Nick,
+        target: String,
+        message: String,
+    },
+    /// Sent by a script with `halloy.notify`
+    Script {
+        title: String,
+        body: String,
+    },
+}
+
+/// Delivers a notification through the configured push service
+pub async fn push(
+    config: &Push,
+    title: &str,
+    body: &str,
+) -> Result<(), reqwest::Error> {
+    let client = reqwest::Client::new();
+
+    let request = match config {
+        Push::Ntfy { url, topic, token } => {
+            let request = client.post(url.trim_end_matches('/')).json(&json!({
+                "topic": topic,
+                "title": title,
+                "message": body,
+            }));
+
+            match token {
+                Some(token) => request.bearer_auth(token),
+                None => request,
+            }
+        }
+        Push::Gotify {
+            url,
+            token,
+            priority,
+        } => client
+            .post(format!("{}/message", url.trim_end_matches('/')))
+            .header("X-Gotify-Key", token)
+            .json(&json!({
+                "title": title,
+                "message": body,
+                "priority": priority,
+            })),
+    };
+
+    request.send().await?.error_for_status()?;
+
+    Ok(())
+}
+
+/// A notification waiting to be sent with the next email digest
+#[derive(Debug, Clone)]
+pub struct DigestEntry {
+    pub time: DateTime<Utc>,
+    pub title: String,
+    pub body: String,
+}
+
+/// Composes the email digest of `entries`, headers included, as expected by
+/// `sendmail -t -i`
+pub fn email_digest(config: &Email, entries: &[DigestEntry]) -> String {
+    let mut email = format!("To: {}\n", config.to);
+
+    if let Some(from) = &config.from {
+        email.push_str(&format!("From: {from}\n"));
+    }
+
+    email.push_str(&format!(
+        "Subject: Halloy: {} new notification{}\n",
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" }
+    ));
+    email.push_str("Content-Type: text/plain; charset=utf-8\n\n");
+
+    for entry in entries {
+        email.push_str(&format!(
+            "[{}] {}\n{}\n\n",
+            entry.time.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+            entry.title,
+            entry.body
+        ));
+    }
+
+    email
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn digest() {
+        let config = Email {
+            to: "me@example.org".to_string(),
+            from: Some("halloy@example.org".to_string()),
+            command: vec!["sendmail".to_string(), "-t".to_string()],
+            interval: 15,
+        };
+        let entry = |title: &str, body: &str| DigestEntry {
+            time: Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap(),
+            title: title.to_string(),
+            body: body.to_string(),
+        };
+
+        let email = email_digest(
+            &config,
+            &[entry("dan in #halloy", "hi"), entry("dan", "one\n.\ntwo")],
+        );
+        let (headers, body) = email.split_once("\n\n").unwrap();
+
+        assert_eq!(
+            headers,
+            "To: me@example.org\n\
+             From: halloy@example.org\n\
+             Subject: Halloy: 2 new notifications\n\
+             Content-Type: text/plain; charset=utf-8"
+        );
+
+        let time = Utc
+            .with_ymd_and_hms(2024, 3, 1, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M");
+
+        assert_eq!(
+            body,
+            format!(
+                "[{time}] dan in #halloy\nhi\n\n[{time}] dan\none\n.\ntwo\n\n"
+            )
+        );
+
+        let email = email_digest(
+            &Email {
+                from: None,
+                ..config
+            },
+            &[entry("dan", "hi")],
+        );
+
+        assert!(email.starts_with(
+            "To: me@example.org\nSubject: Halloy: 1 new notification\n"
+        ));
+    }
 }
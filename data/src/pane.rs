@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::Buffer;
+use crate::buffer::Density;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum Pane {
@@ -12,6 +13,9 @@ pub enum Pane {
     },
     Buffer {
         buffer: Buffer,
+        /// Density chosen for the pane, overriding `buffer.density`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        density: Option<Density>,
     },
     Empty,
 }
@@ -0,0 +1,315 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+
+use crate::config::buffer::Polls;
+use crate::message::{self, Source};
+
+/// A poll started in a channel, along with the votes cast since.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poll {
+    pub question: String,
+    pub options: Vec<String>,
+    /// Latest vote per (normalized) nickname.
+    votes: IndexMap<String, usize>,
+}
+
+impl Poll {
+    /// Number of votes for each option, in option order.
+    pub fn tally(&self) -> Vec<usize> {
+        let mut tally = vec![0; self.options.len()];
+
+        for option in self.votes.values() {
+            tally[*option] += 1;
+        }
+
+        tally
+    }
+
+    pub fn total_votes(&self) -> usize {
+        self.votes.len()
+    }
+
+    /// The option the given nickname last voted for, if any.
+    pub fn vote_of(&self, nick: &str) -> Option<usize> {
+        self.votes.get(nick).copied()
+    }
+}
+
+/// Polls started in a buffer, along with the votes cast since. Messages are
+/// fed in chronological order, and only the most recently started poll
+/// receives votes. Polls are keyed by the hash of the message that started
+/// them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Tally {
+    polls: HashMap<message::Hash, Poll>,
+    current: Option<message::Hash>,
+}
+
+impl Tally {
+    pub fn get(&self, hash: &message::Hash) -> Option<&Poll> {
+        self.polls.get(hash)
+    }
+
+    pub fn feed(&mut self, message: &message::Message, config: &Polls) {
+        let Source::User(user) = message.target.source() else {
+            return;
+        };
+
+        let text = message.text();
+
+        if let Some((question, options)) = parse_poll(&text, config) {
+            self.polls.insert(
+                message.hash,
+                Poll {
+                    question,
+                    options,
+                    votes: IndexMap::new(),
+                },
+            );
+            self.current = Some(message.hash);
+        } else if let Some(poll) =
+            self.current.and_then(|hash| self.polls.get_mut(&hash))
+            && let Some(option) = parse_vote(&text, &poll.options, config)
+        {
+            let nick = user.nickname().as_normalized_str().to_string();
+
+            // A user may change their vote; keep it ordered by latest vote
+            poll.votes.shift_remove(&nick);
+            poll.votes.insert(nick, option);
+        }
+    }
+}
+
+/// Tallies every poll in `messages`, which are in chronological order.
+pub fn collect<'a>(
+    messages: impl IntoIterator<Item = &'a message::Message>,
+    config: &Polls,
+) -> Tally {
+    messages
+        .into_iter()
+        .fold(Tally::default(), |mut tally, message| {
+            tally.feed(message, config);
+            tally
+        })
+}
+
+/// The tally of the messages last shown in a buffer. Unchanged messages are
+/// not scanned again, and messages added after them are fed to the previous
+/// tally.
+#[derive(Debug, Clone, Default)]
+pub struct Cache(RefCell<Option<Cached>>);
+
+#[derive(Debug, Clone)]
+struct Cached {
+    config: Polls,
+    first: message::Hash,
+    last: message::Hash,
+    len: usize,
+    tally: Tally,
+}
+
+impl Cache {
+    /// Tally of `messages`, which are in chronological order.
+    pub fn tally(
+        &self,
+        messages: &[&message::Message],
+        config: &Polls,
+    ) -> Tally {
+        let mut cached = self.0.borrow_mut();
+
+        let (Some(first), Some(last)) = (messages.first(), messages.last())
+        else {
+            *cached = None;
+
+            return Tally::default();
+        };
+
+        let tally = match cached.take() {
+            Some(Cached {
+                config: cached_config,
+                first: cached_first,
+                last: cached_last,
+                len,
+                mut tally,
+            }) if cached_config == *config
+                && cached_first == first.hash
+                && messages
+                    .get(len - 1)
+                    .is_some_and(|message| message.hash == cached_last) =>
+            {
+                for message in &messages[len..] {
+                    tally.feed(message, config);
+                }
+
+                tally
+            }
+            _ => collect(messages.iter().copied(), config),
+        };
+
+        *cached = Some(Cached {
+            config: config.clone(),
+            first: first.hash,
+            last: last.hash,
+            len: messages.len(),
+            tally: tally.clone(),
+        });
+
+        tally
+    }
+}
+
+/// Parses `<start_prefix> question | option | option [| ...]`.
+fn parse_poll(text: &str, config: &Polls) -> Option<(String, Vec<String>)> {
+    let rest = strip_keyword(text, &config.start_prefix)?;
+
+    let mut parts = rest.split('|').map(str::trim);
+
+    let question = parts.next().filter(|question| !question.is_empty())?;
+    let options = parts
+        .filter(|option| !option.is_empty())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    (options.len() >= 2).then(|| (question.to_string(), options))
+}
+
+/// Parses `<vote_prefix> <number>` (1-indexed) or `<vote_prefix> <option>`.
+fn parse_vote(text: &str, options: &[String], config: &Polls) -> Option<usize> {
+    let choice = strip_keyword(text, &config.vote_prefix)?.trim();
+
+    if let Ok(number) = choice.parse::<usize>() {
+        return (1..=options.len()).contains(&number).then(|| number - 1);
+    }
+
+    options
+        .iter()
+        .position(|option| option.eq_ignore_ascii_case(choice))
+}
+
+fn strip_keyword<'a>(text: &'a str, keyword: &str) -> Option<&'a str> {
+    let rest = text.trim_start().strip_prefix(keyword)?;
+
+    (rest.is_empty() || rest.starts_with(char::is_whitespace)).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use irc::proto;
+
+    use super::*;
+    use crate::isupport::CaseMap;
+    use crate::message::{Message, Target};
+    use crate::target::Channel;
+    use crate::user::{Nick, User};
+
+    fn message(nick: &str, text: &str) -> Message {
+        Message::sent(
+            Target::Channel {
+                channel: Channel::from_str(
+                    "#halloy",
+                    proto::DEFAULT_CHANNEL_PREFIXES,
+                    CaseMap::default(),
+                ),
+                source: Source::User(User::from(Nick::from_str(
+                    nick,
+                    CaseMap::default(),
+                ))),
+            },
+            message::plain(text.to_string()),
+        )
+    }
+
+    #[test]
+    fn poll() {
+        let config = Polls::default();
+
+        assert_eq!(
+            parse_poll("!poll Lunch? | pizza | sushi | tacos", &config),
+            Some((
+                "Lunch?".to_string(),
+                vec![
+                    "pizza".to_string(),
+                    "sushi".to_string(),
+                    "tacos".to_string()
+                ]
+            ))
+        );
+        assert_eq!(parse_poll("!poll Lunch? | pizza", &config), None);
+        assert_eq!(parse_poll("!polling Lunch? | a | b", &config), None);
+    }
+
+    #[test]
+    fn vote() {
+        let config = Polls::default();
+        let options = vec!["pizza".to_string(), "sushi".to_string()];
+
+        assert_eq!(parse_vote("!vote 2", &options, &config), Some(1));
+        assert_eq!(parse_vote("!vote Pizza", &options, &config), Some(0));
+        assert_eq!(parse_vote("!vote 3", &options, &config), None);
+        assert_eq!(parse_vote("!vote 0", &options, &config), None);
+        assert_eq!(parse_vote("vote 1", &options, &config), None);
+    }
+
+    #[test]
+    fn tally() {
+        let config = Polls::default();
+        let messages = [
+            message("alice", "!poll Lunch? | pizza | sushi"),
+            message("bob", "!vote 1"),
+            message("carol", "!vote sushi"),
+            message("bob", "!vote 2"),
+            message("dave", "hungry"),
+        ];
+
+        let tally = collect(&messages, &config);
+        let poll = tally.get(&messages[0].hash).unwrap();
+
+        assert_eq!(poll.tally(), vec![0, 2]);
+        assert_eq!(poll.total_votes(), 2);
+        assert_eq!(poll.vote_of("bob"), Some(1));
+
+        // Only the latest poll receives votes
+        let messages = [
+            message("alice", "!poll Lunch? | pizza | sushi"),
+            message("bob", "!poll Dinner? | soup | salad"),
+            message("carol", "!vote 1"),
+        ];
+
+        let tally = collect(&messages, &config);
+
+        assert_eq!(tally.get(&messages[0].hash).unwrap().tally(), vec![0, 0]);
+        assert_eq!(tally.get(&messages[1].hash).unwrap().tally(), vec![1, 0]);
+    }
+
+    #[test]
+    fn cache() {
+        let config = Polls::default();
+        let messages = [
+            message("alice", "!poll Lunch? | pizza | sushi"),
+            message("bob", "!vote 1"),
+            message("carol", "!vote 2"),
+            message("dave", "!vote 2"),
+        ];
+        let messages = messages.iter().collect::<Vec<_>>();
+
+        let cache = Cache::default();
+
+        assert_eq!(
+            cache.tally(&messages[..2], &config),
+            collect(messages[..2].iter().copied(), &config)
+        );
+        // Appended messages are fed to the cached tally
+        assert_eq!(
+            cache.tally(&messages, &config),
+            collect(messages.iter().copied(), &config)
+        );
+        // Messages which no longer start the same are tallied again
+        assert_eq!(
+            cache.tally(&messages[1..], &config),
+            collect(messages[1..].iter().copied(), &config)
+        );
+        assert_eq!(cache.tally(&[], &config), Tally::default());
+    }
+}
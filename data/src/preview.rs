@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::sync::{LazyLock, OnceLock};
 use std::time::Duration;
@@ -6,6 +6,7 @@ use std::time::Duration;
 use ::image::image_dimensions;
 use fancy_regex::Regex;
 use iced_wgpu::wgpu;
+use indexmap::IndexSet;
 use log::debug;
 use reqwest::header::{self, HeaderValue};
 use serde::{Deserialize, Serialize};
@@ -17,7 +18,7 @@ use tokio::time;
 use url::Url;
 
 pub use self::card::Card;
-pub use self::image::Image;
+pub use self::image::{Image, Thumbnail};
 use crate::target::Target;
 use crate::{config, isupport};
 
@@ -35,6 +36,9 @@ static OPENGRAPH_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     )
     .expect("valid opengraph regex")
 });
+static TITLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<title[^>]*>(.*?)</title>").expect("valid title regex")
+});
 
 #[derive(Clone, Copy)]
 pub struct Previews<'a> {
@@ -63,7 +67,7 @@ impl<'a> Previews<'a> {
         self.collection.get(url).filter(|state| match state {
             State::Loading => true,
             State::Loaded(preview) => match preview {
-                Preview::Card(_) => self.cards_are_visible,
+                Preview::Card(_) | Preview::Title(_) => self.cards_are_visible,
                 Preview::Image(_) => self.images_are_visible,
             },
             State::Error(_) => true,
@@ -71,20 +75,160 @@ impl<'a> Previews<'a> {
     }
 }
 
-pub type Collection = HashMap<Url, State>;
+/// Previews of the URLs visible in buffers, along with previews that are no
+/// longer visible but retained while their thumbnails fit in memory
+#[derive(Debug, Default)]
+pub struct Collection {
+    states: HashMap<Url, State>,
+    /// Retained URLs, least recently visible first
+    retained: IndexSet<Url>,
+    /// Loaded images, visible or retained
+    images: usize,
+    /// Size of the thumbnails of `images`
+    bytes: usize,
+}
+
+impl Collection {
+    pub fn get(&self, url: &Url) -> Option<&State> {
+        self.states.get(url)
+    }
+
+    /// Starts tracking `visible`, returning the URLs which need loading.
+    ///
+    /// Previews which are no longer visible are retained, and evicted least
+    /// recently visible first once `memory_limit` bytes are exceeded.
+    pub fn track(
+        &mut self,
+        visible: &HashSet<Url>,
+        memory_limit: usize,
+    ) -> Vec<Url> {
+        let missing = visible
+            .iter()
+            .filter(|url| !self.states.contains_key(*url))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for url in &missing {
+            self.states.insert(url.clone(), State::Loading);
+        }
+
+        self.retained.retain(|url| !visible.contains(url));
+
+        let hidden = self
+            .states
+            .keys()
+            .filter(|url| {
+                !visible.contains(*url) && !self.retained.contains(*url)
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        for url in hidden {
+            if matches!(self.states.get(&url), Some(State::Loaded(_))) {
+                self.retained.insert(url);
+            } else {
+                self.remove(&url);
+            }
+        }
+
+        self.evict(memory_limit);
+
+        missing
+    }
+
+    pub fn loaded(&mut self, url: Url, preview: Preview, memory_limit: usize) {
+        if self.states.contains_key(&url) {
+            self.remove(&url);
+            self.count(&preview, true);
+            self.states.insert(url, State::Loaded(preview));
+
+            self.evict(memory_limit);
+        }
+    }
+
+    pub fn failed(&mut self, url: Url, error: LoadError) {
+        if self.states.contains_key(&url) {
+            self.remove(&url);
+            self.states.insert(url, State::Error(error));
+        }
+    }
+
+    pub fn usage(&self) -> Usage {
+        Usage {
+            visible: self.images - self.retained.len(),
+            retained: self.retained.len(),
+            bytes: self.bytes,
+        }
+    }
+
+    fn evict(&mut self, memory_limit: usize) {
+        while self.bytes > memory_limit {
+            let Some(url) = self.retained.shift_remove_index(0) else {
+                break;
+            };
+
+            self.remove(&url);
+        }
+    }
+
+    fn remove(&mut self, url: &Url) {
+        if let Some(State::Loaded(preview)) = self.states.remove(url) {
+            self.count(&preview, false);
+        }
+    }
+
+    /// Adds the image of `preview` to the running totals, or takes it off
+    fn count(&mut self, preview: &Preview, add: bool) {
+        let Some(image) = preview.image() else {
+            return;
+        };
+
+        let size = image
+            .thumbnail
+            .as_ref()
+            .map_or(0, |thumbnail| thumbnail.size);
+
+        if add {
+            self.images += 1;
+            self.bytes += size;
+        } else {
+            self.images -= 1;
+            self.bytes -= size;
+        }
+    }
+}
+
+/// Memory held by the thumbnails of loaded previews
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    pub visible: usize,
+    pub retained: usize,
+    pub bytes: usize,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Preview {
     Card(Card),
     Image(Image),
+    /// Title of the url, as printed by a configured resolver command
+    Title(String),
 }
 
 impl Preview {
-    pub fn image(&self) -> &Image {
+    pub fn image(&self) -> Option<&Image> {
         match self {
-            Self::Card(card) => &card.image,
-            Self::Image(image) => image,
+            Self::Card(card) => Some(&card.image),
+            Self::Image(image) => Some(image),
+            Self::Title(_) => None,
+        }
+    }
+
+    fn image_mut(&mut self) -> Option<&mut Image> {
+        match self {
+            Self::Card(card) => Some(&mut card.image),
+            Self::Image(image) => Some(image),
+            Self::Title(_) => None,
         }
     }
 }
@@ -104,6 +248,14 @@ pub async fn load(
         return Err(LoadError::Disabled);
     }
 
+    // Urls with a resolver are never requested directly, and resolved
+    // titles aren't cached, so changes to the command apply right away
+    if let Some(resolver) = config.resolver(url.as_str()) {
+        return resolve_title(&url, resolver, &config)
+            .await
+            .map(Preview::Title);
+    }
+
     let result = if let Some(state) = cache::load(&url, &config).await {
         match state {
             cache::State::Ok(preview) => Ok(preview),
@@ -124,9 +276,9 @@ pub async fn load(
         }
     };
 
-    if let Ok(ref preview) = result {
-        let image = preview.image();
-
+    let mut preview = if let Some(image) =
+        result.as_ref().ok().and_then(Preview::image)
+    {
         if let Ok((image_width, image_height)) = image_dimensions(&image.path) {
             // As per iced, it is a webgpu requirement that:
             //   BufferCopyView.layout.bytes_per_row % wgpu::COPY_BYTES_PER_ROW_ALIGNMENT == 0
@@ -154,7 +306,13 @@ pub async fn load(
         }
     } else {
         result
+    }?;
+
+    if let Some(image) = preview.image_mut() {
+        image.thumbnail = Thumbnail::decode(image.path.clone()).await;
     }
+
+    Ok(preview)
 }
 
 async fn load_uncached(
@@ -171,44 +329,9 @@ async fn load_uncached(
             let mut title = None;
             let mut description = None;
 
-            for captures in OPENGRAPH_REGEX
-                .captures_iter(&String::from_utf8_lossy(&bytes))
-                .filter_map(Result::ok)
+            for (property, content) in
+                opengraph_properties(&String::from_utf8_lossy(&bytes))
             {
-                let Some((((key_1, value_1), key_2), value_2)) = captures
-                    .get(1)
-                    .map(|r| r.as_str())
-                    .zip(captures.get(2).map(|r| r.as_str()))
-                    .zip(captures.get(3).map(|r| r.as_str()))
-                    .zip(captures.get(4).map(|r| r.as_str()))
-                else {
-                    continue;
-                };
-
-                let value_1 = decode_html_string(
-                    value_1
-                        .trim_start_matches(['\'', '"'])
-                        .trim_end_matches(['\'', '"']),
-                );
-                let value_2 = decode_html_string(
-                    value_2
-                        .trim_start_matches(['\'', '"'])
-                        .trim_end_matches(['\'', '"']),
-                );
-
-                let (property, content) = if (key_1 == "property"
-                    || key_1 == "name")
-                    && key_2 == "content"
-                {
-                    (value_1, value_2)
-                } else if key_1 == "content"
-                    && (key_2 == "property" || key_2 == "name")
-                {
-                    (value_2, value_1)
-                } else {
-                    continue;
-                };
-
                 match property.as_str() {
                     "og:url" => canonical_url = Some(content.parse()?),
                     "og:image" => image_url = Some(content.parse()?),
@@ -237,6 +360,115 @@ async fn load_uncached(
     }
 }
 
+/// Fetches the title of the page at `url`, preferring its open graph title
+/// over its `<title>` element.
+pub async fn title(
+    url: Url,
+    config: config::Preview,
+) -> Result<String, LoadError> {
+    debug!("Loading title for {url}");
+
+    if let Some(resolver) = config.resolver(url.as_str()) {
+        return resolve_title(&url, resolver, &config).await;
+    }
+
+    let Fetched::Other(bytes) = fetch(url, &config).await? else {
+        return Err(LoadError::NotHtml);
+    };
+
+    let html = String::from_utf8_lossy(&bytes);
+
+    opengraph_properties(&html)
+        .find_map(|(property, content)| {
+            (property == "og:title").then_some(content)
+        })
+        .or_else(|| {
+            TITLE_REGEX
+                .captures(&html)
+                .ok()
+                .flatten()
+                .and_then(|captures| captures.get(1))
+                .map(|title| decode_html_string(title.as_str().trim()))
+        })
+        .filter(|title| !title.is_empty())
+        .ok_or(LoadError::MissingProperty("title"))
+}
+
+/// Runs the command of `resolver` for `url`, taking the first line it
+/// prints as the title.
+async fn resolve_title(
+    url: &Url,
+    resolver: &config::preview::Resolver,
+    config: &config::Preview,
+) -> Result<String, LoadError> {
+    debug!("Resolving title for {url}");
+
+    let (program, args) = resolver
+        .command(url.as_str())
+        .ok_or(LoadError::EmptyCommand)?;
+
+    let output = time::timeout(
+        Duration::from_millis(config.request.timeout_ms),
+        tokio::process::Command::new(&program)
+            .args(&args)
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .map_err(|_| LoadError::ResolverTimedOut)??;
+
+    if !output.status.success() {
+        return Err(LoadError::ResolverFailed(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(ToString::to_string)
+        .ok_or(LoadError::MissingProperty("title"))
+}
+
+/// `(property, content)` pairs of the open graph `<meta>` tags in `html`.
+fn opengraph_properties(
+    html: &str,
+) -> impl Iterator<Item = (String, String)> + '_ {
+    OPENGRAPH_REGEX
+        .captures_iter(html)
+        .filter_map(Result::ok)
+        .filter_map(|captures| {
+            let (((key_1, value_1), key_2), value_2) = captures
+                .get(1)
+                .map(|r| r.as_str())
+                .zip(captures.get(2).map(|r| r.as_str()))
+                .zip(captures.get(3).map(|r| r.as_str()))
+                .zip(captures.get(4).map(|r| r.as_str()))?;
+
+            let value_1 = decode_html_string(
+                value_1
+                    .trim_start_matches(['\'', '"'])
+                    .trim_end_matches(['\'', '"']),
+            );
+            let value_2 = decode_html_string(
+                value_2
+                    .trim_start_matches(['\'', '"'])
+                    .trim_end_matches(['\'', '"']),
+            );
+
+            if (key_1 == "property" || key_1 == "name") && key_2 == "content" {
+                Some((value_1, value_2))
+            } else if key_1 == "content"
+                && (key_2 == "property" || key_2 == "name")
+            {
+                Some((value_2, value_1))
+            } else {
+                None
+            }
+        })
+}
+
 enum Fetched {
     Image(Image),
     Other(Vec<u8>),
@@ -364,6 +596,12 @@ pub enum LoadError {
     ParseUrl(#[from] url::ParseError),
     #[error("io error: {0}")]
     Io(#[from] io::Error),
+    #[error("resolver command is empty")]
+    EmptyCommand,
+    #[error("resolver command timed out")]
+    ResolverTimedOut,
+    #[error("resolver command failed: {0}")]
+    ResolverFailed(String),
     #[error("unable to verify image dimensions fit in maximum buffer size")]
     ImageDimensionsUnknown,
     #[error(
@@ -374,3 +612,94 @@ pub enum LoadError {
         max_buffer_size: u64,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use iced_core::image::Handle;
+
+    use super::*;
+
+    fn url(n: usize) -> Url {
+        Url::parse(&format!("https://example.com/{n}.png")).unwrap()
+    }
+
+    fn preview(n: usize, size: usize) -> Preview {
+        let mut image = Image::new(
+            image::Format::Png,
+            url(n),
+            image::Digest::new(&[n as u8; 32]),
+        );
+
+        image.thumbnail = Some(Thumbnail {
+            handle: Handle::from_path(&image.path),
+            size,
+        });
+
+        Preview::Image(image)
+    }
+
+    fn visible(urls: &[usize]) -> HashSet<Url> {
+        urls.iter().copied().map(url).collect()
+    }
+
+    #[test]
+    fn usage() {
+        let mut collection = Collection::default();
+
+        assert_eq!(collection.track(&visible(&[1, 2, 3]), 100).len(), 3);
+
+        collection.loaded(url(1), preview(1, 30), 100);
+        collection.loaded(url(2), preview(2, 40), 100);
+        collection.failed(url(3), LoadError::NotImage);
+
+        let usage = collection.usage();
+        assert_eq!((usage.visible, usage.retained, usage.bytes), (2, 0, 70));
+
+        // Loaded previews are retained once hidden, the others dropped
+        assert!(collection.track(&visible(&[1]), 100).is_empty());
+        assert!(collection.get(&url(3)).is_none());
+
+        let usage = collection.usage();
+        assert_eq!((usage.visible, usage.retained, usage.bytes), (1, 1, 70));
+
+        // and tracked again once visible
+        assert!(collection.track(&visible(&[1, 2]), 100).is_empty());
+
+        let usage = collection.usage();
+        assert_eq!((usage.visible, usage.retained, usage.bytes), (2, 0, 70));
+    }
+
+    #[test]
+    fn evicts_least_recently_visible() {
+        let mut collection = Collection::default();
+
+        collection.track(&visible(&[1, 2, 3]), 100);
+
+        for n in 1..=3 {
+            collection.loaded(url(n), preview(n, 30), 100);
+        }
+
+        collection.track(&visible(&[2, 3]), 100);
+        collection.track(&visible(&[3]), 100);
+
+        // Over the limit with visible previews alone, all retained ones go
+        collection.track(&visible(&[3, 4]), 50);
+        assert!(collection.get(&url(1)).is_none());
+        assert!(collection.get(&url(2)).is_none());
+
+        collection.loaded(url(4), preview(4, 30), 100);
+        collection.track(&visible(&[4]), 100);
+        collection.track(&visible(&[5]), 100);
+
+        let usage = collection.usage();
+        assert_eq!((usage.visible, usage.retained, usage.bytes), (0, 2, 60));
+
+        // Least recently visible first
+        collection.loaded(url(5), preview(5, 30), 70);
+        assert!(collection.get(&url(3)).is_none());
+        assert!(collection.get(&url(4)).is_some());
+
+        let usage = collection.usage();
+        assert_eq!((usage.visible, usage.retained, usage.bytes), (1, 1, 60));
+    }
+}
@@ -37,7 +37,7 @@ pub async fn load(url: &Url, config: &config::Preview) -> Option<State> {
                 super::fetch(image.url.clone(), config).await.ok()?;
             }
         }
-        State::Error => {}
+        State::Ok(Preview::Title(_)) | State::Error => {}
     }
 
     Some(state)
@@ -1,6 +1,7 @@
 use std::path::PathBuf;
 
 use derive_more::derive::AsRef;
+use iced_core::image::Handle;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
@@ -9,6 +10,11 @@ use super::cache;
 pub type Format = image::ImageFormat;
 pub type Error = image::ImageError;
 
+// Bounds of the thumbnail shown inline, twice the largest size previews are
+// displayed at so they stay sharp on high DPI displays
+const THUMBNAIL_WIDTH: u32 = 1100;
+const THUMBNAIL_HEIGHT: u32 = 700;
+
 /// SHA256 digest of image
 #[derive(Debug, Clone, Serialize, Deserialize, AsRef)]
 pub struct Digest(String);
@@ -26,6 +32,8 @@ pub struct Image {
     pub url: Url,
     pub digest: Digest,
     pub path: PathBuf,
+    #[serde(skip)]
+    pub thumbnail: Option<Thumbnail>,
 }
 
 impl Image {
@@ -37,8 +45,58 @@ impl Image {
             url,
             digest,
             path,
+            thumbnail: None,
         }
     }
+
+    /// Handle to draw inline, the decoded thumbnail when there is one
+    pub fn handle(&self) -> Handle {
+        self.thumbnail.as_ref().map_or_else(
+            || Handle::from_path(&self.path),
+            |thumbnail| thumbnail.handle.clone(),
+        )
+    }
+}
+
+/// Downscaled and decoded image kept in memory while its preview is cached
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub handle: Handle,
+    /// Size of the decoded RGBA pixels in bytes, which is also the size of
+    /// the texture uploaded when it is drawn
+    pub size: usize,
+}
+
+impl Thumbnail {
+    pub async fn decode(path: PathBuf) -> Option<Self> {
+        tokio::task::spawn_blocking(move || {
+            let image = image::open(&path)
+                .inspect_err(|error| {
+                    log::debug!("Failed to decode {}: {error}", path.display());
+                })
+                .ok()?;
+
+            let image = if image.width() > THUMBNAIL_WIDTH
+                || image.height() > THUMBNAIL_HEIGHT
+            {
+                image.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_HEIGHT)
+            } else {
+                image
+            };
+
+            let rgba = image.into_rgba8();
+            let (width, height) = rgba.dimensions();
+            let pixels = rgba.into_raw();
+
+            Some(Self {
+                size: pixels.len(),
+                handle: Handle::from_rgba(width, height, pixels),
+            })
+        })
+        .await
+        .ok()
+        .flatten()
+    }
 }
 
 pub fn format(bytes: &[u8]) -> Option<Format> {
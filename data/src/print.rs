@@ -0,0 +1,216 @@
+//! A date range of a buffer laid out for printing, as an HTML document
+//! which browsers can print or save as a PDF.
+use std::path::PathBuf;
+
+use chrono::{Local, NaiveDate};
+
+use crate::share::{self, Kind, Line};
+use crate::{Message, config, environment, history, isupport};
+
+/// Days printed, inclusive, in local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Range {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.from <= date && date <= self.to
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("no messages between {0} and {1}")]
+    Empty(NaiveDate, NaiveDate),
+    #[error(transparent)]
+    History(#[from] history::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Writes the messages of `kind` within `range` to a printable document,
+/// returning its path.
+pub async fn export(
+    kind: history::Kind,
+    range: Range,
+    config: config::Buffer,
+) -> Result<PathBuf, Error> {
+    let history::Loaded { messages, .. } =
+        history::load(kind.clone(), None).await?;
+
+    let days = days(&messages, range, &config);
+
+    if days.is_empty() {
+        return Err(Error::Empty(range.from, range.to));
+    }
+
+    let title = format!("{kind} — {} to {}", range.from, range.to);
+
+    let dir = environment::cache_dir().join("print");
+    tokio::fs::create_dir_all(&dir).await?;
+
+    let path = dir.join(format!(
+        "{}-{}-{}.html",
+        file_name(&kind.to_string()),
+        range.from,
+        range.to
+    ));
+
+    tokio::fs::write(&path, document(&title, &days)).await?;
+
+    Ok(path)
+}
+
+/// Lines of `messages` within `range`, grouped by the day they were sent.
+fn days(
+    messages: &[Message],
+    range: Range,
+    config: &config::Buffer,
+) -> Vec<(NaiveDate, Vec<Line>)> {
+    let options = share::Options {
+        anonymize: false,
+        timestamps: true,
+    };
+
+    messages
+        .iter()
+        .filter(|message| {
+            range.contains(
+                message.server_time.with_timezone(&Local).date_naive(),
+            )
+        })
+        .fold(
+            Vec::<(NaiveDate, Vec<&Message>)>::new(),
+            |mut days, message| {
+                let date =
+                    message.server_time.with_timezone(&Local).date_naive();
+
+                match days.last_mut() {
+                    Some((day, messages)) if *day == date => {
+                        messages.push(message);
+                    }
+                    _ => days.push((date, vec![message])),
+                }
+
+                days
+            },
+        )
+        .into_iter()
+        .map(|(date, messages)| {
+            // Not anonymized, so nicknames aren't matched
+            let casemapping = isupport::CaseMap::default();
+
+            (date, share::lines(&messages, options, casemapping, config))
+        })
+        .filter(|(_, lines)| !lines.is_empty())
+        .collect()
+}
+
+/// HTML document of `days`, styled black on white for paper.
+fn document(title: &str, days: &[(NaiveDate, Vec<Line>)]) -> String {
+    let body = days
+        .iter()
+        .map(|(date, lines)| {
+            let lines = lines
+                .iter()
+                .map(|line| {
+                    let timestamp = line
+                        .timestamp
+                        .as_ref()
+                        .map(|timestamp| {
+                            format!(
+                                "<span class=\"timestamp\">{}</span> ",
+                                share::escape(timestamp)
+                            )
+                        })
+                        .unwrap_or_default();
+
+                    let content = match (line.kind, &line.nick) {
+                        (Kind::Message, Some(nick)) => format!(
+                            "<span class=\"nick\">&lt;{}&gt;</span> {}",
+                            share::escape(nick),
+                            share::escape(&line.text)
+                        ),
+                        (Kind::Action, _) => format!(
+                            "<span class=\"action\">* {}</span>",
+                            share::escape(&line.text)
+                        ),
+                        _ => format!(
+                            "<span class=\"server\">{}</span>",
+                            share::escape(&line.text)
+                        ),
+                    };
+
+                    format!("<div class=\"line\">{timestamp}{content}</div>")
+                })
+                .collect::<String>();
+
+            format!(
+                "<section><h2>{}</h2>{lines}</section>",
+                date.format("%A, %B %-d, %Y")
+            )
+        })
+        .collect::<String>();
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{title}</title>\n<style>{STYLE}</style>\n</head>\n\
+         <body onload=\"window.print()\">\n<h1>{title}</h1>\n{body}\n\
+         </body>\n</html>\n",
+        title = share::escape(title),
+    )
+}
+
+const STYLE: &str = "\
+body { margin: 2em; color: #000; background: #fff; \
+font: 10pt/1.4 monospace; } \
+h1 { font-size: 14pt; margin: 0 0 1em; } \
+h2 { font-size: 11pt; margin: 1.5em 0 0.5em; \
+border-bottom: 1px solid #000; break-after: avoid; } \
+.line { white-space: pre-wrap; overflow-wrap: anywhere; \
+break-inside: avoid; } \
+.timestamp, .server { color: #555; } \
+.nick { font-weight: bold; } \
+.action { font-style: italic; } \
+@page { margin: 2cm; } \
+@media print { body { margin: 0; } }";
+
+/// `name` with characters unsafe in file names replaced.
+fn file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prints_days() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let days = vec![(
+            date,
+            vec![Line {
+                timestamp: Some("12:00".to_string()),
+                nick: Some("dan".to_string()),
+                kind: Kind::Message,
+                text: "a <b> c".to_string(),
+            }],
+        )];
+
+        let document = document("#halloy", &days);
+
+        assert!(document.contains("<h2>Friday, March 1, 2024</h2>"));
+        assert!(document.contains("&lt;dan&gt;</span> a &lt;b&gt; c"));
+        assert_eq!(file_name("libera/#halloy"), "libera__halloy");
+    }
+}
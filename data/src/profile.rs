@@ -0,0 +1,122 @@
+//! Named profiles, selected with `--profile <name>`, which keep their own
+//! window geometry, open buffers and pane layout.
+
+/// The profile Halloy was launched with. The default profile has no name
+/// and uses the unsuffixed state files.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile(Option<String>);
+
+impl Profile {
+    pub fn new(name: impl Into<String>) -> Result<Self, Error> {
+        let name = name.into();
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(Error::InvalidName(name));
+        }
+
+        Ok(Self(Some(name)))
+    }
+
+    /// Reads the profile from `--profile <name>` or `--profile=<name>` in
+    /// the command line arguments.
+    pub fn from_args(
+        args: impl IntoIterator<Item = String>,
+    ) -> Result<Self, Error> {
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let name = if arg == "--profile" {
+                args.next().ok_or(Error::MissingName)?
+            } else if let Some(name) = arg.strip_prefix("--profile=") {
+                name.to_string()
+            } else {
+                continue;
+            };
+
+            return Self::new(name);
+        }
+
+        Ok(Self::default())
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.0.as_deref()
+    }
+
+    /// Name of the state file `file_name` for this profile, e.g.
+    /// `window-work.json` for `window.json` in the `work` profile.
+    pub fn file_name(&self, file_name: &str) -> String {
+        match (self.name(), file_name.split_once('.')) {
+            (Some(name), Some((stem, extension))) => {
+                format!("{stem}-{name}.{extension}")
+            }
+            (Some(name), None) => format!("{file_name}-{name}"),
+            (None, _) => file_name.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum Error {
+    #[error("--profile requires a name")]
+    MissingName,
+    #[error(
+        "invalid profile name {0:?}, only letters, digits, '-' and '_' are allowed"
+    )]
+    InvalidName(String),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn from_args() {
+        let work = Profile::new("work").unwrap();
+
+        assert_eq!(
+            Profile::from_args(args(&["halloy"])).unwrap(),
+            Profile::default()
+        );
+        assert_eq!(
+            Profile::from_args(args(&["halloy", "--profile", "work"])).unwrap(),
+            work
+        );
+        assert_eq!(
+            Profile::from_args(args(&["halloy", "--profile=work"])).unwrap(),
+            work
+        );
+        assert_eq!(
+            Profile::from_args(args(&["halloy", "--profile-startup"])).unwrap(),
+            Profile::default()
+        );
+        assert!(matches!(
+            Profile::from_args(args(&["halloy", "--profile"])),
+            Err(Error::MissingName)
+        ));
+        assert!(matches!(
+            Profile::from_args(args(&["halloy", "--profile", "../work"])),
+            Err(Error::InvalidName(_))
+        ));
+    }
+
+    #[test]
+    fn file_name() {
+        let work = Profile::new("work").unwrap();
+
+        assert_eq!(work.file_name("window.json"), "window-work.json");
+        assert_eq!(
+            work.file_name("dashboard.json.gz"),
+            "dashboard-work.json.gz"
+        );
+        assert_eq!(Profile::default().file_name("window.json"), "window.json");
+    }
+}
@@ -0,0 +1,611 @@
+//! Lua scripts, loaded from the `scripts` folder of the data directory,
+//! which can register commands and act on messages as they're received and
+//! sent.
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use mlua::{Function, HookTriggers, IntoLuaMulti, Lua, Table, Value, VmState};
+
+use crate::{Message, Server, buffer, environment, message};
+
+pub const DIR_NAME: &str = "scripts";
+
+/// Instructions a script can run when loaded, and each time one of its
+/// hooks or commands is called, before it's stopped and disabled. Keeps a
+/// runaway loop from freezing Halloy.
+const INSTRUCTION_LIMIT: u32 = 10_000_000;
+/// Instructions between checks of the limit
+const INSTRUCTION_CHECK_INTERVAL: u32 = 10_000;
+
+/// Code of a script, named after its file
+#[derive(Debug, Clone)]
+pub struct Source {
+    pub name: String,
+    pub code: String,
+}
+
+/// Something a script asked for while one of its hooks or commands ran.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Input handled as if typed into `buffer`, or into the buffer of
+    /// `target` on the same server
+    Send {
+        buffer: buffer::Upstream,
+        target: Option<String>,
+        text: String,
+    },
+    /// Text shown in `buffer`, or in the buffer of `target` on the same
+    /// server, without being sent
+    Print {
+        buffer: buffer::Upstream,
+        target: Option<String>,
+        text: String,
+    },
+    Notify {
+        server: Server,
+        title: String,
+        body: String,
+    },
+}
+
+/// The `scripts` folder, created if it doesn't exist yet.
+pub fn dir() -> PathBuf {
+    let dir = environment::data_dir().join(DIR_NAME);
+
+    if !dir.exists() {
+        std::fs::create_dir_all(dir.as_path())
+            .expect("expected permissions to create scripts folder");
+    }
+
+    dir
+}
+
+/// Reads the `.lua` files of the scripts folder, in order of their names.
+pub async fn load() -> Vec<Source> {
+    let dir = dir();
+
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return vec![];
+    };
+
+    let mut sources = vec![];
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+
+        if path.extension().is_none_or(|extension| extension != "lua") {
+            continue;
+        }
+
+        let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+
+        match tokio::fs::read_to_string(&path).await {
+            Ok(code) => sources.push(Source {
+                name: name.to_string(),
+                code,
+            }),
+            Err(error) => {
+                log::warn!("failed to read script {}: {error}", path.display());
+            }
+        }
+    }
+
+    sources.sort_by(|a, b| a.name.cmp(&b.name));
+
+    sources
+}
+
+#[derive(Default)]
+pub struct Manager {
+    scripts: Vec<Script>,
+}
+
+impl Manager {
+    /// Runs `sources`, each in a Lua state of its own. Scripts which fail
+    /// to run are skipped.
+    pub fn new(sources: Vec<Source>) -> Self {
+        let scripts = sources
+            .into_iter()
+            .filter_map(|source| match Script::run(&source) {
+                Ok(script) => {
+                    log::info!("loaded script {}", source.name);
+
+                    Some(script)
+                }
+                Err(error) => {
+                    log::warn!(
+                        "failed to load script {}: {error}",
+                        source.name
+                    );
+
+                    None
+                }
+            })
+            .collect();
+
+        Self { scripts }
+    }
+
+    /// Runs the `on_message` hooks on a received `message`, which is
+    /// `None` once a hook swallowed it.
+    pub fn on_message(
+        &self,
+        server: &Server,
+        mut message: Message,
+    ) -> Option<Message> {
+        // Our own messages echoed back by the server were seen by the
+        // `on_send` hooks already
+        if message.is_echo {
+            return Some(message);
+        }
+
+        let nick = match message.target.source() {
+            message::Source::User(user)
+            | message::Source::Action(Some(user)) => {
+                user.nickname().to_string()
+            }
+            _ => return Some(message),
+        };
+
+        let buffer = match &message.target {
+            message::Target::Channel { channel, .. } => {
+                buffer::Upstream::Channel(server.clone(), channel.clone())
+            }
+            message::Target::Query { query, .. } => {
+                buffer::Upstream::Query(server.clone(), query.clone())
+            }
+            _ => buffer::Upstream::Server(server.clone()),
+        };
+
+        for script in self.enabled() {
+            // Cloned so the hooks can be registered to while they run
+            let functions = script.hooks().on_message.clone();
+
+            for function in functions {
+                let outcome = script.call(&function, &buffer, |lua| {
+                    let table = context(lua, &buffer)?;
+                    table.set("nick", nick.as_str())?;
+                    table.set("text", message.text())?;
+
+                    Ok(table)
+                });
+
+                match outcome {
+                    Outcome::Keep => {}
+                    Outcome::Replace(text) => {
+                        message.content = message::parse_fragments(text);
+                    }
+                    Outcome::Swallow => return None,
+                }
+            }
+        }
+
+        Some(message)
+    }
+
+    /// Runs the `on_send` hooks on `text` about to be sent from `buffer`,
+    /// returning the text to send, if any.
+    pub fn on_send(
+        &self,
+        buffer: &buffer::Upstream,
+        text: &str,
+    ) -> Option<String> {
+        let mut text = text.to_string();
+
+        for script in self.enabled() {
+            let functions = script.hooks().on_send.clone();
+
+            for function in functions {
+                let outcome = script.call(&function, buffer, |lua| {
+                    let table = context(lua, buffer)?;
+                    table.set("text", text.as_str())?;
+
+                    Ok(table)
+                });
+
+                match outcome {
+                    Outcome::Keep => {}
+                    Outcome::Replace(replaced) => text = replaced,
+                    Outcome::Swallow => return None,
+                }
+            }
+        }
+
+        Some(text)
+    }
+
+    /// Runs the command `input` invokes from `buffer` if a script
+    /// registered it, returning whether one did.
+    pub fn run_command(&self, buffer: &buffer::Upstream, input: &str) -> bool {
+        let Some((name, args)) = input
+            .strip_prefix('/')
+            .map(|command| command.split_once(' ').unwrap_or((command, "")))
+        else {
+            return false;
+        };
+        let name = name.to_lowercase();
+
+        for script in self.enabled() {
+            let Some(function) = script.hooks().commands.get(&name).cloned()
+            else {
+                continue;
+            };
+
+            let args = args.trim().to_string();
+
+            script.call_with(&function, buffer, |lua| {
+                Ok((args, context(lua, buffer)?))
+            });
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Scripts which haven't been disabled for running too long.
+    fn enabled(&self) -> impl Iterator<Item = &Script> {
+        self.scripts
+            .iter()
+            .filter(|script| !script.hooks().is_disabled)
+    }
+
+    /// Takes the actions scripts asked for since last called.
+    pub fn take_actions(&self) -> Vec<Action> {
+        self.scripts
+            .iter()
+            .flat_map(|script| std::mem::take(&mut script.hooks().actions))
+            .collect()
+    }
+}
+
+/// What a hook returned: `false` swallows, a string replaces the text, and
+/// anything else keeps it as is.
+enum Outcome {
+    Keep,
+    Replace(String),
+    Swallow,
+}
+
+impl From<Value> for Outcome {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Boolean(false) => Outcome::Swallow,
+            Value::String(text) => Outcome::Replace(text.to_string_lossy()),
+            _ => Outcome::Keep,
+        }
+    }
+}
+
+/// State of a script, kept in its Lua state.
+#[derive(Default)]
+struct Hooks {
+    on_message: Vec<Function>,
+    on_send: Vec<Function>,
+    commands: HashMap<String, Function>,
+    /// Buffer of the hook or command being run, which `halloy.send`,
+    /// `halloy.print` and `halloy.notify` act on
+    buffer: Option<buffer::Upstream>,
+    actions: Vec<Action>,
+    /// Instructions run since the script was last called into, counted in
+    /// steps of [`INSTRUCTION_CHECK_INTERVAL`]
+    instructions: u32,
+    /// Set once the script ran past [`INSTRUCTION_LIMIT`]
+    is_disabled: bool,
+}
+
+struct Script {
+    name: String,
+    lua: Lua,
+}
+
+impl Script {
+    fn run(source: &Source) -> mlua::Result<Self> {
+        let lua = Lua::new();
+
+        lua.set_app_data(Hooks::default());
+        lua.set_hook(
+            HookTriggers::new()
+                .every_nth_instruction(INSTRUCTION_CHECK_INTERVAL),
+            |lua, _| {
+                let mut hooks = hooks(lua);
+
+                hooks.instructions += INSTRUCTION_CHECK_INTERVAL;
+
+                if hooks.instructions >= INSTRUCTION_LIMIT {
+                    hooks.is_disabled = true;
+
+                    return Err(mlua::Error::runtime(format!(
+                        "stopped after running {INSTRUCTION_LIMIT} \
+                         instructions, the script is disabled"
+                    )));
+                }
+
+                Ok(VmState::Continue)
+            },
+        );
+        lua.globals().set("halloy", api(&lua)?)?;
+        lua.load(&source.code).set_name(&source.name).exec()?;
+
+        Ok(Self {
+            name: source.name.clone(),
+            lua,
+        })
+    }
+
+    fn hooks(&self) -> mlua::AppDataRefMut<'_, Hooks> {
+        hooks(&self.lua)
+    }
+
+    fn call(
+        &self,
+        function: &Function,
+        buffer: &buffer::Upstream,
+        args: impl FnOnce(&Lua) -> mlua::Result<Table>,
+    ) -> Outcome {
+        self.call_with(function, buffer, args)
+            .map_or(Outcome::Keep, Outcome::from)
+    }
+
+    fn call_with<A: IntoLuaMulti>(
+        &self,
+        function: &Function,
+        buffer: &buffer::Upstream,
+        args: impl FnOnce(&Lua) -> mlua::Result<A>,
+    ) -> Option<Value> {
+        {
+            let mut hooks = self.hooks();
+            hooks.buffer = Some(buffer.clone());
+            hooks.instructions = 0;
+        }
+
+        let result =
+            args(&self.lua).and_then(|args| function.call::<Value>(args));
+
+        self.hooks().buffer = None;
+
+        result
+            .inspect_err(|error| {
+                log::warn!("script {} failed: {error}", self.name);
+            })
+            .ok()
+    }
+}
+
+fn hooks(lua: &Lua) -> mlua::AppDataRefMut<'_, Hooks> {
+    lua.app_data_mut::<Hooks>()
+        .expect("hooks are set when the script is created")
+}
+
+/// Table passed to hooks and commands, describing the buffer they run for.
+fn context(lua: &Lua, buffer: &buffer::Upstream) -> mlua::Result<Table> {
+    let table = lua.create_table()?;
+
+    table.set("server", buffer.server().to_string())?;
+    table.set("target", buffer.target().map(|target| target.to_string()))?;
+
+    Ok(table)
+}
+
+/// The `halloy` table scripts are given.
+fn api(lua: &Lua) -> mlua::Result<Table> {
+    let halloy = lua.create_table()?;
+
+    halloy.set(
+        "on_message",
+        lua.create_function(|lua, function: Function| {
+            hooks(lua).on_message.push(function);
+
+            Ok(())
+        })?,
+    )?;
+
+    halloy.set(
+        "on_send",
+        lua.create_function(|lua, function: Function| {
+            hooks(lua).on_send.push(function);
+
+            Ok(())
+        })?,
+    )?;
+
+    halloy.set(
+        "command",
+        lua.create_function(|lua, (name, function): (String, Function)| {
+            hooks(lua)
+                .commands
+                .insert(name.trim_start_matches('/').to_lowercase(), function);
+
+            Ok(())
+        })?,
+    )?;
+
+    halloy.set(
+        "send",
+        lua.create_function(
+            |lua, (text, target): (String, Option<String>)| {
+                let mut hooks = hooks(lua);
+                let buffer = current_buffer(&hooks, "send")?;
+
+                hooks.actions.push(Action::Send {
+                    buffer,
+                    target,
+                    text,
+                });
+
+                Ok(())
+            },
+        )?,
+    )?;
+
+    halloy.set(
+        "print",
+        lua.create_function(
+            |lua, (text, target): (String, Option<String>)| {
+                let mut hooks = hooks(lua);
+                let buffer = current_buffer(&hooks, "print")?;
+
+                hooks.actions.push(Action::Print {
+                    buffer,
+                    target,
+                    text,
+                });
+
+                Ok(())
+            },
+        )?,
+    )?;
+
+    halloy.set(
+        "notify",
+        lua.create_function(|lua, (title, body): (String, Option<String>)| {
+            let mut hooks = hooks(lua);
+            let buffer = current_buffer(&hooks, "notify")?;
+
+            hooks.actions.push(Action::Notify {
+                server: buffer.server().clone(),
+                title,
+                body: body.unwrap_or_default(),
+            });
+
+            Ok(())
+        })?,
+    )?;
+
+    Ok(halloy)
+}
+
+fn current_buffer(
+    hooks: &Hooks,
+    function: &str,
+) -> mlua::Result<buffer::Upstream> {
+    hooks.buffer.clone().ok_or_else(|| {
+        mlua::Error::runtime(format!(
+            "halloy.{function} can only be used from a hook or command"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use irc::proto;
+
+    use super::*;
+    use crate::isupport::CaseMap;
+    use crate::target::Channel;
+    use crate::user::{Nick, User};
+
+    fn manager(code: &str) -> Manager {
+        Manager::new(vec![Source {
+            name: "test".to_string(),
+            code: code.to_string(),
+        }])
+    }
+
+    fn server() -> Server {
+        Server::from(Arc::<str>::from("libera"))
+    }
+
+    fn channel() -> Channel {
+        Channel::from_str(
+            "#halloy",
+            proto::DEFAULT_CHANNEL_PREFIXES,
+            CaseMap::default(),
+        )
+    }
+
+    fn received(text: &str) -> Message {
+        Message::sent(
+            message::Target::Channel {
+                channel: channel(),
+                source: message::Source::User(User::from(Nick::from_str(
+                    "dan",
+                    CaseMap::default(),
+                ))),
+            },
+            message::plain(text.to_string()),
+        )
+    }
+
+    #[test]
+    fn on_message() {
+        let manager = manager(
+            r#"
+            halloy.on_message(function(message)
+                if message.text == "secret" then
+                    return false
+                elseif message.text == "hi" then
+                    return "hello from " .. message.nick .. " in " .. message.target
+                end
+            end)
+            "#,
+        );
+        let on_message = |text| {
+            manager
+                .on_message(&server(), received(text))
+                .map(|message| message.text())
+        };
+
+        assert_eq!(on_message("bye").as_deref(), Some("bye"));
+        assert_eq!(
+            on_message("hi").as_deref(),
+            Some("hello from dan in #halloy")
+        );
+        assert_eq!(on_message("secret"), None);
+    }
+
+    #[test]
+    fn on_send() {
+        let manager = manager(
+            r#"
+            halloy.on_send(function(message)
+                if message.text:sub(1, 1) == "!" then
+                    return false
+                end
+
+                return message.text:upper()
+            end)
+
+            halloy.on_send(function(message)
+                halloy.print("sent " .. message.text)
+            end)
+            "#,
+        );
+        let buffer = buffer::Upstream::Channel(server(), channel());
+
+        assert_eq!(manager.on_send(&buffer, "hi").as_deref(), Some("HI"));
+        assert_eq!(manager.on_send(&buffer, "!quiet"), None);
+
+        assert!(matches!(
+            manager.take_actions().as_slice(),
+            [Action::Print { text, target: None, .. }] if text == "sent HI"
+        ));
+    }
+
+    #[test]
+    fn runaway_scripts() {
+        assert!(manager("while true do end").scripts.is_empty());
+
+        let manager = manager(
+            r#"
+            halloy.on_send(function(message)
+                if message.text == "loop" then
+                    while true do end
+                end
+
+                return "replaced"
+            end)
+            "#,
+        );
+        let buffer = buffer::Upstream::Channel(server(), channel());
+
+        assert_eq!(manager.on_send(&buffer, "hi").as_deref(), Some("replaced"));
+        assert_eq!(manager.on_send(&buffer, "loop").as_deref(), Some("loop"));
+        // Disabled once stopped
+        assert_eq!(manager.on_send(&buffer, "hi").as_deref(), Some("hi"));
+    }
+}
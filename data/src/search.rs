@@ -0,0 +1,369 @@
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::message::{self, Source};
+use crate::{digest, history, isupport};
+
+pub use self::index::Index;
+
+mod index;
+
+const FILTERS: &[&str] = &["from", "in", "before", "after", "has"];
+
+/// A parsed history search query, e.g.
+/// `from:nick in:#channel before:2024-01-01 has:link "exact phrase"`.
+///
+/// Every term must match for a message to match the query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    /// Word contained in the message text (case-insensitive), which the
+    /// search [`Index`] looks up by the start of a word
+    Word(String),
+    /// Phrase contained in the message text (case-insensitive), looked up
+    /// like [`Term::Word`]
+    Phrase(String),
+    From(String),
+    In(String),
+    Before(NaiveDate),
+    After(NaiveDate),
+    Has(Has),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Has {
+    Link,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Error {
+    #[error("search query is empty")]
+    Empty,
+    #[error("unterminated quote")]
+    UnterminatedQuote,
+    #[error("missing value for \"{0}:\"")]
+    MissingValue(String),
+    #[error("invalid date \"{0}\", expected YYYY-MM-DD")]
+    InvalidDate(String),
+    #[error("unknown value \"{0}\" for \"has:\", expected \"link\"")]
+    UnknownHas(String),
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let terms = tokenize(input)?
+            .into_iter()
+            .map(|token| {
+                let text = &input[token];
+
+                if text.starts_with('"') {
+                    Ok(Term::Phrase(text.trim_matches('"').to_lowercase()))
+                } else if let Some(filter) = parse_filter(text) {
+                    filter
+                } else {
+                    Ok(Term::Word(text.to_lowercase()))
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if terms.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        Ok(Query { terms })
+    }
+}
+
+impl<'de> Deserialize<'de> for Query {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let query = String::deserialize(deserializer)?;
+
+        query.parse().map_err(|err| {
+            serde::de::Error::custom(format!(
+                "invalid search query '{query}': {err}"
+            ))
+        })
+    }
+}
+
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Word(word) => write!(f, "{word}"),
+            Term::Phrase(phrase) => write!(f, "\"{phrase}\""),
+            Term::From(nick) => write!(f, "from:{nick}"),
+            Term::In(target) => write!(f, "in:{target}"),
+            Term::Before(date) => write!(f, "before:{date}"),
+            Term::After(date) => write!(f, "after:{date}"),
+            Term::Has(Has::Link) => write!(f, "has:link"),
+        }
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terms = self
+            .terms
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", terms.join(" "))
+    }
+}
+
+impl Query {
+    /// Whether `message` of the buffer `kind` matches, comparing nicknames
+    /// and targets with the casemapping of the buffer's server.
+    pub fn matches(
+        &self,
+        kind: &history::Kind,
+        message: &message::Message,
+        casemapping: isupport::CaseMap,
+    ) -> bool {
+        if !matches!(
+            message.target.source(),
+            Source::User(_) | Source::Action(_)
+        ) {
+            return false;
+        }
+
+        let text = message.text().to_lowercase();
+
+        self.terms.iter().all(|term| match term {
+            Term::Word(word) | Term::Phrase(word) => text.contains(word),
+            Term::From(nick) => digest::sender(message).is_some_and(|sender| {
+                sender.as_normalized_str() == casemapping.normalize(nick)
+            }),
+            Term::In(target) => kind.target().is_some_and(|kind| {
+                kind.as_normalized_str() == casemapping.normalize(target)
+            }),
+            Term::Before(date) => message.server_time < start_of(*date),
+            Term::After(date) => message.server_time >= start_of(*date),
+            Term::Has(Has::Link) => match &message.content {
+                message::Content::Fragments(fragments) => {
+                    fragments.iter().any(|fragment| fragment.url().is_some())
+                }
+                _ => false,
+            },
+        })
+    }
+
+    /// Byte ranges of `text` matching the query's words and phrases, in
+    /// order and without overlap.
+    pub fn highlights(&self, text: &str) -> Vec<Range<usize>> {
+        let lowercase = text.to_lowercase();
+
+        // Lowercasing may change byte lengths, in which case ranges can't be
+        // mapped back onto the original text
+        if lowercase.len() != text.len() {
+            return vec![];
+        }
+
+        let mut ranges = self
+            .terms
+            .iter()
+            .filter_map(|term| match term {
+                Term::Word(word) | Term::Phrase(word) if !word.is_empty() => {
+                    Some(word)
+                }
+                _ => None,
+            })
+            .flat_map(|needle| {
+                lowercase
+                    .match_indices(needle.as_str())
+                    .map(|(start, matched)| start..start + matched.len())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        ranges.sort_by_key(|range| (range.start, range.end));
+
+        ranges.into_iter().fold(vec![], |mut merged, range| {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => {
+                    last.end = last.end.max(range.end);
+                }
+                _ => merged.push(range),
+            }
+
+            merged
+        })
+    }
+}
+
+/// Splits `input` on whitespace outside of double quotes.
+fn tokenize(input: &str) -> Result<Vec<Range<usize>>, Error> {
+    let mut tokens = vec![];
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut in_quote = false;
+        let mut end = input.len();
+
+        while let Some(&(index, c)) = chars.peek() {
+            if c == '"' {
+                in_quote = !in_quote;
+            } else if c.is_whitespace() && !in_quote {
+                end = index;
+                break;
+            }
+
+            chars.next();
+        }
+
+        if in_quote {
+            return Err(Error::UnterminatedQuote);
+        }
+
+        tokens.push(start..end);
+    }
+
+    Ok(tokens)
+}
+
+/// Parses `name:value` for the known filter names, `None` for anything else
+/// (e.g. a link), which is searched for as a word.
+fn parse_filter(text: &str) -> Option<Result<Term, Error>> {
+    let (name, value) = text.split_once(':')?;
+    let name = name.to_lowercase();
+
+    if !FILTERS.contains(&name.as_str()) {
+        return None;
+    }
+
+    let value = value.trim_matches('"');
+
+    if value.is_empty() {
+        return Some(Err(Error::MissingValue(name)));
+    }
+
+    let date = || {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|_| Error::InvalidDate(value.to_string()))
+    };
+
+    Some(match name.as_str() {
+        "from" => Ok(Term::From(value.to_string())),
+        "in" => Ok(Term::In(value.to_string())),
+        "before" => date().map(Term::Before),
+        "after" => date().map(Term::After),
+        // has
+        _ => match value.to_lowercase().as_str() {
+            "link" | "url" => Ok(Term::Has(Has::Link)),
+            _ => Err(Error::UnknownHas(value.to_string())),
+        },
+    })
+}
+
+fn start_of(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .expect("midnight is valid")
+        .and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "from:nick in:#channel before:2024-01-01 has:link \"exact phrase\" word"
+                .parse::<Query>(),
+            Ok(Query {
+                terms: vec![
+                    Term::From("nick".to_string()),
+                    Term::In("#channel".to_string()),
+                    Term::Before(NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()),
+                    Term::Has(Has::Link),
+                    Term::Phrase("exact phrase".to_string()),
+                    Term::Word("word".to_string()),
+                ]
+            })
+        );
+        assert_eq!(
+            "in:\"#with space\"".parse::<Query>(),
+            Ok(Query {
+                terms: vec![Term::In("#with space".to_string())]
+            })
+        );
+    }
+
+    #[test]
+    fn validation() {
+        assert_eq!("".parse::<Query>(), Err(Error::Empty));
+        assert_eq!("   ".parse::<Query>(), Err(Error::Empty));
+        assert_eq!(
+            "\"unterminated".parse::<Query>(),
+            Err(Error::UnterminatedQuote)
+        );
+        assert_eq!(
+            "from:".parse::<Query>(),
+            Err(Error::MissingValue("from".to_string()))
+        );
+        assert_eq!(
+            "before:yesterday".parse::<Query>(),
+            Err(Error::InvalidDate("yesterday".to_string()))
+        );
+        assert_eq!(
+            "has:image".parse::<Query>(),
+            Err(Error::UnknownHas("image".to_string()))
+        );
+    }
+
+    #[test]
+    fn words_with_colons() {
+        assert_eq!(
+            "to:nick https://example.com/a?b note:".parse::<Query>(),
+            Ok(Query {
+                terms: vec![
+                    Term::Word("to:nick".to_string()),
+                    Term::Word("https://example.com/a?b".to_string()),
+                    Term::Word("note:".to_string()),
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize("from:nick  \"a b\" c"),
+            Ok(vec![0..9, 11..16, 17..18])
+        );
+    }
+
+    #[test]
+    fn highlights() {
+        let query = "hello \"big world\" from:nick".parse::<Query>().unwrap();
+
+        assert_eq!(
+            query.highlights("Hello there, Big World! hello"),
+            vec![0..5, 13..22, 24..29]
+        );
+        assert_eq!(
+            "lo hello".parse::<Query>().unwrap().highlights("hello"),
+            vec![0..5]
+        );
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Bound;
+
+use chrono::{DateTime, Utc};
+
+use super::{Query, Term};
+use crate::history;
+use crate::message::{self, Message, Source};
+
+/// Rebuild a buffer's index once it tracks this many more messages than the
+/// buffer holds, e.g. after messages were evicted from memory.
+const STALE_THRESHOLD: usize = 1024;
+
+/// Inverted index of the words in each buffer's messages, kept up to date as
+/// messages arrive so searching recent history doesn't scan every message.
+#[derive(Debug, Default)]
+pub struct Index(HashMap<history::Kind, Buffer>);
+
+#[derive(Debug, Default)]
+struct Buffer {
+    /// Lowercase words and the messages containing them, ordered so words
+    /// starting with a prefix are a range
+    words: BTreeMap<String, HashSet<message::Hash>>,
+    /// Server time of each indexed message, which history is ordered by
+    messages: HashMap<message::Hash, DateTime<Utc>>,
+}
+
+impl Index {
+    pub fn insert(&mut self, kind: &history::Kind, message: &Message) {
+        if !is_searchable(kind, message) {
+            return;
+        }
+
+        let buffer = self.0.entry(kind.clone()).or_default();
+
+        if buffer
+            .messages
+            .insert(message.hash, message.server_time)
+            .is_some()
+        {
+            return;
+        }
+
+        for word in words(&message.text().to_lowercase()) {
+            buffer
+                .words
+                .entry(word.to_string())
+                .or_default()
+                .insert(message.hash);
+        }
+    }
+
+    /// Replaces the index of `kind` with `messages`.
+    pub fn rebuild(&mut self, kind: &history::Kind, messages: &[Message]) {
+        self.0.remove(kind);
+
+        for message in messages {
+            self.insert(kind, message);
+        }
+    }
+
+    /// Rebuilds the index of `kind` if it has drifted far from `messages`.
+    pub fn prune(&mut self, kind: &history::Kind, messages: &[Message]) {
+        if self.0.get(kind).is_some_and(|buffer| {
+            buffer.messages.len() > messages.len() + STALE_THRESHOLD
+        }) {
+            self.rebuild(kind, messages);
+        }
+    }
+
+    pub fn remove(&mut self, kind: &history::Kind) {
+        self.0.remove(kind);
+    }
+
+    /// Messages of `kind` with a word starting with each of the words of
+    /// `query`, or `None` if the query has no words or phrases to narrow the
+    /// search by.
+    ///
+    /// Candidates still need to be checked with [`Query::matches`].
+    pub fn candidates(
+        &self,
+        kind: &history::Kind,
+        query: &Query,
+    ) -> Option<HashSet<message::Hash>> {
+        let needles = query
+            .terms
+            .iter()
+            .flat_map(|term| match term {
+                Term::Word(text) | Term::Phrase(text) => words(text).collect(),
+                _ => vec![],
+            })
+            .collect::<Vec<_>>();
+
+        if needles.is_empty() {
+            return None;
+        }
+
+        let Some(buffer) = self.0.get(kind) else {
+            return Some(HashSet::new());
+        };
+
+        needles
+            .into_iter()
+            .map(|needle| {
+                buffer
+                    .words
+                    .range::<str, _>((
+                        Bound::Included(needle),
+                        Bound::Unbounded,
+                    ))
+                    .take_while(|(word, _)| word.starts_with(needle))
+                    .flat_map(|(_, hashes)| hashes.iter().copied())
+                    .collect::<HashSet<_>>()
+            })
+            .reduce(|candidates, matches| {
+                candidates.intersection(&matches).copied().collect()
+            })
+    }
+
+    /// Position of the indexed message `hash` within `messages`, the
+    /// history of `kind` ordered by server time.
+    pub fn position(
+        &self,
+        kind: &history::Kind,
+        messages: &[Message],
+        hash: message::Hash,
+    ) -> Option<usize> {
+        let server_time = *self.0.get(kind)?.messages.get(&hash)?;
+
+        let start = messages
+            .partition_point(|message| message.server_time < server_time);
+
+        messages[start..]
+            .iter()
+            .take_while(|message| message.server_time == server_time)
+            .position(|message| message.hash == hash)
+            .map(|offset| start + offset)
+    }
+}
+
+/// Words of `text`, split on anything but letters and digits
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+}
+
+fn is_searchable(kind: &history::Kind, message: &Message) -> bool {
+    matches!(kind, history::Kind::Channel(..) | history::Kind::Query(..))
+        && matches!(
+            message.target.source(),
+            Source::User(_) | Source::Action(_)
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::message::tests::message_from_irc_message;
+    use crate::server::{Server, ServerName};
+    use crate::{isupport, target};
+
+    #[test]
+    fn candidates() {
+        let isupport = HashMap::new();
+        let kind = history::Kind::Channel(
+            Server::from(ServerName::from("libera")),
+            target::Channel::from_str(
+                "#chan",
+                isupport::get_chantypes_or_default(&isupport),
+                isupport::get_casemapping_or_default(&isupport),
+            ),
+        );
+        let messages = [
+            ":dan!d@localhost PRIVMSG #chan :Hey what's up!\r\n",
+            ":chat!test@user/test/bot/chat PRIVMSG #chan :quack quack\r\n",
+            ":dan!d@localhost PRIVMSG #chan :What a quacking duck\r\n",
+            ":WiZ JOIN #chan\r\n",
+        ]
+        .map(message_from_irc_message);
+
+        let mut index = Index::default();
+        index.rebuild(&kind, &messages);
+
+        let candidates = |index: &Index, query: &str| {
+            let query = query.parse::<Query>().unwrap();
+
+            index.candidates(&kind, &query).map(|candidates| {
+                messages
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, message)| candidates.contains(&message.hash))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(candidates(&index, "quack"), Some(vec![1, 2]));
+        assert_eq!(candidates(&index, "WHAT"), Some(vec![0, 2]));
+        assert_eq!(candidates(&index, "what's"), Some(vec![0]));
+        assert_eq!(candidates(&index, "up!"), Some(vec![0]));
+        assert_eq!(candidates(&index, "\"quacking duck\""), Some(vec![2]));
+        assert_eq!(candidates(&index, "uack"), Some(vec![]));
+        assert_eq!(candidates(&index, "goose"), Some(vec![]));
+        assert_eq!(candidates(&index, "join"), Some(vec![]));
+        assert_eq!(candidates(&index, "from:dan"), None);
+        assert_eq!(candidates(&index, "!!"), None);
+
+        for (i, message) in messages.iter().enumerate().take(3) {
+            assert_eq!(index.position(&kind, &messages, message.hash), Some(i));
+        }
+        assert_eq!(index.position(&kind, &messages, messages[3].hash), None);
+
+        index.remove(&kind);
+        assert_eq!(candidates(&index, "quack"), Some(vec![]));
+    }
+}
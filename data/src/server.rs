@@ -229,6 +229,24 @@ impl ConfigMap {
                 config.nick_password =
                     Some(read_from_command(nick_pass_command).await?);
             }
+            if let Some(oper) = &mut config.oper {
+                if let Some(pass_file) = &oper.password_file {
+                    if oper.password.is_some()
+                        || oper.password_command.is_some()
+                    {
+                        return Err(Error::DuplicateOperPassword);
+                    }
+                    let pass = fs::read_to_string(pass_file).await?;
+                    oper.password = pass.lines().next().map(String::from);
+                }
+                if let Some(pass_command) = &oper.password_command {
+                    if oper.password.is_some() {
+                        return Err(Error::DuplicateOperPassword);
+                    }
+                    oper.password =
+                        Some(read_from_command(pass_command).await?);
+                }
+            }
             if let Some(sasl) = &mut config.sasl {
                 match sasl {
                     Sasl::Plain {
@@ -281,6 +299,10 @@ impl ConfigMap {
         Ok(Self(map))
     }
 
+    pub fn get(&self, server: &ServerName) -> Option<&Arc<config::Server>> {
+        self.0.get(server)
+    }
+
     pub fn iter(
         &self,
     ) -> impl Iterator<Item = (&ServerName, &Arc<config::Server>)> {
@@ -0,0 +1,447 @@
+//! Messages shared outside of Halloy, as a formatted quote or an image.
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use fancy_regex::Regex;
+use iced_core::Color;
+
+use crate::appearance::theme::color_to_hex;
+use crate::isupport::CaseMap;
+use crate::message::{self, Source};
+use crate::{Message, config};
+
+/// Messages around the one shared which the range can be extended to, on
+/// either side
+pub const CONTEXT: usize = 25;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Nicknames replaced by `user1`, `user2`, etc., in order of
+    /// appearance, and hostmasks and IP addresses redacted
+    pub anonymize: bool,
+    pub timestamps: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            anonymize: false,
+            timestamps: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Message,
+    Action,
+    Server,
+}
+
+/// A shared message, formatted the way it's quoted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub timestamp: Option<String>,
+    /// Sender of a [`Kind::Message`]
+    pub nick: Option<String>,
+    pub kind: Kind,
+    pub text: String,
+}
+
+/// Colors of an HTML quote, taken from the theme.
+#[derive(Debug, Clone)]
+pub struct Colors {
+    pub background: Color,
+    pub text: Color,
+    pub timestamp: Color,
+    pub action: Color,
+    pub server: Color,
+    /// Colors of the nicknames, as they're shown in the lines
+    pub nicknames: HashMap<String, Color>,
+}
+
+/// `message` along with the [`CONTEXT`] of `messages` around it, and its
+/// index among them.
+pub fn context(
+    messages: &[&Message],
+    message: &Message,
+) -> Option<(Vec<Message>, usize)> {
+    let index = messages
+        .iter()
+        .position(|candidate| candidate.hash == message.hash)?;
+
+    let first = index.saturating_sub(CONTEXT);
+    let last = (index + CONTEXT).min(messages.len() - 1);
+
+    Some((
+        messages[first..=last]
+            .iter()
+            .map(|message| (*message).clone())
+            .collect(),
+        index - first,
+    ))
+}
+
+/// Shown in place of a redacted hostmask or IP address
+const REDACTED: &str = "[redacted]";
+
+static HOSTMASK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"[^\s()<>\[\]]+@[^\s()<>\[\]]+").unwrap());
+
+static IP_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"(?i)(?<![\w.:])(",
+        // IPv4
+        r"\d{1,3}(\.\d{1,3}){3}",
+        // IPv6, either in full or compressed
+        r"|([0-9a-f]{1,4}:){3,7}[0-9a-f]{1,4}",
+        r"|([0-9a-f]{1,4}:)+:([0-9a-f]{1,4}(:[0-9a-f]{1,4})*)?",
+        r")(?![\w.:])",
+    ))
+    .unwrap()
+});
+
+/// Lines of `messages`, with nicknames matched using the server's
+/// `casemapping` when anonymizing.
+pub fn lines(
+    messages: &[&Message],
+    options: Options,
+    casemapping: CaseMap,
+    config: &config::Buffer,
+) -> Vec<Line> {
+    let aliases = if options.anonymize {
+        aliases(messages, casemapping)
+    } else {
+        vec![]
+    };
+
+    messages
+        .iter()
+        .filter_map(|message| {
+            let (kind, nick) = match message.target.source() {
+                Source::User(user) => {
+                    (Kind::Message, Some(user.nickname().to_string()))
+                }
+                Source::Action(_) => (Kind::Action, None),
+                Source::Server(_) => (Kind::Server, None),
+                Source::Internal(message::source::Internal::Status(_)) => {
+                    (Kind::Server, None)
+                }
+                Source::Internal(_) => return None,
+            };
+
+            let timestamp = options
+                .timestamps
+                .then(|| config.format_timestamp(&message.server_time))
+                .flatten();
+
+            let mut text = message.text();
+            let mut nick = nick;
+
+            if options.anonymize {
+                text = redact(&text);
+
+                for (normalized, alias) in &aliases {
+                    text = replace_word(&text, normalized, alias, casemapping);
+                }
+
+                nick = nick.map(|nick| {
+                    let normalized = casemapping.normalize(&nick);
+
+                    aliases
+                        .iter()
+                        .find(|(original, _)| *original == normalized)
+                        .map_or(nick, |(_, alias)| alias.clone())
+                });
+            }
+
+            Some(Line {
+                timestamp,
+                nick,
+                kind,
+                text,
+            })
+        })
+        .collect()
+}
+
+/// Plain text quote of `lines`, one message per line.
+pub fn text(lines: &[Line]) -> String {
+    lines
+        .iter()
+        .map(|line| {
+            let content = match (line.kind, &line.nick) {
+                (Kind::Message, Some(nick)) => {
+                    format!("<{nick}> {}", line.text)
+                }
+                (Kind::Action, _) => format!("* {}", line.text),
+                _ => line.text.clone(),
+            };
+
+            match &line.timestamp {
+                Some(timestamp) => format!("{timestamp} {content}"),
+                None => content,
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// HTML quote of `lines`, styled inline so it can be pasted anywhere.
+pub fn html(lines: &[Line], colors: &Colors) -> String {
+    let span = |color: Color, content: &str| {
+        format!(
+            "<span style=\"color: {}\">{}</span>",
+            color_to_hex(color),
+            escape(content)
+        )
+    };
+
+    let body = lines
+        .iter()
+        .map(|line| {
+            let mut parts = vec![];
+
+            if let Some(timestamp) = &line.timestamp {
+                parts.push(span(colors.timestamp, timestamp));
+            }
+
+            match (line.kind, &line.nick) {
+                (Kind::Message, Some(nick)) => {
+                    let color = colors
+                        .nicknames
+                        .get(nick)
+                        .copied()
+                        .unwrap_or(colors.text);
+
+                    parts.push(span(color, &format!("<{nick}>")));
+                    parts.push(span(colors.text, &line.text));
+                }
+                (Kind::Action, _) => {
+                    parts
+                        .push(span(colors.action, &format!("* {}", line.text)));
+                }
+                _ => parts.push(span(colors.server, &line.text)),
+            }
+
+            format!("<div>{}</div>", parts.join(" "))
+        })
+        .collect::<String>();
+
+    let style = format!(
+        "margin: 0; padding: 8px; font-family: monospace; \
+         white-space: pre-wrap; background: {}; color: {}",
+        color_to_hex(colors.background),
+        color_to_hex(colors.text),
+    );
+
+    format!("<blockquote style=\"{style}\">{body}</blockquote>")
+}
+
+/// Nicknames of the senders of `messages` and of the users in their server
+/// messages, such as joins, normalized with `casemapping` and along with
+/// their aliases, in order of appearance.
+fn aliases(
+    messages: &[&Message],
+    casemapping: CaseMap,
+) -> Vec<(String, String)> {
+    let mut aliases: Vec<(String, String)> = vec![];
+
+    for message in messages {
+        let nick = match message.target.source() {
+            Source::User(user) | Source::Action(Some(user)) => {
+                user.nickname().to_string()
+            }
+            Source::Server(Some(server)) => match server.nick() {
+                Some(nick) => nick.to_string(),
+                None => continue,
+            },
+            _ => continue,
+        };
+        let nick = casemapping.normalize(&nick);
+
+        if !aliases.iter().any(|(original, _)| *original == nick) {
+            let alias = format!("user{}", aliases.len() + 1);
+
+            aliases.push((nick, alias));
+        }
+    }
+
+    aliases
+}
+
+/// Replaces hostmasks and IP addresses in `text`.
+fn redact(text: &str) -> String {
+    let text = HOSTMASK_REGEX.replace_all(text, REDACTED);
+
+    IP_REGEX.replace_all(&text, REDACTED).into_owned()
+}
+
+/// Replaces the occurrences of `word`, normalized with `casemapping`, in
+/// `text` which aren't part of a longer word.
+fn replace_word(
+    text: &str,
+    word: &str,
+    replacement: &str,
+    casemapping: CaseMap,
+) -> String {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+
+    // Normalized a character at a time, keeping where each of its bytes
+    // came from in `text` so matches can be mapped back onto it
+    let mut normalized = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+
+    for (offset, c) in text.char_indices() {
+        let c = casemapping.normalize(c.encode_utf8(&mut [0; 4]));
+
+        offsets.extend(std::iter::repeat_n(offset, c.len()));
+        normalized.push_str(&c);
+    }
+    offsets.push(text.len());
+
+    let mut replaced = String::with_capacity(text.len());
+    let mut end = 0;
+
+    for (index, _) in normalized.match_indices(word) {
+        let start = offsets[index];
+        let match_end = offsets[index + word.len()];
+
+        if start < end {
+            continue;
+        }
+
+        let before = text[..start].chars().next_back();
+        let after = text[match_end..].chars().next();
+
+        if before.is_some_and(is_word_char) || after.is_some_and(is_word_char) {
+            continue;
+        }
+
+        replaced.push_str(&text[end..start]);
+        replaced.push_str(replacement);
+        end = match_end;
+    }
+
+    replaced.push_str(&text[end..]);
+
+    replaced
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_whole_words() {
+        let casemapping = CaseMap::default();
+
+        assert_eq!(
+            replace_word("bob: ask bobby, bob", "bob", "user1", casemapping),
+            "user1: ask bobby, user1"
+        );
+        assert_eq!(
+            replace_word("<bob>", "bob", "user1", casemapping),
+            "<user1>"
+        );
+    }
+
+    #[test]
+    fn replaces_casemapped_words() {
+        assert_eq!(
+            replace_word("Bob, BOB: hi", "bob", "user1", CaseMap::ASCII),
+            "user1, user1: hi"
+        );
+        assert_eq!(
+            replace_word("ask Bob[m]", "bob{m}", "user1", CaseMap::RFC1459),
+            "ask user1"
+        );
+        assert_eq!(
+            replace_word("ask Bob[m]", "bob{m}", "user1", CaseMap::ASCII),
+            "ask Bob[m]"
+        );
+    }
+
+    #[test]
+    fn anonymizes_server_messages() {
+        use crate::message::source::server::{Kind as ServerKind, Server};
+        use crate::message::{Target, plain};
+        use crate::user::Nick;
+
+        let casemapping = CaseMap::ASCII;
+        let message = |source: Source, text: &str| {
+            Message::sent(Target::Server { source }, plain(text.to_string()))
+        };
+
+        let messages = [
+            message(
+                Source::Server(Some(Server::new(
+                    ServerKind::Join,
+                    Some(Nick::from_str("Alice", casemapping)),
+                ))),
+                "Alice (~alice@192.168.0.1) has joined",
+            ),
+            message(
+                Source::Server(None),
+                "connected to 10.0.0.1 and 2001:db8::1 at 12:30:00",
+            ),
+        ];
+        let lines = lines(
+            &messages.iter().collect::<Vec<_>>(),
+            Options {
+                anonymize: true,
+                timestamps: false,
+            },
+            casemapping,
+            &config::Buffer::default(),
+        );
+
+        assert_eq!(lines[0].text, "user1 ([redacted]) has joined");
+        assert_eq!(
+            lines[1].text,
+            "connected to [redacted] and [redacted] at 12:30:00"
+        );
+    }
+
+    #[test]
+    fn quotes_lines() {
+        let lines = vec![
+            Line {
+                timestamp: Some("[12:00]".to_string()),
+                nick: Some("user1".to_string()),
+                kind: Kind::Message,
+                text: "hi <there>".to_string(),
+            },
+            Line {
+                timestamp: None,
+                nick: None,
+                kind: Kind::Action,
+                text: "user2 waves".to_string(),
+            },
+        ];
+
+        assert_eq!(text(&lines), "[12:00] <user1> hi <there>\n* user2 waves");
+
+        let html = html(
+            &lines,
+            &Colors {
+                background: Color::BLACK,
+                text: Color::WHITE,
+                timestamp: Color::WHITE,
+                action: Color::WHITE,
+                server: Color::WHITE,
+                nicknames: HashMap::new(),
+            },
+        );
+
+        assert!(html.contains("&lt;user1&gt;"));
+        assert!(html.contains("hi &lt;there&gt;"));
+    }
+}
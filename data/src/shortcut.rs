@@ -35,14 +35,19 @@ pub enum Command {
     LeaveBuffer,
     ToggleNicklist,
     ToggleTopic,
+    ToggleDensity,
     ToggleSidebar,
     ToggleFullscreen,
+    ToggleAlwaysOnTop,
+    ToggleMiniMode,
+    ToggleBorderless,
     CommandBar,
     ReloadConfiguration,
     FileTransfers,
     Logs,
     ThemeEditor,
     Highlights,
+    Search,
     QuitApplication,
     ScrollUpPage,
     ScrollDownPage,
@@ -51,6 +56,8 @@ pub enum Command {
     CycleNextUnreadBuffer,
     CyclePreviousUnreadBuffer,
     MarkAsRead,
+    /// Open the buffer pinned in the slot, counting from zero
+    FocusPinnedBuffer(usize),
 }
 
 macro_rules! default {
@@ -144,6 +151,7 @@ impl KeyBind {
     default!(toggle_nick_list, "m", COMMAND | ALT);
     default!(toggle_sidebar, "b", COMMAND | ALT);
     default!(toggle_topic, "t", COMMAND | ALT);
+    default!(toggle_density, "d", COMMAND | ALT);
     #[cfg(target_os = "macos")]
     default!(toggle_fullscreen, "f", COMMAND | CTRL);
     #[cfg(not(target_os = "macos"))]
@@ -154,6 +162,7 @@ impl KeyBind {
     default!(logs, "l", COMMAND);
     default!(theme_editor, "t", COMMAND);
     default!(highlights, "i", COMMAND);
+    default!(search, "f", COMMAND | SHIFT);
     default!(scroll_up_page, PageUp);
     default!(scroll_down_page, PageDown);
     // Don't use HOME / END since text input is always focused
@@ -164,6 +173,18 @@ impl KeyBind {
     // Command + m is minimize in macOS
     default!(mark_as_read, "m", COMMAND | SHIFT);
 
+    /// Default keybinds of the pinned buffer slots, `Command` + `1` to `9`.
+    pub fn focus_pinned_buffers() -> Vec<KeyBind> {
+        (1..=9)
+            .map(|digit| KeyBind {
+                key_code: KeyCode(keyboard::Key::Character(
+                    digit.to_string().into(),
+                )),
+                modifiers: COMMAND,
+            })
+            .collect()
+    }
+
     pub fn is_pressed(
         &self,
         key_code: impl Into<KeyCode>,
@@ -5,8 +5,8 @@ use std::{cmp, fmt};
 use irc::proto;
 use serde::{Deserialize, Serialize};
 
-use crate::isupport;
 use crate::user::{Nick, NickRef, User};
+use crate::{isupport, mask};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Target {
@@ -57,6 +57,15 @@ impl Target {
         }
     }
 
+    /// Whether the target's name matches `mask`, see [`mask::matches`]
+    pub fn matches_mask(
+        &self,
+        mask: &str,
+        casemapping: isupport::CaseMap,
+    ) -> bool {
+        mask::matches(mask, self.as_str(), casemapping)
+    }
+
     pub fn parse(
         target: &str,
         chantypes: &[char],
@@ -78,6 +87,15 @@ impl Target {
             }))
         }
     }
+
+    /// Normalizes the target again, for when the server's `CASEMAPPING`
+    /// changed after it was created.
+    pub fn renormalize(&mut self, casemapping: isupport::CaseMap) {
+        match self {
+            Target::Channel(channel) => channel.renormalize(casemapping),
+            Target::Query(query) => query.renormalize(casemapping),
+        }
+    }
 }
 
 impl PartialEq for Target {
@@ -135,6 +153,55 @@ impl fmt::Display for Target {
     }
 }
 
+/// Category of server traffic which can be split out of the server buffer
+/// into a buffer of its own
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    strum::Display,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    /// NOTICEs sent by the server itself
+    Notices,
+    /// Server notices for operators (SNOTICEs)
+    #[strum(serialize = "Server Notices")]
+    Snotices,
+    Wallops,
+    #[strum(serialize = "MOTD")]
+    Motd,
+    /// Numeric replies which aren't about a channel or user
+    Numerics,
+}
+
+impl Category {
+    pub const ALL: &'static [Self] = &[
+        Self::Notices,
+        Self::Snotices,
+        Self::Wallops,
+        Self::Motd,
+        Self::Numerics,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Category::Notices => "notices",
+            Category::Snotices => "snotices",
+            Category::Wallops => "wallops",
+            Category::Motd => "motd",
+            Category::Numerics => "numerics",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct ChannelData {
     prefixes: Vec<char>,
@@ -204,10 +271,68 @@ impl Channel {
         }
     }
 
+    /// Checks a channel name the user typed (without any `STATUSMSG`
+    /// prefixes) before it is sent to the server, so mistakes can be
+    /// reported without waiting for an error numeric.
+    pub fn validate(
+        name: &str,
+        chantypes: &[char],
+        channel_len: Option<u16>,
+    ) -> Result<(), ChannelNameError> {
+        let Some(first) = name.chars().next() else {
+            return Err(ChannelNameError::Empty);
+        };
+
+        if !chantypes.contains(&first) {
+            return Err(ChannelNameError::MissingChantype {
+                chantypes: chantypes.iter().collect(),
+            });
+        }
+
+        if let Some(forbidden) =
+            name.chars().find(|c| FORBIDDEN_CHANNEL_CHARS.contains(c))
+        {
+            return Err(ChannelNameError::ForbiddenChar(forbidden));
+        }
+
+        if let Some(max_len) = channel_len.map(usize::from)
+            && name.len() > max_len
+        {
+            return Err(ChannelNameError::TooLong {
+                len: name.len(),
+                max_len,
+            });
+        }
+
+        Ok(())
+    }
+
     pub fn prefixes(&self) -> &[char] {
         &self.0.prefixes
     }
 
+    /// Normalizes the channel again, for when the server's `CASEMAPPING`
+    /// changed after it was created.
+    pub fn renormalize(&mut self, casemapping: isupport::CaseMap) {
+        let prefixes_len = self
+            .0
+            .prefixes
+            .iter()
+            .copied()
+            .map(char::len_utf8)
+            .sum::<usize>();
+
+        let normalized = casemapping.normalize(&self.0.raw[prefixes_len..]);
+
+        if normalized != self.0.normalized {
+            *self = Channel::from(ChannelData {
+                prefixes: self.0.prefixes.clone(),
+                normalized,
+                raw: self.0.raw.clone(),
+            });
+        }
+    }
+
     pub fn to_target(&self) -> Target {
         Target::Channel(self.clone())
     }
@@ -316,6 +441,19 @@ impl Query {
         }
     }
 
+    /// Normalizes the query again, for when the server's `CASEMAPPING`
+    /// changed after it was created.
+    pub fn renormalize(&mut self, casemapping: isupport::CaseMap) {
+        let normalized = casemapping.normalize(&self.0.raw);
+
+        if normalized != self.0.normalized {
+            *self = Query::from(QueryData {
+                normalized,
+                raw: self.0.raw.clone(),
+            });
+        }
+    }
+
     pub fn to_target(&self) -> Target {
         Target::Query(self.clone())
     }
@@ -353,6 +491,122 @@ impl fmt::Display for Query {
     }
 }
 
+/// A comma-separated list of targets (e.g. `#a,#b,nick`), as accepted by
+/// `PRIVMSG` and `NOTICE`.
+///
+/// Empty entries are skipped, and a target given more than once is only kept
+/// the first time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TargetList(Vec<Target>);
+
+impl TargetList {
+    pub fn parse(
+        targets: &str,
+        chantypes: &[char],
+        statusmsg: &[char],
+        casemapping: isupport::CaseMap,
+    ) -> Self {
+        let mut list = Vec::<Target>::new();
+
+        for target in targets.split(',').map(str::trim) {
+            if target.is_empty() {
+                continue;
+            }
+
+            let target =
+                Target::parse(target, chantypes, statusmsg, casemapping);
+
+            if !list.contains(&target) {
+                list.push(target);
+            }
+        }
+
+        Self(list)
+    }
+
+    /// Checks the list is non-empty and within the server's `TARGMAX` for
+    /// the command, if any.
+    pub fn validate(
+        &self,
+        target_limit: Option<u16>,
+    ) -> Result<(), TargetListError> {
+        if self.0.is_empty() {
+            return Err(TargetListError::Empty);
+        }
+
+        if let Some(max_number) = target_limit.map(usize::from)
+            && self.0.len() > max_number
+        {
+            return Err(TargetListError::TooManyTargets {
+                number: self.0.len(),
+                max_number,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Target> {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for TargetList {
+    type Item = Target;
+    type IntoIter = std::vec::IntoIter<Target>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl fmt::Display for TargetList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let targets = self.0.iter().map(Target::as_str).collect::<Vec<_>>();
+
+        write!(f, "{}", targets.join(","))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum TargetListError {
+    #[error("no targets")]
+    Empty,
+    #[error("too many targets ({number}/{max_number} allowed)")]
+    TooManyTargets { number: usize, max_number: usize },
+}
+
+const FORBIDDEN_CHANNEL_CHARS: &[char] = &[' ', ',', '\x07'];
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ChannelNameError {
+    #[error("channel name is empty")]
+    Empty,
+    #[error("must start with one of {chantypes}")]
+    MissingChantype { chantypes: String },
+    #[error("cannot contain {}", fmt_forbidden_char(*.0))]
+    ForbiddenChar(char),
+    #[error("too long ({len}/{max_len} characters)")]
+    TooLong { len: usize, max_len: usize },
+}
+
+fn fmt_forbidden_char(c: char) -> &'static str {
+    match c {
+        ' ' => "spaces",
+        ',' => "commas",
+        '\x07' => "^G (BEL)",
+        _ => "control characters",
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ParseError {
     #[error("unable to parse channel from {0}")]
@@ -379,3 +633,136 @@ pub fn join_targets(targets: Vec<&str>) -> String {
         String::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_mask() {
+        let chantypes = proto::DEFAULT_CHANNEL_PREFIXES;
+        let casemapping = isupport::CaseMap::RFC1459;
+
+        let channel =
+            Target::parse("#Halloy[dev]", chantypes, &[], casemapping);
+        let query = Target::parse("Dan^", chantypes, &[], casemapping);
+
+        assert!(channel.matches_mask("#halloy*", casemapping));
+        assert!(channel.matches_mask("#*{dev}", casemapping));
+        assert!(!channel.matches_mask("#*{dev}", isupport::CaseMap::ASCII));
+        assert!(!channel.matches_mask("halloy*", casemapping));
+        assert!(query.matches_mask("dan~", casemapping));
+        assert!(query.matches_mask("d???", casemapping));
+        assert!(!query.matches_mask("d??", casemapping));
+    }
+
+    #[test]
+    fn validate_channel() {
+        let chantypes = proto::DEFAULT_CHANNEL_PREFIXES;
+
+        assert_eq!(Channel::validate("#halloy", chantypes, None), Ok(()));
+        assert_eq!(Channel::validate("&halloy", chantypes, Some(7)), Ok(()));
+        assert_eq!(
+            Channel::validate("", chantypes, None),
+            Err(ChannelNameError::Empty)
+        );
+        assert_eq!(
+            Channel::validate("halloy", &['#'], None),
+            Err(ChannelNameError::MissingChantype {
+                chantypes: String::from("#")
+            })
+        );
+        assert_eq!(
+            Channel::validate("#hal loy", chantypes, None),
+            Err(ChannelNameError::ForbiddenChar(' '))
+        );
+        assert_eq!(
+            Channel::validate("#hal,loy", chantypes, None),
+            Err(ChannelNameError::ForbiddenChar(','))
+        );
+        assert_eq!(
+            Channel::validate("#hal\x07loy", chantypes, None),
+            Err(ChannelNameError::ForbiddenChar('\x07'))
+        );
+        assert_eq!(
+            Channel::validate("#halloy", chantypes, Some(6)),
+            Err(ChannelNameError::TooLong { len: 7, max_len: 6 })
+        );
+    }
+
+    #[test]
+    fn target_list() {
+        let chantypes = proto::DEFAULT_CHANNEL_PREFIXES;
+        let casemapping = isupport::CaseMap::default();
+
+        let list = TargetList::parse(
+            "#a,,Bob, #b,#A,bob,",
+            chantypes,
+            &[],
+            casemapping,
+        );
+
+        assert_eq!(list.to_string(), "#a,Bob,#b");
+        assert!(matches!(
+            list.iter().collect::<Vec<_>>()[..],
+            [Target::Channel(_), Target::Query(_), Target::Channel(_)]
+        ));
+
+        assert_eq!(list.validate(None), Ok(()));
+        assert_eq!(list.validate(Some(3)), Ok(()));
+        assert_eq!(
+            list.validate(Some(2)),
+            Err(TargetListError::TooManyTargets {
+                number: 3,
+                max_number: 2
+            })
+        );
+
+        let list = TargetList::parse(",,", chantypes, &[], casemapping);
+
+        assert!(list.is_empty());
+        assert_eq!(list.validate(None), Err(TargetListError::Empty));
+    }
+
+    #[test]
+    fn renormalize() {
+        let chantypes = proto::DEFAULT_CHANNEL_PREFIXES;
+        let statusmsg = ['@', '+'];
+
+        let mut braces = Channel::parse(
+            "@#{halloy}",
+            chantypes,
+            &statusmsg,
+            isupport::CaseMap::ASCII,
+        )
+        .unwrap();
+        let mut brackets = Channel::parse(
+            "#[halloy]",
+            chantypes,
+            &statusmsg,
+            isupport::CaseMap::ASCII,
+        )
+        .unwrap();
+
+        assert_ne!(braces, brackets);
+
+        braces.renormalize(isupport::CaseMap::RFC1459);
+        brackets.renormalize(isupport::CaseMap::RFC1459);
+
+        assert_eq!(braces, brackets);
+        assert_eq!(braces.as_str(), "@#{halloy}");
+        assert_eq!(braces.prefixes(), ['@']);
+
+        let mut target = Target::parse(
+            "Nick~",
+            chantypes,
+            &statusmsg,
+            isupport::CaseMap::ASCII,
+        );
+
+        target.renormalize(isupport::CaseMap::RFC1459);
+
+        assert_eq!(target.as_normalized_str(), "nick^");
+        assert_eq!(target.as_str(), "Nick~");
+    }
+}
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 use crate::config::buffer::UsernameFormat;
-use crate::{isupport, mode};
+use crate::{isupport, mask, mode};
 
 #[derive(Debug, Clone)]
 pub struct User {
@@ -36,19 +36,44 @@ impl Hash for User {
     }
 }
 
+/// Bridge relaying users from another network onto IRC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bridge {
+    /// Matrix users, puppeted by matrix-appservice-irc as their display
+    /// name followed by `[m]`, or connected from a `matrix` host cloak
+    Matrix,
+    /// Matrix users connected through Heisenbridge, recognized by their
+    /// `heisenbridge` host cloak
+    Heisenbridge,
+}
+
+/// Name shown for users puppeted by a bridge
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BridgedName {
+    /// The user's nickname
+    #[default]
+    Nickname,
+    /// The user's display name on the other side of the bridge
+    DisplayName,
+}
+
 // our list of users is essentially a hashmap with an order defined by access level
 #[derive(Debug, Default)]
-pub struct ChannelUsers(IndexSet<User>);
+pub struct ChannelUsers {
+    users: IndexSet<User>,
+    // set on every change, cleared once the change has been shown
+    dirty: bool,
+}
 
 impl FromIterator<User> for ChannelUsers {
     fn from_iter<T>(iter: T) -> Self
     where
         T: IntoIterator<Item = User>,
     {
-        let mut set: IndexSet<User> = iter.into_iter().collect();
+        let mut users: IndexSet<User> = iter.into_iter().collect();
         // we can't use `.sort_by_cached_key` here since it borrows a user.
-        set.sort_by(|k1, k2| k1.key().cmp(&k2.key()));
-        Self(set)
+        users.sort_by(|k1, k2| k1.key().cmp(&k2.key()));
+        Self { users, dirty: true }
     }
 }
 impl<'a> IntoIterator for &'a ChannelUsers {
@@ -61,40 +86,65 @@ impl<'a> IntoIterator for &'a ChannelUsers {
 
 impl ChannelUsers {
     pub fn iter(&self) -> indexmap::set::Iter<'_, User> {
-        self.0.iter()
+        self.users.iter()
     }
 
     pub fn resolve(&self, user: &User) -> Option<&User> {
-        self.0.get(user)
+        self.users.get(user)
     }
 
     pub fn take(&mut self, user: &User) -> Option<User> {
-        self.0.shift_take(user)
+        let user = self.users.shift_take(user);
+        self.dirty |= user.is_some();
+        user
     }
 
     pub fn insert(&mut self, user: User) -> bool {
         // TODO(pounce, #1070) change to `insert_sorted_by_key` when merged
         let (Ok(i) | Err(i)) =
-            self.0.binary_search_by_key(&user.key(), User::key);
-        self.0.insert_before(i, user).1
+            self.users.binary_search_by_key(&user.key(), User::key);
+        self.dirty = true;
+        self.users.insert_before(i, user).1
+    }
+
+    /// Inserts (or replaces) all `users`, sorting the list once rather than
+    /// searching for the position of each user.
+    pub fn extend(&mut self, users: impl IntoIterator<Item = User>) {
+        for user in users {
+            self.users.replace(user);
+        }
+
+        self.users.sort_by(|k1, k2| k1.key().cmp(&k2.key()));
+        self.dirty = true;
     }
 
     pub fn remove(&mut self, user: &User) -> bool {
-        self.0.shift_remove(user)
+        let removed = self.users.shift_remove(user);
+        self.dirty |= removed;
+        removed
     }
 
     pub fn get_by_nick(&self, nick: NickRef) -> Option<&User> {
-        self.0.get(&nick)
+        self.users.get(&nick)
     }
 
     #[must_use]
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.users.len()
     }
 
     #[must_use]
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.users.is_empty()
+    }
+
+    /// Whether the list changed since it was last marked clean
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    pub fn mark_clean(&mut self) {
+        self.dirty = false;
     }
 }
 
@@ -176,15 +226,25 @@ impl User {
         self.nickname.seed()
     }
 
+    /// Name to show for the user, see [`User::bridged`] for users
+    /// puppeted by a bridge.
     pub fn display(
         &self,
         with_access_levels: bool,
         truncate: Option<u16>,
+        bridged_name: BridgedName,
     ) -> String {
+        let name = match bridged_name {
+            BridgedName::Nickname => self.as_str(),
+            BridgedName::DisplayName => self
+                .bridged()
+                .map_or(self.as_str(), |(_, display_name)| display_name),
+        };
+
         let mut nickname = if with_access_levels {
-            format!("{}{}", self.highest_access_level(), self.nickname())
+            format!("{}{name}", self.highest_access_level())
         } else {
-            self.nickname().to_string()
+            name.to_string()
         };
 
         if let Some(len) = truncate {
@@ -198,6 +258,50 @@ impl User {
         self.nickname.raw.as_ref()
     }
 
+    /// The bridge puppeting this user and the user's display name on the
+    /// other side of it, if their nickname follows a known puppet format
+    /// or their hostname is a known bridge cloak. Bridges route messages
+    /// sent to the puppet's nickname, so that is still what the user is
+    /// queried and mentioned by.
+    pub fn bridged(&self) -> Option<(Bridge, &str)> {
+        // Bridges append digits or underscores when the nickname is taken
+        let nick = self
+            .as_str()
+            .trim_end_matches(|c: char| c.is_ascii_digit() || c == '_');
+
+        let display_name = nick
+            .strip_suffix("[m]")
+            .or_else(|| nick.strip_suffix("[M]"))
+            .filter(|display_name| !display_name.is_empty());
+
+        // Cloaks such as `gateway/matrix/...` or `matrix.org/...`
+        let cloak = self.hostname().filter(|host| host.contains('/')).and_then(
+            |hostname| {
+                hostname.split('/').find_map(|segment| {
+                    let segment = segment.to_ascii_lowercase();
+
+                    if segment == "heisenbridge" {
+                        Some(Bridge::Heisenbridge)
+                    } else if segment == "matrix"
+                        || segment.starts_with("matrix.")
+                    {
+                        Some(Bridge::Matrix)
+                    } else {
+                        None
+                    }
+                })
+            },
+        );
+
+        match (cloak, display_name) {
+            (Some(bridge), display_name) => {
+                Some((bridge, display_name.unwrap_or(self.as_str())))
+            }
+            (None, Some(display_name)) => Some((Bridge::Matrix, display_name)),
+            (None, None) => None,
+        }
+    }
+
     pub fn as_normalized_str(&self) -> &str {
         self.nickname.normalized.as_ref()
     }
@@ -218,6 +322,16 @@ impl User {
         self.hostname.as_deref()
     }
 
+    /// `nick!user@host`, if both the username and hostname are known.
+    pub fn hostmask(&self) -> Option<String> {
+        Some(format!(
+            "{}!{}@{}",
+            self.nickname(),
+            self.username()?,
+            self.hostname()?
+        ))
+    }
+
     pub fn accountname(&self) -> Option<&str> {
         self.accountname.as_deref()
     }
@@ -280,6 +394,20 @@ impl User {
         }
     }
 
+    /// Takes the access levels and, when present, the hostmask of an entry
+    /// from a names list, keeping what the names list doesn't carry.
+    pub fn update_from_names(&mut self, names: User) {
+        self.access_levels = names.access_levels;
+
+        if names.username.is_some() {
+            self.username = names.username;
+        }
+
+        if names.hostname.is_some() {
+            self.hostname = names.hostname;
+        }
+    }
+
     pub fn update_away(&mut self, away: bool) {
         self.away = away;
     }
@@ -330,6 +458,29 @@ impl User {
         })
     }
 
+    /// Whether the user matches `mask`, see [`mask::matches`]. Masks
+    /// containing `!` or `@` are matched against the user's full
+    /// `nick!user@host`, others against their nickname only. Unknown parts
+    /// of the hostmask are matched as empty, so only `*` matches them.
+    pub fn matches_mask(
+        &self,
+        mask: &str,
+        casemapping: isupport::CaseMap,
+    ) -> bool {
+        if mask.contains(['!', '@']) {
+            let hostmask = format!(
+                "{}!{}@{}",
+                self.nickname(),
+                self.username().unwrap_or_default(),
+                self.hostname().unwrap_or_default()
+            );
+
+            mask::matches(mask, &hostmask, casemapping)
+        } else {
+            self.nickname.matches_mask(mask, casemapping)
+        }
+    }
+
     pub fn parse(
         value: &str,
         casemapping: Option<isupport::CaseMap>,
@@ -496,6 +647,15 @@ impl<'a> Nick {
         self.normalized = casemapping.normalize(self.raw.as_str());
     }
 
+    /// Whether the nickname matches `mask`, see [`mask::matches`]
+    pub fn matches_mask(
+        &self,
+        mask: &str,
+        casemapping: isupport::CaseMap,
+    ) -> bool {
+        mask::matches(mask, self.as_str(), casemapping)
+    }
+
     pub fn seed(&self) -> &str {
         self.normalized.as_ref()
     }
@@ -676,6 +836,30 @@ impl TryFrom<mode::Channel> for AccessLevel {
 mod tests {
     use super::*;
 
+    #[test]
+    fn update_from_names() {
+        let mut user = User::parse("+dan!d@localhost", None, None)
+            .unwrap()
+            .with_accountname("dan");
+        user.update_away(true);
+
+        user.update_from_names(User::parse("@+dan", None, None).unwrap());
+
+        assert!(user.has_access_level(AccessLevel::Oper));
+        assert!(user.has_access_level(AccessLevel::Voice));
+        assert_eq!(user.hostmask().as_deref(), Some("dan!d@localhost"));
+        assert_eq!(user.accountname(), Some("dan"));
+        assert!(user.is_away());
+
+        user.update_from_names(
+            User::parse("+dan!e@remote", None, None).unwrap(),
+        );
+
+        assert!(!user.has_access_level(AccessLevel::Oper));
+        assert!(user.has_access_level(AccessLevel::Voice));
+        assert_eq!(user.hostmask().as_deref(), Some("dan!e@remote"));
+    }
+
     #[test]
     fn user_serde() {
         use serde_test::{Token, assert_tokens};
@@ -868,6 +1052,81 @@ mod tests {
         assert!(!user.matches_masks(&["[invalid".to_string()]));
     }
 
+    #[test]
+    fn matches_mask() {
+        let casemapping = isupport::CaseMap::RFC1459;
+        let user =
+            User::parse("@[Dan]!~d@irc.example.org", None, None).unwrap();
+        let nick_only = User::parse("[Dan]", None, None).unwrap();
+
+        assert!(user.matches_mask("{dan}", casemapping));
+        assert!(user.matches_mask("{d?n}*", casemapping));
+        assert!(!user.matches_mask("{dan}", isupport::CaseMap::ASCII));
+        assert!(user.matches_mask("*!~*@*.EXAMPLE.org", casemapping));
+        assert!(user.matches_mask("{dan}!*", casemapping));
+        assert!(!user.matches_mask("*!*@*.example.com", casemapping));
+
+        assert!(nick_only.matches_mask("{dan}!*@*", casemapping));
+        assert!(!nick_only.matches_mask("*!*@*.example.org", casemapping));
+    }
+
+    #[test]
+    fn bridged() {
+        let tests = [
+            ("alice[m]", Some("alice")),
+            ("Alice_Smith[m]1", Some("Alice_Smith")),
+            ("bob[M]__", Some("bob")),
+            ("[m]", None),
+            ("dan", None),
+            ("dan[m]x", None),
+        ];
+
+        for (nick, expected) in tests {
+            let user = User::parse(&format!("@{nick}"), None, None).unwrap();
+
+            assert_eq!(
+                user.bridged(),
+                expected.map(|display_name| (Bridge::Matrix, display_name)),
+                "{nick}"
+            );
+            assert_eq!(
+                user.display(true, None, BridgedName::DisplayName),
+                format!("@{}", expected.unwrap_or(nick))
+            );
+            assert_eq!(user.display(false, None, BridgedName::Nickname), nick);
+        }
+
+        let tests = [
+            ("alice!alice@gateway/matrix/alice", Bridge::Matrix, "alice"),
+            ("bob[m]!bob@matrix.org/bob", Bridge::Matrix, "bob"),
+            (
+                "erin!erin@user/Heisenbridge/erin",
+                Bridge::Heisenbridge,
+                "erin",
+            ),
+        ];
+
+        for (hostmask, bridge, display_name) in tests {
+            let user = User::parse(hostmask, None, None).unwrap();
+
+            assert_eq!(
+                user.bridged(),
+                Some((bridge, display_name)),
+                "{hostmask}"
+            );
+        }
+
+        for hostmask in [
+            "dan!dan@matrix.example.org",
+            "dan!dan@user/matrixfan",
+            "dan!dan@user/dan",
+        ] {
+            let user = User::parse(hostmask, None, None).unwrap();
+
+            assert_eq!(user.bridged(), None, "{hostmask}");
+        }
+    }
+
     #[test]
     fn chanmap() {
         let users = &[
@@ -891,4 +1150,36 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn channel_users_extend() {
+        let mut channel_users: ChannelUsers = ["dan", "+erin", "alice"]
+            .into_iter()
+            .map(|user| User::parse(user, None, None).unwrap())
+            .collect();
+        channel_users.mark_clean();
+
+        channel_users.extend(
+            ["@dan", "bob"]
+                .into_iter()
+                .map(|user| User::parse(user, None, None).unwrap()),
+        );
+
+        assert!(channel_users.is_dirty());
+        assert_eq!(
+            channel_users
+                .iter()
+                .map(|user| user.display(true, None, BridgedName::Nickname))
+                .collect::<Vec<_>>(),
+            vec!["@dan", "+erin", "alice", "bob"]
+        );
+
+        channel_users.mark_clean();
+        let bob = User::parse("bob", None, None).unwrap();
+        assert!(channel_users.resolve(&bob).is_some());
+        assert!(!channel_users.is_dirty());
+
+        channel_users.remove(&bob);
+        assert!(channel_users.is_dirty());
+    }
 }
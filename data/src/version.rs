@@ -1,4 +1,10 @@
-use crate::environment::VERSION;
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::{env, io};
+
+use tokio::fs;
+
+use crate::environment::{self, VERSION};
 
 const LATEST_REMOTE_RELEASE_URL: &str =
     "https://api.github.com/repos/squidowl/halloy/releases/latest";
@@ -6,7 +12,7 @@ const LATEST_REMOTE_RELEASE_URL: &str =
 #[derive(Debug, Clone)]
 pub struct Version {
     pub current: String,
-    pub remote: Option<String>,
+    pub remote: Option<Release>,
 }
 
 impl Default for Version {
@@ -25,18 +31,92 @@ impl Version {
         }
     }
 
+    /// Whether the remote release is newer than the running version. An
+    /// older remote release, or one that can't be parsed, isn't offered.
     pub fn is_old(&self) -> bool {
-        match &self.remote {
-            Some(remote) => &self.current != remote,
-            None => false,
-        }
+        self.remote.as_ref().is_some_and(|remote| {
+            compare(&remote.version, &self.current) == Some(Ordering::Greater)
+        })
+    }
+
+    /// The newer remote release, if any.
+    pub fn newer(&self) -> Option<&Release> {
+        self.remote.as_ref().filter(|_| self.is_old())
+    }
+}
+
+/// Compares versions such as `2025.6`, `v2025.6.1` or `2025.7-rc1`
+/// component-wise, with missing components as zero and a pre-release before
+/// its release.
+fn compare(a: &str, b: &str) -> Option<Ordering> {
+    fn parse(version: &str) -> Option<(Vec<u64>, Option<&str>)> {
+        let version = version.trim().trim_start_matches('v');
+        // Build metadata doesn't affect precedence
+        let version = version.split_once('+').map_or(version, |(v, _)| v);
+        let (release, pre_release) = match version.split_once('-') {
+            Some((release, pre_release)) => (release, Some(pre_release)),
+            None => (version, None),
+        };
+
+        let numbers = release
+            .split('.')
+            .map(|number| number.parse().ok())
+            .collect::<Option<Vec<u64>>>()?;
+
+        Some((numbers, pre_release))
     }
+
+    let (a_numbers, a_pre_release) = parse(a)?;
+    let (b_numbers, b_pre_release) = parse(b)?;
+
+    let len = a_numbers.len().max(b_numbers.len());
+    let component = |numbers: &[u64], i| numbers.get(i).copied().unwrap_or(0);
+
+    let numbers = (0..len)
+        .map(|i| component(&a_numbers, i).cmp(&component(&b_numbers, i)))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(Ordering::Equal);
+
+    Some(numbers.then_with(|| match (a_pre_release, b_pre_release) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => a.cmp(b),
+    }))
+}
+
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: String,
+    /// Release notes, in markdown.
+    pub notes: String,
+    pub url: String,
+    /// Build for this platform, when Halloy was installed in a way that can
+    /// be updated by downloading it.
+    pub asset: Option<Asset>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Asset {
+    pub name: String,
+    pub url: String,
 }
 
-pub async fn latest_remote_version() -> Option<String> {
+pub async fn latest_remote_release() -> Option<Release> {
     #[derive(serde::Deserialize)]
-    struct Release {
+    struct GithubRelease {
         tag_name: String,
+        #[serde(default)]
+        body: Option<String>,
+        html_url: String,
+        #[serde(default)]
+        assets: Vec<GithubAsset>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct GithubAsset {
+        name: String,
+        browser_download_url: String,
     }
 
     let client = reqwest::Client::builder()
@@ -51,9 +131,156 @@ pub async fn latest_remote_version() -> Option<String> {
         .await
         .ok()?;
 
-    response
-        .json::<Release>()
-        .await
-        .ok()
-        .map(|release| release.tag_name)
+    let release = response.json::<GithubRelease>().await.ok()?;
+
+    let asset = if is_self_updatable() {
+        release
+            .assets
+            .into_iter()
+            .find(|asset| is_platform_asset(&asset.name))
+            .map(|asset| Asset {
+                name: asset.name,
+                url: asset.browser_download_url,
+            })
+    } else {
+        None
+    };
+
+    Some(Release {
+        version: release.tag_name,
+        notes: release.body.unwrap_or_default(),
+        url: release.html_url,
+        asset,
+    })
+}
+
+/// Downloads `asset` into the user's download directory, returning the
+/// path it was saved to.
+pub async fn download(asset: Asset) -> Result<PathBuf, DownloadError> {
+    let client = reqwest::Client::builder().user_agent("halloy").build()?;
+
+    let bytes = client
+        .get(&asset.url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+
+    let dir = dirs_next::download_dir().unwrap_or_else(environment::cache_dir);
+    fs::create_dir_all(&dir).await?;
+
+    // Asset names come from the release, don't let them escape `dir`
+    let name = PathBuf::from(&asset.name)
+        .file_name()
+        .map_or_else(|| PathBuf::from("halloy-update"), PathBuf::from);
+    let path = dir.join(name);
+
+    fs::write(&path, &bytes).await?;
+
+    Ok(path)
+}
+
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum DownloadError {
+    #[error("request failed: {0}")]
+    Request(String),
+    #[error("failed to save: {0}")]
+    Io(String),
+}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Request(error.to_string())
+    }
+}
+
+impl From<io::Error> for DownloadError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error.to_string())
+    }
+}
+
+/// Whether a new build can be downloaded and installed by the user, as
+/// opposed to Halloy being managed by a package manager or sandbox which
+/// should deliver the update instead.
+fn is_self_updatable() -> bool {
+    if env::var_os("FLATPAK_ID").is_some() || env::var_os("SNAP").is_some() {
+        return false;
+    }
+
+    if cfg!(target_os = "linux") {
+        // Installed by a package manager
+        return env::current_exe().is_ok_and(|exe| {
+            !exe.starts_with("/usr") && !exe.starts_with("/nix")
+        });
+    }
+
+    cfg!(any(target_os = "macos", target_os = "windows"))
+}
+
+fn is_platform_asset(name: &str) -> bool {
+    let name = name.to_lowercase();
+
+    if cfg!(target_os = "windows") {
+        name.ends_with(".msi")
+    } else if cfg!(target_os = "macos") {
+        name.ends_with(".dmg")
+    } else if cfg!(target_os = "linux") {
+        name.ends_with(".tar.gz")
+            && name.contains("linux")
+            && name.contains(env::consts::ARCH)
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_old() {
+        let mut version = Version::new();
+        assert!(!version.is_old());
+
+        let release = |version: &str| Release {
+            version: version.to_string(),
+            notes: String::new(),
+            url: String::new(),
+            asset: None,
+        };
+
+        version.remote = Some(release(VERSION));
+        assert!(!version.is_old());
+        assert!(version.newer().is_none());
+
+        version.remote = Some(release("9999.1"));
+        assert!(version.is_old());
+        assert_eq!(
+            version.newer().map(|release| release.version.as_str()),
+            Some("9999.1")
+        );
+
+        // Older than the running version, such as when running a build
+        // ahead of the latest release
+        version.remote = Some(release("2000.1"));
+        assert!(!version.is_old());
+        assert!(version.newer().is_none());
+
+        version.remote = Some(release("not a version"));
+        assert!(!version.is_old());
+    }
+
+    #[test]
+    fn compares_versions() {
+        assert_eq!(compare("2025.6", "2025.6"), Some(Ordering::Equal));
+        assert_eq!(compare("v2025.6", "2025.6.0"), Some(Ordering::Equal));
+        assert_eq!(compare("2025.10", "2025.9"), Some(Ordering::Greater));
+        assert_eq!(compare("2025.6.1", "2025.6"), Some(Ordering::Greater));
+        assert_eq!(compare("2025.6", "2026.1"), Some(Ordering::Less));
+        assert_eq!(compare("2025.7-rc1", "2025.7"), Some(Ordering::Less));
+        assert_eq!(compare("2025.7-rc1", "2025.6"), Some(Ordering::Greater));
+        assert_eq!(compare("2025.x", "2025.6"), None);
+    }
 }
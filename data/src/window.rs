@@ -1,77 +1,425 @@
+use std::collections::HashMap;
 use std::io;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 
-use iced_core::{Point, Size};
+use display_info::DisplayInfo;
+use iced_core::{Point, Rectangle, Size};
 use serde::{Deserialize, Serialize};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 
-use crate::environment;
+use crate::{Buffer, Profile, environment};
 
 pub const MIN_SIZE: Size = Size::new(426.0, 240.0);
 
+/// Share of the primary display a window takes up by default.
+const DEFAULT_DISPLAY_SHARE: f32 = 0.6;
+
+/// Size of windows which haven't been placed yet, based on the primary
+/// display.
+static DEFAULT_SIZE: LazyLock<Size> =
+    LazyLock::new(|| default_size(primary(&screens())));
+
 pub mod position;
 pub mod size;
+mod work_area;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Window {
+    /// Position of the window when it isn't maximized or fullscreen.
+    #[serde(with = "serde_position")]
+    pub position: Option<Point>,
+    /// Size of the window when it isn't maximized or fullscreen.
+    #[serde(with = "serde_size")]
+    pub size: Size,
+    pub is_maximized: bool,
+    pub is_fullscreen: bool,
+    pub is_always_on_top: bool,
+    /// Whether the window is in mini mode, using the `mini` geometry instead.
+    pub is_mini: bool,
+    /// Geometry of the window in mini mode, once it has been used.
+    pub mini: Option<Geometry>,
+    /// Whether the window is drawn without the system's decorations, using
+    /// Halloy's own title bar instead.
+    pub is_borderless: bool,
+    /// Display the window was on, set when saving.
+    pub display: Option<Display>,
+    /// Display the window was on in mini mode, set when saving.
+    pub mini_display: Option<Display>,
+}
+
+/// Position and size of a window, kept for the alternate mini mode.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Geometry {
     #[serde(with = "serde_position")]
     pub position: Option<Point>,
     #[serde(with = "serde_size")]
     pub size: Size,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Display {
+    pub id: u32,
+    pub name: String,
+    pub scale_factor: f32,
+    /// Origin of the display, in logical coordinates.
+    #[serde(with = "serde_point")]
+    pub origin: Point,
+}
+
+impl Display {
+    fn from_screen(screen: &Screen) -> Self {
+        Self {
+            id: screen.id,
+            name: screen.name.clone(),
+            scale_factor: screen.scale_factor,
+            origin: screen.bounds.position(),
+        }
+    }
+}
+
+/// A display as windows are placed on it, in logical coordinates.
+#[derive(Debug, Clone)]
+struct Screen {
+    id: u32,
+    name: String,
+    scale_factor: f32,
+    is_primary: bool,
+    bounds: Rectangle,
+    /// Part of the display not covered by taskbars, docks or panels.
+    work_area: Rectangle,
+}
+
+impl Screen {
+    fn new(info: &DisplayInfo, work_area: Option<Rectangle>) -> Self {
+        let bounds = work_area::bounds(info);
+        let scale = 1.0 / logical_scale_factor(info);
+
+        Self {
+            id: info.id,
+            name: info.name.clone(),
+            scale_factor: info.scale_factor,
+            is_primary: info.is_primary,
+            bounds: bounds * scale,
+            work_area: work_area.unwrap_or(bounds) * scale,
+        }
+    }
+}
+
+fn screens() -> Vec<Screen> {
+    let displays = DisplayInfo::all().unwrap_or_default();
+
+    displays
+        .iter()
+        .zip(work_area::get(&displays))
+        .map(|(info, work_area)| Screen::new(info, work_area))
+        .collect()
+}
+
+fn primary(screens: &[Screen]) -> Option<&Screen> {
+    screens
+        .iter()
+        .find(|screen| screen.is_primary)
+        .or(screens.first())
+}
+
+fn screen_at(screens: &[Screen], position: Point) -> Option<&Screen> {
+    screens
+        .iter()
+        .find(|screen| screen.bounds.contains(position))
+}
+
+/// A share of the work area of `screen`, or 1024×768 if the displays can't
+/// be queried.
+fn default_size(screen: Option<&Screen>) -> Size {
+    screen
+        .map(|screen| screen.work_area.size())
+        .filter(|size| size.width > 0.0 && size.height > 0.0)
+        .map_or(Size::new(1024.0, 768.0), |size| {
+            (size * DEFAULT_DISPLAY_SHARE).max(MIN_SIZE)
+        })
+}
+
 impl Default for Window {
     fn default() -> Self {
         Self {
             position: None,
-            size: Size {
-                width: 1024.0,
-                height: 768.0,
-            },
+            size: *DEFAULT_SIZE,
+            is_maximized: false,
+            is_fullscreen: false,
+            is_always_on_top: false,
+            is_mini: false,
+            mini: None,
+            is_borderless: false,
+            display: None,
+            mini_display: None,
         }
     }
 }
 
 impl Window {
-    pub async fn load() -> Result<Window, Error> {
-        let path = path()?;
+    pub async fn load(profile: &Profile) -> Result<Window, Error> {
+        let path = path(profile)?;
         let bytes = fs::read(path).await?;
-        let Window { position, size } = serde_json::from_slice(&bytes)?;
+        let Window {
+            position,
+            size,
+            is_maximized,
+            is_fullscreen,
+            is_always_on_top,
+            is_mini,
+            mini,
+            is_borderless,
+            display,
+            mini_display,
+        } = serde_json::from_slice(&bytes)?;
 
-        let size = size.max(MIN_SIZE);
-        let position = position
-            .filter(|pos| pos.y.is_sign_positive() && pos.x.is_sign_positive())
-            .filter(|pos| is_position_valid(*pos));
+        let screens = screens();
+        let (position, size) =
+            restore_geometry(position, size, display.as_ref(), &screens);
+        let mini = mini.map(|mini| {
+            let (position, size) = restore_geometry(
+                mini.position,
+                mini.size,
+                mini_display.as_ref(),
+                &screens,
+            );
 
-        Ok(Window { position, size })
+            Geometry { position, size }
+        });
+
+        Ok(Window {
+            position,
+            size,
+            is_maximized,
+            is_fullscreen,
+            is_always_on_top,
+            is_mini: is_mini && mini.is_some(),
+            mini,
+            is_borderless,
+            display: None,
+            mini_display: None,
+        })
     }
 
-    pub async fn save(self) -> Result<(), Error> {
-        let path = path()?;
+    pub async fn save(mut self, profile: Profile) -> Result<(), Error> {
+        let path = path(&profile)?;
+
+        self.record_displays();
 
         let bytes = serde_json::to_vec(&self)?;
-        fs::write(path, &bytes).await?;
+
+        // Written aside and moved into place, so a crash mid-write leaves the
+        // previous window state intact
+        let temporary = path.with_extension("json.tmp");
+
+        let mut file = fs::File::create(&temporary).await?;
+        file.write_all(&bytes).await?;
+        file.sync_all().await?;
+
+        fs::rename(&temporary, &path).await?;
 
         Ok(())
     }
+
+    /// Records the displays the window is on, so it can be restored onto
+    /// them.
+    fn record_displays(&mut self) {
+        let screens = screens();
+        let display = |position: Option<Point>| {
+            position
+                .and_then(|position| screen_at(&screens, position))
+                .map(Display::from_screen)
+        };
+
+        self.display = display(self.position);
+        self.mini_display = display(self.mini.and_then(|mini| mini.position));
+    }
+}
+
+/// Restores a saved position and size onto the display they were saved on,
+/// shrinking the window to fit the display's work area if it has become
+/// smaller, e.g. after its scale factor was raised, and moving it out from
+/// under taskbars and docks.
+fn restore_geometry(
+    position: Option<Point>,
+    size: Size,
+    display: Option<&Display>,
+    screens: &[Screen],
+) -> (Option<Point>, Size) {
+    let size = size.max(MIN_SIZE);
+
+    let position = match (position, display) {
+        (Some(position), Some(display)) => {
+            restore_on_display(position, size, display, screens)
+        }
+        // Saved before displays were recorded
+        (position, None) => position
+            .filter(|pos| pos.y.is_sign_positive() && pos.x.is_sign_positive())
+            .filter(|pos| screen_at(screens, *pos).is_some()),
+        (None, Some(_)) => None,
+    };
+
+    let Some((position, screen)) = position.and_then(|position| {
+        screen_at(screens, position).map(|screen| (position, screen))
+    }) else {
+        return (position, size);
+    };
+
+    let work_area = screen.work_area;
+    let size = size.min(work_area.size()).max(MIN_SIZE);
+    let position = Point::new(
+        position
+            .x
+            .min(work_area.x + work_area.width - size.width)
+            .max(work_area.x),
+        position
+            .y
+            .min(work_area.y + work_area.height - size.height)
+            .max(work_area.y),
+    );
+
+    (Some(position), size)
+}
+
+/// Moves `position` onto the display it was saved on, keeping its offset
+/// from the display's origin in case the displays were rearranged. If the
+/// display is gone, the window is centered on the primary display instead.
+fn restore_on_display(
+    position: Point,
+    size: Size,
+    display: &Display,
+    screens: &[Screen],
+) -> Option<Point> {
+    if let Some(screen) = screens
+        .iter()
+        .find(|screen| screen.name == display.name && screen.id == display.id)
+        .or_else(|| screens.iter().find(|screen| screen.name == display.name))
+    {
+        // The offset is logical, so only needs rescaling if the display's
+        // scale factor changed since it was saved
+        let scale = display.scale_factor / screen.scale_factor;
+
+        return Some(Point::new(
+            screen.bounds.x + (position.x - display.origin.x) * scale,
+            screen.bounds.y + (position.y - display.origin.y) * scale,
+        ));
+    }
+
+    let work_area = primary(screens)?.work_area;
+
+    Some(Point::new(
+        work_area.x + ((work_area.width - size.width) / 2.0).max(0.0),
+        work_area.y + ((work_area.height - size.height) / 2.0).max(0.0),
+    ))
+}
+
+/// Factor between the coordinates `display_info` reports and logical ones.
+/// macOS already reports logical coordinates.
+fn logical_scale_factor(info: &DisplayInfo) -> f32 {
+    if cfg!(target_os = "macos") || info.scale_factor <= 0.0 {
+        1.0
+    } else {
+        info.scale_factor
+    }
+}
+
+/// Geometry of popped out buffer windows and auxiliary windows, keyed by
+/// [`Key`], so a window opened again reopens where it was last placed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Popouts(HashMap<String, Window>);
+
+/// Windows besides buffers whose geometry is remembered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Auxiliary {
+    ThemeEditor,
+}
+
+/// A window whose geometry is remembered.
+#[derive(Debug, Clone, Copy)]
+pub enum Key<'a> {
+    Buffer(&'a Buffer),
+    Auxiliary(Auxiliary),
+}
+
+impl Key<'_> {
+    fn to_key(self) -> String {
+        match self {
+            Key::Buffer(buffer) => buffer.key(),
+            // `window:` isn't a prefix of any buffer key
+            Key::Auxiliary(Auxiliary::ThemeEditor) => {
+                "window:theme-editor".to_string()
+            }
+        }
+    }
 }
 
-fn path() -> Result<PathBuf, Error> {
+impl<'a> From<&'a Buffer> for Key<'a> {
+    fn from(buffer: &'a Buffer) -> Self {
+        Key::Buffer(buffer)
+    }
+}
+
+impl From<Auxiliary> for Key<'_> {
+    fn from(auxiliary: Auxiliary) -> Self {
+        Key::Auxiliary(auxiliary)
+    }
+}
+
+impl Popouts {
+    pub fn get<'a>(&self, key: impl Into<Key<'a>>) -> Option<Window> {
+        let window = self.0.get(&key.into().to_key())?.clone();
+        let (position, size) = restore_geometry(
+            window.position,
+            window.size,
+            window.display.as_ref(),
+            &screens(),
+        );
+
+        Some(Window {
+            position,
+            size,
+            ..window
+        })
+    }
+
+    /// Records the displays popped out windows are on, see
+    /// [`Window::save`].
+    pub fn record_displays(&mut self) {
+        self.0.values_mut().for_each(Window::record_displays);
+    }
+
+    pub fn moved<'a>(&mut self, key: impl Into<Key<'a>>, position: Point) {
+        self.0.entry(key.into().to_key()).or_default().position =
+            Some(position);
+    }
+
+    pub fn resized<'a>(&mut self, key: impl Into<Key<'a>>, size: Size) {
+        self.0.entry(key.into().to_key()).or_default().size = size;
+    }
+
+    pub fn set_always_on_top<'a>(
+        &mut self,
+        key: impl Into<Key<'a>>,
+        is_always_on_top: bool,
+    ) {
+        self.0
+            .entry(key.into().to_key())
+            .or_default()
+            .is_always_on_top = is_always_on_top;
+    }
+}
+
+fn path(profile: &Profile) -> Result<PathBuf, Error> {
     let parent = environment::data_dir();
 
     if !parent.exists() {
         std::fs::create_dir_all(&parent)?;
     }
 
-    Ok(parent.join("window.json"))
-}
-
-/// Check if a window position is valid (within visible screen bounds)
-fn is_position_valid(position: Point) -> bool {
-    display_info::DisplayInfo::from_point(position.x as i32, position.y as i32)
-        .is_ok()
+    Ok(parent.join(profile.file_name("window.json")))
 }
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -157,3 +505,194 @@ mod serde_size {
         .serialize(serializer)
     }
 }
+
+mod serde_point {
+    use serde::{Deserializer, Serializer};
+
+    use super::*;
+
+    #[derive(Deserialize, Serialize)]
+    struct SerdePoint {
+        x: f32,
+        y: f32,
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Point, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let SerdePoint { x, y } = SerdePoint::deserialize(deserializer)?;
+
+        Ok(Point { x, y })
+    }
+
+    pub fn serialize<S: Serializer>(
+        point: &Point,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        SerdePoint {
+            x: point.x,
+            y: point.y,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn screen(name: &str, bounds: Rectangle, work_area: Rectangle) -> Screen {
+        Screen {
+            id: 1,
+            name: name.to_string(),
+            scale_factor: 1.0,
+            is_primary: name == "primary",
+            bounds,
+            work_area,
+        }
+    }
+
+    fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Rectangle {
+        Rectangle::new(Point::new(x, y), Size::new(width, height))
+    }
+
+    fn display(name: &str, origin: Point) -> Display {
+        Display {
+            id: 1,
+            name: name.to_string(),
+            scale_factor: 1.0,
+            origin,
+        }
+    }
+
+    #[test]
+    fn default_size_follows_work_area() {
+        // Taskbar along the bottom
+        let laptop = screen(
+            "primary",
+            rectangle(0.0, 0.0, 1280.0, 1040.0),
+            rectangle(0.0, 0.0, 1280.0, 1000.0),
+        );
+        assert_eq!(default_size(Some(&laptop)), Size::new(768.0, 600.0));
+
+        let tiny = screen(
+            "primary",
+            rectangle(0.0, 0.0, 640.0, 360.0),
+            rectangle(0.0, 0.0, 640.0, 360.0),
+        );
+        assert_eq!(default_size(Some(&tiny)), MIN_SIZE);
+
+        assert_eq!(default_size(None), Size::new(1024.0, 768.0));
+    }
+
+    #[test]
+    fn restored_window_fits_work_area() {
+        // Dock along the bottom, menu bar along the top
+        let screens = [screen(
+            "primary",
+            rectangle(0.0, 0.0, 1440.0, 900.0),
+            rectangle(0.0, 25.0, 1440.0, 795.0),
+        )];
+        let display = display("primary", Point::ORIGIN);
+
+        // Moved out from under the dock
+        let (position, size) = restore_geometry(
+            Some(Point::new(100.0, 500.0)),
+            Size::new(800.0, 600.0),
+            Some(&display),
+            &screens,
+        );
+        assert_eq!(position, Some(Point::new(100.0, 220.0)));
+        assert_eq!(size, Size::new(800.0, 600.0));
+
+        // Shrunk to the work area and moved below the menu bar
+        let (position, size) = restore_geometry(
+            Some(Point::new(0.0, 0.0)),
+            Size::new(1600.0, 1000.0),
+            Some(&display),
+            &screens,
+        );
+        assert_eq!(position, Some(Point::new(0.0, 25.0)));
+        assert_eq!(size, Size::new(1440.0, 795.0));
+    }
+
+    #[test]
+    fn restored_window_follows_its_display() {
+        // The secondary display was moved from the right of the primary one
+        // to its left
+        let screens = [
+            screen(
+                "primary",
+                rectangle(0.0, 0.0, 1920.0, 1080.0),
+                rectangle(0.0, 0.0, 1920.0, 1040.0),
+            ),
+            screen(
+                "secondary",
+                rectangle(-1280.0, 0.0, 1280.0, 1024.0),
+                rectangle(-1280.0, 0.0, 1280.0, 1024.0),
+            ),
+        ];
+
+        let (position, _) = restore_geometry(
+            Some(Point::new(2020.0, 100.0)),
+            Size::new(800.0, 600.0),
+            Some(&display("secondary", Point::new(1920.0, 0.0))),
+            &screens,
+        );
+        assert_eq!(position, Some(Point::new(-1180.0, 100.0)));
+
+        // Centered in the primary display's work area once it's gone
+        let (position, _) = restore_geometry(
+            Some(Point::new(2020.0, 100.0)),
+            Size::new(800.0, 600.0),
+            Some(&display("unplugged", Point::new(1920.0, 0.0))),
+            &screens,
+        );
+        assert_eq!(position, Some(Point::new(560.0, 220.0)));
+    }
+
+    #[test]
+    fn restored_window_rescales_offset() {
+        let mut screens = [screen(
+            "primary",
+            rectangle(0.0, 0.0, 1280.0, 720.0),
+            rectangle(0.0, 0.0, 1280.0, 720.0),
+        )];
+        // Saved at a scale factor of 1, now 2
+        screens[0].scale_factor = 2.0;
+
+        let (position, _) = restore_geometry(
+            Some(Point::new(400.0, 200.0)),
+            Size::new(426.0, 240.0),
+            Some(&display("primary", Point::ORIGIN)),
+            &screens,
+        );
+        assert_eq!(position, Some(Point::new(200.0, 100.0)));
+    }
+
+    #[test]
+    fn legacy_position_must_be_on_a_display() {
+        let screens = [screen(
+            "primary",
+            rectangle(0.0, 0.0, 1920.0, 1080.0),
+            rectangle(0.0, 0.0, 1920.0, 1080.0),
+        )];
+
+        let (position, _) = restore_geometry(
+            Some(Point::new(3000.0, 100.0)),
+            Size::new(800.0, 600.0),
+            None,
+            &screens,
+        );
+        assert_eq!(position, None);
+
+        let (position, _) = restore_geometry(
+            Some(Point::new(100.0, 100.0)),
+            Size::new(800.0, 600.0),
+            None,
+            &screens,
+        );
+        assert_eq!(position, Some(Point::new(100.0, 100.0)));
+    }
+}
@@ -0,0 +1,227 @@
+//! Parts of the displays which aren't covered by taskbars, docks or panels,
+//! in the coordinates `display_info` reports.
+
+use display_info::DisplayInfo;
+use iced_core::{Point, Rectangle, Size};
+
+/// Work area of each of `displays`, `None` where it can't be queried.
+pub fn get(displays: &[DisplayInfo]) -> Vec<Option<Rectangle>> {
+    platform::get(displays)
+}
+
+pub fn bounds(info: &DisplayInfo) -> Rectangle {
+    Rectangle::new(
+        Point::new(info.x as f32, info.y as f32),
+        Size::new(info.width as f32, info.height as f32),
+    )
+}
+
+/// Maps `work`, the part of `monitor` left by the system, onto the bounds of
+/// the display, since the platform may not scale them like `display_info`.
+#[cfg_attr(
+    not(any(target_os = "windows", target_os = "macos")),
+    allow(dead_code)
+)]
+fn within(
+    info: &DisplayInfo,
+    monitor: Rectangle,
+    work: Rectangle,
+) -> Option<Rectangle> {
+    if monitor.width <= 0.0 || monitor.height <= 0.0 {
+        return None;
+    }
+
+    let bounds = bounds(info);
+    let scale_x = bounds.width / monitor.width;
+    let scale_y = bounds.height / monitor.height;
+
+    Some(Rectangle {
+        x: bounds.x + (work.x - monitor.x) * scale_x,
+        y: bounds.y + (work.y - monitor.y) * scale_y,
+        width: work.width * scale_x,
+        height: work.height * scale_y,
+    })
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::RECT;
+    use windows::Win32::Graphics::Gdi::{GetMonitorInfoW, MONITORINFO};
+
+    use super::*;
+
+    pub fn get(displays: &[DisplayInfo]) -> Vec<Option<Rectangle>> {
+        displays.iter().map(work_area).collect()
+    }
+
+    fn work_area(info: &DisplayInfo) -> Option<Rectangle> {
+        let mut monitor_info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+
+        if !unsafe { GetMonitorInfoW(info.raw_handle, &mut monitor_info) }
+            .as_bool()
+        {
+            return None;
+        }
+
+        within(
+            info,
+            rectangle(monitor_info.rcMonitor),
+            rectangle(monitor_info.rcWork),
+        )
+    }
+
+    fn rectangle(rect: RECT) -> Rectangle {
+        Rectangle::new(
+            Point::new(rect.left as f32, rect.top as f32),
+            Size::new(
+                (rect.right - rect.left) as f32,
+                (rect.bottom - rect.top) as f32,
+            ),
+        )
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::MainThreadMarker;
+    use objc2_app_kit::NSScreen;
+    use objc2_foundation::{NSNumber, NSRect, NSString};
+
+    use super::*;
+
+    pub fn get(displays: &[DisplayInfo]) -> Vec<Option<Rectangle>> {
+        // AppKit can only be queried from the main thread
+        let Some(main_thread) = MainThreadMarker::new() else {
+            return vec![None; displays.len()];
+        };
+
+        let screens = NSScreen::screens(main_thread);
+        let screen_number = NSString::from_str("NSScreenNumber");
+
+        displays
+            .iter()
+            .map(|info| {
+                let screen = screens.iter().find(|screen| {
+                    screen
+                        .deviceDescription()
+                        .objectForKey(&screen_number)
+                        .and_then(|number| number.downcast::<NSNumber>().ok())
+                        .is_some_and(|number| {
+                            number.unsignedIntValue() == info.raw_handle
+                        })
+                })?;
+
+                within(
+                    info,
+                    flipped(screen.frame()),
+                    flipped(screen.visibleFrame()),
+                )
+            })
+            .collect()
+    }
+
+    /// `rect` with its origin at the top left, AppKit puts it at the bottom
+    /// left.
+    fn flipped(rect: NSRect) -> Rectangle {
+        Rectangle::new(
+            Point::new(
+                rect.origin.x as f32,
+                -(rect.origin.y + rect.size.height) as f32,
+            ),
+            Size::new(rect.size.width as f32, rect.size.height as f32),
+        )
+    }
+}
+
+#[cfg(all(target_family = "unix", not(target_os = "macos")))]
+mod platform {
+    use xcb::Xid;
+    use xcb::x;
+
+    use super::*;
+
+    pub fn get(displays: &[DisplayInfo]) -> Vec<Option<Rectangle>> {
+        let work_area = desktop_work_area();
+
+        displays
+            .iter()
+            .map(|info| {
+                work_area
+                    .and_then(|work_area| bounds(info).intersection(&work_area))
+            })
+            .collect()
+    }
+
+    /// `_NET_WORKAREA` of the current desktop, which X11 window managers set
+    /// to the part of the screen their panels leave. Wayland compositors
+    /// don't expose it.
+    fn desktop_work_area() -> Option<Rectangle> {
+        if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+            return None;
+        }
+
+        let (connection, screen) = xcb::Connection::connect(None).ok()?;
+        let root = connection
+            .get_setup()
+            .roots()
+            .nth(usize::try_from(screen).ok()?)?
+            .root();
+
+        let cardinals = |name: &str| -> Option<Vec<u32>> {
+            let atom = connection
+                .wait_for_reply(connection.send_request(&x::InternAtom {
+                    only_if_exists: true,
+                    name: name.as_bytes(),
+                }))
+                .ok()?
+                .atom();
+
+            if atom.is_none() {
+                return None;
+            }
+
+            let reply = connection
+                .wait_for_reply(connection.send_request(&x::GetProperty {
+                    delete: false,
+                    window: root,
+                    property: atom,
+                    r#type: x::ATOM_CARDINAL,
+                    long_offset: 0,
+                    long_length: 1024,
+                }))
+                .ok()?;
+
+            (reply.format() == 32).then(|| reply.value::<u32>().to_vec())
+        };
+
+        let desktop = cardinals("_NET_CURRENT_DESKTOP")
+            .and_then(|desktop| desktop.first().copied())
+            .unwrap_or_default();
+        let work_areas = cardinals("_NET_WORKAREA")?;
+        let work_area = work_areas
+            .chunks_exact(4)
+            .nth(desktop as usize)
+            .or_else(|| work_areas.chunks_exact(4).next())?;
+
+        Some(Rectangle::new(
+            Point::new(work_area[0] as f32, work_area[1] as f32),
+            Size::new(work_area[2] as f32, work_area[3] as f32),
+        ))
+    }
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    all(target_family = "unix", not(target_os = "macos"))
+)))]
+mod platform {
+    use super::*;
+
+    pub fn get(displays: &[DisplayInfo]) -> Vec<Option<Rectangle>> {
+        vec![None; displays.len()]
+    }
+}
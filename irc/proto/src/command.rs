@@ -116,6 +116,8 @@ pub enum Command {
     MARKREAD(String, Option<String>),
     /// <subcommand> [<targets>]
     MONITOR(String, Option<String>),
+    /// <old_channel> <new_channel> [<reason>]
+    RENAME(String, String, Option<String>),
     /// <realname>
     SETNAME(String),
     /// <msgtarget>
@@ -234,6 +236,7 @@ impl Command {
             "KNOCK" if len > 0 => KNOCK(req!(), opt!()),
             "MARKREAD" if len > 0 => MARKREAD(req!(), opt!()),
             "MONITOR" if len > 0 => MONITOR(req!(), opt!()),
+            "RENAME" if len > 1 => RENAME(req!(), req!(), opt!()),
             "SETNAME" if len > 0 => SETNAME(req!()),
             "TAGMSG" if len > 0 => TAGMSG(req!()),
             "USERIP" if len > 0 => USERIP(req!()),
@@ -339,6 +342,9 @@ impl Command {
             Command::KNOCK(a, b) => std::iter::once(a).chain(b).collect(),
             Command::MARKREAD(a, b) => std::iter::once(a).chain(b).collect(),
             Command::MONITOR(a, b) => std::iter::once(a).chain(b).collect(),
+            Command::RENAME(a, b, c) => {
+                std::iter::once(a).chain(Some(b)).chain(c).collect()
+            }
             Command::SETNAME(a) => vec![a],
             Command::TAGMSG(a) => vec![a],
             Command::USERIP(a) => vec![a],
@@ -419,6 +425,7 @@ impl Command {
             KNOCK(_, _) => "KNOCK".into(),
             MARKREAD(_, _) => "MARKREAD".into(),
             MONITOR(_, _) => "MONITOR".into(),
+            RENAME(_, _, _) => "RENAME".into(),
             SETNAME(_) => "SETNAME".into(),
             TAGMSG(_) => "TAGMSG".into(),
             USERIP(_) => "USERIP".into(),
@@ -443,6 +450,9 @@ pub enum Numeric {
     RPL_MYINFO = 4,
     RPL_ISUPPORT = 5,
     RPL_BOUNCE = 10,
+    RPL_MAP = 15,
+    RPL_MAPEND = 17,
+    RPL_STATSLINKINFO = 211,
     RPL_STATSCOMMANDS = 212,
     RPL_ENDOFSTATS = 219,
     RPL_STATSUPTIME = 242,
@@ -463,6 +473,7 @@ pub enum Numeric {
     RPL_NONE = 300,
     RPL_AWAY = 301,
     RPL_USERHOST = 302,
+    RPL_ISON = 303,
     RPL_UNAWAY = 305,
     RPL_NOWAWAY = 306,
     RPL_WHOREPLY = 352,
@@ -512,6 +523,7 @@ pub enum Numeric {
     RPL_YOUREOPER = 381,
     RPL_REHASHING = 382,
     RPL_TIME = 391,
+    RPL_HOSTHIDDEN = 396,
     ERR_UNKNOWNERROR = 400,
     ERR_NOSUCHNICK = 401,
     ERR_NOSUCHSERVER = 402,
@@ -565,6 +577,8 @@ pub enum Numeric {
     RPL_MONLIST = 732,
     RPL_ENDOFMONLIST = 733,
     ERR_MONLISTFULL = 734,
+    RPL_RSACHALLENGE2 = 740,
+    RPL_ENDOFRSACHALLENGE2 = 741,
     RPL_LOGGEDIN = 900,
     RPL_LOGGEDOUT = 901,
     ERR_NICKLOCKED = 902,
@@ -589,6 +603,9 @@ impl TryFrom<u16> for Numeric {
             4 => RPL_MYINFO,
             5 => RPL_ISUPPORT,
             10 => RPL_BOUNCE,
+            15 => RPL_MAP,
+            17 => RPL_MAPEND,
+            211 => RPL_STATSLINKINFO,
             212 => RPL_STATSCOMMANDS,
             219 => RPL_ENDOFSTATS,
             242 => RPL_STATSUPTIME,
@@ -609,6 +626,7 @@ impl TryFrom<u16> for Numeric {
             300 => RPL_NONE,
             301 => RPL_AWAY,
             302 => RPL_USERHOST,
+            303 => RPL_ISON,
             305 => RPL_UNAWAY,
             306 => RPL_NOWAWAY,
             352 => RPL_WHOREPLY,
@@ -658,6 +676,7 @@ impl TryFrom<u16> for Numeric {
             381 => RPL_YOUREOPER,
             382 => RPL_REHASHING,
             391 => RPL_TIME,
+            396 => RPL_HOSTHIDDEN,
             400 => ERR_UNKNOWNERROR,
             401 => ERR_NOSUCHNICK,
             402 => ERR_NOSUCHSERVER,
@@ -711,6 +730,8 @@ impl TryFrom<u16> for Numeric {
             732 => RPL_MONLIST,
             733 => RPL_ENDOFMONLIST,
             734 => ERR_MONLISTFULL,
+            740 => RPL_RSACHALLENGE2,
+            741 => RPL_ENDOFRSACHALLENGE2,
             900 => RPL_LOGGEDIN,
             901 => RPL_LOGGEDOUT,
             902 => ERR_NICKLOCKED,
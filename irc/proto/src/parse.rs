@@ -339,8 +339,8 @@ mod test {
                             "5555:5555:0:55:5555:5555:5555:5555".into(),
                         ),
                     })),
-                    command: Command::Unknown(
-                        "396".to_string(),
+                    command: Command::Numeric(
+                        RPL_HOSTHIDDEN,
                         vec![
                             "test".to_string(),
                             "user/test".to_string(),
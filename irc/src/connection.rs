@@ -13,6 +13,7 @@ use tokio_util::codec::Framed;
 
 pub use self::proxy::Proxy;
 
+mod happy_eyeballs;
 mod proxy;
 mod tls;
 
@@ -50,7 +51,7 @@ impl<Codec> Connection<Codec> {
     pub async fn new(config: Config<'_>, codec: Codec) -> Result<Self, Error> {
         let stream = match config.proxy {
             None => IrcStream::Tcp(
-                TcpStream::connect((config.server, config.port)).await?,
+                happy_eyeballs::connect(config.server, config.port).await?,
             ),
             Some(proxy) => proxy.connect(config.server, config.port).await?,
         };
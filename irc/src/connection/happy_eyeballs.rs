@@ -0,0 +1,122 @@
+//! Connects to a host by racing its resolved addresses (Happy Eyeballs,
+//! RFC 8305), so a host with unreachable IPv6 (or IPv4) addresses connects
+//! as fast as one without.
+
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures::StreamExt;
+use futures::stream::FuturesUnordered;
+use tokio::net::{self, TcpStream};
+use tokio::time;
+
+/// Delay before racing the next address while an attempt is in flight
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+pub async fn connect(host: &str, port: u16) -> io::Result<TcpStream> {
+    // Resolution runs off the runtime, it won't block other connections
+    let addresses = interleave(net::lookup_host((host, port)).await?);
+
+    let mut addresses = addresses.into_iter();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+
+    if let Some(address) = addresses.next() {
+        attempts.push(TcpStream::connect(address));
+    }
+
+    loop {
+        let has_next = !addresses.as_slice().is_empty();
+
+        tokio::select! {
+            Some(result) = attempts.next() => match result {
+                Ok(stream) => return Ok(stream),
+                Err(error) => {
+                    last_error = Some(error);
+
+                    // Don't wait out the delay for a failed attempt
+                    if let Some(address) = addresses.next() {
+                        attempts.push(TcpStream::connect(address));
+                    }
+                }
+            },
+            () = time::sleep(CONNECTION_ATTEMPT_DELAY), if has_next => {
+                if let Some(address) = addresses.next() {
+                    attempts.push(TcpStream::connect(address));
+                }
+            }
+            else => break,
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no addresses found for {host}"),
+        )
+    }))
+}
+
+/// Alternates address families, starting with the family of the first
+/// resolved address (the system's preference)
+fn interleave(addresses: impl Iterator<Item = SocketAddr>) -> Vec<SocketAddr> {
+    let mut addresses = addresses.peekable();
+
+    let prefer_ipv6 = addresses.peek().is_some_and(SocketAddr::is_ipv6);
+
+    let (ipv6, ipv4): (Vec<_>, Vec<_>) =
+        addresses.partition(SocketAddr::is_ipv6);
+
+    let (mut first, mut second) = if prefer_ipv6 {
+        (ipv6.into_iter(), ipv4.into_iter())
+    } else {
+        (ipv4.into_iter(), ipv6.into_iter())
+    };
+
+    let mut interleaved = vec![];
+
+    loop {
+        match (first.next(), second.next()) {
+            (None, None) => break,
+            (a, b) => interleaved.extend(a.into_iter().chain(b)),
+        }
+    }
+
+    interleaved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interleave_families() {
+        let addresses = [
+            "[2001:db8::1]:6697",
+            "[2001:db8::2]:6697",
+            "[2001:db8::3]:6697",
+            "192.0.2.1:6697",
+        ]
+        .into_iter()
+        .map(|address| address.parse::<SocketAddr>().unwrap());
+
+        let expected = [
+            "[2001:db8::1]:6697",
+            "192.0.2.1:6697",
+            "[2001:db8::2]:6697",
+            "[2001:db8::3]:6697",
+        ]
+        .into_iter()
+        .map(|address| address.parse::<SocketAddr>().unwrap())
+        .collect::<Vec<_>>();
+
+        assert_eq!(interleave(addresses), expected);
+
+        let addresses = ["192.0.2.1:6697", "[2001:db8::1]:6697"]
+            .into_iter()
+            .map(|address| address.parse::<SocketAddr>().unwrap());
+
+        assert!(interleave(addresses)[0].is_ipv4());
+    }
+}
@@ -5,9 +5,8 @@ use async_http_proxy::{
 };
 use fast_socks5::client::{Config as Socks5Config, Socks5Stream};
 use thiserror::Error;
-use tokio::net::TcpStream;
 
-use super::IrcStream;
+use super::{IrcStream, happy_eyeballs};
 
 #[derive(Debug, Clone)]
 pub enum Proxy {
@@ -82,7 +81,7 @@ pub async fn connect_http(
     username: Option<String>,
     password: Option<String>,
 ) -> Result<IrcStream, Error> {
-    let mut stream = TcpStream::connect((proxy_server, proxy_port)).await?;
+    let mut stream = happy_eyeballs::connect(proxy_server, proxy_port).await?;
     if let Some((username, password)) = username.zip(password) {
         http_connect_tokio_with_basic_auth(
             &mut stream,
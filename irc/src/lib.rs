@@ -1,6 +1,6 @@
 #![allow(clippy::large_enum_variant)]
 
-pub use tokio_util::codec::BytesCodec;
+pub use tokio_util::codec::{BytesCodec, LinesCodec, LinesCodecError};
 
 pub use self::codec::Codec;
 pub use self::connection::Connection;
@@ -1,10 +1,15 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
 pub use data::appearance::theme::{
     Buffer, Button, Buttons, General, ServerMessages, Styles, Text,
     color_to_hex, hex_to_color,
 };
-use data::config;
+use data::{buffer, config, isupport};
+use iced::Color;
 use iced::widget::text::LineHeight;
 
+use super::Mode;
 use crate::widget::combo_box;
 
 pub mod button;
@@ -13,6 +18,7 @@ pub mod container;
 pub mod context_menu;
 pub mod font_style;
 pub mod menu;
+pub mod minimap;
 pub mod pane_grid;
 pub mod progress_bar;
 pub mod rule;
@@ -26,6 +32,62 @@ pub mod text_input;
 pub const TEXT_SIZE: f32 = 13.0;
 pub const ICON_SIZE: f32 = 12.0;
 
+/// Opacity of the window background, like the font it's read from the
+/// configuration on startup since the window has to be created translucent.
+static OPACITY: OnceLock<f32> = OnceLock::new();
+
+pub fn set_opacity(config: Option<&data::Config>) {
+    let _ = OPACITY.set(config.map_or(1.0, |config| config.opacity.into()));
+}
+
+pub fn is_translucent() -> bool {
+    OPACITY.get().is_some_and(|opacity| *opacity < 1.0)
+}
+
+/// `color` with the window opacity applied, for the backgrounds which the
+/// desktop shows through.
+///
+/// Buffers are drawn over the window background, so instead of the
+/// configured opacity both layers get the alpha which adds up to it once
+/// they are stacked.
+pub fn translucent(color: Color) -> Color {
+    let opacity = OPACITY.get().copied().unwrap_or(1.0);
+
+    Color {
+        a: color.a * (1.0 - (1.0 - opacity).sqrt()),
+        ..color
+    }
+}
+
+/// Dims `color` against the buffer background of `theme`.
+///
+/// Dimmed colors are translucent, so on a translucent window they are
+/// blended with the buffer background up front. Otherwise whatever is
+/// behind the window would show through the text.
+pub fn dim(
+    dimmed: config::buffer::Dimmed,
+    color: Color,
+    theme: &Theme,
+) -> Color {
+    let background = theme.styles().buffer.background;
+    let color = dimmed.transform_color(color, background);
+
+    if !is_translucent() {
+        return color;
+    }
+
+    let blend = |foreground: f32, background: f32| {
+        foreground * color.a + background * (1.0 - color.a)
+    };
+
+    Color {
+        r: blend(color.r, background.r),
+        g: blend(color.g, background.g),
+        b: blend(color.b, background.b),
+        a: 1.0,
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Theme {
     Selected(data::Theme),
@@ -78,7 +140,7 @@ impl Default for Theme {
 impl iced::theme::Base for Theme {
     fn base(&self) -> iced::theme::Style {
         iced::theme::Style {
-            background_color: self.styles().general.background,
+            background_color: translucent(self.styles().general.background),
             text_color: self.styles().text.primary.color,
         }
     }
@@ -98,6 +160,46 @@ impl iced::theme::Base for Theme {
 
 impl combo_box::Catalog for Theme {}
 
+/// Themes which can be used in place of the selected theme for the buffers
+/// of a server or target, see [`data::Config::theme`].
+#[derive(Debug, Clone)]
+pub struct Overrides {
+    themes: HashMap<String, Theme>,
+    mode: Mode,
+}
+
+impl Overrides {
+    pub fn new(config: &data::Config, mode: Mode) -> Self {
+        Self {
+            themes: config
+                .appearance
+                .all
+                .iter()
+                .map(|theme| (theme.name.clone(), Theme::from(theme.clone())))
+                .collect(),
+            mode,
+        }
+    }
+
+    /// Follow the OS appearance for overrides with a light and dark theme
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// The theme to use for `buffer`, if it's configured to override the
+    /// selected theme
+    pub fn get(
+        &self,
+        config: &data::Config,
+        buffer: &buffer::Upstream,
+        casemapping: isupport::CaseMap,
+    ) -> Option<&Theme> {
+        let theme = config.theme(buffer, casemapping)?;
+
+        self.themes.get(theme.name(self.mode == Mode::Dark))
+    }
+}
+
 pub fn line_height(config: &config::Font) -> f32 {
     LineHeight::default()
         .to_absolute(
@@ -106,6 +106,17 @@ pub fn primary(theme: &Theme, status: Status, selected: bool) -> Style {
     button(foreground, background, background_hover, status)
 }
 
+/// Entry in a context menu, which looks hovered when focused with the
+/// keyboard.
+pub fn context_menu(theme: &Theme, status: Status, focused: bool) -> Style {
+    let status = match status {
+        Status::Active if focused => Status::Hovered,
+        status => status,
+    };
+
+    primary(theme, status, false)
+}
+
 pub fn secondary(theme: &Theme, status: Status, selected: bool) -> Style {
     let foreground = theme.styles().text.primary.color;
     let button_colors = theme.styles().buttons.secondary;
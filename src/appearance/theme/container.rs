@@ -1,7 +1,7 @@
 use iced::widget::container::{Catalog, Style, StyleFn, transparent};
 use iced::{Background, Border, Color, border};
 
-use super::Theme;
+use super::{Theme, is_translucent, translucent};
 
 impl Catalog for Theme {
     type Class<'a> = StyleFn<'a, Self>;
@@ -19,7 +19,7 @@ pub fn buffer(theme: &Theme, selected: bool) -> Style {
     let buffer = theme.styles().buffer;
 
     Style {
-        background: Some(Background::Color(buffer.background)),
+        background: Some(Background::Color(translucent(buffer.background))),
         border: Border {
             radius: 4.0.into(),
             width: 1.0,
@@ -33,11 +33,35 @@ pub fn buffer(theme: &Theme, selected: bool) -> Style {
     }
 }
 
+/// Border of a pane, its title bar and body draw their own backgrounds
+/// so that neither is stacked over the other.
+pub fn pane(theme: &Theme, selected: bool) -> Style {
+    Style {
+        background: None,
+        ..buffer(theme, selected)
+    }
+}
+
+pub fn pane_body(theme: &Theme) -> Style {
+    Style {
+        background: Some(Background::Color(translucent(
+            theme.styles().buffer.background,
+        ))),
+        border: Border {
+            radius: border::bottom_left(4).bottom_right(4),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
 pub fn buffer_title_bar(theme: &Theme) -> Style {
     let styles = theme.styles().buffer;
 
     Style {
-        background: Some(Background::Color(styles.background_title_bar)),
+        background: Some(Background::Color(translucent(
+            styles.background_title_bar,
+        ))),
         text_color: Some(theme.styles().text.secondary.color),
         border: Border {
             radius: border::top_left(4).top_right(4),
@@ -52,7 +76,9 @@ pub fn buffer_text_input(theme: &Theme) -> Style {
     let styles = theme.styles().buffer;
 
     Style {
-        background: Some(Background::Color(styles.background_text_input)),
+        background: Some(Background::Color(translucent(
+            styles.background_text_input,
+        ))),
         text_color: Some(theme.styles().text.secondary.color),
         border: Border {
             radius: border::bottom_left(4).bottom_right(4),
@@ -80,6 +106,37 @@ pub fn table(theme: &Theme, idx: usize) -> Style {
     }
 }
 
+pub fn message_row(
+    theme: &Theme,
+    alternate: bool,
+    hovered: bool,
+    highlighted: bool,
+) -> Style {
+    let buffer = theme.styles().buffer;
+
+    let background = buffer
+        .hover
+        .filter(|_| hovered)
+        .or(buffer.highlight_background.filter(|_| highlighted))
+        .or(buffer.background_alt.filter(|_| alternate));
+
+    Style {
+        background: background.map(translucent).map(Background::Color),
+        ..Default::default()
+    }
+}
+
+pub fn highlight_accent(theme: &Theme) -> Style {
+    Style {
+        background: theme
+            .styles()
+            .buffer
+            .highlight_border
+            .map(Background::Color),
+        ..Default::default()
+    }
+}
+
 pub fn none(_theme: &Theme) -> Style {
     Style {
         background: None,
@@ -101,8 +158,10 @@ pub fn primary_background_hover(theme: &Theme) -> Style {
 }
 
 pub fn general(theme: &Theme) -> Style {
+    // A translucent window background is already drawn by the window
     Style {
-        background: Some(Background::Color(theme.styles().general.background)),
+        background: (!is_translucent())
+            .then_some(Background::Color(theme.styles().general.background)),
         text_color: Some(theme.styles().text.primary.color),
         ..Default::default()
     }
@@ -149,3 +208,17 @@ pub fn transparent_overlay(theme: &Theme) -> Style {
         ..Default::default()
     }
 }
+
+pub fn color_swatch(theme: &Theme, color: Option<Color>) -> Style {
+    let general = theme.styles().general;
+
+    Style {
+        background: color.map(Background::Color),
+        border: Border {
+            radius: 2.0.into(),
+            width: 1.0,
+            color: general.border,
+        },
+        ..Default::default()
+    }
+}
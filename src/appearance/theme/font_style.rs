@@ -28,6 +28,25 @@ pub fn nickname(theme: &Theme, is_user_offline: bool) -> Option<FontStyle> {
     }
 }
 
+/// Font style of the sender of a message, which differs from [`nickname`]
+/// when the message highlights us and the theme styles those nicknames.
+pub fn message_nickname(
+    theme: &Theme,
+    is_user_offline: bool,
+    is_highlight: bool,
+) -> Option<FontStyle> {
+    if is_highlight && !is_user_offline {
+        theme
+            .styles()
+            .buffer
+            .nickname_highlight
+            .font_style
+            .or_else(|| nickname(theme, false))
+    } else {
+        nickname(theme, is_user_offline)
+    }
+}
+
 pub fn server(
     theme: &Theme,
     server: Option<&message::source::Server>,
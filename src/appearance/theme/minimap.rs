@@ -0,0 +1,15 @@
+use super::Theme;
+use crate::widget::minimap::Style;
+
+pub fn primary(theme: &Theme) -> Style {
+    let styles = theme.styles();
+
+    Style {
+        highlight: styles
+            .general
+            .highlight_indicator
+            .unwrap_or(styles.general.unread_indicator),
+        sent: styles.buffer.nickname.color,
+        search_match: styles.buffer.url.color,
+    }
+}
@@ -111,10 +111,11 @@ pub fn server(
 pub fn nicklist_nickname(theme: &Theme, config: &Config, user: &User) -> Style {
     nickname_style(
         theme,
-        config.buffer.channel.nicklist.color,
+        &config.buffer.channel.nicklist.color,
         user,
         config.buffer.channel.nicklist.away.is_away(user.is_away()),
         false,
+        false,
     )
 }
 
@@ -123,10 +124,11 @@ pub fn nickname(
     config: &Config,
     user: &User,
     is_user_offline: bool,
+    is_highlight: bool,
 ) -> Style {
     nickname_style(
         theme,
-        config.buffer.channel.message.nickname_color,
+        &config.buffer.channel.message.nickname_color,
         user,
         config
             .buffer
@@ -134,32 +136,38 @@ pub fn nickname(
             .away
             .is_away(user.is_away() || is_user_offline),
         config.buffer.nickname.offline.is_offline(is_user_offline),
+        is_highlight,
     )
 }
 
 pub fn topic_nickname(theme: &Theme, config: &Config, user: &User) -> Style {
     nickname_style(
         theme,
-        config.buffer.channel.message.nickname_color,
+        &config.buffer.channel.message.nickname_color,
         user,
         None,
         false,
+        false,
     )
 }
 
 fn nickname_style(
     theme: &Theme,
-    kind: data::buffer::Color,
+    kind: &data::buffer::Color,
     user: &User,
     is_away: Option<buffer::Away>,
     is_offline: bool,
+    is_highlight: bool,
 ) -> Style {
-    let seed = match kind {
-        data::buffer::Color::Solid => None,
-        data::buffer::Color::Unique => Some(user.seed()),
-    };
-
-    let color = text::nickname(theme, seed, is_away, is_offline).color;
+    let color = text::nickname(
+        theme,
+        kind,
+        Some(user.seed()),
+        is_away,
+        is_offline,
+        is_highlight,
+    )
+    .color;
 
     Style {
         color,
@@ -167,6 +175,30 @@ fn nickname_style(
     }
 }
 
+/// Operator server notices, colored by severity. Informational notices
+/// are styled like any other server message.
+pub fn snotice(
+    theme: &Theme,
+    server: Option<&message::source::Server>,
+    severity: message::SnoticeSeverity,
+) -> Style {
+    let color = match severity {
+        message::SnoticeSeverity::Info => return self::server(theme, server),
+        message::SnoticeSeverity::Warning => theme
+            .styles()
+            .text
+            .warning
+            .color
+            .unwrap_or(theme.styles().general.unread_indicator),
+        message::SnoticeSeverity::Alert => theme.styles().text.error.color,
+    };
+
+    Style {
+        color: Some(color),
+        selection_color: theme.styles().buffer.selection,
+    }
+}
+
 pub fn status(theme: &Theme, status: message::source::Status) -> Style {
     let color = match status {
         message::source::Status::Success => text::success(theme).color,
@@ -220,7 +252,8 @@ impl selectable_rich_text::Link for message::Link {
             data::message::Link::Url(_) => true,
             data::message::Link::User(_)
             | data::message::Link::Channel(_)
-            | data::message::Link::GoToMessage(..) => false,
+            | data::message::Link::GoToMessage(..)
+            | data::message::Link::Message => false,
         }
     }
 }
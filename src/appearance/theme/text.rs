@@ -1,4 +1,4 @@
-use data::appearance::theme::randomize_color;
+use data::appearance::theme::nickname_color;
 use data::config::buffer;
 use iced::widget::text::{Catalog, Style, StyleFn};
 
@@ -98,13 +98,15 @@ pub fn url(theme: &Theme) -> Style {
 
 pub fn nickname<T: AsRef<str>>(
     theme: &Theme,
+    kind: &data::buffer::Color,
     seed: Option<T>,
     is_away: Option<buffer::Away>,
     is_offline: bool,
+    is_highlight: bool,
 ) -> Style {
     let calculate_alpha_color = |color| {
         if let Some(buffer::Away::Dimmed(dimmed)) = is_away {
-            dimmed.transform_color(color, theme.styles().buffer.background)
+            super::dim(dimmed, color, theme)
         } else {
             color
         }
@@ -119,16 +121,30 @@ pub fn nickname<T: AsRef<str>>(
         };
     }
 
+    // Likewise for the sender of a message which highlights us
+    if is_highlight
+        && let Some(color) = theme.styles().buffer.nickname_highlight.color
+    {
+        return Style {
+            color: Some(calculate_alpha_color(color)),
+        };
+    }
+
     let nickname = theme.styles().buffer.nickname;
 
-    // If we have a seed we randomize the color based on the seed before adding any alpha value.
-    let color = match seed {
-        Some(seed) => calculate_alpha_color(randomize_color(
-            nickname.color,
-            seed.as_ref(),
-        )),
-        None => calculate_alpha_color(nickname.color),
-    };
+    // If we have a seed we pick the color based on the seed before adding any alpha value.
+    let color = seed
+        .and_then(|seed| {
+            nickname_color(
+                kind,
+                nickname.color,
+                theme.styles().buffer.background,
+                seed.as_ref(),
+            )
+        })
+        .unwrap_or(nickname.color);
+
+    let color = calculate_alpha_color(color);
 
     Style { color: Some(color) }
 }
@@ -4,30 +4,39 @@ pub use data::buffer::{Autocomplete, Internal, Settings, Upstream};
 use data::dashboard::BufferAction;
 use data::target::{self, Target};
 use data::user::Nick;
-use data::{Config, buffer, file_transfer, history, message, preview};
+use data::{
+    Config, buffer, file_transfer, history, isupport, message, preview, script,
+};
 use iced::{Size, Task};
 
+pub use self::admin::Admin;
 pub use self::channel::Channel;
+pub use self::dcc_chat::DccChat;
 pub use self::file_transfers::FileTransfers;
 pub use self::highlights::Highlights;
+pub use self::input_view::send_inputs;
 pub use self::logs::Logs;
 pub use self::query::Query;
+pub use self::search::Search;
 pub use self::server::Server;
 use crate::Theme;
 use crate::screen::dashboard::sidebar;
 use crate::widget::Element;
 
+pub mod admin;
 pub mod channel;
+pub mod context_menu;
+pub mod dcc_chat;
 pub mod empty;
 pub mod file_transfers;
 pub mod highlights;
 mod input_view;
 pub mod logs;
-mod message_view;
+pub mod message_view;
 pub mod query;
-mod scroll_view;
+pub mod scroll_view;
+pub mod search;
 pub mod server;
-pub mod context_menu;
 
 #[derive(Clone, Debug)]
 pub enum Buffer {
@@ -38,6 +47,9 @@ pub enum Buffer {
     FileTransfers(FileTransfers),
     Logs(Logs),
     Highlights(Highlights),
+    Search(Search),
+    Admin(Admin),
+    DccChat(DccChat),
 }
 
 #[derive(Debug, Clone)]
@@ -48,13 +60,18 @@ pub enum Message {
     FileTransfers(file_transfers::Message),
     Logs(logs::Message),
     Highlights(highlights::Message),
+    Search(search::Message),
+    Admin(admin::Message),
+    DccChat(dcc_chat::Message),
 }
 
 pub enum Event {
     ContextMenu(context_menu::Event),
     OpenBuffers(Vec<(Target, BufferAction)>),
     LeaveBuffers(Vec<Target>, Option<String>),
-    GoToMessage(data::Server, target::Channel, message::Hash),
+    /// Go to a message, marking the matches of a search query if it was
+    /// found by one
+    GoToMessage(data::Buffer, message::Hash, Option<data::search::Query>),
     History(Task<history::manager::Message>),
     RequestOlderChatHistory,
     PreviewChanged,
@@ -62,6 +79,8 @@ pub enum Event {
     MarkAsRead(history::Kind),
     OpenUrl(String),
     ImagePreview(PathBuf, url::Url),
+    /// Prompt for operator credentials, with the name if it's known
+    OperPrompt(Option<String>),
 }
 
 impl Buffer {
@@ -75,6 +94,11 @@ impl Buffer {
                 buffer::Upstream::Server(server) => {
                     Self::Server(Server::new(server, pane_size, config))
                 }
+                buffer::Upstream::ServerCategory(server, category) => {
+                    Self::Server(Server::category(
+                        server, category, pane_size, config,
+                    ))
+                }
                 buffer::Upstream::Channel(server, channel) => Self::Channel(
                     Channel::new(server, channel, pane_size, config),
                 ),
@@ -92,7 +116,10 @@ impl Buffer {
                 buffer::Internal::Highlights => {
                     Self::Highlights(Highlights::new(pane_size, config))
                 }
+                buffer::Internal::Search => Self::Search(Search::new()),
+                buffer::Internal::Admin => Self::Admin(Admin::new()),
             },
+            data::Buffer::DccChat(id) => Self::DccChat(DccChat::new(id)),
         }
     }
     pub fn empty() -> Self {
@@ -107,7 +134,10 @@ impl Buffer {
             Buffer::Empty
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => None,
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => None,
         }
     }
 
@@ -116,10 +146,13 @@ impl Buffer {
             Buffer::Empty
             | Buffer::Channel(_)
             | Buffer::Server(_)
-            | Buffer::Query(_) => None,
+            | Buffer::Query(_)
+            | Buffer::DccChat(_) => None,
             Buffer::FileTransfers(_) => Some(buffer::Internal::FileTransfers),
             Buffer::Logs(_) => Some(buffer::Internal::Logs),
             Buffer::Highlights(_) => Some(buffer::Internal::Highlights),
+            Buffer::Search(_) => Some(buffer::Internal::Search),
+            Buffer::Admin(_) => Some(buffer::Internal::Admin),
         }
     }
 
@@ -144,6 +177,13 @@ impl Buffer {
             Buffer::Highlights(_) => {
                 Some(data::Buffer::Internal(buffer::Internal::Highlights))
             }
+            Buffer::Search(_) => {
+                Some(data::Buffer::Internal(buffer::Internal::Search))
+            }
+            Buffer::Admin(_) => {
+                Some(data::Buffer::Internal(buffer::Internal::Admin))
+            }
+            Buffer::DccChat(state) => Some(data::Buffer::DccChat(state.id)),
         }
     }
 
@@ -155,7 +195,10 @@ impl Buffer {
             Buffer::Empty
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => None,
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => None,
         }
     }
 
@@ -169,7 +212,10 @@ impl Buffer {
             | Buffer::Server(_)
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => None,
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => None,
         }
     }
 
@@ -179,12 +225,14 @@ impl Buffer {
         clients: &mut data::client::Map,
         history: &mut history::Manager,
         file_transfers: &mut file_transfer::Manager,
+        dcc_chats: &mut data::dcc_chat::Manager,
+        scripts: &script::Manager,
         config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         match (self, message) {
             (Buffer::Channel(state), Message::Channel(message)) => {
                 let (command, event) =
-                    state.update(message, clients, history, config);
+                    state.update(message, clients, history, scripts, config);
 
                 let event = event.map(|event| match event {
                     channel::Event::ContextMenu(event) => {
@@ -209,13 +257,14 @@ impl Buffer {
                     channel::Event::ImagePreview(path, url) => {
                         Event::ImagePreview(path, url)
                     }
+                    channel::Event::OperPrompt(name) => Event::OperPrompt(name),
                 });
 
                 (command.map(Message::Channel), event)
             }
             (Buffer::Server(state), Message::Server(message)) => {
                 let (command, event) =
-                    state.update(message, clients, history, config);
+                    state.update(message, clients, history, scripts, config);
 
                 let event = event.map(|event| match event {
                     server::Event::ContextMenu(event) => {
@@ -233,13 +282,14 @@ impl Buffer {
                     server::Event::ImagePreview(path, url) => {
                         Event::ImagePreview(path, url)
                     }
+                    server::Event::OperPrompt(name) => Event::OperPrompt(name),
                 });
 
                 (command.map(Message::Server), event)
             }
             (Buffer::Query(state), Message::Query(message)) => {
                 let (command, event) =
-                    state.update(message, clients, history, config);
+                    state.update(message, clients, history, scripts, config);
 
                 let event = event.map(|event| match event {
                     query::Event::ContextMenu(event) => {
@@ -264,6 +314,7 @@ impl Buffer {
                     query::Event::ImagePreview(path, url) => {
                         Event::ImagePreview(path, url)
                     }
+                    query::Event::OperPrompt(name) => Event::OperPrompt(name),
                 });
 
                 (command.map(Message::Query), event)
@@ -311,7 +362,13 @@ impl Buffer {
                         server,
                         channel,
                         message,
-                    ) => Event::GoToMessage(server, channel, message),
+                    ) => Event::GoToMessage(
+                        data::Buffer::Upstream(buffer::Upstream::Channel(
+                            server, channel,
+                        )),
+                        message,
+                        None,
+                    ),
                     highlights::Event::History(task) => Event::History(task),
                     highlights::Event::OpenUrl(url) => Event::OpenUrl(url),
                     highlights::Event::ImagePreview(path, url) => {
@@ -321,6 +378,27 @@ impl Buffer {
 
                 (command.map(Message::Highlights), event)
             }
+            (Buffer::Search(state), Message::Search(message)) => {
+                let (command, event) = state.update(message, history, clients);
+
+                let event = event.map(|event| match event {
+                    search::Event::GoToMessage(buffer, message, query) => {
+                        Event::GoToMessage(buffer, message, query)
+                    }
+                });
+
+                (command.map(Message::Search), event)
+            }
+            (Buffer::Admin(state), Message::Admin(message)) => {
+                state.update(message, clients);
+
+                (Task::none(), None)
+            }
+            (Buffer::DccChat(state), Message::DccChat(message)) => {
+                let command = state.update(message, dcc_chats);
+
+                (command.map(Message::DccChat), None)
+            }
             _ => (Task::none(), None),
         }
     }
@@ -329,19 +407,22 @@ impl Buffer {
         &'a self,
         clients: &'a data::client::Map,
         file_transfers: &'a file_transfer::Manager,
+        dcc_chats: &'a data::dcc_chat::Manager,
         history: &'a history::Manager,
         previews: &'a preview::Collection,
         settings: Option<&'a buffer::Settings>,
+        density: buffer::Density,
         config: &'a Config,
         theme: &'a Theme,
         is_focused: bool,
         sidebar: &'a sidebar::Sidebar,
+        is_mini: bool,
     ) -> Element<'a, Message> {
         match self {
             Buffer::Empty => empty::view(config, sidebar),
             Buffer::Channel(state) => channel::view(
-                state, clients, history, previews, settings, config, theme,
-                is_focused,
+                state, clients, history, previews, settings, density, config,
+                theme, is_focused, is_mini,
             )
             .map(Message::Channel),
             Buffer::Server(state) => {
@@ -349,7 +430,8 @@ impl Buffer {
                     .map(Message::Server)
             }
             Buffer::Query(state) => query::view(
-                state, clients, history, previews, config, theme, is_focused,
+                state, clients, history, previews, density, config, theme,
+                is_focused,
             )
             .map(Message::Query),
             Buffer::FileTransfers(state) => {
@@ -363,6 +445,16 @@ impl Buffer {
                 highlights::view(state, clients, history, config, theme)
                     .map(Message::Highlights)
             }
+            Buffer::Search(state) => {
+                search::view(state, config, theme).map(Message::Search)
+            }
+            Buffer::Admin(state) => {
+                admin::view(state, clients, theme).map(Message::Admin)
+            }
+            Buffer::DccChat(state) => {
+                dcc_chat::view(state, dcc_chats, clients, config, theme)
+                    .map(Message::DccChat)
+            }
         }
     }
 
@@ -396,10 +488,13 @@ impl Buffer {
             Buffer::Empty
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => Task::none(),
+            | Buffer::Highlights(_)
+            | Buffer::Admin(_) => Task::none(),
             Buffer::Channel(channel) => channel.focus().map(Message::Channel),
             Buffer::Server(server) => server.focus().map(Message::Server),
             Buffer::Query(query) => query.focus().map(Message::Query),
+            Buffer::Search(search) => search.focus().map(Message::Search),
+            Buffer::DccChat(chat) => chat.focus().map(Message::DccChat),
         }
     }
 
@@ -408,7 +503,10 @@ impl Buffer {
             Buffer::Empty
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => {}
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => {}
             Buffer::Channel(channel) => channel.reset(),
             Buffer::Server(server) => server.reset(),
             Buffer::Query(query) => query.reset(),
@@ -425,7 +523,10 @@ impl Buffer {
             Buffer::Empty
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => Task::none(),
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
             Buffer::Server(state) => state
                 .input_view
                 .insert_user(nick, state.buffer.clone(), history, autocomplete)
@@ -447,9 +548,47 @@ impl Buffer {
         }
     }
 
+    pub fn edit_input(
+        &mut self,
+        history: &mut history::Manager,
+        edit: impl FnOnce(&str) -> String,
+    ) -> Task<Message> {
+        match self {
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Logs(_)
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
+            Buffer::Server(state) => state
+                .input_view
+                .edit(state.buffer.clone(), history, edit)
+                .map(|message| {
+                    Message::Server(server::Message::InputView(message))
+                }),
+            Buffer::Channel(state) => state
+                .input_view
+                .edit(state.buffer.clone(), history, edit)
+                .map(|message| {
+                    Message::Channel(channel::Message::InputView(message))
+                }),
+            Buffer::Query(state) => state
+                .input_view
+                .edit(state.buffer.clone(), history, edit)
+                .map(|message| {
+                    Message::Query(query::Message::InputView(message))
+                }),
+        }
+    }
+
     pub fn scroll_up_page(&mut self) -> Task<Message> {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
             Buffer::Channel(channel) => {
                 channel.scroll_view.scroll_up_page().map(|message| {
                     Message::Channel(channel::Message::ScrollView(message))
@@ -482,7 +621,11 @@ impl Buffer {
 
     pub fn scroll_down_page(&mut self) -> Task<Message> {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
             Buffer::Channel(channel) => {
                 channel.scroll_view.scroll_down_page().map(|message| {
                     Message::Channel(channel::Message::ScrollView(message))
@@ -515,7 +658,11 @@ impl Buffer {
 
     pub fn scroll_to_start(&mut self, config: &Config) -> Task<Message> {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
             Buffer::Channel(channel) => {
                 channel.scroll_view.scroll_to_start(config).map(|message| {
                     Message::Channel(channel::Message::ScrollView(message))
@@ -549,7 +696,11 @@ impl Buffer {
 
     pub fn scroll_to_end(&mut self, config: &Config) -> Task<Message> {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
             Buffer::Channel(channel) => {
                 channel.scroll_view.scroll_to_end(config).map(|message| {
                     Message::Channel(channel::Message::ScrollView(message))
@@ -580,6 +731,29 @@ impl Buffer {
         }
     }
 
+    pub fn set_search_query(
+        &mut self,
+        query: Option<data::search::Query>,
+        casemapping: isupport::CaseMap,
+    ) {
+        match self {
+            Buffer::Channel(state) => {
+                state.scroll_view.set_search_query(query, casemapping);
+            }
+            Buffer::Query(state) => {
+                state.scroll_view.set_search_query(query, casemapping);
+            }
+            Buffer::Empty
+            | Buffer::Server(_)
+            | Buffer::FileTransfers(_)
+            | Buffer::Logs(_)
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => {}
+        }
+    }
+
     pub fn scroll_to_message(
         &mut self,
         message: message::Hash,
@@ -587,7 +761,11 @@ impl Buffer {
         config: &Config,
     ) -> Task<Message> {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
             Buffer::Channel(state) => state
                 .scroll_view
                 .scroll_to_message(
@@ -601,12 +779,7 @@ impl Buffer {
                 }),
             Buffer::Server(state) => state
                 .scroll_view
-                .scroll_to_message(
-                    message,
-                    scroll_view::Kind::Server(&state.server),
-                    history,
-                    config,
-                )
+                .scroll_to_message(message, state.kind(), history, config)
                 .map(|message| {
                     Message::Server(server::Message::ScrollView(message))
                 }),
@@ -654,7 +827,11 @@ impl Buffer {
         config: &Config,
     ) -> Task<Message> {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => Task::none(),
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => Task::none(),
             Buffer::Channel(state) => state
                 .scroll_view
                 .scroll_to_backlog(
@@ -667,11 +844,7 @@ impl Buffer {
                 }),
             Buffer::Server(state) => state
                 .scroll_view
-                .scroll_to_backlog(
-                    scroll_view::Kind::Server(&state.server),
-                    history,
-                    config,
-                )
+                .scroll_to_backlog(state.kind(), history, config)
                 .map(|message| {
                     Message::Server(server::Message::ScrollView(message))
                 }),
@@ -708,7 +881,11 @@ impl Buffer {
 
     pub fn is_scrolled_to_bottom(&self) -> Option<bool> {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => None,
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => None,
             Buffer::Channel(channel) => {
                 Some(channel.scroll_view.is_scrolled_to_bottom())
             }
@@ -730,7 +907,10 @@ impl Buffer {
             Buffer::Empty
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => false,
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => false,
             Buffer::Server(state) => state.input_view.close_picker(),
             Buffer::Channel(state) => state.input_view.close_picker(),
             Buffer::Query(state) => state.input_view.close_picker(),
@@ -739,9 +919,13 @@ impl Buffer {
 
     pub fn update_pane_size(&mut self, pane_size: Size, config: &Config) {
         match self {
-            Buffer::Empty | Buffer::FileTransfers(_) => (),
+            Buffer::Empty
+            | Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => (),
             Buffer::Channel(channel) => {
-                channel.scroll_view.update_pane_size(pane_size, config);
+                channel.update_pane_size(pane_size, config);
             }
             Buffer::Server(server) => {
                 server.scroll_view.update_pane_size(pane_size, config);
@@ -0,0 +1,255 @@
+use chrono::Local;
+use data::admin::{self, Query, Reply};
+use data::{Server, client};
+use iced::widget::{
+    Scrollable, button, center, column, container, row, scrollable, text,
+    text_input,
+};
+use iced::{Length, alignment};
+
+use crate::widget::Element;
+use crate::{Theme, font, icon, theme};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    SelectServer(Server),
+    SelectQuery(Query),
+    StatsLetter(String),
+    Refresh,
+}
+
+pub fn view<'a>(
+    state: &'a Admin,
+    clients: &'a client::Map,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let server = state.server(clients);
+
+    let servers = row(clients.connected_servers().map(|server| {
+        let selected = state.server(clients) == Some(server);
+
+        button(
+            text(server.to_string())
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get)),
+        )
+        .padding([2, 6])
+        .style(move |theme, status| {
+            theme::button::secondary(theme, status, selected)
+        })
+        .on_press(Message::SelectServer(server.clone()))
+        .into()
+    }))
+    .spacing(4)
+    .wrap();
+
+    let tab = |label: &'a str, query: Query| {
+        let selected = std::mem::discriminant(&state.query)
+            == std::mem::discriminant(&query);
+
+        button(
+            text(label)
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get)),
+        )
+        .padding([2, 6])
+        .style(move |theme, status| {
+            theme::button::secondary(theme, status, selected)
+        })
+        .on_press(Message::SelectQuery(query))
+    };
+
+    let stats_letter = matches!(state.query, Query::Stats(_)).then(|| {
+        text_input("letter", &state.stats_letter)
+            .on_input(Message::StatsLetter)
+            .on_submit(Message::Refresh)
+            .padding([2, 6])
+            .width(64)
+            .style(theme::text_input::primary)
+    });
+
+    let refresh = button(icon::refresh().style(theme::text::primary))
+        .padding([2, 6])
+        .style(|theme, status| theme::button::secondary(theme, status, false))
+        .on_press_maybe(server.is_some().then_some(Message::Refresh));
+
+    let controls = row![
+        tab("STATS", Query::Stats(state.stats_letter())),
+        tab("LINKS", Query::Links),
+        tab("MAP", Query::Map),
+        stats_letter,
+        refresh,
+    ]
+    .spacing(4)
+    .align_y(alignment::Vertical::Center);
+
+    let report =
+        server.and_then(|server| clients.admin_report(server, state.query));
+
+    let body = match (server, report) {
+        (None, _) => placeholder("Connect to a server to administer it", theme),
+        (Some(_), None) => placeholder("Refresh to query the server", theme),
+        (Some(_), Some(report)) => self::report(report, theme),
+    };
+
+    column![servers, controls, body]
+        .spacing(8)
+        .padding(8)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+fn placeholder<'a>(label: &'a str, theme: &'a Theme) -> Element<'a, Message> {
+    center(
+        text(label)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get)),
+    )
+    .into()
+}
+
+fn report<'a>(
+    report: &'a admin::Report,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let status = text(if report.is_complete {
+        format!(
+            "Received {}",
+            report.requested_at.with_timezone(&Local).format("%H:%M:%S")
+        )
+    } else {
+        "Waiting for the server…".to_string()
+    })
+    .style(theme::text::secondary)
+    .font_maybe(theme::font_style::secondary(theme).map(font::get));
+
+    let error = report.error.as_ref().map(|error| {
+        text(error)
+            .style(theme::text::error)
+            .font_maybe(theme::font_style::error(theme).map(font::get))
+    });
+
+    let cell = |content: String| {
+        text(content)
+            .style(theme::text::primary)
+            .font_maybe(theme::font_style::primary(theme).map(font::get))
+    };
+
+    let rows: Element<'a, Message> = match &report.reply {
+        Reply::Stats(rows) => column(rows.iter().map(|stats_row| {
+            row![
+                container(
+                    text(&stats_row.numeric)
+                        .style(theme::text::secondary)
+                        .font_maybe(
+                            theme::font_style::secondary(theme).map(font::get),
+                        ),
+                )
+                .width(40),
+            ]
+            .extend(stats_row.fields.iter().map(|field| {
+                container(cell(field.clone())).width(Length::Fill).into()
+            }))
+            .spacing(8)
+            .into()
+        }))
+        .spacing(2)
+        .into(),
+        Reply::Links(links) => {
+            column(admin::link_tree(links).into_iter().map(|(depth, link)| {
+                let branch = if depth == 0 {
+                    String::new()
+                } else {
+                    format!("{}└ ", "  ".repeat(depth - 1))
+                };
+
+                row![
+                    container(cell(format!("{branch}{}", link.server)))
+                        .width(Length::FillPortion(2)),
+                    container(cell(link.hops.to_string())).width(40),
+                    container(cell(link.info.clone()))
+                        .width(Length::FillPortion(3)),
+                ]
+                .spacing(8)
+                .into()
+            }))
+            .spacing(2)
+            .into()
+        }
+        Reply::Map(lines) => {
+            column(lines.iter().map(|line| cell(line.clone()).into())).into()
+        }
+    };
+
+    column![
+        status,
+        error,
+        container(Scrollable::new(rows).direction(
+            scrollable::Direction::Vertical(
+                scrollable::Scrollbar::new().width(1).scroller_width(1),
+            )
+        ),)
+        .width(Length::Fill)
+        .height(Length::Fill),
+    ]
+    .spacing(8)
+    .into()
+}
+
+#[derive(Debug, Clone)]
+pub struct Admin {
+    server: Option<Server>,
+    query: Query,
+    stats_letter: String,
+}
+
+impl Default for Admin {
+    fn default() -> Self {
+        Self {
+            server: None,
+            query: Query::Stats('u'),
+            stats_letter: "u".to_string(),
+        }
+    }
+}
+
+impl Admin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The selected server, or the first connected one
+    fn server<'a>(&'a self, clients: &'a client::Map) -> Option<&'a Server> {
+        self.server
+            .as_ref()
+            .filter(|server| clients.connected_servers().any(|s| s == *server))
+            .or_else(|| clients.connected_servers().next())
+    }
+
+    fn stats_letter(&self) -> char {
+        self.stats_letter.chars().next().unwrap_or('u')
+    }
+
+    pub fn update(&mut self, message: Message, clients: &mut client::Map) {
+        match message {
+            Message::SelectServer(server) => {
+                self.server = Some(server);
+            }
+            Message::SelectQuery(query) => {
+                self.query = query;
+            }
+            Message::StatsLetter(input) => {
+                // Only a single letter is sent with STATS
+                self.stats_letter =
+                    input.chars().last().map(String::from).unwrap_or_default();
+                self.query = Query::Stats(self.stats_letter());
+            }
+            Message::Refresh => {
+                if let Some(server) = self.server(clients).cloned() {
+                    clients.request_admin(&server, self.query);
+                }
+            }
+        }
+    }
+}
@@ -4,15 +4,15 @@ use data::dashboard::BufferAction;
 use data::preview::{self, Previews};
 use data::server::Server;
 use data::target::{self, Target};
-use data::user::{ChannelUsers, Nick};
-use data::{Config, User, buffer, history, message};
-use iced::widget::{column, container, row};
-use iced::{Length, Size, Task, padding};
+use data::user::{AccessLevel, ChannelUsers, Nick};
+use data::{Config, User, buffer, history, message, script};
+use iced::widget::{button, column, container, row, stack, text};
+use iced::{Length, Size, Task, alignment, padding};
 
 use super::message_view::{ChannelQueryLayout, TargetInfo};
 use super::{context_menu, input_view, scroll_view};
-use crate::Theme;
 use crate::widget::Element;
+use crate::{Theme, font, icon, theme};
 
 mod topic;
 
@@ -22,6 +22,7 @@ pub enum Message {
     InputView(input_view::Message),
     ContextMenu(context_menu::Message),
     Topic(topic::Message),
+    ToggleMembers,
 }
 
 pub enum Event {
@@ -35,6 +36,7 @@ pub enum Event {
     MarkAsRead(history::Kind),
     OpenUrl(String),
     ImagePreview(PathBuf, url::Url),
+    OperPrompt(Option<String>),
 }
 
 pub fn view<'a>(
@@ -43,9 +45,11 @@ pub fn view<'a>(
     history: &'a history::Manager,
     previews: &'a preview::Collection,
     settings: Option<&'a buffer::Settings>,
+    density: buffer::Density,
     config: &'a Config,
     theme: &'a Theme,
     is_focused: bool,
+    is_mini: bool,
 ) -> Element<'a, Message> {
     let server = &state.server;
     let chantypes = clients.get_chantypes(server);
@@ -81,12 +85,14 @@ pub fn view<'a>(
         casemapping,
         prefix,
         server,
+        clients,
         theme,
         target: TargetInfo::Channel {
             users,
             channel,
             our_user,
         },
+        density,
     };
 
     let messages = container(
@@ -105,8 +111,11 @@ pub fn view<'a>(
     .width(Length::FillPortion(2))
     .height(Length::Fill);
 
+    let nicklist = clients.get_nicklist(&state.server, channel);
+    let member_count = nicklist.map_or(0, <[User]>::len);
+
     let nick_list = nick_list::view(
-        server, prefix, channel, users, our_user, config, theme,
+        server, prefix, channel, nicklist, our_user, clients, config, theme,
     )
     .map(Message::ContextMenu);
 
@@ -138,19 +147,63 @@ pub fn view<'a>(
 
     let content = column![topic, messages];
 
-    let nicklist_enabled = settings
-        .map_or(config.buffer.channel.nicklist.enabled, |settings| {
-            settings.channel.nicklist.enabled
-        });
+    // The nicklist is hidden in mini mode to save space
+    let nicklist_enabled = !is_mini
+        && settings
+            .map_or(config.buffer.channel.nicklist.enabled, |settings| {
+                settings.channel.nicklist.enabled
+            });
+
+    let position = config.buffer.channel.nicklist.position;
+
+    // Narrow panes trade the nicklist for a button opening it as an overlay
+    let is_compact = nicklist_enabled
+        && config
+            .buffer
+            .channel
+            .nicklist
+            .compact_below
+            .is_some_and(|width| state.pane_size.width < width);
+
+    let content: Element<'a, Message> = if is_compact {
+        let members = members_button(member_count, state.show_members, theme);
+
+        let content = match position {
+            data::channel::Position::Left => row![members, content],
+            data::channel::Position::Right => row![content, members],
+        }
+        .spacing(4)
+        .padding(padding::left(8).right(8));
 
-    let content =
-        match (nicklist_enabled, config.buffer.channel.nicklist.position) {
+        if state.show_members {
+            let overlay = container(
+                container(nick_list)
+                    .padding(8)
+                    .height(Length::Fill)
+                    .style(theme::container::tooltip),
+            )
+            .padding(padding::top(30).bottom(4).left(8).right(8))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .align_x(match position {
+                data::channel::Position::Left => alignment::Horizontal::Left,
+                data::channel::Position::Right => alignment::Horizontal::Right,
+            });
+
+            stack![content, overlay].into()
+        } else {
+            content.into()
+        }
+    } else {
+        match (nicklist_enabled, position) {
             (true, data::channel::Position::Left) => row![nick_list, content],
             (true, data::channel::Position::Right) => row![content, nick_list],
             (false, _) => { row![content] }.height(Length::Fill),
         }
         .spacing(4)
-        .padding(padding::left(8).right(8));
+        .padding(padding::left(8).right(8))
+        .into()
+    };
 
     let body = column![container(content).height(Length::Fill), text_input]
         .height(Length::Fill);
@@ -168,6 +221,10 @@ pub struct Channel {
     pub target: target::Channel,
     pub scroll_view: scroll_view::State,
     pub input_view: input_view::State,
+    pub topic_editor: Option<topic::Editor>,
+    pub pane_size: Size,
+    /// Whether the nicklist is shown over the messages of a narrow pane
+    pub show_members: bool,
 }
 
 impl Channel {
@@ -183,14 +240,25 @@ impl Channel {
             target,
             scroll_view: scroll_view::State::new(pane_size, config),
             input_view: input_view::State::new(),
+            topic_editor: None,
+            pane_size,
+            show_members: false,
         }
     }
 
+    /// Follows the channel after the server renamed it
+    pub fn rename(&mut self, target: target::Channel) {
+        self.buffer =
+            buffer::Upstream::Channel(self.server.clone(), target.clone());
+        self.target = target;
+    }
+
     pub fn update(
         &mut self,
         message: Message,
         clients: &mut data::client::Map,
         history: &mut history::Manager,
+        scripts: &script::Manager,
         config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         match message {
@@ -212,6 +280,9 @@ impl Channel {
                         Some(Event::OpenBuffers(vec![(target, buffer_action)]))
                     }
                     scroll_view::Event::GoToMessage(..) => None,
+                    scroll_view::Event::RestoreHistory(kind) => history
+                        .restore(kind, clients)
+                        .map(|task| Event::History(Task::future(task))),
                     scroll_view::Event::RequestOlderChatHistory => {
                         Some(Event::RequestOlderChatHistory)
                     }
@@ -243,6 +314,7 @@ impl Channel {
                     &self.buffer,
                     clients,
                     history,
+                    scripts,
                     config,
                 );
                 let command = command.map(Message::InputView);
@@ -268,6 +340,9 @@ impl Channel {
                     Some(input_view::Event::Cleared { history_task }) => {
                         (command, Some(Event::History(history_task)))
                     }
+                    Some(input_view::Event::OperPrompt { name }) => {
+                        (command, Some(Event::OperPrompt(name)))
+                    }
                     None => (command, None),
                 }
             }
@@ -275,9 +350,11 @@ impl Channel {
                 Task::none(),
                 Some(Event::ContextMenu(context_menu::update(message))),
             ),
-            Message::Topic(message) => (
-                Task::none(),
-                topic::update(message).map(|event| match event {
+            Message::Topic(message) => {
+                let (command, event) =
+                    topic::update(message, &mut self.topic_editor);
+
+                let event = event.map(|event| match event {
                     topic::Event::ContextMenu(event) => {
                         Event::ContextMenu(event)
                     }
@@ -288,20 +365,84 @@ impl Channel {
                         )])
                     }
                     topic::Event::OpenUrl(url) => Event::OpenUrl(url),
-                }),
-            ),
+                    topic::Event::SetTopic(topic) => {
+                        let channel = &self.target;
+
+                        let command = if topic.is_empty() {
+                            format!("/cleartopic {channel}")
+                        } else {
+                            format!("/topic {channel} {topic}")
+                        };
+
+                        Event::ContextMenu(context_menu::Event::RunCommand(
+                            self.server.clone(),
+                            Some(channel.clone()),
+                            command,
+                        ))
+                    }
+                });
+
+                (command.map(Message::Topic), event)
+            }
+            Message::ToggleMembers => {
+                self.show_members = !self.show_members;
+
+                (Task::none(), None)
+            }
         }
     }
 
+    pub fn update_pane_size(&mut self, pane_size: Size, config: &Config) {
+        self.scroll_view.update_pane_size(pane_size, config);
+        self.pane_size = pane_size;
+    }
+
     pub fn focus(&self) -> Task<Message> {
-        self.input_view.focus().map(Message::InputView)
+        if let Some(editor) = &self.topic_editor {
+            editor.focus().map(Message::Topic)
+        } else {
+            self.input_view.focus().map(Message::InputView)
+        }
     }
 
     pub fn reset(&mut self) {
         self.input_view.reset();
+        self.topic_editor = None;
+        self.show_members = false;
     }
 }
 
+/// Count of the channel's members, toggling the nicklist overlay.
+fn members_button<'a>(
+    member_count: usize,
+    show_members: bool,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    container(
+        button(
+            column![
+                icon::people().style(theme::text::secondary),
+                text(member_count.to_string())
+                    .size(theme::TEXT_SIZE - 1.0)
+                    .style(theme::text::secondary)
+                    .font_maybe(
+                        theme::font_style::secondary(theme).map(font::get)
+                    ),
+            ]
+            .spacing(2)
+            .align_x(iced::Alignment::Center),
+        )
+        .padding(4)
+        .on_press(Message::ToggleMembers)
+        .style(move |theme, status| {
+            theme::button::secondary(theme, status, show_members)
+        }),
+    )
+    .padding(padding::top(4))
+    .height(Length::Fill)
+    .into()
+}
+
 fn topic<'a>(
     state: &'a Channel,
     clients: &'a data::client::Map,
@@ -326,6 +467,13 @@ fn topic<'a>(
 
     let topic = clients.get_channel_topic(&state.server, &state.target)?;
 
+    // Anyone may set the topic unless it's protected (+t), in which case
+    // half-op or higher is required
+    let editable = our_user
+        .is_some_and(|user| user.highest_access_level() >= AccessLevel::HalfOp)
+        || clients.is_topic_protected(&state.server, &state.target)
+            == Some(false);
+
     Some(
         topic::view(
             &state.server,
@@ -339,6 +487,9 @@ fn topic<'a>(
             config.buffer.channel.topic_banner.max_lines,
             users,
             our_user,
+            editable,
+            state.topic_editor.as_ref(),
+            clients,
             config,
             theme,
         )
@@ -348,7 +499,6 @@ fn topic<'a>(
 
 mod nick_list {
     use context_menu::Message;
-    use data::user::ChannelUsers;
     use data::{Config, Server, User, config, isupport, target};
     use iced::Length;
     use iced::advanced::text;
@@ -362,8 +512,9 @@ mod nick_list {
         server: &'a Server,
         prefix: &'a [isupport::PrefixMap],
         channel: &'a target::Channel,
-        users: Option<&'a ChannelUsers>,
+        users: Option<&'a [User]>,
         our_user: Option<&'a User>,
+        clients: &'a data::client::Map,
         config: &'a Config,
         theme: &'a Theme,
     ) -> Element<'a, Message> {
@@ -376,9 +527,13 @@ mod nick_list {
                     .into_iter()
                     .flatten()
                     .map(|user| {
-                        user.display(nicklist_config.show_access_levels, None)
-                            .chars()
-                            .count()
+                        user.display(
+                            nicklist_config.show_access_levels,
+                            None,
+                            config.buffer.nickname.bridged_name(),
+                        )
+                        .chars()
+                        .count()
                     })
                     .max()
                     .unwrap_or_default();
@@ -388,9 +543,11 @@ mod nick_list {
         };
 
         let content = column(users.into_iter().flatten().map(|user| {
-            let content = selectable_text(
-                user.display(nicklist_config.show_access_levels, None),
-            )
+            let content = selectable_text(user.display(
+                nicklist_config.show_access_levels,
+                None,
+                config.buffer.nickname.bridged_name(),
+            ))
             .font_maybe(
                 theme::font_style::nickname(theme, false).map(font::get),
             )
@@ -415,6 +572,7 @@ mod nick_list {
                 user,
                 Some(user),
                 our_user,
+                clients,
                 config,
                 theme,
                 &config.buffer.channel.nicklist.click,
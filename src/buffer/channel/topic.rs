@@ -1,11 +1,16 @@
 use chrono::{DateTime, Local, Utc};
 use data::user::{ChannelUsers, NickRef};
 use data::{Config, Server, User, isupport, message, target};
-use iced::widget::{Scrollable, column, container, row, rule, scrollable};
-use iced::{Color, Length, padding};
+use iced::widget::{
+    self, Scrollable, column, container, operation, row, rule, scrollable,
+    text_input,
+};
+use iced::{Color, Length, Task, padding};
 
-use super::context_menu;
-use crate::widget::{Element, double_pass, message_content, selectable_text};
+use super::context_menu::{self, Context};
+use crate::widget::{
+    Element, double_pass, key_press, message_content, selectable_text,
+};
 use crate::{Theme, font, theme};
 
 #[derive(Debug, Clone)]
@@ -13,27 +18,99 @@ pub enum Event {
     ContextMenu(context_menu::Event),
     OpenChannel(target::Channel),
     OpenUrl(String),
+    SetTopic(String),
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     ContextMenu(context_menu::Message),
     Link(message::Link),
+    EditorInput(String),
+    EditorSubmit,
+    EditorCancel,
 }
 
-pub fn update(message: Message) -> Option<Event> {
-    match message {
-        Message::ContextMenu(message) => {
-            Some(Event::ContextMenu(context_menu::update(message)))
+/// Inline editor for the topic, opened from the topic context menu
+#[derive(Debug, Clone)]
+pub struct Editor {
+    value: String,
+    id: widget::Id,
+}
+
+impl Editor {
+    fn new(value: String) -> Self {
+        Self {
+            value,
+            id: widget::Id::unique(),
         }
+    }
+
+    pub fn focus(&self) -> Task<Message> {
+        let id = self.id.clone();
+
+        operation::is_focused(id.clone()).then(move |is_focused| {
+            if is_focused {
+                Task::none()
+            } else {
+                operation::focus(id.clone())
+            }
+        })
+    }
+}
+
+pub fn update(
+    message: Message,
+    editor: &mut Option<Editor>,
+) -> (Task<Message>, Option<Event>) {
+    match message {
+        Message::ContextMenu(message) => match context_menu::update(message) {
+            context_menu::Event::EditTopic(topic) => {
+                let new_editor = Editor::new(topic);
+                let id = new_editor.id.clone();
+
+                *editor = Some(new_editor);
+
+                (
+                    Task::batch(vec![
+                        operation::focus(id.clone()),
+                        operation::move_cursor_to_end(id),
+                    ]),
+                    None,
+                )
+            }
+            event => (Task::none(), Some(Event::ContextMenu(event))),
+        },
         Message::Link(message::Link::Channel(channel)) => {
-            Some(Event::OpenChannel(channel))
+            (Task::none(), Some(Event::OpenChannel(channel)))
+        }
+        Message::Link(message::Link::Url(url)) => {
+            (Task::none(), Some(Event::OpenUrl(url)))
+        }
+        Message::Link(message::Link::User(user)) => (
+            Task::none(),
+            Some(Event::ContextMenu(context_menu::Event::InsertNickname(
+                user.nickname().to_owned(),
+            ))),
+        ),
+        Message::Link(
+            message::Link::GoToMessage(..) | message::Link::Message,
+        ) => (Task::none(), None),
+        Message::EditorInput(value) => {
+            if let Some(editor) = editor {
+                editor.value = value;
+            }
+
+            (Task::none(), None)
+        }
+        Message::EditorSubmit => (
+            Task::none(),
+            editor.take().map(|editor| Event::SetTopic(editor.value)),
+        ),
+        Message::EditorCancel => {
+            *editor = None;
+
+            (Task::none(), None)
         }
-        Message::Link(message::Link::Url(url)) => Some(Event::OpenUrl(url)),
-        Message::Link(message::Link::User(user)) => Some(Event::ContextMenu(
-            context_menu::Event::InsertNickname(user.nickname().to_owned()),
-        )),
-        Message::Link(message::Link::GoToMessage(..)) => None,
     }
 }
 
@@ -49,6 +126,9 @@ pub fn view<'a>(
     max_lines: u16,
     users: Option<&'a ChannelUsers>,
     our_user: Option<&'a User>,
+    editable: bool,
+    editor: Option<&'a Editor>,
+    clients: &'a data::client::Map,
     config: &'a Config,
     theme: &'a Theme,
 ) -> Element<'a, Message> {
@@ -75,19 +155,24 @@ pub fn view<'a>(
                 user,
                 Some(user),
                 our_user,
+                clients,
                 config,
                 theme,
                 &config.buffer.nickname.click,
             )
         } else {
-            selectable_text(user.display(false, None))
-                .font_maybe(
-                    theme::font_style::nickname(theme, false).map(font::get),
-                )
-                .style(move |theme| {
-                    theme::selectable_text::topic_nickname(theme, config, &user)
-                })
-                .into()
+            selectable_text(user.display(
+                false,
+                None,
+                config.buffer.nickname.bridged_name(),
+            ))
+            .font_maybe(
+                theme::font_style::nickname(theme, false).map(font::get),
+            )
+            .style(move |theme| {
+                theme::selectable_text::topic_nickname(theme, config, &user)
+            })
+            .into()
         };
 
         Some(
@@ -107,8 +192,21 @@ pub fn view<'a>(
         )
     });
 
-    let content = column![
-        message_content(
+    let topic = if let Some(editor) = editor {
+        key_press(
+            text_input("Set topic...", &editor.value)
+                .id(editor.id.clone())
+                .on_input(Message::EditorInput)
+                .on_submit(Message::EditorSubmit)
+                .font_maybe(theme::font_style::topic(theme).map(font::get))
+                .style(theme::text_input::primary)
+                .padding([0, 4]),
+            key_press::Key::Named(key_press::Named::Escape),
+            key_press::Modifiers::default(),
+            Message::EditorCancel,
+        )
+    } else {
+        message_content::with_message_context(
             content,
             chantypes,
             casemapping,
@@ -117,10 +215,35 @@ pub fn view<'a>(
             theme::selectable_text::topic,
             theme::font_style::topic,
             Option::<fn(Color) -> Color>::None,
+            config
+                .buffer
+                .formatting
+                .strips_colors(Some(channel.as_str())),
+            move |link| match link {
+                message::Link::Url(url) => {
+                    context_menu::Entry::url_list(url, config)
+                }
+                message::Link::Message => context_menu::Entry::topic_list(),
+                _ => vec![],
+            },
+            move |link, entry, length, focused| {
+                let context = link_context(link, content, who, editable);
+
+                entry
+                    .view(context, length, focused, config, theme)
+                    .map(Message::ContextMenu)
+            },
+            move |link, entry| {
+                let context = link_context(link, content, who, editable)?;
+
+                entry.label(&context, config)
+            },
+            Option::<(fn(message::Link) -> _, fn(message::Link) -> _)>::None,
             config,
-        ),
-        set_by
-    ];
+        )
+    };
+
+    let content = column![topic, set_by];
 
     let scrollable = Scrollable::new(
         container(content).width(Length::Fill).padding(padding()),
@@ -147,6 +270,23 @@ pub fn view<'a>(
     .into()
 }
 
+fn link_context<'a>(
+    link: &'a message::Link,
+    content: &'a message::Content,
+    who: Option<NickRef<'a>>,
+    editable: bool,
+) -> Option<Context<'a>> {
+    match link {
+        message::Link::Url(url) => Some(Context::Url(url)),
+        message::Link::Message => Some(Context::Topic {
+            content,
+            who,
+            editable,
+        }),
+        _ => None,
+    }
+}
+
 fn padding() -> [u16; 2] {
     [0, 8]
 }
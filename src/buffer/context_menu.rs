@@ -1,13 +1,15 @@
 use chrono::{DateTime, Local, Utc};
+use data::config::buffer::context_menu::{TimestampEntry, UrlEntry, UserEntry};
 use data::dashboard::BufferAction;
-use data::user::Nick;
-use data::{Config, Server, User, config, ctcp, isupport, target};
+use data::user::{Nick, NickRef};
+use data::{Config, Server, User, buffer, config, ctcp, isupport, target};
 use iced::widget::{Space, button, column, container, row, rule, text};
 use iced::{Length, Padding, padding};
 
 use crate::widget::{Element, context_menu, double_pass};
-use crate::{Theme, font, theme, widget};
+use crate::{Theme, font, i18n, theme, widget};
 
+#[derive(Clone, Copy)]
 pub enum Context<'a> {
     User {
         server: &'a Server,
@@ -15,9 +17,20 @@ pub enum Context<'a> {
         channel: Option<&'a target::Channel>,
         user: &'a User,
         current_user: Option<&'a User>,
+        clients: &'a data::client::Map,
+    },
+    Channel {
+        server: &'a Server,
+        channel: &'a target::Channel,
+    },
+    Topic {
+        content: &'a data::message::Content,
+        who: Option<NickRef<'a>>,
+        editable: bool,
     },
     Url(&'a String),
     Timestamp(&'a DateTime<Utc>),
+    Message(&'a data::Message),
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -25,76 +38,439 @@ pub enum Entry {
     // user context
     Whois,
     Query,
+    QueryInNewWindow,
     ToggleAccessLevelOp,
     ToggleAccessLevelVoice,
     SendFile,
+    DccChat,
+    InviteTo,
+    CopyHostmask,
+    Monitor,
     UserInfo,
     HorizontalRule,
     CtcpRequestTime,
     CtcpRequestVersion,
+    CtcpRequestPing,
+    CtcpRequestClientInfo,
+    CtcpRequestSource,
+    CtcpRequestUserInfo,
+    Custom(usize),
+    Kill,
+    Gline,
+    // channel context
+    CopyChannelName,
+    SetTopic,
+    ChannelModes,
+    ChannelInfo,
+    AccessList,
+    OpenLogFile,
+    Part,
+    PartWithReason,
+    CloseBuffer,
+    // topic context
+    CopyTopic,
+    CopySetter,
+    EditTopic,
     // url context
+    OpenUrl,
+    OpenWith(usize),
     CopyUrl,
+    CopyTitle,
     // timestamp context
     Timestamp,
+    // message context
+    CopyMessage,
+    CopySender,
+    QuoteMessage,
+    Reply,
+    ReplyStatusMessage,
+    CopyRawModes,
+    Share,
+    MessageDetails,
 }
 
 impl Entry {
-    pub fn timestamp_list() -> Vec<Self> {
-        vec![Entry::Timestamp]
+    pub fn timestamp_list(config: &Config) -> Vec<Self> {
+        separated(config.buffer.context_menu.timestamp.iter().map(|entry| {
+            match entry {
+                TimestampEntry::CopyTimestamp => Entry::Timestamp,
+                TimestampEntry::Separator => Entry::HorizontalRule,
+            }
+        }))
     }
 
-    pub fn url_list() -> Vec<Self> {
-        vec![Entry::CopyUrl]
-    }
+    pub fn url_list(url: &str, config: &Config) -> Vec<Self> {
+        let context_menu = &config.buffer.context_menu;
 
-    pub fn user_list(is_channel: bool, our_user: Option<&User>) -> Vec<Self> {
-        if is_channel {
-            if our_user.is_some_and(|u| {
-                u.has_access_level(data::user::AccessLevel::Oper)
-            }) {
-                vec![
-                    Entry::UserInfo,
-                    Entry::HorizontalRule,
-                    Entry::Whois,
-                    Entry::Query,
-                    Entry::SendFile,
-                    Entry::HorizontalRule,
-                    Entry::ToggleAccessLevelOp,
-                    Entry::ToggleAccessLevelVoice,
-                    Entry::HorizontalRule,
-                    Entry::CtcpRequestVersion,
-                    Entry::CtcpRequestTime,
-                ]
-            } else {
-                vec![
-                    Entry::UserInfo,
-                    Entry::HorizontalRule,
-                    Entry::Whois,
-                    Entry::Query,
-                    Entry::SendFile,
-                    Entry::HorizontalRule,
-                    Entry::CtcpRequestVersion,
-                    Entry::CtcpRequestTime,
-                ]
+        separated(context_menu.url.iter().flat_map(|entry| {
+            match entry {
+                UrlEntry::OpenUrl => vec![Entry::OpenUrl],
+                // One entry per command configured for the URL
+                UrlEntry::OpenWith => context_menu
+                    .open_with
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, open_with)| open_with.matches(url))
+                    .map(|(index, _)| Entry::OpenWith(index))
+                    .collect(),
+                UrlEntry::CopyUrl => vec![Entry::CopyUrl],
+                UrlEntry::CopyTitle => vec![Entry::CopyTitle],
+                UrlEntry::Separator => vec![Entry::HorizontalRule],
             }
+        }))
+    }
+
+    pub fn server_message_list(message: &data::Message) -> Vec<Self> {
+        if message.raw_modes().is_some() {
+            vec![
+                Entry::CopyRawModes,
+                Entry::HorizontalRule,
+                Entry::MessageDetails,
+            ]
         } else {
-            vec![Entry::Whois, Entry::SendFile]
+            vec![Entry::MessageDetails]
+        }
+    }
+
+    pub fn message_list(message: &data::Message) -> Vec<Self> {
+        let mut list = vec![
+            Entry::CopyMessage,
+            Entry::CopySender,
+            Entry::HorizontalRule,
+            Entry::QuoteMessage,
+            Entry::Reply,
+        ];
+
+        if message.target.prefixes().is_some() {
+            list.push(Entry::ReplyStatusMessage);
         }
+
+        list.extend([
+            Entry::HorizontalRule,
+            Entry::Share,
+            Entry::MessageDetails,
+        ]);
+
+        list
+    }
+
+    pub fn topic_list() -> Vec<Self> {
+        vec![
+            Entry::CopyTopic,
+            Entry::CopySetter,
+            Entry::HorizontalRule,
+            Entry::EditTopic,
+        ]
+    }
+
+    pub fn channel_list() -> Vec<Self> {
+        vec![
+            Entry::CopyChannelName,
+            Entry::HorizontalRule,
+            Entry::SetTopic,
+            Entry::ChannelModes,
+            Entry::ChannelInfo,
+            Entry::AccessList,
+            Entry::OpenLogFile,
+            Entry::HorizontalRule,
+            Entry::Part,
+            Entry::PartWithReason,
+            Entry::CloseBuffer,
+        ]
+    }
+
+    pub fn user_list(
+        is_channel: bool,
+        our_user: Option<&User>,
+        oper_actions: bool,
+        config: &Config,
+    ) -> Vec<Self> {
+        let is_oper = our_user
+            .is_some_and(|u| u.has_access_level(data::user::AccessLevel::Oper));
+
+        let oper_entries = oper_actions
+            .then_some([Entry::HorizontalRule, Entry::Kill, Entry::Gline])
+            .into_iter()
+            .flatten();
+
+        separated(
+            config
+                .buffer
+                .context_menu
+                .user
+                .iter()
+                .enumerate()
+                .filter_map(|(index, entry)| {
+                    let entry = match entry {
+                        UserEntry::UserInfo => Entry::UserInfo,
+                        UserEntry::Whois => Entry::Whois,
+                        UserEntry::Query => Entry::Query,
+                        UserEntry::QueryInNewWindow => Entry::QueryInNewWindow,
+                        UserEntry::SendFile => Entry::SendFile,
+                        UserEntry::DccChat => Entry::DccChat,
+                        UserEntry::InviteTo => Entry::InviteTo,
+                        UserEntry::CopyHostmask => Entry::CopyHostmask,
+                        UserEntry::Monitor => Entry::Monitor,
+                        UserEntry::ToggleOp => Entry::ToggleAccessLevelOp,
+                        UserEntry::ToggleVoice => Entry::ToggleAccessLevelVoice,
+                        UserEntry::CtcpVersion => Entry::CtcpRequestVersion,
+                        UserEntry::CtcpTime => Entry::CtcpRequestTime,
+                        UserEntry::CtcpPing => Entry::CtcpRequestPing,
+                        UserEntry::CtcpClientInfo => {
+                            Entry::CtcpRequestClientInfo
+                        }
+                        UserEntry::CtcpSource => Entry::CtcpRequestSource,
+                        UserEntry::CtcpUserInfo => Entry::CtcpRequestUserInfo,
+                        UserEntry::Separator => Entry::HorizontalRule,
+                        UserEntry::Custom(_) => Entry::Custom(index),
+                    };
+
+                    let is_available = match entry {
+                        Entry::UserInfo
+                        | Entry::Query
+                        | Entry::QueryInNewWindow
+                        | Entry::CtcpRequestVersion
+                        | Entry::CtcpRequestTime
+                        | Entry::CtcpRequestPing
+                        | Entry::CtcpRequestClientInfo
+                        | Entry::CtcpRequestSource
+                        | Entry::CtcpRequestUserInfo => is_channel,
+                        Entry::ToggleAccessLevelOp
+                        | Entry::ToggleAccessLevelVoice => {
+                            is_channel && is_oper
+                        }
+                        _ => true,
+                    };
+
+                    is_available.then_some(entry)
+                })
+                .chain(oper_entries),
+        )
+    }
+
+    /// Text shown for the entry, which typed characters are matched
+    /// against. `None` for entries which can't be activated.
+    pub fn label(
+        self,
+        context: &Context<'_>,
+        config: &Config,
+    ) -> Option<String> {
+        let label = match (self, context) {
+            (Entry::Whois, Context::User { .. }) => "context-menu-whois",
+            (Entry::Query, Context::User { .. }) => "context-menu-message",
+            (Entry::QueryInNewWindow, Context::User { .. }) => {
+                "context-menu-query-in-new-window"
+            }
+            (
+                Entry::ToggleAccessLevelOp,
+                Context::User {
+                    prefix,
+                    channel: Some(_),
+                    user,
+                    current_user,
+                    ..
+                },
+            ) => {
+                let (is_op, action) = toggle_access_level(
+                    prefix,
+                    current_user.unwrap_or(user),
+                    '@',
+                    data::user::AccessLevel::Oper,
+                )?;
+
+                return Some(i18n::tr_args(
+                    if is_op {
+                        "context-menu-take-op"
+                    } else {
+                        "context-menu-give-op"
+                    },
+                    &[("action", &action)],
+                ));
+            }
+            (
+                Entry::ToggleAccessLevelVoice,
+                Context::User {
+                    prefix,
+                    channel: Some(_),
+                    user,
+                    current_user,
+                    ..
+                },
+            ) => {
+                let (has_voice, action) = toggle_access_level(
+                    prefix,
+                    current_user.unwrap_or(user),
+                    '+',
+                    data::user::AccessLevel::Voice,
+                )?;
+
+                return Some(i18n::tr_args(
+                    if has_voice {
+                        "context-menu-take-voice"
+                    } else {
+                        "context-menu-give-voice"
+                    },
+                    &[("action", &action)],
+                ));
+            }
+            (Entry::SendFile, Context::User { .. }) => "context-menu-send-file",
+            (Entry::DccChat, Context::User { .. }) => "context-menu-dcc-chat",
+            (Entry::InviteTo, Context::User { .. }) => "context-menu-invite-to",
+            (Entry::CopyHostmask, Context::User { .. }) => {
+                "context-menu-copy-hostmask"
+            }
+            (
+                Entry::Monitor,
+                Context::User {
+                    server,
+                    user,
+                    clients,
+                    ..
+                },
+            ) => {
+                if clients.is_monitored(server, user.nickname()) {
+                    "context-menu-stop-monitoring"
+                } else {
+                    "context-menu-monitor"
+                }
+            }
+            (Entry::CtcpRequestTime, Context::User { .. }) => {
+                "context-menu-ctcp-time"
+            }
+            (Entry::CtcpRequestVersion, Context::User { .. }) => {
+                "context-menu-ctcp-version"
+            }
+            (Entry::CtcpRequestPing, Context::User { .. }) => {
+                "context-menu-ctcp-ping"
+            }
+            (Entry::CtcpRequestClientInfo, Context::User { .. }) => {
+                "context-menu-ctcp-clientinfo"
+            }
+            (Entry::CtcpRequestSource, Context::User { .. }) => {
+                "context-menu-ctcp-source"
+            }
+            (Entry::CtcpRequestUserInfo, Context::User { .. }) => {
+                "context-menu-ctcp-userinfo"
+            }
+            (Entry::Custom(index), Context::User { .. }) => {
+                return match config.buffer.context_menu.user.get(index) {
+                    Some(UserEntry::Custom(entry)) => Some(entry.label.clone()),
+                    _ => None,
+                };
+            }
+            (Entry::Kill, Context::User { .. }) => "context-menu-kill",
+            (Entry::Gline, Context::User { .. }) => "context-menu-gline",
+            (Entry::CopyChannelName, Context::Channel { .. }) => {
+                "context-menu-copy-channel-name"
+            }
+            (Entry::SetTopic, Context::Channel { .. }) => {
+                "context-menu-set-topic"
+            }
+            (Entry::ChannelModes, Context::Channel { .. }) => {
+                "context-menu-channel-modes"
+            }
+            (Entry::ChannelInfo, Context::Channel { .. }) => {
+                "context-menu-channel-info"
+            }
+            (Entry::AccessList, Context::Channel { .. }) => {
+                "context-menu-access-list"
+            }
+            (Entry::OpenLogFile, Context::Channel { .. }) => {
+                "context-menu-open-log-file"
+            }
+            (Entry::Part, Context::Channel { .. }) => "context-menu-part",
+            (Entry::PartWithReason, Context::Channel { .. }) => {
+                "context-menu-part-with-reason"
+            }
+            (Entry::CloseBuffer, Context::Channel { .. }) => {
+                "context-menu-close-buffer"
+            }
+            (Entry::CopyTopic, Context::Topic { .. }) => {
+                "context-menu-copy-topic"
+            }
+            (Entry::CopySetter, Context::Topic { .. }) => {
+                "context-menu-copy-setter"
+            }
+            (Entry::EditTopic, Context::Topic { .. }) => {
+                "context-menu-edit-topic"
+            }
+            (Entry::OpenUrl, Context::Url(_)) => "context-menu-open-in-browser",
+            (Entry::OpenWith(index), Context::Url(_)) => {
+                return config.buffer.context_menu.open_with.get(index).map(
+                    |open_with| {
+                        i18n::tr_args(
+                            "context-menu-open-with",
+                            &[("label", &open_with.label)],
+                        )
+                    },
+                );
+            }
+            (Entry::CopyUrl, Context::Url(_)) => "context-menu-copy-url",
+            (Entry::CopyTitle, Context::Url(_)) => {
+                "context-menu-copy-page-title"
+            }
+            (Entry::Timestamp, Context::Timestamp(date_time)) => {
+                return Some(i18n::format_date(
+                    &date_time.with_timezone(&Local),
+                    &config.buffer.timestamp.context_menu_format,
+                ));
+            }
+            (Entry::CopyMessage, Context::Message(_)) => {
+                "context-menu-copy-message"
+            }
+            (Entry::CopySender, Context::Message(_)) => {
+                "context-menu-copy-sender"
+            }
+            (Entry::QuoteMessage, Context::Message(_)) => "context-menu-quote",
+            (Entry::Reply, Context::Message(_)) => "context-menu-reply",
+            (Entry::ReplyStatusMessage, Context::Message(message)) => {
+                return message.target.prefixes().map(|prefixes| {
+                    i18n::tr_args(
+                        "context-menu-reply-to",
+                        &[(
+                            "recipients",
+                            &buffer::status_message_recipients(prefixes),
+                        )],
+                    )
+                });
+            }
+            (Entry::CopyRawModes, Context::Message(message)) => {
+                return message.raw_modes().map(|raw_modes| {
+                    i18n::tr_args(
+                        "context-menu-copy-modes",
+                        &[("modes", &raw_modes)],
+                    )
+                });
+            }
+            (Entry::Share, Context::Message(_)) => "context-menu-share",
+            (Entry::MessageDetails, Context::Message(_)) => {
+                "context-menu-message-details"
+            }
+            _ => return None,
+        };
+
+        Some(i18n::tr(label))
     }
 
     pub fn view<'a>(
         self,
         context: Option<Context<'_>>,
         length: Length,
+        focused: bool,
         config: &Config,
-        theme: &Theme,
+        theme: &'a Theme,
     ) -> Element<'a, Message> {
+        let label = context
+            .as_ref()
+            .and_then(|context| self.label(context, config))
+            .unwrap_or_default();
+
         context.map_or(row![].into(), |context| match (self, context) {
             (Entry::Whois, Context::User { server, user, .. }) => {
                 let message =
                     Message::Whois(server.clone(), user.nickname().to_owned());
 
-                menu_button("Whois".to_string(), Some(message), length, theme)
+                menu_button(label, Some(message), length, focused, theme)
             }
             (Entry::Query, Context::User { server, user, .. }) => {
                 let message = Message::Query(
@@ -103,7 +479,16 @@ impl Entry {
                     config.actions.buffer.message_user,
                 );
 
-                menu_button("Message".to_string(), Some(message), length, theme)
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::QueryInNewWindow, Context::User { server, user, .. }) => {
+                let message = Message::Query(
+                    server.clone(),
+                    target::Query::from(user.clone()),
+                    BufferAction::NewWindow,
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
             }
             (
                 Entry::ToggleAccessLevelOp,
@@ -112,39 +497,27 @@ impl Entry {
                     prefix,
                     channel,
                     user,
+                    current_user,
                     ..
                 },
             ) => {
-                let operator_mode = prefix.iter().find_map(|prefix_map| {
-                    (prefix_map.prefix == '@').then_some(prefix_map.mode)
-                });
-
-                let (label, message) =
-                    if let (Some(channel), Some(operator_mode)) =
-                        (channel, operator_mode)
-                    {
-                        let is_op = user
-                            .has_access_level(data::user::AccessLevel::Oper);
-                        let prefix = if is_op { "-" } else { "+" };
-                        let action = format!("{prefix}{operator_mode}");
-
-                        (
-                            format!(
-                                "{} Op ({action})",
-                                if is_op { "Take" } else { "Give" }
-                            ),
-                            Some(Message::ToggleAccessLevel(
-                                server.clone(),
-                                channel.clone(),
-                                user.nickname().to_owned(),
-                                action,
-                            )),
+                let message = channel
+                    .zip(toggle_access_level(
+                        prefix,
+                        current_user.unwrap_or(user),
+                        '@',
+                        data::user::AccessLevel::Oper,
+                    ))
+                    .map(|(channel, (_, action))| {
+                        Message::ToggleAccessLevel(
+                            server.clone(),
+                            channel.clone(),
+                            user.nickname().to_owned(),
+                            action,
                         )
-                    } else {
-                        (String::new(), None)
-                    };
+                    });
 
-                menu_button(label, message, length, theme)
+                menu_button(label, message, length, focused, theme)
             }
             (
                 Entry::ToggleAccessLevelVoice,
@@ -153,50 +526,106 @@ impl Entry {
                     prefix,
                     channel,
                     user,
+                    current_user,
                     ..
                 },
             ) => {
-                let voice_mode = prefix.iter().find_map(|prefix_map| {
-                    (prefix_map.prefix == '+').then_some(prefix_map.mode)
-                });
-
-                let (label, message) =
-                    if let (Some(channel), Some(voice_mode)) =
-                        (channel, voice_mode)
-                    {
-                        let has_voice = user
-                            .has_access_level(data::user::AccessLevel::Voice);
-                        let prefix = if has_voice { "-" } else { "+" };
-                        let action = format!("{prefix}{voice_mode}");
-
-                        (
-                            format!(
-                                "{} Voice ({action})",
-                                if has_voice { "Take" } else { "Give" }
-                            ),
-                            Some(Message::ToggleAccessLevel(
-                                server.clone(),
-                                channel.clone(),
-                                user.nickname().to_owned(),
-                                action,
-                            )),
+                let message = channel
+                    .zip(toggle_access_level(
+                        prefix,
+                        current_user.unwrap_or(user),
+                        '+',
+                        data::user::AccessLevel::Voice,
+                    ))
+                    .map(|(channel, (_, action))| {
+                        Message::ToggleAccessLevel(
+                            server.clone(),
+                            channel.clone(),
+                            user.nickname().to_owned(),
+                            action,
                         )
-                    } else {
-                        (String::new(), None)
-                    };
+                    });
 
-                menu_button(label, message, length, theme)
+                menu_button(label, message, length, focused, theme)
             }
             (Entry::SendFile, Context::User { server, user, .. }) => {
                 let message = Message::SendFile(server.clone(), user.clone());
 
-                menu_button(
-                    "Send File".to_string(),
-                    Some(message),
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::DccChat, Context::User { server, user, .. }) => {
+                let message = Message::DccChat(server.clone(), user.clone());
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (
+                Entry::InviteTo,
+                Context::User {
+                    server,
+                    channel,
+                    user,
+                    clients,
+                    ..
+                },
+            ) => {
+                // Channels the user can be invited to, excluding the one
+                // they're being viewed in
+                let channels = clients
+                    .get_channels(server)
+                    .filter(|joined| Some(*joined) != channel)
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                if channels.is_empty() {
+                    return menu_button(label, None, length, focused, theme);
+                }
+
+                invite_to(
+                    server,
+                    user.nickname().to_owned(),
+                    channels,
                     length,
+                    focused,
                     theme,
                 )
             }
+            (
+                Entry::CopyHostmask,
+                Context::User {
+                    server,
+                    user,
+                    current_user,
+                    clients,
+                    ..
+                },
+            ) => {
+                // Fetch the userhost from the server if it isn't known yet
+                let message = user
+                    .hostmask()
+                    .or_else(|| current_user.and_then(User::hostmask))
+                    .or_else(|| {
+                        clients.resolve_hostmask(server, user.nickname())
+                    })
+                    .map_or_else(
+                        || {
+                            Message::RequestHostmask(
+                                server.clone(),
+                                user.nickname().to_owned(),
+                            )
+                        },
+                        Message::CopyHostmask,
+                    );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::Monitor, Context::User { server, user, .. }) => {
+                let message = Message::ToggleMonitor(
+                    server.clone(),
+                    user.nickname().to_owned(),
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
             (
                 Entry::UserInfo,
                 Context::User {
@@ -223,12 +652,7 @@ impl Entry {
                     None,
                 );
 
-                menu_button(
-                    "Local Time (TIME)".to_string(),
-                    Some(message),
-                    length,
-                    theme,
-                )
+                menu_button(label, Some(message), length, focused, theme)
             }
             (Entry::CtcpRequestVersion, Context::User { server, user, .. }) => {
                 let message = Message::CtcpRequest(
@@ -238,22 +662,229 @@ impl Entry {
                     None,
                 );
 
-                menu_button(
-                    "Client (VERSION)".to_string(),
-                    Some(message),
-                    length,
-                    theme,
-                )
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CtcpRequestPing, Context::User { server, user, .. }) => {
+                let message = Message::CtcpRequest(
+                    ctcp::Command::Ping,
+                    server.clone(),
+                    user.nickname().to_owned(),
+                    None,
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (
+                Entry::CtcpRequestClientInfo,
+                Context::User { server, user, .. },
+            ) => {
+                let message = Message::CtcpRequest(
+                    ctcp::Command::ClientInfo,
+                    server.clone(),
+                    user.nickname().to_owned(),
+                    None,
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CtcpRequestSource, Context::User { server, user, .. }) => {
+                let message = Message::CtcpRequest(
+                    ctcp::Command::Source,
+                    server.clone(),
+                    user.nickname().to_owned(),
+                    None,
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (
+                Entry::CtcpRequestUserInfo,
+                Context::User { server, user, .. },
+            ) => {
+                let message = Message::CtcpRequest(
+                    ctcp::Command::UserInfo,
+                    server.clone(),
+                    user.nickname().to_owned(),
+                    None,
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (
+                Entry::Custom(index),
+                Context::User {
+                    server,
+                    channel,
+                    user,
+                    ..
+                },
+            ) => {
+                let Some(UserEntry::Custom(entry)) =
+                    config.buffer.context_menu.user.get(index)
+                else {
+                    return row![].into();
+                };
+
+                let message = Message::Command(
+                    server.clone(),
+                    channel.cloned(),
+                    entry.command(user.nickname(), channel, server),
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (
+                entry @ (Entry::Kill | Entry::Gline),
+                Context::User {
+                    server,
+                    channel,
+                    user,
+                    current_user,
+                    clients,
+                    ..
+                },
+            ) => {
+                let message = clients.oper_actions(server).map(|oper| {
+                    let template = if matches!(entry, Entry::Kill) {
+                        &oper.kill
+                    } else {
+                        &oper.gline
+                    };
+
+                    let buffer = channel.map_or_else(
+                        || buffer::Upstream::Server(server.clone()),
+                        |channel| {
+                            buffer::Upstream::Channel(
+                                server.clone(),
+                                channel.clone(),
+                            )
+                        },
+                    );
+
+                    Message::InsertInput(
+                        buffer,
+                        config::server::Oper::action_command(
+                            template,
+                            current_user.unwrap_or(user),
+                        ),
+                    )
+                });
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (Entry::CopyChannelName, Context::Channel { channel, .. }) => {
+                let message = Message::CopyChannelName(channel.clone());
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::SetTopic, Context::Channel { server, channel }) => {
+                let message = Message::InsertInput(
+                    buffer::Upstream::Channel(server.clone(), channel.clone()),
+                    format!("/topic {channel} "),
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::ChannelModes, Context::Channel { server, channel }) => {
+                let message = Message::Command(
+                    server.clone(),
+                    Some(channel.clone()),
+                    format!("/mode {channel}"),
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::ChannelInfo, Context::Channel { server, channel }) => {
+                let message =
+                    Message::ChannelInfo(server.clone(), channel.clone());
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::AccessList, Context::Channel { server, channel }) => {
+                let message =
+                    Message::AccessList(server.clone(), channel.clone());
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::OpenLogFile, Context::Channel { server, channel }) => {
+                let message = Message::OpenLogFile(buffer::Upstream::Channel(
+                    server.clone(),
+                    channel.clone(),
+                ));
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::Part, Context::Channel { server, channel }) => {
+                let message = Message::Part(buffer::Upstream::Channel(
+                    server.clone(),
+                    channel.clone(),
+                ));
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::PartWithReason, Context::Channel { server, channel }) => {
+                let message = Message::InsertInput(
+                    buffer::Upstream::Channel(server.clone(), channel.clone()),
+                    format!("/part {channel} "),
+                );
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CloseBuffer, Context::Channel { server, channel }) => {
+                let message = Message::CloseBuffer(buffer::Upstream::Channel(
+                    server.clone(),
+                    channel.clone(),
+                ));
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CopyTopic, Context::Topic { content, .. }) => {
+                let message = Message::CopyMessage(content.text().into_owned());
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CopySetter, Context::Topic { who, .. }) => {
+                let message =
+                    who.map(|who| Message::CopySender(who.to_owned()));
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (
+                Entry::EditTopic,
+                Context::Topic {
+                    content, editable, ..
+                },
+            ) => {
+                let message = editable
+                    .then(|| Message::EditTopic(content.text().into_owned()));
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (Entry::OpenUrl, Context::Url(url)) => {
+                let message = Message::OpenUrl(url.clone());
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::OpenWith(index), Context::Url(url)) => {
+                let message = config
+                    .buffer
+                    .context_menu
+                    .open_with
+                    .get(index)
+                    .and_then(|open_with| open_with.command(url))
+                    .map(|(program, args)| Message::OpenWith(program, args));
+
+                menu_button(label, message, length, focused, theme)
             }
             (Entry::CopyUrl, Context::Url(url)) => {
                 let message = Message::CopyUrl(url.clone());
 
-                menu_button(
-                    "Copy URL".to_string(),
-                    Some(message),
-                    length,
-                    theme,
-                )
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CopyTitle, Context::Url(url)) => {
+                let message = Message::CopyTitle(url.clone());
+
+                menu_button(label, Some(message), length, focused, theme)
             }
             (Entry::Timestamp, Context::Timestamp(date_time)) => {
                 let message = Message::CopyTimestamp(
@@ -261,33 +892,124 @@ impl Entry {
                     config.buffer.timestamp.copy_format.clone(),
                 );
 
-                menu_button(
-                    format!(
-                        "{}",
-                        date_time.with_timezone(&Local).format(
-                            &config.buffer.timestamp.context_menu_format
-                        )
-                    ),
-                    Some(message),
-                    length,
-                    theme,
-                )
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CopyMessage, Context::Message(message)) => {
+                let message = Message::CopyMessage(message.text());
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::CopySender, Context::Message(message)) => {
+                let message = sender(message).map(Message::CopySender);
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (Entry::QuoteMessage, Context::Message(message)) => {
+                let message = sender(message)
+                    .map(|nick| Message::QuoteMessage(nick, message.text()));
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (Entry::Reply, Context::Message(message)) => {
+                let message = sender(message).map(Message::Reply);
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (Entry::ReplyStatusMessage, Context::Message(message)) => {
+                let message = match &message.target {
+                    data::message::Target::Channel { channel, .. } => {
+                        Some(Message::ReplyStatusMessage(channel.to_string()))
+                    }
+                    _ => None,
+                };
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (Entry::CopyRawModes, Context::Message(message)) => {
+                let message = message.raw_modes().map(Message::CopyMessage);
+
+                menu_button(label, message, length, focused, theme)
+            }
+            (Entry::Share, Context::Message(message)) => {
+                let message = Message::Share(Box::new((*message).clone()));
+
+                menu_button(label, Some(message), length, focused, theme)
+            }
+            (Entry::MessageDetails, Context::Message(message)) => {
+                let message =
+                    Message::MessageDetails(Box::new((*message).clone()));
+
+                menu_button(label, Some(message), length, focused, theme)
             }
             _ => row![].into(),
         })
     }
 }
 
+// Drops separators which don't separate anything, as entries unavailable in
+// the context are left out
+fn separated(entries: impl IntoIterator<Item = Entry>) -> Vec<Entry> {
+    let mut separated = vec![];
+
+    for entry in entries {
+        if matches!(entry, Entry::HorizontalRule)
+            && separated
+                .last()
+                .is_none_or(|last| matches!(last, Entry::HorizontalRule))
+        {
+            continue;
+        }
+
+        separated.push(entry);
+    }
+
+    if matches!(separated.last(), Some(Entry::HorizontalRule)) {
+        separated.pop();
+    }
+
+    separated
+}
+
+fn sender(message: &data::Message) -> Option<Nick> {
+    match message.target.source() {
+        data::message::Source::User(user) => Some(user.nickname().to_owned()),
+        _ => None,
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum Message {
     Whois(Server, Nick),
     Query(Server, target::Query, BufferAction),
     ToggleAccessLevel(Server, target::Channel, Nick, String),
     SendFile(Server, User),
+    DccChat(Server, User),
+    CopyHostmask(String),
+    RequestHostmask(Server, Nick),
+    ToggleMonitor(Server, Nick),
     InsertNickname(Nick),
     CtcpRequest(ctcp::Command, Server, Nick, Option<String>),
+    Command(Server, Option<target::Channel>, String),
+    InsertInput(buffer::Upstream, String),
+    Part(buffer::Upstream),
+    CloseBuffer(buffer::Upstream),
+    OpenLogFile(buffer::Upstream),
+    CopyChannelName(target::Channel),
+    EditTopic(String),
+    OpenUrl(String),
+    OpenWith(String, Vec<String>),
     CopyUrl(String),
+    CopyTitle(String),
     CopyTimestamp(DateTime<Utc>, Option<String>),
+    CopyMessage(String),
+    CopySender(Nick),
+    QuoteMessage(Nick, String),
+    Reply(Nick),
+    ReplyStatusMessage(String),
+    Share(Box<data::Message>),
+    MessageDetails(Box<data::Message>),
+    ChannelInfo(Server, target::Channel),
+    AccessList(Server, target::Channel),
 }
 
 #[derive(Debug, Clone)]
@@ -296,10 +1018,33 @@ pub enum Event {
     OpenQuery(Server, target::Query, BufferAction),
     ToggleAccessLevel(Server, target::Channel, Nick, String),
     SendFile(Server, User),
+    DccChat(Server, User),
+    CopyHostmask(String),
+    RequestHostmask(Server, Nick),
+    ToggleMonitor(Server, Nick),
     InsertNickname(Nick),
     CtcpRequest(ctcp::Command, Server, Nick, Option<String>),
+    RunCommand(Server, Option<target::Channel>, String),
+    InsertInput(buffer::Upstream, String),
+    LeaveBuffer(buffer::Upstream),
+    CloseBuffer(buffer::Upstream),
+    OpenLogFile(buffer::Upstream),
+    CopyChannelName(target::Channel),
+    EditTopic(String),
+    OpenUrl(String),
+    OpenWith(String, Vec<String>),
     CopyUrl(String),
+    CopyTitle(String),
     CopyTimestamp(DateTime<Utc>, Option<String>),
+    CopyMessage(String),
+    CopySender(Nick),
+    QuoteMessage(Nick, String),
+    Reply(Nick),
+    ReplyStatusMessage(String),
+    Share(Box<data::Message>),
+    MessageDetails(Box<data::Message>),
+    ChannelInfo(Server, target::Channel),
+    AccessList(Server, target::Channel),
 }
 
 pub fn update(message: Message) -> Event {
@@ -312,14 +1057,49 @@ pub fn update(message: Message) -> Event {
             Event::ToggleAccessLevel(server, target, nick, mode)
         }
         Message::SendFile(server, user) => Event::SendFile(server, user),
+        Message::DccChat(server, user) => Event::DccChat(server, user),
+        Message::CopyHostmask(hostmask) => Event::CopyHostmask(hostmask),
+        Message::RequestHostmask(server, nick) => {
+            Event::RequestHostmask(server, nick)
+        }
+        Message::ToggleMonitor(server, nick) => {
+            Event::ToggleMonitor(server, nick)
+        }
         Message::InsertNickname(nick) => Event::InsertNickname(nick),
         Message::CtcpRequest(command, server, nick, params) => {
             Event::CtcpRequest(command, server, nick, params)
         }
+        Message::Command(server, channel, command) => {
+            Event::RunCommand(server, channel, command)
+        }
+        Message::InsertInput(buffer, text) => Event::InsertInput(buffer, text),
+        Message::Part(buffer) => Event::LeaveBuffer(buffer),
+        Message::CloseBuffer(buffer) => Event::CloseBuffer(buffer),
+        Message::OpenLogFile(buffer) => Event::OpenLogFile(buffer),
+        Message::CopyChannelName(channel) => Event::CopyChannelName(channel),
+        Message::EditTopic(topic) => Event::EditTopic(topic),
+        Message::OpenUrl(url) => Event::OpenUrl(url),
+        Message::OpenWith(program, args) => Event::OpenWith(program, args),
         Message::CopyUrl(url) => Event::CopyUrl(url),
+        Message::CopyTitle(url) => Event::CopyTitle(url),
         Message::CopyTimestamp(date_time, format) => {
             Event::CopyTimestamp(date_time, format)
         }
+        Message::CopyMessage(text) => Event::CopyMessage(text),
+        Message::CopySender(nick) => Event::CopySender(nick),
+        Message::QuoteMessage(nick, text) => Event::QuoteMessage(nick, text),
+        Message::Reply(nick) => Event::Reply(nick),
+        Message::ReplyStatusMessage(target) => {
+            Event::ReplyStatusMessage(target)
+        }
+        Message::Share(message) => Event::Share(message),
+        Message::MessageDetails(message) => Event::MessageDetails(message),
+        Message::ChannelInfo(server, channel) => {
+            Event::ChannelInfo(server, channel)
+        }
+        Message::AccessList(server, channel) => {
+            Event::AccessList(server, channel)
+        }
     }
 }
 
@@ -331,11 +1111,17 @@ pub fn user<'a>(
     user: &'a User,
     current_user: Option<&'a User>,
     our_user: Option<&'a User>,
+    clients: &'a data::client::Map,
     config: &'a Config,
     theme: &'a Theme,
     click: &'a config::buffer::NicknameClickAction,
 ) -> Element<'a, Message> {
-    let entries = Entry::user_list(channel.is_some(), our_user);
+    let entries = Entry::user_list(
+        channel.is_some(),
+        our_user,
+        clients.oper_actions(server).is_some(),
+        config,
+    );
 
     let message = match click {
         data::config::buffer::NicknameClickAction::OpenQuery => Message::Query(
@@ -356,7 +1142,7 @@ pub fn user<'a>(
         context_menu::ToggleBehavior::KeepOpen,
         base,
         entries,
-        move |entry, length| {
+        move |entry, length, focused| {
             entry.view(
                 Some(Context::User {
                     server,
@@ -364,13 +1150,59 @@ pub fn user<'a>(
                     channel,
                     user,
                     current_user,
+                    clients,
                 }),
                 length,
+                focused,
+                config,
+                theme,
+            )
+        },
+    )
+    .labels(move |entry| {
+        entry.label(
+            &Context::User {
+                server,
+                prefix,
+                channel,
+                user,
+                current_user,
+                clients,
+            },
+            config,
+        )
+    })
+    .into()
+}
+
+pub fn channel<'a>(
+    content: impl Into<Element<'a, Message>>,
+    server: &'a Server,
+    channel: &'a target::Channel,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let entries = Entry::channel_list();
+
+    context_menu(
+        context_menu::MouseButton::default(),
+        context_menu::Anchor::Cursor,
+        context_menu::ToggleBehavior::KeepOpen,
+        content,
+        entries,
+        move |entry, length, focused| {
+            entry.view(
+                Some(Context::Channel { server, channel }),
+                length,
+                focused,
                 config,
                 theme,
             )
         },
     )
+    .labels(move |entry| {
+        entry.label(&Context::Channel { server, channel }, config)
+    })
     .into()
 }
 
@@ -380,7 +1212,7 @@ pub fn timestamp<'a>(
     config: &'a Config,
     theme: &'a Theme,
 ) -> Element<'a, Message> {
-    let entries = Entry::timestamp_list();
+    let entries = Entry::timestamp_list(config);
 
     context_menu(
         context_menu::MouseButton::default(),
@@ -388,15 +1220,77 @@ pub fn timestamp<'a>(
         context_menu::ToggleBehavior::KeepOpen,
         content,
         entries,
-        move |entry, length| {
+        move |entry, length, focused| {
             entry.view(
                 Some(Context::Timestamp(date_time)),
                 length,
+                focused,
                 config,
                 theme,
             )
         },
     )
+    .labels(move |entry| entry.label(&Context::Timestamp(date_time), config))
+    .into()
+}
+
+fn invite_to<'a>(
+    server: &Server,
+    nick: Nick,
+    channels: Vec<target::Channel>,
+    length: Length,
+    focused: bool,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let server = server.clone();
+    let entries = (0..channels.len()).collect();
+    let labels = channels.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    let base = container(
+        text(format!("{} \u{203a}", i18n::tr("context-menu-invite-to")))
+            .style(theme::text::primary)
+            .font_maybe(theme::font_style::primary(theme).map(font::get)),
+    )
+    .padding(5)
+    .width(length)
+    .style(move |theme| {
+        if focused {
+            container::Style {
+                background: Some(
+                    theme.styles().buttons.primary.background_hover.into(),
+                ),
+                ..container::Style::default()
+            }
+        } else {
+            container::Style::default()
+        }
+    });
+
+    context_menu(
+        context_menu::MouseButton::Left,
+        context_menu::Anchor::Widget,
+        context_menu::ToggleBehavior::Close,
+        base,
+        entries,
+        move |index: usize, length, focused| {
+            let channel = &channels[index];
+
+            let message = Message::Command(
+                server.clone(),
+                Some(channel.clone()),
+                format!("/invite {nick} {channel}"),
+            );
+
+            menu_button(
+                channel.to_string(),
+                Some(message),
+                length,
+                focused,
+                theme,
+            )
+        },
+    )
+    .labels(move |index| labels.get(index).cloned())
     .into()
 }
 
@@ -404,6 +1298,7 @@ fn menu_button(
     content: String,
     message: Option<Message>,
     length: Length,
+    focused: bool,
     theme: &Theme,
 ) -> Element<'static, Message> {
     button(
@@ -413,10 +1308,30 @@ fn menu_button(
     )
     .padding(5)
     .width(length)
+    .style(move |theme, status| {
+        theme::button::context_menu(theme, status, focused)
+    })
     .on_press_maybe(message)
     .into()
 }
 
+// Whether the user has the access level, and the mode change toggling it
+fn toggle_access_level(
+    prefix: &[isupport::PrefixMap],
+    user: &User,
+    symbol: char,
+    access_level: data::user::AccessLevel,
+) -> Option<(bool, String)> {
+    let mode = prefix.iter().find_map(|prefix_map| {
+        (prefix_map.prefix == symbol).then_some(prefix_map.mode)
+    })?;
+
+    let has_access_level = user.has_access_level(access_level);
+    let prefix = if has_access_level { "-" } else { "+" };
+
+    Some((has_access_level, format!("{prefix}{mode}")))
+}
+
 fn right_justified_padding() -> Padding {
     padding::all(5).right(5.0 + double_pass::horizontal_expansion())
 }
@@ -432,7 +1347,7 @@ fn user_info<'a>(
         Some(user) => {
             if user.is_away() {
                 Some(
-                    text("(Away)")
+                    text(i18n::tr("context-menu-user-away"))
                         .style(theme::text::secondary)
                         .font_maybe(
                             theme::font_style::secondary(theme).map(font::get),
@@ -444,7 +1359,7 @@ fn user_info<'a>(
             }
         }
         None => Some(
-            text("(Offline)")
+            text(i18n::tr("context-menu-user-offline"))
                 .style(theme::text::secondary)
                 .font_maybe(theme::font_style::secondary(theme).map(font::get))
                 .width(length),
@@ -462,13 +1377,14 @@ fn user_info<'a>(
         .nickname
         .offline
         .is_offline(current_user.is_none());
-    let seed = match config.buffer.nickname.color {
-        data::buffer::Color::Solid => None,
-        data::buffer::Color::Unique => Some(nickname.seed()),
-    };
-
-    let style =
-        theme::text::nickname(theme, seed, is_user_away, is_user_offline);
+    let style = theme::text::nickname(
+        theme,
+        &config.buffer.nickname.color,
+        Some(nickname.seed()),
+        is_user_away,
+        is_user_offline,
+        false,
+    );
 
     let nickname = text(nickname.to_string()).style(move |_| style).font_maybe(
         theme::font_style::nickname(theme, is_user_offline).map(font::get),
@@ -0,0 +1,256 @@
+use data::user::{BridgedName, Nick};
+use data::{Config, User, dcc_chat};
+use iced::widget::{
+    self, Scrollable, button, center, column, container, operation, row,
+    scrollable, text, text_input,
+};
+use iced::{Length, Task, alignment};
+
+use crate::widget::{Element, selectable_text};
+use crate::{Theme, font, icon, theme};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Input(String),
+    Send,
+    Accept,
+    Close,
+}
+
+pub fn view<'a>(
+    state: &'a DccChat,
+    dcc_chats: &'a dcc_chat::Manager,
+    clients: &'a data::client::Map,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let Some(chat) = dcc_chats.get(&state.id) else {
+        return center(container(
+            column![
+                icon::people()
+                    .size(theme::TEXT_SIZE + 3.0)
+                    .style(theme::text::secondary),
+                text("Chat is no longer available")
+                    .style(theme::text::secondary)
+                    .font_maybe(
+                        theme::font_style::secondary(theme).map(font::get)
+                    ),
+            ]
+            .spacing(8)
+            .align_x(iced::Alignment::Center),
+        ))
+        .into();
+    };
+
+    let remote_user = &chat.remote_user;
+    let our_user = clients
+        .nickname(&chat.server)
+        .map(|nick| User::from(Nick::from(nick)));
+
+    let status = status(chat, theme);
+
+    let lines = column(chat.lines.iter().map(|line| {
+        let user = match line.direction {
+            dcc_chat::Direction::Sent => our_user.as_ref(),
+            dcc_chat::Direction::Received => Some(remote_user),
+        };
+
+        self::line(line, user, config, theme)
+    }))
+    .spacing(2)
+    .padding([0, 2]);
+
+    let lines = container(
+        Scrollable::new(lines)
+            .direction(scrollable::Direction::Vertical(
+                scrollable::Scrollbar::new().width(1).scroller_width(1),
+            ))
+            .anchor_bottom()
+            .style(theme::scrollable::hidden),
+    )
+    .width(Length::Fill)
+    .height(Length::Fill);
+
+    let input = text_input("Send message...", &state.input)
+        .id(state.input_id.clone())
+        .on_input_maybe(chat.is_connected().then_some(Message::Input))
+        .on_submit(Message::Send)
+        .padding(8)
+        .style(theme::text_input::primary);
+
+    column![status, lines, input]
+        .spacing(8)
+        .padding(8)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+fn status<'a>(
+    chat: &dcc_chat::DccChat,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let nick = chat.remote_user.nickname();
+
+    let description = match &chat.status {
+        dcc_chat::Status::PendingApproval => {
+            format!("{nick} wants to chat directly. Accept to connect.")
+        }
+        dcc_chat::Status::Queued => {
+            format!("Chat with {nick}. Waiting for open port.")
+        }
+        dcc_chat::Status::Ready => {
+            format!("Chat with {nick}. Waiting for them to connect.")
+        }
+        dcc_chat::Status::Connected => format!("Connected to {nick}."),
+        dcc_chat::Status::Closed { error: None } => {
+            format!("Chat with {nick} closed.")
+        }
+        dcc_chat::Status::Closed { error: Some(error) } => {
+            format!("Chat with {nick} closed: {error}")
+        }
+    };
+
+    let description = container(
+        text(description)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get)),
+    )
+    .width(Length::Fill);
+
+    let accept = matches!(chat.status, dcc_chat::Status::PendingApproval)
+        .then(|| status_button("Accept", Message::Accept, theme));
+
+    let close = status_button(
+        if chat.is_closed() { "Clear" } else { "Close" },
+        Message::Close,
+        theme,
+    );
+
+    row![description, accept, close]
+        .spacing(4)
+        .align_y(alignment::Vertical::Center)
+        .into()
+}
+
+fn status_button<'a>(
+    label: &'a str,
+    message: Message,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    button(
+        text(label)
+            .style(theme::text::primary)
+            .font_maybe(theme::font_style::primary(theme).map(font::get)),
+    )
+    .padding([2, 6])
+    .style(|theme, status| theme::button::secondary(theme, status, false))
+    .on_press(message)
+    .into()
+}
+
+fn line<'a>(
+    line: &'a dcc_chat::Line,
+    user: Option<&User>,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let timestamp =
+        config
+            .buffer
+            .format_timestamp(&line.timestamp)
+            .map(|timestamp| {
+                selectable_text(timestamp)
+                    .style(theme::selectable_text::timestamp)
+                    .font_maybe(
+                        theme::font_style::timestamp(theme).map(font::get),
+                    )
+            });
+
+    let nick = user.map_or_else(String::new, |user| {
+        user.display(false, None, BridgedName::Nickname)
+    });
+
+    if line.is_action {
+        return row![
+            timestamp,
+            selectable_text(format!("{nick} {}", line.text))
+                .style(theme::selectable_text::action)
+                .font_maybe(theme::font_style::action(theme).map(font::get)),
+        ]
+        .into();
+    }
+
+    let nickname = user.cloned().map(|user| {
+        selectable_text(format!("<{nick}> "))
+            .style(move |theme| {
+                theme::selectable_text::topic_nickname(theme, config, &user)
+            })
+            .font_maybe(
+                theme::font_style::nickname(theme, false).map(font::get),
+            )
+    });
+
+    row![
+        timestamp,
+        nickname,
+        selectable_text(&line.text)
+            .style(theme::selectable_text::default)
+            .font_maybe(theme::font_style::primary(theme).map(font::get)),
+    ]
+    .into()
+}
+
+#[derive(Debug, Clone)]
+pub struct DccChat {
+    pub id: dcc_chat::Id,
+    input: String,
+    input_id: widget::Id,
+}
+
+impl DccChat {
+    pub fn new(id: dcc_chat::Id) -> Self {
+        Self {
+            id,
+            input: String::new(),
+            input_id: widget::Id::unique(),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        dcc_chats: &mut dcc_chat::Manager,
+    ) -> Task<Message> {
+        match message {
+            Message::Input(input) => {
+                self.input = input;
+            }
+            Message::Send => {
+                if !self.input.is_empty() {
+                    dcc_chats.send(&self.id, &std::mem::take(&mut self.input));
+                }
+            }
+            Message::Accept => {
+                dcc_chats.approve(&self.id);
+            }
+            Message::Close => {
+                dcc_chats.remove(&self.id);
+            }
+        }
+
+        Task::none()
+    }
+
+    pub fn focus(&self) -> Task<Message> {
+        let input_id = self.input_id.clone();
+
+        operation::is_focused(input_id.clone()).then(move |is_focused| {
+            if is_focused {
+                Task::none()
+            } else {
+                operation::focus(input_id.clone())
+            }
+        })
+    }
+}
@@ -1,13 +1,14 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use data::{Config, file_transfer};
 use iced::widget::{
     Scrollable, button, center, column, container, scrollable, text,
 };
-use iced::{Length, Task};
+use iced::{Length, Task, task};
 
 use crate::widget::{Element, Text};
-use crate::{Theme, font, icon, theme};
+use crate::{Theme, dialog, font, icon, theme};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -59,11 +60,13 @@ pub fn view<'a>(
 }
 
 #[derive(Debug, Default, Clone)]
-pub struct FileTransfers;
+pub struct FileTransfers {
+    save_dialogs: HashMap<file_transfer::Id, task::Handle>,
+}
 
 impl FileTransfers {
     pub fn new() -> Self {
-        FileTransfers
+        Self::default()
     }
 
     pub fn update(
@@ -85,23 +88,21 @@ impl FileTransfers {
                             ));
                         }
                         None => {
-                            return Task::perform(
-                                async move {
-                                    rfd::AsyncFileDialog::new()
-                                        .set_file_name(transfer.filename)
-                                        .save_file()
-                                        .await
-                                        .map(|handle| {
-                                            handle.path().to_path_buf()
-                                        })
-                                },
-                                move |path| Message::SavePathSelected(id, path),
-                            );
+                            let (task, handle) =
+                                dialog::save_file(transfer.filename, || None);
+
+                            self.save_dialogs.insert(id, handle);
+
+                            return task.map(move |path| {
+                                Message::SavePathSelected(id, path)
+                            });
                         }
                     }
                 }
             }
             Message::SavePathSelected(id, path) => {
+                self.save_dialogs.remove(&id);
+
                 if let Some(path) = path {
                     file_transfers.approve(&id, path);
                 }
@@ -103,29 +103,32 @@ pub fn view<'a>(
                             ShownStatus::Historical => false,
                         };
 
-                    let text =
-                        selectable_text(
-                            config.buffer.nickname.brackets.format(
-                                user.display(with_access_levels, truncate),
-                            ),
-                        )
-                        .font_maybe(
-                            theme::font_style::nickname(theme, is_user_offline)
-                                .map(font::get),
+                    let text = selectable_text(
+                        config.buffer.nickname.brackets.format(user.display(
+                            with_access_levels,
+                            truncate,
+                            config.buffer.nickname.bridged_name(),
+                        )),
+                    )
+                    .font_maybe(
+                        theme::font_style::nickname(theme, is_user_offline)
+                            .map(font::get),
+                    )
+                    .style(move |theme| {
+                        theme::selectable_text::nickname(
+                            theme,
+                            config,
+                            match config.buffer.nickname.shown_status {
+                                ShownStatus::Current => {
+                                    current_user.unwrap_or(user)
+                                }
+                                ShownStatus::Historical => user,
+                            },
+                            is_user_offline,
+                            // Every message here highlights us
+                            false,
                         )
-                        .style(move |theme| {
-                            theme::selectable_text::nickname(
-                                theme,
-                                config,
-                                match config.buffer.nickname.shown_status {
-                                    ShownStatus::Current => {
-                                        current_user.unwrap_or(user)
-                                    }
-                                    ShownStatus::Historical => user,
-                                },
-                                is_user_offline,
-                            )
-                        });
+                    });
 
                     let chantypes = clients.get_chantypes(server);
                     let casemapping = clients.get_casemapping(server);
@@ -140,6 +143,7 @@ pub fn view<'a>(
                             user,
                             current_user,
                             None,
+                            clients,
                             config,
                             theme,
                             &config.buffer.nickname.click,
@@ -160,16 +164,25 @@ pub fn view<'a>(
                         theme::selectable_text::default,
                         theme::font_style::primary,
                         Option::<fn(Color) -> Color>::None,
+                        config
+                            .buffer
+                            .formatting
+                            .strips_colors(message.target.buffer_name()),
                         move |link| match link {
                             message::Link::User(_) => {
-                                context_menu::Entry::user_list(true, None)
+                                context_menu::Entry::user_list(
+                                    true,
+                                    None,
+                                    clients.oper_actions(server).is_some(),
+                                    config,
+                                )
                             }
-                            message::Link::Url(_) => {
-                                context_menu::Entry::url_list()
+                            message::Link::Url(url) => {
+                                context_menu::Entry::url_list(url, config)
                             }
                             _ => vec![],
                         },
-                        move |link, entry, length| {
+                        move |link, entry, length, focused| {
                             let context = if let Some(user) = link.user() {
                                 Some(Context::User {
                                     server,
@@ -177,15 +190,32 @@ pub fn view<'a>(
                                     channel: Some(channel),
                                     user,
                                     current_user,
+                                    clients,
                                 })
                             } else {
                                 link.url().map(Context::Url)
                             };
 
                             entry
-                                .view(context, length, config, theme)
+                                .view(context, length, focused, config, theme)
                                 .map(scroll_view::Message::ContextMenu)
                         },
+                        move |link, entry| {
+                            let context = if let Some(user) = link.user() {
+                                Context::User {
+                                    server,
+                                    prefix,
+                                    channel: Some(channel),
+                                    user,
+                                    current_user,
+                                    clients,
+                                }
+                            } else {
+                                Context::Url(link.url()?)
+                            };
+
+                            entry.label(&context, config)
+                        },
                         config,
                     );
 
@@ -243,6 +273,10 @@ pub fn view<'a>(
                         theme::selectable_text::action,
                         theme::font_style::action,
                         Option::<fn(Color) -> Color>::None,
+                        config
+                            .buffer
+                            .formatting
+                            .strips_colors(message.target.buffer_name()),
                         config,
                     );
 
@@ -312,6 +346,9 @@ impl Highlights {
                         channel,
                         message,
                     ) => Some(Event::GoToMessage(server, channel, message)),
+                    scroll_view::Event::RestoreHistory(kind) => history
+                        .restore(kind, clients)
+                        .map(|task| Event::History(Task::future(task))),
                     scroll_view::Event::RequestOlderChatHistory => None,
                     scroll_view::Event::PreviewChanged => None,
                     scroll_view::Event::HidePreview(..) => None,
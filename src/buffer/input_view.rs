@@ -1,14 +1,15 @@
+use std::borrow::Cow;
 use std::time::Duration;
 
 use data::buffer::{self, Autocomplete, Upstream};
 use data::dashboard::BufferAction;
 use data::history::{self, ReadMarker};
 use data::input::{self, Cache, RawInput};
-use data::message::server_time;
+use data::message::{self, server_time};
 use data::rate_limit::TokenPriority;
 use data::target::Target;
-use data::user::Nick;
-use data::{Config, User, client, command};
+use data::user::{BridgedName, Nick};
+use data::{Config, User, client, command, script};
 use iced::widget::{
     self, column, container, operation, row, rule, text, text_input,
 };
@@ -35,6 +36,8 @@ pub enum Event {
     Cleared {
         history_task: Task<history::manager::Message>,
     },
+    /// Prompt for the operator credentials missing from `/oper`
+    OperPrompt { name: Option<String> },
 }
 
 #[derive(Debug, Clone)]
@@ -50,6 +53,13 @@ pub enum Message {
         buffer: Upstream,
         command: command::Irc,
     },
+    /// Output of a program run with `/exec` from `buffer`, and whether it's
+    /// sent to the buffer's target
+    Exec {
+        buffer: Upstream,
+        send: bool,
+        event: client::exec::Event,
+    },
 }
 
 pub fn view<'a>(
@@ -97,15 +107,14 @@ pub fn view<'a>(
             .away
             .is_away(our_user.is_none_or(User::is_away));
 
-        let seed = match config.buffer.nickname.color {
-            data::buffer::Color::Solid => None,
-            data::buffer::Color::Unique => {
-                our_user.map(|user| Some(user.seed()))
-            }
-        }
-        .flatten();
-
-        theme::text::nickname(theme, seed, is_user_away, false)
+        theme::text::nickname(
+            theme,
+            &config.buffer.nickname.color,
+            our_user.map(User::seed),
+            is_user_away,
+            false,
+            false,
+        )
     };
 
     let maybe_our_user =
@@ -115,6 +124,7 @@ pub fn view<'a>(
                     text(user.display(
                         config.buffer.text_input.nickname.show_access_level,
                         None,
+                        BridgedName::Nickname,
                     ))
                     .style(move |_| our_user_style)
                     .font_maybe(
@@ -171,6 +181,10 @@ pub fn view<'a>(
             .error
             .as_deref()
             .map(|error_str| error(error_str, theme)),
+        state
+            .warning
+            .as_deref()
+            .map(|warning_str| warning(warning_str, theme)),
     ]
     .padding([0, 8])
     .spacing(4);
@@ -192,10 +206,58 @@ fn error<'a, 'b, Message: 'a>(
     .into()
 }
 
+/// Whether a parse error is shown while typing, rather than only once the
+/// input is sent, as the input can't become valid by typing more.
+fn shows_while_typing(error: &input::Error) -> bool {
+    match error {
+        input::Error::ExceedsByteLimit { .. } => true,
+        input::Error::Command(command::Error::IncorrectArgCount {
+            actual,
+            max,
+            ..
+        }) => *actual > *max,
+        input::Error::Command(command::Error::MissingSlash) => false,
+        input::Error::Command(command::Error::MissingCommand) => false,
+        input::Error::Command(command::Error::NoModeString) => false,
+        input::Error::Command(command::Error::InvalidModeString) => true,
+        input::Error::Command(command::Error::ArgTooLong { .. }) => true,
+        input::Error::Command(command::Error::TooManyTargets { .. }) => true,
+        input::Error::Command(command::Error::NotPositiveInteger) => true,
+        input::Error::Command(command::Error::InvalidChannelName {
+            ..
+        }) => true,
+        input::Error::Command(command::Error::NoTargets) => true,
+        input::Error::Command(command::Error::InvalidChannel { .. }) => true,
+    }
+}
+
+fn fmt_adjustments(adjustments: &[command::Adjustment]) -> String {
+    adjustments
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn warning<'a, 'b, Message: 'a>(
+    warning: &'b str,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    container(
+        text(warning.to_string())
+            .style(theme::text::tertiary)
+            .font_maybe(theme::font_style::tertiary(theme).map(font::get)),
+    )
+    .padding(8)
+    .style(theme::container::tooltip)
+    .into()
+}
+
 #[derive(Debug, Clone)]
 pub struct State {
     input_id: widget::Id,
     error: Option<String>,
+    warning: Option<String>,
     completion: Completion,
     selected_history: Option<usize>,
 }
@@ -211,6 +273,7 @@ impl State {
         Self {
             input_id: widget::Id::unique(),
             error: None,
+            warning: None,
             completion: Completion::default(),
             selected_history: None,
         }
@@ -222,6 +285,7 @@ impl State {
         buffer: &buffer::Upstream,
         clients: &mut client::Map,
         history: &mut history::Manager,
+        scripts: &script::Manager,
         config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         let current_target = buffer.target();
@@ -289,15 +353,20 @@ impl State {
 
                 history.record_input_history(buffer, message.clone());
 
-                if let Ok(data::input::Parsed::Input(input)) = input::parse(
+                if let Ok(data::input::Parsed::Input(inputs, _)) = input::parse(
                     buffer.clone(),
                     config.buffer.text_input.auto_format,
                     message.as_str(),
                     clients.nickname(buffer.server()),
                     &clients.get_isupport(buffer.server()),
-                ) && let Some(encoded) = input.encoded()
-                {
-                    clients.send(buffer, encoded, TokenPriority::User);
+                    config.buffer.commands.server_limits,
+                    &config.aliases,
+                ) {
+                    for encoded in
+                        inputs.iter().filter_map(input::Input::encoded)
+                    {
+                        clients.send(buffer, encoded, TokenPriority::User);
+                    }
                 }
 
                 (Task::none(), None)
@@ -305,6 +374,7 @@ impl State {
             Message::Input(input) => {
                 // Reset error state
                 self.error = None;
+                self.warning = None;
                 // Reset selected history
                 self.selected_history = None;
 
@@ -335,45 +405,24 @@ impl State {
                 let input =
                     self.completion.complete_emoji(&input).unwrap_or(input);
 
-                if let Err(error) = input::parse(
+                match input::parse(
                     buffer.clone(),
                     config.buffer.text_input.auto_format,
                     &input,
                     clients.nickname(buffer.server()),
                     &clients.get_isupport(buffer.server()),
-                ) && match error {
-                    input::Error::ExceedsByteLimit { .. } => true,
-                    input::Error::Command(
-                        command::Error::IncorrectArgCount {
-                            actual, max, ..
-                        },
-                    ) => actual > max,
-                    input::Error::Command(command::Error::MissingSlash) => {
-                        false
+                    config.buffer.commands.server_limits,
+                    &config.aliases,
+                ) {
+                    Ok(input::Parsed::Input(_, adjustments))
+                        if !adjustments.is_empty() =>
+                    {
+                        self.warning = Some(fmt_adjustments(&adjustments));
                     }
-                    input::Error::Command(command::Error::MissingCommand) => {
-                        false
+                    Err(error) if shows_while_typing(&error) => {
+                        self.error = Some(error.to_string());
                     }
-                    input::Error::Command(command::Error::NoModeString) => {
-                        false
-                    }
-                    input::Error::Command(
-                        command::Error::InvalidModeString,
-                    ) => true,
-                    input::Error::Command(command::Error::ArgTooLong {
-                        ..
-                    }) => true,
-                    input::Error::Command(command::Error::TooManyTargets {
-                        ..
-                    }) => true,
-                    input::Error::Command(
-                        command::Error::NotPositiveInteger,
-                    ) => true,
-                    input::Error::Command(
-                        command::Error::InvalidChannelName { .. },
-                    ) => true,
-                } {
-                    self.error = Some(error.to_string());
+                    _ => (),
                 }
 
                 history.record_text(RawInput {
@@ -393,6 +442,7 @@ impl State {
 
                 // Reset error
                 self.error = None;
+                self.warning = None;
                 // Reset selected history
                 self.selected_history = None;
 
@@ -405,19 +455,53 @@ impl State {
                 } else if !raw_input.is_empty() {
                     self.completion.reset();
 
+                    // Commands registered by scripts take precedence over
+                    // built-in ones
+                    if scripts.run_command(buffer, raw_input) {
+                        history
+                            .record_input_history(buffer, raw_input.to_owned());
+
+                        return (
+                            Task::none(),
+                            Some(Event::InputSent {
+                                history_task: Task::none(),
+                            }),
+                        );
+                    }
+
+                    // Scripts can change or swallow messages before they're
+                    // sent
+                    let text = if raw_input.starts_with('/') {
+                        Cow::Borrowed(raw_input)
+                    } else if let Some(text) =
+                        scripts.on_send(buffer, raw_input)
+                    {
+                        Cow::Owned(text)
+                    } else {
+                        history
+                            .record_input_history(buffer, raw_input.to_owned());
+
+                        return (Task::none(), None);
+                    };
+
                     // Parse input
-                    let input = match input::parse(
+                    let inputs = match input::parse(
                         buffer.clone(),
                         config.buffer.text_input.auto_format,
-                        raw_input,
+                        &text,
                         clients.nickname(buffer.server()),
                         &clients.get_isupport(buffer.server()),
+                        config.buffer.commands.server_limits,
+                        &config.aliases,
                     ) {
                         Ok(input::Parsed::Internal(command)) => {
-                            history.record_input_history(
-                                buffer,
-                                raw_input.to_owned(),
-                            );
+                            // Keep operator passwords out of input history
+                            if !matches!(command, command::Internal::Oper(..)) {
+                                history.record_input_history(
+                                    buffer,
+                                    raw_input.to_owned(),
+                                );
+                            }
 
                             match command {
                                 command::Internal::OpenBuffers(targets) => {
@@ -578,6 +662,9 @@ impl State {
                                                 }
                                                 // If it's a server or query, we want to follow config for actions.
                                                 Upstream::Server(..)
+                                                | Upstream::ServerCategory(
+                                                    ..,
+                                                )
                                                 | Upstream::Query(..) => {
                                                     config
                                                         .actions
@@ -614,6 +701,19 @@ impl State {
 
                                     return (Task::none(), event);
                                 }
+                                command::Internal::Oper(name, password) => {
+                                    return match clients.oper(
+                                        buffer,
+                                        name.clone(),
+                                        password,
+                                    ) {
+                                        Ok(()) => (Task::none(), None),
+                                        Err(_) => (
+                                            Task::none(),
+                                            Some(Event::OperPrompt { name }),
+                                        ),
+                                    };
+                                }
                                 command::Internal::SysInfo => {
                                     return (
                                         iced::system::information()
@@ -621,9 +721,25 @@ impl State {
                                         None,
                                     );
                                 }
+                                command::Internal::Exec(command, send) => {
+                                    let buffer = buffer.clone();
+
+                                    return (
+                                        Task::stream(client::exec::exec(
+                                            command,
+                                            &config.buffer.commands.exec,
+                                        ))
+                                        .map(move |event| Message::Exec {
+                                            buffer: buffer.clone(),
+                                            send,
+                                            event,
+                                        }),
+                                        None,
+                                    );
+                                }
                             }
                         }
-                        Ok(input::Parsed::Input(input)) => input,
+                        Ok(input::Parsed::Input(inputs, _)) => inputs,
                         Err(error) => {
                             self.error = Some(error.to_string());
                             return (Task::none(), None);
@@ -632,74 +748,8 @@ impl State {
 
                     history.record_input_history(buffer, raw_input.to_owned());
 
-                    if let Some(encoded) = input.encoded() {
-                        let sent_time = server_time(&encoded);
-
-                        clients.send(buffer, encoded, TokenPriority::User);
-
-                        if config.buffer.mark_as_read.on_message_sent {
-                            let chantypes =
-                                clients.get_chantypes(buffer.server());
-                            let statusmsg =
-                                clients.get_statusmsg(buffer.server());
-                            let casemapping =
-                                clients.get_casemapping(buffer.server());
-
-                            if let Some(targets) =
-                                input.targets(chantypes, statusmsg, casemapping)
-                            {
-                                for target in targets {
-                                    clients.send_markread(
-                                        buffer.server(),
-                                        target,
-                                        ReadMarker::from_date_time(sent_time),
-                                        TokenPriority::High,
-                                    );
-                                }
-                            }
-                        }
-                    }
-
-                    let mut history_task = Task::none();
-
-                    if let Some(nick) = clients.nickname(buffer.server()) {
-                        let mut user = nick.to_owned().into();
-                        let mut channel_users = None;
-
-                        let chantypes = clients.get_chantypes(buffer.server());
-                        let statusmsg = clients.get_statusmsg(buffer.server());
-                        let casemapping =
-                            clients.get_casemapping(buffer.server());
-
-                        // Resolve our attributes if sending this message in a channel
-                        if let buffer::Upstream::Channel(server, channel) =
-                            &buffer
-                        {
-                            channel_users =
-                                clients.get_channel_users(server, channel);
-
-                            if let Some(user_with_attributes) = clients
-                                .resolve_user_attributes(server, channel, &user)
-                            {
-                                user = user_with_attributes.clone();
-                            }
-                        }
-
-                        history_task = Task::batch(
-                            history
-                                .record_input_message(
-                                    input,
-                                    user,
-                                    channel_users,
-                                    chantypes,
-                                    statusmsg,
-                                    casemapping,
-                                    config,
-                                )
-                                .into_iter()
-                                .map(Task::future),
-                        );
-                    }
+                    let history_task =
+                        send_inputs(inputs, buffer, clients, history, config);
 
                     (Task::none(), Some(Event::InputSent { history_task }))
                 } else {
@@ -822,6 +872,72 @@ impl State {
             // Capture escape so that closing context menu or commands/emojis picker
             // does not defocus input
             Message::Escape => (Task::none(), None),
+            Message::Exec {
+                buffer,
+                send,
+                event,
+            } => {
+                // Routed to the buffer it was run from, even if the pane
+                // has since switched to another buffer
+                let buffer = &buffer;
+
+                let history_task = match event {
+                    client::exec::Event::Output(line) if send => {
+                        let Some(target) = buffer.target() else {
+                            return (Task::none(), None);
+                        };
+
+                        // Blank lines can't be sent
+                        if line.trim().is_empty() {
+                            return (Task::none(), None);
+                        }
+
+                        let input = data::Input::command(
+                            buffer.clone(),
+                            command::Irc::Msg(target.to_string(), line),
+                        );
+
+                        send_inputs(
+                            vec![input],
+                            buffer,
+                            clients,
+                            history,
+                            config,
+                        )
+                    }
+                    client::exec::Event::Output(line) => {
+                        let message = data::Message::local(
+                            buffer,
+                            message::Source::Server(None),
+                            message::plain(line),
+                        );
+
+                        record_local_message(
+                            message, buffer, clients, history, config,
+                        )
+                    }
+                    client::exec::Event::Finished(Ok(())) => {
+                        return (Task::none(), None);
+                    }
+                    client::exec::Event::Finished(Err(error)) => {
+                        let message = data::Message::local(
+                            buffer,
+                            message::Source::Internal(
+                                message::source::Internal::Status(
+                                    message::source::Status::Error,
+                                ),
+                            ),
+                            message::plain(error.to_string()),
+                        );
+
+                        record_local_message(
+                            message, buffer, clients, history, config,
+                        )
+                    }
+                };
+
+                (Task::none(), Some(Event::InputSent { history_task }))
+            }
             Message::SendCommand { buffer, command } => {
                 let input =
                     data::Input::command(buffer.clone(), command).encoded();
@@ -872,6 +988,7 @@ impl State {
 
     pub fn reset(&mut self) {
         self.error = None;
+        self.warning = None;
         self.completion = Completion::default();
         self.selected_history = None;
     }
@@ -910,7 +1027,125 @@ impl State {
         operation::move_cursor_to_end(self.input_id.clone())
     }
 
+    /// Replaces the input text with the result of `edit` applied to the
+    /// current input text.
+    pub fn edit(
+        &mut self,
+        buffer: buffer::Upstream,
+        history: &mut history::Manager,
+        edit: impl FnOnce(&str) -> String,
+    ) -> Task<Message> {
+        let text = edit(&history.input(&buffer).text);
+
+        history.record_text(RawInput {
+            buffer: buffer.clone(),
+            text: text.clone(),
+        });
+
+        history.record_draft(RawInput { buffer, text });
+
+        operation::move_cursor_to_end(self.input_id.clone())
+    }
+
     pub fn close_picker(&mut self) -> bool {
         self.completion.close_picker()
     }
 }
+
+/// Records `message`, which isn't sent anywhere, into `buffer`.
+fn record_local_message(
+    message: data::Message,
+    buffer: &buffer::Upstream,
+    clients: &client::Map,
+    history: &mut history::Manager,
+    config: &Config,
+) -> Task<history::manager::Message> {
+    history
+        .record_message(
+            buffer.server(),
+            clients.get_casemapping(buffer.server()),
+            message,
+            &config.buffer,
+        )
+        .map_or_else(Task::none, Task::future)
+}
+
+/// Sends `inputs` from `buffer`, returning the task recording them into
+/// history.
+pub fn send_inputs(
+    inputs: Vec<input::Input>,
+    buffer: &buffer::Upstream,
+    clients: &mut client::Map,
+    history: &mut history::Manager,
+    config: &Config,
+) -> Task<history::manager::Message> {
+    for input in &inputs {
+        let Some(encoded) = input.encoded() else {
+            continue;
+        };
+
+        let sent_time = server_time(&encoded);
+
+        clients.send(buffer, encoded, TokenPriority::User);
+
+        if config.buffer.mark_as_read.on_message_sent {
+            let chantypes = clients.get_chantypes(buffer.server());
+            let statusmsg = clients.get_statusmsg(buffer.server());
+            let casemapping = clients.get_casemapping(buffer.server());
+
+            if let Some(targets) =
+                input.targets(chantypes, statusmsg, casemapping)
+            {
+                for target in targets {
+                    clients.send_markread(
+                        buffer.server(),
+                        target,
+                        ReadMarker::from_date_time(sent_time),
+                        TokenPriority::High,
+                    );
+                }
+            }
+        }
+    }
+
+    let mut history_task = Task::none();
+
+    if let Some(nick) = clients.nickname(buffer.server()) {
+        let mut user = nick.to_owned().into();
+        let mut channel_users = None;
+
+        let chantypes = clients.get_chantypes(buffer.server());
+        let statusmsg = clients.get_statusmsg(buffer.server());
+        let casemapping = clients.get_casemapping(buffer.server());
+
+        // Resolve our attributes if sending this message in a channel
+        if let buffer::Upstream::Channel(server, channel) = &buffer {
+            channel_users = clients.get_channel_users(server, channel);
+
+            if let Some(user_with_attributes) =
+                clients.resolve_user_attributes(server, channel, &user)
+            {
+                user = user_with_attributes.clone();
+            }
+        }
+
+        history_task = Task::batch(
+            inputs
+                .into_iter()
+                .flat_map(|input| {
+                    history.record_input_message(
+                        input,
+                        user.clone(),
+                        channel_users,
+                        chantypes,
+                        statusmsg,
+                        casemapping,
+                        config,
+                    )
+                })
+                .map(Task::future),
+        );
+    }
+
+    history_task
+}
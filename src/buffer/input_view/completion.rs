@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::sync::LazyLock;
@@ -6,6 +7,7 @@ use std::{fmt, iter};
 use chrono::{DateTime, Utc};
 use const_format::concatcp;
 use data::buffer::{OrderBy, SkinTone, SortDirection};
+use data::config::{Aliases, alias};
 use data::isupport::{self, find_target_limit};
 use data::target::{self, Target};
 use data::user::{ChannelUsers, Nick, NickRef};
@@ -22,6 +24,8 @@ use crate::widget::{Element, double_pass};
 
 const MAX_SHOWN_COMMAND_ENTRIES: usize = 5;
 const MAX_SHOWN_EMOJI_ENTRIES: usize = 8;
+const ALIAS_ARGUMENTS: [&str; 9] =
+    ["$1", "$2", "$3", "$4", "$5", "$6", "$7", "$8", "$9"];
 
 #[derive(Debug, Clone, Default)]
 pub struct Completion {
@@ -66,6 +70,7 @@ impl Completion {
                 current_target,
                 supports_detach,
                 isupport,
+                &config.aliases,
             );
 
             // Disallow user completions when selecting a command
@@ -294,6 +299,7 @@ impl Commands {
         current_target: Option<&Target>,
         supports_detach: bool,
         isupport: &HashMap<isupport::Kind, isupport::Parameter>,
+        aliases: &Aliases,
     ) {
         let Some((head, rest)) = input.split_once('/') else {
             *self = Self::Idle;
@@ -316,7 +322,7 @@ impl Commands {
             // MOTD
             {
                 Command {
-                    title: "MOTD",
+                    title: Cow::Borrowed("MOTD"),
                     args: vec![Argument {
                         text: "server",
                         kind: ArgumentKind::Optional { skipped: false },
@@ -328,7 +334,7 @@ impl Commands {
             // QUIT
             {
                 Command {
-                    title: "QUIT",
+                    title: Cow::Borrowed("QUIT"),
                     args: vec![Argument {
                         text: "reason",
                         kind: ArgumentKind::Optional { skipped: false },
@@ -468,7 +474,7 @@ impl Commands {
             // ME
             {
                 Command {
-                    title: "ME",
+                    title: Cow::Borrowed("ME"),
                     args: vec![Argument {
                         text: "action",
                         kind: ArgumentKind::Required,
@@ -497,7 +503,7 @@ impl Commands {
                 }
 
                 Command {
-                    title: "MODE",
+                    title: Cow::Borrowed("MODE"),
                     args: vec![Argument {
                         text: "target",
                         kind: if default.is_some() {
@@ -516,7 +522,7 @@ impl Commands {
             // RAW
             {
                 Command {
-                    title: "RAW",
+                    title: Cow::Borrowed("RAW"),
                     args: vec![
                         Argument {
                             text: "command",
@@ -535,7 +541,7 @@ impl Commands {
             // FORMAT
             {
                 Command {
-                    title: "FORMAT",
+                    title: Cow::Borrowed("FORMAT"),
                     args: vec![Argument {
                         text: "text",
                         kind: ArgumentKind::Required,
@@ -549,7 +555,7 @@ impl Commands {
             // HOP
             {
                 Command {
-                    title: "HOP",
+                    title: Cow::Borrowed("HOP"),
                     args: vec![
                         Argument {
                             text: "channel",
@@ -567,18 +573,62 @@ impl Commands {
                     subcommands: None,
                 }
             },
+            // OPER
+            {
+                Command {
+                    title: Cow::Borrowed("OPER"),
+                    args: vec![
+                        Argument {
+                            text: "name",
+                            kind: ArgumentKind::Optional { skipped: false },
+                            tooltip: Some(String::from(
+                                "the operator name, defaults to the configured one",
+                            )),
+                        },
+                        Argument {
+                            text: "password",
+                            kind: ArgumentKind::Optional { skipped: false },
+                            tooltip: Some(String::from(
+                                "the operator password, defaults to the configured password or challenge key",
+                            )),
+                        },
+                    ],
+                    subcommands: None,
+                }
+            },
             // SYSINFO
             {
                 Command {
-                    title: "SYSINFO",
+                    title: Cow::Borrowed("SYSINFO"),
                     args: vec![],
                     subcommands: None,
                 }
             },
+            // EXEC
+            {
+                Command {
+                    title: Cow::Borrowed("EXEC"),
+                    args: vec![
+                        Argument {
+                            text: "-o",
+                            kind: ArgumentKind::Optional { skipped: false },
+                            tooltip: Some(String::from(
+                                "send the output to the channel or query instead of only showing it",
+                            )),
+                        },
+                        Argument {
+                            text: "command",
+                            kind: ArgumentKind::Required,
+                            tooltip: None,
+                        },
+                    ],
+                    subcommands: None,
+                }
+            },
             // CLEAR
             {
                 Command {
-                    title: "CLEAR",
+                    title: Cow::Borrowed("CLEAR"),
                     args: vec![],
                     subcommands: None,
                 }
@@ -590,7 +640,7 @@ impl Commands {
                     .map(target::Channel::to_string);
 
                 Command {
-                    title: "CLEARTOPIC",
+                    title: Cow::Borrowed("CLEARTOPIC"),
                     args: vec![Argument {
                         text: "channel",
                         kind: if default.is_some() {
@@ -612,7 +662,7 @@ impl Commands {
                     .map(target::Query::to_string);
 
                 Command {
-                title: "CTCP",
+                title: Cow::Borrowed("CTCP"),
                 args: vec![
                     Argument {
                         text: "nick",
@@ -702,6 +752,16 @@ impl Commands {
 
         command_list.extend(isupport_commands);
 
+        // Aliases take precedence over the commands they share a name with
+        command_list.retain(|command| aliases.get(&command.title).is_none());
+
+        let command_list = aliases
+            .iter()
+            .sorted_by_key(|(name, _)| *name)
+            .map(|(name, command)| alias_command(name, command))
+            .chain(command_list)
+            .collect::<Vec<_>>();
+
         match self {
             // Command not fully typed, show filtered entries
             _ if !has_space => {
@@ -753,7 +813,7 @@ impl Commands {
 
         if let Self::Selected { command, .. } = self {
             // Mark skipped arguments as skipped
-            match command.title {
+            match command.title.as_ref() {
                 "CTCP" => {
                     if let Some(nick) = rest.split_ascii_whitespace().nth(1)
                         && matches!(
@@ -770,6 +830,14 @@ impl Commands {
                         nick.kind.skip();
                     }
                 }
+                "EXEC" => {
+                    if let Some(first) = rest.split_ascii_whitespace().nth(1)
+                        && first != "-o"
+                        && let Some(output) = command.args.get_mut(0)
+                    {
+                        output.kind.skip();
+                    }
+                }
                 "KICK" => {
                     if let Some(channel) = rest.split_ascii_whitespace().nth(1)
                     {
@@ -819,7 +887,7 @@ impl Commands {
                             .saturating_sub(1),
                     )
                 {
-                    let subcmd = (String::from(command.title) + " " + subcmd)
+                    let subcmd = (command.title.to_string() + " " + subcmd)
                         .to_lowercase();
 
                     let subcommand = subcommands.iter().find(|subcommand| {
@@ -942,7 +1010,12 @@ impl Commands {
                 subcommand,
             } => {
                 if config.buffer.commands.show_description {
-                    Some(command.view(input, subcommand.as_ref(), theme))
+                    Some(command.view(
+                        input,
+                        subcommand.as_ref(),
+                        &config.aliases,
+                        theme,
+                    ))
                 } else {
                     None
                 }
@@ -953,7 +1026,7 @@ impl Commands {
 
 #[derive(Debug, Clone)]
 pub struct Command {
-    title: &'static str,
+    title: Cow<'static, str>,
     args: Vec<Argument>,
     subcommands: Option<Vec<Command>>,
 }
@@ -964,8 +1037,12 @@ const MODE_USER_PATTERN: &str =
     concatcp!("mode ", REQUIRED_ARG_PREFIX, "user", REQUIRED_ARG_SUFFIX);
 
 impl Command {
-    fn description(&self) -> Option<&'static str> {
-        Some(match self.title.to_lowercase().as_str() {
+    fn description(&self, aliases: &Aliases) -> Option<Cow<'static, str>> {
+        if let Some(command) = aliases.get(&self.title) {
+            return Some(Cow::Owned(format!("Alias for {command}")));
+        }
+
+        Some(Cow::Borrowed(match self.title.to_lowercase().as_str() {
             "away" => {
                 "Mark yourself as away. If already away, the status is removed"
             }
@@ -1013,11 +1090,13 @@ impl Command {
             "clear" => "Clears the buffer",
             "cleartopic" => "Clear the topic of a channel",
             "sysinfo" => "Send system information",
+            "exec" => "Run a program and show its output in the buffer",
+            "oper" => "Authenticate as an IRC operator",
             "detach" => {
                 "Hide the channel, leaving the bouncer's connection to the channel active"
             }
             _ => return None,
-        })
+        }))
     }
 
     fn alias(&self) -> Vec<&str> {
@@ -1045,6 +1124,7 @@ impl Command {
         &self,
         input: &str,
         subcommand: Option<&Command>,
+        aliases: &Aliases,
         theme: &'a Theme,
     ) -> Element<'a, Message> {
         let command_prefix = format!("/{}", self.title.to_lowercase());
@@ -1078,7 +1158,7 @@ impl Command {
             .saturating_sub(1),
         );
 
-        let title = Some(Element::from(text(self.title)));
+        let title = Some(Element::from(text(self.title.to_string())));
 
         let arg_text = |index: usize, arg: &Argument| {
             let content = text(format!("{arg}"))
@@ -1154,8 +1234,9 @@ impl Command {
                         text(
                             subcommand
                                 .title
-                                .strip_prefix(self.title)
+                                .strip_prefix(self.title.as_ref())
                                 .unwrap_or_default()
+                                .to_string()
                         )
                         .style(move |theme| {
                             if 0 == active_arg {
@@ -1187,8 +1268,8 @@ impl Command {
 
         container(column![
             subcommand
-                .map_or(self.description(), |subcommand| {
-                    subcommand.description()
+                .map_or(self.description(aliases), |subcommand| {
+                    subcommand.description(aliases)
                 })
                 .map(|description| {
                     text(description).style(theme::text::secondary).font_maybe(
@@ -1439,7 +1520,7 @@ fn away_command(max_len: Option<u16>) -> Command {
     let tooltip = max_len.map(|max_len| format!("maximum length: {max_len}"));
 
     Command {
-        title: "AWAY",
+        title: Cow::Borrowed("AWAY"),
         args: vec![Argument {
             text: "reason",
             kind: ArgumentKind::Optional { skipped: false },
@@ -1451,7 +1532,7 @@ fn away_command(max_len: Option<u16>) -> Command {
 
 fn ctcp_action_command() -> Command {
     Command {
-        title: "CTCP ACTION",
+        title: Cow::Borrowed("CTCP ACTION"),
         args: vec![Argument {
             text: "text",
             kind: ArgumentKind::Required,
@@ -1465,7 +1546,7 @@ fn ctcp_action_command() -> Command {
 
 fn ctcp_clientinfo_command() -> Command {
     Command {
-        title: "CTCP CLIENTINFO",
+        title: Cow::Borrowed("CTCP CLIENTINFO"),
         args: vec![],
         subcommands: None,
     }
@@ -1473,7 +1554,7 @@ fn ctcp_clientinfo_command() -> Command {
 
 fn ctcp_userinfo_command() -> Command {
     Command {
-        title: "CTCP USERINFO",
+        title: Cow::Borrowed("CTCP USERINFO"),
         args: vec![],
         subcommands: None,
     }
@@ -1481,7 +1562,7 @@ fn ctcp_userinfo_command() -> Command {
 
 fn ctcp_ping_command() -> Command {
     Command {
-        title: "CTCP PING",
+        title: Cow::Borrowed("CTCP PING"),
         args: vec![Argument {
             text: "info",
             kind: ArgumentKind::Required,
@@ -1495,7 +1576,7 @@ fn ctcp_ping_command() -> Command {
 
 fn ctcp_source_command() -> Command {
     Command {
-        title: "CTCP SOURCE",
+        title: Cow::Borrowed("CTCP SOURCE"),
         args: vec![],
         subcommands: None,
     }
@@ -1503,7 +1584,7 @@ fn ctcp_source_command() -> Command {
 
 fn ctcp_time_command() -> Command {
     Command {
-        title: "CTCP TIME",
+        title: Cow::Borrowed("CTCP TIME"),
         args: vec![],
         subcommands: None,
     }
@@ -1511,7 +1592,7 @@ fn ctcp_time_command() -> Command {
 
 fn ctcp_version_command() -> Command {
     Command {
-        title: "CTCP VERSION",
+        title: Cow::Borrowed("CTCP VERSION"),
         args: vec![],
         subcommands: None,
     }
@@ -1519,7 +1600,7 @@ fn ctcp_version_command() -> Command {
 
 fn chathistory_command(maximum_limit: &u16) -> Command {
     Command {
-        title: "CHATHISTORY",
+        title: Cow::Borrowed("CHATHISTORY"),
         args: vec![Argument {
             text: "subcommand",
             kind: ArgumentKind::Required,
@@ -1551,7 +1632,7 @@ fn chathistory_after_command(maximum_limit: &u16) -> Command {
     };
 
     Command {
-        title: "CHATHISTORY AFTER",
+        title: Cow::Borrowed("CHATHISTORY AFTER"),
         args: vec![
             Argument {
                 text: "target",
@@ -1583,7 +1664,7 @@ fn chathistory_around_command(maximum_limit: &u16) -> Command {
     };
 
     Command {
-        title: "CHATHISTORY AROUND",
+        title: Cow::Borrowed("CHATHISTORY AROUND"),
         args: vec![
             Argument {
                 text: "target",
@@ -1615,7 +1696,7 @@ fn chathistory_before_command(maximum_limit: &u16) -> Command {
     };
 
     Command {
-        title: "CHATHISTORY BEFORE",
+        title: Cow::Borrowed("CHATHISTORY BEFORE"),
         args: vec![
             Argument {
                 text: "target",
@@ -1647,7 +1728,7 @@ fn chathistory_between_command(maximum_limit: &u16) -> Command {
     };
 
     Command {
-        title: "CHATHISTORY BETWEEN",
+        title: Cow::Borrowed("CHATHISTORY BETWEEN"),
         args: vec![
             Argument {
                 text: "target",
@@ -1686,7 +1767,7 @@ fn chathistory_latest_command(maximum_limit: &u16) -> Command {
     };
 
     Command {
-        title: "CHATHISTORY LATEST",
+        title: Cow::Borrowed("CHATHISTORY LATEST"),
         args: vec![
             Argument {
                 text: "target",
@@ -1719,7 +1800,7 @@ fn chathistory_targets_command(maximum_limit: &u16) -> Command {
     };
 
     Command {
-        title: "CHATHISTORY TARGETS",
+        title: Cow::Borrowed("CHATHISTORY TARGETS"),
         args: vec![
             Argument {
                 text: "timestamp",
@@ -1746,7 +1827,7 @@ fn chathistory_targets_command(maximum_limit: &u16) -> Command {
 }
 
 static CNOTICE_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "CNOTICE",
+    title: Cow::Borrowed("CNOTICE"),
     args: vec![
         Argument {
             text: "nickname",
@@ -1768,7 +1849,7 @@ static CNOTICE_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
 });
 
 static CPRIVMSG_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "CPRIVMSG",
+    title: Cow::Borrowed("CPRIVMSG"),
     args: vec![
         Argument {
             text: "nickname",
@@ -1789,6 +1870,31 @@ static CPRIVMSG_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
     subcommands: None,
 });
 
+fn alias_command(name: &str, command: &str) -> Command {
+    let arguments = alias::Arguments::of(command);
+
+    let positional = ALIAS_ARGUMENTS
+        .into_iter()
+        .take(arguments.positional)
+        .map(|text| Argument {
+            text,
+            kind: ArgumentKind::Required,
+            tooltip: None,
+        });
+
+    let any = arguments.any.then_some(Argument {
+        text: "$*",
+        kind: ArgumentKind::Optional { skipped: false },
+        tooltip: None,
+    });
+
+    Command {
+        title: Cow::Owned(name.to_uppercase()),
+        args: positional.chain(any).collect(),
+        subcommands: None,
+    }
+}
+
 fn detach_command(
     default: Option<String>,
     channel_len: Option<u16>,
@@ -1808,7 +1914,7 @@ fn detach_command(
     }
 
     Command {
-        title: "DETACH",
+        title: Cow::Borrowed("DETACH"),
         args: vec![Argument {
             text: "channels",
             kind: if default.is_some() {
@@ -1865,7 +1971,7 @@ fn join_command(
     }
 
     Command {
-        title: "JOIN",
+        title: Cow::Borrowed("JOIN"),
         args: vec![
             Argument {
                 text: "channels",
@@ -1900,7 +2006,7 @@ fn kick_command(
         max_len.map(|max_len| format!("maximum length: {max_len}"));
 
     Command {
-        title: "KICK",
+        title: Cow::Borrowed("KICK"),
         args: vec![
             Argument {
                 text: "channel",
@@ -1929,7 +2035,7 @@ fn kick_command(
 }
 
 static KNOCK_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "KNOCK",
+    title: Cow::Borrowed("KNOCK"),
     args: vec![
         Argument {
             text: "channel",
@@ -1946,7 +2052,7 @@ static KNOCK_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
 });
 
 static LIST_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "LIST",
+    title: Cow::Borrowed("LIST"),
     args: vec![Argument {
         text: "channels",
         kind: ArgumentKind::Optional { skipped: false },
@@ -1987,7 +2093,7 @@ fn list_command(
         );
 
         Command {
-            title: "LIST",
+            title: Cow::Borrowed("LIST"),
             args: vec![
                 Argument {
                     text: "channels",
@@ -2004,7 +2110,7 @@ fn list_command(
         }
     } else {
         Command {
-            title: "LIST",
+            title: Cow::Borrowed("LIST"),
             args: vec![Argument {
                 text: "channels",
                 kind: ArgumentKind::Optional { skipped: false },
@@ -2017,7 +2123,7 @@ fn list_command(
 
 fn monitor_command(target_limit: &Option<u16>) -> Command {
     Command {
-        title: "MONITOR",
+        title: Cow::Borrowed("MONITOR"),
         args: vec![Argument {
             text: "subcommand",
             kind: ArgumentKind::Required,
@@ -2052,7 +2158,7 @@ fn monitor_add_command(target_limit: &Option<u16>) -> Command {
     }
 
     Command {
-        title: "MONITOR +",
+        title: Cow::Borrowed("MONITOR +"),
         args: vec![Argument {
             text: "targets",
             kind: ArgumentKind::Required,
@@ -2063,7 +2169,7 @@ fn monitor_add_command(target_limit: &Option<u16>) -> Command {
 }
 
 static MONITOR_REMOVE_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "MONITOR -",
+    title: Cow::Borrowed("MONITOR -"),
     args: vec![Argument {
         text: "targets",
         kind: ArgumentKind::Required,
@@ -2073,19 +2179,19 @@ static MONITOR_REMOVE_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
 });
 
 static MONITOR_CLEAR_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "MONITOR C",
+    title: Cow::Borrowed("MONITOR C"),
     args: vec![],
     subcommands: None,
 });
 
 static MONITOR_LIST_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "MONITOR L",
+    title: Cow::Borrowed("MONITOR L"),
     args: vec![],
     subcommands: None,
 });
 
 static MONITOR_STATUS_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "MONITOR S",
+    title: Cow::Borrowed("MONITOR S"),
     args: vec![],
     subcommands: None,
 });
@@ -2167,12 +2273,12 @@ fn mode_channel_command(
     }
 
     Command {
-        title: concatcp!(
+        title: Cow::Borrowed(concatcp!(
             "MODE ",
             REQUIRED_ARG_PREFIX,
             "channel",
             REQUIRED_ARG_SUFFIX
-        ),
+        )),
         args: vec![
             Argument {
                 text: "modestring",
@@ -2203,12 +2309,12 @@ fn mode_user_command(mode_limit: Option<u16>) -> Command {
     }
 
     Command {
-        title: concatcp!(
+        title: Cow::Borrowed(concatcp!(
             "MODE ",
             REQUIRED_ARG_PREFIX,
             "user",
             REQUIRED_ARG_SUFFIX
-        ),
+        )),
         args: vec![Argument {
             text: "modestring",
             kind: ArgumentKind::Optional { skipped: false },
@@ -2251,7 +2357,7 @@ fn msg_command(
     }
 
     Command {
-        title: "MSG",
+        title: Cow::Borrowed("MSG"),
         args: vec![
             Argument {
                 text: "targets",
@@ -2281,7 +2387,7 @@ fn names_command(target_limit: Option<u16>) -> Command {
     }
 
     Command {
-        title: "NAMES",
+        title: Cow::Borrowed("NAMES"),
         args: vec![Argument {
             text: "channels",
             kind: ArgumentKind::Required,
@@ -2295,7 +2401,7 @@ fn nick_command(max_len: Option<u16>) -> Command {
     let tooltip = max_len.map(|max_len| format!("maximum length: {max_len}"));
 
     Command {
-        title: "NICK",
+        title: Cow::Borrowed("NICK"),
         args: vec![Argument {
             text: "nickname",
             kind: ArgumentKind::Required,
@@ -2338,7 +2444,7 @@ fn notice_command(
     }
 
     Command {
-        title: "NOTICE",
+        title: Cow::Borrowed("NOTICE"),
         args: vec![
             Argument {
                 text: "targets",
@@ -2372,7 +2478,7 @@ fn part_command(default: Option<String>, max_len: Option<u16>) -> Command {
     }
 
     Command {
-        title: "PART",
+        title: Cow::Borrowed("PART"),
         args: vec![
             Argument {
                 text: "targets",
@@ -2395,7 +2501,7 @@ fn part_command(default: Option<String>, max_len: Option<u16>) -> Command {
 
 fn setname_command(max_len: &u16) -> Command {
     Command {
-        title: "SETNAME",
+        title: Cow::Borrowed("SETNAME"),
         args: vec![Argument {
             text: "realname",
             kind: ArgumentKind::Required,
@@ -2414,7 +2520,7 @@ fn topic_command(default: Option<String>, max_len: Option<u16>) -> Command {
     }
 
     Command {
-        title: "TOPIC",
+        title: Cow::Borrowed("TOPIC"),
         args: vec![
             Argument {
                 text: "channel",
@@ -2438,7 +2544,7 @@ fn topic_command(default: Option<String>, max_len: Option<u16>) -> Command {
 }
 
 static USERIP_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
-    title: "USERIP",
+    title: Cow::Borrowed("USERIP"),
     args: vec![Argument {
         text: "nickname",
         kind: ArgumentKind::Required,
@@ -2449,7 +2555,7 @@ static USERIP_COMMAND: LazyLock<Command> = LazyLock::new(|| Command {
 
 fn whox_command() -> Command {
     Command {
-        title: "WHO",
+        title: Cow::Borrowed("WHO"),
         args: vec![
             Argument {
                 text: "target",
@@ -2487,7 +2593,7 @@ fn whox_command() -> Command {
 
 fn who_command() -> Command {
     Command {
-        title: "WHO",
+        title: Cow::Borrowed("WHO"),
         args: vec![Argument {
             text: "target",
             kind: ArgumentKind::Required,
@@ -2508,7 +2614,7 @@ fn whois_command(target_limit: Option<u16>) -> Command {
     }
 
     Command {
-        title: "WHOIS",
+        title: Cow::Borrowed("WHOIS"),
         args: vec![
             Argument {
                 text: "server",
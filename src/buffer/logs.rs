@@ -91,6 +91,10 @@ pub fn view<'a>(
                         theme::selectable_text::logs,
                         theme::font_style::primary,
                         Option::<fn(Color) -> Color>::None,
+                        config
+                            .buffer
+                            .formatting
+                            .strips_colors(message.target.buffer_name()),
                         config,
                     );
 
@@ -157,6 +161,9 @@ impl Logs {
                         Some(Event::OpenBuffer(target, buffer_action))
                     }
                     scroll_view::Event::GoToMessage(_, _, _) => None,
+                    scroll_view::Event::RestoreHistory(kind) => history
+                        .restore(kind, clients)
+                        .map(|task| Event::History(Task::future(task))),
                     scroll_view::Event::RequestOlderChatHistory => None,
                     scroll_view::Event::PreviewChanged => None,
                     scroll_view::Event::HidePreview(..) => None,
@@ -1,3 +1,4 @@
+use data::buffer::Density;
 use data::config::buffer::nickname::ShownStatus;
 use data::isupport::{CaseMap, PrefixMap};
 use data::server::Server;
@@ -59,11 +60,32 @@ pub struct ChannelQueryLayout<'a> {
     pub casemapping: CaseMap,
     pub prefix: &'a [PrefixMap],
     pub server: &'a Server,
+    pub clients: &'a data::client::Map,
     pub theme: &'a Theme,
     pub target: TargetInfo<'a>,
+    pub density: Density,
 }
 
 impl<'a> ChannelQueryLayout<'a> {
+    /// Messages reporting links under the cursor, for the url hover card.
+    #[allow(clippy::type_complexity)]
+    fn link_hover(
+        &self,
+    ) -> Option<(fn(message::Link) -> Message, fn(message::Link) -> Message)>
+    {
+        self.config.buffer.url.hover_card.enabled.then_some((
+            Message::LinkHovered as fn(_) -> _,
+            Message::LinkUnhovered as fn(_) -> _,
+        ))
+    }
+
+    fn strips_colors(&self, message: &data::Message) -> bool {
+        self.config
+            .buffer
+            .formatting
+            .strips_colors(message.target.buffer_name())
+    }
+
     fn format_timestamp(
         &self,
         message: &'a data::Message,
@@ -155,8 +177,7 @@ impl<'a> ChannelQueryLayout<'a> {
                         self.config
                             .buffer
                             .status_message_prefix
-                            .brackets
-                            .format(String::from_iter(prefixes))
+                            .format(prefixes)
                     ))
                     .style(theme::selectable_text::tertiary)
                     .font_maybe(
@@ -178,6 +199,7 @@ impl<'a> ChannelQueryLayout<'a> {
         message: &'a data::Message,
         max_nick_width: Option<f32>,
         user: &'a User,
+        grouped: bool,
     ) -> (Element<'a, Message>, Element<'a, Message>) {
         let with_access_levels = self.config.buffer.nickname.show_access_levels;
         let truncate = self.config.buffer.nickname.truncate;
@@ -194,6 +216,8 @@ impl<'a> ChannelQueryLayout<'a> {
             ShownStatus::Historical => false,
         };
 
+        let is_highlight = message.triggers_highlight();
+
         let nickname_style = theme::selectable_text::nickname(
             self.theme,
             self.config,
@@ -202,20 +226,36 @@ impl<'a> ChannelQueryLayout<'a> {
                 ShownStatus::Historical => user,
             },
             is_user_offline,
+            is_highlight,
         );
 
-        let mut text = selectable_text(
-            self.config
-                .buffer
-                .nickname
-                .brackets
-                .format(user.display(with_access_levels, truncate)),
-        )
-        .style(move |_| nickname_style)
-        .font_maybe(
-            theme::font_style::nickname(self.theme, is_user_offline)
+        let nickname =
+            self.config.buffer.nickname.brackets.format(user.display(
+                with_access_levels,
+                truncate,
+                self.config.buffer.nickname.bridged_name(),
+            ));
+
+        // Messages continuing a group leave the nickname's space blank
+        let blank_nick = grouped.then(|| {
+            selectable_text("").width(max_nick_width.unwrap_or_else(|| {
+                font::width_from_chars(
+                    nickname.chars().count(),
+                    &self.config.font,
+                )
+            }))
+        });
+
+        let mut text = selectable_text(nickname)
+            .style(move |_| nickname_style)
+            .font_maybe(
+                theme::font_style::message_nickname(
+                    self.theme,
+                    is_user_offline,
+                    is_highlight,
+                )
                 .map(font::get),
-        );
+            );
 
         if let Some(width) = max_nick_width {
             text = text.width(width).align_x(text::Alignment::Right);
@@ -230,6 +270,7 @@ impl<'a> ChannelQueryLayout<'a> {
                 user,
                 user_in_channel,
                 self.target.our_user(),
+                self.clients,
                 self.config,
                 self.theme,
                 &self.config.buffer.nickname.click,
@@ -243,7 +284,7 @@ impl<'a> ChannelQueryLayout<'a> {
 
         let formatter = *self;
 
-        let message_content = message_content::with_context(
+        let message_content = message_content::with_message_context(
             &message.content,
             self.chantypes,
             self.casemapping,
@@ -252,27 +293,57 @@ impl<'a> ChannelQueryLayout<'a> {
             theme::selectable_text::default,
             theme::font_style::primary,
             Option::<fn(Color) -> Color>::None,
+            self.strips_colors(message),
             move |link| match link {
                 message::Link::User(_) => context_menu::Entry::user_list(
                     formatter.target.is_channel(),
                     formatter.target.our_user(),
+                    formatter.clients.oper_actions(formatter.server).is_some(),
+                    formatter.config,
                 ),
-                message::Link::Url(_) => context_menu::Entry::url_list(),
+                message::Link::Url(url) => {
+                    context_menu::Entry::url_list(url, formatter.config)
+                }
+                message::Link::Message => {
+                    context_menu::Entry::message_list(message)
+                }
                 _ => vec![],
             },
-            move |link, entry, length| {
+            move |link, entry, length, focused| {
+                let context = if let message::Link::Message = link {
+                    Some(Context::Message(message))
+                } else {
+                    formatter.link_context(link)
+                };
+
                 entry
                     .view(
-                        formatter.link_context(link),
+                        context,
                         length,
+                        focused,
                         formatter.config,
                         formatter.theme,
                     )
                     .map(Message::ContextMenu)
             },
+            move |link, entry| {
+                let context = if let message::Link::Message = link {
+                    Some(Context::Message(message))
+                } else {
+                    formatter.link_context(link)
+                };
+
+                entry.label(&context?, formatter.config)
+            },
+            self.link_hover(),
             self.config,
         );
 
+        let nick = match blank_nick {
+            Some(blank_nick) => Element::from(blank_nick),
+            None => nick,
+        };
+
         (nick, Element::from(container(message_content)))
     }
 
@@ -291,12 +362,9 @@ impl<'a> ChannelQueryLayout<'a> {
                 theme::selectable_text::server(message_theme, server);
 
             if let Some(dimmed) = dimmed {
-                style.color = style.color.map(|color| {
-                    dimmed.transform_color(
-                        color,
-                        formatter.theme.styles().buffer.background,
-                    )
-                });
+                style.color = style
+                    .color
+                    .map(|color| theme::dim(dimmed, color, formatter.theme));
             }
 
             style
@@ -312,7 +380,7 @@ impl<'a> ChannelQueryLayout<'a> {
             message_font_style,
         );
 
-        let message_content = message_content::with_context(
+        let message_content = message_content::with_message_context(
             &message.content,
             formatter.chantypes,
             formatter.casemapping,
@@ -322,32 +390,54 @@ impl<'a> ChannelQueryLayout<'a> {
             message_font_style,
             Some(|color: Color| -> Color {
                 if let Some(dimmed) = dimmed {
-                    dimmed.transform_color(
-                        color,
-                        formatter.theme.styles().buffer.background,
-                    )
+                    theme::dim(dimmed, color, formatter.theme)
                 } else {
                     color
                 }
             }),
+            self.strips_colors(message),
             move |link| match link {
                 message::Link::User(_) => context_menu::Entry::user_list(
                     formatter.target.is_channel(),
                     formatter.target.our_user(),
+                    formatter.clients.oper_actions(formatter.server).is_some(),
+                    formatter.config,
                 ),
-                message::Link::Url(_) => context_menu::Entry::url_list(),
+                message::Link::Url(url) => {
+                    context_menu::Entry::url_list(url, formatter.config)
+                }
+                message::Link::Message => {
+                    context_menu::Entry::server_message_list(message)
+                }
                 _ => vec![],
             },
-            move |link, entry, length| {
+            move |link, entry, length, focused| {
+                let context = if let message::Link::Message = link {
+                    Some(Context::Message(message))
+                } else {
+                    formatter.link_context(link)
+                };
+
                 entry
                     .view(
-                        formatter.link_context(link),
+                        context,
                         length,
+                        focused,
                         formatter.config,
                         formatter.theme,
                     )
                     .map(Message::ContextMenu)
             },
+            move |link, entry| {
+                let context = if let message::Link::Message = link {
+                    Some(Context::Message(message))
+                } else {
+                    formatter.link_context(link)
+                };
+
+                entry.label(&context?, formatter.config)
+            },
+            self.link_hover(),
             self.config,
         );
 
@@ -369,7 +459,7 @@ impl<'a> ChannelQueryLayout<'a> {
         };
 
         let formatter = *self;
-        let message_content = message_content::with_context(
+        let message_content = message_content::with_message_context(
             &message.content,
             formatter.chantypes,
             formatter.casemapping,
@@ -381,32 +471,54 @@ impl<'a> ChannelQueryLayout<'a> {
                 if let Some(dimmed) =
                     formatter.config.buffer.server_messages.condense.dimmed
                 {
-                    dimmed.transform_color(
-                        color,
-                        formatter.theme.styles().buffer.background,
-                    )
+                    theme::dim(dimmed, color, formatter.theme)
                 } else {
                     color
                 }
             }),
+            self.strips_colors(message),
             move |link| match link {
                 message::Link::User(_) => context_menu::Entry::user_list(
                     formatter.target.is_channel(),
                     formatter.target.our_user(),
+                    formatter.clients.oper_actions(formatter.server).is_some(),
+                    formatter.config,
                 ),
-                message::Link::Url(_) => context_menu::Entry::url_list(),
+                message::Link::Url(url) => {
+                    context_menu::Entry::url_list(url, formatter.config)
+                }
+                message::Link::Message => {
+                    context_menu::Entry::server_message_list(message)
+                }
                 _ => vec![],
             },
-            move |link, entry, length| {
+            move |link, entry, length, focused| {
+                let context = if let message::Link::Message = link {
+                    Some(Context::Message(message))
+                } else {
+                    formatter.link_context(link)
+                };
+
                 entry
                     .view(
-                        formatter.link_context(link),
+                        context,
                         length,
+                        focused,
                         formatter.config,
                         formatter.theme,
                     )
                     .map(Message::ContextMenu)
             },
+            move |link, entry| {
+                let context = if let message::Link::Message = link {
+                    Some(Context::Message(message))
+                } else {
+                    formatter.link_context(link)
+                };
+
+                entry.label(&context?, formatter.config)
+            },
+            self.link_hover(),
             self.config,
         );
 
@@ -439,6 +551,7 @@ impl<'a> ChannelQueryLayout<'a> {
                 channel: self.target.channel(),
                 user,
                 current_user,
+                clients: self.clients,
             })
         } else {
             link.url().map(Context::Url)
@@ -453,6 +566,45 @@ impl<'a> LayoutMessage<'a> for ChannelQueryLayout<'a> {
         max_nick_width: Option<f32>,
         max_prefix_width: Option<f32>,
         max_excess_timestamp_width: Option<f32>,
+    ) -> Option<Element<'a, Message>> {
+        self.layout(
+            message,
+            max_nick_width,
+            max_prefix_width,
+            max_excess_timestamp_width,
+            false,
+        )
+    }
+
+    fn density(&self) -> Density {
+        self.density
+    }
+
+    fn format_grouped(
+        &self,
+        message: &'a data::Message,
+        max_nick_width: Option<f32>,
+        max_prefix_width: Option<f32>,
+        max_excess_timestamp_width: Option<f32>,
+    ) -> Option<Element<'a, Message>> {
+        self.layout(
+            message,
+            max_nick_width,
+            max_prefix_width,
+            max_excess_timestamp_width,
+            true,
+        )
+    }
+}
+
+impl<'a> ChannelQueryLayout<'a> {
+    fn layout(
+        &self,
+        message: &'a data::Message,
+        max_nick_width: Option<f32>,
+        max_prefix_width: Option<f32>,
+        max_excess_timestamp_width: Option<f32>,
+        grouped: bool,
     ) -> Option<Element<'a, Message>> {
         let timestamp = self.format_timestamp(message);
         let prefixes =
@@ -466,6 +618,7 @@ impl<'a> LayoutMessage<'a> for ChannelQueryLayout<'a> {
                     message,
                     max_nick_width,
                     user,
+                    grouped,
                 )),
                 message::Source::Server(server_message) => {
                     Some(self.format_server_message(
@@ -491,6 +644,7 @@ impl<'a> LayoutMessage<'a> for ChannelQueryLayout<'a> {
                         theme::selectable_text::action,
                         theme::font_style::action,
                         Option::<fn(Color) -> Color>::None,
+                        self.strips_colors(message),
                         self.config,
                     );
 
@@ -524,6 +678,7 @@ impl<'a> LayoutMessage<'a> for ChannelQueryLayout<'a> {
                         message_style,
                         message_font_style,
                         Option::<fn(Color) -> Color>::None,
+                        self.strips_colors(message),
                         self.config,
                     );
 
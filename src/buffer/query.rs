@@ -4,14 +4,14 @@ use data::dashboard::BufferAction;
 use data::preview::{self, Previews};
 use data::target::{self, Target};
 use data::user::Nick;
-use data::{Config, Server, User, buffer, history, message};
-use iced::widget::{column, container, space};
+use data::{Config, Server, User, buffer, history, message, script};
+use iced::widget::{column, container, space, text};
 use iced::{Length, Size, Task};
 
 use super::message_view::{ChannelQueryLayout, TargetInfo};
 use super::{context_menu, input_view, scroll_view};
-use crate::Theme;
 use crate::widget::Element;
+use crate::{Theme, font, theme};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -30,6 +30,7 @@ pub enum Event {
     MarkAsRead(history::Kind),
     OpenUrl(String),
     ImagePreview(PathBuf, url::Url),
+    OperPrompt(Option<String>),
 }
 
 pub fn view<'a>(
@@ -37,6 +38,7 @@ pub fn view<'a>(
     clients: &'a data::client::Map,
     history: &'a history::Manager,
     previews: &'a preview::Collection,
+    density: buffer::Density,
     config: &'a Config,
     theme: &'a Theme,
     is_focused: bool,
@@ -68,8 +70,10 @@ pub fn view<'a>(
         casemapping,
         prefix,
         server,
+        clients,
         theme,
         target: TargetInfo::Query,
+        density,
     };
 
     let messages = container(
@@ -92,20 +96,31 @@ pub fn view<'a>(
         data::buffer::TextInputVisibility::Always => true,
     };
 
+    // Messages to an offline peer can't be delivered
+    let is_offline = clients.query_presence(server, query) == Some(false);
+
     let text_input = show_text_input.then(|| {
         column![
             space::vertical().height(4),
+            is_offline.then(|| {
+                text(format!("{query} is offline"))
+                    .style(theme::text::secondary)
+                    .font_maybe(
+                        theme::font_style::secondary(theme).map(font::get),
+                    )
+            }),
             input_view::view(
                 &state.input_view,
                 input,
                 is_focused,
                 our_user.as_ref(),
-                !status.connected(),
+                !status.connected() || is_offline,
                 config,
                 theme,
             )
             .map(Message::InputView)
         ]
+        .spacing(4)
         .width(Length::Fill)
     });
 
@@ -142,11 +157,19 @@ impl Query {
         }
     }
 
+    /// Follows the query after the server's `CASEMAPPING` changed
+    pub fn rename(&mut self, target: target::Query) {
+        self.buffer =
+            buffer::Upstream::Query(self.server.clone(), target.clone());
+        self.target = target;
+    }
+
     pub fn update(
         &mut self,
         message: Message,
         clients: &mut data::client::Map,
         history: &mut history::Manager,
+        scripts: &script::Manager,
         config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         match message {
@@ -168,6 +191,9 @@ impl Query {
                         Some(Event::OpenBuffers(vec![(target, buffer_action)]))
                     }
                     scroll_view::Event::GoToMessage(_, _, _) => None,
+                    scroll_view::Event::RestoreHistory(kind) => history
+                        .restore(kind, clients)
+                        .map(|task| Event::History(Task::future(task))),
                     scroll_view::Event::RequestOlderChatHistory => {
                         Some(Event::RequestOlderChatHistory)
                     }
@@ -199,6 +225,7 @@ impl Query {
                     &self.buffer,
                     clients,
                     history,
+                    scripts,
                     config,
                 );
                 let command = command.map(Message::InputView);
@@ -224,6 +251,9 @@ impl Query {
                     Some(input_view::Event::Cleared { history_task }) => {
                         (command, Some(Event::History(history_task)))
                     }
+                    Some(input_view::Event::OperPrompt { name }) => {
+                        (command, Some(Event::OperPrompt(name)))
+                    }
                     None => (command, None),
                 }
             }
@@ -1,19 +1,22 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::time::Duration;
 
 use chrono::{DateTime, Local, NaiveDate, NaiveTime, Utc};
-use data::buffer::DateSeparators;
+use data::appearance::theme::FontStyle;
+use data::buffer::{DateSeparators, Density};
 use data::dashboard::BufferAction;
-use data::isupport::ChatHistoryState;
+use data::isupport::{self, ChatHistoryState};
 use data::message::{self, Limit};
+use data::poll::{self, Poll};
 use data::preview::{self, Previews};
 use data::server::Server;
 use data::target::{self, Target};
-use data::{Config, Preview, client, history};
+use data::{Config, Preview, client, destination, digest, history, search};
 use iced::widget::{
     self, Scrollable, button, center, column, container, image, mouse_area,
-    right, row, rule, scrollable, space, stack, text,
+    progress_bar, right, row, rule, scrollable, space, stack, text,
 };
 use iced::{ContentFit, Length, Padding, Size, Task, alignment, padding};
 use tokio::time;
@@ -22,13 +25,18 @@ use self::correct_viewport::correct_viewport;
 use self::keyed::keyed;
 use super::context_menu;
 use crate::widget::{
-    Element, MESSAGE_MARKER_TEXT, notify_visibility, on_resize,
+    Element, MESSAGE_MARKER_TEXT, minimap, notify_visibility, on_resize,
     selectable_text, tooltip,
 };
-use crate::{Theme, font, icon, theme};
+use crate::{Theme, font, i18n, icon, theme};
 
 const HIDE_BUTTON_WIDTH: f32 = 22.0;
+const HIGHLIGHT_ACCENT_WIDTH: f32 = 3.0;
 const SCROLL_TO_TIMEOUT: Duration = Duration::from_millis(200);
+/// Space above each group of messages in the comfortable density
+const GROUP_SPACING: f32 = 8.0;
+/// Longest pause between messages of a user which keeps them grouped
+const GROUP_TIMEOUT: chrono::TimeDelta = chrono::TimeDelta::minutes(5);
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -49,10 +57,17 @@ pub enum Message {
     ExitingViewport(message::Hash),
     PreviewHovered(message::Hash, usize),
     PreviewUnhovered(message::Hash, usize),
+    MessageHovered(message::Hash),
+    MessageUnhovered(message::Hash),
     HidePreview(message::Hash, url::Url),
     MarkAsRead,
     ContentResized(Size),
     PendingScrollTo,
+    Vote(Server, target::Channel, String),
+    ScrollToMessage(message::Hash),
+    LinkHovered(message::Link),
+    LinkUnhovered(message::Link),
+    LinkResolved(String, Result<String, destination::Error>),
 }
 
 #[derive(Debug, Clone)]
@@ -61,6 +76,7 @@ pub enum Event {
     OpenBuffer(Target, BufferAction),
     GoToMessage(Server, target::Channel, message::Hash),
     RequestOlderChatHistory,
+    RestoreHistory(history::Kind),
     PreviewChanged,
     HidePreview(history::Kind, message::Hash, url::Url),
     MarkAsRead,
@@ -71,6 +87,7 @@ pub enum Event {
 #[derive(Debug, Clone, Copy)]
 pub enum Kind<'a> {
     Server(&'a Server),
+    ServerCategory(&'a Server, target::Category),
     Channel(&'a Server, &'a target::Channel),
     Query(&'a Server, &'a target::Query),
     Logs,
@@ -81,6 +98,7 @@ impl Kind<'_> {
     fn server(&self) -> Option<&Server> {
         match self {
             Kind::Server(server)
+            | Kind::ServerCategory(server, _)
             | Kind::Channel(server, _)
             | Kind::Query(server, _) => Some(server),
             Kind::Logs | Kind::Highlights => None,
@@ -92,6 +110,9 @@ impl From<Kind<'_>> for history::Kind {
     fn from(value: Kind<'_>) -> Self {
         match value {
             Kind::Server(server) => history::Kind::Server(server.clone()),
+            Kind::ServerCategory(server, category) => {
+                history::Kind::ServerCategory(server.clone(), category)
+            }
             Kind::Channel(server, channel) => {
                 history::Kind::Channel(server.clone(), channel.clone())
             }
@@ -112,6 +133,29 @@ pub trait LayoutMessage<'a> {
         max_prefix_width: Option<f32>,
         max_excess_timestamp_width: Option<f32>,
     ) -> Option<Element<'a, Message>>;
+
+    /// How messages are laid out, which only layouts able to group
+    /// messages by user make comfortable.
+    fn density(&self) -> Density {
+        Density::Compact
+    }
+
+    /// Formats a message continuing the group of the one before it, from
+    /// the same user.
+    fn format_grouped(
+        &self,
+        message: &'a data::Message,
+        max_nick_width: Option<f32>,
+        max_prefix_width: Option<f32>,
+        max_excess_timestamp_width: Option<f32>,
+    ) -> Option<Element<'a, Message>> {
+        self.format(
+            message,
+            max_nick_width,
+            max_prefix_width,
+            max_excess_timestamp_width,
+        )
+    }
 }
 
 impl<'a, T> LayoutMessage<'a> for T
@@ -224,23 +268,93 @@ pub fn view<'a>(
     let max_excess_timestamp_width = max_excess_timestamp_chars
         .map(|len| font::width_from_chars(len, &config.font));
 
+    let polls = match kind {
+        Kind::Channel(_, channel)
+            if config.buffer.polls.is_enabled(channel.as_str()) =>
+        {
+            state.polls.tally(
+                &old_messages
+                    .iter()
+                    .chain(&new_messages)
+                    .copied()
+                    .collect::<Vec<_>>(),
+                &config.buffer.polls,
+            )
+        }
+        _ => poll::Tally::default(),
+    };
+
+    // Divider after the last backfilled message
+    let backfill = match kind {
+        Kind::Channel(..) | Kind::Query(..) => history.backfill(&kind.into()),
+        _ => None,
+    }
+    .and_then(|backfill| {
+        old_messages
+            .iter()
+            .chain(&new_messages)
+            .rev()
+            .find(|message| message.server_time <= backfill.end)
+            .map(|message| (message.hash, backfill))
+    });
+
+    let buffer_styles = theme.styles().buffer;
+    let (striped, hoverable) = (
+        buffer_styles.background_alt.is_some(),
+        buffer_styles.hover.is_some(),
+    );
+    let highlight_accent = buffer_styles.highlight_border.is_some();
+    let highlight_styled =
+        buffer_styles.highlight_background.is_some() || highlight_accent;
+
+    let is_comfortable = formatter.density() == Density::Comfortable;
+
     let message_rows = |last_date: Option<NaiveDate>,
+                        first_row: usize,
                         messages: &[&'a data::Message]| {
         messages
             .iter()
-            .filter_map(|message| {
-                formatter
-                    .format(
+            .scan(None, |previous: &mut Option<&data::Message>, message| {
+                let grouped = is_comfortable
+                    && previous.is_some_and(|previous| {
+                        continues_group(previous, message)
+                    });
+
+                *previous = Some(*message);
+
+                Some((message, grouped))
+            })
+            .filter_map(|(message, grouped)| {
+                if grouped {
+                    formatter.format_grouped(
                         message,
                         max_nick_width,
                         max_prefix_width,
                         max_excess_timestamp_width,
                     )
-                    .map(|element| {
-                        (message, keyed(keyed::Key::message(message), element))
-                    })
+                } else {
+                    formatter.format(
+                        message,
+                        max_nick_width,
+                        max_prefix_width,
+                        max_excess_timestamp_width,
+                    )
+                }
+                .map(|element| {
+                    // Groups are set apart by space above them
+                    let element = if is_comfortable && !grouped {
+                        container(element)
+                            .padding(padding::top(GROUP_SPACING))
+                            .into()
+                    } else {
+                        element
+                    };
+
+                    (message, keyed(keyed::Key::message(message), element))
+                })
             })
-            .scan(last_date, |last_date, (message, element)| {
+            .enumerate()
+            .scan(last_date, |last_date, (row, (message, element))| {
                 let date =
                     message.server_time.with_timezone(&Local).date_naive();
 
@@ -319,6 +433,78 @@ pub fn view<'a>(
                     element
                 };
 
+                let content =
+                    if let (Some(poll), Kind::Channel(server, channel)) =
+                        (polls.get(&message.hash), kind)
+                    {
+                        column![
+                            content,
+                            poll_row(poll, server, channel, config, theme)
+                        ]
+                        .into()
+                    } else {
+                        content
+                    };
+
+                let highlighted =
+                    highlight_styled && message.triggers_highlight();
+
+                let content = if striped || hoverable || highlighted {
+                    let alternate =
+                        striped && !(first_row + row).is_multiple_of(2);
+                    let hovered = hoverable
+                        && state.hovered_message == Some(message.hash);
+
+                    let content: Element<'a, Message> = container(content)
+                        .width(Length::Fill)
+                        .style(move |theme| {
+                            theme::container::message_row(
+                                theme,
+                                alternate,
+                                hovered,
+                                highlighted,
+                            )
+                        })
+                        .into();
+
+                    // The accent is laid out beside the message rather than
+                    // beneath it, so it can't show through translucent
+                    // backgrounds
+                    let content = if highlight_accent && highlighted {
+                        row![
+                            container(
+                                space::vertical().width(HIGHLIGHT_ACCENT_WIDTH)
+                            )
+                            .height(Length::Fill)
+                            .style(theme::container::highlight_accent),
+                            content,
+                        ]
+                        .into()
+                    } else {
+                        content
+                    };
+
+                    if hoverable {
+                        mouse_area(content)
+                            .on_enter(Message::MessageHovered(message.hash))
+                            .on_exit(Message::MessageUnhovered(message.hash))
+                            .into()
+                    } else {
+                        content
+                    }
+                } else {
+                    content
+                };
+
+                let content = match backfill {
+                    Some((hash, backfill)) if hash == message.hash => column![
+                        content,
+                        backfill_divider(backfill, divider_font_size, theme)
+                    ]
+                    .into(),
+                    _ => content,
+                };
+
                 if is_new_day && config.buffer.date_separators.show {
                     Some(
                         column![
@@ -373,11 +559,12 @@ pub fn view<'a>(
             .collect::<Vec<_>>()
     };
 
-    let old = message_rows(None, &old_messages);
+    let old = message_rows(None, 0, &old_messages);
     let new = message_rows(
         old_messages.last().map(|message| {
             message.server_time.with_timezone(&Local).date_naive()
         }),
+        old.len(),
         &new_messages,
     );
 
@@ -413,11 +600,22 @@ pub fn view<'a>(
         row![]
     };
 
+    let digest = match kind {
+        Kind::Channel(..) | Kind::Query(..) => digest::collect(
+            &new_messages,
+            history.read_marker(&kind.into()),
+            &config.buffer.digest,
+        ),
+        _ => None,
+    }
+    .map(|digest| digest_view(&digest, theme));
+
     let content = on_resize(
         column![
             top_row,
             column(old).spacing(config.buffer.line_spacing),
             keyed(keyed::Key::Divider, divider),
+            digest,
             column(new).spacing(config.buffer.line_spacing),
             space::vertical().height(config.buffer.line_spacing),
         ]
@@ -425,7 +623,7 @@ pub fn view<'a>(
         Message::ContentResized,
     );
 
-    correct_viewport(
+    let scrollable = correct_viewport(
         Scrollable::new(container(content).width(Length::Fill).padding([0, 8]))
             .direction(scrollable::Direction::Vertical(
                 scrollable::Scrollbar::default()
@@ -444,9 +642,239 @@ pub fn view<'a>(
             .id(state.scrollable.clone()),
         state.scrollable.clone(),
         matches!(state.status, Status::Unlocked),
+    );
+
+    let content = match history_minimap(state, kind, history, config) {
+        Some(minimap) => row![scrollable, minimap].into(),
+        None => scrollable,
+    };
+
+    match hover_card(state, config, theme) {
+        Some(card) => stack![
+            content,
+            container(card)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .align_y(alignment::Vertical::Bottom)
+                .padding(8),
+        ]
+        .into(),
+        None => content,
+    }
+}
+
+/// Card with the full url under the cursor, the domain it leads to and
+/// anything suspicious about it.
+fn hover_card<'a>(
+    state: &State,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Option<Element<'a, Message>> {
+    if !config.buffer.url.hover_card.enabled {
+        return None;
+    }
+
+    let url = state.hovered_url.as_ref()?;
+    let destination = destination::Destination::inspect(url)?;
+
+    let font_size = config.font.size.map_or(theme::TEXT_SIZE, f32::from);
+
+    let redirect: Option<Element<'a, Message>> = match state
+        .resolved_urls
+        .get(url)
+    {
+        Some(Resolution::Pending) => Some(
+            text("Resolving redirects…")
+                .size(font_size - 1.0)
+                .style(theme::text::secondary)
+                .font_maybe(theme::font_style::secondary(theme).map(font::get))
+                .into(),
+        ),
+        Some(Resolution::Resolved(resolved))
+            if *resolved != destination.url =>
+        {
+            destination::Destination::inspect(resolved).map(|resolved| {
+                column![
+                    text("Redirects to")
+                        .size(font_size - 1.0)
+                        .style(theme::text::secondary)
+                        .font_maybe(
+                            theme::font_style::secondary(theme).map(font::get),
+                        ),
+                    destination_view(resolved, font_size, theme),
+                ]
+                .spacing(4)
+                .into()
+            })
+        }
+        Some(Resolution::Resolved(_) | Resolution::Failed) | None => None,
+    };
+
+    Some(
+        container(
+            column![destination_view(destination, font_size, theme), redirect]
+                .spacing(8),
+        )
+        .max_width(480)
+        .padding(8)
+        .style(theme::container::tooltip)
+        .into(),
+    )
+}
+
+fn destination_view<'a>(
+    destination: destination::Destination,
+    font_size: f32,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let secondary_font = theme::font_style::secondary(theme).map(font::get);
+    let domain_font_style = theme::font_style::primary(theme)
+        .map_or(FontStyle::Bold, |font_style| font_style + FontStyle::Bold);
+
+    let host = destination.host.map(|host| {
+        row![
+            host.subdomain.map(|subdomain| {
+                text(format!("{subdomain}."))
+                    .size(font_size)
+                    .style(theme::text::secondary)
+                    .font_maybe(secondary_font.clone())
+            }),
+            text(host.domain)
+                .size(font_size)
+                .style(theme::text::primary)
+                .font(font::get(domain_font_style)),
+        ]
+    });
+
+    let warnings = destination.warnings.into_iter().map(|warning| {
+        text(warning.to_string())
+            .size(font_size - 1.0)
+            .style(theme::text::error)
+            .font_maybe(theme::font_style::error(theme).map(font::get))
+            .into()
+    });
+
+    column![
+        host,
+        text(destination.url)
+            .size(font_size - 1.0)
+            .style(theme::text::secondary)
+            .font_maybe(secondary_font),
+        column(warnings).spacing(2),
+    ]
+    .spacing(4)
+    .into()
+}
+
+/// Markers for the highlights, sent messages and search matches of the
+/// whole history, not only of the messages currently laid out.
+fn history_minimap<'a>(
+    state: &State,
+    kind: Kind,
+    history: &'a history::Manager,
+    config: &'a Config,
+) -> Option<Element<'a, Message>> {
+    if !config.pane.minimap.enabled
+        || matches!(kind, Kind::Logs | Kind::Highlights)
+    {
+        return None;
+    }
+
+    let kind = history::Kind::from(kind);
+    let revision = history.revision(&kind)?;
+    let query = state.search_query.as_ref().map(|(query, _)| query);
+
+    let mut cached = state.minimap.borrow_mut();
+
+    let markers = match cached.as_ref() {
+        Some(cached)
+            if cached.revision == revision
+                && cached.query.as_ref() == query =>
+        {
+            cached.markers.clone()
+        }
+        _ => {
+            let markers = minimap_markers(state, &kind, history, config)?;
+
+            *cached = Some(CachedMinimap {
+                revision,
+                query: query.cloned(),
+                markers: markers.clone(),
+            });
+
+            markers
+        }
+    };
+
+    Some(minimap(
+        markers,
+        config.pane.minimap.width as f32,
+        theme::minimap::primary,
+    ))
+}
+
+fn minimap_markers(
+    state: &State,
+    kind: &history::Kind,
+    history: &history::Manager,
+    config: &Config,
+) -> Option<Vec<minimap::Marker<Message>>> {
+    let history::View {
+        old_messages,
+        new_messages,
+        ..
+    } = history.get_messages(kind, None, &config.buffer)?;
+
+    let last = (old_messages.len() + new_messages.len()).saturating_sub(1);
+
+    let search_matches = state
+        .search_query
+        .as_ref()
+        .map(|(query, casemapping)| {
+            history
+                .search_buffer(kind, query, *casemapping)
+                .into_iter()
+                .map(|message| message.hash)
+                .collect::<HashSet<_>>()
+        })
+        .unwrap_or_default();
+
+    Some(
+        old_messages
+            .iter()
+            .chain(&new_messages)
+            .enumerate()
+            .filter_map(|(index, message)| {
+                let marker = if search_matches.contains(&message.hash) {
+                    minimap::Kind::SearchMatch
+                } else if message.triggers_highlight() {
+                    minimap::Kind::Highlight
+                } else if matches!(message.direction, message::Direction::Sent)
+                {
+                    minimap::Kind::Sent
+                } else {
+                    return None;
+                };
+
+                Some(minimap::Marker {
+                    position: index as f32 / last.max(1) as f32,
+                    kind: marker,
+                    on_press: Message::ScrollToMessage(message.hash),
+                })
+            })
+            .collect(),
     )
 }
 
+/// Minimap markers of the history last shown, rebuilt only once its
+/// messages or the search query change
+#[derive(Debug, Clone)]
+struct CachedMinimap {
+    revision: history::Revision,
+    query: Option<search::Query>,
+    markers: Vec<minimap::Marker<Message>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct State {
     pub scrollable: widget::Id,
@@ -457,6 +885,25 @@ pub struct State {
     pending_scroll_to: Option<keyed::Key>,
     visible_url_messages: HashMap<message::Hash, Vec<url::Url>>,
     hovered_preview: Option<(message::Hash, usize)>,
+    hovered_message: Option<message::Hash>,
+    /// Query whose matches are marked in the minimap, along with the
+    /// casemapping of the buffer's server. Cleared once scrolled back to
+    /// the latest messages.
+    search_query: Option<(search::Query, isupport::CaseMap)>,
+    minimap: RefCell<Option<CachedMinimap>>,
+    /// Url under the cursor, shown in the hover card
+    hovered_url: Option<String>,
+    /// Where hovered urls lead when redirects are resolved
+    resolved_urls: HashMap<String, Resolution>,
+    /// Polls of the messages last shown
+    polls: poll::Cache,
+}
+
+#[derive(Debug, Clone)]
+enum Resolution {
+    Pending,
+    Resolved(String),
+    Failed,
 }
 
 impl State {
@@ -472,6 +919,12 @@ impl State {
             pending_scroll_to: None,
             visible_url_messages: HashMap::new(),
             hovered_preview: None,
+            hovered_message: None,
+            search_query: None,
+            minimap: RefCell::default(),
+            hovered_url: None,
+            resolved_urls: HashMap::new(),
+            polls: poll::Cache::default(),
         }
     }
 
@@ -493,6 +946,9 @@ impl State {
                 status: old_status,
                 viewport,
             } => {
+                // Links which scroll out of view don't report being exited
+                self.hovered_url = None;
+
                 let relative_offset = viewport.relative_offset().y;
                 let absolute_offset = viewport.absolute_offset().y;
                 let height = self.pane_size.height;
@@ -520,6 +976,11 @@ impl State {
                             event = Some(Event::MarkAsRead);
                         }
 
+                        // Back from the search result
+                        if !matches!(self.status, Status::Bottom) {
+                            self.search_query = None;
+                        }
+
                         self.status = Status::Bottom;
 
                         if matches!(self.limit, Limit::Bottom(_)) {
@@ -570,6 +1031,14 @@ impl State {
                             self.limit = Limit::Since(oldest.server_time);
                         }
                     }
+                    // Hit top & older messages were evicted from memory
+                    _ if old_status.is_top(relative_offset)
+                        && !has_more_older_messages
+                        && history.is_evicted(&kind.into()) =>
+                    {
+                        event = Some(Event::RestoreHistory(kind.into()));
+                        self.status = Status::Unlocked;
+                    }
                     // Hit top
                     _ if old_status.is_top(relative_offset) => {
                         // If we're infinite scroll & out of messages, load more via chathistory
@@ -684,6 +1153,9 @@ impl State {
                     Some(Event::GoToMessage(server, channel, message)),
                 );
             }
+            Message::Link(message::Link::Message) => {
+                return (Task::none(), None);
+            }
             Message::ScrollTo(keyed::Hit {
                 hit_bounds,
                 scrollable,
@@ -765,6 +1237,51 @@ impl State {
                     self.hovered_preview = None;
                 }
             }
+            Message::MessageHovered(hash) => {
+                self.hovered_message = Some(hash);
+            }
+            Message::MessageUnhovered(hash) => {
+                if self.hovered_message == Some(hash) {
+                    self.hovered_message = None;
+                }
+            }
+            Message::LinkHovered(message::Link::Url(url)) => {
+                let task = if config.buffer.url.hover_card.resolve_redirects
+                    && !self.resolved_urls.contains_key(&url)
+                {
+                    self.resolved_urls.insert(url.clone(), Resolution::Pending);
+
+                    let resolving = url.clone();
+
+                    Task::perform(destination::resolve(url.clone()), |result| {
+                        Message::LinkResolved(resolving, result)
+                    })
+                } else {
+                    Task::none()
+                };
+
+                self.hovered_url = Some(url);
+
+                return (task, None);
+            }
+            Message::LinkUnhovered(message::Link::Url(url)) => {
+                if self.hovered_url.as_ref() == Some(&url) {
+                    self.hovered_url = None;
+                }
+            }
+            Message::LinkHovered(_) | Message::LinkUnhovered(_) => {}
+            Message::LinkResolved(url, result) => {
+                let resolution = match result {
+                    Ok(destination) => Resolution::Resolved(destination),
+                    Err(error) => {
+                        log::debug!("failed to resolve {url}: {error}");
+
+                        Resolution::Failed
+                    }
+                };
+
+                self.resolved_urls.insert(url, resolution);
+            }
             Message::HidePreview(message, url) => {
                 return (
                     Task::none(),
@@ -798,6 +1315,23 @@ impl State {
                     return (scroll_to, None);
                 }
             }
+            Message::Vote(server, channel, vote) => {
+                // Votes are sent as a regular message to the channel
+                return (
+                    Task::none(),
+                    Some(Event::ContextMenu(context_menu::Event::RunCommand(
+                        server,
+                        Some(channel),
+                        vote,
+                    ))),
+                );
+            }
+            Message::ScrollToMessage(message) => {
+                return (
+                    self.scroll_to_message(message, kind, history, config),
+                    None,
+                );
+            }
         }
 
         (Task::none(), None)
@@ -852,6 +1386,7 @@ impl State {
     }
 
     pub fn scroll_to_end(&mut self, config: &Config) -> Task<Message> {
+        self.search_query = None;
         self.status = Status::Bottom;
         self.limit =
             Limit::Bottom(step_messages(2.0 * self.pane_size.height, config));
@@ -861,6 +1396,14 @@ impl State {
         )
     }
 
+    pub fn set_search_query(
+        &mut self,
+        query: Option<search::Query>,
+        casemapping: isupport::CaseMap,
+    ) {
+        self.search_query = query.map(|query| (query, casemapping));
+    }
+
     pub fn is_scrolled_to_bottom(&self) -> bool {
         matches!(self.status, Status::Bottom)
     }
@@ -1282,6 +1825,200 @@ mod keyed {
     }
 }
 
+fn poll_row<'a>(
+    poll: &Poll,
+    server: &Server,
+    channel: &target::Channel,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let total = poll.total_votes();
+
+    let options: Vec<Element<'a, Message>> = poll
+        .tally()
+        .into_iter()
+        .zip(&poll.options)
+        .enumerate()
+        .map(|(index, (votes, option))| {
+            let vote =
+                format!("{} {}", config.buffer.polls.vote_prefix, index + 1);
+
+            button(column![
+                row![
+                    text(format!("{}. {option}", index + 1))
+                        .shaping(text::Shaping::Advanced)
+                        .style(theme::text::primary)
+                        .font_maybe(
+                            theme::font_style::primary(theme).map(font::get)
+                        )
+                        .width(Length::Fill),
+                    text(format!(
+                        "{votes} vote{}",
+                        if votes == 1 { "" } else { "s" }
+                    ))
+                    .style(theme::text::secondary)
+                    .font_maybe(
+                        theme::font_style::secondary(theme).map(font::get)
+                    ),
+                ]
+                .spacing(8),
+                container(progress_bar(
+                    0.0..=total.max(1) as f32,
+                    votes as f32
+                ))
+                .padding([4, 0])
+                .height(11),
+            ])
+            .padding([2, 4])
+            .width(Length::Fill)
+            .style(|theme, status| theme::button::primary(theme, status, false))
+            .on_press(Message::Vote(server.clone(), channel.clone(), vote))
+            .into()
+        })
+        .collect();
+
+    container(
+        column![
+            text(poll.question.clone())
+                .shaping(text::Shaping::Advanced)
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get)),
+        ]
+        .extend(options)
+        .push(
+            text(format!(
+                "{total} vote{} total",
+                if total == 1 { "" } else { "s" }
+            ))
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get)),
+        )
+        .spacing(4)
+        .max_width(400),
+    )
+    .padding(8)
+    .into()
+}
+
+fn backfill_divider<'a>(
+    backfill: history::Backfill,
+    font_size: f32,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let label = match (backfill.unread, backfill.highlights) {
+        (0, _) => "caught up via backfill".to_string(),
+        (unread, 0) => format!(
+            "caught up via backfill: {} unread",
+            i18n::format_count(unread)
+        ),
+        (unread, highlights) => format!(
+            "caught up via backfill: {} unread, {} highlighted",
+            i18n::format_count(unread),
+            i18n::format_count(highlights)
+        ),
+    };
+
+    row![
+        container(rule::horizontal(1))
+            .width(Length::Fill)
+            .padding(padding::right(6)),
+        text(label)
+            .size(font_size)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get)),
+        container(rule::horizontal(1))
+            .width(Length::Fill)
+            .padding(padding::left(6))
+    ]
+    .padding(2)
+    .align_y(iced::Alignment::Center)
+    .into()
+}
+
+fn digest_view<'a>(
+    digest: &digest::Digest<'a>,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let heading = |label: &str, count: usize| {
+        text(format!("{label} ({count})"))
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+    };
+
+    let entry = |content: String, message: Message| -> Element<'a, Message> {
+        button(
+            text(content)
+                .shaping(text::Shaping::Advanced)
+                .wrapping(text::Wrapping::None)
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get)),
+        )
+        .padding([2, 4])
+        .width(Length::Fill)
+        .style(|theme, status| theme::button::primary(theme, status, false))
+        .on_press(message)
+        .into()
+    };
+
+    let message_entry = |message: &data::Message| {
+        let content = match digest::sender(message) {
+            Some(nick) => format!("{nick}: {}", message.text()),
+            None => message.text(),
+        };
+
+        entry(content, Message::ScrollToMessage(message.hash))
+    };
+
+    let mut content = column![
+        text("While you were away")
+            .style(theme::text::primary)
+            .font_maybe(theme::font_style::primary(theme).map(font::get)),
+    ]
+    .spacing(4);
+
+    for (label, messages) in [
+        ("Highlights", &digest.highlights),
+        ("Questions", &digest.questions),
+    ] {
+        if !messages.is_empty() {
+            content = content
+                .push(heading(label, messages.len()))
+                .extend(messages.iter().copied().map(message_entry));
+        }
+    }
+
+    if !digest.urls.is_empty() {
+        content = content.push(heading("Links", digest.urls.len())).extend(
+            digest.urls.iter().map(|(_, url)| {
+                entry(
+                    url.to_string(),
+                    Message::Link(message::Link::Url(url.to_string())),
+                )
+            }),
+        );
+    }
+
+    container(content.max_width(600))
+        .padding(8)
+        .width(Length::Fill)
+        .into()
+}
+
+/// Whether `message` belongs to the group of `previous`, sent by the same
+/// user on the same day without a long pause.
+fn continues_group(previous: &data::Message, message: &data::Message) -> bool {
+    let (message::Source::User(previous_user), message::Source::User(user)) =
+        (previous.target.source(), message.target.source())
+    else {
+        return false;
+    };
+
+    previous_user.nickname() == user.nickname()
+        && message.server_time - previous.server_time < GROUP_TIMEOUT
+        && previous.server_time.with_timezone(&Local).date_naive()
+            == message.server_time.with_timezone(&Local).date_naive()
+}
+
 fn preview_row<'a>(
     message: &'a data::Message,
     preview: &'a Preview,
@@ -1295,7 +2032,7 @@ fn preview_row<'a>(
 ) -> Element<'a, Message> {
     let content = match preview {
         data::Preview::Card(preview::Card {
-            image: preview::Image { path, .. },
+            image: card_image,
             title,
             description,
             ..
@@ -1322,7 +2059,8 @@ fn preview_row<'a>(
                         }),
                         config.preview.card.show_image.then_some(
                             container(
-                                image(path).content_fit(ContentFit::ScaleDown)
+                                image(card_image.handle())
+                                    .content_fit(ContentFit::ScaleDown)
                             )
                             .max_height(200)
                         ),
@@ -1335,24 +2073,44 @@ fn preview_row<'a>(
             .style(theme::button::preview_card)
             .on_press(Message::Link(message::Link::Url(url.to_string()))),
         ),
-        data::Preview::Image(preview::Image { path, url, .. }) => keyed(
+        data::Preview::Image(preview_image) => keyed(
             keyed::Key::Preview(message.hash, idx),
             button(
-                container(image(path).content_fit(ContentFit::ScaleDown))
-                    .max_width(550)
-                    .max_height(350),
+                container(
+                    image(preview_image.handle())
+                        .content_fit(ContentFit::ScaleDown),
+                )
+                .max_width(550)
+                .max_height(350),
             )
             .on_press(match config.preview.image.action {
-                data::config::preview::ImageAction::OpenUrl => {
-                    Message::Link(message::Link::Url(url.to_string()))
-                }
+                data::config::preview::ImageAction::OpenUrl => Message::Link(
+                    message::Link::Url(preview_image.url.to_string()),
+                ),
                 data::config::preview::ImageAction::Preview => {
-                    Message::ImagePreview(path.to_path_buf(), url.clone())
+                    Message::ImagePreview(
+                        preview_image.path.clone(),
+                        preview_image.url.clone(),
+                    )
                 }
             })
             .padding(0)
             .style(theme::button::bare),
         ),
+        data::Preview::Title(title) => keyed(
+            keyed::Key::Preview(message.hash, idx),
+            button(
+                text(title)
+                    .shaping(text::Shaping::Advanced)
+                    .style(theme::text::secondary)
+                    .font_maybe(
+                        theme::font_style::secondary(theme).map(font::get),
+                    ),
+            )
+            .padding(0)
+            .style(theme::button::bare)
+            .on_press(Message::Link(message::Link::Url(url.to_string()))),
+        ),
     };
 
     let timestamp_gap = config
@@ -1376,8 +2134,7 @@ fn preview_row<'a>(
                             config
                                 .buffer
                                 .status_message_prefix
-                                .brackets
-                                .format(String::from_iter(prefixes))
+                                .format(prefixes)
                                 .chars()
                                 .count()
                                 + 1,
@@ -1395,30 +2152,34 @@ fn preview_row<'a>(
             let space = selectable_text(" ");
             let with_access_levels = config.buffer.nickname.show_access_levels;
             let truncate = config.buffer.nickname.truncate;
+            let bridged = config.buffer.nickname.bridged_name();
 
-            let nick = if let message::Source::User(user) =
-                message.target.source()
-            {
-                let mut nick = selectable_text(
-                    " ".repeat(
-                        config
-                            .buffer
-                            .nickname
-                            .brackets
-                            .format(user.display(with_access_levels, truncate))
-                            .chars()
-                            .count(),
-                    ),
-                );
+            let nick =
+                if let message::Source::User(user) = message.target.source() {
+                    let mut nick = selectable_text(
+                        " ".repeat(
+                            config
+                                .buffer
+                                .nickname
+                                .brackets
+                                .format(user.display(
+                                    with_access_levels,
+                                    truncate,
+                                    bridged,
+                                ))
+                                .chars()
+                                .count(),
+                        ),
+                    );
 
-                if let Some(width) = max_nick_width {
-                    nick = nick.width(width);
-                }
+                    if let Some(width) = max_nick_width {
+                        nick = nick.width(width);
+                    }
 
-                Some(nick)
-            } else {
-                None
-            };
+                    Some(nick)
+                } else {
+                    None
+                };
 
             let timestamp_nickname_row =
                 row![timestamp_gap, prefixes, nick, space,];
@@ -0,0 +1,335 @@
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use chrono::Local;
+use data::{Config, client, history, message, search};
+use iced::widget::{
+    self, Scrollable, button, center, column, container, operation, row,
+    scrollable, span, text, text_input,
+};
+use iced::{Length, Task};
+
+use crate::widget::{Element, selectable_rich_text};
+use crate::{Theme, font, icon, theme};
+
+/// Maximum number of results shown for a query.
+const LIMIT: usize = 500;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    Input(String),
+    Submit,
+    Select(search::Query),
+    GoToMessage(data::Buffer, message::Hash),
+    /// Matches found on disk for the query
+    Found(search::Query, Vec<(history::Kind, data::Message)>),
+}
+
+impl selectable_rich_text::Link for Message {}
+
+pub enum Event {
+    /// Go to a message found by the submitted query
+    GoToMessage(data::Buffer, message::Hash, Option<search::Query>),
+}
+
+pub fn view<'a>(
+    state: &'a Search,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let error = state.query.as_ref().and_then(|query| query.as_ref().err());
+    let is_valid = error.is_none();
+
+    let input = text_input(
+        "from:nick in:#channel before:2024-01-01 has:link \"exact phrase\"",
+        &state.input,
+    )
+    .id(state.input_id.clone())
+    .on_input(Message::Input)
+    .on_submit(Message::Submit)
+    .padding(8)
+    .style(move |theme, status| {
+        if is_valid {
+            theme::text_input::primary(theme, status)
+        } else {
+            theme::text_input::error(theme, status)
+        }
+    });
+
+    let error = error.map(|error| {
+        text(error.to_string())
+            .style(theme::text::error)
+            .font_maybe(theme::font_style::error(theme).map(font::get))
+    });
+
+    let watches = (!config.search.watches.is_empty()).then(|| {
+        row(config.search.watches.iter().map(|watch| {
+            let selected = state.submitted.as_ref() == Some(&watch.query);
+
+            button(
+                text(&watch.name).style(theme::text::primary).font_maybe(
+                    theme::font_style::primary(theme).map(font::get),
+                ),
+            )
+            .padding([2, 6])
+            .style(move |theme, status| {
+                theme::button::secondary(theme, status, selected)
+            })
+            .on_press(Message::Select(watch.query.clone()))
+            .into()
+        }))
+        .spacing(4)
+        .wrap()
+    });
+
+    let results: Element<'a, Message> = match &state.submitted {
+        Some(query) => {
+            if state.results.is_empty() && state.searching {
+                placeholder("Searching…", theme)
+            } else if state.results.is_empty() {
+                placeholder("No messages found", theme)
+            } else {
+                container(
+                    Scrollable::new(
+                        column(state.results.iter().map(|(kind, message)| {
+                            result(query, kind, message, config, theme)
+                        }))
+                        .spacing(4)
+                        .padding([0, 2]),
+                    )
+                    .direction(scrollable::Direction::Vertical(
+                        scrollable::Scrollbar::new().width(1).scroller_width(1),
+                    ))
+                    .style(theme::scrollable::hidden),
+                )
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .into()
+            }
+        }
+        None => placeholder("Search the history of open buffers", theme),
+    };
+
+    column![input, error, watches, results]
+        .spacing(8)
+        .padding(8)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .into()
+}
+
+fn placeholder<'a>(label: &'a str, theme: &'a Theme) -> Element<'a, Message> {
+    center(
+        column![
+            icon::search()
+                .size(theme::TEXT_SIZE + 3.0)
+                .style(theme::text::secondary),
+            text(label)
+                .style(theme::text::secondary)
+                .font_maybe(theme::font_style::secondary(theme).map(font::get)),
+        ]
+        .spacing(8)
+        .align_x(iced::Alignment::Center),
+    )
+    .into()
+}
+
+fn result<'a>(
+    query: &search::Query,
+    kind: &history::Kind,
+    message: &'a data::Message,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let timestamp = text(
+        message
+            .server_time
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+    )
+    .style(theme::text::timestamp)
+    .font_maybe(theme::font_style::timestamp(theme).map(font::get));
+
+    let location = selectable_rich_text::<_, _, (), _, _>(vec![
+        span(match kind {
+            history::Kind::Channel(server, channel) => {
+                format!("{} @ {server}", channel.as_str())
+            }
+            history::Kind::Query(server, query) => {
+                format!("{} @ {server}", query.as_str())
+            }
+            _ => kind.to_string(),
+        })
+        .font_maybe(theme.styles().buffer.url.font_style.map(font::get))
+        .color(theme.styles().buffer.url.color)
+        .link(Message::GoToMessage(
+            data::Buffer::from(kind.clone()),
+            message.hash,
+        )),
+    ])
+    .on_link(std::convert::identity);
+
+    let nick = data::digest::sender(message).map(|nick| {
+        text(config.buffer.nickname.brackets.format(nick))
+            .style(theme::text::secondary)
+            .font_maybe(
+                theme::font_style::nickname(theme, false).map(font::get),
+            )
+    });
+
+    let content = message.text();
+    let mut spans = vec![];
+    let mut end = 0;
+
+    for range in query.highlights(&content) {
+        spans.push(span(content[end..range.start].to_string()));
+        spans.push(
+            span(content[range.clone()].to_string())
+                .background(theme.styles().buffer.highlight),
+        );
+        end = range.end;
+    }
+
+    spans.push(span(content[end..].to_string()));
+
+    let content = selectable_rich_text::<_, Message, (), _, _>(spans)
+        .font_maybe(theme::font_style::primary(theme).map(font::get))
+        .color(theme.styles().text.primary.color);
+
+    column![
+        row![timestamp, location, nick].spacing(8),
+        container(content).padding([0, 4]),
+    ]
+    .spacing(2)
+    .into()
+}
+
+#[derive(Debug, Clone)]
+pub struct Search {
+    input: String,
+    query: Option<Result<search::Query, search::Error>>,
+    submitted: Option<search::Query>,
+    /// Matches of the submitted query, found when it was submitted
+    results: Vec<(history::Kind, data::Message)>,
+    /// Whether history on disk is still being searched
+    searching: bool,
+    input_id: widget::Id,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            input: String::new(),
+            query: None,
+            submitted: None,
+            results: vec![],
+            searching: false,
+            input_id: widget::Id::unique(),
+        }
+    }
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(
+        &mut self,
+        message: Message,
+        history: &history::Manager,
+        clients: &client::Map,
+    ) -> (Task<Message>, Option<Event>) {
+        match message {
+            Message::Input(input) => {
+                self.query = (!input.trim().is_empty()).then(|| input.parse());
+                self.input = input;
+            }
+            Message::Submit => {
+                if let Some(Ok(query)) = &self.query {
+                    return (
+                        self.submit(query.clone(), history, clients),
+                        None,
+                    );
+                }
+            }
+            Message::Select(query) => {
+                self.input = query.to_string();
+                self.query = Some(Ok(query.clone()));
+
+                return (self.submit(query, history, clients), None);
+            }
+            Message::GoToMessage(buffer, message) => {
+                return (
+                    Task::none(),
+                    Some(Event::GoToMessage(
+                        buffer,
+                        message,
+                        self.submitted.clone(),
+                    )),
+                );
+            }
+            Message::Found(query, found) => {
+                // Results of an earlier query
+                if self.submitted.as_ref() != Some(&query) {
+                    return (Task::none(), None);
+                }
+
+                let hashes = self
+                    .results
+                    .iter()
+                    .map(|(_, message)| message.hash)
+                    .collect::<HashSet<_>>();
+
+                self.results.extend(
+                    found
+                        .into_iter()
+                        .filter(|(_, message)| !hashes.contains(&message.hash)),
+                );
+                self.results
+                    .sort_by_key(|(_, message)| Reverse(message.server_time));
+                self.results.truncate(LIMIT);
+                self.searching = false;
+            }
+        }
+
+        (Task::none(), None)
+    }
+
+    /// Searches the loaded history once, rather than on every redraw, then
+    /// the history only on disk in the background. Submitting the query
+    /// again picks up newer messages.
+    fn submit(
+        &mut self,
+        query: search::Query,
+        history: &history::Manager,
+        clients: &client::Map,
+    ) -> Task<Message> {
+        self.results = history
+            .search(&query, LIMIT, clients)
+            .into_iter()
+            .map(|(kind, message)| (kind.clone(), message.clone()))
+            .collect();
+        self.submitted = Some(query.clone());
+
+        let unloaded = history.unloaded(clients);
+
+        if unloaded.is_empty() {
+            self.searching = false;
+
+            return Task::none();
+        }
+
+        self.searching = true;
+
+        Task::perform(
+            history::search(unloaded, query.clone(), LIMIT),
+            move |found| Message::Found(query.clone(), found),
+        )
+    }
+
+    pub fn focus(&self) -> Task<Message> {
+        operation::focus(self.input_id.clone())
+    }
+}
@@ -1,9 +1,9 @@
 use std::path::PathBuf;
 
 use data::dashboard::BufferAction;
-use data::target::Target;
+use data::target::{self, Target};
 use data::user::Nick;
-use data::{Config, User, buffer, history, message};
+use data::{Config, User, buffer, history, message, script};
 use iced::widget::{column, container, row, space};
 use iced::{Color, Length, Size, Task};
 
@@ -25,6 +25,7 @@ pub enum Event {
     MarkAsRead(history::Kind),
     OpenUrl(String),
     ImagePreview(PathBuf, url::Url),
+    OperPrompt(Option<String>),
 }
 
 pub fn view<'a>(
@@ -47,7 +48,7 @@ pub fn view<'a>(
     let messages = container(
         scroll_view::view(
             &state.scroll_view,
-            scroll_view::Kind::Server(&state.server),
+            state.kind(),
             history,
             None,
             None,
@@ -74,17 +75,26 @@ pub fn view<'a>(
 
                 match message.target.source() {
                     message::Source::Server(server) => {
+                        let severity = message.snotice_severity();
+
                         let message = message_content(
                             &message.content,
                             chantypes,
                             casemapping,
                             theme,
                             scroll_view::Message::Link,
-                            move |theme| {
-                                theme::selectable_text::server(
+                            move |theme| match severity {
+                                Some(severity) => {
+                                    theme::selectable_text::snotice(
+                                        theme,
+                                        server.as_ref(),
+                                        severity,
+                                    )
+                                }
+                                None => theme::selectable_text::server(
                                     theme,
                                     server.as_ref(),
-                                )
+                                ),
                             },
                             move |theme| {
                                 theme::font_style::server(
@@ -93,6 +103,10 @@ pub fn view<'a>(
                                 )
                             },
                             Option::<fn(Color) -> Color>::None,
+                            config
+                                .buffer
+                                .formatting
+                                .strips_colors(message.target.buffer_name()),
                             config,
                         );
 
@@ -121,6 +135,10 @@ pub fn view<'a>(
                                 theme::font_style::status(theme, *status)
                             },
                             Option::<fn(Color) -> Color>::None,
+                            config
+                                .buffer
+                                .formatting
+                                .strips_colors(message.target.buffer_name()),
                             config,
                         );
 
@@ -193,11 +211,35 @@ impl Server {
         }
     }
 
+    /// Buffer of the server traffic in `category`, split out of the server
+    /// buffer
+    pub fn category(
+        server: data::server::Server,
+        category: target::Category,
+        pane_size: Size,
+        config: &Config,
+    ) -> Self {
+        Self {
+            buffer: buffer::Upstream::ServerCategory(server.clone(), category),
+            ..Self::new(server, pane_size, config)
+        }
+    }
+
+    pub fn kind(&self) -> scroll_view::Kind<'_> {
+        match self.buffer {
+            buffer::Upstream::ServerCategory(_, category) => {
+                scroll_view::Kind::ServerCategory(&self.server, category)
+            }
+            _ => scroll_view::Kind::Server(&self.server),
+        }
+    }
+
     pub fn update(
         &mut self,
         message: Message,
         clients: &mut data::client::Map,
         history: &mut history::Manager,
+        scripts: &script::Manager,
         config: &Config,
     ) -> (Task<Message>, Option<Event>) {
         match message {
@@ -205,7 +247,7 @@ impl Server {
                 let (command, event) = self.scroll_view.update(
                     message,
                     false,
-                    scroll_view::Kind::Server(&self.server),
+                    self.kind(),
                     history,
                     clients,
                     config,
@@ -219,6 +261,9 @@ impl Server {
                         Some(Event::OpenBuffers(vec![(target, buffer_action)]))
                     }
                     scroll_view::Event::GoToMessage(_, _, _) => None,
+                    scroll_view::Event::RestoreHistory(kind) => history
+                        .restore(kind, clients)
+                        .map(|task| Event::History(Task::future(task))),
                     scroll_view::Event::RequestOlderChatHistory => None,
                     scroll_view::Event::PreviewChanged => None,
                     scroll_view::Event::HidePreview(..) => None,
@@ -244,6 +289,7 @@ impl Server {
                     &self.buffer,
                     clients,
                     history,
+                    scripts,
                     config,
                 );
                 let command = command.map(Message::InputView);
@@ -268,6 +314,9 @@ impl Server {
                     Some(input_view::Event::Cleared { history_task }) => {
                         (command, Some(Event::History(history_task)))
                     }
+                    Some(input_view::Event::OperPrompt { name }) => {
+                        (command, Some(Event::OperPrompt(name)))
+                    }
                     None => (command, None),
                 }
             }
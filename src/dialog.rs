@@ -0,0 +1,55 @@
+//! File dialogs, run as tasks so a slow filesystem never stalls the UI.
+use std::path::{Path, PathBuf};
+
+use iced::Task;
+use iced::task;
+
+/// Picks a file to open.
+///
+/// The dialog's result is discarded once the returned handle is dropped.
+pub fn pick_file() -> (Task<Option<PathBuf>>, task::Handle) {
+    abortable(async {
+        rfd::AsyncFileDialog::new()
+            .pick_file()
+            .await
+            .map(|handle| handle.path().to_path_buf())
+    })
+}
+
+/// Picks where to save `file_name`, starting in the directory returned by
+/// `directory`, which is resolved off the UI thread.
+///
+/// The dialog's result is discarded once the returned handle is dropped.
+pub fn save_file(
+    file_name: String,
+    directory: impl FnOnce() -> Option<PathBuf> + Send + 'static,
+) -> (Task<Option<PathBuf>>, task::Handle) {
+    abortable(async move {
+        let mut dialog = rfd::AsyncFileDialog::new().set_file_name(file_name);
+
+        if let Some(directory) = directory() {
+            dialog = dialog.set_directory(directory);
+        }
+
+        dialog
+            .save_file()
+            .await
+            .map(|handle| handle.path().to_path_buf())
+    })
+}
+
+/// Name of `path` to suggest in a save dialog
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn abortable(
+    dialog: impl Future<Output = Option<PathBuf>> + Send + 'static,
+) -> (Task<Option<PathBuf>>, task::Handle) {
+    let (task, handle) = Task::future(dialog).abortable();
+
+    (task, handle.abort_on_drop())
+}
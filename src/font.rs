@@ -1,40 +1,43 @@
 use std::borrow::Cow;
 use std::sync::OnceLock;
 
-use data::appearance::theme::FontStyle;
+use data::appearance::theme::{FontStyle, FontWeight};
 use data::{Config, config};
 use iced::font;
 
-pub static MONO: Font = Font::new(false, false);
-pub static MONO_BOLD: Font = Font::new(true, false);
-pub static MONO_ITALICS: Font = Font::new(false, true);
-pub static MONO_BOLD_ITALICS: Font = Font::new(true, true);
+pub static MONO: Font = Font::new(FontWeight::Normal, false);
+pub static MONO_BOLD: Font = Font::new(FontWeight::Bold, false);
+pub static MONO_ITALICS: Font = Font::new(FontWeight::Normal, true);
+pub static MONO_BOLD_ITALICS: Font = Font::new(FontWeight::Bold, true);
+pub static MONO_LIGHT: Font = Font::new(FontWeight::Light, false);
+pub static MONO_LIGHT_ITALICS: Font = Font::new(FontWeight::Light, true);
+pub static MONO_SEMIBOLD: Font = Font::new(FontWeight::Semibold, false);
+pub static MONO_SEMIBOLD_ITALICS: Font = Font::new(FontWeight::Semibold, true);
 pub const ICON: iced::Font = iced::Font::with_name("halloy-icons");
 
 #[derive(Debug, Clone)]
 pub struct Font {
-    bold: bool,
+    weight: FontWeight,
     italics: bool,
     inner: OnceLock<iced::Font>,
 }
 
 impl Font {
-    const fn new(bold: bool, italics: bool) -> Self {
+    const fn new(weight: FontWeight, italics: bool) -> Self {
         Self {
-            bold,
+            weight,
             italics,
             inner: OnceLock::new(),
         }
     }
 
-    fn set(
-        &self,
-        name: String,
-        weight: font::Weight,
-        bold_weight: font::Weight,
-    ) {
-        let name = Box::leak(name.into_boxed_str());
-        let weight = if self.bold { bold_weight } else { weight };
+    fn set(&self, name: &'static str, weights: &Weights) {
+        let weight = match self.weight {
+            FontWeight::Light => weights.light,
+            FontWeight::Normal => weights.normal,
+            FontWeight::Semibold => weights.semibold,
+            FontWeight::Bold => weights.bold,
+        };
         let style = if self.italics {
             font::Style::Italic
         } else {
@@ -75,10 +78,72 @@ pub fn set(config: Option<&Config>) {
             | font::Weight::Black => font::Weight::Black,
         });
 
-    MONO.set(family.clone(), weight, bold_weight);
-    MONO_BOLD.set(family.clone(), weight, bold_weight);
-    MONO_ITALICS.set(family.clone(), weight, bold_weight);
-    MONO_BOLD_ITALICS.set(family, weight, bold_weight);
+    let weights = Weights {
+        light: offset_weight(weight, -1),
+        normal: weight,
+        semibold: midway_weight(weight, bold_weight),
+        bold: bold_weight,
+    };
+    let family: &'static str = Box::leak(family.into_boxed_str());
+
+    for font in [
+        &MONO,
+        &MONO_BOLD,
+        &MONO_ITALICS,
+        &MONO_BOLD_ITALICS,
+        &MONO_LIGHT,
+        &MONO_LIGHT_ITALICS,
+        &MONO_SEMIBOLD,
+        &MONO_SEMIBOLD_ITALICS,
+    ] {
+        font.set(family, &weights);
+    }
+}
+
+/// Weights the font styles resolve to, derived from the configured weights.
+struct Weights {
+    light: font::Weight,
+    normal: font::Weight,
+    semibold: font::Weight,
+    bold: font::Weight,
+}
+
+const WEIGHTS: [font::Weight; 9] = [
+    font::Weight::Thin,
+    font::Weight::ExtraLight,
+    font::Weight::Light,
+    font::Weight::Normal,
+    font::Weight::Medium,
+    font::Weight::Semibold,
+    font::Weight::Bold,
+    font::Weight::ExtraBold,
+    font::Weight::Black,
+];
+
+fn weight_index(weight: font::Weight) -> usize {
+    WEIGHTS
+        .iter()
+        .position(|candidate| *candidate == weight)
+        .unwrap_or_default()
+}
+
+fn offset_weight(weight: font::Weight, offset: isize) -> font::Weight {
+    let index = weight_index(weight)
+        .saturating_add_signed(offset)
+        .min(WEIGHTS.len() - 1);
+
+    WEIGHTS[index]
+}
+
+/// The weight between `weight` and `bold_weight`, rounded towards bold.
+fn midway_weight(
+    weight: font::Weight,
+    bold_weight: font::Weight,
+) -> font::Weight {
+    let (weight, bold_weight) =
+        (weight_index(weight), weight_index(bold_weight));
+
+    WEIGHTS[(weight + bold_weight).div_ceil(2)]
 }
 
 pub fn load() -> Vec<Cow<'static, [u8]>> {
@@ -127,5 +192,9 @@ pub fn get(font_style: FontStyle) -> Font {
         FontStyle::Bold => MONO_BOLD.clone(),
         FontStyle::Italic => MONO_ITALICS.clone(),
         FontStyle::ItalicBold => MONO_BOLD_ITALICS.clone(),
+        FontStyle::Light => MONO_LIGHT.clone(),
+        FontStyle::ItalicLight => MONO_LIGHT_ITALICS.clone(),
+        FontStyle::Semibold => MONO_SEMIBOLD.clone(),
+        FontStyle::ItalicSemibold => MONO_SEMIBOLD_ITALICS.clone(),
     }
 }
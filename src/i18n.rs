@@ -0,0 +1,208 @@
+//! Translated UI strings.
+//!
+//! English strings are bundled with Halloy. Translations are [fluent] files
+//! named after their locale (e.g. `de.ftl` or `pt-BR.ftl`) and loaded from
+//! the `locales` directory in the config directory on startup. Messages
+//! missing from a translation fall back to English.
+//!
+//! Dates and numbers are formatted for the same locale.
+//!
+//! [fluent]: https://projectfluent.org
+use std::fmt;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, TimeZone};
+use data::Config;
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use unic_langid::LanguageIdentifier;
+
+const FALLBACK_LOCALE: &str = "en-US";
+const FALLBACK: &str = include_str!("../assets/locales/en-US.ftl");
+
+static BUNDLES: OnceLock<Vec<FluentBundle<FluentResource>>> = OnceLock::new();
+static LOCALE: OnceLock<chrono::Locale> = OnceLock::new();
+
+/// Loads the translation for the configured locale, or the system locale
+/// if none is configured.
+pub fn set(config: Option<&Config>) {
+    let locale = config
+        .and_then(|config| config.locale.clone())
+        .or_else(sys_locale::get_locale)
+        .and_then(|locale| match locale.parse::<LanguageIdentifier>() {
+            Ok(locale) => Some(locale),
+            Err(error) => {
+                log::warn!("invalid locale {locale:?}: {error}");
+                None
+            }
+        });
+
+    if let Some(locale) = locale.as_ref().and_then(posix_locale) {
+        let _ = LOCALE.set(locale);
+    }
+
+    let mut bundles = vec![];
+
+    if let Some(locale) = locale
+        && locale.language.as_str() != "en"
+        && let Some(bundle) = load_translation(&locale)
+    {
+        bundles.push(bundle);
+    }
+
+    let fallback = FALLBACK_LOCALE
+        .parse::<LanguageIdentifier>()
+        .expect("valid fallback locale");
+    let resource = FluentResource::try_new(FALLBACK.to_string())
+        .expect("valid fallback translation");
+    bundles.push(bundle(fallback, resource));
+
+    let _ = BUNDLES.set(bundles);
+}
+
+/// The translation of message `id`.
+pub fn tr(id: &str) -> String {
+    format(id, None)
+}
+
+/// The translation of message `id`, with its `{ $variable }`s replaced by
+/// `args`.
+pub fn tr_args(id: &str, args: &[(&'static str, &str)]) -> String {
+    let mut fluent_args = FluentArgs::new();
+
+    for (name, value) in args {
+        fluent_args.set(*name, value.to_string());
+    }
+
+    format(id, Some(&fluent_args))
+}
+
+/// `date_time` formatted with strftime `format`, with month and day names
+/// in the locale's language.
+pub fn format_date<Tz: TimeZone>(
+    date_time: &DateTime<Tz>,
+    format: &str,
+) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    date_time.format_localized(format, locale()).to_string()
+}
+
+/// `count` with the locale's thousands separator, e.g. `12,345` or
+/// `12 345`.
+pub fn format_count(count: usize) -> String {
+    let locale = locale();
+    let separator =
+        pure_rust_locales::locale_match!(locale => LC_NUMERIC::THOUSANDS_SEP);
+    let grouping =
+        pure_rust_locales::locale_match!(locale => LC_NUMERIC::GROUPING);
+
+    let digits = count.to_string();
+    let mut rest = digits.as_str();
+    let mut groups = vec![];
+    // The last group size repeats for the remaining digits
+    let mut sizes = grouping.iter().copied();
+    let mut size = None;
+
+    loop {
+        size = sizes.next().or(size);
+
+        match size.and_then(|size| usize::try_from(size).ok()) {
+            Some(size) if size > 0 && size < rest.len() => {
+                let (head, group) = rest.split_at(rest.len() - size);
+                groups.push(group);
+                rest = head;
+            }
+            _ => break,
+        }
+    }
+
+    groups.push(rest);
+    groups.reverse();
+    groups.join(separator)
+}
+
+fn locale() -> chrono::Locale {
+    LOCALE.get().copied().unwrap_or(chrono::Locale::POSIX)
+}
+
+/// The POSIX locale for `locale`, e.g. `de_AT` for `de-AT`, or `de_DE` for
+/// `de`.
+fn posix_locale(locale: &LanguageIdentifier) -> Option<chrono::Locale> {
+    let language = locale.language.as_str();
+    let region = locale.region.map_or_else(
+        || language.to_uppercase(),
+        |region| region.as_str().to_string(),
+    );
+
+    chrono::Locale::try_from(format!("{language}_{region}").as_str())
+        .or_else(|_| chrono::Locale::try_from(language))
+        .ok()
+}
+
+fn format(id: &str, args: Option<&FluentArgs>) -> String {
+    BUNDLES
+        .get()
+        .into_iter()
+        .flatten()
+        .find_map(|bundle| {
+            let pattern = bundle.get_message(id)?.value()?;
+            let mut errors = vec![];
+            let value = bundle.format_pattern(pattern, args, &mut errors);
+
+            if !errors.is_empty() {
+                log::debug!("errors formatting {id}: {errors:?}");
+            }
+
+            Some(value.into_owned())
+        })
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Loads `<locale>.ftl`, or `<language>.ftl` if there is no translation
+/// specific to the region.
+fn load_translation(
+    locale: &LanguageIdentifier,
+) -> Option<FluentBundle<FluentResource>> {
+    let dir = Config::config_dir().join("locales");
+
+    let (path, source) = [locale.to_string(), locale.language.to_string()]
+        .into_iter()
+        .map(|name| dir.join(format!("{name}.ftl")))
+        .find_map(|path| {
+            let source = std::fs::read_to_string(&path).ok()?;
+            Some((path, source))
+        })?;
+
+    let resource = match FluentResource::try_new(source) {
+        Ok(resource) => resource,
+        // Use the messages which did parse
+        Err((resource, errors)) => {
+            log::warn!(
+                "errors parsing translation {}: {errors:?}",
+                path.display()
+            );
+            resource
+        }
+    };
+
+    log::info!("loaded translation {}", path.display());
+
+    Some(bundle(locale.clone(), resource))
+}
+
+fn bundle(
+    locale: LanguageIdentifier,
+    resource: FluentResource,
+) -> FluentBundle<FluentResource> {
+    let mut bundle = FluentBundle::new_concurrent(vec![locale]);
+    // Isolation marks render as boxes with some fonts
+    bundle.set_use_isolating(false);
+
+    if let Err(errors) = bundle.add_resource(resource) {
+        log::warn!("conflicting translation messages: {errors:?}");
+    }
+
+    bundle
+}
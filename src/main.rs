@@ -4,16 +4,25 @@
 mod appearance;
 mod audio;
 mod buffer;
+mod dialog;
 mod event;
 mod font;
+mod i18n;
 mod icon;
 mod logger;
 mod modal;
 mod notification;
 mod platform_specific;
 mod screen;
+#[cfg(feature = "snapshots")]
+mod snapshot;
+mod startup;
 mod stream;
+mod title_bar;
+mod tray;
+mod update;
 mod url;
+mod watcher;
 mod widget;
 mod window;
 
@@ -46,7 +55,13 @@ use self::notification::Notifications;
 use self::widget::Element;
 use self::window::Window;
 
+/// Window changes are saved once the window has been left alone this long,
+/// so a crash doesn't lose them.
+const SAVE_WINDOW_AFTER: Duration = Duration::from_secs(2);
+
 pub fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let launched = Instant::now();
+
     let mut args = env::args();
     args.next();
 
@@ -58,6 +73,14 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    let profile = data::Profile::from_args(env::args())?;
+
+    if env::args().any(|arg| arg == "--profile-startup") {
+        startup::enable(launched);
+    }
+
+    let start_minimized = env::args().any(|arg| arg == "--start-minimized");
+
     let is_debug = cfg!(debug_assertions);
 
     // Prepare notifications.
@@ -82,8 +105,11 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let (config_load, window_load) = {
         rt.block_on(async {
+            let started = Instant::now();
             let config = Config::load().await;
-            let window = data::Window::load().await;
+            startup::record(startup::Stage::ConfigParse, started.elapsed());
+
+            let window = data::Window::load(&profile).await;
 
             (config, window)
         })
@@ -96,6 +122,13 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     // DANGER ZONE - font must be set using config
     // before we do any iced related stuff w/ it
     font::set(config_load.as_ref().ok());
+    i18n::set(config_load.as_ref().ok());
+    theme::set_opacity(config_load.as_ref().ok());
+
+    #[cfg(feature = "snapshots")]
+    if let Some(args) = snapshot::Args::parse(env::args()) {
+        return Ok(snapshot::run(&args, config_load.as_ref().ok())?);
+    }
 
     let destination = data::Url::find_in(std::env::args());
     if let Some(loc) = &destination
@@ -119,8 +152,10 @@ pub fn main() -> Result<(), Box<dyn std::error::Error>> {
             Halloy::new(
                 config_load.clone(),
                 window_load.clone(),
+                profile.clone(),
                 destination.clone(),
                 log_stream,
+                start_minimized,
                 // we start with an unspecified mode because we are guaranteed to
                 // receive a message from mundy containing the correct mode on startup.
                 appearance::Mode::Unspecified,
@@ -152,7 +187,8 @@ fn settings(config_load: &Result<Config, config::Error>) -> iced::Settings {
         default_text_size: default_text_size.into(),
         id: None,
         antialiasing: false,
-        fonts: font::load(),
+        // Loaded once the main window is shown, see `Halloy::new`
+        fonts: vec![],
     }
 }
 
@@ -160,47 +196,80 @@ fn handle_irc_error(e: anyhow::Error) {
     log::error!("{e:#}");
 }
 
+/// Runs the `on_connect` (or `on_disconnect`) commands of `server`.
+fn run_on_connect(
+    server: Server,
+    stream: client::on_connect::Stream,
+) -> Task<Message> {
+    Task::stream(stream)
+        .map(move |event| Message::OnConnect(server.clone(), event))
+}
+
 struct Halloy {
     version: Version,
+    update_banner: update::Banner,
     screen: Screen,
     current_mode: appearance::Mode,
     theme: Theme,
+    /// Themes configured for the buffers of specific servers and targets
+    theme_overrides: theme::Overrides,
     config: Config,
     clients: data::client::Map,
     servers: server::Map,
     modal: Option<Modal>,
     main_window: Window,
+    profile: data::Profile,
     pending_logs: Vec<data::log::Record>,
     notifications: Notifications,
+    tray: Option<tray::Tray>,
+    /// When the main window last changed without being saved
+    window_changed: Option<Instant>,
 }
 
 impl Halloy {
     pub fn load_from_state(
         main_window: window::Id,
+        profile: data::Profile,
         config_load: Result<Config, config::Error>,
         current_mode: appearance::Mode,
     ) -> (Halloy, Task<Message>) {
         let main_window = Window::new(main_window);
-        let load_dashboard = |config: &Config| match data::Dashboard::load() {
-            Ok(dashboard) => {
-                if config.pane.restore_on_launch {
-                    screen::Dashboard::restore(dashboard, config, &main_window)
-                } else {
-                    screen::Dashboard::empty(&main_window, config)
-                }
-            }
-            Err(error) => {
-                if data::Dashboard::exists().is_ok_and(|exists| exists) {
-                    log::warn!("failed to load dashboard: {error}");
-                } else {
-                    // Most likely this means it is the user's first launch,
-                    // downgrade severity to info
-                    log::info!("failed to load dashboard: {error}");
+        let load_dashboard =
+            |config: &Config| match data::Dashboard::load(&profile) {
+                Ok(dashboard) => {
+                    if config.pane.restore_on_launch {
+                        screen::Dashboard::restore(
+                            dashboard,
+                            profile.clone(),
+                            config,
+                            &main_window,
+                        )
+                    } else {
+                        screen::Dashboard::empty(
+                            &main_window,
+                            profile.clone(),
+                            config,
+                        )
+                    }
                 }
+                Err(error) => {
+                    if data::Dashboard::exists(&profile)
+                        .is_ok_and(|exists| exists)
+                    {
+                        log::warn!("failed to load dashboard: {error}");
+                    } else {
+                        // Most likely this means it is the user's first launch,
+                        // downgrade severity to info
+                        log::info!("failed to load dashboard: {error}");
+                    }
 
-                screen::Dashboard::empty(&main_window, config)
-            }
-        };
+                    screen::Dashboard::empty(
+                        &main_window,
+                        profile.clone(),
+                        config,
+                    )
+                }
+            };
 
         let (screen, servers, config, command) = match config_load {
             Ok(config) => {
@@ -235,16 +304,21 @@ impl Halloy {
         (
             Halloy {
                 version: Version::new(),
+                update_banner: update::Banner::default(),
                 screen,
                 current_mode,
                 theme: current_mode.theme(&config.appearance.selected).into(),
+                theme_overrides: theme::Overrides::new(&config, current_mode),
                 clients: data::client::Map::default(),
                 servers,
                 config,
                 modal: None,
                 main_window,
+                profile,
                 pending_logs: vec![],
                 notifications,
+                tray: None,
+                window_changed: None,
             },
             command,
         )
@@ -268,48 +342,111 @@ pub enum Message {
     Welcome(welcome::Message),
     Event(window::Id, Event),
     Tick(Instant),
-    Version(Option<String>),
+    RefreshNicklists,
+    Version(Option<version::Release>),
+    Update(update::Message),
     Modal(modal::Message),
     RouteReceived(String),
     AppearanceChange(appearance::Mode),
     Window(window::Id, window::Event),
+    MainWindowChanged(window::Change, window::State),
     WindowSettingsSaved(Result<(), window::Error>),
     Logging(Vec<logger::Record>),
     OnConnect(Server, client::on_connect::Event),
+    FontsLoaded(Instant, Vec<Result<(), iced::font::Error>>),
+    Tray(tray::Event),
+    TitleBar(title_bar::Message),
+    FilesChanged(watcher::Change),
+    ConfigChanged(Result<Config, config::Error>),
+    ThemesChanged(Result<Config, config::Error>),
 }
 
 impl Halloy {
     fn new(
         config_load: Result<Config, config::Error>,
         window_load: Result<data::Window, window::Error>,
+        profile: data::Profile,
         url_received: Option<data::Url>,
         log_stream: ReceiverStream<Vec<logger::Record>>,
+        start_minimized: bool,
         current_mode: appearance::Mode,
     ) -> (Halloy, Task<Message>) {
-        let data::Window { size, position } = window_load.unwrap_or_default();
-        let position =
-            position.map(window::Position::Specific).unwrap_or_default();
-
         let default_config = Config::default();
         let config = config_load.as_ref().unwrap_or(&default_config);
 
+        // The config only sets the level of a window without saved state,
+        // after which it's toggled from the level the window has
+        let mut window_load = window_load.unwrap_or_else(|_| data::Window {
+            is_always_on_top: config.always_on_top,
+            ..data::Window::default()
+        });
+        let mini = window_load.mini.filter(|_| window_load.is_mini);
+        let position = mini
+            .map_or(window_load.position, |mini| mini.position)
+            .map(window::Position::Specific)
+            .unwrap_or_default();
+
+        window_load.is_borderless = config.borderless;
+
+        // Servers stay connected while the window is hidden, so it's only
+        // started hidden when it can be shown again from the tray
+        let start_hidden = config.tray.enabled
+            && (start_minimized || config.tray.start_minimized);
+
         let (main_window, open_main_window) = window::open(window::Settings {
-            size,
+            size: mini.map_or(window_load.size, |mini| mini.size),
             position,
+            maximized: window_load.is_maximized && mini.is_none(),
+            fullscreen: window_load.is_fullscreen && mini.is_none(),
+            level: window::level(window_load.is_always_on_top),
             min_size: Some(window::MIN_SIZE),
             exit_on_close_request: false,
+            decorations: window::decorations(config)
+                && !window_load.is_borderless,
+            visible: !start_hidden,
             ..window::settings(config)
         });
 
-        let (mut halloy, command) =
-            Halloy::load_from_state(main_window, config_load, current_mode);
-        let latest_remote_version =
-            Task::perform(version::latest_remote_version(), Message::Version);
+        let check_for_update = config.update.check;
+
+        let (mut halloy, command) = Halloy::load_from_state(
+            main_window,
+            profile,
+            config_load,
+            current_mode,
+        );
+        halloy.main_window.restore(window_load);
+        halloy.main_window.is_hidden = start_hidden;
+
+        let show_main_window = halloy.toggle_tray();
 
+        if let Screen::Dashboard(dashboard) = &mut halloy.screen
+            && halloy.main_window.is_mini
+        {
+            dashboard.set_mini_mode(true);
+        }
+        let latest_remote_release = if check_for_update {
+            Task::perform(version::latest_remote_release(), Message::Version)
+        } else {
+            Task::none()
+        };
+
+        let fonts_requested = Instant::now();
+        let load_fonts =
+            Task::batch(font::load().into_iter().map(iced::font::load))
+                .collect()
+                .map(move |results| {
+                    Message::FontsLoaded(fonts_requested, results)
+                });
+
+        // Fonts and history are loaded lazily, after the main window is shown
         let mut commands = vec![
-            open_main_window.then(|_| Task::none()),
-            command,
-            latest_remote_version,
+            open_main_window.then(|_| Task::none()).chain(Task::batch([
+                show_main_window,
+                load_fonts,
+                command,
+            ])),
+            latest_remote_release,
             Task::stream(log_stream).map(Message::Logging),
         ];
 
@@ -320,6 +457,122 @@ impl Halloy {
         (halloy, Task::batch(commands))
     }
 
+    fn exit(&mut self) -> Task<Message> {
+        let exit = if let Screen::Dashboard(dashboard) = &mut self.screen {
+            dashboard
+                .exit(&mut self.clients, &self.config)
+                .map(Message::Dashboard)
+        } else {
+            iced::exit()
+        };
+
+        self.save_window().chain(exit)
+    }
+
+    /// Applies a reloaded configuration, following the changes to servers,
+    /// themes, notification sounds and the tray.
+    fn reload_config(&mut self, updated: Config) -> Task<Message> {
+        let removed_servers = self
+            .servers
+            .extract_if(|server, _| !updated.servers.contains(&server.name))
+            .collect::<Vec<_>>();
+
+        for (server, config) in updated.servers.iter() {
+            let server = server.clone().into();
+            if let Some(server) = self.servers.get_mut(&server) {
+                *server = config.clone();
+            } else {
+                self.servers.insert(server, config.clone());
+            }
+        }
+
+        self.servers.set_order(updated.sidebar.order_by);
+
+        self.theme =
+            self.current_mode.theme(&updated.appearance.selected).into();
+
+        // Load new notification sounds.
+        self.notifications = Notifications::new(&updated);
+
+        let toggle_borderless = updated.borderless != self.config.borderless
+            && updated.borderless != self.main_window.is_borderless;
+
+        self.config = updated;
+        self.theme_overrides =
+            theme::Overrides::new(&self.config, self.current_mode);
+
+        let show_main_window = self.toggle_tray();
+
+        let mut tasks = removed_servers
+            .into_iter()
+            .filter_map(|(server, _)| {
+                self.clients
+                    .quit(&server, None)
+                    .map(|stream| run_on_connect(server, stream))
+            })
+            .collect::<Vec<_>>();
+
+        if let Screen::Dashboard(dashboard) = &mut self.screen {
+            dashboard.update_filters(
+                &self.servers,
+                &self.clients,
+                &self.config.buffer,
+            );
+        }
+
+        // Follows the configuration when it changes,
+        // otherwise the toggled state is kept
+        if toggle_borderless {
+            tasks.push(self.toggle_borderless());
+        }
+
+        tasks.push(show_main_window);
+
+        Task::batch(tasks)
+    }
+
+    fn toggle_borderless(&mut self) -> Task<Message> {
+        self.main_window.is_borderless = !self.main_window.is_borderless;
+        self.window_changed = Some(Instant::now());
+
+        window::toggle_borderless(self.main_window.id, &self.config)
+    }
+
+    /// Saves the main window if it changed since it was last saved.
+    fn save_window(&mut self) -> Task<Message> {
+        if self.window_changed.take().is_some() {
+            Task::perform(
+                data::Window::from(self.main_window).save(self.profile.clone()),
+                Message::WindowSettingsSaved,
+            )
+        } else {
+            Task::none()
+        }
+    }
+
+    /// Shows or removes the tray icon to match the configuration. The main
+    /// window is shown if it was hidden to a tray that's no longer there.
+    fn toggle_tray(&mut self) -> Task<Message> {
+        if !self.config.tray.enabled {
+            self.tray = None;
+        } else if self.tray.is_none() {
+            match tray::Tray::new() {
+                Ok(tray) => self.tray = Some(tray),
+                Err(error) => {
+                    log::warn!("failed to create tray icon: {error}");
+                }
+            }
+        }
+
+        if self.tray.is_none() && self.main_window.is_hidden {
+            self.main_window.is_hidden = false;
+
+            window::show(self.main_window.id, self.main_window.is_fullscreen)
+        } else {
+            Task::none()
+        }
+    }
+
     fn handle_url(&mut self, url: Url) -> Task<Message> {
         match url {
             data::Url::ServerConnect {
@@ -354,23 +607,33 @@ impl Halloy {
     }
 
     fn title(&self, _window_id: window::Id) -> String {
-        String::from("Halloy")
+        match self.profile.name() {
+            Some(profile) => format!("Halloy ({profile})"),
+            None => String::from("Halloy"),
+        }
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::AppearanceReloaded(appearance) => {
                 self.config.appearance = appearance;
+                self.theme_overrides =
+                    theme::Overrides::new(&self.config, self.current_mode);
                 Task::none()
             }
             Message::ScreenConfigReloaded(updated) => {
                 let (halloy, command) = Halloy::load_from_state(
                     self.main_window.id,
+                    self.profile.clone(),
                     updated,
                     self.current_mode,
                 );
+                let tray = self.tray.take();
                 *self = halloy;
-                command
+                self.tray = tray;
+                let show_main_window = self.toggle_tray();
+
+                Task::batch([command, show_main_window])
             }
             Message::Dashboard(message) => {
                 let Screen::Dashboard(dashboard) = &mut self.screen else {
@@ -392,61 +655,15 @@ impl Halloy {
                 let event_task = match event {
                     Some(dashboard::Event::ConfigReloaded(config)) => {
                         match config {
-                            Ok(updated) => {
-                                let removed_servers = self
-                                    .servers
-                                    .extract_if(|server, _| {
-                                        !updated.servers.contains(&server.name)
-                                    })
-                                    .collect::<Vec<_>>();
-
-                                for (server, config) in updated.servers.iter() {
-                                    let server = server.clone().into();
-                                    if let Some(server) =
-                                        self.servers.get_mut(&server)
-                                    {
-                                        *server = config.clone();
-                                    } else {
-                                        self.servers
-                                            .insert(server, config.clone());
-                                    }
-                                }
-
-                                self.servers
-                                    .set_order(updated.sidebar.order_by);
-
-                                self.theme = self
-                                    .current_mode
-                                    .theme(&updated.appearance.selected)
-                                    .into();
-
-                                // Load new notification sounds.
-                                self.notifications =
-                                    Notifications::new(&updated);
-
-                                self.config = updated;
-
-                                for (server, _) in removed_servers {
-                                    self.clients.quit(&server, None);
-                                }
-                                if let Screen::Dashboard(dashboard) =
-                                    &mut self.screen
-                                {
-                                    dashboard.update_filters(
-                                        &self.servers,
-                                        &self.clients,
-                                        &self.config.buffer,
-                                    );
-                                }
-                            }
+                            Ok(updated) => self.reload_config(updated),
                             Err(error) => {
                                 self.modal = Some(
                                     Modal::ReloadConfigurationError(error),
                                 );
-                            }
-                        };
 
-                        Task::none()
+                                Task::none()
+                            }
+                        }
                     }
                     Some(dashboard::Event::ReloadThemes) => {
                         Task::future(Config::load()).then(|config| match config
@@ -457,22 +674,28 @@ impl Halloy {
                             Err(_) => Task::none(),
                         })
                     }
-                    Some(dashboard::Event::QuitServer(server, reason)) => {
-                        self.clients.quit(&server, reason);
-                        Task::none()
-                    }
+                    Some(dashboard::Event::QuitServer(server, reason)) => self
+                        .clients
+                        .quit(&server, reason)
+                        .map_or_else(Task::none, |stream| {
+                            run_on_connect(server, stream)
+                        }),
                     Some(dashboard::Event::IrcError(e)) => {
                         handle_irc_error(e);
                         Task::none()
                     }
                     Some(dashboard::Event::Exit) => {
-                        let pending_exit = self.clients.exit();
+                        let (pending_exit, on_disconnect) = self.clients.exit();
 
                         if pending_exit.is_empty() {
                             iced::exit()
                         } else {
                             self.screen = Screen::Exit { pending_exit };
-                            Task::none()
+                            Task::batch(on_disconnect.into_iter().map(
+                                |(server, stream)| {
+                                    run_on_connect(server, stream)
+                                },
+                            ))
                         }
                     }
                     Some(dashboard::Event::OpenUrl(
@@ -504,9 +727,150 @@ impl Halloy {
                             url,
                             timer: None,
                             window: id,
+                            save_dialog: None,
+                        });
+                        Task::none()
+                    }
+                    Some(dashboard::Event::MessageDetails(message)) => {
+                        let Some((id, _, _)) = dashboard.get_focused() else {
+                            return Task::none();
+                        };
+
+                        self.modal = Some(Modal::MessageDetails {
+                            message,
+                            window: id,
+                        });
+                        Task::none()
+                    }
+                    Some(dashboard::Event::Share(
+                        messages,
+                        index,
+                        casemapping,
+                    )) => {
+                        let Some((id, _, _)) = dashboard.get_focused() else {
+                            return Task::none();
+                        };
+
+                        self.modal =
+                            Some(Modal::Share(modal::share::State::new(
+                                messages,
+                                index,
+                                casemapping,
+                                id,
+                            )));
+                        Task::none()
+                    }
+                    Some(dashboard::Event::ChannelInfo(server, channel)) => {
+                        let Some((id, _, _)) = dashboard.get_focused() else {
+                            return Task::none();
+                        };
+
+                        self.modal = Some(Modal::ChannelInfo {
+                            server,
+                            channel,
+                            window: id,
+                        });
+                        Task::none()
+                    }
+                    Some(dashboard::Event::ThemeBrowser) => {
+                        let (state, task) = modal::theme_browser::State::load();
+
+                        self.modal = Some(Modal::ThemeBrowser(state));
+
+                        task.map(Message::Modal)
+                    }
+                    Some(dashboard::Event::AccessList(server, channel)) => {
+                        let Some((id, _, _)) = dashboard.get_focused() else {
+                            return Task::none();
+                        };
+
+                        self.clients.request_access_list(&server, &channel);
+
+                        self.modal = Some(Modal::AccessList {
+                            server,
+                            channel,
+                            window: id,
+                            mask: String::new(),
+                            flags: String::new(),
+                        });
+                        Task::none()
+                    }
+                    Some(dashboard::Event::Identity(server)) => {
+                        let Some(config) = self.servers.get(&server) else {
+                            return Task::none();
+                        };
+
+                        self.modal = Some(Modal::Identity {
+                            server,
+                            config,
+                            window: self.main_window.id,
                         });
                         Task::none()
                     }
+                    Some(dashboard::Event::Print(buffer)) => {
+                        self.modal =
+                            Some(Modal::Print(modal::print::State::new(
+                                buffer,
+                                self.main_window.id,
+                            )));
+                        Task::none()
+                    }
+                    Some(dashboard::Event::Oper(buffer, name)) => {
+                        let Some((id, _, _)) = dashboard.get_focused() else {
+                            return Task::none();
+                        };
+
+                        let oper = self
+                            .servers
+                            .get(buffer.server())
+                            .and_then(|config| config.oper.as_ref());
+
+                        // Prefilled with the configured name, if any
+                        let name = name.or_else(|| {
+                            oper.and_then(|oper| oper.name.clone())
+                        });
+                        let has_challenge_key = oper
+                            .is_some_and(|oper| oper.challenge_key.is_some());
+
+                        let (state, task) = modal::oper::State::new(
+                            buffer,
+                            name,
+                            has_challenge_key,
+                            id,
+                        );
+
+                        self.modal = Some(Modal::Oper(state));
+
+                        task.map(Message::Modal)
+                    }
+                    Some(dashboard::Event::ToggleAlwaysOnTop) => {
+                        self.main_window.is_always_on_top =
+                            !self.main_window.is_always_on_top;
+
+                        self.window_changed = Some(Instant::now());
+
+                        window::set_always_on_top(
+                            self.main_window.id,
+                            self.main_window.is_always_on_top,
+                        )
+                    }
+                    Some(dashboard::Event::ToggleBorderless) => {
+                        self.toggle_borderless()
+                    }
+                    Some(dashboard::Event::ToggleMiniMode) => {
+                        let (geometry, maximized) =
+                            self.main_window.toggle_mini(&self.config);
+
+                        dashboard.set_mini_mode(self.main_window.is_mini);
+
+                        self.window_changed = Some(Instant::now());
+
+                        window::apply_geometry(
+                            self.main_window.id,
+                            geometry,
+                            maximized,
+                        )
+                    }
                     None => Task::none(),
                 };
 
@@ -517,11 +881,15 @@ impl Halloy {
                 ])
             }
             Message::Version(remote) => {
-                // Set latest known remote version
+                // Set latest known remote release
                 self.version.remote = remote;
 
                 Task::none()
             }
+            Message::Update(message) => self
+                .update_banner
+                .update(message, &self.version)
+                .map(Message::Update),
             Message::Help(message) => {
                 let Screen::Help(help) = &mut self.screen else {
                     return Task::none();
@@ -604,6 +972,9 @@ impl Halloy {
                         return Task::none();
                     };
 
+                    self.clients
+                        .set_monitored(&server, dashboard.monitored(&server));
+
                     let broadcast = if is_initial {
                         self.notifications.notify(
                             &self.config.notifications,
@@ -662,12 +1033,17 @@ impl Halloy {
                         )
                         .map(Message::Dashboard)
                 }
+                stream::Update::Lag(server, lag) => {
+                    self.clients.set_lag(&server, lag);
+
+                    Task::none()
+                }
                 stream::Update::MessagesReceived(server, messages) => {
                     let Screen::Dashboard(dashboard) = &mut self.screen else {
                         return Task::none();
                     };
 
-                    let commands = messages
+                    let mut commands = messages
                         .into_iter()
                         .flat_map(|message| {
                             let events = match self.clients.receive(
@@ -702,6 +1078,7 @@ impl Halloy {
                                 let statusmsg = self.clients.get_statusmsg(&server);
                                 let casemapping = self.clients.get_casemapping(&server);
                                 let prefix = self.clients.get_prefix(&server);
+                                let chanmodes = self.clients.get_chanmodes(&server);
 
                                 match event {
                                     Event::Single(encoded, our_nick) => {
@@ -715,7 +1092,10 @@ impl Halloy {
                                             statusmsg,
                                             casemapping,
                                             prefix,
-                                        ) {
+                                            chanmodes,
+                                        ) && let Some(message) =
+                                            dashboard.run_message_hooks(&server, message)
+                                        {
                                             commands.push(
                                                 dashboard
                                                     .record_message(
@@ -728,6 +1108,33 @@ impl Halloy {
                                             );
                                         }
                                     }
+                                    Event::SingleInCategory(encoded, our_nick, category) => {
+                                        if let Some(message) = data::Message::received(
+                                            encoded,
+                                            our_nick,
+                                            &self.config,
+                                            resolve_user_attributes,
+                                            channel_users,
+                                            chantypes,
+                                            statusmsg,
+                                            casemapping,
+                                            prefix,
+                                            chanmodes,
+                                        ) && let Some(message) =
+                                            dashboard.run_message_hooks(&server, message)
+                                        {
+                                            commands.push(
+                                                dashboard
+                                                    .record_message(
+                                                        &server,
+                                                        casemapping,
+                                                        message.with_category(category),
+                                                        &self.config.buffer,
+                                                    )
+                                                    .map(Message::Dashboard),
+                                            );
+                                        }
+                                    }
                                     Event::PrivOrNotice(
                                         encoded,
                                         our_nick,
@@ -743,7 +1150,20 @@ impl Halloy {
                                             statusmsg,
                                             casemapping,
                                             prefix,
-                                        ) {
+                                            chanmodes,
+                                        ) && let Some(message) =
+                                            dashboard.run_message_hooks(&server, message)
+                                        {
+                                            if !message.is_echo && highlight_notification_enabled {
+                                                for notification in self.config.search.notifications(&server, &message, casemapping) {
+                                                    self.notifications.notify(
+                                                        &self.config.notifications,
+                                                        &notification,
+                                                        &server,
+                                                    );
+                                                }
+                                            }
+
                                             if let Some((mut message, channel, user, description)) =
                                                 message.into_highlight(server.clone())
                                             {
@@ -755,6 +1175,11 @@ impl Halloy {
                                                 );
 
                                                 if !message.blocked && highlight_notification_enabled {
+                                                    let alias = self
+                                                        .config
+                                                        .alias(&server, &channel.to_target(), casemapping)
+                                                        .map(ToString::to_string);
+
                                                     self.notifications.notify(
                                                         &self.config.notifications,
                                                         &Notification::Highlight {
@@ -762,6 +1187,7 @@ impl Halloy {
                                                             channel,
                                                             message: message.text(),
                                                             description,
+                                                            alias,
                                                         },
                                                         &server,
                                                     );
@@ -796,7 +1222,10 @@ impl Halloy {
                                             statusmsg,
                                             casemapping,
                                             prefix,
-                                        ) {
+                                            chanmodes,
+                                        ) && let Some(message) =
+                                            dashboard.run_message_hooks(&server, message)
+                                        {
                                             commands.push(
                                                 dashboard
                                                     .record_message(
@@ -810,11 +1239,56 @@ impl Halloy {
                                         }
                                     }
                                     Event::Broadcast(broadcast) => match broadcast {
+                                        data::client::Broadcast::HostnameExposed {
+                                            hostname,
+                                            sent_time,
+                                        } => {
+                                            let casemapping = self.clients.get_casemapping(&server);
+
+                                            commands.push(
+                                                dashboard
+                                                    .broadcast(
+                                                        &server,
+                                                        casemapping,
+                                                        &self.config,
+                                                        sent_time,
+                                                        Broadcast::HostnameExposed {
+                                                            hostname,
+                                                        },
+                                                    )
+                                                    .map(Message::Dashboard),
+                                            );
+                                        }
+                                        data::client::Broadcast::CtcpRequest {
+                                            user,
+                                            command,
+                                            replied,
+                                            sent_time,
+                                        } => {
+                                            let casemapping = self.clients.get_casemapping(&server);
+
+                                            commands.push(
+                                                dashboard
+                                                    .broadcast(
+                                                        &server,
+                                                        casemapping,
+                                                        &self.config,
+                                                        sent_time,
+                                                        Broadcast::CtcpRequest {
+                                                            user,
+                                                            command,
+                                                            replied,
+                                                        },
+                                                    )
+                                                    .map(Message::Dashboard),
+                                            );
+                                        }
                                         data::client::Broadcast::Quit {
                                             user,
                                             comment,
                                             channels,
                                             sent_time,
+                                            batch,
                                         } => {
                                             let casemapping = self.clients.get_casemapping(&server);
 
@@ -830,6 +1304,7 @@ impl Halloy {
                                                             comment,
                                                             user_channels: channels,
                                                             casemapping,
+                                                            batch,
                                                         },
                                                     )
                                                     .map(Message::Dashboard),
@@ -960,6 +1435,19 @@ impl Halloy {
                                             commands.push(command.map(Message::Dashboard));
                                         }
                                     }
+                                    Event::DccChatRequest(request) => {
+                                        commands.push(
+                                            dashboard
+                                                .receive_dcc_chat(
+                                                    &server,
+                                                    casemapping,
+                                                    request,
+                                                    &mut self.clients,
+                                                    &self.config,
+                                                )
+                                                .map(Message::Dashboard),
+                                        );
+                                    }
                                     Event::UpdateReadMarker(target, read_marker) => {
                                         commands.push(
                                             dashboard
@@ -973,6 +1461,13 @@ impl Halloy {
                                                 .map(Message::Dashboard),
                                         );
                                     }
+                                    Event::ChannelRenamed(old, new) => {
+                                        commands.push(
+                                            dashboard
+                                                .rename_channel(&server, old, new, &self.clients)
+                                                .map(Message::Dashboard),
+                                        );
+                                    }
                                     Event::JoinedChannel(channel, server_time) => {
                                         let command = dashboard
                                             .load_metadata(
@@ -1027,6 +1522,14 @@ impl Halloy {
                                             commands.push(command);
                                         }
                                     }
+                                    Event::Backfilled(target, start, end) => {
+                                        dashboard.record_backfill(
+                                            server.clone(),
+                                            target,
+                                            start,
+                                            end,
+                                        );
+                                    }
                                     Event::DirectMessage(encoded, our_nick, user) => {
                                         if let Some(message) = data::Message::received(
                                             encoded,
@@ -1038,6 +1541,7 @@ impl Halloy {
                                             statusmsg,
                                             casemapping,
                                             prefix,
+                                            chanmodes,
                                         )
                                             && let Ok(query) = target::Query::parse(
                                                 user.as_str(),
@@ -1046,6 +1550,10 @@ impl Halloy {
                                                 casemapping,
                                             ) {
                                                 let blocked = FilterChain::borrow(dashboard.get_filters()).filter_query(&query);
+                                                let alias = self
+                                                    .config
+                                                    .alias(&server, &query.to_target(), casemapping)
+                                                    .map(ToString::to_string);
                                                 let has_unread = dashboard.history().has_unread(&history::Kind::Query(server.clone(), query));
 
                                                 if !blocked && (has_unread || !self.main_window.focused) {
@@ -1054,6 +1562,7 @@ impl Halloy {
                                                         &Notification::DirectMessage{
                                                             user,
                                                             message: message.text(),
+                                                            alias,
                                                         },
                                                         &server,
                                                     );
@@ -1077,19 +1586,18 @@ impl Halloy {
                                     Event::OnConnect(
                                         on_connect,
                                     ) => {
-                                        let server = server.clone();
-                                        commands.push(
-                                            Task::stream(on_connect)
-                                                .map(move |event| {
-                                                    Message::OnConnect(
-                                                        server.clone(),
-                                                        event
-                                                    )
-                                                })
-                                        );
+                                        commands.push(run_on_connect(
+                                            server.clone(),
+                                            on_connect,
+                                        ));
                                     }
                                     data::client::Event::AddedIsupportParam(param) => {
                                         if matches!(param, data::isupport::Parameter::CASEMAPPING(_)) {
+                                            commands.push(
+                                                dashboard
+                                                    .renormalize_targets(&server, &self.clients)
+                                                    .map(Message::Dashboard),
+                                            );
                                             dashboard.renormalize_history(&server, &self.clients);
                                         }
 
@@ -1111,9 +1619,48 @@ impl Halloy {
                                             dashboard.reprocess_history(&self.clients, &self.config.buffer);
                                         }
                                     }
+                                    data::client::Event::RemovedIsupportParam(kind) => {
+                                        if kind == data::isupport::Kind::CASEMAPPING {
+                                            commands.push(
+                                                dashboard
+                                                    .renormalize_targets(&server, &self.clients)
+                                                    .map(Message::Dashboard),
+                                            );
+                                            dashboard.renormalize_history(&server, &self.clients);
+                                        }
+
+                                        if matches!(
+                                            kind,
+                                            data::isupport::Kind::CASEMAPPING
+                                                | data::isupport::Kind::CHANTYPES
+                                        ) {
+                                            let chantypes = self.clients.get_chantypes(&server);
+                                            let casemapping = self.clients.get_casemapping(&server);
+
+                                            FilterChain::sync_isupport(
+                                                dashboard.get_filters(),
+                                                &server,
+                                                chantypes,
+                                                casemapping
+                                            );
+
+                                            dashboard.reprocess_history(&self.clients, &self.config.buffer);
+                                        }
+                                    }
                                     Event::BouncerNetwork(server, config) => {
                                         self.servers.insert(server, config.into());
                                     }
+                                    Event::HostmaskResolved(hostmask) => {
+                                        commands.push(iced::clipboard::write(hostmask));
+                                    }
+                                    Event::ChannelKeysUpdated => {
+                                        if let Some(command) = dashboard
+                                            .save_channel_keys(&self.clients, &server)
+                                            .map(|command| command.map(Message::Dashboard))
+                                        {
+                                            commands.push(command);
+                                        }
+                                    }
                                 }
                             }
 
@@ -1121,6 +1668,10 @@ impl Halloy {
                         })
                         .collect::<Vec<_>>();
 
+                    commands.push(
+                        dashboard.script_actions().map(Message::Dashboard),
+                    );
+
                     Task::batch(commands)
                 }
                 stream::Update::Quit(server, reason) => {
@@ -1147,6 +1698,7 @@ impl Halloy {
                                             comment: reason,
                                             user_channels: channels,
                                             casemapping,
+                                            batch: None,
                                         },
                                     )
                                     .map(Message::Dashboard)
@@ -1184,28 +1736,130 @@ impl Halloy {
                 Task::none()
             }
             Message::Tick(now) => {
+                if let Screen::Dashboard(dashboard) = &self.screen {
+                    self.clients.track_queries(&dashboard.open_queries());
+                    self.clients
+                        .set_visible_channels(&dashboard.open_channels());
+                }
+
                 if let Err(e) = self.clients.tick(now) {
                     handle_irc_error(e);
                 }
 
-                if let Screen::Dashboard(dashboard) = &mut self.screen {
-                    dashboard.tick(now, &self.clients).map(Message::Dashboard)
-                } else {
-                    Task::none()
+                self.notifications.tick(now);
+
+                if let (Some(tray), Screen::Dashboard(dashboard)) =
+                    (&mut self.tray, &self.screen)
+                {
+                    tray.set_indicator(if self.config.tray.show_unread {
+                        tray::Indicator::new(
+                            dashboard.has_unread(),
+                            dashboard.has_highlight(),
+                        )
+                    } else {
+                        tray::Indicator::Idle
+                    });
                 }
+
+                let save_window =
+                    if self.window_changed.is_some_and(|changed| {
+                        now.duration_since(changed) >= SAVE_WINDOW_AFTER
+                    }) {
+                        self.save_window()
+                    } else {
+                        Task::none()
+                    };
+
+                let tick =
+                    if let Screen::Dashboard(dashboard) = &mut self.screen {
+                        dashboard
+                            .tick(now, &self.clients, &self.config)
+                            .map(Message::Dashboard)
+                    } else {
+                        Task::none()
+                    };
+
+                Task::batch(vec![save_window, tick])
+            }
+            Message::RefreshNicklists => {
+                self.clients.refresh_nicklists();
+
+                Task::none()
             }
             Message::Modal(message) => {
                 let Some(modal) = &mut self.modal else {
                     return Task::none();
                 };
 
-                let (command, event) = modal.update(message);
+                let (command, event) =
+                    modal.update(message, &self.config, &self.theme);
 
                 if let Some(event) = event {
                     match event {
                         modal::Event::CloseModal => {
                             self.modal = None;
                         }
+                        modal::Event::RequestAccessList => {
+                            if let Some(Modal::AccessList {
+                                server,
+                                channel,
+                                ..
+                            }) = &self.modal
+                            {
+                                self.clients
+                                    .request_access_list(server, channel);
+                            }
+                        }
+                        modal::Event::AddAccess { mask, flags } => {
+                            if let Some(Modal::AccessList {
+                                server,
+                                channel,
+                                ..
+                            }) = &self.modal
+                            {
+                                self.clients
+                                    .add_access(server, channel, &mask, &flags);
+                            }
+                        }
+                        modal::Event::RemoveAccess { mask } => {
+                            if let Some(Modal::AccessList {
+                                server,
+                                channel,
+                                ..
+                            }) = &self.modal
+                            {
+                                self.clients
+                                    .remove_access(server, channel, &mask);
+                            }
+                        }
+                        modal::Event::Oper {
+                            buffer,
+                            name,
+                            password,
+                        } => {
+                            self.modal = None;
+
+                            if let Err(error) =
+                                self.clients.oper(&buffer, Some(name), password)
+                            {
+                                log::warn!("failed to send oper: {error}");
+                            }
+                        }
+                        modal::Event::ReloadThemes => {
+                            return Task::batch([
+                                command.map(Message::Modal),
+                                Task::future(Config::load()).then(|config| {
+                                    match config {
+                                        Ok(config) => Task::done(
+                                            Message::AppearanceReloaded(
+                                                config.appearance,
+                                            ),
+                                        ),
+                                        Err(_) => Task::none(),
+                                    }
+                                }),
+                            ]);
+                        }
                         modal::Event::AcceptNewServer => {
                             if let Some(Modal::ServerConnect {
                                 server,
@@ -1269,10 +1923,20 @@ impl Halloy {
                 if id == self.main_window.id {
                     match event {
                         window::Event::Moved(position) => {
-                            self.main_window.position = Some(position);
+                            return window::state(id).map(move |state| {
+                                Message::MainWindowChanged(
+                                    window::Change::Moved(position),
+                                    state,
+                                )
+                            });
                         }
                         window::Event::Resized(size) => {
-                            self.main_window.size = size;
+                            return window::state(id).map(move |state| {
+                                Message::MainWindowChanged(
+                                    window::Change::Resized(size),
+                                    state,
+                                )
+                            });
                         }
                         window::Event::Focused => {
                             self.main_window.focused = true;
@@ -1284,22 +1948,20 @@ impl Halloy {
                             self.main_window.opened(position, size);
                         }
                         window::Event::CloseRequested => {
-                            if let Screen::Dashboard(dashboard) =
-                                &mut self.screen
+                            if self.tray.is_some()
+                                && self.config.tray.close_to_tray
                             {
-                                return dashboard
-                                    .exit(&mut self.clients, &self.config)
-                                    .map(Message::Dashboard);
-                            } else {
-                                return iced::exit();
+                                self.main_window.is_hidden = true;
+                                return window::hide(id);
                             }
+
+                            return self.exit();
                         }
                     }
 
-                    let mut tasks = vec![Task::perform(
-                        data::Window::from(self.main_window).save(),
-                        Message::WindowSettingsSaved,
-                    )];
+                    self.window_changed = Some(Instant::now());
+
+                    let mut tasks = vec![];
 
                     if let Some(Screen::Dashboard(dashboard)) =
                         matches!(event, window::Event::Focused)
@@ -1321,6 +1983,12 @@ impl Halloy {
                     Task::none()
                 }
             }
+            Message::MainWindowChanged(change, state) => {
+                self.main_window.changed(change, state);
+                self.window_changed = Some(Instant::now());
+
+                Task::none()
+            }
             Message::WindowSettingsSaved(result) => {
                 if let Err(err) = result {
                     log::error!("window settings failed to save: {err:?}");
@@ -1328,11 +1996,130 @@ impl Halloy {
 
                 Task::none()
             }
+            Message::FontsLoaded(requested, results) => {
+                for result in results {
+                    if let Err(err) = result {
+                        log::error!("failed to load font: {err:?}");
+                    }
+                }
+
+                startup::record(startup::Stage::FontLoad, requested.elapsed());
+
+                Task::none()
+            }
+            Message::Tray(event) => match event {
+                tray::Event::ToggleWindow => {
+                    let id = self.main_window.id;
+
+                    self.main_window.is_hidden = !self.main_window.is_hidden;
+
+                    if self.main_window.is_hidden {
+                        window::hide(id)
+                    } else {
+                        window::show(id, self.main_window.is_fullscreen)
+                    }
+                }
+                tray::Event::MarkAllAsRead => {
+                    if let Screen::Dashboard(dashboard) = &mut self.screen {
+                        dashboard.mark_all_as_read(&mut self.clients);
+                    }
+
+                    Task::none()
+                }
+                tray::Event::Quit => self.exit(),
+            },
+            Message::TitleBar(message) => {
+                let id = self.main_window.id;
+
+                match message {
+                    title_bar::Message::Drag => window::drag(id),
+                    title_bar::Message::Minimize => window::minimize(id, true),
+                    title_bar::Message::ToggleMaximize => {
+                        window::toggle_maximize(id)
+                    }
+                    title_bar::Message::Close => Task::done(Message::Window(
+                        id,
+                        window::Event::CloseRequested,
+                    )),
+                }
+            }
+            Message::FilesChanged(change) => match change {
+                watcher::Change::Config => match &self.screen {
+                    Screen::Dashboard(_) => {
+                        Task::perform(Config::load(), Message::ConfigChanged)
+                    }
+                    // Leave the help or welcome screen once the
+                    // configuration is fixed or created
+                    Screen::Help(_) | Screen::Welcome(_) => Task::perform(
+                        Config::load(),
+                        Message::ScreenConfigReloaded,
+                    ),
+                    Screen::Exit { .. } => Task::none(),
+                },
+                watcher::Change::Themes(paths) => Task::perform(
+                    async move {
+                        for path in paths {
+                            Config::check_theme(&path).await?;
+                        }
+
+                        Config::load().await
+                    },
+                    Message::ThemesChanged,
+                ),
+                watcher::Change::Scripts => match &mut self.screen {
+                    Screen::Dashboard(dashboard) => {
+                        dashboard.load_scripts().map(Message::Dashboard)
+                    }
+                    Screen::Help(_)
+                    | Screen::Welcome(_)
+                    | Screen::Exit { .. } => Task::none(),
+                },
+            },
+            Message::ConfigChanged(config) => match config {
+                Ok(updated) => self.reload_config(updated),
+                Err(error) => {
+                    log::warn!("configuration not reloaded: {error}");
+                    notification::toast(
+                        "Configuration not reloaded",
+                        &error.to_string(),
+                    );
+
+                    Task::none()
+                }
+            },
+            Message::ThemesChanged(config) => match config {
+                Ok(updated) => {
+                    let mut appearance = updated.appearance;
+                    appearance.selected = self
+                        .config
+                        .appearance
+                        .selected
+                        .reloaded(&appearance.all);
+
+                    self.theme =
+                        self.current_mode.theme(&appearance.selected).into();
+
+                    Task::done(Message::AppearanceReloaded(appearance))
+                }
+                Err(error) => {
+                    log::warn!("themes not reloaded: {error}");
+                    notification::toast(
+                        "Themes not reloaded",
+                        &error.to_string(),
+                    );
+
+                    Task::none()
+                }
+            },
             Message::AppearanceChange(mode) => {
+                // Tracked regardless of the selected theme, since overrides
+                // can follow the OS appearance on their own
+                self.current_mode = mode;
+                self.theme_overrides.set_mode(mode);
+
                 if let data::appearance::Selected::Dynamic { .. } =
                     &self.config.appearance.selected
                 {
-                    self.current_mode = mode;
                     self.theme = self
                         .current_mode
                         .theme(&self.config.appearance.selected)
@@ -1424,16 +2211,43 @@ impl Halloy {
 
         // Main window.
         if id == self.main_window.id {
+            if startup::is_enabled() {
+                startup::mark(startup::Stage::FirstFrame);
+
+                let history_loaded = match &self.screen {
+                    Screen::Dashboard(dashboard) => dashboard.history_loaded(),
+                    _ => true,
+                };
+
+                if history_loaded {
+                    startup::mark(startup::Stage::HistoryLoad);
+                }
+            }
+
             let screen = match &self.screen {
-                Screen::Dashboard(dashboard) => dashboard
-                    .view(
-                        &self.servers,
-                        &self.clients,
+                Screen::Dashboard(dashboard) => {
+                    let dashboard = dashboard
+                        .view(
+                            &self.servers,
+                            &self.clients,
+                            &self.version,
+                            self.main_window.is_mini,
+                            &self.config,
+                            &self.theme,
+                            &self.theme_overrides,
+                        )
+                        .map(Message::Dashboard);
+
+                    if let Some(banner) = self.update_banner.view(
                         &self.version,
                         &self.config,
                         &self.theme,
-                    )
-                    .map(Message::Dashboard),
+                    ) {
+                        column![banner.map(Message::Update), dashboard].into()
+                    } else {
+                        dashboard
+                    }
+                }
                 Screen::Help(help) => help.view(&self.theme).map(Message::Help),
                 Screen::Welcome(welcome) => {
                     welcome.view(&self.theme).map(Message::Welcome)
@@ -1441,8 +2255,31 @@ impl Halloy {
                 Screen::Exit { .. } => column![].into(),
             };
 
+            let title_bar = (self.main_window.is_borderless
+                && !self.main_window.is_fullscreen)
+                .then(|| {
+                    let lag = match &self.screen {
+                        Screen::Dashboard(dashboard) => {
+                            dashboard.focused_server().and_then(|server| {
+                                self.clients
+                                    .lag(&server)
+                                    .map(|lag| (server, lag))
+                            })
+                        }
+                        _ => None,
+                    };
+
+                    title_bar::view(
+                        lag,
+                        self.main_window.is_maximized,
+                        &self.config,
+                        &self.theme,
+                    )
+                    .map(Message::TitleBar)
+                });
+
             let content = container(
-                container(screen)
+                container(column![title_bar, screen])
                     .width(Length::Fill)
                     .height(Length::Fill)
                     .style(theme::container::general),
@@ -1458,7 +2295,9 @@ impl Halloy {
                 {
                     widget::modal(
                         content,
-                        modal.view(&self.theme).map(Message::Modal),
+                        modal
+                            .view(&self.clients, &self.config, &self.theme)
+                            .map(Message::Modal),
                         || Message::Modal(modal::Message::Cancel),
                     )
                 }
@@ -1468,7 +2307,13 @@ impl Halloy {
         } else if let Screen::Dashboard(dashboard) = &self.screen {
             let content = container(
                 dashboard
-                    .view_window(id, &self.clients, &self.config, &self.theme)
+                    .view_window(
+                        id,
+                        &self.clients,
+                        &self.config,
+                        &self.theme,
+                        &self.theme_overrides,
+                    )
                     .map(Message::Dashboard),
             )
             .padding(padding::top(platform_specific_padding));
@@ -1478,7 +2323,9 @@ impl Halloy {
             match &self.modal {
                 Some(modal) if modal.window_id() == Some(id) => widget::modal(
                     content,
-                    modal.view(&self.theme).map(Message::Modal),
+                    modal
+                        .view(&self.clients, &self.config, &self.theme)
+                        .map(Message::Modal),
                     || Message::Modal(modal::Message::Cancel),
                 ),
                 _ => column![content].into(),
@@ -1515,8 +2362,40 @@ impl Halloy {
             streams,
         ];
 
+        if self.tray.is_some() {
+            subscriptions.push(tray::events().map(Message::Tray));
+        }
+
+        // The help and welcome screens watch for the configuration to be
+        // fixed or created, as there's no loaded configuration to ask
+        let watch_files = match &self.screen {
+            Screen::Dashboard(_) => self.config.reload_on_change,
+            Screen::Help(_) | Screen::Welcome(_) => true,
+            Screen::Exit { .. } => false,
+        };
+
+        if watch_files {
+            subscriptions
+                .push(watcher::subscription().map(Message::FilesChanged));
+        }
+
+        if let Screen::Dashboard(dashboard) = &self.screen {
+            subscriptions.push(watcher::scripts().map(Message::FilesChanged));
+            subscriptions
+                .push(dashboard.subscription().map(Message::Dashboard));
+        }
+
+        // Nicklists only follow their channel's users periodically, so a
+        // flood of joins, parts or names replies is laid out once
+        if self.clients.has_stale_nicklists() {
+            subscriptions.push(
+                iced::time::every(client::NICKLIST_REFRESH_INTERVAL)
+                    .map(|_| Message::RefreshNicklists),
+            );
+        }
+
         // We only want to listen for appearance changes if user has dynamic themes.
-        if self.config.appearance.selected.is_dynamic() {
+        if self.config.follows_os_appearance() {
             subscriptions.push(
                 appearance::subscription().map(Message::AppearanceChange),
             );
@@ -1,16 +1,27 @@
 use std::path::PathBuf;
 use std::time::Instant;
 
-use data::{Server, config};
-use iced::Task;
+use std::sync::Arc;
+
+use data::appearance::theme::community;
+use data::{Config, Server, config, target};
+use iced::{Rectangle, Task, task};
 
 use crate::widget::Element;
-use crate::{Theme, window};
+use crate::{Theme, dialog, window};
 
+pub mod access_list;
+pub mod channel_info;
 pub mod connect_to_server;
+pub mod identity;
 pub mod image_preview;
+pub mod message_details;
+pub mod oper;
+pub mod print;
 pub mod prompt_before_open_url;
 pub mod reload_configuration_error;
+pub mod share;
+pub mod theme_browser;
 
 #[derive(Debug)]
 pub enum Modal {
@@ -29,7 +40,33 @@ pub enum Modal {
         url: url::Url,
         timer: Option<Instant>,
         window: window::Id,
+        save_dialog: Option<task::Handle>,
+    },
+    MessageDetails {
+        message: Box<data::Message>,
+        window: window::Id,
+    },
+    ChannelInfo {
+        server: Server,
+        channel: target::Channel,
+        window: window::Id,
+    },
+    AccessList {
+        server: Server,
+        channel: target::Channel,
+        window: window::Id,
+        mask: String,
+        flags: String,
+    },
+    Identity {
+        server: Server,
+        config: Arc<config::Server>,
+        window: window::Id,
     },
+    ThemeBrowser(theme_browser::State),
+    Share(share::State),
+    Oper(oper::State),
+    Print(print::State),
 }
 
 #[derive(Debug, Clone)]
@@ -39,6 +76,57 @@ pub enum Message {
     // Modal specific messages
     ServerConnect(ServerConnect),
     ImagePreview(ImagePreview),
+    AccessList(AccessList),
+    ThemeBrowser(ThemeBrowser),
+    Share(Share),
+    Oper(Oper),
+    Print(Print),
+}
+
+#[derive(Debug, Clone)]
+pub enum ThemeBrowser {
+    IndexLoaded(Result<Vec<community::Entry>, community::Error>),
+    Select(community::Entry),
+    Fetched(Result<community::Candidate, community::Error>),
+    Install,
+    Installed(Result<String, community::Error>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Share {
+    Select(usize),
+    Anonymize(bool),
+    Timestamps(bool),
+    CopyText,
+    CopyHtml,
+    SaveImage,
+    SaveImageTo(Option<PathBuf>),
+    Captured(PathBuf, Option<Rectangle>, iced::window::Screenshot),
+    SavedImage(Result<PathBuf, String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum Oper {
+    Name(String),
+    Password(String),
+    Submit,
+}
+
+#[derive(Debug, Clone)]
+pub enum Print {
+    From(String),
+    To(String),
+    Export,
+    Exported(Result<PathBuf, String>),
+}
+
+#[derive(Debug, Clone)]
+pub enum AccessList {
+    Refresh,
+    Mask(String),
+    Flags(String),
+    Add,
+    Remove(String),
 }
 
 #[derive(Debug, Clone)]
@@ -56,6 +144,21 @@ pub enum ServerConnect {
 pub enum Event {
     CloseModal,
     AcceptNewServer,
+    RequestAccessList,
+    AddAccess {
+        mask: String,
+        flags: String,
+    },
+    RemoveAccess {
+        mask: String,
+    },
+    ReloadThemes,
+    Oper {
+        buffer: data::buffer::Upstream,
+        name: String,
+        /// Empty to answer a CHALLENGE with the configured `challenge_key`
+        password: Option<String>,
+    },
 }
 
 impl Modal {
@@ -69,13 +172,24 @@ impl Modal {
                 url: _,
                 timer: _,
                 window,
+                save_dialog: _,
             } => Some(*window),
+            Modal::MessageDetails { message: _, window } => Some(*window),
+            Modal::ChannelInfo { window, .. } => Some(*window),
+            Modal::AccessList { window, .. } => Some(*window),
+            Modal::Identity { window, .. } => Some(*window),
+            Modal::ThemeBrowser(_) => None,
+            Modal::Share(state) => Some(state.window()),
+            Modal::Oper(state) => Some(state.window()),
+            Modal::Print(state) => Some(state.window()),
         }
     }
 
     pub fn update(
         &mut self,
         message: Message,
+        config: &Config,
+        theme: &Theme,
     ) -> (Task<Message>, Option<Event>) {
         match message {
             Message::Cancel => (Task::none(), Some(Event::CloseModal)),
@@ -95,42 +209,104 @@ impl Modal {
                 let _ = open::that_detached(url);
                 (Task::none(), Some(Event::CloseModal))
             }
+            Message::AccessList(access_list) => {
+                let Modal::AccessList { mask, flags, .. } = self else {
+                    return (Task::none(), None);
+                };
+
+                let event = match access_list {
+                    AccessList::Refresh => Some(Event::RequestAccessList),
+                    AccessList::Mask(value) => {
+                        *mask = value;
+                        None
+                    }
+                    AccessList::Flags(value) => {
+                        *flags = value;
+                        None
+                    }
+                    AccessList::Add => {
+                        (!mask.trim().is_empty()).then(|| Event::AddAccess {
+                            mask: std::mem::take(mask).trim().to_string(),
+                            flags: std::mem::take(flags).trim().to_string(),
+                        })
+                    }
+                    AccessList::Remove(mask) => {
+                        Some(Event::RemoveAccess { mask })
+                    }
+                };
+
+                (Task::none(), event)
+            }
+            Message::ThemeBrowser(message) => {
+                if let Modal::ThemeBrowser(state) = self {
+                    state.update(message)
+                } else {
+                    (Task::none(), None)
+                }
+            }
+            Message::Share(message) => {
+                if let Modal::Share(state) = self {
+                    state.update(message, config, theme)
+                } else {
+                    (Task::none(), None)
+                }
+            }
+            Message::Oper(message) => {
+                if let Modal::Oper(state) = self {
+                    state.update(message)
+                } else {
+                    (Task::none(), None)
+                }
+            }
+            Message::Print(message) => {
+                if let Modal::Print(state) = self {
+                    state.update(message, config)
+                } else {
+                    (Task::none(), None)
+                }
+            }
             Message::ImagePreview(image_preview) => match image_preview {
-                ImagePreview::SaveImage(source) => (
-                    Task::perform(
-                        async move {
-                            if let Some(handle) = rfd::AsyncFileDialog::new()
-                                .set_file_name(
-                                    source
-                                        .file_name()
-                                        .and_then(|n| n.to_str())
-                                        .unwrap_or_default(),
-                                )
-                                .save_file()
-                                .await
-                            {
-                                let destination = handle.path();
-                                tokio::fs::copy(&source, destination)
-                                    .await
-                                    .ok()
-                                    .map(|_| destination.to_path_buf())
-                            } else {
-                                None
-                            }
-                        },
-                        move |path| {
-                            Message::ImagePreview(ImagePreview::SavedImage(
-                                path,
-                            ))
-                        },
-                    ),
-                    None,
-                ),
+                ImagePreview::SaveImage(source) => {
+                    let (task, handle) =
+                        dialog::save_file(dialog::file_name(&source), || None);
+
+                    if let Modal::ImagePreview { save_dialog, .. } = self {
+                        *save_dialog = Some(handle);
+                    }
+
+                    (
+                        task.then(move |destination| {
+                            let source = source.clone();
+
+                            Task::perform(
+                                async move {
+                                    let destination = destination?;
+
+                                    tokio::fs::copy(&source, &destination)
+                                        .await
+                                        .ok()
+                                        .map(|_| destination)
+                                },
+                                |path| {
+                                    Message::ImagePreview(
+                                        ImagePreview::SavedImage(path),
+                                    )
+                                },
+                            )
+                        }),
+                        None,
+                    )
+                }
                 ImagePreview::SavedImage(path) => {
-                    if path.is_some()
-                        && let Modal::ImagePreview { timer, .. } = self
+                    if let Modal::ImagePreview {
+                        timer, save_dialog, ..
+                    } = self
                     {
-                        *timer = Some(Instant::now());
+                        *save_dialog = None;
+
+                        if path.is_some() {
+                            *timer = Some(Instant::now());
+                        }
                     }
 
                     (Task::none(), None)
@@ -139,14 +315,23 @@ impl Modal {
         }
     }
 
-    pub fn view<'a>(&'a self, theme: &'a Theme) -> Element<'a, Message> {
+    pub fn view<'a>(
+        &'a self,
+        clients: &'a data::client::Map,
+        config: &'a Config,
+        theme: &'a Theme,
+    ) -> Element<'a, Message> {
         match self {
             Modal::ReloadConfigurationError(error) => {
                 reload_configuration_error::view(error, theme)
             }
             Modal::ServerConnect {
-                url: raw, config, ..
-            } => connect_to_server::view(raw, config, theme),
+                url: raw,
+                config: server_config,
+                ..
+            } => {
+                connect_to_server::view(raw, server_config, &config.ctcp, theme)
+            }
             Modal::PromptBeforeOpenUrl { url, window: _ } => {
                 prompt_before_open_url::view(url, theme)
             }
@@ -155,7 +340,34 @@ impl Modal {
                 url,
                 timer,
                 window: _,
+                save_dialog: _,
             } => image_preview::view(source, url, timer, theme),
+            Modal::MessageDetails { message, window: _ } => {
+                message_details::view(message, theme)
+            }
+            Modal::ChannelInfo {
+                server,
+                channel,
+                window: _,
+            } => channel_info::view(server, channel, clients, theme),
+            Modal::AccessList {
+                server,
+                channel,
+                mask,
+                flags,
+                window: _,
+            } => {
+                access_list::view(server, channel, mask, flags, clients, theme)
+            }
+            Modal::Identity {
+                server,
+                config: server_config,
+                window: _,
+            } => identity::view(server, server_config, clients, config, theme),
+            Modal::ThemeBrowser(state) => theme_browser::view(state, theme),
+            Modal::Share(state) => share::view(state, config, theme),
+            Modal::Oper(state) => oper::view(state, theme),
+            Modal::Print(state) => print::view(state, theme),
         }
     }
 }
@@ -0,0 +1,188 @@
+use data::user::{AccessLevel, Nick};
+use data::{Server, User, target};
+use iced::widget::{
+    Column, button, column, container, row, scrollable, text, text_input,
+};
+use iced::{Length, alignment};
+
+use super::{AccessList, Message};
+use crate::widget::Element;
+use crate::{Theme, font, theme};
+
+pub fn view<'a>(
+    server: &'a Server,
+    channel: &'a target::Channel,
+    mask: &'a str,
+    flags: &'a str,
+    clients: &'a data::client::Map,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let secondary = |value: String| {
+        text(value)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+    };
+    let primary = |value: String| {
+        text(value)
+            .style(theme::text::primary)
+            .font_maybe(theme::font_style::primary(theme).map(font::get))
+    };
+    let small_button = |label: &'a str, message: Option<Message>| {
+        button(text(label))
+            .padding([2, 6])
+            .style(|theme, status| {
+                theme::button::secondary(theme, status, false)
+            })
+            .on_press_maybe(message)
+    };
+
+    // Changes are only offered to channel operators, services have the
+    // final say on whether they're allowed
+    let can_change = clients
+        .nickname(server)
+        .map(|nick| User::from(Nick::from(nick)))
+        .and_then(|user| {
+            clients.resolve_user_attributes(server, channel, &user)
+        })
+        .is_some_and(|user| user.has_access_level(AccessLevel::Oper));
+
+    let list = clients.access_list(server, channel);
+    let is_pending = clients.is_access_list_pending(server, channel);
+
+    let status = if is_pending {
+        Some(secondary("Waiting for services…".to_string()))
+    } else if list.is_none() {
+        Some(secondary("Not requested yet".to_string()))
+    } else if list.is_some_and(|list| list.entries.is_empty()) {
+        Some(secondary("No entries".to_string()))
+    } else {
+        None
+    };
+
+    let table: Element<'a, Message> = match list {
+        Some(list) if !list.entries.is_empty() => {
+            let header = (!list.header.is_empty()).then(|| {
+                row(list.header.iter().enumerate().map(|(index, column)| {
+                    cell(index, secondary(column.clone()).into())
+                }))
+                .push(can_change.then(|| container(text("")).width(64)))
+                .spacing(8)
+            });
+
+            let entries = list.entries.iter().map(|entry| {
+                let remove = can_change.then(|| {
+                    container(small_button(
+                        "Remove",
+                        list.mask(entry).map(|mask| {
+                            Message::AccessList(AccessList::Remove(
+                                mask.to_string(),
+                            ))
+                        }),
+                    ))
+                    .width(64)
+                });
+
+                row(entry.fields.iter().enumerate().map(|(index, field)| {
+                    cell(index, primary(field.clone()).into())
+                }))
+                .push(remove)
+                .spacing(8)
+                .align_y(alignment::Vertical::Center)
+                .into()
+            });
+
+            scrollable(
+                Column::new()
+                    .push(header)
+                    .extend(entries)
+                    .spacing(4)
+                    .padding([0, 8]),
+            )
+            .height(Length::Shrink)
+            .into()
+        }
+        _ => column![].into(),
+    };
+
+    let notes = column(
+        list.into_iter()
+            .flat_map(|list| &list.notes)
+            .map(|note| secondary(note.clone()).into()),
+    )
+    .spacing(2);
+
+    let add = can_change.then(|| {
+        let on_submit = Message::AccessList(AccessList::Add);
+
+        row![
+            text_input("Nickname or mask", mask)
+                .on_input(|value| Message::AccessList(AccessList::Mask(value)))
+                .on_submit(on_submit.clone())
+                .padding([2, 6])
+                .style(theme::text_input::primary),
+            text_input("Flags", flags)
+                .on_input(|value| {
+                    Message::AccessList(AccessList::Flags(value))
+                })
+                .on_submit(on_submit.clone())
+                .padding([2, 6])
+                .width(120)
+                .style(theme::text_input::primary),
+            small_button(
+                "Add",
+                (!mask.trim().is_empty()).then_some(on_submit),
+            ),
+        ]
+        .spacing(4)
+        .align_y(alignment::Vertical::Center)
+    });
+
+    let footer_button = |label: &'a str, message: Message| {
+        button(
+            container(text(label))
+                .align_x(alignment::Horizontal::Center)
+                .width(Length::Fill),
+        )
+        .padding(5)
+        .width(Length::Fixed(120.0))
+        .style(|theme, status| theme::button::secondary(theme, status, false))
+        .on_press(message)
+    };
+
+    container(
+        column![
+            text(format!("{channel} access list")),
+            status,
+            table,
+            notes,
+            add,
+            row![
+                footer_button(
+                    "Refresh",
+                    Message::AccessList(AccessList::Refresh)
+                ),
+                footer_button("Close", Message::Cancel),
+            ]
+            .spacing(8),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(700)
+    .width(Length::Fill)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
+
+/// The first column holds the entry's number
+fn cell<'a>(
+    index: usize,
+    content: Element<'a, Message>,
+) -> Element<'a, Message> {
+    if index == 0 {
+        container(content).width(48).into()
+    } else {
+        container(content).width(Length::Fill).into()
+    }
+}
@@ -0,0 +1,112 @@
+use chrono::Local;
+use data::{Server, mode, target};
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Length, alignment};
+
+use super::Message;
+use crate::widget::Element;
+use crate::{Theme, font, theme};
+
+pub fn view<'a>(
+    server: &'a Server,
+    channel: &'a target::Channel,
+    clients: &'a data::client::Map,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let field = |label: &'a str, value: String| -> Element<'a, Message> {
+        row![
+            text(label)
+                .style(theme::text::secondary)
+                .font_maybe(theme::font_style::secondary(theme).map(font::get))
+                .width(Length::Fixed(80.0)),
+            text(value)
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get)),
+        ]
+        .spacing(8)
+        .into()
+    };
+    let secondary = |value: &'a str| -> Element<'a, Message> {
+        text(value)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+            .into()
+    };
+
+    let metadata = clients.get_channel_metadata(server, channel);
+    let users = clients
+        .get_channel_users(server, channel)
+        .map(data::user::ChannelUsers::len);
+
+    let fields = column![
+        field("Server", server.to_string()),
+        users.map(|users| field("Users", users.to_string())),
+        metadata
+            .and_then(data::client::Metadata::limit)
+            .map(|limit| field("User limit", limit.to_string())),
+        // Don't show the key itself
+        metadata
+            .and_then(data::client::Metadata::key)
+            .map(|_| field("Key", String::from("Required to join"))),
+        metadata
+            .and_then(|metadata| metadata.created)
+            .map(|created| {
+                field("Created", created.with_timezone(&Local).to_rfc2822())
+            }),
+    ]
+    .spacing(2);
+
+    let modes: Element<'a, Message> = match metadata
+        .and_then(data::client::Metadata::modes)
+    {
+        None => secondary("Not received yet"),
+        Some([]) => secondary("No modes set"),
+        Some(modes) => scrollable(column(modes.iter().map(|(mode, arg)| {
+            let name = mode::Channel::from(*mode).to_string();
+            let arg = match (mode, arg) {
+                ('k', _) | (_, None) => String::new(),
+                (_, Some(arg)) => format!(" {arg}"),
+            };
+
+            text(format!("+{mode} {name}{arg}"))
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get))
+                .into()
+        })))
+        .height(Length::Shrink)
+        .into(),
+    };
+
+    container(
+        column![
+            text(channel.to_string()),
+            fields,
+            column![
+                text("Modes").style(theme::text::secondary).font_maybe(
+                    theme::font_style::secondary(theme).map(font::get)
+                ),
+                modes,
+            ]
+            .spacing(2)
+            .width(Length::Fill),
+            button(
+                container(text("Close"))
+                    .align_x(alignment::Horizontal::Center)
+                    .width(Length::Fill),
+            )
+            .padding(5)
+            .width(Length::Fixed(250.0))
+            .style(|theme, status| theme::button::secondary(
+                theme, status, false
+            ))
+            .on_press(Message::Cancel),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(500)
+    .width(Length::Shrink)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
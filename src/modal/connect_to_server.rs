@@ -8,8 +8,9 @@ use crate::{Theme, font, theme};
 
 pub fn view<'a>(
     raw: &'a str,
-    config: &config::Server,
-    theme: &Theme,
+    config: &'a config::Server,
+    ctcp: &config::Ctcp,
+    theme: &'a Theme,
 ) -> Element<'a, Message> {
     container(
         column![
@@ -18,6 +19,8 @@ pub fn view<'a>(
                 .style(theme::text::tertiary)
                 .font_maybe(theme::font_style::tertiary(theme).map(font::get)),
         ]
+        // What the server and its users will learn about us
+        .push(super::identity::fields(config, ctcp, None, theme))
         .push(
             checkbox(
                 "Accept invalid certificates",
@@ -0,0 +1,127 @@
+use data::environment::VERSION;
+use data::{Server, config};
+use iced::widget::{Column, button, column, container, row, text};
+use iced::{Length, alignment};
+
+use super::Message;
+use crate::widget::Element;
+use crate::{Theme, font, theme};
+
+pub fn view<'a>(
+    server: &'a Server,
+    server_config: &'a config::Server,
+    clients: &'a data::client::Map,
+    config: &'a data::Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    container(
+        column![
+            text(format!("Identity on {server}")),
+            fields(
+                server_config,
+                &config.ctcp,
+                clients.visible_host(server),
+                theme
+            ),
+            button(
+                container(text("Close"))
+                    .align_x(alignment::Horizontal::Center)
+                    .width(Length::Fill),
+            )
+            .padding(5)
+            .width(Length::Fixed(250.0))
+            .style(|theme, status| theme::button::secondary(
+                theme, status, false
+            ))
+            .on_press(Message::Cancel),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(500)
+    .width(Length::Shrink)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
+
+/// What other users and the server learn about us on connecting, given
+/// `host` once connected and whether it's cloaked.
+pub fn fields<'a>(
+    server_config: &'a config::Server,
+    ctcp: &config::Ctcp,
+    host: Option<(&'a str, bool)>,
+    theme: &'a Theme,
+) -> Column<'a, Message> {
+    let field = |label: &'a str, value: String| -> Element<'a, Message> {
+        row![
+            text(label)
+                .style(theme::text::secondary)
+                .font_maybe(theme::font_style::secondary(theme).map(font::get))
+                .width(Length::Fixed(110.0)),
+            text(value)
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get)),
+        ]
+        .spacing(8)
+        .into()
+    };
+
+    let nickname = &server_config.nickname;
+
+    let hostname = match host {
+        Some((host, true)) => format!("{host} (cloaked)"),
+        Some((host, false)) => format!("{host} (not cloaked)"),
+        None => "Your IP address or its hostname, unless the network cloaks it"
+            .to_string(),
+    };
+
+    let authentication =
+        match (&server_config.sasl, &server_config.nick_password) {
+            (Some(config::server::Sasl::Plain { username, .. }), _) => {
+                format!("SASL as {username}")
+            }
+            (Some(config::server::Sasl::External { .. }), _) => {
+                "SASL with a client certificate".to_string()
+            }
+            (None, Some(_)) => "NickServ, after connecting".to_string(),
+            (None, None) => "None".to_string(),
+        };
+
+    let not_answered = || "Not answered".to_string();
+
+    column![
+        field("Nickname", nickname.clone()),
+        field(
+            "Username",
+            server_config.username.as_ref().unwrap_or(nickname).clone(),
+        ),
+        field(
+            "Real name",
+            server_config.realname.as_ref().unwrap_or(nickname).clone(),
+        ),
+        field("Hostname", hostname),
+        field("Authentication", authentication),
+        field(
+            "CTCP VERSION",
+            if ctcp.version {
+                ctcp.version_reply(VERSION)
+            } else {
+                not_answered()
+            },
+        ),
+        field(
+            "CTCP TIME",
+            if ctcp.time {
+                "Your local time and time zone".to_string()
+            } else {
+                not_answered()
+            },
+        ),
+        field(
+            "CTCP USERINFO",
+            ctcp.userinfo.clone().unwrap_or_else(not_answered),
+        ),
+    ]
+    .spacing(2)
+}
@@ -0,0 +1,89 @@
+use chrono::SecondsFormat;
+use iced::widget::{button, column, container, row, scrollable, text};
+use iced::{Length, alignment};
+
+use super::Message;
+use crate::widget::Element;
+use crate::{Theme, font, theme};
+
+pub fn view<'a>(
+    message: &'a data::Message,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let field = |label: &'a str, value: String| -> Element<'a, Message> {
+        row![
+            text(label)
+                .style(theme::text::secondary)
+                .font_maybe(theme::font_style::secondary(theme).map(font::get))
+                .width(Length::Fixed(60.0)),
+            text(value)
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get)),
+        ]
+        .spacing(8)
+        .into()
+    };
+
+    let time = field(
+        "Time",
+        message
+            .server_time
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    );
+    let id = message.id.clone().map(|id| field("ID", id));
+
+    let tags: Element<'a, Message> = if message.tags.is_empty() {
+        text("No other tags")
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+            .into()
+    } else {
+        scrollable(column(message.tags.iter().map(|(key, value)| {
+            let tag = if value.is_empty() {
+                key.clone()
+            } else {
+                format!("{key}={value}")
+            };
+
+            text(tag)
+                .style(theme::text::primary)
+                .font_maybe(theme::font_style::primary(theme).map(font::get))
+                .into()
+        })))
+        .height(Length::Shrink)
+        .into()
+    };
+
+    container(
+        column![
+            text("Message details"),
+            column![time, id].spacing(2),
+            column![
+                text("Tags").style(theme::text::secondary).font_maybe(
+                    theme::font_style::secondary(theme).map(font::get)
+                ),
+                tags,
+            ]
+            .spacing(2)
+            .width(Length::Fill),
+            button(
+                container(text("Close"))
+                    .align_x(alignment::Horizontal::Center)
+                    .width(Length::Fill),
+            )
+            .padding(5)
+            .width(Length::Fixed(250.0))
+            .style(|theme, status| theme::button::secondary(
+                theme, status, false
+            ))
+            .on_press(Message::Cancel),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(500)
+    .width(Length::Shrink)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
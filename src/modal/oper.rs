@@ -0,0 +1,176 @@
+use data::buffer::Upstream;
+use iced::widget::{
+    self, button, column, container, operation, row, text, text_input,
+};
+use iced::{Length, Task, alignment};
+
+use super::{Event, Message, Oper};
+use crate::widget::Element;
+use crate::{Theme, font, theme, window};
+
+/// Prompt for operator credentials, which are sent from `buffer` and kept
+/// out of the input and its history.
+#[derive(Debug)]
+pub struct State {
+    buffer: Upstream,
+    name: String,
+    password: String,
+    /// Whether a `challenge_key` is configured, answering a CHALLENGE in
+    /// place of the password when it's left empty
+    has_challenge_key: bool,
+    name_input: widget::Id,
+    password_input: widget::Id,
+    window: window::Id,
+}
+
+impl State {
+    pub fn new(
+        buffer: Upstream,
+        name: Option<String>,
+        has_challenge_key: bool,
+        window: window::Id,
+    ) -> (Self, Task<Message>) {
+        let state = Self {
+            buffer,
+            name: name.unwrap_or_default(),
+            password: String::new(),
+            has_challenge_key,
+            name_input: widget::Id::unique(),
+            password_input: widget::Id::unique(),
+            window,
+        };
+
+        // Starts with the password if the name is known already
+        let focus = if state.name.is_empty() {
+            operation::focus(state.name_input.clone())
+        } else {
+            operation::focus(state.password_input.clone())
+        };
+
+        (state, focus)
+    }
+
+    pub fn window(&self) -> window::Id {
+        self.window
+    }
+
+    fn can_submit(&self) -> bool {
+        !self.name.trim().is_empty()
+            && (!self.password.is_empty() || self.has_challenge_key)
+    }
+
+    pub fn update(&mut self, message: Oper) -> (Task<Message>, Option<Event>) {
+        match message {
+            Oper::Name(name) => {
+                self.name = name;
+
+                (Task::none(), None)
+            }
+            Oper::Password(password) => {
+                self.password = password;
+
+                (Task::none(), None)
+            }
+            Oper::Submit => {
+                if self.name.trim().is_empty() {
+                    return (operation::focus(self.name_input.clone()), None);
+                }
+
+                if !self.can_submit() {
+                    return (
+                        operation::focus(self.password_input.clone()),
+                        None,
+                    );
+                }
+
+                let password = Some(std::mem::take(&mut self.password))
+                    .filter(|password| !password.is_empty());
+
+                (
+                    Task::none(),
+                    Some(Event::Oper {
+                        buffer: self.buffer.clone(),
+                        name: self.name.trim().to_string(),
+                        password,
+                    }),
+                )
+            }
+        }
+    }
+}
+
+pub fn view<'a>(state: &'a State, theme: &'a Theme) -> Element<'a, Message> {
+    let label = |value: &'a str| {
+        text(value)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+            .width(Length::Fixed(80.0))
+    };
+
+    let can_submit = state.can_submit();
+
+    let password_placeholder = if state.has_challenge_key {
+        "Empty to answer a challenge"
+    } else {
+        "Operator password"
+    };
+
+    let action = |value: &'a str, message: Option<Message>| {
+        button(
+            container(text(value))
+                .align_x(alignment::Horizontal::Center)
+                .width(Length::Fill),
+        )
+        .padding(5)
+        .width(Length::Fixed(120.0))
+        .style(|theme, status| theme::button::secondary(theme, status, false))
+        .on_press_maybe(message)
+    };
+
+    container(
+        column![
+            text(format!("Operator login on {}", state.buffer.server())),
+            column![
+                row![
+                    label("Name"),
+                    text_input("Operator name", &state.name)
+                        .id(state.name_input.clone())
+                        .on_input(|value| Message::Oper(Oper::Name(value)))
+                        .on_submit(Message::Oper(Oper::Submit))
+                        .padding(6),
+                ]
+                .spacing(8)
+                .align_y(alignment::Vertical::Center),
+                row![
+                    label("Password"),
+                    text_input(password_placeholder, &state.password)
+                        .id(state.password_input.clone())
+                        .secure(true)
+                        .on_input(|value| {
+                            Message::Oper(Oper::Password(value))
+                        })
+                        .on_submit(Message::Oper(Oper::Submit))
+                        .padding(6),
+                ]
+                .spacing(8)
+                .align_y(alignment::Vertical::Center),
+            ]
+            .spacing(8),
+            row![
+                action(
+                    "Log in",
+                    can_submit.then_some(Message::Oper(Oper::Submit))
+                ),
+                action("Cancel", Some(Message::Cancel)),
+            ]
+            .spacing(8),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(400)
+    .width(Length::Fill)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
@@ -0,0 +1,204 @@
+use chrono::{Local, NaiveDate};
+use data::buffer::Upstream;
+use data::{Config, history, print};
+use iced::widget::{button, column, container, row, text, text_input};
+use iced::{Length, Task, alignment};
+
+use super::{Event, Message, Print};
+use crate::widget::Element;
+use crate::{Theme, font, theme, window};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Date range of a buffer to print, which is opened in the browser to be
+/// printed or saved as a PDF.
+#[derive(Debug)]
+pub struct State {
+    kind: history::Kind,
+    from: String,
+    to: String,
+    window: window::Id,
+    is_exporting: bool,
+    error: Option<String>,
+}
+
+impl State {
+    /// Starts out with today, in local time.
+    pub fn new(buffer: Upstream, window: window::Id) -> Self {
+        let today = Local::now().date_naive().format(DATE_FORMAT).to_string();
+
+        Self {
+            kind: history::Kind::from_input_buffer(buffer),
+            from: today.clone(),
+            to: today,
+            window,
+            is_exporting: false,
+            error: None,
+        }
+    }
+
+    pub fn window(&self) -> window::Id {
+        self.window
+    }
+
+    fn range(&self) -> Result<print::Range, String> {
+        let parse = |date: &str| {
+            NaiveDate::parse_from_str(date.trim(), DATE_FORMAT).map_err(|_| {
+                format!("{:?} isn't a YYYY-MM-DD date", date.trim())
+            })
+        };
+
+        let range = print::Range {
+            from: parse(&self.from)?,
+            to: parse(&self.to)?,
+        };
+
+        if range.from > range.to {
+            return Err("The range ends before it starts".to_string());
+        }
+
+        Ok(range)
+    }
+
+    pub fn update(
+        &mut self,
+        message: Print,
+        config: &Config,
+    ) -> (Task<Message>, Option<Event>) {
+        match message {
+            Print::From(from) => {
+                self.from = from;
+                self.error = None;
+            }
+            Print::To(to) => {
+                self.to = to;
+                self.error = None;
+            }
+            Print::Export => {
+                let range = match self.range() {
+                    Ok(range) => range,
+                    Err(error) => {
+                        self.error = Some(error);
+
+                        return (Task::none(), None);
+                    }
+                };
+
+                self.is_exporting = true;
+                self.error = None;
+
+                return (
+                    Task::perform(
+                        print::export(
+                            self.kind.clone(),
+                            range,
+                            config.buffer.clone(),
+                        ),
+                        |result| {
+                            Message::Print(Print::Exported(
+                                result.map_err(|error| error.to_string()),
+                            ))
+                        },
+                    ),
+                    None,
+                );
+            }
+            Print::Exported(result) => {
+                self.is_exporting = false;
+
+                match result {
+                    // The browser shows a print dialog once it's loaded
+                    Ok(path) => {
+                        if let Err(error) = open::that_detached(&path) {
+                            log::warn!(
+                                "failed to open {}: {error}",
+                                path.display()
+                            );
+                        }
+
+                        return (Task::none(), Some(Event::CloseModal));
+                    }
+                    Err(error) => {
+                        log::warn!("failed to print {}: {error}", self.kind);
+
+                        self.error = Some(error);
+                    }
+                }
+            }
+        }
+
+        (Task::none(), None)
+    }
+}
+
+pub fn view<'a>(state: &'a State, theme: &'a Theme) -> Element<'a, Message> {
+    let label = |value: &'a str| {
+        text(value)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+            .width(Length::Fixed(50.0))
+    };
+
+    let date = |value: &'a str, on_input: fn(String) -> Print| {
+        text_input("YYYY-MM-DD", value)
+            .on_input(move |value| Message::Print(on_input(value)))
+            .on_submit(Message::Print(Print::Export))
+            .padding(6)
+    };
+
+    let action = |value: &'a str, message: Option<Message>| {
+        button(
+            container(text(value))
+                .align_x(alignment::Horizontal::Center)
+                .width(Length::Fill),
+        )
+        .padding(5)
+        .width(Length::Fixed(120.0))
+        .style(|theme, status| theme::button::secondary(theme, status, false))
+        .on_press_maybe(message)
+    };
+
+    let error = state
+        .error
+        .as_ref()
+        .map(|error| text(error).style(theme::text::error));
+
+    container(
+        column![
+            text(format!("Print {}", state.kind)),
+            text("Opens in your browser, to print or save as a PDF")
+                .style(theme::text::secondary)
+                .font_maybe(theme::font_style::secondary(theme).map(font::get)),
+            column![
+                row![label("From"), date(&state.from, Print::From)]
+                    .spacing(8)
+                    .align_y(alignment::Vertical::Center),
+                row![label("To"), date(&state.to, Print::To)]
+                    .spacing(8)
+                    .align_y(alignment::Vertical::Center),
+            ]
+            .spacing(8),
+            error,
+            row![
+                action(
+                    if state.is_exporting {
+                        "Preparing…"
+                    } else {
+                        "Print…"
+                    },
+                    (!state.is_exporting)
+                        .then_some(Message::Print(Print::Export)),
+                ),
+                action("Cancel", Some(Message::Cancel)),
+            ]
+            .spacing(8),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(400)
+    .width(Length::Fill)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
@@ -0,0 +1,415 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use data::share::{self, Kind, Line};
+use data::{Config, isupport};
+use iced::widget::{
+    self, Column, button, checkbox, column, container, row, scrollable, text,
+};
+use iced::{Length, Rectangle, Task, alignment, clipboard, task};
+
+use super::{Event, Message, Share};
+use crate::widget::Element;
+use crate::{Theme, dialog, font, theme, window};
+
+#[derive(Debug)]
+pub struct State {
+    messages: Vec<data::Message>,
+    /// Casemapping of the server `messages` are from, to match nicknames
+    /// when anonymizing
+    casemapping: isupport::CaseMap,
+    /// Selected range of `messages`, inclusive
+    start: usize,
+    end: usize,
+    options: share::Options,
+    preview: widget::Id,
+    window: window::Id,
+    save_dialog: Option<task::Handle>,
+    status: Option<Status>,
+}
+
+#[derive(Debug)]
+enum Status {
+    Copied,
+    Saved(PathBuf),
+    Failed(String),
+}
+
+impl State {
+    /// Shares the message at `index` of `messages`, with the range
+    /// extendable to the others.
+    pub fn new(
+        messages: Vec<data::Message>,
+        index: usize,
+        casemapping: isupport::CaseMap,
+        window: window::Id,
+    ) -> Self {
+        Self {
+            messages,
+            casemapping,
+            start: index,
+            end: index,
+            options: share::Options::default(),
+            preview: widget::Id::unique(),
+            window,
+            save_dialog: None,
+            status: None,
+        }
+    }
+
+    pub fn window(&self) -> window::Id {
+        self.window
+    }
+
+    fn lines(&self, config: &Config) -> Vec<Line> {
+        let selected = self.messages[self.start..=self.end]
+            .iter()
+            .collect::<Vec<_>>();
+
+        share::lines(&selected, self.options, self.casemapping, &config.buffer)
+    }
+
+    pub fn update(
+        &mut self,
+        message: Share,
+        config: &Config,
+        theme: &Theme,
+    ) -> (Task<Message>, Option<Event>) {
+        match message {
+            Share::Select(index) => {
+                // Clicking outside the range extends it, and clicking
+                // inside starts over from there
+                if index < self.start {
+                    self.start = index;
+                } else if index > self.end {
+                    self.end = index;
+                } else {
+                    self.start = index;
+                    self.end = index;
+                }
+
+                self.status = None;
+            }
+            Share::Anonymize(anonymize) => {
+                self.options.anonymize = anonymize;
+            }
+            Share::Timestamps(timestamps) => {
+                self.options.timestamps = timestamps;
+            }
+            Share::CopyText => {
+                self.status = Some(Status::Copied);
+
+                return (
+                    clipboard::write(share::text(&self.lines(config))),
+                    None,
+                );
+            }
+            Share::CopyHtml => {
+                let lines = self.lines(config);
+                let html = share::html(&lines, &colors(&lines, config, theme));
+
+                self.status = Some(Status::Copied);
+
+                return (clipboard::write(html), None);
+            }
+            Share::SaveImage => {
+                let (task, handle) =
+                    dialog::save_file("messages.png".to_string(), || None);
+
+                self.save_dialog = Some(handle);
+
+                return (
+                    task.map(|path| Message::Share(Share::SaveImageTo(path))),
+                    None,
+                );
+            }
+            Share::SaveImageTo(path) => {
+                self.save_dialog = None;
+
+                let Some(path) = path else {
+                    return (Task::none(), None);
+                };
+
+                let window = self.window;
+
+                // The dialog is closed by now, so the preview can be
+                // captured as it's shown
+                return (
+                    widget::container::visible_bounds(self.preview.clone())
+                        .then(move |bounds| {
+                            let path = path.clone();
+
+                            iced::window::screenshot(window).map(
+                                move |screenshot| {
+                                    Message::Share(Share::Captured(
+                                        path.clone(),
+                                        bounds,
+                                        screenshot,
+                                    ))
+                                },
+                            )
+                        }),
+                    None,
+                );
+            }
+            Share::Captured(path, bounds, screenshot) => {
+                return (
+                    Task::perform(
+                        save_image(path, bounds, screenshot),
+                        |result| Message::Share(Share::SavedImage(result)),
+                    ),
+                    None,
+                );
+            }
+            Share::SavedImage(result) => {
+                self.status = Some(match result {
+                    Ok(path) => Status::Saved(path),
+                    Err(error) => {
+                        log::warn!("failed to save shared messages: {error}");
+
+                        Status::Failed(error)
+                    }
+                });
+            }
+        }
+
+        (Task::none(), None)
+    }
+}
+
+/// Crops `screenshot` to `bounds` and writes it to `path` as a PNG.
+async fn save_image(
+    path: PathBuf,
+    bounds: Option<Rectangle>,
+    screenshot: iced::window::Screenshot,
+) -> Result<PathBuf, String> {
+    let bounds = bounds.ok_or_else(|| "preview isn't shown".to_string())?;
+
+    let scale = screenshot.scale_factor;
+    let region = Rectangle {
+        x: (bounds.x * scale).round() as u32,
+        y: (bounds.y * scale).round() as u32,
+        width: (bounds.width * scale).round() as u32,
+        height: (bounds.height * scale).round() as u32,
+    };
+
+    let cropped = screenshot.crop(region).map_err(|error| error.to_string())?;
+
+    let image = image::RgbaImage::from_raw(
+        cropped.size.width,
+        cropped.size.height,
+        cropped.rgba.to_vec(),
+    )
+    .ok_or_else(|| "screenshot has an unexpected size".to_string())?;
+
+    tokio::task::spawn_blocking(move || {
+        image
+            .save_with_format(&path, image::ImageFormat::Png)
+            .map(|()| path)
+            .map_err(|error| error.to_string())
+    })
+    .await
+    .map_err(|error| error.to_string())?
+}
+
+/// Colors of the theme used in an HTML quote of `lines`.
+fn colors(lines: &[Line], config: &Config, theme: &Theme) -> share::Colors {
+    let styles = theme.styles();
+    let nickname_color = |nick: &str| {
+        theme::text::nickname(
+            theme,
+            &config.buffer.nickname.color,
+            Some(nick),
+            None,
+            false,
+            false,
+        )
+        .color
+        .unwrap_or(styles.text.primary.color)
+    };
+
+    share::Colors {
+        background: styles.buffer.background,
+        text: styles.text.primary.color,
+        timestamp: styles.buffer.timestamp.color,
+        action: styles.buffer.action.color,
+        server: styles.text.secondary.color,
+        nicknames: lines
+            .iter()
+            .filter_map(|line| line.nick.as_ref())
+            .map(|nick| (nick.clone(), nickname_color(nick)))
+            .collect::<HashMap<_, _>>(),
+    }
+}
+
+pub fn view<'a>(
+    state: &'a State,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let messages = scrollable(Column::with_children(
+        state.messages.iter().enumerate().map(|(index, message)| {
+            let is_selected = (state.start..=state.end).contains(&index);
+
+            let sender = data::digest::sender(message)
+                .map(|nick| format!("<{nick}> "))
+                .unwrap_or_default();
+
+            button(
+                text(format!("{sender}{}", message.text()))
+                    .style(theme::text::primary)
+                    .font_maybe(
+                        theme::font_style::primary(theme).map(font::get),
+                    )
+                    .wrapping(text::Wrapping::None),
+            )
+            .padding([2, 6])
+            .width(Length::Fill)
+            .style(move |theme, status| {
+                theme::button::primary(theme, status, is_selected)
+            })
+            .on_press(Message::Share(Share::Select(index)))
+            .into()
+        }),
+    ))
+    .height(Length::Fixed(160.0));
+
+    let lines = state.lines(config);
+
+    let preview = container(
+        Column::with_children(
+            lines.iter().map(|line| preview_line(line, config, theme)),
+        )
+        .spacing(config.buffer.line_spacing),
+    )
+    .id(state.preview.clone())
+    .padding(12)
+    .width(Length::Fill)
+    .style(|theme| theme::container::buffer(theme, false));
+
+    let options = row![
+        checkbox("Anonymize nicknames", state.options.anonymize)
+            .on_toggle(|toggle| Message::Share(Share::Anonymize(toggle))),
+        checkbox("Timestamps", state.options.timestamps)
+            .on_toggle(|toggle| Message::Share(Share::Timestamps(toggle))),
+    ]
+    .spacing(16);
+
+    let action = |label: &'a str, message: Option<Share>| {
+        button(
+            container(text(label))
+                .align_x(alignment::Horizontal::Center)
+                .width(Length::Fill),
+        )
+        .padding(5)
+        .width(Length::Fixed(150.0))
+        .style(|theme, status| theme::button::secondary(theme, status, false))
+        .on_press_maybe(message.map(Message::Share))
+    };
+
+    let status = state.status.as_ref().map(|status| match status {
+        Status::Copied => {
+            text("Copied to clipboard").style(theme::text::success)
+        }
+        Status::Saved(path) => text(format!("Saved to {}", path.display()))
+            .style(theme::text::success),
+        Status::Failed(error) => text(format!("Failed to save image: {error}"))
+            .style(theme::text::error),
+    });
+
+    container(
+        column![
+            text("Share messages"),
+            text(
+                "Click messages to extend the range, or inside it to start over"
+            )
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get)),
+            messages,
+            options,
+            preview,
+            status,
+            row![
+                action("Copy as Text", Some(Share::CopyText)),
+                action("Copy as HTML", Some(Share::CopyHtml)),
+                action(
+                    "Save as Image…",
+                    state.save_dialog.is_none().then_some(Share::SaveImage),
+                ),
+                button(
+                    container(text("Close"))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(150.0))
+                .style(|theme, status| theme::button::secondary(
+                    theme, status, false
+                ))
+                .on_press(Message::Cancel),
+            ]
+            .spacing(8),
+        ]
+        .spacing(12)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(800)
+    .width(Length::Fill)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
+
+fn preview_line<'a>(
+    line: &Line,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let timestamp = line.timestamp.clone().map(|timestamp| {
+        text(timestamp)
+            .style(theme::text::timestamp)
+            .font_maybe(theme::font_style::timestamp(theme).map(font::get))
+    });
+
+    let content: Element<'a, Message> = match (line.kind, &line.nick) {
+        (Kind::Message, Some(nick)) => {
+            let nick = nick.clone();
+            let color = config.buffer.nickname.color.clone();
+
+            row![
+                text(format!("<{nick}>"))
+                    .style(move |theme| {
+                        theme::text::nickname(
+                            theme,
+                            &color,
+                            Some(&nick),
+                            None,
+                            false,
+                            false,
+                        )
+                    })
+                    .font_maybe(
+                        theme::font_style::nickname(theme, false)
+                            .map(font::get)
+                    ),
+                text(line.text.clone())
+                    .style(theme::text::primary)
+                    .font_maybe(
+                        theme::font_style::primary(theme).map(font::get)
+                    ),
+            ]
+            .spacing(6)
+            .into()
+        }
+        (Kind::Action, _) => text(format!("* {}", line.text))
+            .style(theme::text::action)
+            .font_maybe(theme::font_style::action(theme).map(font::get))
+            .into(),
+        _ => text(line.text.clone())
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+            .into(),
+    };
+
+    row![timestamp, content].spacing(6).into()
+}
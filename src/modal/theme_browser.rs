@@ -0,0 +1,445 @@
+use data::appearance::theme::community::{self, Candidate, Entry};
+use iced::widget::{
+    Column, button, column, container, row, scrollable, text, themer,
+};
+use iced::{Color, Length, Task, alignment};
+
+use super::{Event, Message, ThemeBrowser};
+use crate::widget::Element;
+use crate::{Theme, font, theme};
+
+#[derive(Debug, Default)]
+pub struct State {
+    index: Index,
+    selected: Option<Selected>,
+}
+
+#[derive(Debug, Default)]
+enum Index {
+    #[default]
+    Loading,
+    Loaded(Vec<Entry>),
+    Failed(String),
+}
+
+#[derive(Debug)]
+enum Selected {
+    Fetching(Entry),
+    Fetched {
+        candidate: Candidate,
+        theme: Theme,
+        install: Install,
+    },
+    Failed(Entry, String),
+}
+
+impl Selected {
+    fn entry(&self) -> &Entry {
+        match self {
+            Selected::Fetching(entry) | Selected::Failed(entry, _) => entry,
+            Selected::Fetched { candidate, .. } => &candidate.entry,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Install {
+    Ready,
+    Installing,
+    Installed,
+    /// A theme with the same name is installed, which isn't replaced
+    Exists,
+    Failed(String),
+}
+
+impl State {
+    /// An empty browser along with the task fetching the community index.
+    pub fn load() -> (Self, Task<Message>) {
+        (
+            Self::default(),
+            Task::perform(community::index(), |result| {
+                Message::ThemeBrowser(ThemeBrowser::IndexLoaded(result))
+            }),
+        )
+    }
+
+    pub fn update(
+        &mut self,
+        message: ThemeBrowser,
+    ) -> (Task<Message>, Option<Event>) {
+        match message {
+            ThemeBrowser::IndexLoaded(Ok(entries)) => {
+                self.index = Index::Loaded(entries);
+            }
+            ThemeBrowser::IndexLoaded(Err(error)) => {
+                log::warn!("failed to load community themes: {error}");
+
+                self.index = Index::Failed(error.to_string());
+            }
+            ThemeBrowser::Select(entry) => {
+                self.selected = Some(Selected::Fetching(entry.clone()));
+
+                return (
+                    Task::perform(community::fetch(entry), |result| {
+                        Message::ThemeBrowser(ThemeBrowser::Fetched(result))
+                    }),
+                    None,
+                );
+            }
+            ThemeBrowser::Fetched(result) => {
+                // Ignore themes which were selected before the current one
+                let Some(Selected::Fetching(entry)) = &self.selected else {
+                    return (Task::none(), None);
+                };
+                let entry = entry.clone();
+
+                self.selected = match result {
+                    Ok(candidate) if candidate.entry == entry => {
+                        let theme = Theme::from(data::Theme::new(
+                            candidate.entry.name.clone(),
+                            candidate.styles,
+                        ));
+
+                        Some(Selected::Fetched {
+                            candidate,
+                            theme,
+                            install: Install::Ready,
+                        })
+                    }
+                    Ok(_) => return (Task::none(), None),
+                    Err(error) => {
+                        Some(Selected::Failed(entry, error.to_string()))
+                    }
+                };
+            }
+            ThemeBrowser::Install => {
+                if let Some(Selected::Fetched {
+                    candidate, install, ..
+                }) = &mut self.selected
+                {
+                    *install = Install::Installing;
+
+                    return (
+                        Task::perform(
+                            community::install(candidate.clone()),
+                            |result| {
+                                Message::ThemeBrowser(ThemeBrowser::Installed(
+                                    result,
+                                ))
+                            },
+                        ),
+                        None,
+                    );
+                }
+            }
+            ThemeBrowser::Installed(result) => {
+                let Some(Selected::Fetched { install, .. }) =
+                    &mut self.selected
+                else {
+                    return (Task::none(), None);
+                };
+
+                match result {
+                    Ok(name) => {
+                        log::info!("Installed theme: {name}");
+
+                        *install = Install::Installed;
+
+                        return (Task::none(), Some(Event::ReloadThemes));
+                    }
+                    Err(community::Error::Exists(name)) => {
+                        log::warn!("Theme is already installed: {name}");
+
+                        *install = Install::Exists;
+                    }
+                    Err(error) => {
+                        log::error!("Failed to install theme: {error}");
+
+                        *install = Install::Failed(error.to_string());
+                    }
+                }
+            }
+        }
+
+        (Task::none(), None)
+    }
+}
+
+pub fn view<'a>(state: &'a State, theme: &'a Theme) -> Element<'a, Message> {
+    let secondary = |value: String| {
+        text(value)
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+    };
+
+    let list: Element<'a, Message> = match &state.index {
+        Index::Loading => secondary("Loading themes…".to_string()).into(),
+        Index::Failed(error) => text(format!("Failed to load themes: {error}"))
+            .style(theme::text::error)
+            .into(),
+        Index::Loaded(entries) if entries.is_empty() => {
+            secondary("No themes available".to_string()).into()
+        }
+        Index::Loaded(entries) => {
+            scrollable(Column::with_children(entries.iter().map(|entry| {
+                let is_selected = state
+                    .selected
+                    .as_ref()
+                    .is_some_and(|selected| selected.entry() == entry);
+
+                button(column![
+                    text(&entry.name).style(theme::text::primary).font_maybe(
+                        theme::font_style::primary(theme).map(font::get)
+                    ),
+                    entry.author.as_ref().map(|author| {
+                        secondary(format!("by {author}"))
+                            .size(theme::TEXT_SIZE - 1.0)
+                    }),
+                ])
+                .padding([4, 6])
+                .width(Length::Fill)
+                .style(move |theme, status| {
+                    theme::button::primary(theme, status, is_selected)
+                })
+                .on_press(Message::ThemeBrowser(ThemeBrowser::Select(
+                    entry.clone(),
+                )))
+                .into()
+            })))
+            .height(Length::Fill)
+            .into()
+        }
+    };
+
+    let details: Element<'a, Message> = match &state.selected {
+        None => {
+            center_text(secondary("Select a theme to preview it".to_string()))
+        }
+        Some(Selected::Fetching(_)) => {
+            center_text(secondary("Downloading theme…".to_string()))
+        }
+        Some(Selected::Failed(_, error)) => center_text(
+            text(format!("Failed to download theme: {error}"))
+                .style(theme::text::error),
+        ),
+        Some(Selected::Fetched {
+            candidate,
+            theme: preview_theme,
+            install,
+        }) => {
+            let (label, message) = match install {
+                Install::Ready => ("Install", Some(ThemeBrowser::Install)),
+                Install::Installing => ("Installing…", None),
+                Install::Installed => ("Installed", None),
+                Install::Exists => ("Already installed", None),
+                Install::Failed(_) => ("Retry", Some(ThemeBrowser::Install)),
+            };
+
+            let install_error = match install {
+                Install::Failed(error) => Some(
+                    text(format!("Failed to install theme: {error}"))
+                        .style(theme::text::error),
+                ),
+                _ => None,
+            };
+
+            column![
+                preview(candidate, preview_theme),
+                install_error,
+                button(
+                    container(text(label))
+                        .align_x(alignment::Horizontal::Center)
+                        .width(Length::Fill),
+                )
+                .padding(5)
+                .width(Length::Fixed(120.0))
+                .style(|theme, status| {
+                    theme::button::secondary(theme, status, false)
+                })
+                .on_press_maybe(message.map(Message::ThemeBrowser)),
+            ]
+            .spacing(10)
+            .align_x(iced::Alignment::End)
+            .into()
+        }
+    };
+
+    container(
+        column![
+            text("Get more themes"),
+            row![
+                container(list).width(Length::Fixed(200.0)),
+                container(details).width(Length::Fill),
+            ]
+            .spacing(16)
+            .height(Length::Fixed(360.0)),
+            button(
+                container(text("Close"))
+                    .align_x(alignment::Horizontal::Center)
+                    .width(Length::Fill),
+            )
+            .padding(5)
+            .width(Length::Fixed(250.0))
+            .style(|theme, status| theme::button::secondary(
+                theme, status, false
+            ))
+            .on_press(Message::Cancel),
+        ]
+        .spacing(20)
+        .align_x(iced::Alignment::Center),
+    )
+    .max_width(800)
+    .width(Length::Fill)
+    .style(theme::container::tooltip)
+    .padding(25)
+    .into()
+}
+
+fn center_text<'a>(
+    content: impl Into<Element<'a, Message>>,
+) -> Element<'a, Message> {
+    container(content)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .into()
+}
+
+/// Sample messages and the main colors of a theme, drawn with the styles
+/// of `preview_theme` rather than the selected theme.
+fn preview<'a>(
+    candidate: &'a Candidate,
+    preview_theme: &'a Theme,
+) -> Element<'a, Message> {
+    let styles = preview_theme.styles();
+
+    let timestamp = || {
+        text("12:00").style(theme::text::timestamp).font_maybe(
+            theme::font_style::timestamp(preview_theme).map(font::get),
+        )
+    };
+    let nickname = |nick: &'static str, is_highlight: bool| {
+        text(format!("<{nick}>"))
+            .style(move |theme| {
+                theme::text::nickname(
+                    theme,
+                    &data::buffer::Color::Unique,
+                    Some(nick),
+                    None,
+                    false,
+                    is_highlight,
+                )
+            })
+            .font_maybe(
+                theme::font_style::message_nickname(
+                    preview_theme,
+                    false,
+                    is_highlight,
+                )
+                .map(font::get),
+            )
+    };
+    let primary = |content: &'static str| {
+        text(content).style(theme::text::primary).font_maybe(
+            theme::font_style::primary(preview_theme).map(font::get),
+        )
+    };
+
+    let messages = column![
+        row![
+            timestamp(),
+            nickname("alice", false),
+            primary("has anyone tried the new theme?"),
+        ]
+        .spacing(6),
+        row![
+            timestamp(),
+            nickname("bob", false),
+            primary("it's on"),
+            text("https://themes.halloy.chat")
+                .style(theme::text::url)
+                .font_maybe(
+                    theme::font_style::url(preview_theme).map(font::get)
+                ),
+        ]
+        .spacing(6),
+        row![
+            timestamp(),
+            text("* carol likes the colors")
+                .style(theme::text::action)
+                .font_maybe(
+                    theme::font_style::action(preview_theme).map(font::get)
+                ),
+        ]
+        .spacing(6),
+        row![
+            timestamp(),
+            text("--> dave has joined")
+                .style(theme::text::secondary)
+                .font_maybe(
+                    theme::font_style::secondary(preview_theme).map(font::get)
+                ),
+        ]
+        .spacing(6),
+        container(
+            row![
+                timestamp(),
+                nickname("alice", true),
+                primary("you should install it"),
+            ]
+            .spacing(6),
+        )
+        .width(Length::Fill)
+        .style(|theme| {
+            theme::container::message_row(theme, false, false, true)
+        }),
+    ]
+    .spacing(4);
+
+    let swatch = |color: Color| {
+        container(text(""))
+            .width(16)
+            .height(16)
+            .style(move |theme| {
+                theme::container::color_swatch(theme, Some(color))
+            })
+    };
+
+    let swatches = row([
+        styles.general.background,
+        styles.buffer.background,
+        styles.text.primary.color,
+        styles.text.secondary.color,
+        styles.buffer.action.color,
+        styles.buffer.url.color,
+        styles.buffer.highlight,
+        styles.general.unread_indicator,
+    ]
+    .into_iter()
+    .map(|color| swatch(color).into()))
+    .spacing(4);
+
+    let author = candidate.entry.author.as_ref().map(|author| {
+        text(format!("by {author}")).style(theme::text::secondary)
+    });
+
+    themer(
+        Some(preview_theme.clone()),
+        container(
+            column![
+                row![
+                    text(&candidate.entry.name).style(theme::text::primary),
+                    author,
+                ]
+                .spacing(6),
+                swatches,
+                messages,
+            ]
+            .spacing(12),
+        )
+        .padding(12)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(|theme| theme::container::buffer(theme, false)),
+    )
+    .into()
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::Instant;
 
 use chrono::{DateTime, TimeDelta, Utc};
 use data::audio::Sound;
@@ -7,11 +8,24 @@ use data::target::join_targets;
 use data::user::Nick;
 use data::{Config, Notification, Server, User};
 
-pub use self::toast::prepare;
+pub use self::toast::{prepare, show as toast};
 use crate::audio;
 
+mod email;
+mod push;
 mod toast;
 
+/// Destination notifications are delivered to (besides their sound)
+trait Backend {
+    /// Whether the notification's config enables this backend
+    fn is_enabled(&self, config: &notification::Notification) -> bool;
+
+    fn send(&mut self, title: &str, body: &str);
+
+    /// Called periodically, e.g. to send out batched notifications
+    fn tick(&mut self, _now: Instant) {}
+}
+
 #[derive(PartialEq, Eq, Hash, Clone)]
 enum NotificationDelayKey {
     Connected,
@@ -20,8 +34,11 @@ enum NotificationDelayKey {
     DirectMessage(Box<str>),
     Highlight,
     FileTransferRequest(Box<str>),
+    DccChatRequest(Box<str>),
     MonitoredOnline,
     MonitoredOffline,
+    Watch(Box<str>),
+    Script(Box<str>),
 }
 
 impl From<&Notification> for NotificationDelayKey {
@@ -41,12 +58,23 @@ impl From<&Notification> for NotificationDelayKey {
                     nick.as_normalized_str().into(),
                 )
             }
+            Notification::DccChatRequest { nick } => {
+                NotificationDelayKey::DccChatRequest(
+                    nick.as_normalized_str().into(),
+                )
+            }
             Notification::MonitoredOnline(..) => {
                 NotificationDelayKey::MonitoredOnline
             }
             Notification::MonitoredOffline(..) => {
                 NotificationDelayKey::MonitoredOffline
             }
+            Notification::Watch { name, .. } => {
+                NotificationDelayKey::Watch(name.as_str().into())
+            }
+            Notification::Script { title, .. } => {
+                NotificationDelayKey::Script(title.as_str().into())
+            }
         }
     }
 }
@@ -54,6 +82,7 @@ impl From<&Notification> for NotificationDelayKey {
 pub struct Notifications {
     recent_notifications: HashMap<NotificationDelayKey, DateTime<Utc>>,
     sounds: HashMap<String, Sound>,
+    backends: Vec<Box<dyn Backend>>,
 }
 
 impl Notifications {
@@ -61,9 +90,26 @@ impl Notifications {
         // Load sounds from different sources.
         let sounds = config.notifications.load_sounds();
 
+        let mut backends: Vec<Box<dyn Backend>> = vec![Box::new(toast::Toast)];
+
+        if let Some(push) = &config.notifications.push {
+            backends.push(Box::new(push::Push::new(push.clone())));
+        }
+
+        if let Some(email) = &config.notifications.email {
+            backends.push(Box::new(email::Digest::new(email.clone())));
+        }
+
         Self {
             recent_notifications: HashMap::new(),
             sounds,
+            backends,
+        }
+    }
+
+    pub fn tick(&mut self, now: Instant) {
+        for backend in &mut self.backends {
+            backend.tick(now);
         }
     }
 
@@ -150,25 +196,42 @@ impl Notifications {
                     );
                 }
             }
-            Notification::DirectMessage { user, message } => {
+            Notification::DccChatRequest { nick } => {
+                if config
+                    .dcc_chat_request
+                    .should_notify(vec![nick.to_string()])
+                {
+                    self.execute(
+                        &config.dcc_chat_request,
+                        notification,
+                        &format!("DCC chat from {nick}"),
+                        &format!("{server}"),
+                    );
+                }
+            }
+            Notification::DirectMessage {
+                user,
+                message,
+                alias,
+            } => {
                 if config
                     .direct_message
                     .should_notify(vec![user.nickname().to_string()])
                 {
+                    let nick = alias
+                        .clone()
+                        .unwrap_or_else(|| user.nickname().to_string());
+
                     let (title, body) = if config.direct_message.show_content {
                         (
                             &format!(
-                                "{} sent you a direct message on {server}",
-                                user.nickname()
+                                "{nick} sent you a direct message on {server}"
                             ),
                             message,
                         )
                     } else {
                         (
-                            &format!(
-                                "{} sent you a direct message",
-                                user.nickname()
-                            ),
+                            &format!("{nick} sent you a direct message"),
                             &format!("{server}"),
                         )
                     };
@@ -186,11 +249,15 @@ impl Notifications {
                 channel,
                 message,
                 description,
+                alias,
             } => {
                 if config.highlight.should_notify(vec![
                     channel.to_string(),
                     user.nickname().to_string(),
                 ]) {
+                    let channel =
+                        alias.clone().unwrap_or_else(|| channel.to_string());
+
                     if config.highlight.show_content {
                         self.execute(
                             &config.highlight,
@@ -214,6 +281,38 @@ impl Notifications {
                     }
                 }
             }
+            Notification::Watch {
+                name,
+                nick,
+                target,
+                message,
+            } => {
+                if config
+                    .watch
+                    .should_notify(vec![target.clone(), nick.to_string()])
+                {
+                    if config.watch.show_content {
+                        self.execute(
+                            &config.watch,
+                            notification,
+                            &format!(
+                                "{nick} matched \"{name}\" in {target} on {server}"
+                            ),
+                            message,
+                        );
+                    } else {
+                        self.execute(
+                            &config.watch,
+                            notification,
+                            &format!("{nick} matched \"{name}\" in {target}"),
+                            &server.name,
+                        );
+                    }
+                }
+            }
+            Notification::Script { title, body } => {
+                self.execute(&config.script, notification, title, body);
+            }
         }
     }
 
@@ -236,8 +335,10 @@ impl Notifications {
 
         self.recent_notifications.insert(delay_key, now);
 
-        if config.show_toast {
-            toast::show(title, body);
+        for backend in &mut self.backends {
+            if backend.is_enabled(config) {
+                backend.send(title, body);
+            }
         }
 
         if let Some(sound_name) = &config.sound
@@ -0,0 +1,100 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use data::config::notification;
+use data::notification::{DigestEntry, email_digest};
+
+use super::Backend;
+
+/// Notifications collected and sent as a single email every
+/// `interval` minutes
+pub struct Digest {
+    config: notification::Email,
+    entries: Vec<DigestEntry>,
+    last_sent: Instant,
+}
+
+impl Digest {
+    pub fn new(config: notification::Email) -> Self {
+        Self {
+            config,
+            entries: vec![],
+            last_sent: Instant::now(),
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.entries.is_empty() {
+            return;
+        }
+
+        let email = email_digest(&self.config, &self.entries);
+        let command = self.config.command.clone();
+
+        self.entries.clear();
+
+        thread::spawn(move || {
+            if let Err(error) = sendmail(&command, &email) {
+                log::error!("Failed to send notification digest: {error}");
+            }
+        });
+    }
+}
+
+impl Backend for Digest {
+    fn is_enabled(&self, config: &notification::Notification) -> bool {
+        config.email
+    }
+
+    fn send(&mut self, title: &str, body: &str) {
+        self.entries.push(DigestEntry {
+            time: Utc::now(),
+            title: title.to_string(),
+            body: body.to_string(),
+        });
+    }
+
+    fn tick(&mut self, now: Instant) {
+        let interval = Duration::from_secs(self.config.interval * 60);
+
+        if now.duration_since(self.last_sent) >= interval {
+            self.last_sent = now;
+            self.flush();
+        }
+    }
+}
+
+// Don't lose collected notifications when the config is reloaded
+impl Drop for Digest {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+fn sendmail(command: &[String], email: &str) -> std::io::Result<()> {
+    let Some((program, args)) = command.split_first() else {
+        return Err(std::io::Error::other("no command configured"));
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(email.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "{program} exited with {status}"
+        )))
+    }
+}
@@ -0,0 +1,49 @@
+use std::thread;
+
+use data::config::notification;
+use tokio::runtime;
+
+use super::Backend;
+
+/// Push notifications through ntfy.sh or Gotify
+pub struct Push {
+    config: notification::Push,
+}
+
+impl Push {
+    pub fn new(config: notification::Push) -> Self {
+        Self { config }
+    }
+}
+
+impl Backend for Push {
+    fn is_enabled(&self, config: &notification::Notification) -> bool {
+        config.push
+    }
+
+    fn send(&mut self, title: &str, body: &str) {
+        let config = self.config.clone();
+        let title = title.to_string();
+        let body = body.to_string();
+
+        // Same as sounds, delivery happens in the background so a slow or
+        // unreachable service can't hold up the UI
+        thread::spawn(move || {
+            let result = runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|error| error.to_string())
+                .and_then(|runtime| {
+                    runtime
+                        .block_on(data::notification::push(
+                            &config, &title, &body,
+                        ))
+                        .map_err(|error| error.to_string())
+                });
+
+            if let Err(error) = result {
+                log::error!("Failed to send push notification: {error}");
+            }
+        });
+    }
+}
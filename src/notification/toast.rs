@@ -1,3 +1,7 @@
+use data::config::notification;
+
+use super::Backend;
+
 #[cfg(target_os = "macos")]
 pub fn prepare() {
     match notify_rust::set_application(data::environment::APPLICATION_ID) {
@@ -11,6 +15,21 @@ pub fn prepare() {
 #[cfg(not(target_os = "macos"))]
 pub fn prepare() {}
 
+/// OS notification, through libnotify on Linux
+pub struct Toast;
+
+impl Backend for Toast {
+    fn is_enabled(&self, config: &notification::Notification) -> bool {
+        config.show_toast
+    }
+
+    fn send(&mut self, title: &str, body: &str) {
+        show(title, body);
+    }
+}
+
+/// Shows an OS notification regardless of the notification settings, e.g.
+/// for errors which would otherwise go unnoticed
 pub fn show(title: &str, body: &str) {
     let mut notification = notify_rust::Notification::new();
 
@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet, VecDeque, hash_map};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use std::{convert, slice};
@@ -16,11 +16,15 @@ use data::rate_limit::TokenPriority;
 use data::target::{self, Target};
 use data::{
     Config, Notification, Server, User, Version, client, command, config,
-    environment, file_transfer, history, preview, server,
+    dcc_chat, environment, file_transfer, history, message, preview, script,
+    server,
 };
 use iced::widget::pane_grid::{self, PaneGrid};
-use iced::widget::{Space, column, container, row};
-use iced::{Length, Size, Task, Vector, advanced, clipboard};
+use iced::widget::{Space, column, container, row, stack, text};
+use iced::{
+    Length, Point, Rectangle, Size, Subscription, Task, Vector, advanced,
+    clipboard, mouse, task,
+};
 
 use self::command_bar::CommandBar;
 use self::pane::Pane;
@@ -32,7 +36,7 @@ use crate::widget::{
     shortcut,
 };
 use crate::window::Window;
-use crate::{Theme, event, notification, theme, window};
+use crate::{Theme, dialog, event, i18n, notification, theme, window};
 
 mod command_bar;
 pub mod pane;
@@ -51,10 +55,26 @@ pub struct Dashboard {
     last_changed: Option<Instant>,
     command_bar: Option<CommandBar>,
     file_transfers: file_transfer::Manager,
+    dcc_chats: dcc_chat::Manager,
     theme_editor: Option<ThemeEditor>,
     notifications: notification::Notifications,
     previews: preview::Collection,
+    show_texture_usage: bool,
+    send_file_dialog: Option<task::Handle>,
+    import_theme_dialog: Option<task::Handle>,
+    clipboard_write: Option<task::Handle>,
     buffer_settings: dashboard::BufferSettings,
+    monitored: dashboard::Monitored,
+    pinned: dashboard::Pinned,
+    popout_windows: data::window::Popouts,
+    /// Whether the focused pane was maximized for mini mode, so it is
+    /// restored when leaving it.
+    mini_maximized: bool,
+    /// Cursor over the main window while one of its panes is dragged
+    pane_drag: Option<DragCursor>,
+    /// Profile the dashboard is saved to
+    profile: data::Profile,
+    scripts: script::Manager,
 }
 
 #[derive(Debug)]
@@ -67,13 +87,31 @@ pub enum Message {
     Task(command_bar::Message),
     Shortcut(shortcut::Command),
     FileTransfer(file_transfer::task::Update),
+    DccChat(dcc_chat::task::Update),
     SendFileSelected(Server, User, Option<PathBuf>),
     CloseContextMenu(window::Id, bool),
     ThemeEditor(theme_editor::Message),
+    ImportThemeSelected(Option<PathBuf>),
+    ThemeImported(Result<Vec<String>, String>),
     ConfigReloaded(Result<Config, config::Error>),
     Client(client::Message),
     LoadPreview((url::Url, Result<data::Preview, data::preview::LoadError>)),
     NewWindow(window::Id, Pane),
+    /// Popout window reopened on startup, which doesn't take focus
+    PopoutRestored(window::Id, Pane),
+    ScriptsLoaded(Vec<script::Source>),
+    Script(script::Action),
+    PaneDragCursor(window::Id, DragCursor),
+    /// Pane dropped without a target, with the cursor and main window size
+    PaneDropped(pane_grid::Pane, Point, Size),
+}
+
+/// Where the cursor is while a pane is dragged.
+#[derive(Debug, Clone, Copy)]
+pub enum DragCursor {
+    Unmoved,
+    Over(Point),
+    Left,
 }
 
 #[derive(Debug)]
@@ -85,11 +123,29 @@ pub enum Event {
     Exit,
     OpenUrl(String, bool),
     ImagePreview(PathBuf, url::Url),
+    MessageDetails(Box<data::Message>),
+    /// Messages to share from, along with the index of the one chosen and
+    /// the casemapping of their server
+    Share(Vec<data::Message>, usize, isupport::CaseMap),
+    ChannelInfo(Server, target::Channel),
+    AccessList(Server, target::Channel),
+    Identity(Server),
+    /// Print a date range of a buffer
+    Print(buffer::Upstream),
+    /// Prompt for the operator credentials to send from a buffer
+    Oper(buffer::Upstream, Option<String>),
+    ThemeBrowser,
+    /// Toggle whether the main window is kept above other windows
+    ToggleAlwaysOnTop,
+    /// Toggle the main window between its regular and mini geometry
+    ToggleMiniMode,
+    ToggleBorderless,
 }
 
 impl Dashboard {
     pub fn empty(
         main_window: &Window,
+        profile: data::Profile,
         config: &Config,
     ) -> (Self, Task<Message>) {
         let (main_panes, pane) =
@@ -111,30 +167,53 @@ impl Dashboard {
             last_changed: None,
             command_bar: None,
             file_transfers: file_transfer::Manager::default(),
+            dcc_chats: dcc_chat::Manager::default(),
             theme_editor: None,
             notifications: notification::Notifications::new(config),
             previews: preview::Collection::default(),
+            show_texture_usage: false,
+            send_file_dialog: None,
+            import_theme_dialog: None,
+            clipboard_write: None,
             buffer_settings: dashboard::BufferSettings::default(),
+            monitored: dashboard::Monitored::default(),
+            pinned: dashboard::Pinned::default(),
+            popout_windows: data::window::Popouts::default(),
+            mini_maximized: false,
+            pane_drag: None,
+            profile,
+            scripts: script::Manager::default(),
         };
 
-        let command = dashboard.track(None);
+        let command =
+            Task::batch(vec![dashboard.track(None), dashboard.load_scripts()]);
 
         (dashboard, command)
     }
 
     pub fn restore(
         dashboard: data::Dashboard,
+        profile: data::Profile,
         config: &Config,
         main_window: &Window,
     ) -> (Self, Task<Message>) {
         let (mut dashboard, task) =
-            Dashboard::from_data(dashboard, config, main_window);
+            Dashboard::from_data(dashboard, profile, config, main_window);
 
-        let tasks = Task::batch(vec![task, dashboard.track(None)]);
+        let tasks = Task::batch(vec![
+            task,
+            dashboard.track(None),
+            dashboard.load_scripts(),
+        ]);
 
         (dashboard, tasks)
     }
 
+    /// Nicknames monitored on `server` from the user context menu.
+    pub fn monitored(&self, server: &Server) -> &[String] {
+        self.monitored.get(server)
+    }
+
     pub fn init_filters(
         &mut self,
         servers: &server::Map,
@@ -183,6 +262,10 @@ impl Dashboard {
         });
     }
 
+    pub fn history_loaded(&self) -> bool {
+        self.history.is_loaded()
+    }
+
     pub fn renormalize_history(
         &mut self,
         server: &data::Server,
@@ -233,14 +316,54 @@ impl Dashboard {
                         self.panes.main.resize(split, ratio);
                         self.last_changed = Some(Instant::now());
                     }
+                    pane::Message::PaneDragged(
+                        pane_grid::DragEvent::Picked { .. },
+                    ) => {
+                        self.pane_drag = Some(DragCursor::Unmoved);
+                    }
                     pane::Message::PaneDragged(
                         pane_grid::DragEvent::Dropped { pane, target },
                     ) => {
+                        self.pane_drag = None;
+
                         // Pane grid interactions only enabled for main window panegrid
                         self.panes.main.drop(pane, target);
                         self.last_changed = Some(Instant::now());
                     }
-                    pane::Message::PaneDragged(_) => {}
+                    pane::Message::PaneDragged(
+                        pane_grid::DragEvent::Canceled { pane },
+                    ) => {
+                        // Dropped outside of the main window, pop it out
+                        // into its own window
+                        match self.pane_drag.take() {
+                            Some(DragCursor::Left) => {
+                                return (
+                                    self.popout_pane(
+                                        Focus {
+                                            window: self.main_window(),
+                                            pane,
+                                        },
+                                        clients,
+                                        config,
+                                    ),
+                                    None,
+                                );
+                            }
+                            Some(DragCursor::Over(position)) => {
+                                return (
+                                    iced::window::size(self.main_window()).map(
+                                        move |size| {
+                                            Message::PaneDropped(
+                                                pane, position, size,
+                                            )
+                                        },
+                                    ),
+                                    None,
+                                );
+                            }
+                            Some(DragCursor::Unmoved) | None => {}
+                        }
+                    }
                     pane::Message::ClosePane => {
                         return (
                             self.close_pane(
@@ -262,15 +385,20 @@ impl Dashboard {
                                 clients,
                                 &mut self.history,
                                 &mut self.file_transfers,
+                                &mut self.dcc_chats,
+                                &self.scripts,
                                 config,
                             );
 
-                            let task = command.map(move |message| {
-                                Message::Pane(
-                                    window,
-                                    pane::Message::Buffer(id, message),
-                                )
-                            });
+                            let task = Task::batch(vec![
+                                command.map(move |message| {
+                                    Message::Pane(
+                                        window,
+                                        pane::Message::Buffer(id, message),
+                                    )
+                                }),
+                                self.script_actions(),
+                            ]);
 
                             let Some(event) = event else {
                                 return (task, None);
@@ -318,9 +446,20 @@ impl Dashboard {
                             return (Task::none(), None);
                         }
                     }
+                    pane::Message::ToggleDensity => {
+                        if let Some((_, _, pane)) = self.get_focused_mut() {
+                            pane.toggle_density(config);
+
+                            self.last_changed = Some(Instant::now());
+                            return (Task::none(), None);
+                        }
+                    }
                     pane::Message::MaximizePane => self.maximize_pane(),
                     pane::Message::Popout => {
-                        return (self.popout_pane(clients, config), None);
+                        return (
+                            self.popout_pane(self.focus, clients, config),
+                            None,
+                        );
                     }
                     pane::Message::Merge => {
                         return (self.merge_pane(clients, config), None);
@@ -370,6 +509,15 @@ impl Dashboard {
                             state.buffer.update_pane_size(size, config);
                         }
                     }
+                    pane::Message::ContextMenu(id, message) => {
+                        return self.handle_context_menu_event(
+                            window,
+                            Some(id),
+                            buffer::context_menu::update(message),
+                            clients,
+                            config,
+                        );
+                    }
                 }
             }
             Message::Sidebar(message) => {
@@ -398,6 +546,15 @@ impl Dashboard {
                         ),
                         None,
                     ),
+                    sidebar::Event::OpenAnother(buffer, action) => (
+                        self.open_additional_buffer(
+                            data::Buffer::Upstream(buffer),
+                            action,
+                            clients,
+                            config,
+                        ),
+                        None,
+                    ),
                     sidebar::Event::Focus(window, pane) => {
                         (self.focus_pane(window, pane), None)
                     }
@@ -470,6 +627,23 @@ impl Dashboard {
 
                         (Task::none(), None)
                     }
+                    sidebar::Event::Reauthenticate(server) => {
+                        clients.reauthenticate(&server);
+
+                        (Task::none(), None)
+                    }
+                    sidebar::Event::Identity(server) => {
+                        (Task::none(), Some(Event::Identity(server)))
+                    }
+                    sidebar::Event::Print(buffer) => {
+                        (Task::none(), Some(Event::Print(buffer)))
+                    }
+                    sidebar::Event::TogglePin(buffer) => {
+                        self.pinned.toggle(&buffer);
+                        self.last_changed = Some(Instant::now());
+
+                        (Task::none(), None)
+                    }
                     sidebar::Event::MarkAsRead(buffer) => {
                         if let Some(kind) = history::Kind::from_buffer(
                             data::Buffer::Upstream(buffer),
@@ -488,6 +662,24 @@ impl Dashboard {
                         let _ = open::that_detached(Config::path());
                         (Task::none(), None)
                     }
+                    sidebar::Event::ContextMenu(event) => {
+                        // Context menu events close the menu themselves
+                        let (task, event) = self.handle_context_menu_event(
+                            main_window.id,
+                            None,
+                            event,
+                            clients,
+                            config,
+                        );
+
+                        return (
+                            Task::batch(vec![
+                                task,
+                                command.map(Message::Sidebar),
+                            ]),
+                            event,
+                        );
+                    }
                 };
 
                 let window = main_window.id;
@@ -506,36 +698,30 @@ impl Dashboard {
                 );
             }
             Message::SelectedText(contents, clipboard_kind) => {
-                let mut last_y = None;
-                let contents = contents.into_iter().fold(
-                    String::new(),
-                    |acc, (y, content)| {
-                        if let Some(_y) = last_y {
-                            let new_line = if y == _y { "" } else { "\n" };
-                            last_y = Some(y);
-
-                            format!("{acc}{new_line}{content}")
-                        } else {
-                            last_y = Some(y);
+                // Joining a large selection can take a while, so it's done
+                // off the UI thread. A newer selection cancels the previous
+                // write if it hasn't finished yet.
+                let (task, handle) =
+                    Task::future(async move { join_selected_text(contents) })
+                        .then(move |contents| {
+                            if contents.is_empty() {
+                                Task::none()
+                            } else {
+                                match clipboard_kind {
+                                    advanced::clipboard::Kind::Standard => {
+                                        clipboard::write(contents)
+                                    }
+                                    advanced::clipboard::Kind::Primary => {
+                                        clipboard::write_primary(contents)
+                                    }
+                                }
+                            }
+                        })
+                        .abortable();
 
-                            content
-                        }
-                    },
-                );
+                self.clipboard_write = Some(handle.abort_on_drop());
 
-                if !contents.is_empty() {
-                    return (
-                        match clipboard_kind {
-                            advanced::clipboard::Kind::Standard => {
-                                clipboard::write(contents)
-                            }
-                            advanced::clipboard::Kind::Primary => {
-                                clipboard::write_primary(contents)
-                            }
-                        },
-                        None,
-                    );
-                }
+                return (task, None);
             }
             Message::History(message) => {
                 if let Some(event) =
@@ -545,8 +731,9 @@ impl Dashboard {
                         history::manager::Event::Loaded(kind) => {
                             let buffer = kind.into();
 
-                            if let Some((window, pane, state)) =
-                                self.panes.get_mut_by_buffer(&buffer)
+                            if let Some((window, pane, state)) = self
+                                .panes
+                                .get_mut_by_buffer(&buffer, self.focus)
                             {
                                 return (
                                     match config.buffer.scroll_position_on_open
@@ -640,7 +827,7 @@ impl Dashboard {
                                     ),
                                     None,
                                 ),
-                                command_bar::Buffer::Popout => (self.popout_pane(clients, config), None),
+                                command_bar::Buffer::Popout => (self.popout_pane(self.focus, clients, config), None),
                                 command_bar::Buffer::Merge => (self.merge_pane(clients, config), None),
                                 command_bar::Buffer::ToggleInternal(buffer) => {
                                     (self.toggle_internal_buffer(clients, config, buffer), None)
@@ -676,6 +863,10 @@ impl Dashboard {
                                     self.side_menu.toggle_visibility();
                                     (Task::none(), None)
                                 }
+                                command_bar::Ui::ToggleTextureUsage => {
+                                    self.show_texture_usage = !self.show_texture_usage;
+                                    (Task::none(), None)
+                                }
                             },
                             command_bar::Command::Theme(command) => match command {
                                 command_bar::Theme::Switch(new) => {
@@ -686,22 +877,46 @@ impl Dashboard {
                                     if let Some(editor) = &self.theme_editor {
                                         (window::gain_focus(editor.window), None)
                                     } else {
-                                        let (editor, task) = ThemeEditor::open(main_window, config);
+                                        let saved = self.popout_windows.get(data::window::Auxiliary::ThemeEditor);
+                                        let (editor, task) = ThemeEditor::open(main_window, saved, config);
 
                                         self.theme_editor = Some(editor);
 
                                         (task.then(|_| Task::none()), None)
                                     }
                                 }
+                                command_bar::Theme::Browse => {
+                                    (Task::none(), Some(Event::ThemeBrowser))
+                                }
                                 command_bar::Theme::OpenThemesWebsite => {
                                     let _ = open::that_detached(environment::THEME_WEBSITE);
                                     (Task::none(), None)
                                 }
+                                command_bar::Theme::Import => {
+                                    if self.import_theme_dialog.is_some() {
+                                        return (Task::none(), None);
+                                    }
+
+                                    let (task, handle) = dialog::pick_file();
+
+                                    self.import_theme_dialog = Some(handle);
+
+                                    (task.map(Message::ImportThemeSelected), None)
+                                }
                             },
                             command_bar::Command::Window(command) => match command {
                                 command_bar::Window::ToggleFullscreen => {
                                     (window::toggle_fullscreen(), None)
                                 }
+                                command_bar::Window::ToggleAlwaysOnTop => {
+                                    self.toggle_always_on_top()
+                                }
+                                command_bar::Window::ToggleMiniMode => {
+                                    (Task::none(), Some(Event::ToggleMiniMode))
+                                }
+                                command_bar::Window::ToggleBorderless => {
+                                    (Task::none(), Some(Event::ToggleBorderless))
+                                }
                             },
                             command_bar::Command::Application(application) => match application {
                                 command_bar::Application::Quit => (self.exit(clients, config), None),
@@ -786,56 +1001,48 @@ impl Dashboard {
                         let all_buffers = all_buffers(clients, &self.history);
                         let open_buffers = open_buffers(self);
 
-                        if let Some((window, pane, state, history)) =
-                            self.get_focused_with_history_mut()
-                            && let Some(buffer) = cycle_next_buffer(
-                                state.buffer.upstream(),
-                                all_buffers,
-                                &open_buffers,
-                            )
+                        if let Some(buffer) =
+                            self.get_focused().and_then(|(_, _, state)| {
+                                cycle_next_buffer(
+                                    state.buffer.upstream(),
+                                    all_buffers,
+                                    &open_buffers,
+                                )
+                            })
                         {
-                            mark_as_read_on_buffer_close(
-                                &state.buffer,
-                                history,
-                                clients,
-                                config,
-                            );
-
-                            state.buffer = Buffer::from_data(
-                                data::Buffer::Upstream(buffer),
-                                state.size,
-                                config,
+                            return (
+                                self.open_additional_buffer(
+                                    data::Buffer::Upstream(buffer),
+                                    BufferAction::ReplacePane,
+                                    clients,
+                                    config,
+                                ),
+                                None,
                             );
-                            self.last_changed = Some(Instant::now());
-                            return (self.focus_pane(window, pane), None);
                         }
                     }
                     CyclePreviousBuffer => {
                         let all_buffers = all_buffers(clients, &self.history);
                         let open_buffers = open_buffers(self);
 
-                        if let Some((window, pane, state, history)) =
-                            self.get_focused_with_history_mut()
-                            && let Some(buffer) = cycle_previous_buffer(
-                                state.buffer.upstream(),
-                                all_buffers,
-                                &open_buffers,
-                            )
+                        if let Some(buffer) =
+                            self.get_focused().and_then(|(_, _, state)| {
+                                cycle_previous_buffer(
+                                    state.buffer.upstream(),
+                                    all_buffers,
+                                    &open_buffers,
+                                )
+                            })
                         {
-                            mark_as_read_on_buffer_close(
-                                &state.buffer,
-                                history,
-                                clients,
-                                config,
-                            );
-
-                            state.buffer = Buffer::from_data(
-                                data::Buffer::Upstream(buffer),
-                                state.size,
-                                config,
+                            return (
+                                self.open_additional_buffer(
+                                    data::Buffer::Upstream(buffer),
+                                    BufferAction::ReplacePane,
+                                    clients,
+                                    config,
+                                ),
+                                None,
                             );
-                            self.last_changed = Some(Instant::now());
-                            return (self.focus_pane(window, pane), None);
                         }
                     }
                     LeaveBuffer => {
@@ -877,6 +1084,14 @@ impl Dashboard {
                             return (Task::none(), None);
                         }
                     }
+                    ToggleDensity => {
+                        if let Some((_, _, pane)) = self.get_focused_mut() {
+                            pane.toggle_density(config);
+
+                            self.last_changed = Some(Instant::now());
+                            return (Task::none(), None);
+                        }
+                    }
                     ToggleSidebar => {
                         self.side_menu.toggle_visibility();
                     }
@@ -940,9 +1155,28 @@ impl Dashboard {
                             None,
                         );
                     }
+                    Search => {
+                        return (
+                            self.toggle_internal_buffer(
+                                clients,
+                                config,
+                                buffer::Internal::Search,
+                            ),
+                            None,
+                        );
+                    }
                     ToggleFullscreen => {
                         return (window::toggle_fullscreen(), None);
                     }
+                    ToggleAlwaysOnTop => {
+                        return self.toggle_always_on_top();
+                    }
+                    ToggleMiniMode => {
+                        return (Task::none(), Some(Event::ToggleMiniMode));
+                    }
+                    ToggleBorderless => {
+                        return (Task::none(), Some(Event::ToggleBorderless));
+                    }
                     QuitApplication => {
                         return (self.exit(clients, config), None);
                     }
@@ -1052,28 +1286,24 @@ impl Dashboard {
                             all_buffers_with_has_unread(clients, &self.history);
                         let open_buffers = open_buffers(self);
 
-                        if let Some((window, pane, state, history)) =
-                            self.get_focused_with_history_mut()
-                            && let Some(buffer) = cycle_next_unread_buffer(
-                                state.buffer.upstream(),
-                                all_buffers,
-                                &open_buffers,
-                            )
+                        if let Some(buffer) =
+                            self.get_focused().and_then(|(_, _, state)| {
+                                cycle_next_unread_buffer(
+                                    state.buffer.upstream(),
+                                    all_buffers,
+                                    &open_buffers,
+                                )
+                            })
                         {
-                            mark_as_read_on_buffer_close(
-                                &state.buffer,
-                                history,
-                                clients,
-                                config,
-                            );
-
-                            state.buffer = Buffer::from_data(
-                                data::Buffer::Upstream(buffer),
-                                state.size,
-                                config,
+                            return (
+                                self.open_additional_buffer(
+                                    data::Buffer::Upstream(buffer),
+                                    BufferAction::ReplacePane,
+                                    clients,
+                                    config,
+                                ),
+                                None,
                             );
-                            self.last_changed = Some(Instant::now());
-                            return (self.focus_pane(window, pane), None);
                         }
                     }
                     CyclePreviousUnreadBuffer => {
@@ -1081,29 +1311,48 @@ impl Dashboard {
                             all_buffers_with_has_unread(clients, &self.history);
                         let open_buffers = open_buffers(self);
 
-                        if let Some((window, pane, state, history)) =
-                            self.get_focused_with_history_mut()
-                            && let Some(buffer) = cycle_previous_unread_buffer(
-                                state.buffer.upstream(),
-                                all_buffers,
-                                &open_buffers,
-                            )
+                        if let Some(buffer) =
+                            self.get_focused().and_then(|(_, _, state)| {
+                                cycle_previous_unread_buffer(
+                                    state.buffer.upstream(),
+                                    all_buffers,
+                                    &open_buffers,
+                                )
+                            })
                         {
-                            mark_as_read_on_buffer_close(
-                                &state.buffer,
-                                history,
-                                clients,
-                                config,
+                            return (
+                                self.open_additional_buffer(
+                                    data::Buffer::Upstream(buffer),
+                                    BufferAction::ReplacePane,
+                                    clients,
+                                    config,
+                                ),
+                                None,
                             );
+                        }
+                    }
+                    FocusPinnedBuffer(slot) => {
+                        let Some(buffer) = self.pinned.get(slot).cloned()
+                        else {
+                            return (Task::none(), None);
+                        };
 
-                            state.buffer = Buffer::from_data(
-                                data::Buffer::Upstream(buffer),
-                                state.size,
-                                config,
-                            );
-                            self.last_changed = Some(Instant::now());
+                        if let Some((window, pane)) = self.panes.find_by_buffer(
+                            &data::Buffer::Upstream(buffer.clone()),
+                            self.focus,
+                        ) {
                             return (self.focus_pane(window, pane), None);
                         }
+
+                        return (
+                            self.open_buffer(
+                                data::Buffer::Upstream(buffer),
+                                config.actions.sidebar.buffer,
+                                clients,
+                                config,
+                            ),
+                            None,
+                        );
                     }
                     MarkAsRead => {
                         if let Some((_, _, pane)) = self.get_focused_mut()
@@ -1125,7 +1374,12 @@ impl Dashboard {
             Message::FileTransfer(update) => {
                 self.file_transfers.update(update, config);
             }
+            Message::DccChat(update) => {
+                self.dcc_chats.update(update, config);
+            }
             Message::SendFileSelected(server, to, path) => {
+                self.send_file_dialog = None;
+
                 if let Some(server_handle) = clients.get_server_handle(&server)
                 {
                     let casemapping = clients.get_casemapping(&server);
@@ -1156,6 +1410,31 @@ impl Dashboard {
                     }
                 }
             }
+            Message::ImportThemeSelected(path) => {
+                self.import_theme_dialog = None;
+
+                if let Some(path) = path {
+                    return (
+                        Task::perform(
+                            data::appearance::theme::import::import(path),
+                            |result| {
+                                Message::ThemeImported(
+                                    result.map_err(|error| error.to_string()),
+                                )
+                            },
+                        ),
+                        None,
+                    );
+                }
+            }
+            Message::ThemeImported(Ok(names)) => {
+                log::info!("Imported themes: {}", names.join(", "));
+
+                return (Task::none(), Some(Event::ReloadThemes));
+            }
+            Message::ThemeImported(Err(error)) => {
+                log::error!("Failed to import color scheme: {error}");
+            }
             Message::CloseContextMenu(window, any_closed) => {
                 if !any_closed {
                     if let Some((_, _, state)) = self.get_focused_mut()
@@ -1229,6 +1508,14 @@ impl Dashboard {
                         "failed to update targets timestamp for {server} to {timestamp}: {error}"
                     );
                 }
+                client::Message::ChannelKeysSaved(server, Ok(())) => {
+                    log::debug!("saved channel keys for {server}");
+                }
+                client::Message::ChannelKeysSaved(server, Err(error)) => {
+                    log::warn!(
+                        "failed to save channel keys for {server}: {error}"
+                    );
+                }
                 client::Message::RequestNewerChatHistory(
                     server,
                     target,
@@ -1291,17 +1578,15 @@ impl Dashboard {
             },
             Message::LoadPreview((url, Ok(preview))) => {
                 log::debug!("Preview loaded for {url}");
-                if let hash_map::Entry::Occupied(mut entry) =
-                    self.previews.entry(url)
-                {
-                    *entry.get_mut() = preview::State::Loaded(preview);
-                }
+                self.previews.loaded(
+                    url,
+                    preview,
+                    config.preview.memory_limit_bytes(),
+                );
             }
             Message::LoadPreview((url, Err(error))) => {
                 log::info!("Failed to load preview for {url}: {error}");
-                if self.previews.contains_key(&url) {
-                    self.previews.insert(url, preview::State::Error(error));
-                }
+                self.previews.failed(url, error);
             }
             Message::NewWindow(window, pane) => {
                 let (state, pane) = pane_grid::State::new(pane);
@@ -1309,17 +1594,66 @@ impl Dashboard {
 
                 return (self.focus_pane(window, pane), None);
             }
+            Message::PopoutRestored(window, pane) => {
+                let (state, _) = pane_grid::State::new(pane);
+                self.panes.popout.insert(window, state);
+            }
+            Message::ScriptsLoaded(sources) => {
+                self.scripts = script::Manager::new(sources);
+            }
+            Message::Script(action) => {
+                return (self.run_script_action(action, clients, config), None);
+            }
+            Message::PaneDragCursor(window, cursor) => {
+                if window == self.main_window() && self.pane_drag.is_some() {
+                    self.pane_drag = Some(cursor);
+                }
+            }
+            Message::PaneDropped(pane, position, size) => {
+                if !Rectangle::with_size(size).contains(position) {
+                    return (
+                        self.popout_pane(
+                            Focus {
+                                window: self.main_window(),
+                                pane,
+                            },
+                            clients,
+                            config,
+                        ),
+                        None,
+                    );
+                }
+            }
         }
 
         (Task::none(), None)
     }
 
+    /// Follows the cursor while a pane is dragged, to tell whether it's
+    /// dropped outside of the main window.
+    pub fn subscription(&self) -> Subscription<Message> {
+        if self.pane_drag.is_none() {
+            return Subscription::none();
+        }
+
+        iced::event::listen_with(|event, _status, window| match event {
+            iced::Event::Mouse(mouse::Event::CursorMoved { position }) => Some(
+                Message::PaneDragCursor(window, DragCursor::Over(position)),
+            ),
+            iced::Event::Mouse(mouse::Event::CursorLeft) => {
+                Some(Message::PaneDragCursor(window, DragCursor::Left))
+            }
+            _ => None,
+        })
+    }
+
     pub fn view_window<'a>(
         &'a self,
         window: window::Id,
         clients: &'a client::Map,
         config: &'a Config,
         theme: &'a Theme,
+        theme_overrides: &'a theme::Overrides,
     ) -> Element<'a, Message> {
         if let Some(state) = self.panes.popout.get(&window) {
             let content = container(
@@ -1337,13 +1671,16 @@ impl Dashboard {
                         false,
                         clients,
                         &self.file_transfers,
+                        &self.dcc_chats,
                         &self.history,
                         &self.previews,
                         &self.side_menu,
                         config,
                         theme,
+                        pane_theme(pane, clients, config, theme_overrides),
                         settings,
                         window != self.main_window(),
+                        false,
                     )
                 })
                 .spacing(4)
@@ -1369,8 +1706,10 @@ impl Dashboard {
         servers: &'a server::Map,
         clients: &'a client::Map,
         version: &'a Version,
+        is_mini: bool,
         config: &'a Config,
         theme: &'a Theme,
+        theme_overrides: &'a theme::Overrides,
     ) -> Element<'a, Message> {
         let pane_grid: Element<_> =
             PaneGrid::new(&self.panes.main, |id, pane, maximized| {
@@ -1379,7 +1718,12 @@ impl Dashboard {
                         window: self.main_window(),
                         pane: id,
                     };
-                let panes = self.panes.main.panes.len();
+                // Mini mode shows a single pane
+                let panes = if is_mini {
+                    1
+                } else {
+                    self.panes.main.panes.len()
+                };
                 let buffer = pane.buffer.data();
                 let settings =
                     buffer.as_ref().and_then(|b| self.buffer_settings.get(b));
@@ -1391,13 +1735,16 @@ impl Dashboard {
                     maximized,
                     clients,
                     &self.file_transfers,
+                    &self.dcc_chats,
                     &self.history,
                     &self.previews,
                     &self.side_menu,
                     config,
                     theme,
+                    pane_theme(pane, clients, config, theme_overrides),
                     settings,
                     false,
+                    is_mini,
                 )
             })
             .on_click(pane::Message::PaneClicked)
@@ -1414,27 +1761,32 @@ impl Dashboard {
             .height(Length::Fill)
             .padding(8);
 
-        let side_menu = self
-            .side_menu
-            .view(
-                servers,
-                clients,
-                &self.history,
-                &self.panes,
-                self.focus,
-                config,
-                &self.file_transfers,
-                version,
-                theme,
-            )
-            .map(|e| e.map(Message::Sidebar));
-
-        let content = match config.sidebar.position {
-            data::config::sidebar::Position::Left
-            | data::config::sidebar::Position::Top => {
-                vec![
-                    side_menu.unwrap_or_else(|| row![].into()),
-                    pane_grid.into(),
+        // Mini mode hides the sidebar
+        let side_menu = if is_mini {
+            None
+        } else {
+            self.side_menu
+                .view(
+                    servers,
+                    clients,
+                    &self.history,
+                    &self.pinned,
+                    &self.panes,
+                    self.focus,
+                    config,
+                    &self.file_transfers,
+                    version,
+                    theme,
+                )
+                .map(|e| e.map(Message::Sidebar))
+        };
+
+        let content = match config.sidebar.position {
+            data::config::sidebar::Position::Left
+            | data::config::sidebar::Position::Top => {
+                vec![
+                    side_menu.unwrap_or_else(|| row![].into()),
+                    pane_grid.into(),
                 ]
             }
             data::config::sidebar::Position::Right
@@ -1459,6 +1811,21 @@ impl Dashboard {
                 .into()
         };
 
+        let texture_usage: Element<Message> = if self.show_texture_usage {
+            texture_usage(self.previews.usage(), config)
+        } else {
+            Space::new().into()
+        };
+
+        let base: Element<Message> = stack![
+            base,
+            container(texture_usage)
+                .align_right(Length::Fill)
+                .align_bottom(Length::Fill)
+                .padding(12),
+        ]
+        .into();
+
         let base = if let Some(command_bar) = self.command_bar.as_ref() {
             let background = anchored_overlay(
                 base,
@@ -1512,217 +1879,13 @@ impl Dashboard {
 
         match event {
             buffer::Event::ContextMenu(event) => {
-                let mut tasks =
-                    vec![context_menu::close(convert::identity).map(
-                        move |any_closed| {
-                            Message::CloseContextMenu(window, any_closed)
-                        },
-                    )];
-
-                match event {
-                    buffer::context_menu::Event::CopyUrl(url) => {
-                        tasks.push(clipboard::write(url));
-                    }
-                    buffer::context_menu::Event::ToggleAccessLevel(
-                        server,
-                        channel,
-                        nick,
-                        mode,
-                    ) => {
-                        let buffer = buffer::Upstream::Channel(
-                            server.clone(),
-                            channel.clone(),
-                        );
-
-                        let command = command::Irc::Mode(
-                            channel.to_string(),
-                            Some(mode),
-                            Some(vec![nick.to_string()]),
-                        );
-                        let input = data::Input::command(buffer, command);
-
-                        if let Some(encoded) = input.encoded() {
-                            clients.send(
-                                &input.buffer,
-                                encoded,
-                                TokenPriority::User,
-                            );
-                        }
-                    }
-                    buffer::context_menu::Event::SendWhois(server, nick) => {
-                        let buffer =
-                            pane.buffer.upstream().cloned().unwrap_or_else(
-                                || buffer::Upstream::Server(server.clone()),
-                            );
-
-                        let command =
-                            command::Irc::Whois(None, nick.to_string());
-
-                        let input =
-                            data::Input::command(buffer.clone(), command);
-
-                        if let Some(encoded) = input.encoded() {
-                            clients.send(
-                                &input.buffer,
-                                encoded,
-                                TokenPriority::User,
-                            );
-                        }
-
-                        if let Some(nick) = clients.nickname(buffer.server()) {
-                            let mut user = nick.to_owned().into();
-                            let mut channel_users = None;
-                            let chantypes =
-                                clients.get_chantypes(buffer.server());
-                            let statusmsg =
-                                clients.get_statusmsg(buffer.server());
-                            let casemapping =
-                                clients.get_casemapping(buffer.server());
-
-                            // Resolve our attributes if sending this message in a channel
-                            if let buffer::Upstream::Channel(server, channel) =
-                                &buffer
-                            {
-                                channel_users =
-                                    clients.get_channel_users(server, channel);
-
-                                if let Some(user_with_attributes) = clients
-                                    .resolve_user_attributes(
-                                        server, channel, &user,
-                                    )
-                                {
-                                    user = user_with_attributes.clone();
-                                }
-                            }
-
-                            if let Some(messages) = input.messages(
-                                user,
-                                channel_users,
-                                chantypes,
-                                statusmsg,
-                                casemapping,
-                                config,
-                            ) {
-                                for message in messages {
-                                    if let Some(task) =
-                                        self.history.record_message(
-                                            input.server(),
-                                            casemapping,
-                                            message,
-                                            &config.buffer,
-                                        )
-                                    {
-                                        tasks.push(Task::perform(
-                                            task,
-                                            Message::History,
-                                        ));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    buffer::context_menu::Event::OpenQuery(
-                        server,
-                        query,
-                        buffer_action,
-                    ) => {
-                        let buffer = buffer::Upstream::Query(server, query);
-
-                        tasks.push(self.open_buffer(
-                            data::Buffer::Upstream(buffer),
-                            buffer_action,
-                            clients,
-                            config,
-                        ));
-                    }
-                    buffer::context_menu::Event::InsertNickname(nick) => {
-                        if let Some((_, _, pane, history)) =
-                            self.get_focused_with_history_mut()
-                        {
-                            tasks.push(
-                                pane.buffer
-                                    .insert_user_to_input(
-                                        nick,
-                                        history,
-                                        &config.buffer.text_input.autocomplete,
-                                    )
-                                    .map(move |message| {
-                                        Message::Pane(
-                                            window,
-                                            pane::Message::Buffer(id, message),
-                                        )
-                                    }),
-                            );
-                        }
-                    }
-                    buffer::context_menu::Event::SendFile(server, nick) => {
-                        tasks.push(Task::perform(
-                            async move {
-                                rfd::AsyncFileDialog::new()
-                                    .pick_file()
-                                    .await
-                                    .map(|handle| handle.path().to_path_buf())
-                            },
-                            move |file| {
-                                Message::SendFileSelected(
-                                    server.clone(),
-                                    nick.clone(),
-                                    file,
-                                )
-                            },
-                        ));
-                    }
-                    buffer::context_menu::Event::CtcpRequest(
-                        command,
-                        server,
-                        nick,
-                        params,
-                    ) => {
-                        let buffer =
-                            pane.buffer.upstream().cloned().unwrap_or_else(
-                                || buffer::Upstream::Server(server.clone()),
-                            );
-
-                        let command = command::Irc::Ctcp(
-                            command,
-                            nick.to_string(),
-                            params,
-                        );
-
-                        let input =
-                            data::Input::command(buffer.clone(), command);
-
-                        if let Some(encoded) = input.encoded() {
-                            clients.send(
-                                &input.buffer,
-                                encoded,
-                                TokenPriority::High,
-                            );
-                        }
-                    }
-                    buffer::context_menu::Event::CopyTimestamp(
-                        date_time,
-                        format,
-                    ) => {
-                        if let Some(format) = format {
-                            tasks.push(clipboard::write(
-                                date_time
-                                    .with_timezone(&Local)
-                                    .format(&format)
-                                    .to_string(),
-                            ));
-                        } else {
-                            tasks.push(clipboard::write(
-                                date_time.to_rfc3339_opts(
-                                    SecondsFormat::Millis,
-                                    true,
-                                ),
-                            ));
-                        }
-                    }
-                }
-
-                return (Task::batch(tasks), None);
+                return self.handle_context_menu_event(
+                    window,
+                    Some(id),
+                    event,
+                    clients,
+                    config,
+                );
             }
             buffer::Event::OpenBuffers(targets) => {
                 let mut tasks = vec![];
@@ -1771,14 +1934,10 @@ impl Dashboard {
             buffer::Event::History(history_task) => {
                 return (history_task.map(Message::History), None);
             }
-            buffer::Event::GoToMessage(server, channel, message) => {
-                let buffer = data::Buffer::Upstream(buffer::Upstream::Channel(
-                    server, channel,
-                ));
-
+            buffer::Event::GoToMessage(buffer, message, query) => {
                 let mut tasks = vec![];
 
-                if self.panes.get_mut_by_buffer(&buffer).is_none() {
+                if self.panes.find_by_buffer(&buffer, self.focus).is_none() {
                     tasks.push(self.open_buffer(
                         buffer.clone(),
                         config.actions.buffer.click_highlight,
@@ -1788,8 +1947,17 @@ impl Dashboard {
                 }
 
                 if let Some((window, pane, state)) =
-                    self.panes.get_mut_by_buffer(&buffer)
+                    self.panes.get_mut_by_buffer(&buffer, self.focus)
                 {
+                    // Jumping to a message other than a search result
+                    // leaves the previous search
+                    state.buffer.set_search_query(
+                        query,
+                        clients.get_casemapping_or_default(
+                            buffer.upstream().map(buffer::Upstream::server),
+                        ),
+                    );
+
                     tasks.push(
                         state
                             .buffer
@@ -1812,19 +1980,9 @@ impl Dashboard {
             }
             buffer::Event::PreviewChanged => {
                 let visible = self.panes.visible_urls();
-                let tracking =
-                    self.previews.keys().cloned().collect::<HashSet<_>>();
-                let missing =
-                    visible.difference(&tracking).cloned().collect::<Vec<_>>();
-                let removed = tracking.difference(&visible);
-
-                for url in &missing {
-                    self.previews.insert(url.clone(), preview::State::Loading);
-                }
-
-                for url in removed {
-                    self.previews.remove(url);
-                }
+                let missing = self
+                    .previews
+                    .track(&visible, config.preview.memory_limit_bytes());
 
                 return (
                     Task::batch(missing.into_iter().map(|url| {
@@ -1864,11 +2022,534 @@ impl Dashboard {
             buffer::Event::ImagePreview(path, url) => {
                 return (Task::none(), Some(Event::ImagePreview(path, url)));
             }
+            buffer::Event::OperPrompt(name) => {
+                if let Some(buffer) = pane.buffer.upstream() {
+                    return (
+                        Task::none(),
+                        Some(Event::Oper(buffer.clone(), name)),
+                    );
+                }
+            }
         }
 
         (Task::none(), None)
     }
 
+    fn handle_context_menu_event(
+        &mut self,
+        window: window::Id,
+        pane: Option<pane_grid::Pane>,
+        event: buffer::context_menu::Event,
+        clients: &mut data::client::Map,
+        config: &Config,
+    ) -> (Task<Message>, Option<Event>) {
+        let upstream = pane
+            .and_then(|pane| self.panes.get(window, pane))
+            .and_then(|pane| pane.buffer.upstream().cloned());
+
+        let mut tasks = vec![context_menu::close(convert::identity).map(
+            move |any_closed| Message::CloseContextMenu(window, any_closed),
+        )];
+        let mut dashboard_event = None;
+
+        match event {
+            buffer::context_menu::Event::OpenUrl(url) => {
+                dashboard_event = Some(Event::OpenUrl(
+                    url,
+                    config.buffer.url.prompt_before_open,
+                ));
+            }
+            buffer::context_menu::Event::OpenWith(program, args) => {
+                tasks.push(
+                    Task::future(async move {
+                        if let Err(error) =
+                            tokio::process::Command::new(&program)
+                                .args(&args)
+                                .status()
+                                .await
+                        {
+                            log::warn!("failed to run {program}: {error}");
+                        }
+                    })
+                    .discard(),
+                );
+            }
+            buffer::context_menu::Event::CopyUrl(url) => {
+                tasks.push(clipboard::write(url));
+            }
+            buffer::context_menu::Event::CopyTitle(url) => {
+                if let Ok(url) = url::Url::parse(&url) {
+                    tasks.push(
+                        Task::future(data::preview::title(
+                            url.clone(),
+                            config.preview.clone(),
+                        ))
+                        .then(move |result| match result {
+                            Ok(title) => clipboard::write(title),
+                            Err(error) => {
+                                log::warn!(
+                                    "failed to load title for {url}: {error}"
+                                );
+                                Task::none()
+                            }
+                        }),
+                    );
+                }
+            }
+            buffer::context_menu::Event::ToggleAccessLevel(
+                server,
+                channel,
+                nick,
+                mode,
+            ) => {
+                let buffer =
+                    buffer::Upstream::Channel(server.clone(), channel.clone());
+
+                let command = command::Irc::Mode(
+                    channel.to_string(),
+                    Some(mode),
+                    Some(vec![nick.to_string()]),
+                );
+                let input = data::Input::command(buffer, command);
+
+                if let Some(encoded) = input.encoded() {
+                    clients.send(&input.buffer, encoded, TokenPriority::User);
+                }
+            }
+            buffer::context_menu::Event::SendWhois(server, nick) => {
+                let buffer = upstream.clone().unwrap_or_else(|| {
+                    buffer::Upstream::Server(server.clone())
+                });
+
+                let command = command::Irc::Whois(None, nick.to_string());
+
+                let input = data::Input::command(buffer.clone(), command);
+
+                if let Some(encoded) = input.encoded() {
+                    clients.send(&input.buffer, encoded, TokenPriority::User);
+                }
+
+                if let Some(nick) = clients.nickname(buffer.server()) {
+                    let mut user = nick.to_owned().into();
+                    let mut channel_users = None;
+                    let chantypes = clients.get_chantypes(buffer.server());
+                    let statusmsg = clients.get_statusmsg(buffer.server());
+                    let casemapping = clients.get_casemapping(buffer.server());
+
+                    // Resolve our attributes if sending this message in a channel
+                    if let buffer::Upstream::Channel(server, channel) = &buffer
+                    {
+                        channel_users =
+                            clients.get_channel_users(server, channel);
+
+                        if let Some(user_with_attributes) = clients
+                            .resolve_user_attributes(server, channel, &user)
+                        {
+                            user = user_with_attributes.clone();
+                        }
+                    }
+
+                    if let Some(messages) = input.messages(
+                        user,
+                        channel_users,
+                        chantypes,
+                        statusmsg,
+                        casemapping,
+                        config,
+                    ) {
+                        for message in messages {
+                            if let Some(task) = self.history.record_message(
+                                input.server(),
+                                casemapping,
+                                message,
+                                &config.buffer,
+                            ) {
+                                tasks.push(Task::perform(
+                                    task,
+                                    Message::History,
+                                ));
+                            }
+                        }
+                    }
+                }
+            }
+            buffer::context_menu::Event::OpenQuery(
+                server,
+                query,
+                buffer_action,
+            ) => {
+                let buffer = buffer::Upstream::Query(server, query);
+
+                tasks.push(self.open_buffer(
+                    data::Buffer::Upstream(buffer),
+                    buffer_action,
+                    clients,
+                    config,
+                ));
+            }
+            buffer::context_menu::Event::InsertNickname(nick) => {
+                if let Some((window, id, pane, history)) =
+                    self.get_focused_with_history_mut()
+                {
+                    tasks.push(
+                        pane.buffer
+                            .insert_user_to_input(
+                                nick,
+                                history,
+                                &config.buffer.text_input.autocomplete,
+                            )
+                            .map(move |message| {
+                                Message::Pane(
+                                    window,
+                                    pane::Message::Buffer(id, message),
+                                )
+                            }),
+                    );
+                }
+            }
+            buffer::context_menu::Event::SendFile(server, nick) => {
+                // Only one send-file dialog is open at a time
+                if self.send_file_dialog.is_none() {
+                    let (task, handle) = dialog::pick_file();
+
+                    self.send_file_dialog = Some(handle);
+
+                    tasks.push(task.map(move |file| {
+                        Message::SendFileSelected(
+                            server.clone(),
+                            nick.clone(),
+                            file,
+                        )
+                    }));
+                }
+            }
+            buffer::context_menu::Event::DccChat(server, user) => {
+                if let Some(id) = self.dcc_chats.find(&server, &user) {
+                    tasks.push(self.open_buffer(
+                        data::Buffer::DccChat(id),
+                        config.actions.buffer.local,
+                        clients,
+                        config,
+                    ));
+                } else if let Some(server_handle) =
+                    clients.get_server_handle(&server)
+                {
+                    let casemapping = clients.get_casemapping(&server);
+                    let query = target::Query::from(&user);
+
+                    let event = self.dcc_chats.offer(
+                        dcc_chat::OfferRequest {
+                            to: user,
+                            server: server.clone(),
+                            server_handle: server_handle.clone(),
+                        },
+                        config,
+                    );
+
+                    let dcc_chat::manager::Event::NewChat(ref chat, _) = event;
+                    let buffer = data::Buffer::DccChat(chat.id);
+
+                    tasks.push(self.handle_dcc_chat_event(
+                        &server,
+                        casemapping,
+                        &query,
+                        event,
+                        &config.buffer,
+                    ));
+                    tasks.push(self.open_buffer(
+                        buffer,
+                        config.actions.buffer.local,
+                        clients,
+                        config,
+                    ));
+                }
+            }
+            buffer::context_menu::Event::CopyHostmask(hostmask) => {
+                tasks.push(clipboard::write(hostmask));
+            }
+            buffer::context_menu::Event::RequestHostmask(server, nick) => {
+                clients.request_hostmask(&server, nick);
+            }
+            buffer::context_menu::Event::ToggleMonitor(server, nick) => {
+                let casemapping = clients.get_casemapping(&server);
+
+                // Nicknames from the server configuration are monitored
+                // again when reconnecting
+                if clients.is_monitored(&server, nick.as_nickref()) {
+                    self.monitored.remove(
+                        &server,
+                        nick.as_nickref(),
+                        casemapping,
+                    );
+                    clients.unmonitor(&server, nick.as_nickref());
+                } else {
+                    self.monitored.insert(
+                        &server,
+                        nick.as_nickref(),
+                        casemapping,
+                    );
+                    clients.monitor(&server, nick);
+                }
+
+                self.last_changed = Some(Instant::now());
+            }
+            buffer::context_menu::Event::CtcpRequest(
+                command,
+                server,
+                nick,
+                params,
+            ) => {
+                let buffer = upstream.clone().unwrap_or_else(|| {
+                    buffer::Upstream::Server(server.clone())
+                });
+
+                let command =
+                    command::Irc::Ctcp(command, nick.to_string(), params);
+
+                let input = data::Input::command(buffer.clone(), command);
+
+                if let Some(encoded) = input.encoded() {
+                    clients.send(&input.buffer, encoded, TokenPriority::High);
+                }
+            }
+            buffer::context_menu::Event::RunCommand(
+                server,
+                channel,
+                command,
+            ) => {
+                let buffer = channel.map_or_else(
+                    || {
+                        upstream.clone().unwrap_or_else(|| {
+                            buffer::Upstream::Server(server.clone())
+                        })
+                    },
+                    |channel| {
+                        buffer::Upstream::Channel(server.clone(), channel)
+                    },
+                );
+
+                // Only IRC commands are supported from the context menu
+                let Ok(data::input::Parsed::Input(inputs, _)) =
+                    data::input::parse(
+                        buffer.clone(),
+                        config.buffer.text_input.auto_format,
+                        &command,
+                        clients.nickname(&server),
+                        &clients.get_isupport(&server),
+                        config.buffer.commands.server_limits,
+                        &config.aliases,
+                    )
+                else {
+                    log::warn!("invalid context menu command: {command}");
+                    return (Task::batch(tasks), None);
+                };
+
+                for input in &inputs {
+                    if let Some(encoded) = input.encoded() {
+                        clients.send(
+                            &input.buffer,
+                            encoded,
+                            TokenPriority::User,
+                        );
+                    }
+                }
+
+                if let Some(nick) = clients.nickname(&server) {
+                    let mut user = nick.to_owned().into();
+                    let mut channel_users = None;
+                    let chantypes = clients.get_chantypes(&server);
+                    let statusmsg = clients.get_statusmsg(&server);
+                    let casemapping = clients.get_casemapping(&server);
+
+                    // Resolve our attributes if sending this message in a channel
+                    if let buffer::Upstream::Channel(server, channel) = &buffer
+                    {
+                        channel_users =
+                            clients.get_channel_users(server, channel);
+
+                        if let Some(user_with_attributes) = clients
+                            .resolve_user_attributes(server, channel, &user)
+                        {
+                            user = user_with_attributes.clone();
+                        }
+                    }
+
+                    for input in inputs {
+                        tasks.extend(
+                            self.history
+                                .record_input_message(
+                                    input,
+                                    user.clone(),
+                                    channel_users,
+                                    chantypes,
+                                    statusmsg,
+                                    casemapping,
+                                    config,
+                                )
+                                .into_iter()
+                                .map(|task| {
+                                    Task::perform(task, Message::History)
+                                }),
+                        );
+                    }
+                }
+            }
+            buffer::context_menu::Event::InsertInput(buffer, text) => {
+                self.history.record_text(data::input::RawInput {
+                    buffer: buffer.clone(),
+                    text: text.clone(),
+                });
+                self.history.record_draft(data::input::RawInput {
+                    buffer: buffer.clone(),
+                    text,
+                });
+
+                tasks.push(self.open_buffer(
+                    data::Buffer::Upstream(buffer),
+                    BufferAction::NewPane,
+                    clients,
+                    config,
+                ));
+            }
+            buffer::context_menu::Event::LeaveBuffer(buffer) => {
+                let (task, _) = self.leave_buffer(clients, config, buffer);
+
+                tasks.push(task);
+            }
+            buffer::context_menu::Event::CloseBuffer(buffer) => {
+                for (window, pane) in self
+                    .panes
+                    .find_all_by_buffer(&data::Buffer::Upstream(buffer))
+                {
+                    tasks.push(self.close_pane(clients, config, window, pane));
+                }
+            }
+            buffer::context_menu::Event::OpenLogFile(buffer) => {
+                if let Some(kind) =
+                    history::Kind::from_buffer(data::Buffer::Upstream(buffer))
+                {
+                    tasks.push(
+                        Task::future(async move {
+                            match history::path(&kind).await {
+                                Ok(path) => {
+                                    let _ = open::that_detached(path);
+                                }
+                                Err(error) => {
+                                    log::warn!(
+                                        "failed to open log file: {error}"
+                                    );
+                                }
+                            }
+                        })
+                        .discard(),
+                    );
+                }
+            }
+            buffer::context_menu::Event::CopyChannelName(channel) => {
+                tasks.push(clipboard::write(channel.to_string()));
+            }
+            // Handled by the channel buffer, which owns the topic editor
+            buffer::context_menu::Event::EditTopic(_) => {}
+            buffer::context_menu::Event::CopyTimestamp(date_time, format) => {
+                if let Some(format) = format {
+                    tasks.push(clipboard::write(i18n::format_date(
+                        &date_time.with_timezone(&Local),
+                        &format,
+                    )));
+                } else {
+                    tasks.push(clipboard::write(
+                        date_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    ));
+                }
+            }
+            buffer::context_menu::Event::CopyMessage(text) => {
+                tasks.push(clipboard::write(text));
+            }
+            buffer::context_menu::Event::CopySender(nick) => {
+                tasks.push(clipboard::write(nick.to_string()));
+            }
+            buffer::context_menu::Event::MessageDetails(message) => {
+                dashboard_event = Some(Event::MessageDetails(message));
+            }
+            buffer::context_menu::Event::Share(message) => {
+                let kind = pane
+                    .and_then(|pane| self.panes.get(window, pane))
+                    .and_then(Pane::resource)
+                    .map(|resource| resource.kind);
+
+                let casemapping = clients.get_casemapping_or_default(
+                    kind.as_ref().and_then(history::Kind::server),
+                );
+
+                if let Some(view) = kind.and_then(|kind| {
+                    self.history.get_messages(&kind, None, &config.buffer)
+                }) {
+                    let messages = view
+                        .old_messages
+                        .into_iter()
+                        .chain(view.new_messages)
+                        .collect::<Vec<_>>();
+
+                    if let Some((messages, index)) =
+                        data::share::context(&messages, &message)
+                    {
+                        dashboard_event =
+                            Some(Event::Share(messages, index, casemapping));
+                    }
+                }
+            }
+            buffer::context_menu::Event::ChannelInfo(server, channel) => {
+                dashboard_event = Some(Event::ChannelInfo(server, channel));
+            }
+            buffer::context_menu::Event::AccessList(server, channel) => {
+                dashboard_event = Some(Event::AccessList(server, channel));
+            }
+            buffer::context_menu::Event::QuoteMessage(nick, quote) => {
+                tasks.push(self.edit_pane_input(window, pane, |text| {
+                    let quote = format!("> <{nick}> {quote} ");
+
+                    if text.is_empty() || text.ends_with(' ') {
+                        format!("{text}{quote}")
+                    } else {
+                        format!("{text} {quote}")
+                    }
+                }));
+            }
+            buffer::context_menu::Event::Reply(nick) => {
+                tasks.push(self.edit_pane_input(window, pane, |text| {
+                    format!("{nick}: {text}")
+                }));
+            }
+            buffer::context_menu::Event::ReplyStatusMessage(target) => {
+                tasks.push(self.edit_pane_input(window, pane, |text| {
+                    format!("/msg {target} {text}")
+                }));
+            }
+        }
+
+        (Task::batch(tasks), dashboard_event)
+    }
+
+    fn edit_pane_input(
+        &mut self,
+        window: window::Id,
+        pane: Option<pane_grid::Pane>,
+        edit: impl FnOnce(&str) -> String,
+    ) -> Task<Message> {
+        let Some((id, state)) = pane.and_then(|id| {
+            self.panes.get_mut(window, id).map(|state| (id, state))
+        }) else {
+            return Task::none();
+        };
+
+        state
+            .buffer
+            .edit_input(&mut self.history, edit)
+            .map(move |message| {
+                Message::Pane(window, pane::Message::Buffer(id, message))
+            })
+    }
+
     pub fn handle_event(
         &mut self,
         window: window::Id,
@@ -1931,7 +2612,12 @@ impl Dashboard {
             *theme = theme.selected();
             window::close(editor.window)
         } else {
-            let (editor, task) = ThemeEditor::open(main_window, config);
+            let (editor, task) = ThemeEditor::open(
+                main_window,
+                self.popout_windows
+                    .get(data::window::Auxiliary::ThemeEditor),
+                config,
+            );
 
             self.theme_editor = Some(editor);
 
@@ -1970,6 +2656,41 @@ impl Dashboard {
         buffer_action: BufferAction,
         clients: &mut data::client::Map,
         config: &Config,
+    ) -> Task<Message> {
+        let open = self.panes.find_by_buffer(&buffer, self.focus);
+
+        match (buffer_action, open) {
+            // If buffer already is open, we swap it with focused pane.
+            (BufferAction::ReplacePane, Some((window, id))) => {
+                self.last_changed = Some(Instant::now());
+
+                if window != self.focus.window || id != self.focus.pane {
+                    return self.swap_pane_with_focus(window, id);
+                } else {
+                    return Task::none();
+                }
+            }
+            // If buffer already is open, we focus it.
+            (BufferAction::NewPane, Some((window, id))) => {
+                self.last_changed = Some(Instant::now());
+                self.focus = Focus { window, pane: id };
+
+                return self.focus_pane(window, id);
+            }
+            _ => {}
+        }
+
+        self.open_additional_buffer(buffer, buffer_action, clients, config)
+    }
+
+    /// Opens `buffer` even if it's already open, each pane showing it
+    /// keeping its own scroll position.
+    fn open_additional_buffer(
+        &mut self,
+        buffer: data::Buffer,
+        buffer_action: BufferAction,
+        clients: &mut data::client::Map,
+        config: &Config,
     ) -> Task<Message> {
         let panes = self.panes.clone();
 
@@ -1977,28 +2698,18 @@ impl Dashboard {
 
         match buffer_action {
             BufferAction::ReplacePane => {
-                // If buffer already is open, we swap it with focused pane.
-                for (window, id, pane) in panes.iter() {
-                    if pane.buffer.data().as_ref() == Some(&buffer) {
-                        if window != self.focus.window || id != self.focus.pane
-                        {
-                            return self.swap_pane_with_focus(window, id);
-                        } else {
-                            return Task::none();
-                        }
-                    }
-                }
-
                 let Focus { window, pane } = self.focus;
 
-                if let Some(state) = self.panes.get_mut(window, pane) {
-                    mark_as_read_on_buffer_close(
-                        &state.buffer,
-                        &mut self.history,
-                        clients,
-                        config,
-                    );
+                mark_as_read_on_pane_close(
+                    &self.panes,
+                    window,
+                    pane,
+                    &mut self.history,
+                    clients,
+                    config,
+                );
 
+                if let Some(state) = self.panes.get_mut(window, pane) {
                     state.buffer =
                         Buffer::from_data(buffer, state.size, config);
                     self.last_changed = Some(Instant::now());
@@ -2013,15 +2724,6 @@ impl Dashboard {
                 }
             }
             BufferAction::NewPane => {
-                // If buffer already is open, we focus it.
-                for (window, id, pane) in panes.iter() {
-                    if pane.buffer.data().as_ref() == Some(&buffer) {
-                        self.focus = Focus { window, pane: id };
-
-                        return self.focus_pane(window, id);
-                    }
-                }
-
                 // If we only have one pane, and its empty, we replace it.
                 if self.panes.len() == 1 {
                     for (id, pane) in panes.main.iter() {
@@ -2109,37 +2811,64 @@ impl Dashboard {
 
                 Task::none()
             }
-            BufferAction::NewWindow => {
-                iced::window::position(self.main_window()).then({
-                    let pane = Pane::new(Buffer::from_data(
-                        buffer.clone(),
-                        Size::default(),
-                        config,
-                    ));
+            BufferAction::NewWindow => self
+                .open_popout(buffer, config)
+                .map(|(window, pane)| Message::NewWindow(window, pane)),
+        }
+    }
 
-                    let config = config.clone();
-                    move |main_window_position| {
-                        let (_, task) = window::open(window::Settings {
-                            // Just big enough to show all components in combobox
-                            position: main_window_position
-                                .map(|point| {
-                                    window::Position::Specific(
-                                        point + Vector::new(20.0, 20.0),
-                                    )
-                                })
-                                .unwrap_or_default(),
-                            exit_on_close_request: false,
-                            ..window::settings(&config)
-                        });
+    /// Opens a window for `buffer`, where it was last popped out to if
+    /// known.
+    fn open_popout(
+        &mut self,
+        buffer: data::Buffer,
+        config: &Config,
+    ) -> Task<(window::Id, Pane)> {
+        let saved = self.popout_windows.get(&buffer);
+
+        // The config only applies to windows without a saved level, which
+        // is recorded so toggling starts from the level the window has
+        let is_always_on_top = saved
+            .as_ref()
+            .map_or(config.always_on_top, |saved| saved.is_always_on_top);
+        self.popout_windows
+            .set_always_on_top(&buffer, is_always_on_top);
+
+        iced::window::position(self.main_window()).then({
+            let pane =
+                Pane::new(Buffer::from_data(buffer, Size::default(), config));
+
+            let config = config.clone();
+            move |main_window_position| {
+                let settings = window::settings(&config);
 
-                        task.map({
-                            let pane = pane.clone();
-                            move |id| Message::NewWindow(id, pane.clone())
+                let (_, task) = window::open(window::Settings {
+                    // Reopen where the buffer was last popped out to,
+                    // otherwise offset from the main window
+                    position: saved
+                        .as_ref()
+                        .and_then(|saved| saved.position)
+                        .or_else(|| {
+                            main_window_position
+                                .map(|point| point + Vector::new(20.0, 20.0))
                         })
-                    }
+                        .map(window::Position::Specific)
+                        .unwrap_or_default(),
+                    size: saved
+                        .as_ref()
+                        .map_or(settings.size, |saved| saved.size),
+                    level: window::level(is_always_on_top),
+                    min_size: Some(window::MIN_SIZE),
+                    exit_on_close_request: false,
+                    ..settings
+                });
+
+                task.map({
+                    let pane = pane.clone();
+                    move |id| (id, pane.clone())
                 })
             }
-        }
+        })
     }
 
     pub fn leave_buffer(
@@ -2148,14 +2877,13 @@ impl Dashboard {
         config: &Config,
         buffer: buffer::Upstream,
     ) -> (Task<Message>, Option<Event>) {
-        let open = self.panes.iter().find_map(|(window, pane, state)| {
-            (state.buffer.upstream() == Some(&buffer)).then_some((window, pane))
-        });
-
         let mut tasks = vec![];
 
-        // Close pane
-        if let Some((window, pane)) = open {
+        // Close every pane showing the buffer
+        for (window, pane) in self
+            .panes
+            .find_all_by_buffer(&data::Buffer::Upstream(buffer.clone()))
+        {
             tasks.push(self.close_pane(clients, config, window, pane));
         }
 
@@ -2194,6 +2922,20 @@ impl Dashboard {
                 // No PART to send, just close history
                 (Task::batch(tasks), None)
             }
+            buffer::Upstream::ServerCategory(server, category) => {
+                tasks.push(
+                    self.history
+                        .close(
+                            history::Kind::ServerCategory(server, category),
+                            clients,
+                        )
+                        .map_or_else(Task::none, |task| {
+                            Task::perform(task, Message::History)
+                        }),
+                );
+
+                (Task::batch(tasks), None)
+            }
         }
     }
 
@@ -2205,16 +2947,20 @@ impl Dashboard {
         target: Target,
         reason: Option<String>,
     ) -> Task<Message> {
-        let open = self.panes.iter().find_map(|(window, pane, state)| {
-            (state.buffer.server() == Some(server.clone())
-                && state.buffer.target() == Some(target.clone()))
-            .then_some((window, pane))
-        });
+        let open = self
+            .panes
+            .iter()
+            .filter(|(_, _, state)| {
+                state.buffer.server() == Some(server.clone())
+                    && state.buffer.target() == Some(target.clone())
+            })
+            .map(|(window, pane, _)| (window, pane))
+            .collect::<Vec<_>>();
 
         let mut tasks = vec![];
 
-        // Close pane
-        if let Some((window, pane)) = open {
+        // Close every pane showing the target
+        for (window, pane) in open {
             tasks.push(self.close_pane(clients, config, window, pane));
         }
 
@@ -2277,6 +3023,134 @@ impl Dashboard {
         }
     }
 
+    /// Loads the scripts folder, replacing the scripts running so far.
+    pub fn load_scripts(&self) -> Task<Message> {
+        Task::perform(script::load(), Message::ScriptsLoaded)
+    }
+
+    /// Runs the message hooks of scripts on a received `message`, which is
+    /// `None` once a script swallowed it.
+    pub fn run_message_hooks(
+        &self,
+        server: &Server,
+        message: data::Message,
+    ) -> Option<data::Message> {
+        self.scripts.on_message(server, message)
+    }
+
+    /// Tasks carrying out what scripts asked for while their hooks and
+    /// commands ran.
+    pub fn script_actions(&self) -> Task<Message> {
+        Task::batch(
+            self.scripts
+                .take_actions()
+                .into_iter()
+                .map(|action| Task::done(Message::Script(action))),
+        )
+    }
+
+    fn run_script_action(
+        &mut self,
+        action: script::Action,
+        clients: &mut data::client::Map,
+        config: &Config,
+    ) -> Task<Message> {
+        // Actions act on the buffer of the hook they were asked for from,
+        // unless they name another target on its server
+        let resolve = |buffer: buffer::Upstream, target: Option<String>| {
+            let Some(target) = target else {
+                return buffer;
+            };
+            let server = buffer.server().clone();
+
+            match Target::parse(
+                &target,
+                clients.get_chantypes(&server),
+                clients.get_statusmsg(&server),
+                clients.get_casemapping(&server),
+            ) {
+                Target::Channel(channel) => {
+                    buffer::Upstream::Channel(server, channel)
+                }
+                Target::Query(query) => buffer::Upstream::Query(server, query),
+            }
+        };
+
+        match action {
+            script::Action::Send {
+                buffer,
+                target,
+                text,
+            } => {
+                let buffer = resolve(buffer, target);
+
+                match data::input::parse(
+                    buffer.clone(),
+                    config.buffer.text_input.auto_format,
+                    &text,
+                    clients.nickname(buffer.server()),
+                    &clients.get_isupport(buffer.server()),
+                    config.buffer.commands.server_limits,
+                    &config.aliases,
+                ) {
+                    Ok(data::input::Parsed::Input(inputs, _)) => {
+                        buffer::send_inputs(
+                            inputs,
+                            &buffer,
+                            clients,
+                            &mut self.history,
+                            config,
+                        )
+                        .map(Message::History)
+                    }
+                    // Only messages and IRC commands can be sent by scripts
+                    Ok(data::input::Parsed::Internal(_)) => {
+                        log::warn!("script can't run {text}");
+
+                        Task::none()
+                    }
+                    Err(error) => {
+                        log::warn!("script input not sent: {error}");
+
+                        Task::none()
+                    }
+                }
+            }
+            script::Action::Print {
+                buffer,
+                target,
+                text,
+            } => {
+                let buffer = resolve(buffer, target);
+                let message = data::Message::local(
+                    &buffer,
+                    message::Source::Server(None),
+                    message::plain(text),
+                );
+
+                self.record_message(
+                    buffer.server(),
+                    clients.get_casemapping(buffer.server()),
+                    message,
+                    &config.buffer,
+                )
+            }
+            script::Action::Notify {
+                server,
+                title,
+                body,
+            } => {
+                self.notifications.notify(
+                    &config.notifications,
+                    &Notification::Script { title, body },
+                    &server,
+                );
+
+                Task::none()
+            }
+        }
+    }
+
     pub fn record_log(&mut self, record: data::log::Record) -> Task<Message> {
         if let Some(task) = self.history.record_log(record) {
             Task::perform(task, Message::History)
@@ -2402,6 +3276,20 @@ impl Dashboard {
             .block_message(message, kind, casemapping, buffer_config);
     }
 
+    pub fn mark_all_as_read(&mut self, clients: &mut data::client::Map) {
+        for kind in self.history.kinds() {
+            mark_as_read(kind, &mut self.history, clients, TokenPriority::User);
+        }
+    }
+
+    pub fn has_unread(&self) -> bool {
+        self.history.any_unread()
+    }
+
+    pub fn has_highlight(&self) -> bool {
+        self.history.any_highlight()
+    }
+
     pub fn update_read_marker(
         &mut self,
         kind: impl Into<history::Kind> + 'static,
@@ -2439,6 +3327,16 @@ impl Dashboard {
         }
     }
 
+    pub fn record_backfill(
+        &mut self,
+        server: Server,
+        target: Target,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) {
+        self.history.record_backfill(server, target, start, end);
+    }
+
     pub fn load_chathistory_targets_timestamp(
         &self,
         clients: &data::client::Map,
@@ -2461,6 +3359,102 @@ impl Dashboard {
             .map(|task| Task::perform(task, Message::Client))
     }
 
+    pub fn rename_channel(
+        &mut self,
+        server: &Server,
+        old: target::Channel,
+        new: target::Channel,
+        clients: &data::client::Map,
+    ) -> Task<Message> {
+        let old_buffer = buffer::Upstream::Channel(server.clone(), old.clone());
+
+        for (_, _, pane) in self.panes.iter_mut() {
+            if let Buffer::Channel(state) = &mut pane.buffer
+                && state.buffer == old_buffer
+            {
+                state.rename(new.clone());
+
+                self.last_changed = Some(Instant::now());
+            }
+        }
+
+        Task::perform(
+            self.history.rename_channel(server, old, new, clients),
+            Message::History,
+        )
+    }
+
+    /// Follows the buffers of `server` after its `CASEMAPPING` changed,
+    /// closing panes that now show the same target as another pane
+    pub fn renormalize_targets(
+        &mut self,
+        server: &Server,
+        clients: &data::client::Map,
+    ) -> Task<Message> {
+        let casemapping = clients.get_casemapping(server);
+
+        let mut open = HashSet::new();
+        let mut duplicates = vec![];
+
+        for (window, pane, state) in self.panes.iter_mut() {
+            let renamed = match &mut state.buffer {
+                Buffer::Channel(channel) if channel.server == *server => {
+                    let mut target = channel.target.clone();
+                    target.renormalize(casemapping);
+
+                    let renamed = target.as_normalized_str()
+                        != channel.target.as_normalized_str();
+                    channel.rename(target);
+
+                    renamed
+                }
+                Buffer::Query(query) if query.server == *server => {
+                    let mut target = query.target.clone();
+                    target.renormalize(casemapping);
+
+                    let renamed = target.as_normalized_str()
+                        != query.target.as_normalized_str();
+                    query.rename(target);
+
+                    renamed
+                }
+                _ => continue,
+            };
+
+            if renamed {
+                self.last_changed = Some(Instant::now());
+            }
+
+            if let Some(buffer) = state.buffer.upstream()
+                && !open.insert(buffer.clone())
+            {
+                duplicates.push((window, pane));
+            }
+        }
+
+        let history = self
+            .history
+            .renormalize_targets(server, clients)
+            .into_iter()
+            .map(|task| Task::perform(task, Message::History));
+
+        let close = duplicates.into_iter().map(|(window, pane)| {
+            Task::done(Message::Sidebar(sidebar::Message::Close(window, pane)))
+        });
+
+        Task::batch(history.chain(close))
+    }
+
+    pub fn save_channel_keys(
+        &self,
+        clients: &data::client::Map,
+        server: &Server,
+    ) -> Option<Task<Message>> {
+        clients
+            .save_channel_keys(server)
+            .map(|task| Task::perform(task, Message::Client))
+    }
+
     pub fn get_focused(&self) -> Option<(window::Id, pane_grid::Pane, &Pane)> {
         let Focus { window, pane } = self.focus;
         self.panes
@@ -2468,6 +3462,38 @@ impl Dashboard {
             .map(|state| (window, pane, state))
     }
 
+    /// Queries open in any pane, whose peers' presence is tracked.
+    pub fn open_queries(&self) -> Vec<(data::Server, target::Query)> {
+        self.panes
+            .iter()
+            .filter_map(|(_, _, pane)| match &pane.buffer {
+                Buffer::Query(state) => {
+                    Some((state.server.clone(), state.target.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Channels open in any pane, which are preferred when WHO polling.
+    pub fn open_channels(&self) -> Vec<(data::Server, target::Channel)> {
+        self.panes
+            .iter()
+            .filter_map(|(_, _, pane)| match &pane.buffer {
+                Buffer::Channel(state) => {
+                    Some((state.server.clone(), state.target.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Server of the focused buffer, if it belongs to one.
+    pub fn focused_server(&self) -> Option<data::Server> {
+        self.get_focused()
+            .and_then(|(_, _, pane)| pane.buffer.server())
+    }
+
     fn get_focused_mut(
         &mut self,
     ) -> Option<(window::Id, pane_grid::Pane, &mut Pane)> {
@@ -2580,6 +3606,25 @@ impl Dashboard {
         self.panes.main.maximized().is_some()
     }
 
+    /// Shows only a single pane of the main window while in mini mode,
+    /// restoring the other panes when leaving it.
+    pub fn set_mini_mode(&mut self, is_mini: bool) {
+        if is_mini {
+            let pane = if self.focus.window == self.main_window() {
+                Some(self.focus.pane)
+            } else {
+                self.panes.main.iter().next().map(|(pane, _)| *pane)
+            };
+
+            if let Some(pane) = pane.filter(|_| !self.is_pane_maximized()) {
+                self.panes.main.maximize(pane);
+                self.mini_maximized = true;
+            }
+        } else if std::mem::take(&mut self.mini_maximized) {
+            self.panes.main.restore();
+        }
+    }
+
     fn new_pane(&mut self, axis: pane_grid::Axis) -> Task<Message> {
         if self.focus.window == self.main_window() {
             // If there is any focused pane on main window, split it
@@ -2644,14 +3689,14 @@ impl Dashboard {
         window: window::Id,
         pane: pane_grid::Pane,
     ) -> Task<Message> {
-        if let Some(state) = self.panes.get(window, pane) {
-            mark_as_read_on_buffer_close(
-                &state.buffer,
-                &mut self.history,
-                clients,
-                config,
-            );
-        }
+        mark_as_read_on_pane_close(
+            &self.panes,
+            window,
+            pane,
+            &mut self.history,
+            clients,
+            config,
+        );
 
         self.last_changed = Some(Instant::now());
 
@@ -2680,10 +3725,15 @@ impl Dashboard {
 
     fn popout_pane(
         &mut self,
+        Focus { window, pane }: Focus,
         clients: &mut data::client::Map,
         config: &Config,
     ) -> Task<Message> {
-        let Focus { pane, .. } = self.focus;
+        // Popouts are only a single pane, so only main window panes can be
+        // popped out
+        if window != self.main_window() {
+            return Task::none();
+        }
 
         self.focus_history = self
             .focus_history
@@ -2794,10 +3844,17 @@ impl Dashboard {
         &mut self,
         now: Instant,
         clients: &data::client::Map,
+        config: &Config,
     ) -> Task<Message> {
+        if let Some(kind) = self.get_focused().and_then(|(_, _, pane)| {
+            pane.buffer.data().and_then(history::Kind::from_buffer)
+        }) {
+            self.history.viewed(kind, now.into());
+        }
+
         let history = Task::batch(
             self.history
-                .tick(now.into(), clients)
+                .tick(now.into(), clients, config.buffer.history.memory_limit)
                 .into_iter()
                 .map(|task| Task::perform(task, Message::History))
                 .collect::<Vec<_>>(),
@@ -2811,7 +3868,10 @@ impl Dashboard {
             self.last_changed = None;
 
             return Task::batch(vec![
-                Task::perform(dashboard.save(), Message::DashboardSaved),
+                Task::perform(
+                    dashboard.save(self.profile.clone()),
+                    Message::DashboardSaved,
+                ),
                 history,
             ]);
         }
@@ -2949,8 +4009,88 @@ impl Dashboard {
         Task::batch(tasks)
     }
 
+    pub fn receive_dcc_chat(
+        &mut self,
+        server: &Server,
+        casemapping: isupport::CaseMap,
+        request: dcc_chat::ReceiveRequest,
+        clients: &mut data::client::Map,
+        config: &Config,
+    ) -> Task<Message> {
+        let query = target::Query::from(&request.from);
+
+        self.notifications.notify(
+            &config.notifications,
+            &Notification::DccChatRequest {
+                nick: request.from.nickname().to_owned(),
+            },
+            server,
+        );
+
+        let event = self.dcc_chats.receive(request, config);
+
+        let dcc_chat::manager::Event::NewChat(ref chat, _) = event;
+        let buffer = data::Buffer::DccChat(chat.id);
+
+        Task::batch([
+            self.handle_dcc_chat_event(
+                server,
+                casemapping,
+                &query,
+                event,
+                &config.buffer,
+            ),
+            // Opened for the offer to be accepted or declined
+            self.open_buffer(
+                buffer,
+                config.actions.buffer.local,
+                clients,
+                config,
+            ),
+        ])
+    }
+
+    fn handle_dcc_chat_event(
+        &mut self,
+        server: &Server,
+        casemapping: isupport::CaseMap,
+        query: &target::Query,
+        event: dcc_chat::manager::Event,
+        buffer_config: &config::Buffer,
+    ) -> Task<Message> {
+        match event {
+            dcc_chat::manager::Event::NewChat(chat, task) => {
+                let message = match chat.direction {
+                    dcc_chat::Direction::Received => {
+                        data::Message::dcc_chat_request_received(
+                            &chat.remote_user,
+                            query,
+                        )
+                    }
+                    dcc_chat::Direction::Sent => {
+                        data::Message::dcc_chat_request_sent(
+                            &chat.remote_user,
+                            query,
+                        )
+                    }
+                };
+
+                Task::batch(vec![
+                    self.record_message(
+                        server,
+                        casemapping,
+                        message,
+                        buffer_config,
+                    ),
+                    Task::run(task, Message::DccChat),
+                ])
+            }
+        }
+    }
+
     fn from_data(
         data: data::Dashboard,
+        profile: data::Profile,
         config: &Config,
         main_window: &Window,
     ) -> (Self, Task<Message>) {
@@ -2976,13 +4116,14 @@ impl Dashboard {
                         b: Box::new(configuration(*b, config)),
                     }
                 }
-                data::Pane::Buffer { buffer } => {
-                    Configuration::Pane(Pane::new(Buffer::from_data(
+                data::Pane::Buffer { buffer, density } => Configuration::Pane(
+                    Pane::new(Buffer::from_data(
                         buffer,
                         Size::default(),
                         config,
-                    )))
-                }
+                    ))
+                    .with_density(density),
+                ),
                 data::Pane::Empty => {
                     Configuration::Pane(Pane::new(Buffer::empty()))
                 }
@@ -2997,22 +4138,28 @@ impl Dashboard {
             popout: HashMap::new(),
         };
 
-        let focus = panes
-            .iter()
-            // This should never fail
-            .find_map(|(window, pane, state)| {
-                (state.buffer.data() == data.focus_buffer)
-                    .then_some(Focus { window, pane })
+        let find_main_pane = |buffer: Option<&data::Buffer>| {
+            panes.main.iter().find_map(|(pane, state)| {
+                (state.buffer.data().as_ref() == buffer).then_some(*pane)
             })
+        };
+
+        // The focused buffer may be popped out, in which case its window
+        // takes focus once reopened
+        let main_focus = data
+            .main_focus_buffer
+            .as_ref()
+            .and_then(|buffer| find_main_pane(Some(buffer)))
+            .or_else(|| find_main_pane(data.focus_buffer.as_ref()))
+            // This should never fail
+            .or_else(|| panes.main.iter().next().map(|(pane, _)| *pane))
             // But if somehow it does, we just focus the "first" pane from the main window
-            .unwrap_or_else(|| {
-                let (_, pane) = pane_grid::State::new(());
+            .unwrap_or_else(|| pane_grid::State::new(()).1);
 
-                Focus {
-                    window: main_window.id,
-                    pane,
-                }
-            });
+        let focus = Focus {
+            window: main_window.id,
+            pane: main_focus,
+        };
 
         let mut dashboard = Self {
             panes,
@@ -3023,10 +4170,22 @@ impl Dashboard {
             last_changed: None,
             command_bar: None,
             file_transfers: file_transfer::Manager::default(),
+            dcc_chats: dcc_chat::Manager::default(),
             theme_editor: None,
             notifications: notification::Notifications::new(config),
             previews: preview::Collection::default(),
+            show_texture_usage: false,
+            send_file_dialog: None,
+            import_theme_dialog: None,
+            clipboard_write: None,
             buffer_settings: data.buffer_settings.clone(),
+            monitored: data.monitored.clone(),
+            pinned: data.pinned.clone(),
+            popout_windows: data.popout_windows.clone(),
+            mini_maximized: false,
+            pane_drag: None,
+            profile,
+            scripts: script::Manager::default(),
         };
 
         let mut tasks = vec![];
@@ -3038,17 +4197,22 @@ impl Dashboard {
             };
 
             if let Some(buffer) = pane.buffer.data() {
-                tasks.push(dashboard.open_buffer(
-                    buffer,
-                    BufferAction::NewWindow,
-                    &mut data::client::Map::default(),
-                    config,
-                ));
+                let is_focused = data.focus_buffer.as_ref() == Some(&buffer);
+                let task = dashboard.open_popout(buffer, config);
+
+                tasks.push(if is_focused {
+                    task.map(|(window, pane)| Message::NewWindow(window, pane))
+                } else {
+                    task.map(|(window, pane)| {
+                        Message::PopoutRestored(window, pane)
+                    })
+                });
             }
         }
 
-        let tasks = Task::batch(tasks)
-            .chain(dashboard.focus_pane(focus.window, focus.pane));
+        let tasks = dashboard
+            .focus_pane(focus.window, focus.pane)
+            .chain(Task::batch(tasks));
 
         (dashboard, tasks)
     }
@@ -3076,10 +4240,27 @@ impl Dashboard {
                 window::Event::Focused => {
                     return self.focus_window_pane(id);
                 }
-                window::Event::Moved(_)
-                | window::Event::Resized(_)
-                | window::Event::Unfocused
-                | window::Event::Opened { .. } => {}
+                window::Event::Moved(position) => {
+                    if let Some(buffer) = self.popout_buffer(id) {
+                        self.popout_windows.moved(&buffer, position);
+                        self.last_changed = Some(Instant::now());
+                    }
+                }
+                window::Event::Resized(size) => {
+                    if let Some(buffer) = self.popout_buffer(id) {
+                        self.popout_windows.resized(&buffer, size);
+                        self.last_changed = Some(Instant::now());
+                    }
+                }
+                window::Event::Opened { position, size } => {
+                    if let Some(buffer) = self.popout_buffer(id) {
+                        if let Some(position) = position {
+                            self.popout_windows.moved(&buffer, position);
+                        }
+                        self.popout_windows.resized(&buffer, size);
+                    }
+                }
+                window::Event::Unfocused => {}
             }
         } else if self.theme_editor.as_ref().is_some_and(|e| e.window == id) {
             match event {
@@ -3089,17 +4270,67 @@ impl Dashboard {
                         return window::close(editor.window);
                     }
                 }
-                window::Event::Moved(_)
-                | window::Event::Resized(_)
-                | window::Event::Focused
-                | window::Event::Unfocused
-                | window::Event::Opened { .. } => {}
+                window::Event::Moved(position) => {
+                    self.popout_windows
+                        .moved(data::window::Auxiliary::ThemeEditor, position);
+                    self.last_changed = Some(Instant::now());
+                }
+                window::Event::Resized(size) => {
+                    self.popout_windows
+                        .resized(data::window::Auxiliary::ThemeEditor, size);
+                    self.last_changed = Some(Instant::now());
+                }
+                window::Event::Opened { position, size } => {
+                    if let Some(position) = position {
+                        self.popout_windows.moved(
+                            data::window::Auxiliary::ThemeEditor,
+                            position,
+                        );
+                    }
+                    self.popout_windows
+                        .resized(data::window::Auxiliary::ThemeEditor, size);
+                }
+                window::Event::Focused | window::Event::Unfocused => {}
             }
         }
 
         Task::none()
     }
 
+    /// Toggles whether the focused window is kept above other windows. The
+    /// main window's state is kept by the application, so only popouts are
+    /// toggled here.
+    fn toggle_always_on_top(&mut self) -> (Task<Message>, Option<Event>) {
+        let window = self.focus.window;
+
+        if window == self.main_window() {
+            return (Task::none(), Some(Event::ToggleAlwaysOnTop));
+        }
+
+        let Some(buffer) = self.popout_buffer(window) else {
+            return (Task::none(), None);
+        };
+
+        let is_always_on_top = !self
+            .popout_windows
+            .get(&buffer)
+            .is_some_and(|saved| saved.is_always_on_top);
+
+        self.popout_windows
+            .set_always_on_top(&buffer, is_always_on_top);
+        self.last_changed = Some(Instant::now());
+
+        (window::set_always_on_top(window, is_always_on_top), None)
+    }
+
+    fn popout_buffer(&self, window: window::Id) -> Option<data::Buffer> {
+        self.panes
+            .popout
+            .get(&window)
+            .and_then(|state| state.iter().next())
+            .and_then(|(_, pane)| pane.buffer.data())
+    }
+
     pub fn preview_theme_in_editor(
         &mut self,
         styles: theme::Styles,
@@ -3112,7 +4343,12 @@ impl Dashboard {
         if let Some(editor) = &self.theme_editor {
             window::gain_focus(editor.window)
         } else {
-            let (editor, task) = ThemeEditor::open(main_window, config);
+            let (editor, task) = ThemeEditor::open(
+                main_window,
+                self.popout_windows
+                    .get(data::window::Auxiliary::ThemeEditor),
+                config,
+            );
 
             self.theme_editor = Some(editor);
 
@@ -3152,11 +4388,12 @@ impl Dashboard {
         let history = self.history.exit(clients);
         let last_changed = self.last_changed.take();
         let dashboard = data::Dashboard::from(&*self);
+        let profile = self.profile.clone();
 
         Task::perform(
             async move {
                 if last_changed.is_some() {
-                    match dashboard.save().await {
+                    match dashboard.save(profile).await {
                         Ok(()) => {
                             log::debug!("dashboard saved");
                         }
@@ -3251,12 +4488,28 @@ fn mark_as_read(
     }
 }
 
-fn mark_as_read_on_buffer_close(
-    buffer: &Buffer,
+/// Marks the buffer of `pane` as read as it's closed, unless another pane
+/// still shows it.
+fn mark_as_read_on_pane_close(
+    panes: &Panes,
+    window: window::Id,
+    pane: pane_grid::Pane,
     history: &mut history::Manager,
     clients: &mut data::client::Map,
     config: &Config,
 ) {
+    let Some(buffer) = panes.get(window, pane).map(|state| &state.buffer)
+    else {
+        return;
+    };
+
+    if buffer
+        .data()
+        .is_some_and(|data| panes.find_all_by_buffer(&data).len() > 1)
+    {
+        return;
+    }
+
     if config
         .buffer
         .mark_as_read
@@ -3315,11 +4568,19 @@ impl<'a> From<&'a Dashboard> for data::Dashboard {
                 .map(|state| from_layout(state, state.layout().clone()))
                 .collect(),
             buffer_settings: dashboard.buffer_settings.clone(),
+            monitored: dashboard.monitored.clone(),
+            pinned: dashboard.pinned.clone(),
+            popout_windows: dashboard.popout_windows.clone(),
             focus_buffer: dashboard.panes.iter().find_map(|(w, p, state)| {
                 (w == focus.window && p == focus.pane)
                     .then_some(state.buffer.data())
                     .flatten()
             }),
+            main_focus_buffer: dashboard
+                .focus_history
+                .front()
+                .and_then(|pane| dashboard.panes.main.get(*pane))
+                .and_then(|state| state.buffer.data()),
         }
     }
 }
@@ -3361,10 +4622,47 @@ impl Panes {
     fn get_mut_by_buffer(
         &mut self,
         buffer: &data::Buffer,
+        focus: Focus,
     ) -> Option<(window::Id, pane_grid::Pane, &mut Pane)> {
-        self.iter_mut().find(|(_, _, state)| {
-            state.buffer.data().is_some_and(|b| b == *buffer)
-        })
+        let (window, pane) = self.find_by_buffer(buffer, focus)?;
+
+        self.get_mut(window, pane)
+            .map(|state| (window, pane, state))
+    }
+
+    /// The pane showing `buffer`, preferring the focused one when the buffer
+    /// is open in more than one pane.
+    fn find_by_buffer(
+        &self,
+        buffer: &data::Buffer,
+        focus: Focus,
+    ) -> Option<(window::Id, pane_grid::Pane)> {
+        let panes = self.find_all_by_buffer(buffer);
+
+        panes
+            .iter()
+            .copied()
+            .find(|(window, pane)| {
+                Focus {
+                    window: *window,
+                    pane: *pane,
+                } == focus
+            })
+            .or_else(|| panes.first().copied())
+    }
+
+    /// Every pane showing `buffer`, since a buffer can be open in more than
+    /// one pane at a time.
+    fn find_all_by_buffer(
+        &self,
+        buffer: &data::Buffer,
+    ) -> Vec<(window::Id, pane_grid::Pane)> {
+        self.iter()
+            .filter(|(_, _, state)| {
+                state.buffer.data().is_some_and(|b| b == *buffer)
+            })
+            .map(|(window, pane, _)| (window, pane))
+            .collect()
     }
 
     fn iter(
@@ -3414,6 +4712,59 @@ impl Panes {
     }
 }
 
+/// Joins selected text fragments, starting a new line whenever a fragment's
+/// vertical position differs from the previous one
+fn join_selected_text(selected: Vec<(f32, String)>) -> String {
+    let mut contents = String::new();
+    let mut last_y = None;
+
+    for (y, content) in selected {
+        if last_y.is_some_and(|last_y| last_y != y) {
+            contents.push('\n');
+        }
+
+        contents.push_str(&content);
+        last_y = Some(y);
+    }
+
+    contents
+}
+
+/// Theme configured for the buffer shown in `pane`, if it overrides the
+/// selected theme
+fn pane_theme<'a>(
+    pane: &Pane,
+    clients: &client::Map,
+    config: &Config,
+    theme_overrides: &'a theme::Overrides,
+) -> Option<&'a Theme> {
+    let buffer = pane.buffer.upstream()?;
+
+    theme_overrides.get(
+        config,
+        buffer,
+        clients.get_casemapping(buffer.server()),
+    )
+}
+
+fn texture_usage<'a>(
+    usage: preview::Usage,
+    config: &Config,
+) -> Element<'a, Message> {
+    let megabytes = usage.bytes as f64 / (1024.0 * 1024.0);
+
+    container(
+        text(format!(
+            "Previews: {} visible, {} cached \u{b7} {megabytes:.1} / {} MB",
+            usage.visible, usage.retained, config.preview.memory_limit,
+        ))
+        .style(theme::text::secondary),
+    )
+    .padding([4, 8])
+    .style(theme::container::tooltip)
+    .into()
+}
+
 fn all_buffers(
     clients: &client::Map,
     history: &history::Manager,
@@ -3422,6 +4773,9 @@ fn all_buffers(
         .connected_servers()
         .flat_map(|server| {
             std::iter::once(buffer::Upstream::Server(server.clone()))
+                .chain(clients.get_sub_buffers(server).iter().map(|category| {
+                    buffer::Upstream::ServerCategory(server.clone(), *category)
+                }))
                 .chain(clients.get_channels(server).map(|channel| {
                     buffer::Upstream::Channel(server.clone(), channel.clone())
                 }))
@@ -3445,6 +4799,15 @@ fn all_buffers_with_has_unread(
                 buffer::Upstream::Server(server.clone()),
                 history.has_unread(&history::Kind::Server(server.clone())),
             ))
+            .chain(clients.get_sub_buffers(server).iter().map(|category| {
+                (
+                    buffer::Upstream::ServerCategory(server.clone(), *category),
+                    history.has_unread(&history::Kind::ServerCategory(
+                        server.clone(),
+                        *category,
+                    )),
+                )
+            }))
             .chain(clients.get_channels(server).map(|channel| {
                 (
                     buffer::Upstream::Channel(server.clone(), channel.clone()),
@@ -163,11 +163,15 @@ pub enum Configuration {
 #[derive(Debug, Clone)]
 pub enum Ui {
     ToggleSidebarVisibility,
+    ToggleTextureUsage,
 }
 
 #[derive(Debug, Clone)]
 pub enum Window {
     ToggleFullscreen,
+    ToggleAlwaysOnTop,
+    ToggleMiniMode,
+    ToggleBorderless,
 }
 
 #[derive(Debug, Clone)]
@@ -175,6 +179,8 @@ pub enum Theme {
     Switch(data::Theme),
     OpenEditor,
     OpenThemesWebsite,
+    Browse,
+    Import,
 }
 
 impl Command {
@@ -298,13 +304,18 @@ impl Configuration {
 
 impl Ui {
     fn list() -> Vec<Self> {
-        vec![Ui::ToggleSidebarVisibility]
+        vec![Ui::ToggleSidebarVisibility, Ui::ToggleTextureUsage]
     }
 }
 
 impl Window {
     fn list() -> Vec<Self> {
-        vec![Window::ToggleFullscreen]
+        vec![
+            Window::ToggleFullscreen,
+            Window::ToggleAlwaysOnTop,
+            Window::ToggleMiniMode,
+            Window::ToggleBorderless,
+        ]
     }
 }
 
@@ -312,7 +323,9 @@ impl Theme {
     fn list(config: &Config) -> Vec<Self> {
         Some(Self::OpenEditor)
             .into_iter()
+            .chain(Some(Self::Browse))
             .chain(Some(Self::OpenThemesWebsite))
+            .chain(Some(Self::Import))
             .chain(config.appearance.all.iter().cloned().map(Self::Switch))
             .collect()
     }
@@ -330,6 +343,9 @@ impl std::fmt::Display for Window {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Window::ToggleFullscreen => write!(f, "Toggle Fullscreen"),
+            Window::ToggleAlwaysOnTop => write!(f, "Toggle Always on Top"),
+            Window::ToggleMiniMode => write!(f, "Toggle Mini Mode"),
+            Window::ToggleBorderless => write!(f, "Toggle Borderless Window"),
         }
     }
 }
@@ -339,11 +355,9 @@ impl std::fmt::Display for Version {
         match self {
             Version::Application(version) => {
                 let latest = version
-                    .remote
-                    .as_ref()
-                    .filter(|remote| remote != &&version.current)
+                    .newer()
                     .map_or("(Latest release)".to_owned(), |remote| {
-                        format!("(Latest: {remote})")
+                        format!("(Latest: {})", remote.version)
                     });
 
                 write!(f, "{} {}", version.current, latest)
@@ -372,6 +386,9 @@ impl std::fmt::Display for Buffer {
                 buffer::Upstream::Server(server) => {
                     write!(f, "Change to {server}")
                 }
+                buffer::Upstream::ServerCategory(server, category) => {
+                    write!(f, "Change to {category} ({server})")
+                }
                 buffer::Upstream::Channel(server, channel) => {
                     write!(f, "Change to {channel} ({server})")
                 }
@@ -415,6 +432,9 @@ impl std::fmt::Display for Ui {
             Ui::ToggleSidebarVisibility => {
                 write!(f, "Toggle sidebar visibility")
             }
+            Ui::ToggleTextureUsage => {
+                write!(f, "Toggle preview texture usage overlay")
+            }
         }
     }
 }
@@ -427,6 +447,13 @@ impl std::fmt::Display for Theme {
             Theme::OpenThemesWebsite => {
                 write!(f, "Discover more themes (Opens website)")
             }
+            Theme::Browse => write!(f, "Get more themes"),
+            Theme::Import => {
+                write!(
+                    f,
+                    "Import terminal color scheme (base16, iTerm, Windows Terminal)"
+                )
+            }
         }
     }
 }
@@ -1,12 +1,12 @@
-use data::user::ChannelUsers;
-use data::{Config, file_transfer, history, preview};
+use data::buffer::Density;
+use data::{Config, dcc_chat, file_transfer, history, preview};
 use iced::Size;
-use iced::widget::{button, center, container, pane_grid, row, text};
+use iced::widget::{button, center, container, pane_grid, row, text, themer};
 
 use super::sidebar;
 use crate::buffer::{self, Buffer};
 use crate::widget::{on_resize, tooltip};
-use crate::{Theme, font, icon, theme, widget};
+use crate::{Theme, font, i18n, icon, theme, widget};
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -19,17 +19,21 @@ pub enum Message {
     MaximizePane,
     ToggleShowUserList,
     ToggleShowTopic,
+    ToggleDensity,
     Popout,
     Merge,
     ScrollToBottom,
     MarkAsRead,
     ContentResized(pane_grid::Pane, Size),
+    ContextMenu(pane_grid::Pane, buffer::context_menu::Message),
 }
 
 #[derive(Clone, Debug)]
 pub struct Pane {
     pub buffer: Buffer,
     pub size: Size,
+    /// Density chosen for the pane, overriding `buffer.density`
+    density: Option<Density>,
     title_bar: TitleBar,
 }
 
@@ -41,10 +45,23 @@ impl Pane {
         Self {
             buffer,
             size: Size::default(), // Will get set initially via `Message::Resized`
+            density: None,
             title_bar: TitleBar::default(),
         }
     }
 
+    pub fn with_density(self, density: Option<Density>) -> Self {
+        Self { density, ..self }
+    }
+
+    pub fn density(&self, config: &Config) -> Density {
+        self.density.unwrap_or(config.buffer.density)
+    }
+
+    pub fn toggle_density(&mut self, config: &Config) {
+        self.density = Some(self.density(config).toggle());
+    }
+
     pub fn view<'a>(
         &'a self,
         id: pane_grid::Pane,
@@ -53,41 +70,93 @@ impl Pane {
         maximized: bool,
         clients: &'a data::client::Map,
         file_transfers: &'a file_transfer::Manager,
+        dcc_chats: &'a dcc_chat::Manager,
         history: &'a history::Manager,
         previews: &'a preview::Collection,
         sidebar: &'a sidebar::Sidebar,
         config: &'a Config,
         theme: &'a Theme,
+        theme_override: Option<&'a Theme>,
         settings: Option<&'a buffer::Settings>,
         is_popout: bool,
+        is_mini: bool,
     ) -> widget::Content<'a, Message> {
         let title_bar_text = match &self.buffer {
             Buffer::Empty => String::new(),
             Buffer::Channel(state) => {
-                let channel = state.target.as_str();
                 let server = &state.server;
+                let channel = config
+                    .alias(
+                        server,
+                        &state.target.to_target(),
+                        clients.get_casemapping(server),
+                    )
+                    .unwrap_or(state.target.as_str());
                 let users = clients
-                    .get_channel_users(&state.server, &state.target)
-                    .map(ChannelUsers::len)
+                    .get_nicklist(&state.server, &state.target)
+                    .map(<[_]>::len)
                     .unwrap_or_default();
 
                 let mode = clients
-                    .get_channel_mode(&state.server, &state.target)
+                    .get_channel_metadata(&state.server, &state.target)
+                    .and_then(data::client::Metadata::mode_string)
+                    .filter(|mode| !mode.is_empty())
                     .map(|mode| format!(" ({mode})"))
                     .unwrap_or_default();
 
-                format!("{channel}{mode} @ {server} - {users} users")
+                format!(
+                    "{channel}{mode} @ {server} - {} users",
+                    i18n::format_count(users)
+                )
             }
-            Buffer::Server(state) => state.server.to_string(),
+            Buffer::Server(state) => match &state.buffer {
+                buffer::Upstream::ServerCategory(server, category) => {
+                    format!("{category} @ {server}")
+                }
+                _ => {
+                    let server = &state.server;
+
+                    if let Some(error) = clients.sasl_error(server) {
+                        format!(
+                            "{server} - SASL authentication failed: {error}"
+                        )
+                    } else if let Some(account) = clients.account(server) {
+                        format!("{server} - logged in as {account}")
+                    } else {
+                        server.to_string()
+                    }
+                }
+            },
             Buffer::Query(state) => {
-                let nick = state.target.as_str();
                 let server = &state.server;
+                let nick = config
+                    .alias(
+                        server,
+                        &state.target.to_target(),
+                        clients.get_casemapping(server),
+                    )
+                    .unwrap_or(state.target.as_str());
 
-                format!("{nick} @ {server}")
+                match clients.query_presence(server, &state.target) {
+                    Some(true) => format!("{nick} @ {server} - online"),
+                    Some(false) => format!("{nick} @ {server} - offline"),
+                    None => format!("{nick} @ {server}"),
+                }
             }
             Buffer::FileTransfers(_) => "File Transfers".to_string(),
             Buffer::Logs(_) => "Logs".to_string(),
             Buffer::Highlights(_) => "Highlights".to_string(),
+            Buffer::Search(_) => "Search".to_string(),
+            Buffer::Admin(_) => "Server Admin".to_string(),
+            Buffer::DccChat(state) => dcc_chats.get(&state.id).map_or_else(
+                || "DCC Chat".to_string(),
+                |chat| {
+                    let nick = chat.remote_user.nickname();
+                    let server = &chat.server;
+
+                    format!("DCC Chat with {nick} @ {server}")
+                },
+            ),
         };
 
         let title_bar = self.title_bar.view(
@@ -100,6 +169,7 @@ impl Pane {
             maximized,
             clients,
             settings,
+            self.density(config),
             config.tooltips,
             is_popout,
             config,
@@ -111,20 +181,38 @@ impl Pane {
             .view(
                 clients,
                 file_transfers,
+                dcc_chats,
                 history,
                 previews,
                 settings,
+                self.density(config),
                 config,
-                theme,
+                theme_override.unwrap_or(theme),
                 is_focused,
                 sidebar,
+                is_mini,
             )
             .map(move |msg| Message::Buffer(id, msg));
 
+        // Buffers with their own theme draw their background from it, the
+        // pane's border follows the selected theme
+        let content: widget::Element<'a, Message> = match theme_override {
+            Some(theme_override) => themer(
+                Some(theme_override.clone()),
+                container(content).style(move |theme| {
+                    theme::container::buffer(theme, is_focused)
+                }),
+            )
+            .into(),
+            None => {
+                container(content).style(theme::container::pane_body).into()
+            }
+        };
+
         widget::Content::new(on_resize(content, move |size| {
             Message::ContentResized(id, size)
         }))
-        .style(move |theme| theme::container::buffer(theme, is_focused))
+        .style(move |theme| theme::container::pane(theme, is_focused))
         .title_bar(title_bar.style(theme::container::buffer_title_bar))
     }
 
@@ -138,7 +226,7 @@ impl Pane {
                 ),
             }),
             Buffer::Server(state) => Some(history::Resource {
-                kind: history::Kind::Server(state.server.clone()),
+                kind: state.kind().into(),
             }),
             Buffer::Query(state) => Some(history::Resource {
                 kind: history::Kind::Query(
@@ -146,7 +234,10 @@ impl Pane {
                     state.target.clone(),
                 ),
             }),
-            Buffer::FileTransfers(_) => None,
+            Buffer::FileTransfers(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => None,
             Buffer::Logs(_) => Some(history::Resource::logs()),
             Buffer::Highlights(_) => Some(history::Resource::highlights()),
         }
@@ -162,7 +253,10 @@ impl Pane {
             | Buffer::Server(_)
             | Buffer::FileTransfers(_)
             | Buffer::Logs(_)
-            | Buffer::Highlights(_) => vec![],
+            | Buffer::Highlights(_)
+            | Buffer::Search(_)
+            | Buffer::Admin(_)
+            | Buffer::DccChat(_) => vec![],
         }
     }
 }
@@ -170,15 +264,16 @@ impl Pane {
 impl TitleBar {
     fn view<'a>(
         &'a self,
-        buffer: &Buffer,
+        buffer: &'a Buffer,
         history: &'a history::Manager,
         value: String,
-        _id: pane_grid::Pane,
+        id: pane_grid::Pane,
         panes: usize,
         _is_focused: bool,
         maximized: bool,
         clients: &'a data::client::Map,
         settings: Option<&'a buffer::Settings>,
+        density: Density,
         show_tooltips: bool,
         is_popout: bool,
         config: &'a Config,
@@ -317,6 +412,32 @@ impl TitleBar {
             } else {
                 None
             },
+            if matches!(buffer, Buffer::Channel(_) | Buffer::Query(_)) {
+                let is_comfortable = density == Density::Comfortable;
+
+                let density_button = button(center(icon::menu()))
+                    .padding(5)
+                    .width(22)
+                    .height(22)
+                    .on_press(Message::ToggleDensity)
+                    .style(move |theme, status| {
+                        theme::button::secondary(theme, status, is_comfortable)
+                    });
+
+                let density_button_with_tooltip = tooltip(
+                    density_button,
+                    show_tooltips.then_some(if is_comfortable {
+                        "Compact layout"
+                    } else {
+                        "Comfortable layout"
+                    }),
+                    tooltip::Position::Bottom,
+                    theme,
+                );
+                Some(density_button_with_tooltip)
+            } else {
+                None
+            },
             if panes > 1 {
                 let maximize_button = button(center(if maximized {
                     icon::restore()
@@ -417,6 +538,19 @@ impl TitleBar {
         .padding([0, 4])
         .align_y(iced::alignment::Vertical::Center);
 
+        let title = if let Buffer::Channel(state) = buffer {
+            buffer::context_menu::channel(
+                title,
+                &state.server,
+                &state.target,
+                config,
+                theme,
+            )
+            .map(move |message| Message::ContextMenu(id, message))
+        } else {
+            title.into()
+        };
+
         widget::TitleBar::new(title)
             .controls(pane_grid::Controls::new(controls))
             .padding(6)
@@ -425,14 +559,13 @@ impl TitleBar {
 
 impl From<Pane> for data::Pane {
     fn from(pane: Pane) -> Self {
+        let density = pane.density;
         let buffer = match pane.buffer {
             Buffer::Empty => return data::Pane::Empty,
             Buffer::Channel(state) => data::Buffer::Upstream(
                 buffer::Upstream::Channel(state.server, state.target),
             ),
-            Buffer::Server(state) => {
-                data::Buffer::Upstream(buffer::Upstream::Server(state.server))
-            }
+            Buffer::Server(state) => data::Buffer::Upstream(state.buffer),
             Buffer::Query(state) => data::Buffer::Upstream(
                 buffer::Upstream::Query(state.server, state.target),
             ),
@@ -443,8 +576,14 @@ impl From<Pane> for data::Pane {
             Buffer::Highlights(_) => {
                 data::Buffer::Internal(buffer::Internal::Highlights)
             }
+            Buffer::Search(_) => {
+                data::Buffer::Internal(buffer::Internal::Search)
+            }
+            Buffer::Admin(_) => data::Buffer::Internal(buffer::Internal::Admin),
+            // The connection doesn't outlive the session
+            Buffer::DccChat(_) => return data::Pane::Empty,
         };
 
-        data::Pane::Buffer { buffer }
+        data::Pane::Buffer { buffer, density }
     }
 }
@@ -1,26 +1,29 @@
 use std::time::Duration;
 
 use data::config::{self, Config, sidebar};
-use data::dashboard::{BufferAction, BufferFocusedAction};
-use data::{Version, buffer, file_transfer, history, server};
+use data::dashboard::{BufferAction, BufferFocusedAction, Pinned};
+use data::{Version, buffer, file_transfer, history, isupport, server};
 use iced::widget::{
-    Column, Row, Scrollable, Space, button, column, container, pane_grid, row,
-    rule, scrollable, space, stack, text,
+    Column, Row, Scrollable, Space, button, column, container, mouse_area,
+    pane_grid, row, rule, scrollable, space, stack, text,
 };
 use iced::{Alignment, Length, Padding, Task, padding};
 use itertools::Either;
 use tokio::time;
 
 use super::{Focus, Panes, Server};
-use crate::widget::{Element, Text, context_menu, double_pass};
-use crate::{Theme, font, icon, platform_specific, theme, window};
+use crate::widget::{Element, Text, context_menu, double_pass, tooltip};
+use crate::{Theme, font, i18n, icon, platform_specific, theme, window};
 
 const CONFIG_RELOAD_DELAY: Duration = Duration::from_secs(1);
+/// Topics are cut off in buffer tooltips after this many characters
+const MAX_TOPIC_CHARS: usize = 240;
 
 #[derive(Debug, Clone)]
 pub enum Message {
     New(buffer::Upstream),
     Popout(buffer::Upstream),
+    OpenAnother(buffer::Upstream, BufferAction),
     Focus(window::Id, pane_grid::Pane),
     Replace(buffer::Upstream),
     Close(window::Id, pane_grid::Pane),
@@ -38,12 +41,20 @@ pub enum Message {
     ReloadComplete,
     MarkAsRead(buffer::Upstream),
     MarkServerAsRead(Server),
+    Reauthenticate(Server),
+    Identity(Server),
+    Print(buffer::Upstream),
+    TogglePin(buffer::Upstream),
+    ContextMenu(crate::buffer::context_menu::Message),
+    Hovered(buffer::Upstream),
+    Unhovered(buffer::Upstream),
 }
 
 #[derive(Debug, Clone)]
 pub enum Event {
     New(buffer::Upstream),
     Popout(buffer::Upstream),
+    OpenAnother(buffer::Upstream, BufferAction),
     Focus(window::Id, pane_grid::Pane),
     Replace(buffer::Upstream),
     Close(window::Id, pane_grid::Pane),
@@ -59,12 +70,20 @@ pub enum Event {
     ConfigReloaded(Result<Config, config::Error>),
     MarkAsRead(buffer::Upstream),
     MarkServerAsRead(Server),
+    Reauthenticate(Server),
+    Identity(Server),
+    Print(buffer::Upstream),
+    TogglePin(buffer::Upstream),
+    ContextMenu(crate::buffer::context_menu::Event),
 }
 
 #[derive(Clone)]
 pub struct Sidebar {
     pub hidden: bool,
     reloading_config: bool,
+    /// Buffer under the cursor, the only one whose details are gathered for
+    /// its tooltip
+    hovered: Option<buffer::Upstream>,
 }
 
 impl Default for Sidebar {
@@ -78,6 +97,7 @@ impl Sidebar {
         Self {
             hidden: false,
             reloading_config: false,
+            hovered: None,
         }
     }
 
@@ -94,6 +114,9 @@ impl Sidebar {
             Message::Popout(source) => {
                 (Task::none(), Some(Event::Popout(source)))
             }
+            Message::OpenAnother(source, action) => {
+                (Task::none(), Some(Event::OpenAnother(source, action)))
+            }
             Message::Focus(window, pane) => {
                 (Task::none(), Some(Event::Focus(window, pane)))
             }
@@ -147,9 +170,38 @@ impl Sidebar {
             Message::MarkServerAsRead(server) => {
                 (Task::none(), Some(Event::MarkServerAsRead(server)))
             }
+            Message::Reauthenticate(server) => {
+                (Task::none(), Some(Event::Reauthenticate(server)))
+            }
+            Message::Identity(server) => {
+                (Task::none(), Some(Event::Identity(server)))
+            }
+            Message::Print(buffer) => {
+                (Task::none(), Some(Event::Print(buffer)))
+            }
+            Message::TogglePin(buffer) => {
+                (Task::none(), Some(Event::TogglePin(buffer)))
+            }
             Message::OpenConfigFile => {
                 (Task::none(), Some(Event::OpenConfigFile))
             }
+            Message::ContextMenu(message) => (
+                Task::none(),
+                Some(Event::ContextMenu(crate::buffer::context_menu::update(
+                    message,
+                ))),
+            ),
+            Message::Hovered(buffer) => {
+                self.hovered = Some(buffer);
+                (Task::none(), None)
+            }
+            Message::Unhovered(buffer) => {
+                // Entering the next buffer may come first
+                if self.hovered.as_ref() == Some(&buffer) {
+                    self.hovered = None;
+                }
+                (Task::none(), None)
+            }
         }
     }
 
@@ -181,7 +233,9 @@ impl Sidebar {
                     context_menu::ToggleBehavior::Close,
                     base,
                     menu,
-                    move |menu, length| {
+                    move |menu, length, focused| {
+                        let title = text(menu.label().unwrap_or_default());
+
                         let context_button =
                             |title: Text<'a>,
                              keybind: Option<&data::shortcut::KeyBind>,
@@ -209,25 +263,30 @@ impl Sidebar {
                                 )
                                 .width(length)
                                 .padding(5)
+                                .style(move |theme, status| {
+                                    theme::button::context_menu(
+                                        theme, status, focused,
+                                    )
+                                })
                                 .on_press(message)
                                 .into()
                             };
 
                         match menu {
                             Menu::RefreshConfig => context_button(
-                                text("Reload config file"),
+                                title,
                                 Some(&keyboard.reload_configuration),
                                 icon::refresh(),
                                 Message::ReloadConfigFile,
                             ),
                             Menu::CommandBar => context_button(
-                                text("Command Bar"),
+                                title,
                                 Some(&keyboard.command_bar),
                                 icon::search(),
                                 Message::ToggleCommandBar,
                             ),
                             Menu::FileTransfers => context_button(
-                                text("File Transfers")
+                                title
                                     .style(if file_transfers.is_empty() {
                                         theme::text::primary
                                     } else {
@@ -253,15 +312,23 @@ impl Sidebar {
                                 ),
                             ),
                             Menu::Highlights => context_button(
-                                text("Highlights"),
+                                title,
                                 Some(&keyboard.highlights),
                                 icon::highlights(),
                                 Message::ToggleInternalBuffer(
                                     buffer::Internal::Highlights,
                                 ),
                             ),
+                            Menu::Search => context_button(
+                                title,
+                                Some(&keyboard.search),
+                                icon::search(),
+                                Message::ToggleInternalBuffer(
+                                    buffer::Internal::Search,
+                                ),
+                            ),
                             Menu::Logs => context_button(
-                                text("Logs")
+                                title
                                     .style(if logs_has_unread {
                                         theme::text::tertiary
                                     } else {
@@ -285,7 +352,7 @@ impl Sidebar {
                                 ),
                             ),
                             Menu::ThemeEditor => context_button(
-                                text("Theme Editor"),
+                                title,
                                 Some(&keyboard.theme_editor),
                                 icon::theme_editor(),
                                 Message::ToggleThemeEditor,
@@ -299,12 +366,10 @@ impl Sidebar {
                                 }
                             },
                             Menu::Update => context_button(
-                                text("New version available")
-                                    .style(theme::text::tertiary)
-                                    .font_maybe(
-                                        theme::font_style::tertiary(theme)
-                                            .map(font::get),
-                                    ),
+                                title.style(theme::text::tertiary).font_maybe(
+                                    theme::font_style::tertiary(theme)
+                                        .map(font::get),
+                                ),
                                 None,
                                 icon::megaphone().style(theme::text::tertiary),
                                 Message::OpenReleaseWebsite,
@@ -320,20 +385,21 @@ impl Sidebar {
                             .padding(5)
                             .into(),
                             Menu::Documentation => context_button(
-                                text("Documentation"),
+                                title,
                                 None,
                                 icon::documentation(),
                                 Message::OpenDocumentation,
                             ),
                             Menu::OpenConfigFile => context_button(
-                                text("Open config file"),
+                                title,
                                 None,
                                 icon::config(),
                                 Message::OpenConfigFile,
                             ),
                         }
                     },
-                ),
+                )
+                .labels(|menu| menu.label().map(str::to_string)),
                 if show_notification_dot {
                     Some(
                         container(
@@ -354,6 +420,7 @@ impl Sidebar {
         servers: &server::Map,
         clients: &data::client::Map,
         history: &'a history::Manager,
+        pinned: &Pinned,
         panes: &'a Panes,
         focus: Focus,
         config: &'a Config,
@@ -376,36 +443,106 @@ impl Sidebar {
                 )
             });
 
+            let button = |buffer: buffer::Upstream,
+                          connected: bool,
+                          server_has_unread: bool,
+                          supports_detach: bool,
+                          has_unread: bool,
+                          has_highlight: bool| {
+                let casemapping = clients.get_casemapping(buffer.server());
+                let authentication = match &buffer {
+                    buffer::Upstream::Server(server) => {
+                        Authentication::new(clients, server)
+                    }
+                    _ => Authentication::default(),
+                };
+                let details = (config.tooltips
+                    && self.hovered.as_ref() == Some(&buffer))
+                .then(|| buffer_details(&buffer, clients, history))
+                .flatten();
+                let is_pinned = pinned.contains(&buffer);
+
+                upstream_buffer_button(
+                    panes,
+                    focus,
+                    buffer,
+                    connected,
+                    config.actions.sidebar.buffer,
+                    config.actions.sidebar.focused_buffer,
+                    config.sidebar.position,
+                    config.sidebar.server_icon_size,
+                    config.sidebar.unread_indicator,
+                    server_has_unread,
+                    supports_detach,
+                    has_unread,
+                    has_highlight,
+                    is_pinned,
+                    authentication,
+                    details,
+                    casemapping,
+                    width,
+                    config,
+                    theme,
+                )
+            };
+
             let mut buffers = vec![];
             let mut client_enumeration = 0;
 
-            for server in servers.keys() {
-                let button =
-                    |buffer: buffer::Upstream,
-                     connected: bool,
-                     server_has_unread: bool,
-                     supports_detach: bool,
-                     has_unread: bool,
-                     has_highlight: bool| {
-                        upstream_buffer_button(
-                            panes,
-                            focus,
-                            buffer,
-                            connected,
-                            config.actions.sidebar.buffer,
-                            config.actions.sidebar.focused_buffer,
-                            config.sidebar.position,
-                            config.sidebar.server_icon_size,
-                            config.sidebar.unread_indicator,
-                            server_has_unread,
-                            supports_detach,
-                            has_unread,
-                            has_highlight,
-                            width,
-                            theme,
-                        )
-                    };
+            // Pinned buffers, ahead of the servers they belong to.
+            for buffer in pinned.iter() {
+                let server = buffer.server();
+
+                let Some(data::client::State::Ready(connection)) =
+                    clients.state(server)
+                else {
+                    continue;
+                };
+
+                let is_listed = match buffer {
+                    buffer::Upstream::Channel(_, channel) => {
+                        connection.channels().any(|joined| joined == channel)
+                    }
+                    buffer::Upstream::Query(_, query) => history
+                        .get_unique_queries(server)
+                        .into_iter()
+                        .any(|listed| listed == query),
+                    buffer::Upstream::Server(_)
+                    | buffer::Upstream::ServerCategory(_, _) => false,
+                };
+
+                if !is_listed {
+                    continue;
+                }
+
+                let kind = history::Kind::from_input_buffer(buffer.clone());
+
+                buffers.push(button(
+                    buffer.clone(),
+                    true,
+                    history.server_has_unread(server.clone()),
+                    clients.get_server_supports_detach(server),
+                    history.has_unread(&kind),
+                    history.has_highlight(&kind),
+                ));
+            }
+
+            if !buffers.is_empty() {
+                if config.sidebar.position.is_horizontal() {
+                    buffers.push(
+                        container(rule::vertical(1))
+                            .padding(padding::top(6))
+                            .height(20)
+                            .width(12)
+                            .align_x(Alignment::Center)
+                            .into(),
+                    );
+                } else {
+                    buffers.push(space::vertical().height(12).into());
+                }
+            }
 
+            for server in servers.keys() {
                 if let Some(state) = clients.state(server) {
                     client_enumeration += 1;
 
@@ -440,14 +577,40 @@ impl Sidebar {
                                 )),
                             ));
 
-                            // Channels from the connected server.
-                            for channel in connection.channels() {
+                            // Server traffic split out of the server buffer.
+                            for category in clients.get_sub_buffers(server) {
+                                let kind = history::Kind::ServerCategory(
+                                    server.clone(),
+                                    *category,
+                                );
+
                                 buffers.push(button(
-                                    buffer::Upstream::Channel(
+                                    buffer::Upstream::ServerCategory(
                                         server.clone(),
-                                        channel.clone(),
+                                        *category,
                                     ),
                                     true,
+                                    false,
+                                    false,
+                                    history.has_unread(&kind),
+                                    history.has_highlight(&kind),
+                                ));
+                            }
+
+                            // Channels from the connected server.
+                            for channel in connection.channels() {
+                                let buffer = buffer::Upstream::Channel(
+                                    server.clone(),
+                                    channel.clone(),
+                                );
+
+                                if pinned.contains(&buffer) {
+                                    continue;
+                                }
+
+                                buffers.push(button(
+                                    buffer,
+                                    true,
                                     history.server_has_unread(server.clone()),
                                     clients.get_server_supports_detach(server),
                                     history.has_unread(
@@ -471,12 +634,17 @@ impl Sidebar {
                                 let query = clients
                                     .resolve_query(server, query)
                                     .unwrap_or(query);
+                                let buffer = buffer::Upstream::Query(
+                                    server.clone(),
+                                    query.clone(),
+                                );
+
+                                if pinned.contains(&buffer) {
+                                    continue;
+                                }
 
                                 buffers.push(button(
-                                    buffer::Upstream::Query(
-                                        server.clone(),
-                                        query.clone(),
-                                    ),
+                                    buffer,
                                     true,
                                     history.server_has_unread(server.clone()),
                                     clients.get_server_supports_detach(server),
@@ -615,6 +783,7 @@ enum Menu {
     ThemeEditor,
     Highlights,
     Logs,
+    Search,
     FileTransfers,
     Version,
     Update,
@@ -624,6 +793,22 @@ enum Menu {
 }
 
 impl Menu {
+    fn label(self) -> Option<&'static str> {
+        match self {
+            Menu::RefreshConfig => Some("Reload config file"),
+            Menu::CommandBar => Some("Command Bar"),
+            Menu::ThemeEditor => Some("Theme Editor"),
+            Menu::Highlights => Some("Highlights"),
+            Menu::Logs => Some("Logs"),
+            Menu::Search => Some("Search"),
+            Menu::FileTransfers => Some("File Transfers"),
+            Menu::Update => Some("New version available"),
+            Menu::Documentation => Some("Documentation"),
+            Menu::OpenConfigFile => Some("Open config file"),
+            Menu::Version | Menu::HorizontalRule => None,
+        }
+    }
+
     fn list(has_new_version: bool) -> Vec<Self> {
         let mut list = vec![Self::Version];
 
@@ -640,6 +825,7 @@ impl Menu {
             Self::Logs,
             Self::OpenConfigFile,
             Self::RefreshConfig,
+            Self::Search,
             Self::ThemeEditor,
         ]);
 
@@ -650,31 +836,95 @@ impl Menu {
 #[derive(Debug, Clone, Copy)]
 enum Entry {
     MarkServerAsRead,
+    Reauthenticate,
+    Identity,
     MarkAsRead,
     NewPane,
     Popout,
     Replace,
+    /// Open an already open buffer in another pane or window
+    OpenAnother(BufferAction),
     Close(window::Id, pane_grid::Pane),
     Swap(window::Id, pane_grid::Pane),
     Leave,
     Detach,
+    Print,
+    /// Pin or unpin, by whether the buffer is pinned
+    Pin(bool),
+    Channel(crate::buffer::context_menu::Entry),
 }
 
 impl Entry {
+    fn label(
+        self,
+        buffer: &buffer::Upstream,
+        config: &Config,
+    ) -> Option<String> {
+        let label = match self {
+            Entry::Channel(entry) => {
+                let context = crate::buffer::context_menu::Context::Channel {
+                    server: buffer.server(),
+                    channel: buffer.channel()?,
+                };
+
+                return entry.label(&context, config);
+            }
+            Entry::MarkServerAsRead => "Mark entire server as read",
+            Entry::Reauthenticate => "Re-authenticate",
+            Entry::Identity => "Show identity",
+            Entry::MarkAsRead => {
+                if matches!(buffer, buffer::Upstream::Server(_)) {
+                    "Mark server buffer as read"
+                } else {
+                    "Mark as read"
+                }
+            }
+            Entry::NewPane => "Open in new pane",
+            Entry::Popout => "Open in new window",
+            Entry::Replace => "Replace current pane",
+            Entry::OpenAnother(BufferAction::NewWindow) => {
+                "Open in another window"
+            }
+            Entry::OpenAnother(_) => "Open in another pane",
+            Entry::Close(_, _) => "Close pane",
+            Entry::Swap(_, _) => "Swap with current pane",
+            Entry::Detach => "Detach from channel",
+            Entry::Print => "Print…",
+            Entry::Pin(false) => "Pin to top of sidebar",
+            Entry::Pin(true) => "Unpin from top of sidebar",
+            Entry::Leave => match buffer {
+                buffer::Upstream::Server(_) => "Leave server",
+                buffer::Upstream::ServerCategory(_, _) => "Close buffer",
+                buffer::Upstream::Channel(_, _) => "Leave channel",
+                buffer::Upstream::Query(_, _) => "Close query",
+            },
+        };
+
+        Some(label.to_string())
+    }
+
     fn list(
         buffer: &buffer::Upstream,
         num_panes: usize,
         open: Option<(window::Id, pane_grid::Pane)>,
         focus: Focus,
         supports_detach: bool,
+        can_reauthenticate: bool,
+        is_pinned: bool,
     ) -> Vec<Self> {
         [
             match buffer {
                 buffer::Upstream::Server(_) => {
-                    vec![Entry::MarkServerAsRead]
+                    std::iter::once(Entry::MarkServerAsRead)
+                        .chain(
+                            can_reauthenticate.then_some(Entry::Reauthenticate),
+                        )
+                        .chain(Some(Entry::Identity))
+                        .collect()
                 }
-                buffer::Upstream::Channel(_, _) => vec![],
-                buffer::Upstream::Query(_, _) => vec![],
+                buffer::Upstream::ServerCategory(_, _)
+                | buffer::Upstream::Channel(_, _)
+                | buffer::Upstream::Query(_, _) => vec![],
             },
             match open {
                 None => Either::Left(
@@ -692,22 +942,152 @@ impl Entry {
                     ),
                 ),
                 Some((window, pane)) => Either::Right(
-                    (num_panes > 1)
-                        .then_some(Entry::Close(window, pane))
-                        .into_iter()
-                        .chain(
-                            (Focus { window, pane } != focus)
-                                .then_some(Entry::Swap(window, pane)),
-                        ),
+                    [
+                        Entry::OpenAnother(BufferAction::NewPane),
+                        Entry::OpenAnother(BufferAction::NewWindow),
+                    ]
+                    .into_iter()
+                    .chain(
+                        (num_panes > 1).then_some(Entry::Close(window, pane)),
+                    )
+                    .chain(
+                        (Focus { window, pane } != focus)
+                            .then_some(Entry::Swap(window, pane)),
+                    ),
                 ),
             }
+            .chain(
+                matches!(
+                    buffer,
+                    buffer::Upstream::Channel(_, _)
+                        | buffer::Upstream::Query(_, _)
+                )
+                .then_some(Entry::Pin(is_pinned)),
+            )
+            .chain(Some(Entry::Print))
             .chain(Some(Entry::Leave))
             .collect(),
+            match buffer {
+                buffer::Upstream::Channel(_, _) => {
+                    use crate::buffer::context_menu::Entry as Channel;
+
+                    [
+                        Channel::HorizontalRule,
+                        Channel::CopyChannelName,
+                        Channel::SetTopic,
+                        Channel::ChannelModes,
+                        Channel::OpenLogFile,
+                        Channel::PartWithReason,
+                    ]
+                    .into_iter()
+                    .map(Entry::Channel)
+                    .collect()
+                }
+                buffer::Upstream::Server(_)
+                | buffer::Upstream::ServerCategory(_, _)
+                | buffer::Upstream::Query(_, _) => vec![],
+            },
         ]
         .concat()
     }
 }
 
+/// SASL and operator status of a server.
+#[derive(Debug, Clone, Default)]
+struct Authentication {
+    tooltip: Option<String>,
+    can_reauthenticate: bool,
+    /// Opered up, which is shown as a badge
+    is_oper: bool,
+}
+
+impl Authentication {
+    fn new(clients: &data::client::Map, server: &Server) -> Self {
+        let tooltip = clients
+            .sasl_error(server)
+            .map(|error| format!("SASL authentication failed: {error}"))
+            .or_else(|| {
+                clients
+                    .account(server)
+                    .map(|account| format!("Logged in as {account}"))
+            });
+
+        Self {
+            tooltip,
+            can_reauthenticate: clients.can_reauthenticate(server),
+            is_oper: clients.is_oper(server),
+        }
+    }
+}
+
+/// Topic, user count and activity of a channel or query, shown as the
+/// tooltip of its buffer.
+fn buffer_details(
+    buffer: &buffer::Upstream,
+    clients: &data::client::Map,
+    history: &history::Manager,
+) -> Option<String> {
+    let mut lines = vec![];
+
+    match buffer {
+        buffer::Upstream::Channel(server, channel) => {
+            if let Some(topic) = clients
+                .get_channel_topic(server, channel)
+                .and_then(|topic| topic.content.as_ref())
+                .map(|content| content.text())
+                .filter(|topic| !topic.is_empty())
+            {
+                lines.push(truncate(&topic, MAX_TOPIC_CHARS));
+            }
+
+            if let Some(users) = clients.get_channel_users(server, channel) {
+                lines.push(match users.len() {
+                    1 => "1 user".to_string(),
+                    count => format!("{count} users"),
+                });
+            }
+        }
+        buffer::Upstream::Query(..) => {}
+        buffer::Upstream::Server(_) | buffer::Upstream::ServerCategory(..) => {
+            return None;
+        }
+    }
+
+    let activity =
+        history.activity(&history::Kind::from_input_buffer(buffer.clone()));
+
+    if let Some(elapsed) = activity.last_message.and_then(|last_message| {
+        (chrono::Utc::now() - last_message).to_std().ok()
+    }) {
+        lines.push(format!(
+            "Last message {}",
+            timeago::Formatter::new().convert(elapsed)
+        ));
+    }
+
+    if activity.unread > 0 {
+        lines.push(match activity.highlights {
+            0 => format!("{} unread", activity.unread),
+            1 => format!("{} unread, 1 highlight", activity.unread),
+            highlights => {
+                format!("{} unread, {highlights} highlights", activity.unread)
+            }
+        });
+    }
+
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        let truncated = text.chars().take(max_chars).collect::<String>();
+
+        format!("{}…", truncated.trim_end())
+    } else {
+        text.to_string()
+    }
+}
+
 fn upstream_buffer_button<'a>(
     panes: &'a Panes,
     focus: Focus,
@@ -722,12 +1102,14 @@ fn upstream_buffer_button<'a>(
     supports_detach: bool,
     has_unread: bool,
     has_highlight: bool,
+    is_pinned: bool,
+    authentication: Authentication,
+    details: Option<String>,
+    casemapping: isupport::CaseMap,
     width: Length,
+    config: &'a Config,
     theme: &'a Theme,
 ) -> Element<'a, Message> {
-    let open = panes.iter().find_map(|(window_id, pane, state)| {
-        (state.buffer.upstream() == Some(&buffer)).then_some((window_id, pane))
-    });
     let is_focused = panes.iter().find_map(|(window_id, pane, state)| {
         (Focus {
             window: window_id,
@@ -736,6 +1118,14 @@ fn upstream_buffer_button<'a>(
             && state.buffer.upstream() == Some(&buffer))
         .then_some((window_id, pane))
     });
+    // Close and swap act on the focused pane when the buffer is open in
+    // more than one
+    let open = is_focused.or_else(|| {
+        panes.iter().find_map(|(window_id, pane, state)| {
+            (state.buffer.upstream() == Some(&buffer))
+                .then_some((window_id, pane))
+        })
+    });
 
     let show_highlight_icon =
         has_highlight && unread_indicator.has_unread_highlight_icon();
@@ -826,7 +1216,7 @@ fn upstream_buffer_button<'a>(
     let content = container(stack![
         container(match &buffer {
             buffer::Upstream::Server(server) => {
-                if let Some(network) = &server.network {
+                let name = if let Some(network) = &server.network {
                     Element::from(row![
                         text(network.name.to_string())
                             .style(buffer_title_style)
@@ -844,18 +1234,43 @@ fn upstream_buffer_button<'a>(
                         .font_maybe(buffer_title_font)
                         .shaping(text::Shaping::Advanced)
                         .into()
-                }
+                };
+
+                let oper = authentication.is_oper.then(|| {
+                    text(i18n::tr("sidebar-oper"))
+                        .style(theme::text::success)
+                        .font_maybe(
+                            theme::font_style::success(theme).map(font::get),
+                        )
+                });
+
+                Element::from(row![name, oper].spacing(6))
+            }
+            buffer::Upstream::ServerCategory(_, category) => {
+                text(category.to_string())
+                    .style(buffer_title_style)
+                    .font_maybe(buffer_title_font)
+                    .shaping(text::Shaping::Advanced)
+                    .into()
             }
-            buffer::Upstream::Channel(_, channel) => text(channel.to_string())
-                .style(buffer_title_style)
-                .font_maybe(buffer_title_font)
-                .shaping(text::Shaping::Advanced)
-                .into(),
-            buffer::Upstream::Query(_, query) => text(query.to_string())
-                .style(buffer_title_style)
-                .font_maybe(buffer_title_font)
-                .shaping(text::Shaping::Advanced)
-                .into(),
+            buffer::Upstream::Channel(server, channel) => text(
+                config
+                    .alias(server, &channel.to_target(), casemapping)
+                    .map_or_else(|| channel.to_string(), str::to_string)
+            )
+            .style(buffer_title_style)
+            .font_maybe(buffer_title_font)
+            .shaping(text::Shaping::Advanced)
+            .into(),
+            buffer::Upstream::Query(server, query) => text(
+                config
+                    .alias(server, &query.to_target(), casemapping)
+                    .map_or_else(|| query.to_string(), str::to_string)
+            )
+            .style(buffer_title_style)
+            .font_maybe(buffer_title_font)
+            .shaping(text::Shaping::Advanced)
+            .into(),
         })
         .padding(Padding::default().left(left_padding))
         .align_y(iced::Alignment::Center),
@@ -907,8 +1322,35 @@ fn upstream_buffer_button<'a>(
                 }
             });
 
-    let entries =
-        Entry::list(&buffer, panes.len(), open, focus, supports_detach);
+    let base = tooltip(
+        base,
+        authentication
+            .tooltip
+            .or(details)
+            .filter(|_| config.tooltips),
+        match position {
+            sidebar::Position::Left => tooltip::Position::Right,
+            sidebar::Position::Right => tooltip::Position::Left,
+            sidebar::Position::Top => tooltip::Position::Bottom,
+            sidebar::Position::Bottom => tooltip::Position::Top,
+        },
+        theme,
+    );
+
+    let base = mouse_area(base)
+        .on_enter(Message::Hovered(buffer.clone()))
+        .on_exit(Message::Unhovered(buffer.clone()));
+
+    let entries = Entry::list(
+        &buffer,
+        panes.len(),
+        open,
+        focus,
+        supports_detach,
+        authentication.can_reauthenticate,
+        is_pinned,
+    );
+    let labels_buffer = buffer.clone();
 
     if entries.is_empty() || !connected {
         base.into()
@@ -919,72 +1361,71 @@ fn upstream_buffer_button<'a>(
             context_menu::ToggleBehavior::KeepOpen,
             base,
             entries,
-            move |entry, length| {
-                let (content, message) = match entry {
-                    Entry::MarkServerAsRead => (
-                        "Mark entire server as read",
+            move |entry, length, focused| {
+                let message = match entry {
+                    Entry::Channel(entry) => {
+                        let context = buffer.channel().map(|channel| {
+                            crate::buffer::context_menu::Context::Channel {
+                                server: buffer.server(),
+                                channel,
+                            }
+                        });
+
+                        return entry
+                            .view(context, length, focused, config, theme)
+                            .map(Message::ContextMenu);
+                    }
+                    Entry::MarkServerAsRead => {
                         if server_has_unread {
                             Some(Message::MarkServerAsRead(
                                 buffer.server().clone(),
                             ))
                         } else {
                             None
-                        },
-                    ),
-                    Entry::MarkAsRead => (
-                        if matches!(&buffer, buffer::Upstream::Server(_)) {
-                            "Mark server buffer as read"
-                        } else {
-                            "Mark as read"
-                        },
+                        }
+                    }
+                    Entry::Reauthenticate => {
+                        Some(Message::Reauthenticate(buffer.server().clone()))
+                    }
+                    Entry::Identity => {
+                        Some(Message::Identity(buffer.server().clone()))
+                    }
+                    Entry::Print => Some(Message::Print(buffer.clone())),
+                    Entry::MarkAsRead => {
                         if has_unread {
                             Some(Message::MarkAsRead(buffer.clone()))
                         } else {
                             None
-                        },
-                    ),
-                    Entry::NewPane => {
-                        ("Open in new pane", Some(Message::New(buffer.clone())))
+                        }
+                    }
+                    Entry::NewPane => Some(Message::New(buffer.clone())),
+                    Entry::Popout => Some(Message::Popout(buffer.clone())),
+                    Entry::Replace => Some(Message::Replace(buffer.clone())),
+                    Entry::OpenAnother(action) => {
+                        Some(Message::OpenAnother(buffer.clone(), action))
                     }
-                    Entry::Popout => (
-                        "Open in new window",
-                        Some(Message::Popout(buffer.clone())),
-                    ),
-                    Entry::Replace => (
-                        "Replace current pane",
-                        Some(Message::Replace(buffer.clone())),
-                    ),
                     Entry::Close(window, pane) => {
-                        ("Close pane", Some(Message::Close(window, pane)))
+                        Some(Message::Close(window, pane))
                     }
-                    Entry::Swap(window, pane) => (
-                        "Swap with current pane",
-                        Some(Message::Swap(window, pane)),
-                    ),
-                    Entry::Detach => (
-                        "Detach from channel",
-                        Some(Message::Detach(buffer.clone())),
-                    ),
-                    Entry::Leave => (
-                        match &buffer {
-                            buffer::Upstream::Server(_) => "Leave server",
-                            buffer::Upstream::Channel(_, _) => "Leave channel",
-                            buffer::Upstream::Query(_, _) => "Close query",
-                        },
-                        Some(Message::Leave(buffer.clone())),
-                    ),
+                    Entry::Swap(window, pane) => {
+                        Some(Message::Swap(window, pane))
+                    }
+                    Entry::Detach => Some(Message::Detach(buffer.clone())),
+                    Entry::Pin(_) => Some(Message::TogglePin(buffer.clone())),
+                    Entry::Leave => Some(Message::Leave(buffer.clone())),
                 };
 
-                button(text(content))
+                button(text(entry.label(&buffer, config).unwrap_or_default()))
                     .width(length)
                     .padding(5)
-                    .style(|theme, status| {
-                        theme::button::primary(theme, status, false)
+                    .style(move |theme, status| {
+                        theme::button::context_menu(theme, status, focused)
                     })
                     .on_press_maybe(message)
                     .into()
             },
         )
+        .labels(move |entry| entry.label(&labels_buffer, config))
         .into()
     }
 }
@@ -7,8 +7,10 @@ use futures::TryFutureExt;
 use iced::Length::*;
 use iced::alignment::Vertical;
 use iced::widget::text::LineHeight;
-use iced::widget::{button, center, column, container, row, text_input};
-use iced::{Color, Length, Task, Vector, alignment, clipboard};
+use iced::widget::{
+    button, center, column, container, row, scrollable, text, text_input,
+};
+use iced::{Color, Length, Task, Vector, alignment, clipboard, task};
 use strum::IntoEnumIterator;
 use tokio::time;
 
@@ -17,7 +19,10 @@ use crate::widget::{
     Element, color_picker, combo_box, font_style_pick_list, tooltip,
 };
 use crate::window::{self, Window};
-use crate::{icon, widget};
+use crate::{dialog, icon, widget};
+
+/// Smallest size the picker and component list fit in
+const MIN_SIZE: iced::Size = iced::Size::new(555.0, 300.0);
 
 #[derive(Debug, Clone)]
 pub enum Event {
@@ -31,7 +36,9 @@ pub enum Message {
     FontStyle(Option<FontStyle>),
     Component(Component),
     HexInput(String),
+    ThemeName(String),
     Save,
+    SaveAsNew,
     Apply,
     Discard,
     Revert,
@@ -50,24 +57,36 @@ pub struct ThemeEditor {
     combo_box: combo_box::State<Component>,
     component: Component,
     hex_input: Option<String>,
+    theme_name: String,
     save_result: Option<bool>,
+    save_dialog: Option<task::Handle>,
     copied: bool,
 }
 
 impl ThemeEditor {
+    /// Opens the editor where it was last placed (`saved`), otherwise
+    /// offset from the main window.
     pub fn open(
         main_window: &Window,
+        saved: Option<data::Window>,
         config: &Config,
     ) -> (Self, Task<window::Id>) {
         let (window, task) = window::open(window::Settings {
-            // Just big enough to show all components in combobox
-            size: iced::Size::new(555.0, 300.0),
-            resizable: false,
-            position: main_window
-                .position
-                .map(|point| {
-                    window::Position::Specific(point + Vector::new(20.0, 20.0))
+            // Room for the component list next to the picker
+            size: saved
+                .as_ref()
+                .map_or(iced::Size::new(760.0, 420.0), |saved| saved.size)
+                .max(MIN_SIZE),
+            min_size: Some(MIN_SIZE),
+            resizable: true,
+            position: saved
+                .and_then(|saved| saved.position)
+                .or_else(|| {
+                    main_window
+                        .position
+                        .map(|point| point + Vector::new(20.0, 20.0))
                 })
+                .map(window::Position::Specific)
                 .unwrap_or_default(),
             exit_on_close_request: false,
             ..window::settings(config)
@@ -81,7 +100,9 @@ impl ThemeEditor {
                 // since picker is same color as background
                 component: Component::Text(Text::Primary),
                 hex_input: None,
+                theme_name: String::new(),
                 save_result: None,
+                save_dialog: None,
                 copied: false,
             },
             task,
@@ -137,17 +158,41 @@ impl ThemeEditor {
 
                 self.hex_input = Some(input);
             }
+            Message::ThemeName(name) => {
+                self.theme_name = name;
+            }
             Message::Save => {
-                let task = async move {
-                    rfd::AsyncFileDialog::new()
-                        .set_directory(Config::themes_dir())
-                        .set_file_name("custom-theme.toml")
-                        .save_file()
-                        .await
-                        .map(|handle| handle.path().to_path_buf())
+                let (task, handle) =
+                    dialog::save_file("custom-theme.toml".to_string(), || {
+                        Some(Config::themes_dir())
+                    });
+
+                self.save_dialog = Some(handle);
+
+                return (task.map(Message::SavePath), None);
+            }
+            Message::SaveAsNew => {
+                let Some(name) = theme_name(&self.theme_name) else {
+                    return (Task::none(), None);
                 };
 
-                return (Task::perform(task, Message::SavePath), None);
+                let path = Config::themes_dir().join(format!("{name}.toml"));
+
+                log::debug!("Saving new theme to {path:?}");
+
+                let styles = *theme.styles();
+
+                // Keep previewing under the new name so the editor and the
+                // themes list agree once the themes are reloaded
+                *theme = theme.preview(data::Theme::new(name, styles));
+
+                return (
+                    Task::perform(
+                        styles.save(path).map_err(|e| e.to_string()),
+                        Message::Saved,
+                    ),
+                    None,
+                );
             }
             Message::Apply => {
                 // Keep theme in preview mode, it'll get overwritten the next time they
@@ -209,8 +254,12 @@ impl ThemeEditor {
 
                 return (Task::none(), None);
             }
-            Message::SavePath(None) => {}
+            Message::SavePath(None) => {
+                self.save_dialog = None;
+            }
             Message::SavePath(Some(path)) => {
+                self.save_dialog = None;
+
                 log::debug!("Saving theme to {path:?}");
 
                 let styles = *theme.styles();
@@ -331,7 +380,23 @@ impl ThemeEditor {
             })
         });
 
-        let content = column![
+        let name_input = text_input("New theme name", &self.theme_name)
+            .on_input(Message::ThemeName)
+            .on_submit(Message::SaveAsNew)
+            .style(theme::text_input::primary);
+        let save_as_new = button(
+            container("Save as New Theme")
+                .align_x(alignment::Horizontal::Center)
+                .width(Fill),
+        )
+        .padding(5)
+        .width(Fill)
+        .style(|theme, status| theme::button::secondary(theme, status, false))
+        .on_press_maybe(
+            theme_name(&self.theme_name).map(|_| Message::SaveAsNew),
+        );
+
+        let editor = column![
             row![
                 container(component).width(Fill),
                 container(hex_input).width(80),
@@ -344,8 +409,19 @@ impl ThemeEditor {
             .spacing(4),
             color_picker,
             row![apply, save].spacing(4),
+            row![container(name_input).width(Fill), save_as_new].spacing(4),
         ]
-        .spacing(8);
+        .spacing(8)
+        .width(Fill);
+
+        let list = scrollable(column(components().map(|component| {
+            component_entry(component, component == self.component)
+        })))
+        .style(theme::scrollable::hidden)
+        .width(200)
+        .height(Fill);
+
+        let content = row![list, editor].spacing(8);
 
         container(content)
             .width(Length::Fill)
@@ -375,6 +451,54 @@ fn icon<'a>(
     )
 }
 
+fn component_entry<'a>(
+    component: Component,
+    selected: bool,
+) -> Element<'a, Message> {
+    let swatch = container(text(""))
+        .width(12)
+        .height(12)
+        .style(move |theme| {
+            theme::container::color_swatch(
+                theme,
+                component.color(theme.styles()),
+            )
+        });
+
+    button(
+        row![
+            swatch,
+            text(component.to_string()).style(theme::text::primary)
+        ]
+        .align_y(Vertical::Center)
+        .spacing(6),
+    )
+    .padding([2, 4])
+    .width(Fill)
+    .style(move |theme, status| theme::button::primary(theme, status, selected))
+    .on_press(Message::Component(component))
+    .into()
+}
+
+/// Theme name (and file stem) for a theme saved from the editor, derived
+/// from the name the user entered.
+fn theme_name(name: &str) -> Option<String> {
+    let stem = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>();
+    let stem = stem.trim_matches('-');
+
+    (!stem.is_empty()).then(|| stem.to_string())
+}
+
 fn success_icon<'a>() -> Element<'a, Message> {
     button(center(icon::checkmark().style(theme::text::success)))
         .width(22)
@@ -655,13 +779,18 @@ impl Text {
 pub enum Buffer {
     Action,
     Background,
+    BackgroundAlt,
     BackgroundTextInput,
     BackgroundTitleBar,
     Border,
     BorderSelected,
     Code,
     Highlight,
+    HighlightBackground,
+    HighlightBorder,
+    Hover,
     Nickname,
+    NicknameHighlight,
     NicknameOffline,
     Selection,
     #[strum(to_string = "server-message-{0}")]
@@ -676,13 +805,18 @@ impl Buffer {
         match self {
             Buffer::Action => Some(styles.action.color),
             Buffer::Background => Some(styles.background),
+            Buffer::BackgroundAlt => styles.background_alt,
             Buffer::BackgroundTextInput => Some(styles.background_text_input),
             Buffer::BackgroundTitleBar => Some(styles.background_title_bar),
             Buffer::Border => Some(styles.border),
             Buffer::BorderSelected => Some(styles.border_selected),
             Buffer::Code => Some(styles.code.color),
             Buffer::Highlight => Some(styles.highlight),
+            Buffer::HighlightBackground => styles.highlight_background,
+            Buffer::HighlightBorder => styles.highlight_border,
+            Buffer::Hover => styles.hover,
             Buffer::Nickname => Some(styles.nickname.color),
+            Buffer::NicknameHighlight => styles.nickname_highlight.color,
             Buffer::NicknameOffline => styles.nickname_offline.color,
             Buffer::Selection => Some(styles.selection),
             Buffer::ServerMessages(server_messages) => {
@@ -698,13 +832,20 @@ impl Buffer {
         match self {
             Buffer::Action => Some(styles.action.font_style),
             Buffer::Background => None,
+            Buffer::BackgroundAlt => None,
             Buffer::BackgroundTextInput => None,
             Buffer::BackgroundTitleBar => None,
             Buffer::Border => None,
             Buffer::BorderSelected => None,
             Buffer::Code => Some(styles.code.font_style),
             Buffer::Highlight => None,
+            Buffer::HighlightBackground => None,
+            Buffer::HighlightBorder => None,
+            Buffer::Hover => None,
             Buffer::Nickname => Some(styles.nickname.font_style),
+            Buffer::NicknameHighlight => {
+                Some(styles.nickname_highlight.font_style)
+            }
             Buffer::NicknameOffline => Some(styles.nickname_offline.font_style),
             Buffer::Selection => None,
             Buffer::ServerMessages(server_messages) => {
@@ -734,6 +875,7 @@ impl Buffer {
                     styles.background = color;
                 }
             }
+            Buffer::BackgroundAlt => styles.background_alt = color,
             Buffer::BackgroundTextInput => {
                 if let Some(color) = color {
                     styles.background_text_input = color;
@@ -765,12 +907,19 @@ impl Buffer {
                     styles.highlight = color;
                 }
             }
+            Buffer::HighlightBackground => styles.highlight_background = color,
+            Buffer::HighlightBorder => styles.highlight_border = color,
+            Buffer::Hover => styles.hover = color,
             Buffer::Nickname => {
                 if let Some(color) = color {
                     styles.nickname.color = color;
                 }
                 styles.nickname.font_style = font_style;
             }
+            Buffer::NicknameHighlight => {
+                styles.nickname_highlight.color = color;
+                styles.nickname_highlight.font_style = font_style;
+            }
             Buffer::NicknameOffline => {
                 styles.nickname_offline.color = color;
                 styles.nickname_offline.font_style = font_style;
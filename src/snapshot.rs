@@ -0,0 +1,377 @@
+//! Renders representative parts of the UI for each theme into images, so
+//! theme authors can preview a theme without connecting anywhere.
+//!
+//! ```sh
+//! cargo run --features snapshots -- --snapshot-themes <directory> [theme]
+//! ```
+//!
+//! Snapshots missing from `<directory>` are written, existing ones are
+//! compared against and reported when they differ.
+
+use std::path::{Path, PathBuf};
+
+use data::isupport::{self, CaseMap};
+use data::message::tests::{
+    SERDE_IRC_MESSAGES, message_from_irc_message, messages_from_broadcast,
+    serde_broadcasts,
+};
+use data::user::Nick;
+use data::{Config, Server, User, target};
+use iced::widget::{button, column, container, row, text};
+use iced::{Length, Padding, Size};
+use thiserror::Error;
+
+use crate::buffer::context_menu::{self, Context, Entry};
+use crate::buffer::message_view::{ChannelQueryLayout, TargetInfo};
+use crate::buffer::scroll_view::LayoutMessage;
+use crate::widget::Element;
+use crate::widget::context_menu::build_menu;
+use crate::{Theme, font, icon, theme};
+
+const FLAG: &str = "--snapshot-themes";
+
+#[derive(Debug)]
+pub struct Args {
+    output: PathBuf,
+    theme: Option<String>,
+}
+
+impl Args {
+    /// `None` unless the snapshot flag was passed.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Option<Self> {
+        let mut args = args.into_iter().skip_while(|arg| arg != FLAG).skip(1);
+
+        let output = args.next()?;
+        let theme = args.next().filter(|arg| !arg.starts_with('-'));
+
+        Some(Self {
+            output: PathBuf::from(output),
+            theme,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("no theme named {0}")]
+    UnknownTheme(String),
+    #[error("failed to render snapshot: {0}")]
+    Render(#[from] iced_test::Error),
+    #[error("{0} snapshots no longer match, see above")]
+    Mismatched(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Scene {
+    Buffer,
+    ContextMenus,
+    Sidebar,
+}
+
+impl Scene {
+    const ALL: [Scene; 3] =
+        [Scene::Buffer, Scene::ContextMenus, Scene::Sidebar];
+
+    fn name(self) -> &'static str {
+        match self {
+            Scene::Buffer => "buffer",
+            Scene::ContextMenus => "context-menus",
+            Scene::Sidebar => "sidebar",
+        }
+    }
+
+    fn size(self) -> Size {
+        match self {
+            Scene::Buffer => Size::new(900.0, 1600.0),
+            Scene::ContextMenus => Size::new(900.0, 560.0),
+            Scene::Sidebar => Size::new(240.0, 320.0),
+        }
+    }
+}
+
+/// Snapshots every scene for the themes in `config`, or only `args.theme`.
+pub fn run(args: &Args, config: Option<&Config>) -> Result<(), Error> {
+    let themes = config.map_or_else(
+        || vec![data::Theme::default()],
+        |config| config.appearance.all.clone(),
+    );
+
+    let themes = match &args.theme {
+        Some(name) => vec![
+            themes
+                .into_iter()
+                .find(|theme| &theme.name == name)
+                .ok_or_else(|| Error::UnknownTheme(name.clone()))?,
+        ],
+        None => themes,
+    };
+
+    // Scenes are always rendered with the default configuration, so user
+    // settings don't show up as styling changes
+    let render_config = Config::default();
+    let fixture = Fixture::new();
+    let mut mismatched = 0;
+
+    for data_theme in themes {
+        let directory = args.output.join(&data_theme.name);
+        let theme = Theme::from(data_theme);
+
+        for scene in Scene::ALL {
+            let path = directory.join(scene.name());
+
+            if snapshot(scene, &path, &fixture, &render_config, &theme)? {
+                println!("{}", path.display());
+            } else {
+                println!("{} (changed)", path.display());
+                mismatched += 1;
+            }
+        }
+    }
+
+    if mismatched > 0 {
+        Err(Error::Mismatched(mismatched))
+    } else {
+        Ok(())
+    }
+}
+
+/// Renders `scene` and compares it with the image at `path`, writing the
+/// image when there is none yet.
+fn snapshot(
+    scene: Scene,
+    path: &Path,
+    fixture: &Fixture,
+    config: &Config,
+    theme: &Theme,
+) -> Result<bool, Error> {
+    let content = match scene {
+        Scene::Buffer => buffer(fixture, config, theme),
+        Scene::ContextMenus => context_menus(fixture, config, theme),
+        Scene::Sidebar => sidebar(config),
+    };
+
+    let settings = iced::Settings {
+        fonts: font::load(),
+        default_font: font::MONO.clone().into(),
+        ..iced::Settings::default()
+    };
+
+    let mut ui = iced_test::Simulator::with_size(
+        settings,
+        scene.size(),
+        container(content)
+            .padding(8)
+            .width(Length::Fill)
+            .height(Length::Fill),
+    );
+
+    Ok(ui.snapshot(theme)?.matches_image(path)?)
+}
+
+/// Sample messages covering every kind of message, along with the server
+/// and channel they are shown in.
+struct Fixture {
+    server: Server,
+    channel: target::Channel,
+    our_user: User,
+    messages: Vec<data::Message>,
+    url: Option<String>,
+    clients: data::client::Map,
+}
+
+impl Fixture {
+    fn new() -> Self {
+        let messages: Vec<data::Message> = SERDE_IRC_MESSAGES
+            .iter()
+            .map(|irc_message| message_from_irc_message(irc_message))
+            .chain(
+                serde_broadcasts()
+                    .into_iter()
+                    .flat_map(messages_from_broadcast),
+            )
+            .collect();
+
+        let url = messages.iter().find_map(|message| match &message.content {
+            data::message::Content::Fragments(fragments) => fragments
+                .iter()
+                .find_map(data::message::Fragment::url)
+                .map(ToString::to_string),
+            _ => None,
+        });
+
+        Self {
+            server: Server {
+                name: "Snapshot Server".into(),
+                network: None,
+            },
+            channel: target::Channel::from_str(
+                "#halloy",
+                isupport::DEFAULT_CHANTYPES,
+                CaseMap::default(),
+            ),
+            our_user: User::from(Nick::from_str(
+                "our_nick",
+                CaseMap::default(),
+            )),
+            messages,
+            url,
+            clients: data::client::Map::default(),
+        }
+    }
+}
+
+fn buffer<'a>(
+    fixture: &'a Fixture,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, ()> {
+    let layout = ChannelQueryLayout {
+        config,
+        chantypes: isupport::DEFAULT_CHANTYPES,
+        casemapping: CaseMap::default(),
+        prefix: &[],
+        server: &fixture.server,
+        clients: &fixture.clients,
+        theme,
+        target: TargetInfo::Channel {
+            channel: &fixture.channel,
+            our_user: Some(&fixture.our_user),
+            users: None,
+        },
+        density: config.buffer.density,
+    };
+
+    let messages = column(fixture.messages.iter().filter_map(|message| {
+        layout
+            .format(message, None, None, None)
+            .map(|element| element.map(|_| ()))
+    }))
+    .spacing(2);
+
+    container(messages)
+        .padding(8)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(|theme| theme::container::buffer(theme, true))
+        .into()
+}
+
+fn context_menus<'a>(
+    fixture: &'a Fixture,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, ()> {
+    let user = Context::User {
+        server: &fixture.server,
+        prefix: &[],
+        channel: Some(&fixture.channel),
+        user: &fixture.our_user,
+        current_user: Some(&fixture.our_user),
+        clients: &fixture.clients,
+    };
+
+    let mut menus = vec![
+        menu(
+            Entry::user_list(true, Some(&fixture.our_user), false, config),
+            user,
+            config,
+            theme,
+        ),
+        menu(
+            Entry::channel_list(),
+            Context::Channel {
+                server: &fixture.server,
+                channel: &fixture.channel,
+            },
+            config,
+            theme,
+        ),
+    ];
+
+    if let Some(message) = fixture.messages.first() {
+        menus.push(menu(
+            Entry::message_list(message),
+            Context::Message(message),
+            config,
+            theme,
+        ));
+    }
+
+    if let Some(url) = &fixture.url {
+        menus.push(menu(
+            Entry::url_list(url, config),
+            Context::Url(url),
+            config,
+            theme,
+        ));
+    }
+
+    row(menus).spacing(16).into()
+}
+
+/// An open context menu with the first entry focused.
+fn menu<'a>(
+    entries: Vec<Entry>,
+    context: Context<'a>,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, ()> {
+    let entry = move |entry: Entry, length, focused| {
+        entry.view(Some(context), length, focused, config, theme)
+    };
+
+    build_menu::<_, context_menu::Message, _, _>(&entries, &entry, Some(0))
+        .map(|_| ())
+}
+
+/// Sidebar buttons in each of the states a buffer can be in.
+fn sidebar(config: &Config) -> Element<'static, ()> {
+    let entry = |label: &'static str,
+                 indicator: Option<Element<'static, ()>>,
+                 is_focused: bool,
+                 is_open: bool| {
+        button(
+            row![text(label).style(theme::text::primary), indicator]
+                .spacing(6)
+                .padding(Padding::default().bottom(1)),
+        )
+        .width(Length::Fill)
+        .on_press(())
+        .style(move |theme, status| {
+            theme::button::sidebar_buffer(theme, status, is_focused, is_open)
+        })
+    };
+
+    let connected = icon::connected()
+        .style(theme::text::primary)
+        .size(config.sidebar.server_icon_size);
+
+    container(
+        column![
+            entry("Snapshot Server", Some(connected.into()), false, false),
+            entry("#focused", None, true, true),
+            entry("#open", None, false, true),
+            entry(
+                "#unread",
+                Some(icon::dot().style(theme::text::unread_indicator).into()),
+                false,
+                false,
+            ),
+            entry(
+                "#highlighted",
+                Some(
+                    icon::dot().style(theme::text::highlight_indicator).into()
+                ),
+                false,
+                false,
+            ),
+            entry("query", None, false, false),
+        ]
+        .spacing(1),
+    )
+    .padding(4)
+    .width(Length::Fill)
+    .height(Length::Fill)
+    .style(theme::container::general)
+    .into()
+}
@@ -0,0 +1,114 @@
+//! Startup profiling, enabled with `halloy --profile-startup`.
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+static PROFILE: OnceLock<Mutex<Profile>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    ConfigParse,
+    FontLoad,
+    FirstFrame,
+    HistoryLoad,
+}
+
+impl Stage {
+    const ALL: [Stage; 4] = [
+        Stage::ConfigParse,
+        Stage::FontLoad,
+        Stage::FirstFrame,
+        Stage::HistoryLoad,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Stage::ConfigParse => "config parse",
+            Stage::FontLoad => "font loading",
+            Stage::FirstFrame => "first frame",
+            Stage::HistoryLoad => "history load",
+        }
+    }
+
+    // Stages measured from launch rather than by their own duration
+    fn since_launch(self) -> bool {
+        matches!(self, Stage::FirstFrame | Stage::HistoryLoad)
+    }
+}
+
+#[derive(Debug)]
+struct Profile {
+    launched: Instant,
+    timings: Vec<(Stage, Duration)>,
+}
+
+pub fn enable(launched: Instant) {
+    let _ = PROFILE.set(Mutex::new(Profile {
+        launched,
+        timings: vec![],
+    }));
+}
+
+pub fn is_enabled() -> bool {
+    PROFILE.get().is_some()
+}
+
+/// Records how long `stage` took. Only the first timing of a stage is kept.
+pub fn record(stage: Stage, elapsed: Duration) {
+    let Some(profile) = PROFILE.get() else {
+        return;
+    };
+    let mut profile = profile.lock().unwrap();
+
+    if profile
+        .timings
+        .iter()
+        .any(|(recorded, _)| *recorded == stage)
+    {
+        return;
+    }
+
+    profile.timings.push((stage, elapsed));
+
+    if profile.timings.len() == Stage::ALL.len() {
+        report(&profile);
+    }
+}
+
+/// Records the time elapsed since launch for `stage`.
+pub fn mark(stage: Stage) {
+    let Some(launched) = PROFILE
+        .get()
+        .map(|profile| profile.lock().unwrap().launched)
+    else {
+        return;
+    };
+
+    record(stage, launched.elapsed());
+}
+
+fn report(profile: &Profile) {
+    let lines = Stage::ALL
+        .into_iter()
+        .filter_map(|stage| {
+            profile
+                .timings
+                .iter()
+                .find(|(recorded, _)| *recorded == stage)
+                .map(|(_, elapsed)| {
+                    format!(
+                        "  {:<14}{:>8.1} ms{}",
+                        stage.label(),
+                        elapsed.as_secs_f64() * 1000.0,
+                        if stage.since_launch() {
+                            " (since launch)"
+                        } else {
+                            ""
+                        }
+                    )
+                })
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    log::info!("startup profile:\n{lines}");
+}
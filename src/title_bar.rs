@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use data::{Config, Server};
+use iced::widget::{button, center, container, mouse_area, row, space, text};
+use iced::{Length, alignment};
+
+use crate::widget::{Element, tooltip};
+use crate::{Theme, font, icon, theme};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Message {
+    Drag,
+    Minimize,
+    ToggleMaximize,
+    Close,
+}
+
+/// Title bar drawn by Halloy in place of the system's when the main window
+/// is borderless.
+pub fn view<'a>(
+    lag: Option<(Server, Duration)>,
+    is_maximized: bool,
+    config: &'a Config,
+    theme: &'a Theme,
+) -> Element<'a, Message> {
+    let control =
+        |content: Element<'a, Message>, message: Message, label: &'a str| {
+            tooltip(
+                button(center(content))
+                    .padding(5)
+                    .width(22)
+                    .height(22)
+                    .on_press(message)
+                    .style(|theme, status| {
+                        theme::button::secondary(theme, status, false)
+                    }),
+                config.tooltips.then_some(label),
+                tooltip::Position::Bottom,
+                theme,
+            )
+        };
+
+    let lag = lag.map(|(server, lag)| {
+        text(format!("{server} {} ms", lag.as_millis()))
+            .style(theme::text::secondary)
+            .font_maybe(theme::font_style::secondary(theme).map(font::get))
+    });
+
+    // Empty space is left to the drag region, which also maximizes on
+    // double click like the system's title bar
+    let title = mouse_area(
+        container(
+            row![
+                text("Halloy")
+                    .style(theme::text::buffer_title_bar)
+                    .font_maybe(
+                        theme::font_style::buffer_title_bar(theme)
+                            .map(font::get),
+                    ),
+                space::horizontal(),
+                lag,
+            ]
+            .spacing(8)
+            .align_y(alignment::Vertical::Center),
+        )
+        .width(Length::Fill)
+        .height(22)
+        .padding([0, 4])
+        .align_y(alignment::Vertical::Center),
+    )
+    .on_press(Message::Drag)
+    .on_double_click(Message::ToggleMaximize);
+
+    let maximize = if is_maximized {
+        control(icon::restore().into(), Message::ToggleMaximize, "Restore")
+    } else {
+        control(icon::maximize().into(), Message::ToggleMaximize, "Maximize")
+    };
+
+    container(
+        row![
+            title,
+            control(text("\u{2013}").into(), Message::Minimize, "Minimize"),
+            maximize,
+            control(icon::cancel().into(), Message::Close, "Close"),
+        ]
+        .spacing(2)
+        .align_y(alignment::Vertical::Center),
+    )
+    .padding(6)
+    .width(Length::Fill)
+    .style(theme::container::buffer_title_bar)
+    .into()
+}
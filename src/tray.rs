@@ -0,0 +1,269 @@
+use std::sync::LazyLock;
+
+use futures::Stream;
+use futures::channel::mpsc;
+use iced::Subscription;
+use image::RgbaImage;
+use tray_icon::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tray_icon::{
+    Icon, MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder,
+    TrayIconEvent,
+};
+
+use crate::i18n;
+
+const TOGGLE_WINDOW: &str = "toggle_window";
+const MARK_ALL_AS_READ: &str = "mark_all_as_read";
+const QUIT: &str = "quit";
+
+static LOGO: LazyLock<Option<RgbaImage>> = LazyLock::new(|| {
+    image::load_from_memory_with_format(
+        include_bytes!(
+            "../assets/linux/icons/hicolor/64x64/apps/org.squidowl.halloy.png"
+        ),
+        image::ImageFormat::Png,
+    )
+    .ok()
+    .map(|image| image.into_rgba8())
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    ToggleWindow,
+    MarkAllAsRead,
+    Quit,
+}
+
+/// State shown by the tray icon.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Indicator {
+    #[default]
+    Idle,
+    Unread,
+    Highlight,
+}
+
+impl Indicator {
+    pub fn new(has_unread: bool, has_highlight: bool) -> Self {
+        if has_highlight {
+            Indicator::Highlight
+        } else if has_unread {
+            Indicator::Unread
+        } else {
+            Indicator::Idle
+        }
+    }
+
+    /// The logo, with a dot in the bottom right corner unless idle.
+    fn icon(self) -> Option<Icon> {
+        let mut image = LOGO.clone()?;
+
+        let color = match self {
+            Indicator::Idle => None,
+            Indicator::Unread => Some([0x6f, 0xb3, 0xd2, 0xff]),
+            Indicator::Highlight => Some([0xe0, 0x4f, 0x4f, 0xff]),
+        };
+
+        if let Some(color) = color {
+            let (width, height) = image.dimensions();
+            let radius = width.min(height) / 5;
+            let (cx, cy) = (width - radius - 1, height - radius - 1);
+
+            for (x, y, pixel) in image.enumerate_pixels_mut() {
+                let (dx, dy) = (x.abs_diff(cx), y.abs_diff(cy));
+
+                if dx * dx + dy * dy <= radius * radius {
+                    pixel.0 = color;
+                }
+            }
+        }
+
+        let (width, height) = image.dimensions();
+
+        Icon::from_rgba(image.into_raw(), width, height).ok()
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Menu(#[from] tray_icon::menu::Error),
+    #[error(transparent)]
+    Tray(#[from] tray_icon::Error),
+    #[cfg(target_os = "linux")]
+    #[error("failed to initialize gtk: {0}")]
+    Gtk(String),
+}
+
+pub struct Tray {
+    handle: Handle,
+    indicator: Indicator,
+}
+
+impl Tray {
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            handle: Handle::spawn()?,
+            indicator: Indicator::Idle,
+        })
+    }
+
+    pub fn set_indicator(&mut self, indicator: Indicator) {
+        if self.indicator != indicator {
+            self.indicator = indicator;
+            self.handle.set_indicator(indicator);
+        }
+    }
+}
+
+fn build() -> Result<TrayIcon, Error> {
+    let menu = Menu::new();
+
+    menu.append_items(&[
+        &MenuItem::with_id(
+            TOGGLE_WINDOW,
+            i18n::tr("tray-show-hide"),
+            true,
+            None,
+        ),
+        &MenuItem::with_id(
+            MARK_ALL_AS_READ,
+            i18n::tr("tray-mark-all-as-read"),
+            true,
+            None,
+        ),
+        &PredefinedMenuItem::separator(),
+        &MenuItem::with_id(QUIT, i18n::tr("tray-quit"), true, None),
+    ])?;
+
+    let mut builder = TrayIconBuilder::new()
+        .with_menu(Box::new(menu))
+        .with_menu_on_left_click(false)
+        .with_tooltip("Halloy");
+
+    if let Some(icon) = Indicator::Idle.icon() {
+        builder = builder.with_icon(icon);
+    }
+
+    Ok(builder.build()?)
+}
+
+// The tray icon is owned by the thread of the event loop, which is iced's
+// own on macOS and Windows
+#[cfg(not(target_os = "linux"))]
+struct Handle(TrayIcon);
+
+#[cfg(not(target_os = "linux"))]
+impl Handle {
+    fn spawn() -> Result<Self, Error> {
+        Ok(Self(build()?))
+    }
+
+    fn set_indicator(&self, indicator: Indicator) {
+        if let Err(error) = self.0.set_icon(indicator.icon()) {
+            log::warn!("failed to update tray icon: {error}");
+        }
+    }
+}
+
+// On Linux, the tray icon needs a gtk event loop, which runs on its own
+// thread until the handle is dropped
+#[cfg(target_os = "linux")]
+struct Handle(std::sync::mpsc::Sender<Indicator>);
+
+#[cfg(target_os = "linux")]
+impl Handle {
+    fn spawn() -> Result<Self, Error> {
+        use std::sync::mpsc::{TryRecvError, channel};
+        use std::time::Duration;
+
+        use gtk::glib::{self, ControlFlow};
+
+        let (sender, receiver) = channel::<Indicator>();
+        let (ready, is_ready) = channel();
+
+        std::thread::spawn(move || {
+            if let Err(error) = gtk::init() {
+                let _ = ready.send(Err(Error::Gtk(error.to_string())));
+                return;
+            }
+
+            let tray = match build() {
+                Ok(tray) => tray,
+                Err(error) => {
+                    let _ = ready.send(Err(error));
+                    return;
+                }
+            };
+
+            let _ = ready.send(Ok(()));
+
+            glib::timeout_add_local(Duration::from_millis(250), move || {
+                loop {
+                    match receiver.try_recv() {
+                        Ok(indicator) => {
+                            if let Err(error) = tray.set_icon(indicator.icon())
+                            {
+                                log::warn!(
+                                    "failed to update tray icon: {error}"
+                                );
+                            }
+                        }
+                        Err(TryRecvError::Empty) => {
+                            return ControlFlow::Continue;
+                        }
+                        Err(TryRecvError::Disconnected) => {
+                            gtk::main_quit();
+                            return ControlFlow::Break;
+                        }
+                    }
+                }
+            });
+
+            gtk::main();
+        });
+
+        is_ready
+            .recv()
+            .map_err(|error| Error::Gtk(error.to_string()))??;
+
+        Ok(Self(sender))
+    }
+
+    fn set_indicator(&self, indicator: Indicator) {
+        let _ = self.0.send(indicator);
+    }
+}
+
+pub fn events() -> Subscription<Event> {
+    Subscription::run(listen)
+}
+
+fn listen() -> impl Stream<Item = Event> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    let menu_sender = sender.clone();
+    MenuEvent::set_event_handler(Some(move |event: MenuEvent| {
+        let event = match event.id.as_ref() {
+            TOGGLE_WINDOW => Event::ToggleWindow,
+            MARK_ALL_AS_READ => Event::MarkAllAsRead,
+            QUIT => Event::Quit,
+            _ => return,
+        };
+
+        let _ = menu_sender.unbounded_send(event);
+    }));
+
+    TrayIconEvent::set_event_handler(Some(move |event: TrayIconEvent| {
+        if let TrayIconEvent::Click {
+            button: MouseButton::Left,
+            button_state: MouseButtonState::Up,
+            ..
+        } = event
+        {
+            let _ = sender.unbounded_send(Event::ToggleWindow);
+        }
+    }));
+
+    receiver
+}
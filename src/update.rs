@@ -0,0 +1,192 @@
+use std::path::PathBuf;
+
+use data::version::{DownloadError, Release};
+use data::{Config, Version};
+use iced::widget::{Space, button, column, container, row, scrollable, text};
+use iced::{Length, Task, alignment};
+
+use crate::widget::Element;
+use crate::{Theme, font, theme};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ToggleNotes,
+    OpenRelease,
+    Download,
+    Downloaded(Result<PathBuf, DownloadError>),
+    Reveal,
+    Dismiss,
+}
+
+/// Banner shown above the dashboard when a newer release is available.
+#[derive(Debug, Default)]
+pub struct Banner {
+    dismissed: bool,
+    show_notes: bool,
+    download: Download,
+}
+
+#[derive(Debug, Default)]
+enum Download {
+    #[default]
+    Idle,
+    InProgress,
+    Done(PathBuf),
+    Failed(DownloadError),
+}
+
+impl Banner {
+    pub fn update(
+        &mut self,
+        message: Message,
+        version: &Version,
+    ) -> Task<Message> {
+        match message {
+            Message::ToggleNotes => {
+                self.show_notes = !self.show_notes;
+            }
+            Message::OpenRelease => {
+                if let Some(release) = version.newer() {
+                    let _ = open::that_detached(&release.url);
+                }
+            }
+            Message::Download => {
+                if let Some(asset) =
+                    version.newer().and_then(|release| release.asset.clone())
+                    && !matches!(self.download, Download::InProgress)
+                {
+                    self.download = Download::InProgress;
+
+                    return Task::perform(
+                        data::version::download(asset),
+                        Message::Downloaded,
+                    );
+                }
+            }
+            Message::Downloaded(Ok(path)) => {
+                log::info!("Update downloaded to {}", path.display());
+                self.download = Download::Done(path);
+            }
+            Message::Downloaded(Err(error)) => {
+                log::warn!("Failed to download update: {error}");
+                self.download = Download::Failed(error);
+            }
+            Message::Reveal => {
+                if let Download::Done(path) = &self.download
+                    && let Some(dir) = path.parent()
+                {
+                    let _ = open::that_detached(dir);
+                }
+            }
+            Message::Dismiss => {
+                self.dismissed = true;
+            }
+        }
+
+        Task::none()
+    }
+
+    pub fn view<'a>(
+        &'a self,
+        version: &'a Version,
+        config: &'a Config,
+        theme: &'a Theme,
+    ) -> Option<Element<'a, Message>> {
+        if self.dismissed || !config.update.banner {
+            return None;
+        }
+
+        let release = version.newer()?;
+
+        let action = |label: &'a str, message: Option<Message>| {
+            button(text(label))
+                .padding([2, 8])
+                .style(|theme, status| {
+                    theme::button::secondary(theme, status, false)
+                })
+                .on_press_maybe(message)
+        };
+
+        let download: Element<'a, Message> =
+            match (&release.asset, &self.download) {
+                (None, _) => Space::new().into(),
+                (Some(_), Download::Idle) => {
+                    action("Download", Some(Message::Download)).into()
+                }
+                (Some(_), Download::InProgress) => {
+                    action("Downloading…", None).into()
+                }
+                (Some(_), Download::Done(_)) => {
+                    action("Show download", Some(Message::Reveal)).into()
+                }
+                (Some(_), Download::Failed(error)) => row![
+                    text(error.to_string())
+                        .style(theme::text::error)
+                        .font_maybe(
+                            theme::font_style::error(theme).map(font::get)
+                        ),
+                    action("Retry", Some(Message::Download)),
+                ]
+                .spacing(8)
+                .align_y(alignment::Vertical::Center)
+                .into(),
+            };
+
+        let header = row![
+            text(format!(
+                "Halloy {} is available (running {})",
+                release.version, version.current
+            ))
+            .style(theme::text::primary)
+            .font_maybe(theme::font_style::primary(theme).map(font::get))
+            .width(Length::Fill),
+            action(
+                if self.show_notes {
+                    "Hide changes"
+                } else {
+                    "What's new"
+                },
+                Some(Message::ToggleNotes),
+            ),
+            action("Release page", Some(Message::OpenRelease)),
+            download,
+            action("Dismiss", Some(Message::Dismiss)),
+        ]
+        .spacing(8)
+        .align_y(alignment::Vertical::Center);
+
+        let content = if self.show_notes {
+            column![header, notes(release, theme)].spacing(8)
+        } else {
+            column![header]
+        };
+
+        Some(
+            container(content)
+                .padding(8)
+                .width(Length::Fill)
+                .style(theme::container::buffer_title_bar)
+                .into(),
+        )
+    }
+}
+
+fn notes<'a>(release: &'a Release, theme: &'a Theme) -> Element<'a, Message> {
+    let notes = if release.notes.trim().is_empty() {
+        "No release notes"
+    } else {
+        release.notes.trim()
+    };
+
+    container(
+        scrollable(
+            text(notes)
+                .style(theme::text::secondary)
+                .font_maybe(theme::font_style::secondary(theme).map(font::get))
+                .width(Length::Fill),
+        )
+        .height(Length::Shrink),
+    )
+    .max_height(240)
+    .into()
+}
@@ -0,0 +1,178 @@
+//! Watches the configuration file, the themes folder and the scripts
+//! folder, so changes made in an editor are applied without reloading
+//! manually.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use data::Config;
+use data::environment::CONFIG_FILE_NAME;
+use futures::channel::mpsc;
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use iced::Subscription;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+/// Editors often save a file in several steps (e.g. truncate, write, then
+/// rename), so changes are reported once they've settled for this long.
+const SETTLE: Duration = Duration::from_millis(300);
+
+const THEMES_DIR_NAME: &str = "themes";
+
+#[derive(Debug, Clone)]
+pub enum Change {
+    /// Theme files were written or removed
+    Themes(Vec<PathBuf>),
+    /// The configuration file was written, which reloads themes as well
+    Config,
+    /// Scripts were written or removed
+    Scripts,
+}
+
+impl Change {
+    fn merge(self, other: Change) -> Change {
+        match (self, other) {
+            (Change::Themes(mut paths), Change::Themes(other)) => {
+                for path in other {
+                    if !paths.contains(&path) {
+                        paths.push(path);
+                    }
+                }
+
+                Change::Themes(paths)
+            }
+            _ => Change::Config,
+        }
+    }
+}
+
+pub fn subscription() -> Subscription<Change> {
+    Subscription::run(watch)
+}
+
+pub fn scripts() -> Subscription<Change> {
+    Subscription::run(watch_scripts)
+}
+
+fn watch() -> impl Stream<Item = Change> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    let config_dir = Config::config_dir();
+    let themes_dir = Config::themes_dir();
+
+    let watcher = notify::recommended_watcher(
+        move |result: notify::Result<notify::Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+
+            for path in event.paths {
+                // Compared by name, as the paths of events may be resolved
+                // differently than the watched directories
+                let change = if path
+                    .parent()
+                    .is_some_and(|parent| parent.ends_with(THEMES_DIR_NAME))
+                {
+                    path.extension()
+                        .is_some_and(|extension| extension == "toml")
+                        .then(|| Change::Themes(vec![path]))
+                } else {
+                    path.file_name()
+                        .is_some_and(|name| name == CONFIG_FILE_NAME)
+                        .then_some(Change::Config)
+                };
+
+                if let Some(change) = change {
+                    let _ = sender.unbounded_send(change);
+                }
+            }
+        },
+    );
+
+    // Directories are watched rather than the files themselves, since
+    // editors which save by renaming replace the watched file
+    let watcher = watcher.and_then(|mut watcher| {
+        watcher.watch(&config_dir, RecursiveMode::NonRecursive)?;
+        watcher.watch(&themes_dir, RecursiveMode::NonRecursive)?;
+
+        Ok(watcher)
+    });
+
+    match watcher {
+        Ok(watcher) => settled(watcher, receiver, Change::merge),
+        Err(error) => {
+            log::warn!("failed to watch configuration for changes: {error}");
+
+            stream::empty().boxed()
+        }
+    }
+}
+
+/// Scripts are watched apart from the configuration, as they live in the
+/// data directory and are always reloaded once changed.
+fn watch_scripts() -> impl Stream<Item = Change> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    let scripts_dir = data::script::dir();
+
+    let watcher = notify::recommended_watcher(
+        move |result: notify::Result<notify::Event>| {
+            let Ok(event) = result else {
+                return;
+            };
+
+            if matches!(event.kind, EventKind::Access(_)) {
+                return;
+            }
+
+            if event.paths.iter().any(|path| {
+                path.extension().is_some_and(|extension| extension == "lua")
+            }) {
+                let _ = sender.unbounded_send(Change::Scripts);
+            }
+        },
+    );
+
+    let watcher = watcher.and_then(|mut watcher| {
+        watcher.watch(&scripts_dir, RecursiveMode::NonRecursive)?;
+
+        Ok(watcher)
+    });
+
+    match watcher {
+        Ok(watcher) => settled(watcher, receiver, |change, _| change),
+        Err(error) => {
+            log::warn!("failed to watch scripts for changes: {error}");
+
+            stream::empty().boxed()
+        }
+    }
+}
+
+/// Reports changes once no further change arrived for [`SETTLE`], keeping
+/// `watcher` alive for as long as the stream is.
+fn settled(
+    watcher: impl Watcher + Send + 'static,
+    receiver: mpsc::UnboundedReceiver<Change>,
+    merge: fn(Change, Change) -> Change,
+) -> BoxStream<'static, Change> {
+    stream::unfold(
+        (watcher, receiver),
+        move |(watcher, mut receiver)| async move {
+            let mut change = receiver.next().await?;
+
+            while let Ok(Some(next)) =
+                tokio::time::timeout(SETTLE, receiver.next()).await
+            {
+                change = merge(change, next);
+            }
+
+            Some((change, (watcher, receiver)))
+        },
+    )
+    .boxed()
+}
@@ -11,6 +11,7 @@ pub use self::double_pass::double_pass;
 pub use self::font_style_pick_list::font_style_pick_list;
 pub use self::key_press::key_press;
 pub use self::message_content::message_content;
+pub use self::minimap::minimap;
 pub use self::modal::modal;
 pub use self::notify_visibility::notify_visibility;
 pub use self::on_resize::on_resize;
@@ -30,6 +31,7 @@ pub mod double_pass;
 pub mod font_style_pick_list;
 pub mod key_press;
 pub mod message_content;
+pub mod minimap;
 pub mod modal;
 pub mod notify_visibility;
 pub mod on_resize;
@@ -1,4 +1,5 @@
 use std::slice;
+use std::time::{Duration, Instant};
 
 use iced::advanced::widget::{Operation, operation, tree};
 use iced::advanced::{
@@ -7,11 +8,16 @@ use iced::advanced::{
 pub use iced::widget::container::{Style, StyleFn};
 use iced::widget::{column, container};
 use iced::{
-    Element, Event, Length, Point, Rectangle, Size, Task, Vector, mouse,
+    Element, Event, Length, Point, Rectangle, Size, Task, Vector, keyboard,
+    mouse,
 };
 
 use super::double_pass;
 
+// Typed characters are matched together if they follow each other within
+// this interval
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_secs(1);
+
 #[derive(Debug, Default, Clone, Copy)]
 pub enum MouseButton {
     Left,
@@ -39,12 +45,13 @@ pub fn context_menu<'a, T, Message, Theme, Renderer>(
     toggle_behavior: ToggleBehavior,
     base: impl Into<Element<'a, Message, Theme, Renderer>>,
     entries: Vec<T>,
-    entry: impl Fn(T, Length) -> Element<'a, Message, Theme, Renderer> + 'a,
+    entry: impl Fn(T, Length, bool) -> Element<'a, Message, Theme, Renderer> + 'a,
 ) -> ContextMenu<'a, T, Message, Theme, Renderer> {
     ContextMenu {
         base: base.into(),
         entries,
         entry: Box::new(entry),
+        label: None,
         activation_button: match activation_button {
             MouseButton::Left => iced::mouse::Button::Left,
             MouseButton::Right => iced::mouse::Button::Right,
@@ -58,7 +65,10 @@ pub fn context_menu<'a, T, Message, Theme, Renderer>(
 pub struct ContextMenu<'a, T, Message, Theme, Renderer> {
     base: Element<'a, Message, Theme, Renderer>,
     entries: Vec<T>,
-    entry: Box<dyn Fn(T, Length) -> Element<'a, Message, Theme, Renderer> + 'a>,
+    entry: Box<
+        dyn Fn(T, Length, bool) -> Element<'a, Message, Theme, Renderer> + 'a,
+    >,
+    label: Option<Box<dyn Fn(T) -> Option<String> + 'a>>,
     activation_button: iced::mouse::Button,
     anchor: Anchor,
     toggle_behavior: ToggleBehavior,
@@ -66,10 +76,29 @@ pub struct ContextMenu<'a, T, Message, Theme, Renderer> {
     menu: Option<Element<'a, Message, Theme, Renderer>>,
 }
 
+impl<'a, T, Message, Theme, Renderer>
+    ContextMenu<'a, T, Message, Theme, Renderer>
+{
+    /// Sets the labels typed characters are matched against. Entries without
+    /// a label are skipped when navigating with the keyboard.
+    pub fn labels(self, label: impl Fn(T) -> Option<String> + 'a) -> Self {
+        Self {
+            label: Some(Box::new(label)),
+            ..self
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct State {
     pub status: Status,
     menu_tree: widget::Tree,
+    // Entry focused with the keyboard
+    focused: Option<usize>,
+    // Focused entry the cached menu was built with
+    menu_focused: Option<usize>,
+    type_ahead: String,
+    type_ahead_at: Option<Instant>,
 }
 
 impl State {
@@ -77,6 +106,10 @@ impl State {
         State {
             status: Status::Closed,
             menu_tree: widget::Tree::empty(),
+            focused: None,
+            menu_focused: None,
+            type_ahead: String::new(),
+            type_ahead_at: None,
         }
     }
 }
@@ -166,10 +199,7 @@ where
     }
 
     fn state(&self) -> tree::State {
-        tree::State::new(State {
-            status: Status::Closed,
-            menu_tree: widget::Tree::empty(),
-        })
+        tree::State::new(State::new())
     }
 
     fn children(&self) -> Vec<widget::Tree> {
@@ -336,6 +366,7 @@ where
             &mut self.menu,
             &self.entries,
             &self.entry,
+            self.label.as_deref(),
             translation,
         );
 
@@ -352,9 +383,13 @@ where
     }
 }
 
-fn build_menu<'a, T, Message, Theme, Renderer>(
+/// The menu shown for `entries` once opened, with `focused` highlighted.
+pub fn build_menu<'a, T, Message, Theme, Renderer>(
     entries: &[T],
-    entry: &(dyn Fn(T, Length) -> Element<'a, Message, Theme, Renderer> + 'a),
+    entry: &(
+         dyn Fn(T, Length, bool) -> Element<'a, Message, Theme, Renderer> + 'a
+     ),
+    focused: Option<usize>,
 ) -> Element<'a, Message, Theme, Renderer>
 where
     T: Copy + 'a,
@@ -367,11 +402,12 @@ where
     let build_menu =
         |length,
          view: &(
-              dyn Fn(T, Length) -> Element<'a, Message, Theme, Renderer> + 'a
+              dyn Fn(T, Length, bool) -> Element<'a, Message, Theme, Renderer>
+                  + 'a
           )| {
-            container(column(
-                entries.iter().copied().map(|entry| view(entry, length)),
-            ))
+            container(column(entries.iter().copied().enumerate().map(
+                |(index, entry)| view(entry, length, focused == Some(index)),
+            )))
             .padding(4)
             .style(|theme| {
                 <Theme as Catalog>::style(theme, &<Theme as Catalog>::default())
@@ -388,7 +424,10 @@ pub fn overlay<'a, 'b, T, Message, Theme, Renderer>(
     state: &'b mut State,
     menu: &'b mut Option<Element<'a, Message, Theme, Renderer>>,
     entries: &[T],
-    entry: &(dyn Fn(T, Length) -> Element<'a, Message, Theme, Renderer> + 'a),
+    entry: &(
+         dyn Fn(T, Length, bool) -> Element<'a, Message, Theme, Renderer> + 'a
+     ),
+    label: Option<&(dyn Fn(T) -> Option<String> + 'a)>,
     translation: Vector,
 ) -> Option<overlay::Element<'b, Message, Theme, Renderer>>
 where
@@ -403,18 +442,30 @@ where
         return None;
     }
 
-    // Ensure overlay is created / diff'd
+    // Ensure overlay is created / diff'd, rebuilding it when the focused
+    // entry changed
     match state.status {
         Status::Open { .. } => match menu {
-            Some(menu) => state.menu_tree.diff(&*menu),
+            Some(menu) if state.menu_focused == state.focused => {
+                state.menu_tree.diff(&*menu);
+            }
+            Some(menu) => {
+                *menu = build_menu(entries, entry, state.focused);
+                state.menu_tree.diff(&*menu);
+                state.menu_focused = state.focused;
+            }
             None => {
-                let _menu = build_menu(entries, entry);
+                let _menu = build_menu(entries, entry, state.focused);
                 state.menu_tree = widget::Tree::new(&_menu);
+                state.menu_focused = state.focused;
                 *menu = Some(_menu);
             }
         },
         Status::Closed => {
             *menu = None;
+            state.focused = None;
+            state.menu_focused = None;
+            state.type_ahead.clear();
         }
     }
 
@@ -423,9 +474,18 @@ where
         .position()
         .zip(menu.as_mut())
         .map(|(position, menu)| {
+            // Without labels every entry can be focused, but none typed
+            let labels = entries
+                .iter()
+                .map(|entry| {
+                    label.map_or(Some(String::new()), |label| label(*entry))
+                })
+                .collect();
+
             overlay::Element::new(Box::new(Overlay {
                 menu,
                 state,
+                labels,
                 position: position + translation,
             }))
         })
@@ -490,9 +550,101 @@ where
 struct Overlay<'a, 'b, Message, Theme, Renderer> {
     menu: &'b mut Element<'a, Message, Theme, Renderer>,
     state: &'b mut State,
+    labels: Vec<Option<String>>,
     position: Point,
 }
 
+impl<Message, Theme, Renderer> Overlay<'_, '_, Message, Theme, Renderer>
+where
+    Renderer: advanced::Renderer,
+{
+    /// Next entry with a label, wrapping around at either end of the menu.
+    fn step(&self, forward: bool) -> Option<usize> {
+        let len = self.labels.len();
+
+        (1..=len)
+            .map(|offset| match (self.state.focused, forward) {
+                (Some(index), true) => (index + offset) % len,
+                (Some(index), false) => (index + len - offset) % len,
+                (None, true) => offset - 1,
+                (None, false) => len - offset,
+            })
+            .find(|index| self.labels[*index].is_some())
+    }
+
+    /// First entry whose label starts with the typed characters. Typing the
+    /// same character again cycles through the entries starting with it.
+    fn type_ahead(&mut self, text: &str) -> Option<usize> {
+        let now = Instant::now();
+
+        if self.state.type_ahead_at.is_none_or(|type_ahead_at| {
+            now.duration_since(type_ahead_at) > TYPE_AHEAD_TIMEOUT
+        }) {
+            self.state.type_ahead.clear();
+        }
+
+        self.state.type_ahead_at = Some(now);
+        self.state.type_ahead.push_str(&text.to_lowercase());
+
+        let query = self.state.type_ahead.as_str();
+        let repeated = query.chars().all(|c| query.starts_with(c));
+        let query = if repeated {
+            query.chars().take(1).collect::<String>()
+        } else {
+            query.to_string()
+        };
+
+        let len = self.labels.len();
+        let start = match self.state.focused {
+            Some(index) if repeated => index + 1,
+            Some(index) => index,
+            None => 0,
+        };
+
+        (0..len).map(|offset| (start + offset) % len).find(|index| {
+            self.labels[*index]
+                .as_ref()
+                .is_some_and(|label| label.to_lowercase().starts_with(&query))
+        })
+    }
+
+    /// Activates the focused entry by clicking it.
+    fn activate(
+        &mut self,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) {
+        // Menu is a container wrapping a column of entries
+        let Some(entry) = self
+            .state
+            .focused
+            .and_then(|index| layout.children().next()?.children().nth(index))
+        else {
+            return;
+        };
+
+        let cursor = mouse::Cursor::Available(entry.bounds().center());
+
+        for event in [
+            mouse::Event::ButtonPressed(mouse::Button::Left),
+            mouse::Event::ButtonReleased(mouse::Button::Left),
+        ] {
+            self.menu.as_widget_mut().update(
+                &mut self.state.menu_tree,
+                &Event::Mouse(event),
+                layout,
+                cursor,
+                renderer,
+                clipboard,
+                shell,
+                &layout.bounds(),
+            );
+        }
+    }
+}
+
 impl<Message, Theme, Renderer> overlay::Overlay<Message, Theme, Renderer>
     for Overlay<'_, '_, Message, Theme, Renderer>
 where
@@ -577,7 +729,9 @@ where
         clipboard: &mut dyn Clipboard,
         shell: &mut Shell<'_, Message>,
     ) {
+        // Cursor is unavailable when it's over a nested menu
         if let Event::Mouse(mouse::Event::ButtonPressed(_)) = &event
+            && !matches!(cursor, mouse::Cursor::Unavailable)
             && cursor.position_over(layout.bounds()).is_none()
             && self.state.status.keep_open_bounds().is_none_or(
                 |keep_open_bounds| {
@@ -588,6 +742,61 @@ where
             self.state.status = Status::Closed;
         }
 
+        // Keyboard focus gives way to the cursor
+        if let Event::Mouse(mouse::Event::CursorMoved { .. }) = &event
+            && self.state.focused.is_some()
+            && cursor.is_over(layout.bounds())
+        {
+            self.state.focused = None;
+            shell.invalidate_layout();
+            shell.request_redraw();
+        }
+
+        if let Event::Keyboard(keyboard::Event::KeyPressed {
+            key,
+            text,
+            modifiers,
+            ..
+        }) = &event
+        {
+            let focused = match key.as_ref() {
+                keyboard::Key::Named(keyboard::key::Named::ArrowDown) => {
+                    Some(self.step(true))
+                }
+                keyboard::Key::Named(keyboard::key::Named::ArrowUp) => {
+                    Some(self.step(false))
+                }
+                keyboard::Key::Named(keyboard::key::Named::Enter)
+                    if self.state.focused.is_some() =>
+                {
+                    self.activate(layout, renderer, clipboard, shell);
+                    shell.capture_event();
+                    None
+                }
+                _ => match text {
+                    Some(text)
+                        if !modifiers.command()
+                            && !text.chars().any(char::is_control) =>
+                    {
+                        Some(self.type_ahead(text))
+                    }
+                    _ => None,
+                },
+            };
+
+            if let Some(focused) = focused {
+                if let Some(focused) = focused
+                    && self.state.focused != Some(focused)
+                {
+                    self.state.focused = Some(focused);
+                    shell.invalidate_layout();
+                    shell.request_redraw();
+                }
+
+                shell.capture_event();
+            }
+        }
+
         self.menu.as_widget_mut().update(
             &mut self.state.menu_tree,
             event,
@@ -614,6 +823,20 @@ where
             renderer,
         )
     }
+
+    fn overlay<'c>(
+        &'c mut self,
+        layout: Layout<'c>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'c, Message, Theme, Renderer>> {
+        self.menu.as_widget_mut().overlay(
+            &mut self.state.menu_tree,
+            layout,
+            renderer,
+            &layout.bounds(),
+            Vector::default(),
+        )
+    }
 }
 
 /// The theme catalog of a [`Catalog`].
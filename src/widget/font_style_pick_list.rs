@@ -26,6 +26,10 @@ impl std::fmt::Display for FontStylePick {
             Self(Some(FontStyle::Bold)) => "Bold",
             Self(Some(FontStyle::Italic)) => "Italic",
             Self(Some(FontStyle::ItalicBold)) => "Bold & Italic",
+            Self(Some(FontStyle::Light)) => "Light",
+            Self(Some(FontStyle::ItalicLight)) => "Light & Italic",
+            Self(Some(FontStyle::Semibold)) => "Semibold",
+            Self(Some(FontStyle::ItalicSemibold)) => "Semibold & Italic",
         })
     }
 }
@@ -40,6 +44,10 @@ pub fn font_style_pick_list<'a, Message: 'a + Clone>(
         FontStylePick(Some(FontStyle::Bold)),
         FontStylePick(Some(FontStyle::Italic)),
         FontStylePick(Some(FontStyle::ItalicBold)),
+        FontStylePick(Some(FontStyle::Light)),
+        FontStylePick(Some(FontStyle::ItalicLight)),
+        FontStylePick(Some(FontStyle::Semibold)),
+        FontStylePick(Some(FontStyle::ItalicSemibold)),
     ];
 
     pick_list(picks, Some(FontStylePick::from(font_style)), on_selected)
@@ -1,4 +1,4 @@
-use data::appearance::theme::{FontStyle, randomize_color};
+use data::appearance::theme::{FontStyle, nickname_color};
 use data::{Config, isupport, message, target};
 use iced::widget::span;
 use iced::widget::text::Span;
@@ -16,6 +16,7 @@ pub fn message_content<'a, M: 'a>(
     style: impl Fn(&Theme) -> selectable_text::Style + 'a,
     font_style: impl Fn(&Theme) -> Option<FontStyle>,
     color_transformation: Option<impl Fn(Color) -> Color>,
+    strip_colors: bool,
     config: &Config,
 ) -> Element<'a, M> {
     message_content_impl::<(), M>(
@@ -27,7 +28,14 @@ pub fn message_content<'a, M: 'a>(
         style,
         font_style,
         color_transformation,
-        Option::<(fn(&message::Link) -> _, fn(&message::Link, _, _) -> _)>::None,
+        strip_colors,
+        Option::<(
+            fn(&message::Link) -> _,
+            fn(&message::Link, _, _, _) -> _,
+            fn(&message::Link, _) -> _,
+        )>::None,
+        None,
+        Option::<(fn(message::Link) -> M, fn(message::Link) -> M)>::None,
         config,
     )
 }
@@ -41,8 +49,10 @@ pub fn with_context<'a, T: Copy + 'a, M: 'a>(
     style: impl Fn(&Theme) -> selectable_text::Style + 'a,
     font_style: impl Fn(&Theme) -> Option<FontStyle>,
     color_transformation: Option<impl Fn(Color) -> Color>,
+    strip_colors: bool,
     link_entries: impl Fn(&message::Link) -> Vec<T> + 'a,
-    entry: impl Fn(&message::Link, T, Length) -> Element<'a, M> + 'a,
+    entry: impl Fn(&message::Link, T, Length, bool) -> Element<'a, M> + 'a,
+    label: impl Fn(&message::Link, T) -> Option<String> + 'a,
     config: &Config,
 ) -> Element<'a, M> {
     message_content_impl(
@@ -54,7 +64,49 @@ pub fn with_context<'a, T: Copy + 'a, M: 'a>(
         style,
         font_style,
         color_transformation,
-        Some((link_entries, entry)),
+        strip_colors,
+        Some((link_entries, entry, label)),
+        None,
+        Option::<(fn(message::Link) -> M, fn(message::Link) -> M)>::None,
+        config,
+    )
+}
+
+/// Like [`with_context`], but right-clicking anywhere on the message (outside
+/// of a link) opens the context menu for [`message::Link::Message`], and
+/// entering and exiting links is reported with `link_hover`.
+pub fn with_message_context<'a, T: Copy + 'a, M: 'a>(
+    content: &'a message::Content,
+    chantypes: &[char],
+    casemapping: isupport::CaseMap,
+    theme: &'a Theme,
+    on_link: impl Fn(message::Link) -> M + 'a,
+    style: impl Fn(&Theme) -> selectable_text::Style + 'a,
+    font_style: impl Fn(&Theme) -> Option<FontStyle>,
+    color_transformation: Option<impl Fn(Color) -> Color>,
+    strip_colors: bool,
+    link_entries: impl Fn(&message::Link) -> Vec<T> + 'a,
+    entry: impl Fn(&message::Link, T, Length, bool) -> Element<'a, M> + 'a,
+    label: impl Fn(&message::Link, T) -> Option<String> + 'a,
+    link_hover: Option<(
+        impl Fn(message::Link) -> M + 'a,
+        impl Fn(message::Link) -> M + 'a,
+    )>,
+    config: &Config,
+) -> Element<'a, M> {
+    message_content_impl(
+        content,
+        chantypes,
+        casemapping,
+        theme,
+        on_link,
+        style,
+        font_style,
+        color_transformation,
+        strip_colors,
+        Some((link_entries, entry, label)),
+        Some(message::Link::Message),
+        link_hover,
         config,
     )
 }
@@ -69,17 +121,42 @@ fn message_content_impl<'a, T: Copy + 'a, M: 'a>(
     style: impl Fn(&Theme) -> selectable_text::Style + 'a,
     font_style: impl Fn(&Theme) -> Option<FontStyle>,
     color_transformation: Option<impl Fn(Color) -> Color>,
+    strip_colors: bool,
     context_menu: Option<(
         impl Fn(&message::Link) -> Vec<T> + 'a,
-        impl Fn(&message::Link, T, Length) -> Element<'a, M> + 'a,
+        impl Fn(&message::Link, T, Length, bool) -> Element<'a, M> + 'a,
+        impl Fn(&message::Link, T) -> Option<String> + 'a,
+    )>,
+    fallback_link: Option<message::Link>,
+    link_hover: Option<(
+        impl Fn(message::Link) -> M + 'a,
+        impl Fn(message::Link) -> M + 'a,
     )>,
     config: &Config,
 ) -> Element<'a, M> {
     match content {
-        data::message::Content::Plain(text) => selectable_text(text)
-            .font_maybe(font_style(theme).map(font::get))
-            .style(style)
-            .into(),
+        data::message::Content::Plain(text) => {
+            if let Some((link_entries, view, label)) = context_menu
+                && let Some(link) = fallback_link
+            {
+                // Plain text has no spans to right-click, so render it as a
+                // single span to be able to open the fallback context menu
+                selectable_rich_text::<M, message::Link, T, Theme, Renderer>(
+                    vec![span(text)],
+                )
+                .on_link(on_link)
+                .font_maybe(font_style(theme).map(font::get))
+                .style(style)
+                .context_menu(link_entries, view, label)
+                .context_menu_fallback(link)
+                .into()
+            } else {
+                selectable_text(text)
+                    .font_maybe(font_style(theme).map(font::get))
+                    .style(style)
+                    .into()
+            }
+        }
         data::message::Content::Fragments(fragments) => {
             let mut text = selectable_rich_text::<
                 M,
@@ -102,7 +179,11 @@ fn message_content_impl<'a, T: Copy + 'a, M: 'a>(
                         };
 
                         match fragment {
-                            data::message::Fragment::Text(s) => span(s),
+                            data::message::Fragment::Text(s)
+                            | data::message::Fragment::ModeChange {
+                                text: s,
+                                ..
+                            } => span(s),
                             data::message::Fragment::Channel(s) => {
                                 span(s.as_str())
                                     .font_maybe(
@@ -125,24 +206,17 @@ fn message_content_impl<'a, T: Copy + 'a, M: 'a>(
                                     ))
                             }
                             data::message::Fragment::User(user, text) => {
-                                let color =
-                                    theme.styles().buffer.nickname.color;
-                                let seed = match &config
-                                    .buffer
-                                    .channel
-                                    .message
-                                    .nickname_color
-                                {
-                                    data::buffer::Color::Solid => None,
-                                    data::buffer::Color::Unique => {
-                                        Some(user.seed())
-                                    }
-                                };
-
-                                let color = match seed {
-                                    Some(seed) => randomize_color(color, seed),
-                                    None => theme.styles().text.primary.color,
-                                };
+                                let color = nickname_color(
+                                    &config
+                                        .buffer
+                                        .channel
+                                        .message
+                                        .nickname_color,
+                                    theme.styles().buffer.nickname.color,
+                                    theme.styles().buffer.background,
+                                    user.seed(),
+                                )
+                                .unwrap_or(theme.styles().text.primary.color);
 
                                 span(text)
                                     .font_maybe(
@@ -160,24 +234,17 @@ fn message_content_impl<'a, T: Copy + 'a, M: 'a>(
                                 user,
                                 text,
                             ) => {
-                                let color =
-                                    theme.styles().buffer.nickname.color;
-                                let seed = match &config
-                                    .buffer
-                                    .channel
-                                    .message
-                                    .nickname_color
-                                {
-                                    data::buffer::Color::Solid => None,
-                                    data::buffer::Color::Unique => {
-                                        Some(user.seed())
-                                    }
-                                };
-
-                                let color = match seed {
-                                    Some(seed) => randomize_color(color, seed),
-                                    None => theme.styles().text.primary.color,
-                                };
+                                let color = nickname_color(
+                                    &config
+                                        .buffer
+                                        .channel
+                                        .message
+                                        .nickname_color,
+                                    theme.styles().buffer.nickname.color,
+                                    theme.styles().buffer.background,
+                                    user.seed(),
+                                )
+                                .unwrap_or(theme.styles().text.primary.color);
 
                                 span(text)
                                     .font_maybe(
@@ -226,18 +293,24 @@ fn message_content_impl<'a, T: Copy + 'a, M: 'a>(
                                 text,
                                 formatting,
                             } => {
+                                let (fg, bg) = if strip_colors {
+                                    (None, None)
+                                } else {
+                                    (formatting.fg, formatting.bg)
+                                };
+
                                 let mut span = span(text)
                                     .color_maybe(
-                                        formatting
-                                            .fg
-                                            .and_then(|color| {
-                                                color.into_iced(theme.styles())
-                                            })
-                                            .map(transform_color),
+                                        fg.and_then(|color| {
+                                            color.into_iced_foreground(
+                                                theme.styles(),
+                                            )
+                                        })
+                                        .map(transform_color),
                                     )
-                                    .background_maybe(formatting.bg.and_then(
-                                        |color| color.into_iced(theme.styles()),
-                                    ))
+                                    .background_maybe(bg.and_then(|color| {
+                                        color.into_iced(theme.styles())
+                                    }))
                                     .underline(formatting.underline)
                                     .strikethrough(formatting.strikethrough);
 
@@ -294,8 +367,16 @@ fn message_content_impl<'a, T: Copy + 'a, M: 'a>(
             .font_maybe(font_style(theme).map(font::get))
             .style(style);
 
-            if let Some((link_entries, view)) = context_menu {
-                text = text.context_menu(link_entries, view);
+            if let Some((link_entries, view, label)) = context_menu {
+                text = text.context_menu(link_entries, view, label);
+
+                if let Some(link) = fallback_link {
+                    text = text.context_menu_fallback(link);
+                }
+            }
+
+            if let Some((on_enter, on_exit)) = link_hover {
+                text = text.on_link_hover(on_enter, on_exit);
             }
 
             text.into()
@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use iced::advanced::renderer::{Quad, Renderer as _};
+use iced::advanced::{self, Layout, mouse};
+use iced::widget::Space;
+use iced::{Color, Length, Rectangle, border};
+
+use super::{Element, Renderer, decorate};
+use crate::Theme;
+
+/// Height of a marker, also the distance within which a click hits it.
+const MARKER_HEIGHT: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Highlight,
+    Sent,
+    SearchMatch,
+}
+
+#[derive(Debug, Clone)]
+pub struct Marker<Message> {
+    /// Position of the marked message, from `0.0` (oldest) to `1.0` (newest)
+    pub position: f32,
+    pub kind: Kind,
+    pub on_press: Message,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub highlight: Color,
+    pub sent: Color,
+    pub search_match: Color,
+}
+
+impl Style {
+    fn color(&self, kind: Kind) -> Color {
+        match kind {
+            Kind::Highlight => self.highlight,
+            Kind::Sent => self.sent,
+            Kind::SearchMatch => self.search_match,
+        }
+    }
+}
+
+/// A strip marking the positions of `markers`, publishing the message of a
+/// marker when it's clicked.
+pub fn minimap<'a, Message: Clone + 'a>(
+    markers: Vec<Marker<Message>>,
+    width: f32,
+    style: fn(&Theme) -> Style,
+) -> Element<'a, Message> {
+    let markers: Rc<[Marker<Message>]> = markers.into();
+    let (update_markers, interaction_markers) =
+        (markers.clone(), markers.clone());
+
+    decorate(Space::new().width(width).height(Length::Fill))
+        .update(
+            move |_state: &mut (),
+                  _inner: &mut Element<'a, Message>,
+                  _tree: &mut advanced::widget::Tree,
+                  event: &iced::Event,
+                  layout: Layout<'_>,
+                  cursor: mouse::Cursor,
+                  _renderer: &Renderer,
+                  _clipboard: &mut dyn advanced::Clipboard,
+                  shell: &mut advanced::Shell<'_, Message>,
+                  _viewport: &Rectangle| {
+                let iced::Event::Mouse(mouse::Event::ButtonPressed(
+                    mouse::Button::Left,
+                )) = event
+                else {
+                    return;
+                };
+
+                if let Some(marker) =
+                    marker_at(&update_markers, layout.bounds(), cursor)
+                {
+                    shell.publish(marker.on_press.clone());
+                    shell.capture_event();
+                }
+            },
+        )
+        .mouse_interaction(
+            move |_state: &(),
+                  _inner: &Element<'a, Message>,
+                  _tree: &advanced::widget::Tree,
+                  layout: Layout<'_>,
+                  cursor: mouse::Cursor,
+                  _viewport: &Rectangle,
+                  _renderer: &Renderer| {
+                if marker_at(&interaction_markers, layout.bounds(), cursor)
+                    .is_some()
+                {
+                    mouse::Interaction::Pointer
+                } else {
+                    mouse::Interaction::default()
+                }
+            },
+        )
+        .draw(
+            move |_state: &(),
+                  _inner: &Element<'a, Message>,
+                  _tree: &advanced::widget::Tree,
+                  renderer: &mut Renderer,
+                  theme: &Theme,
+                  _style: &advanced::renderer::Style,
+                  layout: Layout<'_>,
+                  _cursor: mouse::Cursor,
+                  _viewport: &Rectangle| {
+                let bounds = layout.bounds();
+                let style = style(theme);
+
+                for marker in markers.iter() {
+                    renderer.fill_quad(
+                        Quad {
+                            bounds: marker_bounds(marker, bounds),
+                            border: border::rounded(1.0),
+                            ..Quad::default()
+                        },
+                        style.color(marker.kind),
+                    );
+                }
+            },
+        )
+        .into()
+}
+
+fn marker_bounds<Message>(
+    marker: &Marker<Message>,
+    bounds: Rectangle,
+) -> Rectangle {
+    let travel = (bounds.height - MARKER_HEIGHT).max(0.0);
+
+    Rectangle {
+        x: bounds.x,
+        y: bounds.y + marker.position.clamp(0.0, 1.0) * travel,
+        width: bounds.width,
+        height: MARKER_HEIGHT,
+    }
+}
+
+/// The marker closest to the cursor, if the cursor is over any marker.
+fn marker_at<'b, Message>(
+    markers: &'b [Marker<Message>],
+    bounds: Rectangle,
+    cursor: mouse::Cursor,
+) -> Option<&'b Marker<Message>> {
+    let position = cursor.position_over(bounds)?;
+
+    markers
+        .iter()
+        .map(|marker| {
+            let distance =
+                (marker_bounds(marker, bounds).center_y() - position.y).abs();
+
+            (marker, distance)
+        })
+        .filter(|(_, distance)| *distance <= MARKER_HEIGHT)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(marker, _)| marker)
+}
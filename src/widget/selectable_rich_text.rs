@@ -55,6 +55,8 @@ pub struct Rich<
     align_y: alignment::Vertical,
     class: Theme::Class<'a>,
     on_link: Option<Box<dyn Fn(Link) -> Message + 'a>>,
+    on_link_enter: Option<Box<dyn Fn(Link) -> Message + 'a>>,
+    on_link_exit: Option<Box<dyn Fn(Link) -> Message + 'a>>,
 
     #[allow(clippy::type_complexity)]
     context_menu: Option<(
@@ -64,10 +66,13 @@ pub struct Rich<
                     &Link,
                     Entry,
                     Length,
+                    bool,
                 ) -> Element<'a, Message, Theme, Renderer>
                 + 'a,
         >,
+        Arc<dyn Fn(&Link, Entry) -> Option<String> + 'a>,
     )>,
+    context_menu_fallback: Option<Link>,
     cached_entries: Vec<Entry>,
     cached_menu: Option<Element<'a, Message, Theme, Renderer>>,
 }
@@ -92,8 +97,11 @@ where
             align_y: alignment::Vertical::Top,
             class: Theme::default(),
             on_link: None,
+            on_link_enter: None,
+            on_link_exit: None,
 
             context_menu: None,
+            context_menu_fallback: None,
             cached_entries: vec![],
             cached_menu: None,
         }
@@ -206,14 +214,45 @@ where
         self
     }
 
+    /// Sets the messages published when the cursor enters and exits a link
+    /// of the [`Rich`] text.
+    pub fn on_link_hover(
+        mut self,
+        on_enter: impl Fn(Link) -> Message + 'a,
+        on_exit: impl Fn(Link) -> Message + 'a,
+    ) -> Self {
+        self.on_link_enter = Some(Box::new(on_enter));
+        self.on_link_exit = Some(Box::new(on_exit));
+        self
+    }
+
     pub fn context_menu(
         self,
         link_entries: impl Fn(&Link) -> Vec<Entry> + 'a,
-        view: impl Fn(&Link, Entry, Length) -> Element<'a, Message, Theme, Renderer>
+        view: impl Fn(
+            &Link,
+            Entry,
+            Length,
+            bool,
+        ) -> Element<'a, Message, Theme, Renderer>
         + 'a,
+        label: impl Fn(&Link, Entry) -> Option<String> + 'a,
     ) -> Self {
         Self {
-            context_menu: Some((Box::new(link_entries), Arc::new(view))),
+            context_menu: Some((
+                Box::new(link_entries),
+                Arc::new(view),
+                Arc::new(label),
+            )),
+            ..self
+        }
+    }
+
+    /// Sets the [`Link`] used to open the context menu when right-clicking
+    /// outside of any link span.
+    pub fn context_menu_fallback(self, link: Link) -> Self {
+        Self {
+            context_menu_fallback: Some(link),
             ..self
         }
     }
@@ -245,6 +284,8 @@ struct State<Link, P: Paragraph> {
     paragraph: P,
     hovered: bool,
     link_hovered: bool,
+    /// Span of the link under the cursor, when entering links is reported
+    hovered_link: Option<usize>,
     interaction: Interaction,
     shown_spoiler: Option<(usize, Color, Highlight)>,
 
@@ -311,6 +352,7 @@ where
             context_menu: context_menu::State::new(),
             hovered: false,
             link_hovered: false,
+            hovered_link: None,
         })
     }
 
@@ -370,18 +412,42 @@ where
         state.hovered = false;
         state.link_hovered = false;
 
+        let mut hovered_link = None;
+
         if let Some(position) = cursor.position_in(layout.bounds()) {
             state.hovered = true;
 
             if self.on_link.is_some()
-                && let Some(span) = state
-                    .paragraph
-                    .hit_span(position)
-                    .and_then(|span| self.spans.get(span))
-                && span.link.is_some()
+                && let Some(index) = state.paragraph.hit_span(position)
+                && self
+                    .spans
+                    .get(index)
+                    .is_some_and(|span| span.link.is_some())
             {
                 state.link_hovered = true;
+                hovered_link = Some(index);
+            }
+        }
+
+        if let Some((on_enter, on_exit)) =
+            self.on_link_enter.as_ref().zip(self.on_link_exit.as_ref())
+            && state.hovered_link != hovered_link
+        {
+            let link = |index: Option<usize>| {
+                index
+                    .and_then(|index| self.spans.get(index))
+                    .and_then(|span| span.link.clone())
+            };
+
+            if let Some(link) = link(state.hovered_link) {
+                shell.publish(on_exit(link));
             }
+
+            if let Some(link) = link(hovered_link) {
+                shell.publish(on_enter(link));
+            }
+
+            state.hovered_link = hovered_link;
         }
 
         match event {
@@ -515,9 +581,12 @@ where
                 mouse::Button::Right,
             )) => {
                 if let Some(position) = cursor.position_in(bounds)
-                    && let Some((link_entries, _)) = &self.context_menu
-                    && let Some((link, entries)) =
-                        state.spans.iter().enumerate().find_map(|(i, span)| {
+                    && let Some((link_entries, _, _)) = &self.context_menu
+                    && let Some((link, entries)) = state
+                        .spans
+                        .iter()
+                        .enumerate()
+                        .find_map(|(i, span)| {
                             if span.link.is_some()
                                 && state
                                     .paragraph
@@ -535,6 +604,12 @@ where
 
                             None
                         })
+                        .or_else(|| {
+                            let link = self.context_menu_fallback.clone()?;
+                            let entries = (link_entries)(&link);
+
+                            (!entries.is_empty()).then_some((link, entries))
+                        })
                 {
                     state.context_menu.status = context_menu::Status::Open {
                         // Need absolute position. Infallible since we're within position_in
@@ -809,12 +884,14 @@ where
             state.context_menu_link = None;
         }
 
-        if let Some((link, (link_entries, view))) = state
+        if let Some((link, (link_entries, view, label))) = state
             .context_menu_link
             .clone()
             .zip(self.context_menu.as_ref())
         {
             let view = view.clone();
+            let label = label.clone();
+            let label_link = link.clone();
 
             // Rebuild if not cached (view recreated)
             if self.cached_entries.is_empty() {
@@ -825,7 +902,10 @@ where
                 &mut state.context_menu,
                 &mut self.cached_menu,
                 &self.cached_entries,
-                &move |entry, length| view(&link, entry, length),
+                &move |entry, length, focused| {
+                    view(&link, entry, length, focused)
+                },
+                Some(&move |entry| label(&label_link, entry)),
                 translation,
             )
         } else {
@@ -4,9 +4,12 @@ use iced::widget::{container, text};
 use super::Element;
 use crate::{Theme, font, theme};
 
+/// Longer tooltips (e.g. channel topics) wrap at this width
+const MAX_WIDTH: f32 = 420.0;
+
 pub fn tooltip<'a, Message: 'a>(
     content: impl Into<Element<'a, Message>>,
-    tooltip: Option<&'a str>,
+    tooltip: Option<impl text::IntoFragment<'a>>,
     position: Position,
     theme: &'a Theme,
 ) -> Element<'a, Message> {
@@ -17,6 +20,7 @@ pub fn tooltip<'a, Message: 'a>(
                 theme::font_style::secondary(theme).map(font::get),
             ))
             .style(theme::container::tooltip)
+            .max_width(MAX_WIDTH)
             .padding(8),
             position,
         )
@@ -1,17 +1,34 @@
 use data::Config;
-pub use data::window::{Error, MIN_SIZE};
+pub use data::window::{Error, Geometry, MIN_SIZE};
 use futures::stream::BoxStream;
 use futures::{Stream, StreamExt};
 use iced::advanced::graphics::futures::subscription;
-pub use iced::window::{Id, Position, Settings, close, gain_focus, open};
+pub use iced::window::{
+    Id, Position, Settings, close, drag, gain_focus, minimize, open,
+    toggle_maximize,
+};
 use iced::{Point, Size, Subscription, Task};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Window {
     pub id: Id,
+    /// Position while not maximized or fullscreen.
     pub position: Option<Point>,
+    /// Size while not maximized or fullscreen.
     pub size: Size,
     pub focused: bool,
+    pub is_maximized: bool,
+    pub is_fullscreen: bool,
+    pub is_always_on_top: bool,
+    pub is_mini: bool,
+    /// Geometry while in mini mode, once it has been used.
+    pub mini: Option<Geometry>,
+    /// Whether the window was maximized when mini mode was entered, so it's
+    /// maximized again when leaving it.
+    pub was_maximized: bool,
+    /// Hidden to the tray.
+    pub is_hidden: bool,
+    pub is_borderless: bool,
 }
 
 impl Window {
@@ -21,14 +38,93 @@ impl Window {
             position: None,
             size: Size::default(),
             focused: false,
+            is_maximized: false,
+            is_fullscreen: false,
+            is_always_on_top: false,
+            is_mini: false,
+            mini: None,
+            was_maximized: false,
+            is_hidden: false,
+            is_borderless: false,
         }
     }
 
+    /// Restores the geometry the window was opened with, so it is kept if
+    /// the window opens maximized or fullscreen.
+    pub fn restore(&mut self, window: data::Window) {
+        self.position = window.position;
+        self.size = window.size;
+        self.is_maximized = window.is_maximized && !window.is_mini;
+        self.was_maximized = window.is_maximized && window.is_mini;
+        self.is_fullscreen = window.is_fullscreen;
+        self.is_always_on_top = window.is_always_on_top;
+        self.is_mini = window.is_mini;
+        self.mini = window.mini;
+        self.is_borderless = window.is_borderless;
+    }
+
     pub fn opened(&mut self, position: Option<Point>, size: Size) {
-        self.position = position;
-        self.size = size;
+        if let Some(mini) = self.mini.as_mut().filter(|_| self.is_mini) {
+            mini.position = position;
+            mini.size = size;
+        } else if !self.is_maximized && !self.is_fullscreen {
+            self.position = position;
+            self.size = size;
+        }
         self.focused = true;
     }
+
+    /// Switches between the regular and mini geometry, returning the
+    /// geometry to apply to the window and whether to maximize it after.
+    pub fn toggle_mini(&mut self, config: &Config) -> (Geometry, bool) {
+        self.is_mini = !self.is_mini;
+
+        if self.is_mini {
+            self.was_maximized = self.is_maximized;
+
+            let geometry = *self.mini.get_or_insert(Geometry {
+                position: self.position,
+                size: Size::new(
+                    config.mini_mode.width,
+                    config.mini_mode.height,
+                )
+                .max(MIN_SIZE),
+            });
+
+            (geometry, false)
+        } else {
+            let geometry = Geometry {
+                position: self.position,
+                size: self.size,
+            };
+
+            (geometry, std::mem::take(&mut self.was_maximized))
+        }
+    }
+
+    /// Records a move or resize, along with the state of the window after
+    /// it. Moves and resizes while maximized or fullscreen aren't kept, so
+    /// the previous size is restored once the window is un-maximized.
+    pub fn changed(&mut self, change: Change, state: State) {
+        self.is_maximized = state.is_maximized;
+        self.is_fullscreen = state.is_fullscreen;
+
+        if state.is_maximized || state.is_fullscreen {
+            return;
+        }
+
+        if let Some(mini) = self.mini.as_mut().filter(|_| self.is_mini) {
+            match change {
+                Change::Moved(position) => mini.position = Some(position),
+                Change::Resized(size) => mini.size = size,
+            }
+        } else {
+            match change {
+                Change::Moved(position) => self.position = Some(position),
+                Change::Resized(size) => self.size = size,
+            }
+        }
+    }
 }
 
 impl From<Window> for data::Window {
@@ -36,10 +132,125 @@ impl From<Window> for data::Window {
         data::Window {
             position: window.position,
             size: window.size,
+            // Kept while in mini mode, so it's restored when leaving it
+            // after a restart
+            is_maximized: window.is_maximized || window.was_maximized,
+            is_fullscreen: window.is_fullscreen,
+            is_always_on_top: window.is_always_on_top,
+            is_mini: window.is_mini,
+            mini: window.mini,
+            is_borderless: window.is_borderless,
+            display: None,
+            mini_display: None,
         }
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub enum Change {
+    Moved(Point),
+    Resized(Size),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct State {
+    pub is_maximized: bool,
+    pub is_fullscreen: bool,
+}
+
+/// Whether the window is currently maximized or fullscreen.
+pub fn state(id: Id) -> Task<State> {
+    iced::window::is_maximized(id).then(move |is_maximized| {
+        iced::window::mode(id).map(move |mode| State {
+            is_maximized,
+            is_fullscreen: mode == iced::window::Mode::Fullscreen,
+        })
+    })
+}
+
+pub fn level(is_always_on_top: bool) -> iced::window::Level {
+    if is_always_on_top {
+        iced::window::Level::AlwaysOnTop
+    } else {
+        iced::window::Level::Normal
+    }
+}
+
+pub fn set_always_on_top<Message: 'static + Send>(
+    id: Id,
+    is_always_on_top: bool,
+) -> Task<Message> {
+    iced::window::set_level(id, level(is_always_on_top))
+}
+
+/// Resizes and moves the window to `geometry`, leaving maximized or
+/// fullscreen so the geometry takes effect, then maximizes it if
+/// `maximized`.
+pub fn apply_geometry<Message: 'static + Send>(
+    id: Id,
+    geometry: Geometry,
+    maximized: bool,
+) -> Task<Message> {
+    let move_to = geometry.position.map_or_else(Task::none, |position| {
+        iced::window::move_to(id, position)
+    });
+
+    Task::batch([
+        iced::window::set_mode(id, iced::window::Mode::Windowed),
+        iced::window::maximize(id, false),
+    ])
+    .chain(iced::window::resize(id, geometry.size))
+    .chain(move_to)
+    .chain(if maximized {
+        iced::window::maximize(id, true)
+    } else {
+        Task::none()
+    })
+}
+
+/// Whether the system draws decorations around the window, unless it's
+/// borderless.
+pub fn decorations(config: &Config) -> bool {
+    if cfg!(target_os = "macos") {
+        config.platform_specific.macos.decorations
+    } else if cfg!(target_os = "windows") {
+        config.platform_specific.windows.decorations
+    } else {
+        config.platform_specific.linux.decorations
+    }
+}
+
+/// Turns the system's decorations off when the window becomes borderless,
+/// and back on when it no longer is. Windows configured without decorations
+/// are left alone.
+pub fn toggle_borderless<Message: 'static + Send>(
+    id: Id,
+    config: &Config,
+) -> Task<Message> {
+    if decorations(config) {
+        iced::window::toggle_decorations(id)
+    } else {
+        Task::none()
+    }
+}
+
+pub fn hide<Message: 'static + Send>(id: Id) -> Task<Message> {
+    iced::window::set_mode(id, iced::window::Mode::Hidden)
+}
+
+pub fn show<Message: 'static + Send>(
+    id: Id,
+    is_fullscreen: bool,
+) -> Task<Message> {
+    let mode = if is_fullscreen {
+        iced::window::Mode::Fullscreen
+    } else {
+        iced::window::Mode::Windowed
+    };
+
+    iced::window::set_mode(id, mode).chain(gain_focus(id))
+}
+
 pub fn toggle_fullscreen<Message: 'static + Send>() -> Task<Message> {
     iced::window::latest().and_then(move |window| {
         iced::window::mode(window).then(move |mode| {
@@ -70,17 +281,25 @@ pub enum Event {
     CloseRequested,
 }
 
+pub fn settings(config: &Config) -> Settings {
+    Settings {
+        transparent: config.opacity.is_translucent(),
+        blur: config.blur && config.opacity.is_translucent(),
+        ..platform_settings(config)
+    }
+}
+
 #[cfg(not(any(
     target_os = "macos",
     target_os = "linux",
     target_os = "windows"
 )))]
-pub fn settings(_config: &Config) -> Settings {
+fn platform_settings(_config: &Config) -> Settings {
     Settings::default()
 }
 
 #[cfg(target_os = "linux")]
-pub fn settings(config: &Config) -> Settings {
+fn platform_settings(config: &Config) -> Settings {
     use data::environment;
     use iced::window;
 
@@ -95,7 +314,7 @@ pub fn settings(config: &Config) -> Settings {
 }
 
 #[cfg(target_os = "macos")]
-pub fn settings(config: &Config) -> Settings {
+fn platform_settings(config: &Config) -> Settings {
     use iced::window;
 
     Settings {
@@ -110,7 +329,7 @@ pub fn settings(config: &Config) -> Settings {
 }
 
 #[cfg(target_os = "windows")]
-pub fn settings(config: &Config) -> Settings {
+fn platform_settings(config: &Config) -> Settings {
     use iced::window;
     use image::EncodableLayout;
 